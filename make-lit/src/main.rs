@@ -320,8 +320,8 @@ fn generate_lightmaps(
         let atlas_max_rows = args.atlas_size / part_image_size;
         let atlas_max_parts = atlas_max_rows * atlas_max_columns;
         let num_parts: u32 = objects
-            .iter()
-            .map(|(_, object_parts)| object_parts.len() as u32)
+            .values()
+            .map(|object_parts| object_parts.len() as u32)
             .sum();
         let num_atlas_files = num_parts.div_ceil(atlas_max_parts);
 
@@ -13,6 +13,20 @@ pub enum RoseLibError {
         source: std::io::Error,
     },
 
+    #[error("Invalid {what} count {count}, only {remaining} bytes remain")]
+    InvalidCount {
+        what: String,
+        count: i64,
+        remaining: u64,
+    },
+
+    #[error("Invalid {what} index {index}, there are only {count}")]
+    InvalidIndex {
+        what: String,
+        index: i64,
+        count: usize,
+    },
+
     #[error(transparent)]
     TryFromIntError(#[from] std::num::TryFromIntError),
 
@@ -34,3 +48,21 @@ impl From<std::convert::Infallible> for RoseLibError {
         unreachable!()
     }
 }
+
+/// Returns the item at an index read from a file, or an error if the index is
+/// out of range.
+pub(crate) fn get_indexed<'a, T>(
+    items: &'a [T],
+    index: impl Into<i64>,
+    what: &str,
+) -> Result<&'a T, RoseLibError> {
+    let index = index.into();
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| items.get(i))
+        .ok_or_else(|| RoseLibError::InvalidIndex {
+            what: what.to_string(),
+            index,
+            count: items.len(),
+        })
+}
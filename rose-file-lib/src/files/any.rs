@@ -0,0 +1,76 @@
+//! Reading any supported file from memory by its extension
+use std::io::Cursor;
+
+use serde::Serialize;
+
+use crate::error::RoseLibError;
+use crate::files::{CHR, EFT, HIM, IFO, LIT, STB, STL, TIL, TSI, ZCA, ZMD, ZMO, ZMS, ZON, ZSC};
+use crate::io::RoseFile;
+
+/// A file of any supported type, as returned by `read_any`, serializes as the
+/// contained file
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum AnyRoseFile {
+    CHR(CHR),
+    EFT(EFT),
+    HIM(HIM),
+    IFO(IFO),
+    LIT(LIT),
+    STB(STB),
+    STL(STL),
+    TIL(TIL),
+    TSI(TSI),
+    ZCA(ZCA),
+    ZMD(ZMD),
+    ZMO(ZMO),
+    ZMS(ZMS),
+    ZON(ZON),
+    ZSC(ZSC),
+}
+
+fn read_bytes<T: RoseFile>(bytes: &[u8]) -> Result<T, RoseLibError> {
+    let mut file = T::new();
+    file.read(&mut Cursor::new(bytes))?;
+    Ok(file)
+}
+
+/// Read a file from memory, choosing the file type from a case insensitive
+/// extension such as `zms` or `.ZMS`.
+///
+/// `RoseFile` can not be used as a trait object, so this is the single entry
+/// point for code which only knows the file type at runtime, e.g. a fuzz
+/// harness. Malformed input returns an error rather than panicking.
+///
+/// # Example
+/// ```rust
+/// use rose_file_lib::files::read_any;
+///
+/// assert!(read_any(&[0xff; 16], "zms").is_err());
+/// ```
+pub fn read_any(bytes: &[u8], extension: &str) -> Result<AnyRoseFile, RoseLibError> {
+    let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+    Ok(match extension.as_str() {
+        "chr" => AnyRoseFile::CHR(read_bytes(bytes)?),
+        "eft" => AnyRoseFile::EFT(read_bytes(bytes)?),
+        "him" => AnyRoseFile::HIM(read_bytes(bytes)?),
+        "ifo" => AnyRoseFile::IFO(read_bytes(bytes)?),
+        "lit" => AnyRoseFile::LIT(read_bytes(bytes)?),
+        "stb" => AnyRoseFile::STB(read_bytes(bytes)?),
+        "stl" => AnyRoseFile::STL(read_bytes(bytes)?),
+        "til" => AnyRoseFile::TIL(read_bytes(bytes)?),
+        "tsi" => AnyRoseFile::TSI(read_bytes(bytes)?),
+        "zca" => AnyRoseFile::ZCA(read_bytes(bytes)?),
+        "zmd" => AnyRoseFile::ZMD(read_bytes(bytes)?),
+        "zmo" => AnyRoseFile::ZMO(read_bytes(bytes)?),
+        "zms" => AnyRoseFile::ZMS(read_bytes(bytes)?),
+        "zon" => AnyRoseFile::ZON(read_bytes(bytes)?),
+        "zsc" => AnyRoseFile::ZSC(read_bytes(bytes)?),
+        _ => {
+            return Err(RoseLibError::Generic(format!(
+                "Unsupported file extension: {}",
+                extension
+            )))
+        }
+    })
+}
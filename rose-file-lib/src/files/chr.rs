@@ -3,8 +3,9 @@ use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
-use crate::error::RoseLibError;
+use crate::error::{get_indexed, RoseLibError};
 use crate::io::{ReadRoseExt, RoseFile, WriteRoseExt};
+use crate::utils::serialize_sorted;
 
 /// Scene file
 pub type CHR = CharacterModels;
@@ -133,6 +134,7 @@ pub struct CharacterModel {
     pub name: String,
     pub skeleton_path: String,
     pub models: Vec<u16>,
+    #[serde(serialize_with = "serialize_sorted")]
     pub motions: HashMap<CharacterMotionType, String>,
     pub effects: Vec<(u16, String)>, // u16: dummy bone index
 }
@@ -255,7 +257,7 @@ impl RoseFile for CharacterModels {
                 continue;
             }
 
-            let skeleton_index = reader.read_u16()? as usize;
+            let skeleton_index = reader.read_u16()?;
             let name = reader.read_cstring()?.to_string();
 
             let model_count = reader.read_u16()?;
@@ -268,7 +270,7 @@ impl RoseFile for CharacterModels {
             let mut motions = HashMap::new();
             for _ in 0..motion_count {
                 let motion_type_index = reader.read_i16()?;
-                let motion_file_index = reader.read_u16()? as usize;
+                let motion_file_index = reader.read_u16()?;
 
                 if motion_type_index < 0 {
                     continue;
@@ -282,21 +284,27 @@ impl RoseFile for CharacterModels {
                     )));
                 };
 
-                motions.insert(motion_type, motion_files[motion_file_index].clone());
+                motions.insert(
+                    motion_type,
+                    get_indexed(&motion_files, motion_file_index, "motion")?.clone(),
+                );
             }
 
             let effect_count = reader.read_u16()?;
             let mut effects = Vec::new();
             for _ in 0..effect_count {
                 let dummy_bone_index = reader.read_u16()?;
-                let effect_file_index = reader.read_u16()? as usize;
+                let effect_file_index = reader.read_u16()?;
 
-                effects.push((dummy_bone_index, effect_files[effect_file_index].clone()));
+                effects.push((
+                    dummy_bone_index,
+                    get_indexed(&effect_files, effect_file_index, "effect")?.clone(),
+                ));
             }
 
             self.models.push(Some(CharacterModel {
                 name,
-                skeleton_path: skeleton_files[skeleton_index].clone(),
+                skeleton_path: get_indexed(&skeleton_files, skeleton_index, "skeleton")?.clone(),
                 models,
                 motions,
                 effects,
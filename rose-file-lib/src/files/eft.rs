@@ -0,0 +1,201 @@
+//! ROSE Online Effects
+//!
+//! An effect is a collection of particle systems and animated meshes which
+//! are spawned together, e.g. the fire and smoke of a torch.
+use serde::{Deserialize, Serialize};
+
+use crate::error::RoseLibError;
+use crate::io::{ReadRoseExt, RoseFile, WriteRoseExt};
+use crate::utils::{Quaternion, Vector3};
+
+/// Effect file
+pub type EFT = Effect;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Effect {
+    pub name: String,
+    pub sound_enabled: bool,
+    pub sound_path: String,
+    pub sound_loop_count: u32,
+    pub particles: Vec<EffectParticle>,
+    pub meshes: Vec<EffectMesh>,
+    pub animation_enabled: bool,
+    pub animation_path: String,
+    pub animation_loop_count: u32,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct EffectParticle {
+    pub name: String,
+    pub unique_name: String,
+    pub stb_index: u32,
+    pub particle_path: String,
+    pub animation_enabled: bool,
+    pub animation_path: String,
+    pub animation_loop_count: u32,
+    pub animation_unknown: u32,
+    pub position: Vector3<f32>,
+    pub rotation: Quaternion,
+    pub start_delay: u32,
+    pub is_linked: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct EffectMesh {
+    pub name: String,
+    pub unique_name: String,
+    pub stb_index: u32,
+    pub mesh_path: String,
+    pub mesh_animation_path: String,
+    pub texture_path: String,
+    pub alpha_enabled: bool,
+    pub two_sided: bool,
+    pub alpha_test_enabled: bool,
+    pub z_test_enabled: bool,
+    pub z_write_enabled: bool,
+    pub src_blend: u32,
+    pub dst_blend: u32,
+    pub blend_op: u32,
+    pub animation_enabled: bool,
+    pub animation_path: String,
+    pub animation_loop_count: u32,
+    pub animation_unknown: u32,
+    pub position: Vector3<f32>,
+    pub rotation: Quaternion,
+    pub start_delay: u32,
+    pub loop_count: u32,
+    pub is_linked: bool,
+}
+
+impl Effect {
+    /// Returns the texture of the first mesh, which is usually the most
+    /// prominent part of the effect.
+    pub fn primary_texture(&self) -> Option<&str> {
+        self.meshes
+            .iter()
+            .map(|mesh| mesh.texture_path.as_str())
+            .find(|path| !path.is_empty())
+    }
+}
+
+impl RoseFile for Effect {
+    fn new() -> Effect {
+        Self::default()
+    }
+
+    fn read<R: ReadRoseExt>(&mut self, reader: &mut R) -> Result<(), RoseLibError> {
+        self.name = reader.read_string_u32()?;
+        self.sound_enabled = reader.read_u32()? != 0;
+        self.sound_path = reader.read_string_u32()?;
+        self.sound_loop_count = reader.read_u32()?;
+
+        let particle_count = reader.read_u32()?;
+        for _ in 0..particle_count {
+            self.particles.push(EffectParticle {
+                name: reader.read_string_u32()?,
+                unique_name: reader.read_string_u32()?,
+                stb_index: reader.read_u32()?,
+                particle_path: reader.read_string_u32()?,
+                animation_enabled: reader.read_u32()? != 0,
+                animation_path: reader.read_string_u32()?,
+                animation_loop_count: reader.read_u32()?,
+                animation_unknown: reader.read_u32()?,
+                position: reader.read_vector3_f32()?,
+                rotation: reader.read_quaternion_wxyz()?,
+                start_delay: reader.read_u32()?,
+                is_linked: reader.read_u32()? != 0,
+            });
+        }
+
+        let mesh_count = reader.read_u32()?;
+        for _ in 0..mesh_count {
+            self.meshes.push(EffectMesh {
+                name: reader.read_string_u32()?,
+                unique_name: reader.read_string_u32()?,
+                stb_index: reader.read_u32()?,
+                mesh_path: reader.read_string_u32()?,
+                mesh_animation_path: reader.read_string_u32()?,
+                texture_path: reader.read_string_u32()?,
+                alpha_enabled: reader.read_u32()? != 0,
+                two_sided: reader.read_u32()? != 0,
+                alpha_test_enabled: reader.read_u32()? != 0,
+                z_test_enabled: reader.read_u32()? != 0,
+                z_write_enabled: reader.read_u32()? != 0,
+                src_blend: reader.read_u32()?,
+                dst_blend: reader.read_u32()?,
+                blend_op: reader.read_u32()?,
+                animation_enabled: reader.read_u32()? != 0,
+                animation_path: reader.read_string_u32()?,
+                animation_loop_count: reader.read_u32()?,
+                animation_unknown: reader.read_u32()?,
+                position: reader.read_vector3_f32()?,
+                rotation: reader.read_quaternion_wxyz()?,
+                start_delay: reader.read_u32()?,
+                loop_count: reader.read_u32()?,
+                is_linked: reader.read_u32()? != 0,
+            });
+        }
+
+        self.animation_enabled = reader.read_u32()? != 0;
+        self.animation_path = reader.read_string_u32()?;
+        self.animation_loop_count = reader.read_u32()?;
+
+        Ok(())
+    }
+
+    fn write<W: WriteRoseExt>(&mut self, writer: &mut W) -> Result<(), RoseLibError> {
+        writer.write_string_u32(&self.name)?;
+        writer.write_u32(self.sound_enabled as u32)?;
+        writer.write_string_u32(&self.sound_path)?;
+        writer.write_u32(self.sound_loop_count)?;
+
+        writer.write_u32(self.particles.len() as u32)?;
+        for particle in self.particles.iter() {
+            writer.write_string_u32(&particle.name)?;
+            writer.write_string_u32(&particle.unique_name)?;
+            writer.write_u32(particle.stb_index)?;
+            writer.write_string_u32(&particle.particle_path)?;
+            writer.write_u32(particle.animation_enabled as u32)?;
+            writer.write_string_u32(&particle.animation_path)?;
+            writer.write_u32(particle.animation_loop_count)?;
+            writer.write_u32(particle.animation_unknown)?;
+            writer.write_vector3_f32(&particle.position)?;
+            writer.write_quaternion_wxyz(&particle.rotation)?;
+            writer.write_u32(particle.start_delay)?;
+            writer.write_u32(particle.is_linked as u32)?;
+        }
+
+        writer.write_u32(self.meshes.len() as u32)?;
+        for mesh in self.meshes.iter() {
+            writer.write_string_u32(&mesh.name)?;
+            writer.write_string_u32(&mesh.unique_name)?;
+            writer.write_u32(mesh.stb_index)?;
+            writer.write_string_u32(&mesh.mesh_path)?;
+            writer.write_string_u32(&mesh.mesh_animation_path)?;
+            writer.write_string_u32(&mesh.texture_path)?;
+            writer.write_u32(mesh.alpha_enabled as u32)?;
+            writer.write_u32(mesh.two_sided as u32)?;
+            writer.write_u32(mesh.alpha_test_enabled as u32)?;
+            writer.write_u32(mesh.z_test_enabled as u32)?;
+            writer.write_u32(mesh.z_write_enabled as u32)?;
+            writer.write_u32(mesh.src_blend)?;
+            writer.write_u32(mesh.dst_blend)?;
+            writer.write_u32(mesh.blend_op)?;
+            writer.write_u32(mesh.animation_enabled as u32)?;
+            writer.write_string_u32(&mesh.animation_path)?;
+            writer.write_u32(mesh.animation_loop_count)?;
+            writer.write_u32(mesh.animation_unknown)?;
+            writer.write_vector3_f32(&mesh.position)?;
+            writer.write_quaternion_wxyz(&mesh.rotation)?;
+            writer.write_u32(mesh.start_delay)?;
+            writer.write_u32(mesh.loop_count)?;
+            writer.write_u32(mesh.is_linked as u32)?;
+        }
+
+        writer.write_u32(self.animation_enabled as u32)?;
+        writer.write_string_u32(&self.animation_path)?;
+        writer.write_u32(self.animation_loop_count)?;
+
+        Ok(())
+    }
+}
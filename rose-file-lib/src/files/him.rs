@@ -10,7 +10,7 @@ use crate::io::{ReadRoseExt, RoseFile, WriteRoseExt};
 pub type HIM = Heightmap;
 
 /// Heightmap
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Heightmap {
     pub width: i32,
     pub length: i32,
@@ -21,6 +21,10 @@ pub struct Heightmap {
 
     pub min_height: f32,
     pub max_height: f32,
+
+    /// Data after the heights, which is not parsed but kept so the file is
+    /// written back unchanged
+    pub trailing_data: Vec<u8>,
 }
 
 impl Heightmap {
@@ -46,9 +50,12 @@ impl RoseFile for Heightmap {
         self.grid_count = reader.read_i32()?;
         self.scale = reader.read_f32()?;
 
-        self.heights = Vec::with_capacity((self.width * self.length) as usize);
-        for _ in 0..self.length {
-            for _ in 0..self.width {
+        let width = reader.check_count(self.width, 4, "height column")?;
+        let length = reader.check_count(self.length, width as u64 * 4, "height row")?;
+
+        self.heights = Vec::with_capacity(width * length);
+        for _ in 0..length {
+            for _ in 0..width {
                 let height = reader.read_f32()?;
 
                 self.heights.push(height);
@@ -63,13 +70,24 @@ impl RoseFile for Heightmap {
             }
         }
 
-        // TODO: File contains more data
+        // TODO: Parse the data after the heights
+        self.trailing_data.clear();
+        reader.read_to_end(&mut self.trailing_data)?;
 
         Ok(())
     }
 
-    fn write<W: WriteRoseExt>(&mut self, _writer: &mut W) -> Result<(), RoseLibError> {
-        // TODO: Implement writer
-        unimplemented!();
+    fn write<W: WriteRoseExt>(&mut self, writer: &mut W) -> Result<(), RoseLibError> {
+        writer.write_i32(self.width)?;
+        writer.write_i32(self.length)?;
+        writer.write_i32(self.grid_count)?;
+        writer.write_f32(self.scale)?;
+
+        for height in self.heights.iter() {
+            writer.write_f32(*height)?;
+        }
+        writer.write_all(&self.trailing_data)?;
+
+        Ok(())
     }
 }
@@ -81,6 +81,9 @@ pub struct ObjectData {
 }
 
 impl ObjectData {
+    /// Size in bytes of an object with an empty name
+    const MIN_SIZE: u64 = 61;
+
     fn read<R: ReadRoseExt>(&mut self, reader: &mut R) -> Result<(), RoseLibError> {
         self.name = reader.read_string_u8()?;
         self.warp_id = reader.read_i16()?;
@@ -93,6 +96,19 @@ impl ObjectData {
         self.scale = reader.read_vector3_f32()?;
         Ok(())
     }
+
+    fn write<W: WriteRoseExt>(&self, writer: &mut W) -> Result<(), RoseLibError> {
+        writer.write_string_u8(&self.name)?;
+        writer.write_i16(self.warp_id)?;
+        writer.write_i16(self.event_id)?;
+        writer.write_i32(self.object_type)?;
+        writer.write_i32(self.object_id)?;
+        writer.write_vector2_i32(&self.map_position)?;
+        writer.write_quaternion(&self.rotation)?;
+        writer.write_vector3_f32(&self.position)?;
+        writer.write_vector3_f32(&self.scale)?;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, PartialOrd)]
@@ -150,7 +166,7 @@ pub struct MonsterSpawn {
     pub tactical_variable: u32,
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct MapData {
     pub map_pos: Vector2<i32>,
     pub zone_pos: Vector2<i32>,
@@ -176,8 +192,9 @@ impl RoseFile for MapData {
 
     fn read<R: ReadRoseExt>(&mut self, reader: &mut R) -> Result<(), RoseLibError> {
         let block_count = reader.read_u32()?;
+        let block_count = reader.check_count(block_count, 8, "block")?;
 
-        let mut blocks = Vec::with_capacity(block_count as usize);
+        let mut blocks = Vec::with_capacity(block_count);
         for _ in 0..block_count {
             let block_type = MapDataBlockType::try_from(reader.read_u32()?)?;
             let block_offset = reader.read_u32()?;
@@ -208,7 +225,8 @@ impl RoseFile for MapData {
                     };
 
                     let patch_count = reader.read_u32()?;
-                    ocean.patches.reserve(patch_count as usize);
+                    let patch_count = reader.check_count(patch_count, 24, "ocean patch")?;
+                    ocean.patches.reserve(patch_count);
 
                     for _ in 0..patch_count {
                         let ocean_patch = OceanPatch {
@@ -225,6 +243,7 @@ impl RoseFile for MapData {
             }
 
             let count = reader.read_u32()?;
+            let count = reader.check_count(count, ObjectData::MIN_SIZE, "object")?;
             for _ in 0..count {
                 let mut data = ObjectData::default();
                 data.read(reader)?;
@@ -270,9 +289,15 @@ impl RoseFile for MapData {
                             ..Default::default()
                         };
 
-                        let size = water_data.width * water_data.height;
-                        water_data.has_water.reserve(size as usize);
-                        water_data.heights.reserve(size as usize);
+                        // Each water cell is a u8 flag and a f32 height
+                        let row_count = reader.check_count(
+                            water_data.height,
+                            water_data.width as u64 * 5,
+                            "water row",
+                        )?;
+                        let size = row_count * water_data.width as usize;
+                        water_data.has_water.reserve(size);
+                        water_data.heights.reserve(size);
 
                         for _ in 0..water_data.height {
                             for _ in 0..water_data.width {
@@ -284,12 +309,14 @@ impl RoseFile for MapData {
                     }
                     MapDataBlockType::MonsterSpawn => {
                         let mut monster_spawn = MonsterSpawn {
+                            data,
                             name: reader.read_string_u8()?,
                             ..Default::default()
                         };
 
                         let basic_count = reader.read_u32()?;
-                        monster_spawn.basic_spawns.reserve(basic_count as usize);
+                        let basic_count = reader.check_count(basic_count, 9, "spawn point")?;
+                        monster_spawn.basic_spawns.reserve(basic_count);
                         for _ in 0..basic_count {
                             let spawn_point = MonsterSpawnPoint {
                                 name: reader.read_string_u8()?,
@@ -300,9 +327,9 @@ impl RoseFile for MapData {
                         }
 
                         let tactical_count = reader.read_u32()?;
-                        monster_spawn
-                            .tactical_spawns
-                            .reserve(tactical_count as usize);
+                        let tactical_count =
+                            reader.check_count(tactical_count, 9, "spawn point")?;
+                        monster_spawn.tactical_spawns.reserve(tactical_count);
                         for _ in 0..tactical_count {
                             let spawn_point = MonsterSpawnPoint {
                                 name: reader.read_string_u8()?,
@@ -340,7 +367,157 @@ impl RoseFile for MapData {
         Ok(())
     }
 
-    fn write<W: WriteRoseExt>(&mut self, _writer: &mut W) -> Result<(), RoseLibError> {
-        unimplemented!();
+    fn write<W: WriteRoseExt>(&mut self, writer: &mut W) -> Result<(), RoseLibError> {
+        // Each ocean is stored in its own block, empty lists are omitted
+        let mut blocks = vec![MapDataBlockType::MapInfo];
+        for (block_type, count) in [
+            (MapDataBlockType::Object, self.objects.len()),
+            (MapDataBlockType::Npc, self.npcs.len()),
+            (MapDataBlockType::Building, self.buildings.len()),
+            (MapDataBlockType::Sound, self.sounds.len()),
+            (MapDataBlockType::Effect, self.effects.len()),
+            (MapDataBlockType::Animation, self.animations.len()),
+            (MapDataBlockType::Water, self.waters.len()),
+            (MapDataBlockType::MonsterSpawn, self.monster_spawns.len()),
+            (MapDataBlockType::Warp, self.warps.len()),
+            (
+                MapDataBlockType::CollisionObject,
+                self.collision_objects.len(),
+            ),
+            (MapDataBlockType::EventObject, self.events.len()),
+        ] {
+            if count > 0 {
+                blocks.push(block_type);
+            }
+        }
+        blocks.extend(std::iter::repeat_n(
+            MapDataBlockType::Ocean,
+            self.oceans.len(),
+        ));
+
+        writer.write_u32(blocks.len() as u32)?;
+        for block_type in blocks.iter() {
+            writer.write_u32(block_type.clone() as u32)?;
+            writer.write_u32(0)?;
+        }
+
+        let mut oceans = self.oceans.iter();
+        for (block_index, block_type) in blocks.iter().enumerate() {
+            let block_offset = writer.stream_position()?;
+            writer.seek(SeekFrom::Start(4 + 8 * block_index as u64 + 4))?;
+            writer.write_u32(block_offset as u32)?;
+            writer.seek(SeekFrom::Start(block_offset))?;
+
+            let objects = match block_type {
+                MapDataBlockType::MapInfo => {
+                    writer.write_vector2_i32(&self.map_pos)?;
+                    writer.write_vector2_i32(&self.zone_pos)?;
+
+                    // Unused 4x4 matrix of floats
+                    for _ in 0..16 {
+                        writer.write_f32(0.0)?;
+                    }
+
+                    writer.write_cstring(&self.name)?;
+                    continue;
+                }
+                MapDataBlockType::Ocean => {
+                    let ocean = oceans.next().unwrap();
+                    writer.write_f32(ocean.size)?;
+                    writer.write_u32(ocean.patches.len() as u32)?;
+                    for patch in ocean.patches.iter() {
+                        writer.write_vector3_f32(&patch.start)?;
+                        writer.write_vector3_f32(&patch.end)?;
+                    }
+                    continue;
+                }
+                MapDataBlockType::Object => &self.objects,
+                MapDataBlockType::Building => &self.buildings,
+                MapDataBlockType::Animation => &self.animations,
+                MapDataBlockType::Warp => &self.warps,
+                MapDataBlockType::CollisionObject => &self.collision_objects,
+                MapDataBlockType::Npc => {
+                    writer.write_u32(self.npcs.len() as u32)?;
+                    for npc in self.npcs.iter() {
+                        npc.data.write(writer)?;
+                        writer.write_i32(npc.ai)?;
+                        writer.write_string_u8(&npc.file)?;
+                    }
+                    continue;
+                }
+                MapDataBlockType::Sound => {
+                    writer.write_u32(self.sounds.len() as u32)?;
+                    for sound in self.sounds.iter() {
+                        sound.data.write(writer)?;
+                        writer.write_string_u8(&sound.file)?;
+                        writer.write_i32(sound.range)?;
+                        writer.write_i32(sound.interval)?;
+                    }
+                    continue;
+                }
+                MapDataBlockType::Effect => {
+                    writer.write_u32(self.effects.len() as u32)?;
+                    for effect in self.effects.iter() {
+                        effect.data.write(writer)?;
+                        writer.write_string_u8(&effect.file)?;
+                    }
+                    continue;
+                }
+                MapDataBlockType::Water => {
+                    writer.write_u32(self.waters.len() as u32)?;
+                    for water in self.waters.iter() {
+                        ObjectData::default().write(writer)?;
+                        writer.write_u32(water.width)?;
+                        writer.write_u32(water.height)?;
+                        for (has_water, height) in water.has_water.iter().zip(water.heights.iter())
+                        {
+                            writer.write_u8(*has_water as u8)?;
+                            writer.write_f32(*height)?;
+                        }
+                    }
+                    continue;
+                }
+                MapDataBlockType::MonsterSpawn => {
+                    writer.write_u32(self.monster_spawns.len() as u32)?;
+                    for monster_spawn in self.monster_spawns.iter() {
+                        monster_spawn.data.write(writer)?;
+                        writer.write_string_u8(&monster_spawn.name)?;
+
+                        for spawn_points in
+                            [&monster_spawn.basic_spawns, &monster_spawn.tactical_spawns]
+                        {
+                            writer.write_u32(spawn_points.len() as u32)?;
+                            for spawn_point in spawn_points.iter() {
+                                writer.write_string_u8(&spawn_point.name)?;
+                                writer.write_u32(spawn_point.monster)?;
+                                writer.write_u32(spawn_point.count)?;
+                            }
+                        }
+
+                        writer.write_u32(monster_spawn.interval)?;
+                        writer.write_u32(monster_spawn.limit)?;
+                        writer.write_u32(monster_spawn.range)?;
+                        writer.write_u32(monster_spawn.tactical_variable)?;
+                    }
+                    continue;
+                }
+                MapDataBlockType::EventObject => {
+                    writer.write_u32(self.events.len() as u32)?;
+                    for event in self.events.iter() {
+                        event.data.write(writer)?;
+                        writer.write_string_u8(&event.function_name)?;
+                        writer.write_string_u8(&event.file)?;
+                    }
+                    continue;
+                }
+            };
+
+            writer.write_u32(objects.len() as u32)?;
+            for object in objects.iter() {
+                object.write(writer)?;
+            }
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,225 @@
+//! ROSE Light Container
+//!
+//! Text file referenced by the light container dummy points of a ZSC model,
+//! listing the lights placed relative to the dummy point, in the same style
+//! as a text ZSC model:
+//!
+//! ```text
+//! numlight 1
+//!
+//! light 1
+//!     type spot
+//!     pos 0 0 50
+//!     dir 0 0 -1
+//!     color 1 0.9 0.7
+//!     intensity 5
+//!     range 800
+//!     cone 20 35
+//! ```
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::RoseLibError;
+use crate::io::{ReadRoseExt, RoseFile, WriteRoseExt};
+use crate::utils::{Color3, Vector3};
+
+/// Light container file
+pub type LCT = LightContainer;
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct LightContainer {
+    pub lights: Vec<Light>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LightType {
+    #[default]
+    Point,
+    Spot,
+}
+
+impl LightType {
+    fn name(self) -> &'static str {
+        match self {
+            LightType::Point => "point",
+            LightType::Spot => "spot",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Light {
+    pub light_type: LightType,
+
+    /// Position relative to the dummy point, in centimeters
+    pub position: Vector3<f32>,
+
+    /// Direction a spot light points in
+    pub direction: Vector3<f32>,
+
+    pub color: Color3,
+    pub intensity: f32,
+
+    /// Distance in centimeters at which the light stops, 0 for no limit
+    pub range: f32,
+
+    /// Angles in degrees from the direction of a spot light, where its
+    /// intensity starts to fall off and where it reaches 0
+    pub inner_cone_angle: f32,
+    pub outer_cone_angle: f32,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            light_type: LightType::Point,
+            position: Vector3::ZERO,
+            direction: Vector3::new(0.0, 0.0, -1.0),
+            color: Color3::WHITE,
+            intensity: 1.0,
+            range: 0.0,
+            inner_cone_angle: 0.0,
+            outer_cone_angle: 45.0,
+        }
+    }
+}
+
+fn parse<T: FromStr>(words: &[&str], idx: usize) -> Result<T, RoseLibError> {
+    if idx >= words.len() {
+        return Err(RoseLibError::Generic(format!(
+            "{} property missing required parameter",
+            words[0]
+        )));
+    }
+
+    words[idx].parse::<T>().map_err(|_| {
+        RoseLibError::Generic(format!(
+            "{} property has invalid value {}",
+            words[0], words[idx]
+        ))
+    })
+}
+
+fn parse_vector3(words: &[&str]) -> Result<Vector3<f32>, RoseLibError> {
+    Ok(Vector3 {
+        x: parse::<f32>(words, 1)?,
+        y: parse::<f32>(words, 2)?,
+        z: parse::<f32>(words, 3)?,
+    })
+}
+
+impl RoseFile for LightContainer {
+    fn new() -> LightContainer {
+        Self::default()
+    }
+
+    fn read<R: ReadRoseExt>(&mut self, reader: &mut R) -> Result<(), RoseLibError> {
+        let mut line = String::new();
+        let mut line_counter = 0;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            line_counter += 1;
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.is_empty() || words[0].starts_with("//") {
+                continue;
+            }
+
+            if words[0] == "numlight" {
+                let _unused_num_light = parse::<i32>(&words, 1)?;
+                continue;
+            } else if words[0] == "light" {
+                self.lights.push(Light::default());
+                continue;
+            }
+
+            let Some(light) = self.lights.last_mut() else {
+                return Err(RoseLibError::Generic(format!(
+                    "Unexpected {} outside of a light on line {}",
+                    words[0], line_counter
+                )));
+            };
+            match words[0] {
+                "type" => {
+                    light.light_type = match parse::<String>(&words, 1)?.as_str() {
+                        "point" => LightType::Point,
+                        "spot" => LightType::Spot,
+                        other => {
+                            return Err(RoseLibError::Generic(format!(
+                                "Unknown light type {} on line {}",
+                                other, line_counter
+                            )))
+                        }
+                    }
+                }
+                "pos" => light.position = parse_vector3(&words)?,
+                "dir" => light.direction = parse_vector3(&words)?,
+                "color" => {
+                    light.color = Color3 {
+                        r: parse::<f32>(&words, 1)?,
+                        g: parse::<f32>(&words, 2)?,
+                        b: parse::<f32>(&words, 3)?,
+                    }
+                }
+                "intensity" => light.intensity = parse::<f32>(&words, 1)?,
+                "range" => light.range = parse::<f32>(&words, 1)?,
+                "cone" => {
+                    light.inner_cone_angle = parse::<f32>(&words, 1)?;
+                    light.outer_cone_angle = parse::<f32>(&words, 2)?;
+                }
+                _ => {
+                    return Err(RoseLibError::Generic(format!(
+                        "Unknown light property {} on line {}",
+                        words[0], line_counter
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write<W: WriteRoseExt>(&mut self, writer: &mut W) -> Result<(), RoseLibError> {
+        writeln!(writer, "numlight {}", self.lights.len())?;
+
+        for (light_index, light) in self.lights.iter().enumerate() {
+            writeln!(writer)?;
+            writeln!(writer, "light {}", light_index + 1)?;
+            writeln!(writer, "\ttype {}", light.light_type.name())?;
+            writeln!(
+                writer,
+                "\tpos {} {} {}",
+                light.position.x, light.position.y, light.position.z
+            )?;
+            if light.light_type == LightType::Spot {
+                writeln!(
+                    writer,
+                    "\tdir {} {} {}",
+                    light.direction.x, light.direction.y, light.direction.z
+                )?;
+            }
+            writeln!(
+                writer,
+                "\tcolor {} {} {}",
+                light.color.r, light.color.g, light.color.b
+            )?;
+            writeln!(writer, "\tintensity {}", light.intensity)?;
+            if light.range != 0.0 {
+                writeln!(writer, "\trange {}", light.range)?;
+            }
+            if light.light_type == LightType::Spot {
+                writeln!(
+                    writer,
+                    "\tcone {} {}",
+                    light.inner_cone_angle, light.outer_cone_angle
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
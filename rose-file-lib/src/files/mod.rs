@@ -1,8 +1,12 @@
+pub mod any;
 pub mod chr;
+pub mod eft;
 pub mod him;
 pub mod ifo;
+pub mod lct;
 pub mod lit;
 pub mod stb;
+pub mod stb_tables;
 pub mod stl;
 pub mod til;
 pub mod tsi;
@@ -13,11 +17,15 @@ pub mod zms;
 pub mod zon;
 pub mod zsc;
 
+pub use self::any::{read_any, AnyRoseFile};
 pub use self::chr::{CHR, MON};
+pub use self::eft::EFT;
 pub use self::him::HIM;
 pub use self::ifo::IFO;
+pub use self::lct::LCT;
 pub use self::lit::LIT;
 pub use self::stb::STB;
+pub use self::stb_tables::{ItemStb, ListZoneStb};
 pub use self::stl::STL;
 pub use self::til::TIL;
 pub use self::tsi::TSI;
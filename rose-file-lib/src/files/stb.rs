@@ -99,8 +99,10 @@ impl RoseFile for DataTable {
         self.identifier = reader.read_string(4)?;
 
         let offset = reader.read_u32()?;
-        let row_count = (reader.read_u32()? as usize).saturating_sub(1);
-        let col_count = (reader.read_u32()? as usize).saturating_sub(1);
+        let row_count = reader.read_u32()?.saturating_sub(1);
+        let row_count = reader.check_count(row_count, 2, "row")?;
+        let col_count = reader.read_u32()?.saturating_sub(1);
+        let col_count = reader.check_count(col_count, 2, "column")?;
         self.row_height = reader.read_u32()? as u16;
 
         let mut column_widths = Vec::with_capacity(col_count);
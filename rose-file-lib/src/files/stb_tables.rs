@@ -0,0 +1,146 @@
+//! Typed accessors for well known data tables
+//!
+//! The wrappers dereference to the underlying `STB`, so untyped access with
+//! `value` and `get` is still available for columns without an accessor.
+//!
+//! There are no wrappers for list_deco and list_cnst as they are not data
+//! tables: they are the deco and cnst ZSCs of each zone, such as
+//! `3ddata/junon/list_deco_jdt.zsc`, which `ZSC` already reads into typed
+//! models. Their paths come from `ListZoneStb::zsc_deco` and `zsc_cnst`.
+use std::ops::Deref;
+use std::path::Path;
+
+use crate::error::RoseLibError;
+use crate::files::STB;
+use crate::io::{ReadRoseExt, RoseFile, WriteRoseExt};
+
+macro_rules! stb_wrapper {
+    ($name:ident) => {
+        impl From<STB> for $name {
+            fn from(stb: STB) -> $name {
+                $name(stb)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = STB;
+
+            fn deref(&self) -> &STB {
+                &self.0
+            }
+        }
+
+        impl RoseFile for $name {
+            fn new() -> $name {
+                $name(STB::new())
+            }
+
+            fn read<R: ReadRoseExt>(&mut self, reader: &mut R) -> Result<(), RoseLibError> {
+                self.0.read(reader)
+            }
+
+            fn write<W: WriteRoseExt>(&mut self, writer: &mut W) -> Result<(), RoseLibError> {
+                self.0.write(writer)
+            }
+        }
+    };
+}
+
+/// Returns a column value, treating empty cells the same as missing ones
+fn non_empty(stb: &STB, row: usize, col: usize) -> Option<&str> {
+    stb.value(row, col).filter(|value| !value.is_empty())
+}
+
+/// Zone list, `3ddata/stb/list_zone.stb`
+#[derive(Debug, Default, PartialEq)]
+pub struct ListZoneStb(pub STB);
+
+stb_wrapper!(ListZoneStb);
+
+impl ListZoneStb {
+    pub const COLUMN_NAME: usize = 1;
+    pub const COLUMN_ZON: usize = 2;
+    pub const COLUMN_MINIMAP: usize = 9;
+    pub const COLUMN_ZSC_DECO: usize = 12;
+    pub const COLUMN_ZSC_CNST: usize = 13;
+
+    /// Zone name, usually a string table key
+    pub fn name(&self, row: usize) -> Option<&str> {
+        non_empty(self, row, Self::COLUMN_NAME)
+    }
+
+    /// Path to the zone's ZON, relative to the assets root
+    pub fn zon_path(&self, row: usize) -> Option<&str> {
+        non_empty(self, row, Self::COLUMN_ZON)
+    }
+
+    /// Path to the minimap texture, relative to the assets root
+    pub fn minimap_path(&self, row: usize) -> Option<&str> {
+        non_empty(self, row, Self::COLUMN_MINIMAP)
+    }
+
+    /// Path to the ZSC used for decoration objects, relative to the assets
+    /// root
+    pub fn zsc_deco(&self, row: usize) -> Option<&str> {
+        non_empty(self, row, Self::COLUMN_ZSC_DECO)
+    }
+
+    /// Path to the ZSC used for construction objects, relative to the assets
+    /// root
+    pub fn zsc_cnst(&self, row: usize) -> Option<&str> {
+        non_empty(self, row, Self::COLUMN_ZSC_CNST)
+    }
+
+    /// Find the row whose ZON path matches `zon_path`, ignoring case and
+    /// path separators. Row 0 is never a valid zone.
+    pub fn find_zone_by_zon(&self, zon_path: &Path) -> Option<usize> {
+        let normalize = |path: &str| path.replace('\\', "/").to_ascii_lowercase();
+        let zon_path = normalize(&zon_path.to_string_lossy());
+        (1..self.rows()).find(|&row| {
+            self.zon_path(row)
+                .is_some_and(|row_zon| normalize(row_zon) == zon_path)
+        })
+    }
+}
+
+/// Any of the item tables, e.g. `3ddata/stb/list_weapon.stb`
+#[derive(Debug, Default, PartialEq)]
+pub struct ItemStb(pub STB);
+
+stb_wrapper!(ItemStb);
+
+impl ItemStb {
+    pub const COLUMN_NAME: usize = 1;
+    pub const COLUMN_ITEM_CLASS: usize = 4;
+    pub const COLUMN_BASE_PRICE: usize = 5;
+    pub const COLUMN_WEIGHT: usize = 7;
+    pub const COLUMN_ICON: usize = 9;
+    pub const COLUMN_FIELD_MODEL: usize = 10;
+
+    /// Item name, usually a string table key
+    pub fn name(&self, row: usize) -> Option<&str> {
+        non_empty(self, row, Self::COLUMN_NAME)
+    }
+
+    pub fn item_class(&self, row: usize) -> Option<i32> {
+        self.value_as_int(row, Self::COLUMN_ITEM_CLASS)
+    }
+
+    pub fn base_price(&self, row: usize) -> Option<i32> {
+        self.value_as_int(row, Self::COLUMN_BASE_PRICE)
+    }
+
+    pub fn weight(&self, row: usize) -> Option<i32> {
+        self.value_as_int(row, Self::COLUMN_WEIGHT)
+    }
+
+    /// Icon index into the item icon TSI
+    pub fn icon(&self, row: usize) -> Option<i32> {
+        self.value_as_int(row, Self::COLUMN_ICON)
+    }
+
+    /// Model index into the matching item ZSC, e.g. `list_weapon.zsc`
+    pub fn field_model(&self, row: usize) -> Option<i32> {
+        self.value_as_int(row, Self::COLUMN_FIELD_MODEL)
+    }
+}
@@ -12,6 +12,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::RoseLibError;
 use crate::io::{ReadRoseExt, RoseFile, WriteRoseExt};
+use crate::utils::serialize_sorted;
 
 /// String Table File
 pub type STL = StringTable;
@@ -19,6 +20,7 @@ pub type STL = StringTable;
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct StringTable {
     pub entry_type: StringTableType,
+    #[serde(serialize_with = "serialize_sorted")]
     pub entries: HashMap<String, StringTableEntry>,
 }
 
@@ -129,7 +131,8 @@ impl RoseFile for StringTable {
     fn read<R: ReadRoseExt>(&mut self, reader: &mut R) -> Result<(), RoseLibError> {
         self.entry_type = StringTableType::from_str(&reader.read_string_u8()?)?;
 
-        let key_count = reader.read_u32()? as usize;
+        let key_count = reader.read_u32()?;
+        let key_count = reader.check_count(key_count, 5, "key")?;
         let mut keys = Vec::with_capacity(key_count);
         for _ in 0..key_count {
             let string_key = reader.read_string_varbyte()?;
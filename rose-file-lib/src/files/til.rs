@@ -9,7 +9,7 @@ use crate::io::{ReadRoseExt, RoseFile, WriteRoseExt};
 /// Tile file
 pub type TIL = Tilemap;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Tilemap {
     pub width: i32,
     pub height: i32,
@@ -26,35 +26,48 @@ impl RoseFile for Tilemap {
         self.width = reader.read_i32()?;
         self.height = reader.read_i32()?;
 
-        self.tiles.resize(
-            self.width as usize,
-            iter::repeat(Tile::new())
-                .take(self.width as usize)
-                .collect(),
-        );
+        // Each tile is 7 bytes
+        let width = reader.check_count(self.width, 7, "tile column")?;
+        let height = reader.check_count(self.height, width as u64 * 7, "tile row")?;
 
-        for h in 0..self.height {
-            for w in 0..self.width {
+        self.tiles
+            .resize(height, iter::repeat_n(Tile::new(), width).collect());
+
+        for h in 0..height {
+            for w in 0..width {
                 let mut t = Tile::new();
                 t.brush_id = reader.read_u8()?;
                 t.tile_idx = reader.read_u8()?;
                 t.tile_set = reader.read_u8()?;
                 t.tile_id = reader.read_i32()?;
 
-                self.tiles[h as usize][w as usize] = t;
+                self.tiles[h][w] = t;
             }
         }
 
         Ok(())
     }
 
-    fn write<W: WriteRoseExt>(&mut self, _writer: &mut W) -> Result<(), RoseLibError> {
-        unimplemented!();
+    fn write<W: WriteRoseExt>(&mut self, writer: &mut W) -> Result<(), RoseLibError> {
+        writer.write_i32(self.width)?;
+        writer.write_i32(self.height)?;
+
+        for h in 0..self.height {
+            for w in 0..self.width {
+                let t = &self.tiles[h as usize][w as usize];
+                writer.write_u8(t.brush_id)?;
+                writer.write_u8(t.tile_idx)?;
+                writer.write_u8(t.tile_set)?;
+                writer.write_i32(t.tile_id)?;
+            }
+        }
+
+        Ok(())
     }
 }
 
 /// Tile
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Tile {
     pub brush_id: u8,
     pub tile_idx: u8,
@@ -66,4 +79,10 @@ impl Tile {
     fn new() -> Tile {
         Self::default()
     }
+
+    /// Tiles which do not reference a zone tile are holes in the terrain,
+    /// e.g. under caves and dungeon entrances
+    pub fn is_hole(&self) -> bool {
+        self.tile_id < 0
+    }
 }
@@ -7,7 +7,7 @@ use crate::utils::Vector3;
 /// Camera File
 pub type ZCA = Camera;
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 enum ProjectionType {
     #[default]
     Orthographic,
@@ -29,7 +29,7 @@ impl TryFrom<u32> for ProjectionType {
     }
 }
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Camera {
     projection_type: ProjectionType,
     model_view: [f32; 16],
@@ -80,7 +80,23 @@ impl RoseFile for Camera {
         Ok(())
     }
 
-    fn write<W: WriteRoseExt>(&mut self, _writer: &mut W) -> Result<(), RoseLibError> {
-        unimplemented!();
+    fn write<W: WriteRoseExt>(&mut self, writer: &mut W) -> Result<(), RoseLibError> {
+        writer.write_string("ZCA0001", 7)?;
+        writer.write_u32(self.projection_type as u32)?;
+
+        for value in self.model_view.iter().chain(self.projection.iter()) {
+            writer.write_f32(*value)?;
+        }
+
+        writer.write_f32(self.field_of_view)?;
+        writer.write_f32(self.aspect_ratio)?;
+        writer.write_f32(self.near_plane)?;
+        writer.write_f32(self.far_plane)?;
+
+        writer.write_vector3_f32(&self.eye_pos)?;
+        writer.write_vector3_f32(&self.eye_center)?;
+        writer.write_vector3_f32(&self.up)?;
+
+        Ok(())
     }
 }
@@ -11,9 +11,31 @@ pub type ZMD = Skeleton;
 const ZMD_IDENTIFIER_2: &str = "ZMD0002";
 const ZMD_IDENTIFIER_3: &str = "ZMD0003";
 
+/// Version of a skeleton file, set when reading and used when writing
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum SkeletonVersion {
+    /// ZMD0002, dummy bones have no rotation
+    Version2,
+
+    /// ZMD0003
+    #[default]
+    Version3,
+}
+
+impl SkeletonVersion {
+    fn identifier(self) -> &'static str {
+        match self {
+            SkeletonVersion::Version2 => ZMD_IDENTIFIER_2,
+            SkeletonVersion::Version3 => ZMD_IDENTIFIER_3,
+        }
+    }
+}
+
 /// Skeleton
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Skeleton {
+    #[serde(default)]
+    pub version: SkeletonVersion,
     pub bones: Vec<Bone>,
     pub dummy_bones: Vec<Bone>,
 }
@@ -25,9 +47,9 @@ impl RoseFile for Skeleton {
 
     fn read<R: ReadRoseExt>(&mut self, reader: &mut R) -> Result<(), RoseLibError> {
         let identifier = reader.read_string(7)?;
-        let version = match identifier.as_str() {
-            ZMD_IDENTIFIER_2 => 2,
-            ZMD_IDENTIFIER_3 => 3,
+        self.version = match identifier.as_str() {
+            ZMD_IDENTIFIER_2 => SkeletonVersion::Version2,
+            ZMD_IDENTIFIER_3 => SkeletonVersion::Version3,
             _ => {
                 return Err(RoseLibError::Generic(format!(
                     "Unsupported ZMD version: {}",
@@ -35,8 +57,11 @@ impl RoseFile for Skeleton {
                 )))
             }
         };
+        let dummy_rotations = self.version != SkeletonVersion::Version2;
 
+        // Smallest size of a bone, with an empty name
         let bone_count = reader.read_u32()?;
+        let bone_count = reader.check_count(bone_count, 4 + 1 + 12 + 16, "bone")?;
         for _ in 0..bone_count {
             let mut bone = Bone::new();
             bone.parent = reader.read_i32()?;
@@ -47,13 +72,19 @@ impl RoseFile for Skeleton {
         }
 
         let dummy_count = reader.read_u32()?;
+        let dummy_size = if dummy_rotations {
+            1 + 4 + 12 + 16
+        } else {
+            1 + 4 + 12
+        };
+        let dummy_count = reader.check_count(dummy_count, dummy_size, "dummy bone")?;
         for _ in 0..dummy_count {
             let mut bone = Bone::new();
             bone.name = reader.read_cstring()?;
             bone.parent = reader.read_i32()?;
             bone.position = reader.read_vector3_f32()?;
 
-            if version == 3 {
+            if dummy_rotations {
                 bone.rotation = reader.read_quaternion_wxyz()?;
             }
 
@@ -64,7 +95,7 @@ impl RoseFile for Skeleton {
     }
 
     fn write<W: WriteRoseExt>(&mut self, writer: &mut W) -> Result<(), RoseLibError> {
-        writer.write_string(ZMD_IDENTIFIER_3, 7)?;
+        writer.write_string(self.version.identifier(), 7)?;
 
         writer.write_u32(self.bones.len() as u32)?;
         for bone in self.bones.iter() {
@@ -79,7 +110,9 @@ impl RoseFile for Skeleton {
             writer.write_cstring(&dummy.name)?;
             writer.write_i32(dummy.parent)?;
             writer.write_vector3_f32(&dummy.position)?;
-            writer.write_quaternion_wxyz(&dummy.rotation)?;
+            if self.version != SkeletonVersion::Version2 {
+                writer.write_quaternion_wxyz(&dummy.rotation)?;
+            }
         }
 
         Ok(())
@@ -37,6 +37,7 @@ impl RoseFile for Motion {
         self.fps = reader.read_u32()?;
         self.frames = reader.read_u32()?;
         let channel_count = reader.read_u32()?;
+        let channel_count = reader.check_count(channel_count, 8, "channel")?;
 
         for _ in 0..channel_count {
             let channel_type = ChannelType::try_from(reader.read_u32()?)?;
@@ -48,7 +49,15 @@ impl RoseFile for Motion {
             self.channels.push(channel);
         }
 
-        for _ in 0..self.frames {
+        // Channels without frame data would otherwise loop over every frame
+        let frame_size = self.channels.iter().map(|c| c.typ.frame_size()).sum();
+        let frame_count = if frame_size > 0 {
+            reader.check_count(self.frames, frame_size, "frame")?
+        } else {
+            0
+        };
+
+        for _ in 0..frame_count {
             for channel in &mut self.channels {
                 match channel.typ {
                     ChannelType::Position => {
@@ -228,7 +237,7 @@ impl Channel {
     }
 
     pub fn uv4_frames(&mut self) -> Option<&mut Vec<Vector2<f32>>> {
-        if let ChannelData::UV1(v) = &mut self.frames {
+        if let ChannelData::UV4(v) = &mut self.frames {
             Some(v)
         } else {
             None
@@ -307,6 +316,19 @@ pub enum ChannelType {
     Scale = 1 << 10,
 }
 
+impl ChannelType {
+    /// Size in bytes of a single frame of the channel
+    pub fn frame_size(self) -> u64 {
+        match self {
+            ChannelType::None => 0,
+            ChannelType::Alpha | ChannelType::Texture | ChannelType::Scale => 4,
+            ChannelType::UV1 | ChannelType::UV2 | ChannelType::UV3 | ChannelType::UV4 => 8,
+            ChannelType::Position | ChannelType::Normal => 12,
+            ChannelType::Rotation => 16,
+        }
+    }
+}
+
 impl TryFrom<u32> for ChannelType {
     type Error = RoseLibError;
 
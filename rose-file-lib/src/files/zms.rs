@@ -8,7 +8,7 @@ use crate::utils::{BoundingBox, Color4, Vector2, Vector3, Vector4};
 /// Mesh File
 pub type ZMS = Mesh;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum MeshType {
     Static,
     Dynamic,
@@ -41,8 +41,20 @@ impl TryFrom<u16> for MeshType {
     }
 }
 
+/// Storage of vertex attributes in ZMS0007 and ZMS0008 files, some clients
+/// expect packed layouts. The layout is not recorded in the file so it must be
+/// set on the mesh before reading.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct VertexLayout {
+    /// UVs are stored as two 16 bit floats instead of two 32 bit floats
+    pub half_float_uvs: bool,
+
+    /// Colors are stored as four u8 instead of four 32 bit floats
+    pub u8_colors: bool,
+}
+
 /// Mesh
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Mesh {
     pub identifier: String,
     pub format: i32,
@@ -55,6 +67,9 @@ pub struct Mesh {
     pub strips: Vec<i16>,
 
     pub mesh_type: Option<MeshType>,
+
+    #[serde(default)]
+    pub layout: VertexLayout,
 }
 
 impl Mesh {
@@ -101,6 +116,7 @@ impl Mesh {
         self.bounding_box.max = reader.read_vector3_f32()?;
 
         let bone_count = reader.read_u32()?;
+        let bone_count = reader.check_count(bone_count, 8, "bone")?;
         for _ in 0..bone_count {
             let _index = reader.read_u32()?;
             let bone_index = reader.read_u32()?;
@@ -108,11 +124,12 @@ impl Mesh {
         }
 
         let vert_count = reader.read_u32()?;
+        let vert_count = reader.check_count(vert_count, 16, "vertex")?;
         for _ in 0..vert_count {
             self.vertices.push(Vertex::new());
         }
 
-        for i in 0..vert_count as usize {
+        for i in 0..vert_count {
             let _index = reader.read_u32()?;
             self.vertices[i].position = reader.read_vector3_f32()?;
             self.vertices[i].position.x /= 100.0;
@@ -121,21 +138,21 @@ impl Mesh {
         }
 
         if self.normals_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 let _index = reader.read_u32()?;
                 self.vertices[i].normal = reader.read_vector3_f32()?;
             }
         }
 
         if self.colors_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 let _index = reader.read_u32()?;
                 self.vertices[i].color = reader.read_color4()?;
             }
         }
 
         if self.bones_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 let _index = reader.read_u32()?;
                 self.vertices[i].bone_weights = reader.read_vector4_f32()?;
 
@@ -148,35 +165,35 @@ impl Mesh {
         }
 
         if self.tangents_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 let _index = reader.read_u32()?;
                 self.vertices[i].tangent = reader.read_vector3_f32()?;
             }
         }
 
         if self.uv1_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 let _index = reader.read_u32()?;
                 self.vertices[i].uv1 = reader.read_vector2_f32()?;
             }
         }
 
         if self.uv2_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 let _index = reader.read_u32()?;
                 self.vertices[i].uv2 = reader.read_vector2_f32()?;
             }
         }
 
         if self.uv3_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 let _index = reader.read_u32()?;
                 self.vertices[i].uv3 = reader.read_vector2_f32()?;
             }
         }
 
         if self.uv4_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 let _index = reader.read_u32()?;
                 self.vertices[i].uv4 = reader.read_vector2_f32()?;
             }
@@ -240,61 +257,75 @@ impl RoseFile for Mesh {
         }
 
         let vert_count = reader.read_i16()?;
+        let vert_count = reader.check_count(vert_count, 0, "vertex")?;
         for _ in 0..vert_count {
             self.vertices.push(Vertex::new());
         }
 
         if self.positions_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 self.vertices[i].position = reader.read_vector3_f32()?;
             }
         }
 
         if self.normals_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 self.vertices[i].normal = reader.read_vector3_f32()?;
             }
         }
 
         if self.colors_enabled() {
-            for i in 0..vert_count as usize {
-                self.vertices[i].color = reader.read_color4()?;
+            for i in 0..vert_count {
+                self.vertices[i].color = if self.layout.u8_colors {
+                    reader.read_color4_u8()?
+                } else {
+                    reader.read_color4()?
+                };
             }
         }
 
         if self.bones_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 self.vertices[i].bone_weights = reader.read_vector4_f32()?;
                 self.vertices[i].bone_indices = reader.read_vector4_i16()?;
             }
         }
 
         if self.tangents_enabled() {
-            for i in 0..vert_count as usize {
+            for i in 0..vert_count {
                 self.vertices[i].tangent = reader.read_vector3_f32()?;
             }
         }
 
+        let half_float_uvs = self.layout.half_float_uvs;
+        let read_uv = |reader: &mut R| {
+            if half_float_uvs {
+                reader.read_vector2_f16()
+            } else {
+                reader.read_vector2_f32()
+            }
+        };
+
         if self.uv1_enabled() {
-            for i in 0..vert_count as usize {
-                self.vertices[i].uv1 = reader.read_vector2_f32()?;
+            for i in 0..vert_count {
+                self.vertices[i].uv1 = read_uv(reader)?;
             }
         }
 
         if self.uv2_enabled() {
-            for i in 0..vert_count as usize {
-                self.vertices[i].uv2 = reader.read_vector2_f32()?;
+            for i in 0..vert_count {
+                self.vertices[i].uv2 = read_uv(reader)?;
             }
         }
 
         if self.uv3_enabled() {
-            for i in 0..vert_count as usize {
-                self.vertices[i].uv3 = reader.read_vector2_f32()?;
+            for i in 0..vert_count {
+                self.vertices[i].uv3 = read_uv(reader)?;
             }
         }
         if self.uv4_enabled() {
-            for i in 0..vert_count as usize {
-                self.vertices[i].uv4 = reader.read_vector2_f32()?;
+            for i in 0..vert_count {
+                self.vertices[i].uv4 = read_uv(reader)?;
             }
         }
 
@@ -351,7 +382,11 @@ impl RoseFile for Mesh {
 
         if self.colors_enabled() {
             for vertex in &self.vertices {
-                writer.write_color4(&vertex.color)?;
+                if self.layout.u8_colors {
+                    writer.write_color4_u8(&vertex.color)?;
+                } else {
+                    writer.write_color4(&vertex.color)?;
+                }
             }
         }
 
@@ -368,27 +403,36 @@ impl RoseFile for Mesh {
             }
         }
 
+        let half_float_uvs = self.layout.half_float_uvs;
+        let write_uv = |writer: &mut W, uv: &Vector2<f32>| {
+            if half_float_uvs {
+                writer.write_vector2_f16(uv)
+            } else {
+                writer.write_vector2_f32(uv)
+            }
+        };
+
         if self.uv1_enabled() {
             for vertex in &self.vertices {
-                writer.write_vector2_f32(&vertex.uv1)?;
+                write_uv(writer, &vertex.uv1)?;
             }
         }
 
         if self.uv2_enabled() {
             for vertex in &self.vertices {
-                writer.write_vector2_f32(&vertex.uv2)?;
+                write_uv(writer, &vertex.uv2)?;
             }
         }
 
         if self.uv3_enabled() {
             for vertex in &self.vertices {
-                writer.write_vector2_f32(&vertex.uv3)?;
+                write_uv(writer, &vertex.uv3)?;
             }
         }
 
         if self.uv4_enabled() {
             for vertex in &self.vertices {
-                writer.write_vector2_f32(&vertex.uv4)?;
+                write_uv(writer, &vertex.uv4)?;
             }
         }
 
@@ -430,12 +474,13 @@ impl Default for Mesh {
             materials: Vec::new(),
             strips: Vec::new(),
             mesh_type: None,
+            layout: VertexLayout::default(),
         }
     }
 }
 
 /// Mesh Vertex
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Vertex {
     pub position: Vector3<f32>,
     pub normal: Vector3<f32>,
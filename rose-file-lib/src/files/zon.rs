@@ -13,7 +13,7 @@ use crate::utils::{Vector2, Vector3};
 pub type ZON = Zone;
 
 /// Zone
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Zone {
     pub zone_type: ZoneType,
     pub width: i32,
@@ -74,19 +74,23 @@ impl RoseFile for Zone {
                     self.grid_size = reader.read_f32()?;
                     self.start_position = reader.read_vector2_i32()?;
 
-                    for _ in 0..self.height {
-                        let row = iter::repeat(ZonePosition::new())
-                            .take(self.width as usize)
-                            .collect();
-                        self.positions.push(row);
-                    }
+                    // Each position is 9 bytes
+                    let width = reader.check_count(self.width, 9, "position column")?;
+                    let height =
+                        reader.check_count(self.height, width as u64 * 9, "position row")?;
 
-                    for w in 0..self.width {
-                        for h in 0..self.height {
+                    self.positions = iter::repeat_n(
+                        iter::repeat_n(ZonePosition::new(), width).collect(),
+                        height,
+                    )
+                    .collect();
+
+                    for w in 0..width {
+                        for h in 0..height {
                             let mut pos = ZonePosition::new();
                             pos.is_used = reader.read_bool()?;
                             pos.position = reader.read_vector2_f32()?;
-                            self.positions[h as usize][w as usize] = pos;
+                            self.positions[h][w] = pos;
                         }
                     }
                 }
@@ -143,13 +147,97 @@ impl RoseFile for Zone {
         Ok(())
     }
 
-    fn write<W: WriteRoseExt>(&mut self, _writer: &mut W) -> Result<(), RoseLibError> {
-        unimplemented!();
+    fn write<W: WriteRoseExt>(&mut self, writer: &mut W) -> Result<(), RoseLibError> {
+        let block_types = [
+            ZoneBlockType::BasicInfo,
+            ZoneBlockType::EventPoints,
+            ZoneBlockType::Textures,
+            ZoneBlockType::Tiles,
+            ZoneBlockType::Economy,
+        ];
+
+        // Zone block type/offset pairs, offsets are filled in after each block
+        writer.write_i32(block_types.len() as i32)?;
+        for block_type in block_types.iter() {
+            writer.write_i32(*block_type as i32)?;
+            writer.write_i32(0)?;
+        }
+
+        for (block_index, block_type) in block_types.iter().enumerate() {
+            let block_offset = writer.stream_position()?;
+            writer.seek(SeekFrom::Start(4 + 8 * block_index as u64 + 4))?;
+            writer.write_i32(block_offset as i32)?;
+            writer.seek(SeekFrom::Start(block_offset))?;
+
+            match block_type {
+                ZoneBlockType::BasicInfo => {
+                    writer.write_i32(self.zone_type as i32)?;
+                    writer.write_i32(self.width)?;
+                    writer.write_i32(self.height)?;
+                    writer.write_i32(self.grid_count)?;
+                    writer.write_f32(self.grid_size)?;
+                    writer.write_vector2_i32(&self.start_position)?;
+
+                    for w in 0..self.width {
+                        for h in 0..self.height {
+                            let pos = &self.positions[h as usize][w as usize];
+                            writer.write_bool(pos.is_used)?;
+                            writer.write_vector2_f32(&pos.position)?;
+                        }
+                    }
+                }
+                ZoneBlockType::EventPoints => {
+                    writer.write_i32(self.event_points.len() as i32)?;
+                    for p in self.event_points.iter() {
+                        writer.write_vector3_f32(&p.position)?;
+                        writer.write_string_u8(&p.name)?;
+                    }
+                }
+                ZoneBlockType::Textures => {
+                    writer.write_i32(self.textures.len() as i32)?;
+                    for texture in self.textures.iter() {
+                        writer.write_string_u8(texture)?;
+                    }
+                }
+                ZoneBlockType::Tiles => {
+                    writer.write_i32(self.tiles.len() as i32)?;
+                    for t in self.tiles.iter() {
+                        writer.write_i32(t.layer1)?;
+                        writer.write_i32(t.layer2)?;
+                        writer.write_i32(t.offset1)?;
+                        writer.write_i32(t.offset2)?;
+                        writer.write_i32(t.blend as i32)?;
+                        writer.write_i32(t.rotation as i32)?;
+                        writer.write_i32(t.tile_type)?;
+                    }
+                }
+                ZoneBlockType::Economy => {
+                    writer.write_string_u8(&self.name)?;
+                    writer.write_i32(self.is_underground as i32)?;
+                    writer.write_string_u8(&self.background_music)?;
+                    writer.write_string_u8(&self.sky)?;
+                    writer.write_i32(self.economy_tick_rate)?;
+                    writer.write_i32(self.population_base)?;
+                    writer.write_i32(self.population_growth_rate)?;
+                    writer.write_i32(self.metal_consumption)?;
+                    writer.write_i32(self.stone_consumption)?;
+                    writer.write_i32(self.wood_consumption)?;
+                    writer.write_i32(self.leather_consumption)?;
+                    writer.write_i32(self.cloth_consumption)?;
+                    writer.write_i32(self.alchemy_consumption)?;
+                    writer.write_i32(self.chemical_consumption)?;
+                    writer.write_i32(self.medicine_consumption)?;
+                    writer.write_i32(self.food_consumption)?;
+                }
+            }
+        }
+
+        Ok(())
     }
 }
 
 /// Zone Type
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub enum ZoneType {
     #[default]
     Grass = 0,
@@ -206,7 +294,7 @@ impl TryFrom<i32> for ZoneType {
 }
 
 /// Zone Block Type
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub enum ZoneBlockType {
     BasicInfo = 0,
     EventPoints = 1,
@@ -234,7 +322,7 @@ impl TryFrom<i32> for ZoneBlockType {
 }
 
 /// Zone Position
-#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct ZonePosition {
     pub position: Vector2<f32>,
     pub is_used: bool,
@@ -247,7 +335,7 @@ impl ZonePosition {
 }
 
 /// Zone Event Position
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct ZoneEventPoint {
     pub position: Vector3<f32>,
     pub name: String,
@@ -260,7 +348,7 @@ impl ZoneEventPoint {
 }
 
 /// ZoneTile
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct ZoneTile {
     pub layer1: i32,
     pub layer2: i32,
@@ -291,7 +379,7 @@ impl Default for ZoneTile {
     }
 }
 /// Zone Tile Rotation
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub enum ZoneTileRotation {
     #[default]
     Unknown = 0,
@@ -9,7 +9,7 @@ use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use serde::{Deserialize, Serialize};
 
-use crate::error::RoseLibError;
+use crate::error::{get_indexed, RoseLibError};
 use crate::io::{ReadRoseExt, RoseFile, WriteRoseExt};
 use crate::utils::{BoundingBox, BoundingCylinder, Color3, Quaternion, Vector2, Vector3};
 
@@ -103,6 +103,9 @@ pub struct ModelPart {
     pub position: Vector3<f32>,
     pub rotation: Quaternion,
     pub scale: Vector3<f32>,
+    /// Set on billboarded parts, such as tree and grass cards, which turn
+    /// to face the camera
+    pub axis_rotation: Option<Quaternion>,
     pub bone_index: Option<u16>,
     pub dummy_index: Option<u16>,
     pub parent: Option<u16>,
@@ -121,6 +124,7 @@ impl Default for ModelPart {
             position: Vector3::ZERO,
             rotation: Quaternion::IDENTITY,
             scale: Vector3::ONE,
+            axis_rotation: None,
             bone_index: None,
             dummy_index: None,
             parent: None,
@@ -255,7 +259,7 @@ enum ModelProperty {
     Position = 1,
     Rotation = 2,
     Scale = 3,
-    AxisRotation = 4, // unused
+    AxisRotation = 4,
     BoneIndex = 5,
     DummyIndex = 6,
     Parent = 7,
@@ -287,7 +291,7 @@ impl RoseFile for ModelList {
         }
 
         let material_count = reader.read_u16()?;
-        let mut materials = Vec::with_capacity(mesh_file_count as usize);
+        let mut materials = Vec::with_capacity(material_count as usize);
         for _ in 0..material_count {
             materials.push(ModelMaterial {
                 path: reader.read_cstring()?,
@@ -353,9 +357,9 @@ impl RoseFile for ModelList {
                 let mesh_index = reader.read_i16()?;
                 let material_index = reader.read_i16()?;
                 let mut part = ModelPart {
-                    mesh_path: meshes[mesh_index as usize].clone(),
+                    mesh_path: get_indexed(&meshes, mesh_index, "mesh")?.clone(),
                     material: if material_index >= 0 {
-                        Some(materials[material_index as usize].clone())
+                        Some(get_indexed(&materials, material_index, "material")?.clone())
                     } else {
                         None
                     },
@@ -381,7 +385,7 @@ impl RoseFile for ModelList {
                         ModelProperty::Rotation => part.rotation = reader.read_quaternion_wxyz()?,
                         ModelProperty::Scale => part.scale = reader.read_vector3_f32()?,
                         ModelProperty::AxisRotation => {
-                            let _unused = reader.read_quaternion_wxyz()?;
+                            part.axis_rotation = Some(reader.read_quaternion_wxyz()?);
                         }
                         ModelProperty::BoneIndex => {
                             let bone_index = reader.read_i16()?;
@@ -406,7 +410,7 @@ impl RoseFile for ModelList {
                             let shape = value & 0b111;
                             let flags = value & !0b111;
                             part.collision_shape = FromPrimitive::from_u32(shape);
-                            part.collision_flags = ModelCollisionFlags::from_bits(flags).unwrap();
+                            part.collision_flags = ModelCollisionFlags::from_bits_retain(flags);
                         }
                         ModelProperty::AnimationPath => {
                             part.animation_path = Some(reader.read_string(size as u64)?);
@@ -433,11 +437,16 @@ impl RoseFile for ModelList {
             for _ in 0..dummy_point_count {
                 let effect_file_id = reader.read_i16()?;
                 let attachment_type = reader.read_u16()?;
+                let effect_path = if effect_file_id >= 0 {
+                    get_indexed(&effects, effect_file_id, "effect")?.as_str()
+                } else {
+                    ""
+                };
                 let attachment = match FromPrimitive::from_u16(attachment_type) {
                     Some(DummyAttachmentType::Normal) => {
-                        if effect_file_id >= 0 && !effects[effect_file_id as usize].is_empty() {
+                        if !effect_path.is_empty() {
                             Some(ModelDummyAttachment::Effect {
-                                path: effects[effect_file_id as usize].clone(),
+                                path: effect_path.to_string(),
                                 only_visible_at_night: false,
                             })
                         } else {
@@ -445,9 +454,9 @@ impl RoseFile for ModelList {
                         }
                     }
                     Some(DummyAttachmentType::DayNight) => {
-                        if effect_file_id >= 0 && !effects[effect_file_id as usize].is_empty() {
+                        if !effect_path.is_empty() {
                             Some(ModelDummyAttachment::Effect {
-                                path: effects[effect_file_id as usize].clone(),
+                                path: effect_path.to_string(),
                                 only_visible_at_night: true,
                             })
                         } else {
@@ -455,9 +464,9 @@ impl RoseFile for ModelList {
                         }
                     }
                     Some(DummyAttachmentType::LightContainer) => {
-                        if effect_file_id >= 0 && !effects[effect_file_id as usize].is_empty() {
+                        if !effect_path.is_empty() {
                             Some(ModelDummyAttachment::Light {
-                                name: effects[effect_file_id as usize].clone(),
+                                name: effect_path.to_string(),
                             })
                         } else {
                             None
@@ -664,6 +673,12 @@ impl RoseFile for ModelList {
                     writer.write_vector3_f32(&part.scale)?;
                 }
 
+                if let Some(axis_rotation) = part.axis_rotation.as_ref() {
+                    writer.write_u8(ModelProperty::AxisRotation as u8)?;
+                    writer.write_u8(16)?;
+                    writer.write_quaternion_wxyz(axis_rotation)?;
+                }
+
                 if let Some(bone_index) = part.bone_index {
                     writer.write_u8(ModelProperty::BoneIndex as u8)?;
                     writer.write_u8(2)?;
@@ -799,10 +814,8 @@ impl RoseFile for Model {
                         alpha_ref,
                         glow_color,
                     } => {
-                        if alpha_test {
-                            if let Some(material) = part.material.as_mut() {
-                                material.alpha_test = Some(alpha_ref);
-                            }
+                        if let Some(material) = part.material.as_mut() {
+                            material.alpha_test = alpha_test.then_some(alpha_ref);
                         }
 
                         if let Some(material) = part.material.as_mut() {
@@ -1176,7 +1189,7 @@ impl RoseFile for Model {
                 let shape = value & 0b111;
                 let flags = value & !0b111;
                 let collision_shape = FromPrimitive::from_u32(shape);
-                let collision_flags = ModelCollisionFlags::from_bits(flags).unwrap();
+                let collision_flags = ModelCollisionFlags::from_bits_retain(flags);
                 if let Some(collision_shape) = collision_shape {
                     parse_part_property(parse_object.as_mut(), line_counter, |part| {
                         part.collision_shape = Some(collision_shape);
@@ -1279,7 +1292,7 @@ impl RoseFile for Model {
 
         for (part_index, part) in self.parts.iter().enumerate() {
             writeln!(writer)?;
-            writeln!(writer, "\tpart {}", part_index + 1)?;
+            writeln!(writer, "obj {}", part_index + 1)?;
             writeln!(writer, "\tmesh {}", &part.mesh_path)?;
 
             if part.position.x != 0.0 || part.position.y != 0.0 || part.position.z != 0.0 {
@@ -1298,7 +1311,7 @@ impl RoseFile for Model {
                 )?;
             }
 
-            if part.scale.x != 0.0 || part.scale.y != 0.0 || part.scale.z != 0.0 {
+            if part.scale != Vector3::ONE {
                 writeln!(
                     writer,
                     "\tscale {} {} {}",
@@ -1345,8 +1358,8 @@ impl RoseFile for Model {
                     writeln!(writer, "\t\tisskin 1")?;
                 }
 
-                if material.alpha != 0.0 {
-                    writeln!(writer, "\t\talpha {}", material.alpha)?;
+                if material.alpha_enabled {
+                    writeln!(writer, "\t\talpha 1")?;
                 }
 
                 if material.two_sided {
@@ -1375,7 +1388,7 @@ impl RoseFile for Model {
                 }
 
                 if material.alpha != 1.0 {
-                    writeln!(writer, "\t\talpha {}", material.alpha)?;
+                    writeln!(writer, "\t\talphavalue {}", material.alpha)?;
                 }
 
                 if let Some(glow) = &material.glow {
@@ -1391,12 +1404,12 @@ impl RoseFile for Model {
 
         if !self.dummy_points.is_empty() {
             writeln!(writer)?;
-            writeln!(writer, "numpoints {}", self.dummy_points.len())?;
+            writeln!(writer, "numpoint {}", self.dummy_points.len())?;
         }
 
         for (index, dummy_point) in self.dummy_points.iter().enumerate() {
             writeln!(writer)?;
-            writeln!(writer, "\tpoint {}", index + 1)?;
+            writeln!(writer, "point {}", index + 1)?;
 
             match &dummy_point.attachment {
                 Some(ModelDummyAttachment::Effect {
@@ -1418,10 +1431,7 @@ impl RoseFile for Model {
                         DummyAttachmentType::LightContainer as i32
                     )?;
                 }
-                None => {
-                    writeln!(writer, "\teffect ")?;
-                    writeln!(writer, "\ttype 0")?;
-                }
+                None => {}
             }
 
             if dummy_point.position.x != 0.0
@@ -1446,10 +1456,7 @@ impl RoseFile for Model {
                 )?;
             }
 
-            if dummy_point.scale.x != 0.0
-                || dummy_point.scale.y != 0.0
-                || dummy_point.scale.z != 0.0
-            {
+            if dummy_point.scale != Vector3::ONE {
                 writeln!(
                     writer,
                     "\tscale {} {} {}",
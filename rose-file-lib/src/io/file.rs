@@ -2,6 +2,8 @@ use std::fs::File;
 use std::path::Path;
 
 use crate::error::RoseLibError;
+#[cfg(feature = "mmap")]
+use crate::io::MmapReader;
 use crate::io::{ReadRoseExt, RoseReader, RoseWriter, WriteRoseExt};
 
 pub trait RoseFile {
@@ -71,6 +73,45 @@ pub trait RoseFile {
         })
     }
 
+    /// Create new RoseFile from a `Path` using a memory mapped reader,
+    /// which avoids copying large files such as zone data through a buffer.
+    ///
+    /// Without the `mmap` feature this is the same as `from_path`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::path::PathBuf;
+    /// use rose_file_lib::files::HIM;
+    /// use rose_file_lib::io::RoseFile;
+    ///
+    /// let p = PathBuf::from("/path/to/30_30.him");
+    /// let _ = HIM::from_path_mmap(&p);
+    /// ```
+    fn from_path_mmap(path: &Path) -> Result<Self, RoseLibError>
+    where
+        Self: Sized,
+    {
+        #[cfg(feature = "mmap")]
+        {
+            let map_error = |source| RoseLibError::FileError {
+                path: path.to_path_buf(),
+                source,
+            };
+            let f = File::open(path).map_err(map_error)?;
+            let mut reader = MmapReader::new(&f).map_err(map_error)?;
+
+            let mut rf = Self::new();
+            rf.read(&mut reader).map_err(|e| match e {
+                RoseLibError::IOError(source) => map_error(source),
+                _ => e,
+            })?;
+            Ok(rf)
+        }
+
+        #[cfg(not(feature = "mmap"))]
+        Self::from_path(path)
+    }
+
     /// Read data from a `File`
     ///
     /// # Example
@@ -0,0 +1,93 @@
+use std::future::Future;
+use std::io::Cursor;
+use std::path::Path;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::RoseLibError;
+use crate::files::{HIM, IFO, STB, ZMD, ZMO, ZMS};
+use crate::io::RoseFile;
+
+/// Async reading and writing of a `RoseFile`.
+///
+/// Most files are read with seeks between blocks, so the whole file is read
+/// into memory asynchronously and then parsed without blocking on any I/O.
+/// Writing serializes into memory and then writes asynchronously.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::path::Path;
+/// use rose_file_lib::files::ZMS;
+/// use rose_file_lib::io::RoseFileAsync;
+///
+/// async fn load() {
+///     let _ = ZMS::from_path_async(Path::new("/path/to/my.zms")).await;
+/// }
+/// ```
+pub trait RoseFileAsync: RoseFile + Sized + Send {
+    /// Create new RoseFile from an `AsyncRead`
+    fn read_async<R>(reader: &mut R) -> impl Future<Output = Result<Self, RoseLibError>> + Send
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            let mut rf = Self::new();
+            rf.read(&mut Cursor::new(bytes))?;
+            Ok(rf)
+        }
+    }
+
+    /// Write data to an `AsyncWrite`
+    fn write_async<W>(
+        &mut self,
+        writer: &mut W,
+    ) -> impl Future<Output = Result<(), RoseLibError>> + Send
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        async move {
+            let mut cursor = Cursor::new(Vec::new());
+            self.write(&mut cursor)?;
+
+            writer.write_all(cursor.get_ref()).await?;
+            writer.flush().await?;
+            Ok(())
+        }
+    }
+
+    /// Create new RoseFile from a `Path`
+    fn from_path_async(path: &Path) -> impl Future<Output = Result<Self, RoseLibError>> + Send {
+        async move {
+            let map_error = |source| RoseLibError::FileError {
+                path: path.to_path_buf(),
+                source,
+            };
+            let mut file = tokio::fs::File::open(path).await.map_err(map_error)?;
+            Self::read_async(&mut file).await.map_err(|e| match e {
+                RoseLibError::IOError(source) => map_error(source),
+                _ => e,
+            })
+        }
+    }
+
+    /// Write data to a file at `Path`
+    fn write_to_path_async(
+        &mut self,
+        path: &Path,
+    ) -> impl Future<Output = Result<(), RoseLibError>> + Send {
+        async move {
+            let mut file = tokio::fs::File::create(path).await?;
+            self.write_async(&mut file).await
+        }
+    }
+}
+
+impl RoseFileAsync for HIM {}
+impl RoseFileAsync for IFO {}
+impl RoseFileAsync for STB {}
+impl RoseFileAsync for ZMD {}
+impl RoseFileAsync for ZMO {}
+impl RoseFileAsync for ZMS {}
@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::io::{self, BufRead, Cursor, Read, Seek, SeekFrom};
+
+use memmap2::Mmap;
+
+/// Reader over a read-only memory mapped file, the file contents are read
+/// directly from the mapping without being copied into a buffer first.
+pub struct MmapReader {
+    cursor: Cursor<Mmap>,
+}
+
+impl MmapReader {
+    /// Memory map a file for reading.
+    ///
+    /// The file must not be modified while it is mapped, ROSE data files are
+    /// only read so this is fine for client assets.
+    pub fn new(file: &File) -> io::Result<MmapReader> {
+        // SAFETY: The mapping is read-only and assets are not expected to be
+        // modified while being read.
+        let mmap = unsafe { Mmap::map(file)? };
+        Ok(MmapReader {
+            cursor: Cursor::new(mmap),
+        })
+    }
+
+    /// The entire mapped file
+    pub fn as_slice(&self) -> &[u8] {
+        self.cursor.get_ref()
+    }
+}
+
+impl Read for MmapReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.cursor.read(buf)
+    }
+}
+
+impl Seek for MmapReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
+
+impl BufRead for MmapReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.cursor.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.cursor.consume(amt)
+    }
+}
@@ -1,11 +1,19 @@
 //! A module for Reading/Writing ROSE data types to/from disk
 
 mod file;
+#[cfg(feature = "async")]
+mod file_async;
+#[cfg(feature = "mmap")]
+mod mmap;
 mod path;
 mod reader;
 mod writer;
 
 pub use self::file::RoseFile;
+#[cfg(feature = "async")]
+pub use self::file_async::RoseFileAsync;
+#[cfg(feature = "mmap")]
+pub use self::mmap::MmapReader;
 pub use self::path::{normalize_path, normalize_path_str, PathRoseExt};
 pub use self::reader::{ReadRoseExt, RoseReader};
 pub use self::writer::{RoseWriter, WriteRoseExt};
@@ -5,6 +5,7 @@ use std::str;
 
 use byteorder::{LittleEndian, ReadBytesExt};
 use encoding_rs::{EUC_KR, UTF_16LE};
+use half::f16;
 
 use crate::error::RoseLibError;
 use crate::utils::{Color3, Color4, Quaternion, Vector2, Vector3, Vector4};
@@ -117,7 +118,10 @@ pub trait ReadRoseExt: Read + Seek + BufRead {
 
     fn read_color3(&mut self) -> Result<Color3, RoseLibError>;
     fn read_color4(&mut self) -> Result<Color4, RoseLibError>;
+    /// Read a color stored as four normalized u8 in RGBA order
+    fn read_color4_u8(&mut self) -> Result<Color4, RoseLibError>;
 
+    fn read_vector2_f16(&mut self) -> Result<Vector2<f32>, RoseLibError>;
     fn read_vector2_f32(&mut self) -> Result<Vector2<f32>, RoseLibError>;
     fn read_vector2_u32(&mut self) -> Result<Vector2<u32>, RoseLibError>;
     fn read_vector2_i32(&mut self) -> Result<Vector2<i32>, RoseLibError>;
@@ -136,6 +140,16 @@ pub trait ReadRoseExt: Read + Seek + BufRead {
     /// Get the position of the stream
     fn position(&mut self) -> Result<u64, RoseLibError>;
 
+    /// Check that `count` items of at least `item_size` bytes each fit in the
+    /// remainder of the stream, so negative or corrupt counts fail before
+    /// allocating
+    fn check_count(
+        &mut self,
+        count: impl Into<i64>,
+        item_size: u64,
+        what: &str,
+    ) -> Result<usize, RoseLibError>;
+
     // Read strings as wide strings (2-bytes)
     fn wide_strings(&self) -> bool;
 }
@@ -262,6 +276,27 @@ where
         Ok(c)
     }
 
+    fn read_color4_u8(&mut self) -> Result<Color4, RoseLibError> {
+        let mut read_channel =
+            || -> Result<f32, RoseLibError> { Ok(ReadRoseExt::read_u8(self)? as f32 / 255.0) };
+
+        Ok(Color4 {
+            r: read_channel()?,
+            g: read_channel()?,
+            b: read_channel()?,
+            a: read_channel()?,
+        })
+    }
+
+    fn read_vector2_f16(&mut self) -> Result<Vector2<f32>, RoseLibError> {
+        let v = Vector2::<f32> {
+            x: f16::from_bits(ReadRoseExt::read_u16(self)?).to_f32(),
+            y: f16::from_bits(ReadRoseExt::read_u16(self)?).to_f32(),
+        };
+
+        Ok(v)
+    }
+
     fn read_vector2_f32(&mut self) -> Result<Vector2<f32>, RoseLibError> {
         let v = Vector2::<f32> {
             x: ReadRoseExt::read_f32(self)?,
@@ -378,6 +413,28 @@ where
         Ok(self.stream_position()?)
     }
 
+    fn check_count(
+        &mut self,
+        count: impl Into<i64>,
+        item_size: u64,
+        what: &str,
+    ) -> Result<usize, RoseLibError> {
+        let count = count.into();
+        let position = self.stream_position()?;
+        let end = self.seek(SeekFrom::End(0))?;
+        self.seek(SeekFrom::Start(position))?;
+
+        let remaining = end.saturating_sub(position);
+        if count < 0 || (count as u64).saturating_mul(item_size) > remaining {
+            return Err(RoseLibError::InvalidCount {
+                what: what.to_string(),
+                count,
+                remaining,
+            });
+        }
+        Ok(count as usize)
+    }
+
     fn wide_strings(&self) -> bool {
         WIDE_STRINGS.with(|b| b.get())
     }
@@ -3,6 +3,7 @@ use std::io;
 use std::io::{BufWriter, Seek, SeekFrom, Write};
 
 use byteorder::{LittleEndian, WriteBytesExt};
+use half::f16;
 
 use crate::error::RoseLibError;
 use crate::utils::{Color3, Color4, Quaternion, Vector2, Vector3, Vector4};
@@ -104,7 +105,10 @@ pub trait WriteRoseExt: Write + Seek {
 
     fn write_color3(&mut self, color: &Color3) -> Result<(), RoseLibError>;
     fn write_color4(&mut self, color: &Color4) -> Result<(), RoseLibError>;
+    /// Write a color as four normalized u8 in RGBA order
+    fn write_color4_u8(&mut self, color: &Color4) -> Result<(), RoseLibError>;
 
+    fn write_vector2_f16(&mut self, v: &Vector2<f32>) -> Result<(), RoseLibError>;
     fn write_vector2_f32(&mut self, v: &Vector2<f32>) -> Result<(), RoseLibError>;
     fn write_vector2_i32(&mut self, v: &Vector2<i32>) -> Result<(), RoseLibError>;
     fn write_vector2_u32(&mut self, v: &Vector2<u32>) -> Result<(), RoseLibError>;
@@ -254,6 +258,19 @@ where
         Ok(())
     }
 
+    fn write_color4_u8(&mut self, color: &Color4) -> Result<(), RoseLibError> {
+        for channel in [color.r, color.g, color.b, color.a] {
+            WriteRoseExt::write_u8(self, (channel.clamp(0.0, 1.0) * 255.0).round() as u8)?;
+        }
+        Ok(())
+    }
+
+    fn write_vector2_f16(&mut self, v: &Vector2<f32>) -> Result<(), RoseLibError> {
+        WriteRoseExt::write_u16(self, f16::from_f32(v.x).to_bits())?;
+        WriteRoseExt::write_u16(self, f16::from_f32(v.y).to_bits())?;
+        Ok(())
+    }
+
     fn write_vector2_f32(&mut self, v: &Vector2<f32>) -> Result<(), RoseLibError> {
         WriteRoseExt::write_f32(self, v.x)?;
         WriteRoseExt::write_f32(self, v.y)?;
@@ -1,7 +1,21 @@
+use std::collections::{BTreeMap, HashMap};
 use std::ops::Mul;
 
 use num_traits::Num;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Serializes a `HashMap` with sorted keys, so text dumps are stable
+pub(crate) fn serialize_sorted<S, K, V>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, PartialOrd)]
 pub struct Color3 {
@@ -0,0 +1,41 @@
+#![cfg(feature = "async")]
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use rose_file_lib::files::{HIM, IFO, STB, ZMD, ZMO, ZMS};
+use rose_file_lib::io::RoseFileAsync;
+
+use pretty_assertions::assert_eq;
+
+async fn round_trip<T: RoseFileAsync + PartialEq + std::fmt::Debug>(name: &str) {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("data");
+    path.push(name);
+
+    let mut file = T::from_path_async(&path).await.unwrap();
+    assert_eq!(file, T::from_path(&path).unwrap());
+
+    let mut bytes = Vec::new();
+    file.write_async(&mut bytes).await.unwrap();
+
+    let written = T::read_async(&mut Cursor::new(bytes)).await.unwrap();
+    assert_eq!(file, written);
+}
+
+#[tokio::test]
+async fn async_round_trip() {
+    round_trip::<HIM>("31_30.him").await;
+    round_trip::<IFO>("31_30.ifo").await;
+    round_trip::<STB>("list_zone.stb").await;
+    round_trip::<ZMD>("male.zmd").await;
+    round_trip::<ZMO>("item_ani.zmo").await;
+    round_trip::<ZMS>("headbad01.zms").await;
+}
+
+#[tokio::test]
+async fn async_missing_file() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/missing.zms");
+    assert!(ZMS::from_path_async(&path).await.is_err());
+}
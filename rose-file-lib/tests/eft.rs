@@ -0,0 +1,59 @@
+use std::io::Cursor;
+
+use rose_file_lib::files::eft::{EffectMesh, EffectParticle};
+use rose_file_lib::files::EFT;
+use rose_file_lib::io::RoseFile;
+use rose_file_lib::utils::{Quaternion, Vector3};
+
+#[test]
+fn write_read_eft() {
+    let mut orig_eft = EFT::new();
+    orig_eft.name = "torch".to_string();
+    orig_eft.sound_enabled = true;
+    orig_eft.sound_path = "3ddata\\sound\\fire.wav".to_string();
+    orig_eft.sound_loop_count = 0;
+    orig_eft.particles.push(EffectParticle {
+        name: "smoke".to_string(),
+        unique_name: "smoke_1".to_string(),
+        particle_path: "3ddata\\effect\\particles\\smoke.ptl".to_string(),
+        position: Vector3 {
+            x: 0.0,
+            y: 0.0,
+            z: 150.0,
+        },
+        rotation: Quaternion {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        },
+        start_delay: 100,
+        is_linked: true,
+        ..Default::default()
+    });
+    orig_eft.meshes.push(EffectMesh {
+        name: "flame".to_string(),
+        mesh_path: "3ddata\\effect\\mesh\\flame.zms".to_string(),
+        texture_path: "3ddata\\effect\\texture\\flame.dds".to_string(),
+        alpha_enabled: true,
+        two_sided: true,
+        src_blend: 5,
+        dst_blend: 2,
+        blend_op: 1,
+        loop_count: 3,
+        ..Default::default()
+    });
+
+    let mut cursor = Cursor::new(Vec::new());
+    orig_eft.write(&mut cursor).unwrap();
+
+    cursor.set_position(0);
+    let mut new_eft = EFT::new();
+    new_eft.read(&mut cursor).unwrap();
+
+    assert_eq!(orig_eft, new_eft);
+    assert_eq!(
+        new_eft.primary_texture(),
+        Some("3ddata\\effect\\texture\\flame.dds")
+    );
+}
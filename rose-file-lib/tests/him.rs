@@ -1,3 +1,4 @@
+use std::io::Cursor;
 use std::path::PathBuf;
 
 use rose_file_lib::files::HIM;
@@ -19,3 +20,24 @@ fn read_him() {
     assert_eq!(him.min_height, 0.0);
     assert_eq!(him.max_height, 5463.6577);
 }
+
+#[test]
+fn write_him() {
+    let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.push("tests");
+    root.push("data");
+
+    let orig_bytes = std::fs::read(root.join("33_30.him")).unwrap();
+    let mut orig_him = HIM::from_path(&root.join("33_30.him")).unwrap();
+
+    let mut cursor = Cursor::new(Vec::new());
+    orig_him.write(&mut cursor).unwrap();
+    assert_eq!(cursor.get_ref(), &orig_bytes);
+
+    cursor.set_position(0);
+
+    let mut new_him = HIM::new();
+    new_him.read(&mut cursor).unwrap();
+
+    assert_eq!(orig_him, new_him);
+}
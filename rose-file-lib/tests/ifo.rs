@@ -1,3 +1,4 @@
+use std::io::Cursor;
 use std::path::PathBuf;
 
 use rose_file_lib::files::IFO;
@@ -24,6 +25,10 @@ fn read_ifo() {
     assert_eq!(ifo.warps.len(), 0);
     assert_eq!(ifo.oceans.len(), 1);
     assert_eq!(ifo.monster_spawns.len(), 35);
+    assert!(ifo
+        .monster_spawns
+        .iter()
+        .all(|spawn| spawn.data.position.x != 0.0 && spawn.range > 0));
     assert_eq!(ifo.collision_objects.len(), 3);
     assert_eq!(ifo.events.len(), 1);
 
@@ -44,3 +49,22 @@ fn read_ifo() {
     assert_eq!(ifo.collision_objects.len(), 0);
     assert_eq!(ifo.events.len(), 0);
 }
+
+#[test]
+fn write_ifo() {
+    let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.push("tests");
+    root.push("data");
+
+    let mut orig_ifo = IFO::from_path(&root.join("31_30.ifo")).unwrap();
+
+    let mut cursor = Cursor::new(Vec::new());
+    orig_ifo.write(&mut cursor).unwrap();
+
+    cursor.set_position(0);
+
+    let mut new_ifo = IFO::new();
+    new_ifo.read(&mut cursor).unwrap();
+
+    assert_eq!(orig_ifo, new_ifo);
+}
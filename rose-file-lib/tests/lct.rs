@@ -0,0 +1,80 @@
+use std::io::Cursor;
+
+use rose_file_lib::files::lct::{Light, LightType};
+use rose_file_lib::files::LCT;
+use rose_file_lib::io::RoseFile;
+use rose_file_lib::utils::{Color3, Vector3};
+
+const LAMP_LIGHTS: &str = "numlight 2
+
+// Glow around the bulb
+light 1
+\ttype point
+\tpos 0 0 50
+\tcolor 1 0.9 0.7
+\tintensity 2
+\trange 400
+
+light 2
+\ttype spot
+\tpos 0 0 45
+\tdir 0 0 -1
+\tintensity 8
+\tcone 20 35
+";
+
+#[test]
+fn read_lct() {
+    let mut lct = LCT::new();
+    lct.read(&mut Cursor::new(LAMP_LIGHTS)).unwrap();
+
+    assert_eq!(
+        lct.lights,
+        [
+            Light {
+                light_type: LightType::Point,
+                position: Vector3::new(0.0, 0.0, 50.0),
+                color: Color3::new(1.0, 0.9, 0.7),
+                intensity: 2.0,
+                range: 400.0,
+                ..Default::default()
+            },
+            Light {
+                light_type: LightType::Spot,
+                position: Vector3::new(0.0, 0.0, 45.0),
+                direction: Vector3::new(0.0, 0.0, -1.0),
+                intensity: 8.0,
+                inner_cone_angle: 20.0,
+                outer_cone_angle: 35.0,
+                ..Default::default()
+            },
+        ]
+    );
+}
+
+#[test]
+fn read_lct_errors() {
+    for text in [
+        "pos 0 0 0\n",
+        "light 1\n\ttype area\n",
+        "light 1\n\tcone 20\n",
+        "light 1\n\tflicker 1\n",
+    ] {
+        let mut lct = LCT::new();
+        assert!(lct.read(&mut Cursor::new(text)).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn write_lct() {
+    let mut lct = LCT::new();
+    lct.read(&mut Cursor::new(LAMP_LIGHTS)).unwrap();
+
+    let mut cursor = Cursor::new(Vec::new());
+    lct.write(&mut cursor).unwrap();
+    cursor.set_position(0);
+
+    let mut new_lct = LCT::new();
+    new_lct.read(&mut cursor).unwrap();
+    assert_eq!(lct, new_lct);
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use rose_file_lib::files::{HIM, IFO, TIL, ZON, ZSC};
+use rose_file_lib::io::RoseFile;
+
+use pretty_assertions::assert_eq;
+
+#[test]
+fn read_mmap() {
+    let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.push("tests");
+    root.push("data");
+
+    let him = root.join("31_30.him");
+    assert_eq!(
+        HIM::from_path_mmap(&him).unwrap(),
+        HIM::from_path(&him).unwrap()
+    );
+
+    let ifo = root.join("31_30.ifo");
+    assert_eq!(
+        IFO::from_path_mmap(&ifo).unwrap(),
+        IFO::from_path(&ifo).unwrap()
+    );
+
+    let til = root.join("31_30.til");
+    assert_eq!(
+        TIL::from_path_mmap(&til).unwrap(),
+        TIL::from_path(&til).unwrap()
+    );
+
+    let zon = root.join("jgt01.zon");
+    assert_eq!(
+        ZON::from_path_mmap(&zon).unwrap(),
+        ZON::from_path(&zon).unwrap()
+    );
+
+    let zsc = root.join("part_npc.zsc");
+    assert_eq!(
+        ZSC::from_path_mmap(&zsc).unwrap(),
+        ZSC::from_path(&zsc).unwrap()
+    );
+}
+
+#[test]
+fn read_mmap_missing() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/missing.him");
+    assert!(HIM::from_path_mmap(&path).is_err());
+}
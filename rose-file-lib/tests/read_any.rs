@@ -0,0 +1,127 @@
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use rose_file_lib::error::RoseLibError;
+use rose_file_lib::files::{read_any, AnyRoseFile, ZMS};
+use rose_file_lib::io::RoseFile;
+
+const FILES: [&str; 15] = [
+    "31_30.him",
+    "31_30.ifo",
+    "31_30.til",
+    "34_30.ifo",
+    "eluxsamtower.zmo",
+    "headbad01.zms",
+    "item_ani.zmo",
+    "jgt01.zon",
+    "list_faceitem_s.stl",
+    "list_zone.stb",
+    "male.zmd",
+    "mob_wolf2.zms",
+    "objectlightmapdata.lit",
+    "part_npc.zsc",
+    "ui.tsi",
+];
+
+fn read_test_file(name: &str) -> Vec<u8> {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("data");
+    path.push(name);
+    std::fs::read(path).unwrap()
+}
+
+fn extension(name: &str) -> &str {
+    name.rsplit('.').next().unwrap()
+}
+
+#[test]
+fn read_any_valid() {
+    for name in FILES {
+        let bytes = read_test_file(name);
+        assert!(read_any(&bytes, extension(name)).is_ok(), "{}", name);
+    }
+
+    let bytes = read_test_file("headbad01.zms");
+    assert!(matches!(
+        read_any(&bytes, ".ZMS").unwrap(),
+        AnyRoseFile::ZMS(_)
+    ));
+    assert!(read_any(&bytes, "png").is_err());
+}
+
+#[test]
+fn read_any_truncated() {
+    for name in FILES {
+        let bytes = read_test_file(name);
+        let step = (bytes.len() / 64).max(1);
+        for length in (0..bytes.len()).step_by(step) {
+            // Only needs to not panic, some formats are valid when truncated
+            let _ = read_any(&bytes[..length], extension(name));
+        }
+    }
+}
+
+fn xorshift(mut seed: u64) -> impl FnMut() -> u64 {
+    move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    }
+}
+
+#[test]
+fn read_any_mutated() {
+    let mut next = xorshift(0x2545_f491_4f6c_dd1d);
+
+    for name in FILES {
+        let original = read_test_file(name);
+        for _ in 0..64 {
+            // Corrupt a few bytes, mostly in the header where the counts are
+            let mut bytes = original.clone();
+            for _ in 0..4 {
+                let range = if next().is_multiple_of(2) {
+                    bytes.len().min(256)
+                } else {
+                    bytes.len()
+                };
+                let offset = next() as usize % range;
+                bytes[offset] = next() as u8;
+            }
+            let _ = read_any(&bytes, extension(name));
+        }
+    }
+}
+
+#[test]
+fn read_any_random() {
+    let mut next = xorshift(0x9e37_79b9_7f4a_7c15);
+    for extension in [
+        "chr", "eft", "him", "ifo", "lit", "stb", "stl", "til", "tsi", "zca", "zmd", "zmo", "zms",
+        "zon", "zsc",
+    ] {
+        for _ in 0..256 {
+            let length = next() as usize % 256;
+            let bytes: Vec<u8> = (0..length).map(|_| next() as u8).collect();
+            let _ = read_any(&bytes, extension);
+        }
+    }
+}
+
+#[test]
+fn read_any_invalid_count() {
+    let mut zms = ZMS::new();
+    let mut cursor = Cursor::new(Vec::new());
+    zms.write(&mut cursor).unwrap();
+    let mut bytes = cursor.into_inner();
+
+    // Vertex count follows the identifier, format, bounding box and bone count
+    let vertex_count_offset = 8 + 4 + 24 + 2;
+    bytes[vertex_count_offset..vertex_count_offset + 2].copy_from_slice(&(-1i16).to_le_bytes());
+
+    assert!(matches!(
+        read_any(&bytes, "zms"),
+        Err(RoseLibError::InvalidCount { count: -1, .. })
+    ));
+}
@@ -1,8 +1,8 @@
 use std::fs::File;
 use std::io::Cursor;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use rose_file_lib::files::STB;
+use rose_file_lib::files::{ListZoneStb, STB};
 use rose_file_lib::io::RoseFile;
 
 #[test]
@@ -53,3 +53,30 @@ fn write_stb() {
     assert_eq!(orig_stb.data.len(), new_stb.data.len());
     assert_eq!(orig_stb, new_stb);
 }
+
+#[test]
+fn list_zone_stb() {
+    let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.push("tests");
+    root.push("data");
+
+    let list_zone = ListZoneStb::from_path(&root.join("list_zone.stb")).unwrap();
+    assert_eq!(list_zone.rows(), 121);
+
+    let zone_id = list_zone
+        .find_zone_by_zon(Path::new("3ddata/maps/junon/jdt01/jdt01.zon"))
+        .unwrap();
+    assert_eq!(
+        list_zone.zon_path(zone_id),
+        Some("3DDATA\\Maps\\Junon\\JDT01\\JDT01.zon")
+    );
+    assert_eq!(
+        list_zone.zsc_deco(zone_id),
+        list_zone.value(zone_id, ListZoneStb::COLUMN_ZSC_DECO)
+    );
+    assert!(list_zone.zsc_deco(zone_id).is_some());
+    assert!(list_zone.zsc_cnst(zone_id).is_some());
+
+    assert_eq!(list_zone.zon_path(list_zone.rows()), None);
+    assert_eq!(list_zone.find_zone_by_zon(Path::new("missing.zon")), None);
+}
@@ -1,3 +1,4 @@
+use std::io::Cursor;
 use std::path::PathBuf;
 
 use rose_file_lib::files::TIL;
@@ -17,3 +18,22 @@ fn read_til() {
         assert_eq!(t.len(), 16);
     }
 }
+
+#[test]
+fn write_til() {
+    let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.push("tests");
+    root.push("data");
+
+    let mut orig_til = TIL::from_path(&root.join("31_30.til")).unwrap();
+
+    let mut cursor = Cursor::new(Vec::new());
+    orig_til.write(&mut cursor).unwrap();
+
+    cursor.set_position(0);
+
+    let mut new_til = TIL::new();
+    new_til.read(&mut cursor).unwrap();
+
+    assert_eq!(orig_til, new_til);
+}
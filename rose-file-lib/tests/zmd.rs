@@ -2,7 +2,7 @@ use std::fs::File;
 use std::io::Cursor;
 use std::path::PathBuf;
 
-use rose_file_lib::files::ZMD;
+use rose_file_lib::files::{zmd::SkeletonVersion, ZMD};
 use rose_file_lib::io::RoseFile;
 
 #[test]
@@ -43,3 +43,37 @@ fn write_zmd() {
 
     assert_eq!(orig_zmd, new_zmd);
 }
+
+#[test]
+fn write_zmd_version2() {
+    let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.push("tests");
+    root.push("data");
+
+    let mut zmd = ZMD::from_path(&root.join("male.zmd")).unwrap();
+    assert_eq!(zmd.version, SkeletonVersion::Version3);
+
+    let mut version3 = Cursor::new(Vec::new());
+    zmd.write(&mut version3).unwrap();
+
+    zmd.version = SkeletonVersion::Version2;
+    let mut version2 = Cursor::new(Vec::new());
+    zmd.write(&mut version2).unwrap();
+
+    // Dummy bones are written without their rotation
+    let version2 = version2.into_inner();
+    assert_eq!(&version2[..7], b"ZMD0002");
+    assert_eq!(
+        version3.into_inner().len() - version2.len(),
+        zmd.dummy_bones.len() * 16
+    );
+
+    let mut new_zmd = ZMD::new();
+    new_zmd.read(&mut Cursor::new(version2)).unwrap();
+    assert_eq!(new_zmd.version, SkeletonVersion::Version2);
+    assert_eq!(new_zmd.bones, zmd.bones);
+    for (new_dummy, dummy) in new_zmd.dummy_bones.iter().zip(zmd.dummy_bones.iter()) {
+        assert_eq!(new_dummy.name, dummy.name);
+        assert_eq!(new_dummy.position, dummy.position);
+    }
+}
@@ -2,9 +2,10 @@ use std::fs::File;
 use std::io::Cursor;
 use std::path::PathBuf;
 
-use rose_file_lib::files::zms::MeshType;
+use rose_file_lib::files::zms::{MeshType, VertexFormat, VertexLayout};
 use rose_file_lib::files::ZMS;
 use rose_file_lib::io::RoseFile;
+use rose_file_lib::utils::Color4;
 
 use pretty_assertions::assert_eq;
 
@@ -134,3 +135,48 @@ fn write_zms() {
         assert_eq!(orig_zms, new_zms);
     }
 }
+
+#[test]
+fn write_zms_packed_layout() {
+    let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.push("tests");
+    root.push("data");
+
+    let mut orig_zms = ZMS::from_path(&root.join("headbad01.zms")).unwrap();
+    orig_zms.format |= VertexFormat::Color as i32;
+    for (i, vertex) in orig_zms.vertices.iter_mut().enumerate() {
+        vertex.color = Color4::new((i % 256) as f32 / 255.0, 0.0, 1.0, 0.5);
+    }
+
+    let mut unpacked = Cursor::new(Vec::new());
+    orig_zms.write(&mut unpacked).unwrap();
+
+    let layout = VertexLayout {
+        half_float_uvs: true,
+        u8_colors: true,
+    };
+    orig_zms.layout = layout;
+    let mut packed = Cursor::new(Vec::new());
+    orig_zms.write(&mut packed).unwrap();
+
+    // Each vertex saves 12 bytes of color and 4 bytes of uv1
+    let vertex_count = orig_zms.vertices.len();
+    assert_eq!(
+        unpacked.get_ref().len() - packed.get_ref().len(),
+        vertex_count * 16
+    );
+
+    packed.set_position(0);
+    let mut new_zms = ZMS::new();
+    new_zms.layout = layout;
+    new_zms.read(&mut packed).unwrap();
+
+    assert_eq!(new_zms.vertices.len(), vertex_count);
+    for (orig, new) in orig_zms.vertices.iter().zip(new_zms.vertices.iter()) {
+        assert_eq!(orig.position, new.position);
+        assert!((orig.color.r - new.color.r).abs() < 0.5 / 255.0);
+        assert!((orig.color.a - new.color.a).abs() < 1.0 / 255.0);
+        assert!((orig.uv1.x - new.uv1.x).abs() < 1e-3);
+        assert!((orig.uv1.y - new.uv1.y).abs() < 1e-3);
+    }
+}
@@ -1,3 +1,4 @@
+use std::io::Cursor;
 use std::path::PathBuf;
 
 use rose_file_lib::files::zon::*;
@@ -39,3 +40,22 @@ fn read_zon() {
     assert_eq!(zon.background_music, "button1");
     assert_eq!(zon.sky, "button2");
 }
+
+#[test]
+fn write_zon() {
+    let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.push("tests");
+    root.push("data");
+
+    let mut orig_zon = ZON::from_path(&root.join("jgt01.zon")).unwrap();
+
+    let mut cursor = Cursor::new(Vec::new());
+    orig_zon.write(&mut cursor).unwrap();
+
+    cursor.set_position(0);
+
+    let mut new_zon = ZON::new();
+    new_zon.read(&mut cursor).unwrap();
+
+    assert_eq!(orig_zon, new_zon);
+}
@@ -2,8 +2,10 @@ use std::fs::File;
 use std::io::Cursor;
 use std::path::PathBuf;
 
-use rose_file_lib::files::ZSC;
+use rose_file_lib::files::zsc::{Model, ModelPart};
+use rose_file_lib::files::{ZSC, ZSCTXT};
 use rose_file_lib::io::RoseFile;
+use rose_file_lib::utils::Quaternion;
 
 #[test]
 #[allow(clippy::bool_assert_comparison)]
@@ -94,3 +96,63 @@ fn write_zsc() {
         assert_eq!(orig, new);
     }
 }
+
+#[test]
+fn write_zsc_axis_rotation() {
+    let mut zsc = ZSC::new();
+    zsc.models.push(Some(Model {
+        parts: vec![ModelPart {
+            mesh_path: "3ddata/test/card.zms".to_string(),
+            axis_rotation: Some(Quaternion::new(0.0, 0.0, 0.70710677, 0.70710677)),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }));
+
+    let mut cursor = Cursor::new(Vec::new());
+    zsc.write(&mut cursor).unwrap();
+    cursor.set_position(0);
+
+    let mut new_zsc = ZSC::new();
+    new_zsc.read(&mut cursor).unwrap();
+    let part = &new_zsc.models[0].as_ref().unwrap().parts[0];
+    assert_eq!(
+        part.axis_rotation,
+        Some(Quaternion::new(0.0, 0.0, 0.70710677, 0.70710677))
+    );
+}
+
+#[test]
+fn write_zsctxt() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    let models = ["part_npc.zsc", "list_weapon.zsc"]
+        .into_iter()
+        .flat_map(|file| ZSC::from_path(&root.join(file)).unwrap().models)
+        .flatten();
+
+    // The text format has no bounding box or axis rotation, and leaves out
+    // rotations which are near the identity
+    let identity = |rotation: &mut Quaternion| {
+        if rotation.is_near_identity() {
+            *rotation = Quaternion::IDENTITY;
+        }
+    };
+    for mut model in models {
+        model.bounding_box = Default::default();
+        for part in model.parts.iter_mut() {
+            part.axis_rotation = None;
+            identity(&mut part.rotation);
+        }
+        for dummy_point in model.dummy_points.iter_mut() {
+            identity(&mut dummy_point.rotation);
+        }
+
+        let mut cursor = Cursor::new(Vec::new());
+        model.write(&mut cursor).unwrap();
+        cursor.set_position(0);
+
+        let mut new_model = ZSCTXT::new();
+        new_model.read(&mut cursor).unwrap();
+        assert_eq!(model, new_model);
+    }
+}
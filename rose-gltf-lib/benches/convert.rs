@@ -0,0 +1,118 @@
+//! Benchmarks of building meshes, baking the terrain of a block and
+//! converting a whole small zone, so changes which make conversions slower
+//! can be measured. Run with `cargo bench -p rose-gltf-lib`.
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rose_gltf_lib::{
+    collect_timings,
+    rose_file_lib::{
+        files::{
+            zms::{Vertex, VertexFormat},
+            ZMS,
+        },
+        io::RoseFile,
+        utils::{BoundingBox, Vector2, Vector3},
+    },
+    rose_to_gltf,
+    test_assets::write_test_assets,
+    RoseGltfConvOptions,
+};
+
+/// Vertices along each side of the grid mesh, within the i16 indices of ZMS
+const GRID_SIZE: u16 = 128;
+
+fn bench_directory(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+/// A rippled grid of `GRID_SIZE` x `GRID_SIZE` vertices, large enough for the
+/// time spent building the mesh to outweigh reading it.
+fn grid_zms() -> ZMS {
+    let size = GRID_SIZE as f32 - 1.0;
+    let vertices = (0..GRID_SIZE)
+        .flat_map(|y| (0..GRID_SIZE).map(move |x| (x as f32, y as f32)))
+        .map(|(x, y)| Vertex {
+            position: Vector3::new(x, y, (x * 0.3).sin() + (y * 0.2).cos()),
+            normal: Vector3::new(0.0, 0.0, 1.0),
+            uv1: Vector2::new(x / size, 1.0 - y / size),
+            ..Default::default()
+        })
+        .collect();
+    let indices = (0..GRID_SIZE - 1)
+        .flat_map(|y| (0..GRID_SIZE - 1).map(move |x| y * GRID_SIZE + x))
+        .flat_map(|corner| {
+            [
+                Vector3::new(corner, corner + 1, corner + GRID_SIZE + 1),
+                Vector3::new(corner, corner + GRID_SIZE + 1, corner + GRID_SIZE),
+            ]
+        })
+        .map(|triangle| Vector3::new(triangle.x as i16, triangle.y as i16, triangle.z as i16))
+        .collect();
+    ZMS {
+        format: VertexFormat::Position as i32
+            | VertexFormat::Normal as i32
+            | VertexFormat::UV1 as i32,
+        bounding_box: BoundingBox {
+            min: Vector3::new(0.0, 0.0, -2.0),
+            max: Vector3::new(size, size, 2.0),
+        },
+        vertices,
+        indices,
+        ..Default::default()
+    }
+}
+
+fn mesh_build(c: &mut Criterion) {
+    let directory = bench_directory("mesh_build");
+    std::fs::create_dir_all(&directory).unwrap();
+    let zms = directory.join("grid.zms");
+    grid_zms().write_to_path(&zms).unwrap();
+
+    let options = RoseGltfConvOptions {
+        generate_missing_tangents: true,
+        ..Default::default()
+    };
+    c.bench_function("mesh_build", |b| {
+        b.iter(|| rose_to_gltf(std::slice::from_ref(&zms), &options).unwrap())
+    });
+}
+
+fn zone(c: &mut Criterion) {
+    let assets = write_test_assets(&bench_directory("zone")).unwrap();
+    let options = RoseGltfConvOptions::default();
+
+    let mut group = c.benchmark_group("zone");
+    group.sample_size(10);
+
+    // Only the time spent generating the terrain mesh and baking its
+    // textures, the rest of the zone conversion is left out
+    group.bench_function("terrain_bake", |b| {
+        b.iter_custom(|iterations| {
+            (0..iterations)
+                .map(|_| {
+                    let (gltf, timings) = collect_timings(|| {
+                        rose_to_gltf(std::slice::from_ref(&assets.zon), &options)
+                    });
+                    gltf.unwrap();
+                    ["terrain_mesh", "terrain_materials"]
+                        .iter()
+                        .filter_map(|phase| timings.get(*phase))
+                        .map(|timing| Duration::from_secs_f64(timing.seconds))
+                        .sum::<Duration>()
+                })
+                .sum()
+        })
+    });
+
+    group.bench_function("convert", |b| {
+        b.iter(|| rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, mesh_build, zone);
+criterion_main!(benches);
@@ -0,0 +1,303 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::GltfData;
+
+/// Number of meshes listed in `GltfAnalysis::largest_meshes`.
+const LARGEST_MESHES_COUNT: usize = 10;
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct BufferViewBytes {
+    pub vertex: u64,
+    pub index: u64,
+    pub animation: u64,
+    pub skin: u64,
+    pub image: u64,
+    pub other: u64,
+}
+
+impl BufferViewBytes {
+    pub fn total(&self) -> u64 {
+        self.vertex + self.index + self.animation + self.skin + self.image + self.other
+    }
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct MeshSize {
+    pub name: String,
+    pub primitives: usize,
+    pub vertices: usize,
+    pub triangles: usize,
+    pub bytes: u64,
+
+    /// Number of nodes which instance this mesh
+    pub instances: usize,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct GltfAnalysis {
+    pub buffer_view_bytes: BufferViewBytes,
+    pub nodes: usize,
+    pub meshes: usize,
+    pub primitives: usize,
+    pub materials: usize,
+    pub textures: usize,
+    pub images: usize,
+    pub animations: usize,
+    pub skins: usize,
+    pub largest_meshes: Vec<MeshSize>,
+
+    /// Groups of image names which have identical pixel data
+    pub duplicate_images: Vec<Vec<String>>,
+
+    /// Number of nodes which reference a mesh already used by another node
+    pub instanced_nodes: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ViewCategory {
+    Vertex,
+    Index,
+    Animation,
+    Skin,
+    Image,
+}
+
+fn accessor_bytes(accessor: &gltf::Accessor) -> u64 {
+    (accessor.count() * accessor.size()) as u64
+}
+
+fn image_name(image: &gltf::Image) -> String {
+    match (image.name(), image.source()) {
+        (Some(name), _) => name.to_string(),
+        (None, gltf::image::Source::Uri { uri, .. }) if !uri.starts_with("data:") => {
+            uri.to_string()
+        }
+        _ => format!("image_{}", image.index()),
+    }
+}
+
+/// Collects statistics about the contents of a glTF file, to guide which
+/// size reducing options are worth using.
+pub fn analyze_gltf(gltf: &GltfData) -> GltfAnalysis {
+    let document = &gltf.document;
+    let mut view_categories: HashMap<usize, ViewCategory> = HashMap::new();
+    let mut categorize = |accessor: &gltf::Accessor, category: ViewCategory| {
+        if let Some(view) = accessor.view() {
+            view_categories.entry(view.index()).or_insert(category);
+        }
+    };
+
+    let mut mesh_instances = vec![0; document.meshes().len()];
+    for node in document.nodes() {
+        if let Some(mesh) = node.mesh() {
+            mesh_instances[mesh.index()] += 1;
+        }
+    }
+
+    let mut largest_meshes = Vec::new();
+    let mut primitives = 0;
+    for mesh in document.meshes() {
+        let mut mesh_size = MeshSize {
+            name: mesh
+                .name()
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| format!("mesh_{}", mesh.index())),
+            instances: mesh_instances[mesh.index()],
+            ..Default::default()
+        };
+
+        for primitive in mesh.primitives() {
+            mesh_size.primitives += 1;
+            for (semantic, accessor) in primitive.attributes() {
+                if semantic == gltf::Semantic::Positions {
+                    mesh_size.vertices += accessor.count();
+                }
+                mesh_size.bytes += accessor_bytes(&accessor);
+                categorize(&accessor, ViewCategory::Vertex);
+            }
+
+            for target in primitive.morph_targets() {
+                for accessor in [target.positions(), target.normals(), target.tangents()]
+                    .into_iter()
+                    .flatten()
+                {
+                    mesh_size.bytes += accessor_bytes(&accessor);
+                    categorize(&accessor, ViewCategory::Vertex);
+                }
+            }
+
+            if let Some(accessor) = primitive.indices() {
+                mesh_size.triangles += accessor.count() / 3;
+                mesh_size.bytes += accessor_bytes(&accessor);
+                categorize(&accessor, ViewCategory::Index);
+            }
+        }
+
+        primitives += mesh_size.primitives;
+        largest_meshes.push(mesh_size);
+    }
+    largest_meshes.sort_by_key(|mesh| std::cmp::Reverse(mesh.bytes));
+    largest_meshes.truncate(LARGEST_MESHES_COUNT);
+
+    for animation in document.animations() {
+        for sampler in animation.samplers() {
+            categorize(&sampler.input(), ViewCategory::Animation);
+            categorize(&sampler.output(), ViewCategory::Animation);
+        }
+    }
+
+    for skin in document.skins() {
+        if let Some(accessor) = skin.inverse_bind_matrices() {
+            categorize(&accessor, ViewCategory::Skin);
+        }
+    }
+
+    for image in document.images() {
+        if let gltf::image::Source::View { view, .. } = image.source() {
+            view_categories.insert(view.index(), ViewCategory::Image);
+        }
+    }
+
+    let mut buffer_view_bytes = BufferViewBytes::default();
+    for view in document.views() {
+        let bytes = match view_categories.get(&view.index()) {
+            Some(ViewCategory::Vertex) => &mut buffer_view_bytes.vertex,
+            Some(ViewCategory::Index) => &mut buffer_view_bytes.index,
+            Some(ViewCategory::Animation) => &mut buffer_view_bytes.animation,
+            Some(ViewCategory::Skin) => &mut buffer_view_bytes.skin,
+            Some(ViewCategory::Image) => &mut buffer_view_bytes.image,
+            None => &mut buffer_view_bytes.other,
+        };
+        *bytes += view.length() as u64;
+    }
+
+    // Group images by a hash of their decoded pixels
+    let mut image_hashes: HashMap<u64, Vec<String>> = HashMap::new();
+    for (image, data) in document.images().zip(gltf.images.iter()) {
+        let mut hasher = DefaultHasher::new();
+        data.width.hash(&mut hasher);
+        data.height.hash(&mut hasher);
+        data.pixels.hash(&mut hasher);
+        image_hashes
+            .entry(hasher.finish())
+            .or_default()
+            .push(image_name(&image));
+    }
+    let mut duplicate_images: Vec<Vec<String>> = image_hashes
+        .into_values()
+        .filter(|names| names.len() > 1)
+        .collect();
+    duplicate_images.sort();
+
+    GltfAnalysis {
+        buffer_view_bytes,
+        nodes: document.nodes().len(),
+        meshes: document.meshes().len(),
+        primitives,
+        materials: document.materials().len(),
+        textures: document.textures().len(),
+        images: document.images().len(),
+        animations: document.animations().len(),
+        skins: document.skins().len(),
+        largest_meshes,
+        duplicate_images,
+        instanced_nodes: mesh_instances
+            .iter()
+            .map(|instances| instances.saturating_sub(1))
+            .sum(),
+    }
+}
+
+fn percent(bytes: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        bytes as f64 * 100.0 / total as f64
+    }
+}
+
+impl fmt::Display for GltfAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = &self.buffer_view_bytes;
+        let total = bytes.total();
+
+        writeln!(f, "Buffer views: {} bytes", total)?;
+        for (category, category_bytes) in [
+            ("vertex", bytes.vertex),
+            ("index", bytes.index),
+            ("animation", bytes.animation),
+            ("skin", bytes.skin),
+            ("image", bytes.image),
+            ("other", bytes.other),
+        ] {
+            writeln!(
+                f,
+                "  {:<10} {:>12} bytes ({:5.1}%)",
+                category,
+                category_bytes,
+                percent(category_bytes, total)
+            )?;
+        }
+
+        writeln!(f, "Nodes: {}", self.nodes)?;
+        writeln!(
+            f,
+            "Meshes: {} ({} primitives)",
+            self.meshes, self.primitives
+        )?;
+        writeln!(f, "Materials: {}", self.materials)?;
+        writeln!(f, "Textures: {} ({} images)", self.textures, self.images)?;
+        writeln!(f, "Animations: {}", self.animations)?;
+        writeln!(f, "Skins: {}", self.skins)?;
+
+        if !self.largest_meshes.is_empty() {
+            writeln!(f, "Largest meshes:")?;
+            for mesh in self.largest_meshes.iter() {
+                writeln!(
+                    f,
+                    "  {:>12} bytes {:>8} vertices {:>8} triangles {:>4} instances  {}",
+                    mesh.bytes, mesh.vertices, mesh.triangles, mesh.instances, mesh.name
+                )?;
+            }
+        }
+
+        if !self.duplicate_images.is_empty() {
+            writeln!(f, "Duplicate images:")?;
+            for names in self.duplicate_images.iter() {
+                writeln!(f, "  {}", names.join(", "))?;
+            }
+        }
+
+        let mut suggestions = Vec::new();
+        if percent(bytes.vertex + bytes.index, total) > 50.0 {
+            suggestions.push("Geometry dominates the file, mesh compression (Draco or meshopt) would reduce it the most.");
+        }
+        if percent(bytes.image, total) > 50.0 {
+            suggestions.push(
+                "Images dominate the file, compressed textures (KTX2) would reduce it the most.",
+            );
+        }
+        if !self.duplicate_images.is_empty() {
+            suggestions
+                .push("Some images are identical, deduplicating them would reduce the file size.");
+        }
+        if self.instanced_nodes > 0 {
+            suggestions.push("Some meshes are used by multiple nodes, GPU instancing would reduce the node count.");
+        }
+
+        if !suggestions.is_empty() {
+            writeln!(f, "Suggestions:")?;
+            for suggestion in suggestions {
+                writeln!(f, "  {}", suggestion)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,11 @@
-use bytes::{BufMut, BytesMut};
-use rose_file_lib::files::{zmo, ZMO};
-use serde_json::json;
+use anyhow::Context;
+use base64::Engine;
+use bytes::{Buf, BufMut};
+use rose_file_lib::{
+    files::{zmo, ZMO},
+    utils::{Quaternion, Vector2, Vector3},
+};
+use serde_json::{json, value::RawValue, Value};
 
 use gltf_json::{
     accessor, animation, buffer,
@@ -8,19 +13,467 @@ use gltf_json::{
     Index, Node,
 };
 
-use crate::pad_align;
+use crate::{
+    error::{CategorizedError, ErrorCategory},
+    pad_align,
+    report::time_phase,
+    spill::BinaryData,
+};
 
 pub trait GetAnimationChannelNode {
     fn get(&self, root: &mut gltf_json::Root, channel: u32) -> Index<Node>;
 }
 
+/// Converts a value in [-1, 1] to a normalized i16 as defined by the glTF spec.
+fn quantize_i16(value: f32) -> i16 {
+    (value.clamp(-1.0, 1.0) * 32767.0).round() as i16
+}
+
+/// Frame data of a channel which glTF can not animate, such as alpha or UV
+/// channels, in the little endian layout of a ZMO.
+fn passthrough_frame_data(frames: &zmo::ChannelData) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    match frames {
+        zmo::ChannelData::Normal(normals) => {
+            for normal in normals.iter() {
+                data.put_f32_le(normal.x);
+                data.put_f32_le(normal.y);
+                data.put_f32_le(normal.z);
+            }
+        }
+        zmo::ChannelData::Alpha(values) | zmo::ChannelData::Texture(values) => {
+            for value in values.iter() {
+                data.put_f32_le(*value);
+            }
+        }
+        zmo::ChannelData::UV1(uvs)
+        | zmo::ChannelData::UV2(uvs)
+        | zmo::ChannelData::UV3(uvs)
+        | zmo::ChannelData::UV4(uvs) => {
+            for uv in uvs.iter() {
+                data.put_f32_le(uv.x);
+                data.put_f32_le(uv.y);
+            }
+        }
+        _ => return None,
+    }
+    Some(data)
+}
+
+fn passthrough_channel_data(typ: zmo::ChannelType, data: &[u8]) -> Option<zmo::ChannelData> {
+    let values: Vec<f32> = data
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+    let vector2s = || {
+        values
+            .chunks_exact(2)
+            .map(|v| Vector2::new(v[0], v[1]))
+            .collect()
+    };
+    Some(match typ {
+        zmo::ChannelType::Normal => zmo::ChannelData::Normal(
+            values
+                .chunks_exact(3)
+                .map(|v| Vector3::new(v[0], v[1], v[2]))
+                .collect(),
+        ),
+        zmo::ChannelType::Alpha => zmo::ChannelData::Alpha(values),
+        zmo::ChannelType::Texture => zmo::ChannelData::Texture(values),
+        zmo::ChannelType::UV1 => zmo::ChannelData::UV1(vector2s()),
+        zmo::ChannelType::UV2 => zmo::ChannelData::UV2(vector2s()),
+        zmo::ChannelType::UV3 => zmo::ChannelData::UV3(vector2s()),
+        zmo::ChannelType::UV4 => zmo::ChannelData::UV4(vector2s()),
+        _ => return None,
+    })
+}
+
+/// Extras recording the fps of `zmo`, and keeping the
+/// channels glTF can not animate so they can be restored by
+/// `read_passthrough_channels` when converting back.
+fn animation_extras(zmo: &ZMO) -> Option<Box<RawValue>> {
+    let channels: Vec<Value> = zmo
+        .channels
+        .iter()
+        .filter_map(|channel| {
+            let data = passthrough_frame_data(&channel.frames)?;
+            Some(json!({
+                "type": channel.typ as u32,
+                "index": channel.index,
+                "data": base64::engine::general_purpose::STANDARD.encode(data),
+            }))
+        })
+        .collect();
+
+    let mut rose_zmo = json!({ "fps": zmo.fps });
+    if !channels.is_empty() {
+        rose_zmo["channels"] = Value::Array(channels);
+    }
+    RawValue::from_string(json!({ "rose_zmo": rose_zmo }).to_string()).ok()
+}
+
+fn rose_zmo_extras(extras: &gltf_json::Extras) -> Option<Value> {
+    extras
+        .as_ref()
+        .and_then(|extras| serde_json::from_str::<Value>(extras.get()).ok())
+        .and_then(|mut extras| extras.get_mut("rose_zmo").map(Value::take))
+}
+
+/// The fps of the ZMO an animation was converted from, as recorded in its
+/// extras by `load_animation`.
+pub fn read_recorded_fps(extras: &gltf_json::Extras) -> Option<u32> {
+    let fps = rose_zmo_extras(extras)?["fps"].as_u64()?;
+    (fps > 0).then_some(fps as u32)
+}
+
+/// Reads the channels kept in the extras of an animation by `load_animation`,
+/// resampled to `num_frames` frames sampled `sample_rate` times per second of
+/// the animation starting from `start_time`.
+pub fn read_passthrough_channels(
+    extras: &gltf_json::Extras,
+    start_time: f32,
+    sample_rate: f32,
+    num_frames: u32,
+) -> Vec<zmo::Channel> {
+    let Some(rose_zmo) = rose_zmo_extras(extras) else {
+        return Vec::new();
+    };
+    let source_fps = rose_zmo["fps"]
+        .as_f64()
+        .map_or(sample_rate, |fps| fps as f32);
+
+    let mut channels = Vec::new();
+    for channel in rose_zmo["channels"].as_array().into_iter().flatten() {
+        let Some(typ) = channel["type"]
+            .as_u64()
+            .and_then(|typ| zmo::ChannelType::try_from(typ as u32).ok())
+        else {
+            continue;
+        };
+        let Some(frames) = channel["data"]
+            .as_str()
+            .and_then(|data| base64::engine::general_purpose::STANDARD.decode(data).ok())
+            .and_then(|data| passthrough_channel_data(typ, &data))
+        else {
+            continue;
+        };
+
+        let mut channel = zmo::Channel {
+            typ,
+            index: channel["index"].as_u64().unwrap_or(0) as u32,
+            frames,
+        };
+        resample_frames(
+            &mut channel.frames,
+            |frame_index| {
+                ((start_time + frame_index as f32 / sample_rate) * source_fps).round() as usize
+            },
+            num_frames,
+        );
+        channels.push(channel);
+    }
+    channels
+}
+
+/// The frames `start_frame..=end_frame` of `zmo`, channels with fewer frames
+/// are truncated.
+pub fn slice_frames(zmo: &ZMO, start_frame: u32, end_frame: u32) -> ZMO {
+    fn slice<T: Copy>(values: &[T], start_frame: u32, end_frame: u32) -> Vec<T> {
+        let end = values.len().min(end_frame as usize + 1);
+        values
+            .get(start_frame as usize..end)
+            .unwrap_or_default()
+            .to_vec()
+    }
+
+    let channels = zmo
+        .channels
+        .iter()
+        .map(|channel| zmo::Channel {
+            typ: channel.typ,
+            index: channel.index,
+            frames: match &channel.frames {
+                zmo::ChannelData::None => zmo::ChannelData::None,
+                zmo::ChannelData::Position(values) => {
+                    zmo::ChannelData::Position(slice(values, start_frame, end_frame))
+                }
+                zmo::ChannelData::Rotation(values) => {
+                    zmo::ChannelData::Rotation(slice(values, start_frame, end_frame))
+                }
+                zmo::ChannelData::Normal(values) => {
+                    zmo::ChannelData::Normal(slice(values, start_frame, end_frame))
+                }
+                zmo::ChannelData::Alpha(values) => {
+                    zmo::ChannelData::Alpha(slice(values, start_frame, end_frame))
+                }
+                zmo::ChannelData::UV1(values) => {
+                    zmo::ChannelData::UV1(slice(values, start_frame, end_frame))
+                }
+                zmo::ChannelData::UV2(values) => {
+                    zmo::ChannelData::UV2(slice(values, start_frame, end_frame))
+                }
+                zmo::ChannelData::UV3(values) => {
+                    zmo::ChannelData::UV3(slice(values, start_frame, end_frame))
+                }
+                zmo::ChannelData::UV4(values) => {
+                    zmo::ChannelData::UV4(slice(values, start_frame, end_frame))
+                }
+                zmo::ChannelData::Texture(values) => {
+                    zmo::ChannelData::Texture(slice(values, start_frame, end_frame))
+                }
+                zmo::ChannelData::Scale(values) => {
+                    zmo::ChannelData::Scale(slice(values, start_frame, end_frame))
+                }
+            },
+        })
+        .collect();
+
+    ZMO {
+        identifier: zmo.identifier.clone(),
+        fps: zmo.fps,
+        frames: end_frame - start_frame + 1,
+        channels,
+    }
+}
+
+/// The factor animation durations are scaled by, which must be positive, or
+/// 1 when there is none.
+pub fn retime_factor(retime: Option<f32>) -> anyhow::Result<f32> {
+    match retime {
+        None => Ok(1.0),
+        Some(factor) if factor.is_finite() && factor > 0.0 => Ok(factor),
+        Some(factor) => Err(CategorizedError::error(
+            ErrorCategory::Validation,
+            format!("Retime factor {} must be greater than 0", factor),
+        )),
+    }
+}
+
+/// Multiplies the keyframe times of every animation by `factor`, which must
+/// be in `binary_data`.
+pub fn retime_animations(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    factor: f32,
+) -> anyhow::Result<()> {
+    let mut inputs: Vec<usize> = root
+        .animations
+        .iter()
+        .flat_map(|animation| animation.samplers.iter())
+        .map(|sampler| sampler.input.value())
+        .collect();
+    inputs.sort_unstable();
+    inputs.dedup();
+
+    for input in inputs {
+        let accessor = &mut root.accessors[input];
+        let Some(view) = accessor.buffer_view else {
+            continue;
+        };
+        let view = &root.buffer_views[view.value()];
+        let start = view.byte_offset.map_or(0, |offset| offset.0 as usize)
+            + accessor.byte_offset.map_or(0, |offset| offset.0 as usize);
+        let end = start + accessor.count.0 as usize * 4;
+
+        let Some(times) = binary_data
+            .read(start..end)
+            .context("Failed to read temporary file")?
+        else {
+            continue;
+        };
+        let mut times = times.into_owned();
+        let mut max_time = 0.0f32;
+        for time in times.chunks_exact_mut(4) {
+            let retimed = (&*time).get_f32_le() * factor;
+            time.copy_from_slice(&retimed.to_le_bytes());
+            max_time = max_time.max(retimed);
+        }
+        binary_data
+            .write_at(start, &times)
+            .context("Failed to write temporary file")?;
+        accessor.max = Some(json!([max_time]));
+    }
+    Ok(())
+}
+
+/// Appends `next` to `frames`, separated by `gap_frames` frames which hold the
+/// last frame, or blend from it to the first frame of `next` with `blend`.
+fn append_frames<T: Copy>(
+    frames: &mut Vec<T>,
+    next: &[T],
+    gap_frames: u32,
+    blend: Option<fn(T, T, f32) -> T>,
+) {
+    if let (Some(&last), Some(&first)) = (frames.last(), next.first()) {
+        for gap_frame in 1..=gap_frames {
+            let t = gap_frame as f32 / (gap_frames + 1) as f32;
+            frames.push(match blend {
+                Some(blend) => blend(last, first, t),
+                None => last,
+            });
+        }
+    }
+    frames.extend_from_slice(next);
+}
+
+fn lerp_f32(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn lerp_vector2(a: Vector2<f32>, b: Vector2<f32>, t: f32) -> Vector2<f32> {
+    Vector2::new(lerp_f32(a.x, b.x, t), lerp_f32(a.y, b.y, t))
+}
+
+fn lerp_vector3(a: Vector3<f32>, b: Vector3<f32>, t: f32) -> Vector3<f32> {
+    Vector3::new(
+        lerp_f32(a.x, b.x, t),
+        lerp_f32(a.y, b.y, t),
+        lerp_f32(a.z, b.z, t),
+    )
+}
+
+fn slerp_quaternion(a: Quaternion, b: Quaternion, t: f32) -> Quaternion {
+    let a = glam::Quat::from_xyzw(a.x, a.y, a.z, a.w).normalize();
+    let b = glam::Quat::from_xyzw(b.x, b.y, b.z, b.w).normalize();
+    let q = a.slerp(b, t);
+    Quaternion::new(q.x, q.y, q.z, q.w)
+}
+
+/// Appends the ZMOs of `animations` end to end, which must have the same fps
+/// and channels, with `gap_frames` frames between each. The gap holds the
+/// last frame of the previous animation, or blends to the next with `blend`.
+pub fn concatenate_zmos(
+    animations: &[(&str, &ZMO)],
+    gap_frames: u32,
+    blend: bool,
+) -> anyhow::Result<ZMO> {
+    let Some(((first_name, first), rest)) = animations.split_first() else {
+        return Err(CategorizedError::error(
+            ErrorCategory::Validation,
+            "No animations to concatenate".to_string(),
+        ));
+    };
+
+    // Slicing copies the channels, which ZMO can not clone
+    let mut result = slice_frames(first, 0, first.frames.saturating_sub(1));
+    result.frames = first.frames;
+
+    for (name, zmo) in rest {
+        if zmo.fps != result.fps {
+            return Err(CategorizedError::error(
+                ErrorCategory::Validation,
+                format!(
+                    "Animation {} has {} fps but {} has {} fps, they can not be concatenated",
+                    name, zmo.fps, first_name, result.fps
+                ),
+            ));
+        }
+        if zmo.channels.len() != result.channels.len() {
+            return Err(CategorizedError::error(
+                ErrorCategory::Validation,
+                format!(
+                    "Animation {} has {} channels but {} has {}, they can not be concatenated",
+                    name,
+                    zmo.channels.len(),
+                    first_name,
+                    result.channels.len()
+                ),
+            ));
+        }
+
+        for channel in result.channels.iter_mut() {
+            let next = zmo
+                .channels
+                .iter()
+                .find(|next| next.typ == channel.typ && next.index == channel.index);
+            let appended = match (&mut channel.frames, next.map(|next| &next.frames)) {
+                (zmo::ChannelData::Position(frames), Some(zmo::ChannelData::Position(next)))
+                | (zmo::ChannelData::Normal(frames), Some(zmo::ChannelData::Normal(next))) => {
+                    append_frames(frames, next, gap_frames, blend.then_some(lerp_vector3));
+                    true
+                }
+                (zmo::ChannelData::Rotation(frames), Some(zmo::ChannelData::Rotation(next))) => {
+                    append_frames(frames, next, gap_frames, blend.then_some(slerp_quaternion));
+                    true
+                }
+                (zmo::ChannelData::Alpha(frames), Some(zmo::ChannelData::Alpha(next)))
+                | (zmo::ChannelData::Scale(frames), Some(zmo::ChannelData::Scale(next))) => {
+                    append_frames(frames, next, gap_frames, blend.then_some(lerp_f32));
+                    true
+                }
+                (zmo::ChannelData::Texture(frames), Some(zmo::ChannelData::Texture(next))) => {
+                    // Texture indices can not be blended
+                    append_frames(frames, next, gap_frames, None);
+                    true
+                }
+                (zmo::ChannelData::UV1(frames), Some(zmo::ChannelData::UV1(next)))
+                | (zmo::ChannelData::UV2(frames), Some(zmo::ChannelData::UV2(next)))
+                | (zmo::ChannelData::UV3(frames), Some(zmo::ChannelData::UV3(next)))
+                | (zmo::ChannelData::UV4(frames), Some(zmo::ChannelData::UV4(next))) => {
+                    append_frames(frames, next, gap_frames, blend.then_some(lerp_vector2));
+                    true
+                }
+                (zmo::ChannelData::None, Some(zmo::ChannelData::None)) => true,
+                _ => false,
+            };
+            if !appended {
+                return Err(CategorizedError::error(
+                    ErrorCategory::Validation,
+                    format!(
+                        "Animation {} has no {:?} channel for index {} which {} has, they can not be concatenated",
+                        name, channel.typ, channel.index, first_name
+                    ),
+                ));
+            }
+        }
+        result.frames += gap_frames + zmo.frames;
+    }
+
+    Ok(result)
+}
+
+/// Replaces the frames with `num_frames` frames, each a copy of the source
+/// frame returned by `source_frame`, clamped to the last source frame.
+fn resample_frames(
+    frames: &mut zmo::ChannelData,
+    source_frame: impl Fn(u32) -> usize,
+    num_frames: u32,
+) {
+    fn resample<T: Copy>(
+        values: &mut Vec<T>,
+        source_frame: impl Fn(u32) -> usize,
+        num_frames: u32,
+    ) {
+        if values.is_empty() {
+            return;
+        }
+        *values = (0..num_frames)
+            .map(|frame_index| values[source_frame(frame_index).min(values.len() - 1)])
+            .collect();
+    }
+
+    match frames {
+        zmo::ChannelData::Normal(values) => resample(values, source_frame, num_frames),
+        zmo::ChannelData::Alpha(values) | zmo::ChannelData::Texture(values) => {
+            resample(values, source_frame, num_frames)
+        }
+        zmo::ChannelData::UV1(values)
+        | zmo::ChannelData::UV2(values)
+        | zmo::ChannelData::UV3(values)
+        | zmo::ChannelData::UV4(values) => resample(values, source_frame, num_frames),
+        _ => {}
+    }
+}
+
 pub fn load_animation(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
+    binary_data: &mut BinaryData,
     zmo: &ZMO,
     name: &str,
     channel_nodes: impl GetAnimationChannelNode,
+    quantize_rotations: bool,
 ) {
+    let _timer = time_phase("animation");
     let mut channels = Vec::new();
     let mut samplers = Vec::new();
 
@@ -80,6 +533,14 @@ pub fn load_animation(
                     binary_data.put_f32_le(-position.y / 100.0);
                 }
             }
+            zmo::ChannelData::Rotation(rotations) if quantize_rotations => {
+                for rotation in rotations.iter() {
+                    binary_data.put_i16_le(quantize_i16(rotation.x));
+                    binary_data.put_i16_le(quantize_i16(rotation.z));
+                    binary_data.put_i16_le(quantize_i16(-rotation.y));
+                    binary_data.put_i16_le(quantize_i16(rotation.w));
+                }
+            }
             zmo::ChannelData::Rotation(rotations) => {
                 for rotation in rotations.iter() {
                     binary_data.put_f32_le(rotation.x);
@@ -111,15 +572,18 @@ pub fn load_animation(
             target: None,
         });
 
+        let quantized = quantize_rotations && matches!(channel.typ, zmo::ChannelType::Rotation);
         let keyframe_data_accessor_index = Index::new(root.accessors.len() as u32);
         root.accessors.push(accessor::Accessor {
             name: Some(format!("{}_Channel{}_DataAccessor", name, channel_id)),
             buffer_view: Some(Index::new(buffer_view_index)),
             byte_offset: Some(USize64(0)),
             count: USize64::from(zmo.frames as usize),
-            component_type: Checked::Valid(accessor::GenericComponentType(
-                accessor::ComponentType::F32,
-            )),
+            component_type: Checked::Valid(accessor::GenericComponentType(if quantized {
+                accessor::ComponentType::I16
+            } else {
+                accessor::ComponentType::F32
+            })),
             extensions: Default::default(),
             extras: Default::default(),
             type_: Checked::Valid(if matches!(channel.typ, zmo::ChannelType::Rotation) {
@@ -129,7 +593,7 @@ pub fn load_animation(
             }),
             min: None,
             max: None,
-            normalized: false,
+            normalized: quantized,
             sparse: None,
         });
 
@@ -162,7 +626,7 @@ pub fn load_animation(
 
     root.animations.push(animation::Animation {
         extensions: Default::default(),
-        extras: Default::default(),
+        extras: animation_extras(zmo),
         channels,
         name: Some(name.to_string()),
         samplers,
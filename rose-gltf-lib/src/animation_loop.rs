@@ -0,0 +1,123 @@
+//! Tagging animations as looped or played once, ROSE only knows this from how
+//! a motion is used, e.g. walking loops while dying plays once.
+use anyhow::Context;
+use rose_file_lib::files::{chr::CharacterMotionType, zmo, ZMO};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::provenance::{extras_object, set_extras};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnimationLoop {
+    /// The animation repeats, e.g. standing, walking or running
+    Loop,
+
+    /// The animation plays once, e.g. attacking or dying
+    Once,
+}
+
+impl AnimationLoop {
+    /// Whether a character plays the motion of `motion_type` in a loop.
+    pub fn from_motion_type(motion_type: CharacterMotionType) -> Self {
+        match motion_type {
+            CharacterMotionType::Stop
+            | CharacterMotionType::Stop2
+            | CharacterMotionType::Move
+            | CharacterMotionType::Run => AnimationLoop::Loop,
+            _ => AnimationLoop::Once,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnimationLoopRule {
+    /// Regex matched against the animation name
+    pub pattern: String,
+
+    pub mode: AnimationLoop,
+}
+
+pub(crate) struct CompiledLoopRules(Vec<(regex::Regex, AnimationLoop)>);
+
+impl CompiledLoopRules {
+    pub fn new(rules: &[AnimationLoopRule]) -> anyhow::Result<Self> {
+        rules
+            .iter()
+            .map(|rule| {
+                regex::Regex::new(&rule.pattern)
+                    .with_context(|| format!("Invalid animation loop pattern: {}", rule.pattern))
+                    .map(|regex| (regex, rule.mode))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map(Self)
+    }
+
+    /// The mode of the first rule whose pattern matches `name`.
+    pub fn find(&self, name: &str) -> Option<AnimationLoop> {
+        self.0
+            .iter()
+            .find(|(regex, _)| regex.is_match(name))
+            .map(|(_, mode)| *mode)
+    }
+}
+
+/// Removes the last frame of `zmo` when every channel ends on its first
+/// frame, which ROSE loops often do but glTF players would show twice.
+pub(crate) fn trim_last_duplicate_frame(zmo: &mut ZMO) {
+    fn ends_on_first<T: PartialEq>(values: &[T]) -> bool {
+        values.len() < 2 || values.first() == values.last()
+    }
+
+    if zmo.frames < 2 {
+        return;
+    }
+
+    let duplicate = zmo.channels.iter().all(|channel| match &channel.frames {
+        zmo::ChannelData::None => true,
+        zmo::ChannelData::Position(values) | zmo::ChannelData::Normal(values) => {
+            ends_on_first(values)
+        }
+        zmo::ChannelData::Rotation(values) => ends_on_first(values),
+        zmo::ChannelData::Alpha(values)
+        | zmo::ChannelData::Texture(values)
+        | zmo::ChannelData::Scale(values) => ends_on_first(values),
+        zmo::ChannelData::UV1(values)
+        | zmo::ChannelData::UV2(values)
+        | zmo::ChannelData::UV3(values)
+        | zmo::ChannelData::UV4(values) => ends_on_first(values),
+    });
+    if !duplicate {
+        return;
+    }
+
+    zmo.frames -= 1;
+    for channel in zmo.channels.iter_mut() {
+        match &mut channel.frames {
+            zmo::ChannelData::None => {}
+            zmo::ChannelData::Position(values) | zmo::ChannelData::Normal(values) => {
+                values.pop();
+            }
+            zmo::ChannelData::Rotation(values) => {
+                values.pop();
+            }
+            zmo::ChannelData::Alpha(values)
+            | zmo::ChannelData::Texture(values)
+            | zmo::ChannelData::Scale(values) => {
+                values.pop();
+            }
+            zmo::ChannelData::UV1(values)
+            | zmo::ChannelData::UV2(values)
+            | zmo::ChannelData::UV3(values)
+            | zmo::ChannelData::UV4(values) => {
+                values.pop();
+            }
+        }
+    }
+}
+
+/// Sets `loop` in the extras of `animation`.
+pub(crate) fn set_animation_loop(animation: &mut gltf_json::Animation, mode: AnimationLoop) {
+    let mut extras = extras_object(&animation.extras);
+    extras.insert("loop".to_string(), Value::Bool(mode == AnimationLoop::Loop));
+    set_extras(&mut animation.extras, extras);
+}
@@ -0,0 +1,456 @@
+//! Assembling a skinned model from ZSC parts, such as carts, castle gear and
+//! avatars.
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use gltf_json::{extensions, mesh, scene, texture, validation::Checked, Index};
+use rose_file_lib::{
+    files::{zsc, ItemStb, MON, STB, ZMD, ZMO, ZSC},
+    io::RoseFile,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::{retime_animations, retime_factor},
+    animation_loop::{
+        set_animation_loop, trim_last_duplicate_frame, AnimationLoop, CompiledLoopRules,
+    },
+    cache::load_cached,
+    embedded_image::apply_texture_budget,
+    finish_gltf,
+    mesh_builder::{deinterleave_vertex_buffers, VertexBufferLayout},
+    new_gltf_root,
+    object_list::ObjectList,
+    post_process::post_process,
+    provenance::{add_asset_provenance, set_asset_info},
+    report::warn,
+    skeletal_animation::{load_bind_pose_animation, load_skeletal_animation, load_skeleton},
+    spill::BinaryData,
+    zone::{convert_position, convert_rotation, convert_scale},
+    AssetLocator, FsAssetLocator, RoseGltfConvOptions,
+};
+
+/// A model from a ZSC to add to an assembly
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AssemblyPart {
+    /// Path to the ZSC containing the model, relative to the assets root
+    pub models: String,
+
+    /// Index of the model in the ZSC
+    pub model_id: usize,
+}
+
+/// Describes a model made of parts which share a skeleton, e.g. a cart made
+/// of a body, engine, wheels and arms from list_pat.zsc and animated by
+/// wheel motions.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ModelAssembly {
+    /// Used as the prefix for node, mesh and animation names
+    pub name: String,
+
+    /// Path to the ZMD which the parts are skinned or attached to, relative
+    /// to the assets root
+    pub skeleton: String,
+
+    pub parts: Vec<AssemblyPart>,
+
+    /// Paths to ZMOs to export as animations of the skeleton, relative to
+    /// the assets root
+    pub motions: Vec<String>,
+
+    /// Loop mode of motions, keyed by their path in `motions`
+    #[serde(default)]
+    pub motion_loops: HashMap<String, AnimationLoop>,
+
+    /// Alternative textures of parts, exported as KHR_materials_variants so
+    /// a single file covers every face or hair texture of an avatar
+    #[serde(default)]
+    pub texture_variants: Vec<TextureVariant>,
+}
+
+/// A texture swap of one part of a `ModelAssembly`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TextureVariant {
+    /// Name of the variant in the KHR_materials_variants extension
+    pub name: String,
+
+    /// Index in `ModelAssembly::parts` of the part whose textures are replaced
+    pub part: usize,
+
+    /// Texture used instead of the part's textures, relative to the assets
+    /// root
+    pub texture: String,
+}
+
+impl ModelAssembly {
+    /// Adds the model of each item row in `items`, e.g. the rows of
+    /// list_pat.stb for the equipped vehicle parts, from the ZSC `models`.
+    pub fn add_items(
+        &mut self,
+        models: &str,
+        items: &ItemStb,
+        rows: &[usize],
+    ) -> anyhow::Result<()> {
+        for &row in rows {
+            let model_id = items
+                .field_model(row)
+                .filter(|&model_id| model_id > 0)
+                .with_context(|| format!("Item {} has no model", row))?;
+            self.parts.push(AssemblyPart {
+                models: models.to_string(),
+                model_id: model_id as usize,
+            });
+        }
+        Ok(())
+    }
+
+    /// Adds the motions of a character from a CHR, looping the motions its
+    /// motion type plays in a loop such as walking.
+    pub fn add_character_motions(&mut self, character: &MON) {
+        let mut motions: Vec<_> = character.motions.iter().collect();
+        motions.sort();
+        for (&motion_type, path) in motions {
+            if path.is_empty() {
+                continue;
+            }
+            if !self.motions.contains(path) {
+                self.motions.push(path.clone());
+            }
+            self.motion_loops
+                .entry(path.clone())
+                .or_insert_with(|| AnimationLoop::from_motion_type(motion_type));
+        }
+    }
+
+    /// Adds a texture variant of `part` for each row of `stb` with a texture
+    /// path in `column`, e.g. the face or hair textures of an avatar. The
+    /// variants are named `<name>_<row>`.
+    pub fn add_texture_variants(&mut self, name: &str, part: usize, stb: &STB, column: usize) {
+        for row in 0..stb.rows() {
+            if let Some(texture) = stb.value(row, column).filter(|path| !path.is_empty()) {
+                self.texture_variants.push(TextureVariant {
+                    name: format!("{}_{}", name, row),
+                    part,
+                    texture: texture.to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Converts a `ModelAssembly` into a single glTF.
+///
+/// Parts with skinned meshes are bound to the skeleton, other parts are
+/// attached to the bone or dummy bone given by their ZSC part, or to their
+/// parent part.
+pub fn assemble_model(
+    assets_root: &Path,
+    assembly: &ModelAssembly,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<gltf::Gltf> {
+    let assets: Arc<dyn AssetLocator> = match &options.asset_locator {
+        Some(asset_locator) => asset_locator.clone(),
+        None => {
+            let mut roots = options.asset_roots.clone();
+            roots.push(assets_root.to_path_buf());
+            Arc::new(FsAssetLocator::new(roots))
+        }
+    };
+    let asset_cache = options.asset_cache.as_deref();
+
+    let mut binary_data = BinaryData::new(options.low_memory);
+    let mut root = new_gltf_root();
+
+    let skeleton_path = assets.resolve(&assembly.skeleton);
+    let zmd = load_cached::<ZMD>(asset_cache, &skeleton_path)
+        .with_context(|| format!("Failed to load {}", skeleton_path.display()))?;
+    let skin_index = load_skeleton(
+        &mut root,
+        &mut binary_data,
+        &assembly.name,
+        &zmd,
+        options.bone_naming,
+    );
+    if options.bind_pose_animation {
+        load_bind_pose_animation(
+            &mut root,
+            &mut binary_data,
+            skin_index,
+            &zmd,
+            options.quantize_animations,
+        );
+    }
+    let joints = root.skins[skin_index.value()].joints.clone();
+    let dummy_node_start = joints[0].value() + zmd.bones.len();
+
+    let sampler = Index::<texture::Sampler>::new(root.samplers.len() as u32);
+    root.samplers.push(texture::Sampler {
+        name: Some(format!("{}_sampler", assembly.name)),
+        mag_filter: Some(Checked::Valid(texture::MagFilter::Linear)),
+        min_filter: Some(Checked::Valid(texture::MinFilter::LinearMipmapLinear)),
+        wrap_s: Checked::Valid(texture::WrappingMode::Repeat),
+        wrap_t: Checked::Valid(texture::WrappingMode::Repeat),
+        extensions: None,
+        extras: Default::default(),
+    });
+
+    // Parts from the same ZSC share an object list so their meshes and
+    // materials are only exported once.
+    let mut object_lists: Vec<(String, ObjectList)> = Vec::new();
+    for (assembly_part_index, assembly_part) in assembly.parts.iter().enumerate() {
+        let models_path = assets.resolve(&assembly_part.models);
+        let object_list_index = match object_lists
+            .iter()
+            .position(|(path, _)| *path == assembly_part.models)
+        {
+            Some(index) => index,
+            None => {
+                let zsc = load_cached::<ZSC>(asset_cache, &models_path)
+                    .with_context(|| format!("Failed to load {}", models_path.display()))?;
+                let mut object_list = ObjectList::new(zsc, sampler);
+                object_list.generate_missing_tangents = options.generate_missing_tangents;
+                object_list.flip_v = options.flip_v;
+                object_list.f32_vertex_colors = options.f32_vertex_colors;
+                object_list.winding = options.winding;
+                object_list.hard_edge_angle = options.hard_edge_angle;
+                object_list.placeholder_textures = options.placeholder_textures;
+                object_list.embedded_images = options.embedded_images;
+                object_list.asset_cache = options.asset_cache.clone();
+                object_list.low_memory = options.low_memory;
+                object_list.skeleton = Some((assembly.skeleton.clone(), zmd.bones.len()));
+                object_lists.push((assembly_part.models.clone(), object_list));
+                object_lists.len() - 1
+            }
+        };
+        let object_list = &mut object_lists[object_list_index].1;
+
+        object_list
+            .load_object(
+                &format!("{}_{}", assembly.name, object_list_index),
+                assembly_part.model_id,
+                &mut root,
+                &mut binary_data,
+                assets.as_ref(),
+            )
+            .with_context(|| {
+                format!(
+                    "Failed to load model {} of {}",
+                    assembly_part.model_id, assembly_part.models
+                )
+            })?;
+        let name_prefix = format!("{}_part_{}", assembly.name, assembly_part_index);
+        let zsc = object_list.zsc.clone();
+        let Some(model) = zsc.models[assembly_part.model_id].as_ref() else {
+            warn(format!(
+                "Model {} of {} is empty",
+                assembly_part.model_id, assembly_part.models
+            ));
+            continue;
+        };
+
+        let mut part_nodes = Vec::with_capacity(model.parts.len());
+        for (part_index, part) in model.parts.iter().enumerate() {
+            let mesh_data = object_list
+                .meshes
+                .get(&part.mesh_path)
+                .expect("Missing mesh");
+            let skinned = mesh_data
+                .attributes
+                .contains_key(&Checked::Valid(mesh::Semantic::Joints(0)));
+
+            let attributes = mesh_data.attributes.clone();
+            let indices = mesh_data.indices;
+            let material = part
+                .material
+                .as_ref()
+                .and_then(|material| object_list.materials.get(material).copied());
+
+            // Map the default material and each texture variant of this part
+            let mut variant_mappings = Vec::new();
+            if let (Some(part_material), Some(material)) = (part.material.as_ref(), material) {
+                for (variant_index, variant) in assembly.texture_variants.iter().enumerate() {
+                    if variant.part != assembly_part_index {
+                        continue;
+                    }
+
+                    let variant_material = zsc::ModelMaterial {
+                        path: variant.texture.clone(),
+                        ..part_material.clone()
+                    };
+                    if let Some(material_index) = object_list.load_material(
+                        &format!("{}_{}", assembly.name, object_list_index),
+                        &variant_material,
+                        &mut root,
+                        &mut binary_data,
+                        assets.as_ref(),
+                    ) {
+                        object_list
+                            .materials
+                            .insert(variant_material.clone(), material_index);
+                    }
+                    if variant_mappings.is_empty() {
+                        variant_mappings.push(extensions::mesh::Mapping {
+                            material: material.value() as u32,
+                            variants: vec![0],
+                        });
+                    }
+                    variant_mappings.push(extensions::mesh::Mapping {
+                        material: object_list.materials[&variant_material].value() as u32,
+                        variants: vec![variant_index as u32 + 1],
+                    });
+                }
+            }
+
+            let primitive_extensions = if variant_mappings.is_empty() {
+                None
+            } else {
+                Some(extensions::mesh::Primitive {
+                    khr_materials_variants: Some(extensions::mesh::KhrMaterialsVariants {
+                        mappings: variant_mappings,
+                    }),
+                    ..Default::default()
+                })
+            };
+
+            let mesh_index = Index::new(root.meshes.len() as u32);
+            root.meshes.push(mesh::Mesh {
+                name: Some(format!("{}_{}_mesh", name_prefix, part_index)),
+                extensions: Default::default(),
+                extras: Default::default(),
+                primitives: vec![mesh::Primitive {
+                    attributes,
+                    extensions: primitive_extensions,
+                    extras: Default::default(),
+                    indices: Some(indices),
+                    material,
+                    mode: Checked::Valid(mesh::Mode::Triangles),
+                    targets: None,
+                }],
+                weights: None,
+            });
+
+            let node_index = Index::new(root.nodes.len() as u32);
+            root.nodes.push(scene::Node {
+                name: Some(format!("{}_{}", name_prefix, part_index)),
+                camera: None,
+                children: None,
+                extensions: Default::default(),
+                extras: Default::default(),
+                matrix: None,
+                mesh: Some(mesh_index),
+                rotation: (!skinned).then(|| convert_rotation(part.rotation)),
+                scale: (!skinned).then(|| convert_scale(part.scale)),
+                translation: (!skinned).then(|| convert_position(part.position)),
+                skin: skinned.then_some(skin_index),
+                weights: None,
+            });
+            part_nodes.push(node_index);
+
+            // Skinned meshes are positioned by their joints, so they must be
+            // in the scene root rather than below another node.
+            let parent_node = if skinned {
+                None
+            } else if let Some(bone_index) = part.bone_index {
+                joints.get(bone_index as usize).copied()
+            } else if let Some(dummy_index) = part.dummy_index {
+                let dummy_index = dummy_index as usize;
+                (dummy_index < zmd.dummy_bones.len())
+                    .then(|| Index::new((dummy_node_start + dummy_index) as u32))
+            } else {
+                part.parent
+                    .and_then(|parent| part_nodes.get(parent as usize).copied())
+            };
+            match parent_node {
+                Some(parent_node) => root.nodes[parent_node.value()]
+                    .children
+                    .get_or_insert_with(Vec::new)
+                    .push(node_index),
+                None => root.scenes[0].nodes.push(node_index),
+            }
+        }
+    }
+
+    if !assembly.texture_variants.is_empty() {
+        let mut variants = vec![extensions::scene::khr_materials_variants::Variant {
+            name: "default".to_string(),
+        }];
+        variants.extend(assembly.texture_variants.iter().map(|variant| {
+            extensions::scene::khr_materials_variants::Variant {
+                name: variant.name.clone(),
+            }
+        }));
+        root.extensions_used
+            .push("KHR_materials_variants".to_string());
+        root.extensions = Some(extensions::Root {
+            khr_materials_variants: Some(extensions::root::KhrMaterialsVariants { variants }),
+            ..Default::default()
+        });
+    }
+
+    if !options.skip_animations {
+        let animation_loops = CompiledLoopRules::new(&options.animation_loops)?;
+        for motion in assembly.motions.iter() {
+            let motion_path = assets.resolve(motion);
+            let mut zmo = ZMO::from_path(&motion_path)
+                .with_context(|| format!("Failed to load {}", motion_path.display()))?;
+            let name = motion_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let animation_loop = animation_loops
+                .find(&name)
+                .or_else(|| assembly.motion_loops.get(motion).copied());
+            if options.trim_last_duplicate_frame && animation_loop == Some(AnimationLoop::Loop) {
+                trim_last_duplicate_frame(&mut zmo);
+            }
+            load_skeletal_animation(
+                &mut root,
+                &mut binary_data,
+                &name,
+                skin_index,
+                &zmo,
+                options.quantize_animations,
+            );
+            if let (Some(animation), Some(animation_loop)) =
+                (root.animations.last_mut(), animation_loop)
+            {
+                set_animation_loop(animation, animation_loop);
+            }
+        }
+    }
+
+    set_asset_info(
+        &mut root,
+        options.copyright.as_deref(),
+        options.generator.as_deref(),
+    );
+    if !options.skip_provenance {
+        let mut sources = vec![assembly.skeleton.clone()];
+        for part in assembly.parts.iter() {
+            if !sources.contains(&part.models) {
+                sources.push(part.models.clone());
+            }
+        }
+        sources.extend(assembly.motions.iter().cloned());
+        add_asset_provenance(&mut root, &sources, &[]);
+    }
+    let retime = retime_factor(options.retime)?;
+    if retime != 1.0 {
+        retime_animations(&mut root, &mut binary_data, retime)?;
+    }
+    if options.vertex_buffer_layout == VertexBufferLayout::Planar {
+        deinterleave_vertex_buffers(&mut root, &mut binary_data)?;
+    }
+    post_process(&mut root, &options.post_process);
+    if let Some(budget) = options.embedded_images.budget {
+        apply_texture_budget(
+            &mut root,
+            &mut binary_data,
+            &options.embedded_images,
+            budget,
+        )?;
+    }
+    finish_gltf(root, binary_data)
+}
@@ -0,0 +1,141 @@
+//! Resolving the paths of assets referenced by ROSE files to files on disk.
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Normalizes a path referenced by a ROSE file, e.g. `3DDATA\MAPS\JDT01.ZON`
+/// becomes `3ddata/maps/jdt01.zon`.
+pub fn normalize_asset_path(path: &str) -> String {
+    path.split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != ".")
+        .map(|component| component.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Resolves the paths of assets referenced by ROSE files.
+///
+/// The default implementation is `FsAssetLocator`, implement this to load
+/// assets from somewhere else, e.g. an extracted VFS with a different layout.
+pub trait AssetLocator: Send + Sync {
+    /// Find the file for a path referenced by a ROSE file, returns None when
+    /// it does not exist.
+    fn locate(&self, path: &str) -> Option<PathBuf>;
+
+    /// The path of a file relative to the assets root, in the same form as
+    /// the paths referenced by ROSE files, or None if it is not an asset.
+    fn asset_path(&self, file_path: &Path) -> Option<String>;
+
+    /// Like `locate`, but returns a best guess path for missing files so
+    /// that errors when opening them name a useful path.
+    fn resolve(&self, path: &str) -> PathBuf {
+        self.locate(path)
+            .unwrap_or_else(|| PathBuf::from(normalize_asset_path(path)))
+    }
+}
+
+/// Locates assets in one or more directories on disk, each containing the
+/// `3ddata` directory. Roots are searched in order and path components are
+/// matched case insensitively.
+#[derive(Clone, Debug, Default)]
+pub struct FsAssetLocator {
+    roots: Vec<PathBuf>,
+}
+
+impl FsAssetLocator {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self { roots }
+    }
+
+    /// Searches `roots` first, then the directory containing the `3ddata`
+    /// directory which `file_path` is in, if any.
+    pub fn for_file(file_path: &Path, roots: &[PathBuf]) -> Self {
+        let mut roots = roots.to_vec();
+        if let Some(root) = find_assets_root_path(file_path) {
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+        Self { roots }
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+}
+
+/// Finds `normalized_path` below `root`, ignoring the case of each component.
+fn find_case_insensitive(root: &Path, normalized_path: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for component in normalized_path.split('/') {
+        let exact = path.join(component);
+        if exact.exists() {
+            path = exact;
+            continue;
+        }
+
+        path = std::fs::read_dir(&path)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .find(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(component))
+            })?
+            .path();
+    }
+    Some(path)
+}
+
+impl AssetLocator for FsAssetLocator {
+    fn locate(&self, path: &str) -> Option<PathBuf> {
+        let normalized_path = normalize_asset_path(path);
+        if normalized_path.is_empty() {
+            return None;
+        }
+
+        self.roots.iter().find_map(|root| {
+            let original_case = root.join(path.replace('\\', "/"));
+            if original_case.is_file() {
+                Some(original_case)
+            } else {
+                find_case_insensitive(root, &normalized_path)
+            }
+        })
+    }
+
+    fn asset_path(&self, file_path: &Path) -> Option<String> {
+        self.roots.iter().find_map(|root| {
+            file_path
+                .strip_prefix(root)
+                .ok()
+                .map(|relative| normalize_asset_path(&relative.to_string_lossy()))
+        })
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf {
+        self.locate(path)
+            .unwrap_or_else(|| match self.roots.first() {
+                Some(root) => root.join(path.replace('\\', "/")),
+                None => PathBuf::from(normalize_asset_path(path)),
+            })
+    }
+}
+
+/// Finds the directory containing the `3ddata` directory which `file_path`
+/// is in.
+pub fn find_assets_root_path(file_path: &Path) -> Option<PathBuf> {
+    let mut path = file_path;
+    while let Some(parent_path) = path.parent() {
+        if parent_path
+            .file_name()
+            .is_some_and(|s| OsStr::new("3ddata").eq_ignore_ascii_case(s))
+        {
+            return parent_path.parent().map(|p| p.to_path_buf());
+        }
+
+        path = parent_path;
+    }
+
+    None
+}
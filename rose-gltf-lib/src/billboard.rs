@@ -0,0 +1,61 @@
+//! Detecting object parts which are billboarded in the client, such as tree
+//! and grass cards which turn to face the camera.
+use glam::{Quat, Vec3};
+use gltf_json::scene::UnitQuaternion;
+use rose_file_lib::files::zsc;
+use serde_json::{json, Value};
+
+use crate::{mesh_builder::MeshData, zone::convert_rotation};
+
+pub(crate) struct Billboard {
+    /// Normal of the card in the space of the mesh
+    normal: Vec3,
+
+    /// The axis rotation of the ZSC part, when it is flagged as a billboard
+    axis_rotation: Option<UnitQuaternion>,
+}
+
+impl Billboard {
+    /// Parts with an axis rotation are always billboards. Otherwise flat,
+    /// upright meshes with an alpha tested material are treated as cards.
+    pub fn detect(part: &zsc::ModelPart, mesh_data: &MeshData) -> Option<Self> {
+        if let Some(axis_rotation) = part.axis_rotation.as_ref() {
+            return Some(Self {
+                normal: mesh_data.plane_normal.unwrap_or(Vec3::Z),
+                axis_rotation: Some(convert_rotation(*axis_rotation)),
+            });
+        }
+
+        let alpha_tested = part
+            .material
+            .as_ref()
+            .is_some_and(|material| material.alpha_test.is_some());
+        mesh_data
+            .plane_normal
+            .filter(|normal| alpha_tested && normal.y.abs() < 0.5)
+            .map(|normal| Self {
+                normal,
+                axis_rotation: None,
+            })
+    }
+
+    /// Rotation which turns the card to face +Z, so that an engine which
+    /// points the node at the camera shows the front of the card.
+    pub fn pre_rotation(&self) -> UnitQuaternion {
+        let rotation = Quat::from_rotation_arc(self.normal, Vec3::Z);
+        UnitQuaternion([rotation.x, rotation.y, rotation.z, rotation.w])
+    }
+
+    /// Value of the `rose_billboard` extras of the part node
+    pub fn extras(&self, pre_rotated: bool) -> Value {
+        let mut extras = json!({
+            "source": if self.axis_rotation.is_some() { "axis_rotation" } else { "card" },
+            "normal": if pre_rotated { Vec3::Z } else { self.normal }.to_array(),
+            "pre_rotated": pre_rotated,
+        });
+        if let Some(axis_rotation) = self.axis_rotation.as_ref() {
+            extras["axis_rotation"] = json!(axis_rotation.0);
+        }
+        extras
+    }
+}
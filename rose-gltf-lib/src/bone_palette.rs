@@ -0,0 +1,227 @@
+use std::collections::{BTreeSet, HashMap};
+
+use rose_file_lib::{files::ZMS, io::RoseFile, utils::Vector3};
+
+use crate::error::{CategorizedError, ErrorCategory};
+
+/// Maximum number of bones a single ZMS can bind to.
+pub const MAX_MESH_BONES: usize = 48;
+
+fn vertex_bones(zms: &ZMS, vertex_index: usize) -> impl Iterator<Item = i16> {
+    let vertex = &zms.vertices[vertex_index];
+    [
+        (vertex.bone_indices.x, vertex.bone_weights.x),
+        (vertex.bone_indices.y, vertex.bone_weights.y),
+        (vertex.bone_indices.z, vertex.bone_weights.z),
+        (vertex.bone_indices.w, vertex.bone_weights.w),
+    ]
+    .into_iter()
+    .filter(|(_, weight)| *weight > 0.0)
+    .map(|(bone_index, _)| bone_index)
+}
+
+fn triangle_bones(zms: &ZMS, triangle: &Vector3<i16>) -> BTreeSet<i16> {
+    [triangle.x, triangle.y, triangle.z]
+        .into_iter()
+        .flat_map(|vertex_index| vertex_bones(zms, vertex_index as u16 as usize))
+        .collect()
+}
+
+fn format_indices(indices: &BTreeSet<i16>) -> String {
+    indices
+        .iter()
+        .map(|index| index.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Checks that the weighted vertex bone indices of a skinned ZMS are within its
+/// bone palette and, when the skeleton is known, that the bone palette is
+/// within the `(name, number of bones)` of the skeleton.
+pub fn validate_bone_palette(
+    zms: &ZMS,
+    mesh_name: &str,
+    skeleton: Option<(&str, usize)>,
+) -> anyhow::Result<()> {
+    let mut problems = Vec::new();
+
+    let invalid_palette_indices: BTreeSet<i16> = (0..zms.vertices.len())
+        .flat_map(|vertex_index| vertex_bones(zms, vertex_index))
+        .filter(|&index| index < 0 || index as usize >= zms.bones.len())
+        .collect();
+    if !invalid_palette_indices.is_empty() {
+        problems.push(format!(
+            "vertices reference bone palette indices {} but the bone palette has {} bones",
+            format_indices(&invalid_palette_indices),
+            zms.bones.len()
+        ));
+    }
+
+    if let Some((skeleton_name, num_skeleton_bones)) = skeleton {
+        let invalid_bones: BTreeSet<i16> = zms
+            .bones
+            .iter()
+            .copied()
+            .filter(|&bone| bone < 0 || bone as usize >= num_skeleton_bones)
+            .collect();
+        if !invalid_bones.is_empty() {
+            problems.push(format!(
+                "bone palette references bones {} but skeleton {} has {} bones",
+                format_indices(&invalid_bones),
+                skeleton_name,
+                num_skeleton_bones
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    Err(CategorizedError::error(
+        ErrorCategory::Validation,
+        format!("Mesh {}: {}", mesh_name, problems.join(", ")),
+    ))
+}
+
+/// Splits a mesh whose vertices reference skeleton bone indices into as few
+/// meshes as possible which each bind to at most `MAX_MESH_BONES` bones.
+///
+/// Triangles are assigned greedily to the first partition which can hold their
+/// bones, the vertices are then compacted per partition. A triangle binds to at
+/// most 12 bones, 4 for each vertex, so it always fits in a partition.
+///
+/// The partitions have no `strips`, which index the vertices of the whole mesh.
+/// Meshes converted from glTF never have strips, only triangle `indices`.
+pub fn partition_mesh_bones(zms: ZMS) -> Vec<ZMS> {
+    let bones_used: BTreeSet<i16> = (0..zms.vertices.len())
+        .flat_map(|vertex_index| vertex_bones(&zms, vertex_index))
+        .collect();
+    if bones_used.len() <= MAX_MESH_BONES {
+        return vec![zms];
+    }
+
+    let mut partitions: Vec<(BTreeSet<i16>, Vec<&Vector3<i16>>)> = Vec::new();
+    for triangle in zms.indices.iter() {
+        let bones = triangle_bones(&zms, triangle);
+        let partition = partitions
+            .iter_mut()
+            .find(|(partition_bones, _)| partition_bones.union(&bones).count() <= MAX_MESH_BONES);
+        match partition {
+            Some((partition_bones, triangles)) => {
+                partition_bones.extend(bones);
+                triangles.push(triangle);
+            }
+            None => partitions.push((bones, vec![triangle])),
+        }
+    }
+
+    partitions
+        .into_iter()
+        .map(|(_, triangles)| {
+            let mut partition = ZMS::new();
+            partition.identifier = zms.identifier.clone();
+            partition.format = zms.format;
+            partition.materials = zms.materials.clone();
+            partition.mesh_type = zms.mesh_type.clone();
+            partition.layout = zms.layout;
+
+            let mut vertex_map = HashMap::new();
+            let mut remap = |vertex_index: i16| -> i16 {
+                *vertex_map.entry(vertex_index).or_insert_with(|| {
+                    partition
+                        .vertices
+                        .push(zms.vertices[vertex_index as u16 as usize].clone());
+                    (partition.vertices.len() - 1) as i16
+                })
+            };
+
+            let indices: Vec<Vector3<i16>> = triangles
+                .into_iter()
+                .map(|triangle| Vector3 {
+                    x: remap(triangle.x),
+                    y: remap(triangle.y),
+                    z: remap(triangle.z),
+                })
+                .collect();
+            partition.indices = indices;
+
+            if let Some(first) = partition.vertices.first() {
+                let mut min_pos = first.position;
+                let mut max_pos = first.position;
+                for vertex in partition.vertices.iter() {
+                    min_pos.x = min_pos.x.min(vertex.position.x);
+                    min_pos.y = min_pos.y.min(vertex.position.y);
+                    min_pos.z = min_pos.z.min(vertex.position.z);
+
+                    max_pos.x = max_pos.x.max(vertex.position.x);
+                    max_pos.y = max_pos.y.max(vertex.position.y);
+                    max_pos.z = max_pos.z.max(vertex.position.z);
+                }
+                partition.bounding_box.min = min_pos;
+                partition.bounding_box.max = max_pos;
+            }
+
+            partition
+        })
+        .collect()
+}
+
+/// Narrows the skeleton bone indices referenced by the vertices down to a bone
+/// palette in `zms.bones`, rewriting the vertex bone indices to index it.
+pub fn apply_bone_palette(zms: &mut ZMS) -> anyhow::Result<()> {
+    let bones_used: BTreeSet<i16> = (0..zms.vertices.len())
+        .flat_map(|vertex_index| vertex_bones(zms, vertex_index))
+        .collect();
+
+    if bones_used.len() > MAX_MESH_BONES {
+        return Err(CategorizedError::error(
+            ErrorCategory::Unsupported,
+            format!(
+                "A mesh can only bind to a maximum of {} bones",
+                MAX_MESH_BONES
+            ),
+        ));
+    }
+
+    // Map from the bone index in the skeleton to the index of the bone
+    // indices list in the mesh
+    let mut bone_map = HashMap::new();
+    zms.bones.clear();
+    for bone_idx in bones_used {
+        bone_map.insert(bone_idx, zms.bones.len());
+        zms.bones.push(bone_idx);
+    }
+
+    for vertex in zms.vertices.iter_mut() {
+        if let Some(new_idx) = bone_map.get(&vertex.bone_indices.x) {
+            vertex.bone_indices.x = *new_idx as i16;
+        } else {
+            vertex.bone_indices.x = 0;
+            vertex.bone_weights.x = 0.0;
+        }
+
+        if let Some(new_idx) = bone_map.get(&vertex.bone_indices.y) {
+            vertex.bone_indices.y = *new_idx as i16;
+        } else {
+            vertex.bone_indices.y = 0;
+            vertex.bone_weights.y = 0.0;
+        }
+
+        if let Some(new_idx) = bone_map.get(&vertex.bone_indices.z) {
+            vertex.bone_indices.z = *new_idx as i16;
+        } else {
+            vertex.bone_indices.z = 0;
+            vertex.bone_weights.z = 0.0;
+        }
+
+        if let Some(new_idx) = bone_map.get(&vertex.bone_indices.w) {
+            vertex.bone_indices.w = *new_idx as i16;
+        } else {
+            vertex.bone_indices.w = 0;
+            vertex.bone_weights.w = 0.0;
+        }
+    }
+
+    Ok(())
+}
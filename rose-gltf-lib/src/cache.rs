@@ -0,0 +1,175 @@
+//! Cache of parsed ROSE files shared between conversions.
+use std::any::Any;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use image::{DynamicImage, ImageError};
+use lru::LruCache;
+use rose_file_lib::{error::RoseLibError, io::RoseFile};
+
+use crate::{assets::normalize_asset_path, report::time_phase};
+
+/// Number of hits and misses of an `AssetCache`
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AssetCacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// The modification time and size of a file when it was loaded, a cached
+/// file is only used while they are unchanged
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct FileVersion {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileVersion {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+struct CachedFile {
+    version: Option<FileVersion>,
+    file: Arc<dyn Any + Send + Sync>,
+}
+
+struct AssetCacheInner {
+    files: LruCache<String, CachedFile>,
+    stats: AssetCacheStats,
+}
+
+/// LRU cache of parsed files such as ZSC, STB and ZMS and of decoded
+/// textures, keyed by their normalized path.
+///
+/// Batch converting many zones would otherwise parse the same object lists
+/// and meshes for every zone. The cache is thread safe, wrap it in an `Arc`
+/// and set it in the options of each conversion to share it.
+///
+/// Each lookup compares the modification time and size of the file with
+/// those it had when it was cached, so files changed on disk while the cache
+/// is kept, e.g. between requests of a long running process, are loaded
+/// again.
+pub struct AssetCache {
+    inner: Mutex<AssetCacheInner>,
+}
+
+impl AssetCache {
+    /// Create a cache holding at most `capacity` files, at least one file is
+    /// always kept.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(AssetCacheInner {
+                files: LruCache::new(NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN)),
+                stats: AssetCacheStats::default(),
+            }),
+        }
+    }
+
+    /// Returns the cached value for `path`, or loads it and adds it to the
+    /// cache.
+    ///
+    /// The value is loaded without holding the lock, so threads loading the
+    /// same path at the same time may both load it.
+    fn get_or_load<T, E>(
+        &self,
+        path: &Path,
+        load: impl FnOnce(&Path) -> Result<T, E>,
+    ) -> Result<Arc<T>, E>
+    where
+        T: Send + Sync + 'static,
+    {
+        let key = normalize_asset_path(&path.to_string_lossy());
+        let version = FileVersion::of(path);
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let cached = inner
+                .files
+                .get(&key)
+                .filter(|cached| version.is_some() && cached.version == version)
+                .and_then(|cached| cached.file.clone().downcast::<T>().ok());
+            if let Some(file) = cached {
+                inner.stats.hits += 1;
+                return Ok(file);
+            }
+            inner.stats.misses += 1;
+        }
+
+        let file = Arc::new(load(path)?);
+        self.inner.lock().unwrap().files.put(
+            key,
+            CachedFile {
+                version,
+                file: file.clone() as Arc<dyn Any + Send + Sync>,
+            },
+        );
+        Ok(file)
+    }
+
+    /// Returns the cached file at `path`, or reads it and adds it to the cache.
+    pub fn get<T>(&self, path: &Path) -> Result<Arc<T>, RoseLibError>
+    where
+        T: RoseFile + Send + Sync + 'static,
+    {
+        self.get_or_load(path, T::from_path_mmap)
+    }
+
+    /// Returns the cached decoded image at `path`, or decodes it and adds it
+    /// to the cache.
+    pub fn image(&self, path: &Path) -> Result<Arc<DynamicImage>, ImageError> {
+        self.get_or_load(path, decode_image)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().files.clear();
+    }
+
+    pub fn stats(&self) -> AssetCacheStats {
+        self.inner.lock().unwrap().stats
+    }
+}
+
+fn decode_image(path: &Path) -> Result<DynamicImage, ImageError> {
+    let _timer = time_phase("image_decode");
+    image::open(path)
+}
+
+/// Read a file through the cache if there is one.
+pub(crate) fn load_cached<T>(
+    cache: Option<&AssetCache>,
+    path: &Path,
+) -> Result<Arc<T>, RoseLibError>
+where
+    T: RoseFile + Send + Sync + 'static,
+{
+    match cache {
+        Some(cache) => cache.get(path),
+        None => Ok(Arc::new(T::from_path_mmap(path)?)),
+    }
+}
+
+/// Decode an image through the cache if there is one.
+pub(crate) fn load_cached_image(
+    cache: Option<&AssetCache>,
+    path: &Path,
+) -> Result<Arc<DynamicImage>, ImageError> {
+    match cache {
+        Some(cache) => cache.image(path),
+        None => Ok(Arc::new(decode_image(path)?)),
+    }
+}
@@ -0,0 +1,44 @@
+//! Cancelling a conversion from another thread, e.g. when the user closes the
+//! window of a GUI converting a zone.
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::error::{CategorizedError, ErrorCategory};
+
+/// Cancels the conversions it was given to when `cancel` is called, clones
+/// share the same state.
+///
+/// Conversions check it between input files, zone blocks and objects, and
+/// return an error of `ErrorCategory::Cancelled`. `export_zones` and
+/// `export_zone_blocks` remove the files they already wrote before
+/// returning it.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Returns a `Cancelled` error when `token` has been cancelled
+pub(crate) fn check_cancelled(token: Option<&CancellationToken>) -> anyhow::Result<()> {
+    if token.is_some_and(|token| token.is_cancelled()) {
+        Err(CategorizedError::error(
+            ErrorCategory::Cancelled,
+            "Conversion was cancelled".to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+//! Removing degenerate triangles and the vertices no triangle uses, which
+//! break tangent generation and make buffers larger than they need to be.
+use glam::Vec3;
+
+use crate::report::warn;
+
+/// Removes the triangles which repeat a vertex or have no area, and rewrites
+/// the indices of the rest to skip the vertices no triangle uses. Returns the
+/// number of triangles removed and the previous index of each vertex kept, in
+/// ascending order, for `compact_vertices`. Meshes without a single valid triangle are left as
+/// they are.
+pub(crate) fn remove_degenerate_triangles(
+    triangles: &mut Vec<[usize; 3]>,
+    positions: &[Vec3],
+) -> (usize, Vec<usize>) {
+    let is_valid = |triangle: &[usize; 3]| {
+        let [Some(a), Some(b), Some(c)] = triangle.map(|index| positions.get(index)) else {
+            return false;
+        };
+        triangle[0] != triangle[1]
+            && triangle[1] != triangle[2]
+            && triangle[0] != triangle[2]
+            && (*b - *a).cross(*c - *a).length_squared() > 0.0
+    };
+    if !triangles.iter().any(is_valid) {
+        return (0, (0..positions.len()).collect());
+    }
+
+    let num_triangles = triangles.len();
+    triangles.retain(is_valid);
+
+    // Kept vertices stay in their original order, so nothing moves when every
+    // vertex is still used
+    let mut used = vec![false; positions.len()];
+    for &index in triangles.iter().flatten() {
+        used[index] = true;
+    }
+    let mut new_indices = vec![0; positions.len()];
+    let mut kept_vertices = Vec::with_capacity(positions.len());
+    for (old_index, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+        new_indices[old_index] = kept_vertices.len();
+        kept_vertices.push(old_index);
+    }
+    for index in triangles.iter_mut().flatten() {
+        *index = new_indices[*index];
+    }
+
+    (num_triangles - triangles.len(), kept_vertices)
+}
+
+/// Keeps the values of the vertices in `kept_vertices`, in that order, a
+/// vertex listed more than once is duplicated. Attributes which the mesh does
+/// not have are left empty.
+pub(crate) fn compact_vertices<T: Clone>(values: &mut Vec<T>, kept_vertices: &[usize]) {
+    if !values.is_empty() {
+        *values = kept_vertices
+            .iter()
+            .map(|&index| values[index].clone())
+            .collect();
+    }
+}
+
+/// Warns about what was removed from a mesh, if anything
+pub(crate) fn report_removed(mesh_name: &str, degenerate_triangles: usize, unused_vertices: usize) {
+    if degenerate_triangles > 0 || unused_vertices > 0 {
+        warn(format!(
+            "Mesh {}: removed {} degenerate triangles and {} unused vertices",
+            mesh_name, degenerate_triangles, unused_vertices
+        ));
+    }
+}
@@ -0,0 +1,94 @@
+//! Sharing identical images, textures and materials, such as the materials
+//! of objects which are in both the deco and cnst lists of a zone.
+use std::{collections::HashMap, io};
+
+use gltf_json::{Index, Root};
+
+use crate::{prune::material_textures, spill::BinaryData};
+
+/// Key used to find duplicates of an object, its JSON without the name
+pub(crate) fn dedup_key<T: Clone + serde::Serialize>(
+    value: &T,
+    clear_name: impl FnOnce(&mut T),
+) -> String {
+    let mut value = value.clone();
+    clear_name(&mut value);
+    serde_json::to_string(&value).unwrap_or_default()
+}
+
+/// The index of the first item with the same key as each item
+fn first_with_same_key<T>(items: &[T], key: impl Fn(&T) -> String) -> Vec<u32> {
+    let mut first_indices = HashMap::new();
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| *first_indices.entry(key(item)).or_insert(index as u32))
+        .collect()
+}
+
+/// Points every use of an image with the same content as an earlier one, or
+/// of a sampler, texture or material which only differs by name, to the
+/// earlier one. The duplicates are left unused for `prune_unused` to remove.
+pub(crate) fn share_duplicates(root: &mut Root, binary_data: &BinaryData) -> io::Result<()> {
+    let image_keys = root
+        .images
+        .iter()
+        .map(|image| {
+            Ok(match (image.buffer_view, image.uri.as_ref()) {
+                (Some(view), _) => {
+                    let view = &root.buffer_views[view.value()];
+                    let start = view.byte_offset.map_or(0, |offset| offset.0 as usize);
+                    let data = binary_data
+                        .read(start..start + view.byte_length.0 as usize)?
+                        .unwrap_or_default();
+                    format!("{}:{:?}", blake3::hash(&data).to_hex(), image.mime_type)
+                }
+                (None, Some(uri)) => uri.clone(),
+                (None, None) => String::new(),
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    let image_map = first_with_same_key(&image_keys, String::clone);
+    let sampler_map = first_with_same_key(&root.samplers, |sampler| {
+        dedup_key(sampler, |sampler| sampler.name = None)
+    });
+
+    for texture in root.textures.iter_mut() {
+        texture.source = Index::new(image_map[texture.source.value()]);
+        texture.sampler = texture
+            .sampler
+            .map(|sampler| Index::new(sampler_map[sampler.value()]));
+    }
+    let texture_map = first_with_same_key(&root.textures, |texture| {
+        dedup_key(texture, |texture| texture.name = None)
+    });
+
+    for material in root.materials.iter_mut() {
+        for texture in material_textures(material) {
+            *texture = Index::new(texture_map[texture.value()]);
+        }
+    }
+    let material_map = first_with_same_key(&root.materials, |material| {
+        dedup_key(material, |material| material.name = None)
+    });
+
+    for primitive in root
+        .meshes
+        .iter_mut()
+        .flat_map(|mesh| mesh.primitives.iter_mut())
+    {
+        primitive.material = primitive
+            .material
+            .map(|material| Index::new(material_map[material.value()]));
+        if let Some(variants) = primitive
+            .extensions
+            .as_mut()
+            .and_then(|extensions| extensions.khr_materials_variants.as_mut())
+        {
+            for mapping in variants.mappings.iter_mut() {
+                mapping.material = material_map[mapping.material as usize];
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Encoding the images embedded in the glTF, every texture is written in the
+//! same format so downstream tools only need to handle one.
+use std::{borrow::Cow, io::Cursor};
+
+use anyhow::Context;
+
+use bytes::BufMut;
+use gltf_json::{buffer, validation::USize64, Index};
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageReader};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pad_align,
+    prune::prune_unused,
+    report::{info, time_phase},
+    spill::BinaryData,
+};
+
+/// Format of the images embedded in the glTF
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageEncoding {
+    #[default]
+    Png,
+
+    /// JPEG with a quality from 1 to 100, the alpha channel is dropped.
+    /// Images whose alpha is used are still written as PNG.
+    Jpeg { quality: u8 },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddedImageOptions {
+    pub encoding: ImageEncoding,
+
+    /// Images whose width or height is larger are scaled down to fit,
+    /// keeping their aspect ratio
+    pub max_size: Option<u32>,
+
+    /// Total bytes of the images once decoded to RGBA8, the largest images
+    /// are halved in size until they fit
+    pub budget: Option<u64>,
+}
+
+impl EmbeddedImageOptions {
+    /// Encodes `image`, returning the data and its mime type. Images whose
+    /// alpha is used by their material, `keep_alpha`, are always written as
+    /// PNG, JPEG has no alpha channel.
+    pub fn encode(&self, image: &DynamicImage, keep_alpha: bool) -> (Vec<u8>, &'static str) {
+        let _timer = time_phase("image_encode");
+        let resized;
+        let image = match self.max_size {
+            Some(max_size) if image.width() > max_size || image.height() > max_size => {
+                resized = image.resize(max_size, max_size, FilterType::Triangle);
+                &resized
+            }
+            _ => image,
+        };
+
+        let mut buffer = Vec::new();
+        match self.encoding {
+            ImageEncoding::Jpeg { quality } if !keep_alpha => {
+                JpegEncoder::new_with_quality(&mut buffer, quality.clamp(1, 100))
+                    .encode_image(&image.to_rgb8())
+                    .expect("Failed to write JPEG");
+                (buffer, "image/jpeg")
+            }
+            _ => {
+                image
+                    .write_to(&mut Cursor::new(&mut buffer), image::ImageFormat::Png)
+                    .expect("Failed to write PNG");
+                (buffer, "image/png")
+            }
+        }
+    }
+}
+
+fn image_memory((width, height): (u32, u32)) -> u64 {
+    width as u64 * height as u64 * 4
+}
+
+/// The encoded data of an image embedded in `binary_data`
+fn embedded_image_data(
+    root: &gltf_json::Root,
+    binary_data: &BinaryData,
+    image: &gltf_json::Image,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let Some(view) = image.buffer_view else {
+        return Ok(None);
+    };
+    let view = &root.buffer_views[view.value()];
+    let start = view.byte_offset.map_or(0, |offset| offset.0 as usize);
+    let data = binary_data
+        .read(start..start + view.byte_length.0 as usize)
+        .context("Failed to read temporary file")?;
+    Ok(data.map(Cow::into_owned))
+}
+
+/// Halves the largest images embedded in `binary_data` until their decoded
+/// size fits `budget`, the replaced image data is left for `prune_unused` to
+/// remove.
+pub(crate) fn apply_texture_budget(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    options: &EmbeddedImageOptions,
+    budget: u64,
+) -> anyhow::Result<()> {
+    // Images which are not used, e.g. of filtered blocks, do not count
+    let empty = binary_data.new_like();
+    *binary_data = prune_unused(root, std::mem::replace(binary_data, empty))?;
+
+    let original_sizes = root
+        .images
+        .iter()
+        .map(|image| {
+            Ok(
+                embedded_image_data(root, binary_data, image)?.and_then(|data| {
+                    ImageReader::new(Cursor::new(data))
+                        .with_guessed_format()
+                        .ok()?
+                        .into_dimensions()
+                        .ok()
+                }),
+            )
+        })
+        .collect::<anyhow::Result<Vec<Option<(u32, u32)>>>>()?;
+
+    let mut sizes = original_sizes.clone();
+    let mut total: u64 = sizes.iter().flatten().copied().map(image_memory).sum();
+    while total > budget {
+        let Some(size) = sizes
+            .iter_mut()
+            .flatten()
+            .filter(|(width, height)| *width > 1 || *height > 1)
+            .max_by_key(|size| image_memory(**size))
+        else {
+            break;
+        };
+        total -= image_memory(*size);
+        *size = ((size.0 / 2).max(1), (size.1 / 2).max(1));
+        total += image_memory(*size);
+    }
+
+    for (image_index, (original_size, size)) in original_sizes.into_iter().zip(sizes).enumerate() {
+        let (Some(original_size), Some(size)) = (original_size, size) else {
+            continue;
+        };
+        if original_size == size {
+            continue;
+        }
+
+        let image = &root.images[image_index];
+        let Some(decoded) = embedded_image_data(root, binary_data, image)?
+            .and_then(|data| image::load_from_memory(&data).ok())
+        else {
+            continue;
+        };
+        // Images were only written as PNG in place of JPEG to keep their alpha
+        let keep_alpha = image
+            .mime_type
+            .as_ref()
+            .is_some_and(|mime_type| mime_type.0 == "image/png");
+        let (data, mime_type) = options.encode(
+            &decoded.resize_exact(size.0, size.1, FilterType::Lanczos3),
+            keep_alpha,
+        );
+
+        pad_align(binary_data);
+        let view_index = Index::new(root.buffer_views.len() as u32);
+        let view = &root.buffer_views[image.buffer_view.unwrap().value()];
+        root.buffer_views.push(buffer::View {
+            name: view.name.clone(),
+            buffer: view.buffer,
+            byte_length: USize64::from(data.len()),
+            byte_offset: Some(USize64::from(binary_data.len())),
+            byte_stride: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+            target: None,
+        });
+        binary_data.put_slice(&data);
+
+        let image = &mut root.images[image_index];
+        image.buffer_view = Some(view_index);
+        image.mime_type = Some(gltf_json::image::MimeType(mime_type.into()));
+        info(format!(
+            "Scaled {} from {}x{} to {}x{} to fit the texture budget",
+            image
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("image {}", image_index)),
+            original_size.0,
+            original_size.1,
+            size.0,
+            size.1
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! Hints in the conventions of game engine importers, so converted zones get
+//! their collision and materials set up on import.
+use rose_file_lib::files::zsc::{
+    ModelCollisionFlags, ModelCollisionShape, ModelMaterial, ModelPart,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EngineHints {
+    #[default]
+    None,
+
+    /// Suffix the names of colliding nodes with `-col` or `-convcol`, which
+    /// Godot's importer turns into static bodies.
+    Godot,
+
+    /// Add `unity` extras with the collider of colliding nodes and the
+    /// Standard shader properties of materials, for an `AssetPostprocessor`
+    /// to apply.
+    Unity,
+}
+
+/// The shape a part collides with, None for parts which can be walked
+/// through.
+pub(crate) fn part_collider(part: &ModelPart) -> Option<ModelCollisionShape> {
+    part.collision_shape.filter(|_| {
+        !part
+            .collision_flags
+            .contains(ModelCollisionFlags::Passthrough)
+    })
+}
+
+impl EngineHints {
+    /// Name of a node whose mesh collides with `collider`
+    pub(crate) fn node_name(self, name: String, collider: Option<ModelCollisionShape>) -> String {
+        match (self, collider) {
+            (EngineHints::Godot, Some(ModelCollisionShape::Mesh)) => format!("{}-col", name),
+            (EngineHints::Godot, Some(_)) => format!("{}-convcol", name),
+            _ => name,
+        }
+    }
+
+    /// Adds the hints of a node whose mesh collides with `collider` to its
+    /// extras
+    pub(crate) fn add_node_extras(self, extras: &mut Value, collider: Option<ModelCollisionShape>) {
+        let (EngineHints::Unity, Some(collider)) = (self, collider) else {
+            return;
+        };
+        extras["unity"] = json!({
+            "collider": match collider {
+                ModelCollisionShape::Sphere => "SphereCollider",
+                ModelCollisionShape::Aabb | ModelCollisionShape::Oobb => "BoxCollider",
+                ModelCollisionShape::Mesh => "MeshCollider",
+            },
+            "isStatic": true,
+        });
+    }
+
+    /// Adds the hints of a material to its extras
+    pub(crate) fn add_material_extras(self, extras: &mut Value, material: &ModelMaterial) {
+        if self != EngineHints::Unity {
+            return;
+        }
+
+        // Modes and blend factors of the Standard shader
+        let (mode, render_type, render_queue) = if material.alpha_test.is_some() {
+            (1, "TransparentCutout", 2450)
+        } else if material.alpha_enabled {
+            (3, "Transparent", 3000)
+        } else {
+            (0, "Opaque", 2000)
+        };
+        let (src_blend, dst_blend) = match (material.alpha_enabled, material.blend_mode) {
+            (true, Some(_)) => (5, 1),
+            (true, None) => (5, 10),
+            (false, _) => (1, 0),
+        };
+        let mut unity = json!({
+            "_Mode": mode,
+            "RenderType": render_type,
+            "renderQueue": render_queue,
+            "_Cull": if material.two_sided { 0 } else { 2 },
+            "_ZWrite": material.z_write_enabled as i32,
+            "_SrcBlend": src_blend,
+            "_DstBlend": dst_blend,
+        });
+        if let Some(alpha_ref) = material.alpha_test {
+            unity["_Cutoff"] = json!(alpha_ref as f32 / 256.0);
+        }
+        extras["unity"] = unity;
+    }
+}
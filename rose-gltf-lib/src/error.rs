@@ -0,0 +1,145 @@
+//! Categories of conversion errors, so callers such as the CLI can tell a
+//! broken input apart from e.g. a missing texture.
+use std::fmt;
+
+use rose_file_lib::error::RoseLibError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCategory {
+    /// An input file could not be parsed
+    InputParse,
+
+    /// A file the input depends on could not be found or loaded, such as
+    /// list_zone.stb, a ZSC or a mesh referenced by a ZSC
+    MissingDependency,
+
+    /// The input uses something the converter does not support, such as an
+    /// unknown file extension
+    Unsupported,
+
+    /// Reading or writing a file failed
+    Io,
+
+    /// The input was read but its contents are not valid, such as a glTF
+    /// failing validation or an animation with an fps of 0
+    Validation,
+
+    /// The conversion was cancelled with a `CancellationToken`
+    Cancelled,
+
+    /// Any other error
+    Other,
+}
+
+impl ErrorCategory {
+    /// Exit code of the CLI for errors of this category, 2 is skipped as it
+    /// is used for invalid command line arguments
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCategory::Other => 1,
+            ErrorCategory::InputParse => 3,
+            ErrorCategory::MissingDependency => 4,
+            ErrorCategory::Unsupported => 5,
+            ErrorCategory::Io => 6,
+            ErrorCategory::Validation => 7,
+            ErrorCategory::Cancelled => 8,
+        }
+    }
+
+    /// Finds the category of an error. A category given with
+    /// `CategoryContext` is used first, otherwise it is guessed from the
+    /// errors in the chain.
+    pub fn of(error: &anyhow::Error) -> Self {
+        if let Some(categorized) = error.downcast_ref::<CategorizedError>() {
+            return categorized.category;
+        }
+
+        for cause in error.chain() {
+            if let Some(error) = cause.downcast_ref::<RoseLibError>() {
+                return match error {
+                    RoseLibError::FileError { .. }
+                    | RoseLibError::IOError(_)
+                    | RoseLibError::SystemTimeError(_) => ErrorCategory::Io,
+                    _ => ErrorCategory::InputParse,
+                };
+            } else if let Some(error) = cause.downcast_ref::<gltf::Error>() {
+                return match error {
+                    gltf::Error::Validation(_) => ErrorCategory::Validation,
+                    gltf::Error::Io(_) => ErrorCategory::Io,
+                    _ => ErrorCategory::InputParse,
+                };
+            } else if let Some(error) = cause.downcast_ref::<image::ImageError>() {
+                return match error {
+                    image::ImageError::Unsupported(_) => ErrorCategory::Unsupported,
+                    image::ImageError::IoError(_) => ErrorCategory::Io,
+                    _ => ErrorCategory::InputParse,
+                };
+            } else if cause.is::<serde_json::Error>() || cause.is::<serde_yaml::Error>() {
+                return ErrorCategory::InputParse;
+            } else if cause.is::<std::io::Error>() {
+                return ErrorCategory::Io;
+            }
+        }
+
+        ErrorCategory::Other
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorCategory::InputParse => "input parse error",
+            ErrorCategory::MissingDependency => "missing dependency",
+            ErrorCategory::Unsupported => "unsupported",
+            ErrorCategory::Io => "I/O error",
+            ErrorCategory::Validation => "validation failure",
+            ErrorCategory::Cancelled => "cancelled",
+            ErrorCategory::Other => "error",
+        })
+    }
+}
+
+/// Context of an error which sets its `ErrorCategory`
+#[derive(Debug)]
+pub(crate) struct CategorizedError {
+    category: ErrorCategory,
+    message: String,
+}
+
+impl CategorizedError {
+    /// Creates an error with the given category
+    pub fn error(category: ErrorCategory, message: String) -> anyhow::Error {
+        anyhow::Error::new(Self { category, message })
+    }
+}
+
+impl fmt::Display for CategorizedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CategorizedError {}
+
+/// Adds context which sets the `ErrorCategory` of an error
+pub(crate) trait CategoryContext<T, E> {
+    fn category_context<F>(self, category: ErrorCategory, f: F) -> anyhow::Result<T>
+    where
+        F: FnOnce() -> String;
+}
+
+impl<T, E, R> CategoryContext<T, E> for R
+where
+    R: anyhow::Context<T, E>,
+{
+    fn category_context<F>(self, category: ErrorCategory, f: F) -> anyhow::Result<T>
+    where
+        F: FnOnce() -> String,
+    {
+        self.with_context(|| CategorizedError {
+            category,
+            message: f(),
+        })
+    }
+}
@@ -0,0 +1,130 @@
+//! Converting terrain heightmap nodes back to HIM. Heights are divided by 100
+//! for glTF, which does not give back the same float when multiplied again,
+//! so the original heights can be kept in the node extras and are reused for
+//! every vertex which was not edited.
+use base64::Engine;
+use rose_file_lib::files::HIM;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Spacing of the heightmap vertices in glTF units, 64 per 160 unit block
+const HEIGHT_SPACING: f32 = 2.5;
+
+#[derive(Serialize, Deserialize)]
+struct HimExtras {
+    block_x: i32,
+    block_y: i32,
+    width: i32,
+    length: i32,
+    grid_count: i32,
+    scale: f32,
+
+    /// Heights as little endian f32, exactly as in the HIM
+    heights: String,
+
+    /// Data after the heights in the HIM
+    #[serde(default)]
+    trailing_data: String,
+}
+
+/// The `rose_him` extras of the heightmap node of a block
+pub(crate) fn him_extras(block_x: i32, block_y: i32, him: &HIM) -> Value {
+    let heights: Vec<u8> = him
+        .heights
+        .iter()
+        .flat_map(|height| height.to_le_bytes())
+        .collect();
+    serde_json::to_value(HimExtras {
+        block_x,
+        block_y,
+        width: him.width,
+        length: him.length,
+        grid_count: him.grid_count,
+        scale: him.scale,
+        heights: base64::engine::general_purpose::STANDARD.encode(heights),
+        trailing_data: base64::engine::general_purpose::STANDARD.encode(&him.trailing_data),
+    })
+    .unwrap_or_default()
+}
+
+fn read_him_extras(node: &gltf::Node) -> Option<(i32, i32, HIM)> {
+    let extras: Value = serde_json::from_str(node.extras().as_ref()?.get()).ok()?;
+    let extras: HimExtras = serde_json::from_value(extras.get("rose_him")?.clone()).ok()?;
+    let heights = base64::engine::general_purpose::STANDARD
+        .decode(&extras.heights)
+        .ok()?;
+    let trailing_data = base64::engine::general_purpose::STANDARD
+        .decode(&extras.trailing_data)
+        .ok()?;
+    let him = HIM {
+        width: extras.width,
+        length: extras.length,
+        grid_count: extras.grid_count,
+        scale: extras.scale,
+        heights: heights
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect(),
+        trailing_data,
+        ..Default::default()
+    };
+    Some((extras.block_x, extras.block_y, him))
+}
+
+/// The block of a node named like `32_32_heightmap`, by `load_heightmap`
+fn parse_heightmap_name(name: &str) -> Option<(i32, i32)> {
+    let (block, _) = name.split_once("_heightmap")?;
+    let (block_x, block_y) = block.split_once('_')?;
+    Some((block_x.parse().ok()?, block_y.parse().ok()?))
+}
+
+/// Builds the HIM of a heightmap node from the heights of its vertices, named
+/// after its block. Heights kept in the node extras are reused for vertices
+/// which still have that height, so unedited terrain converts back to an
+/// identical HIM. Returns None for nodes which are not heightmaps.
+pub(crate) fn heightmap_to_him(
+    node: &gltf::Node,
+    buffers: &[gltf::buffer::Data],
+) -> Option<(String, HIM)> {
+    let mesh = node.mesh()?;
+    let (block_x, block_y, mut him) = read_him_extras(node).or_else(|| {
+        let (block_x, block_y) = parse_heightmap_name(node.name()?)?;
+        let him = HIM {
+            width: 65,
+            length: 65,
+            grid_count: 4,
+            scale: 250.0,
+            heights: vec![0.0; 65 * 65],
+            ..Default::default()
+        };
+        Some((block_x, block_y, him))
+    })?;
+    let original_heights = him.heights.clone();
+    him.heights
+        .resize((him.width * him.length).max(0) as usize, 0.0);
+
+    for primitive in mesh.primitives() {
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
+        let Some(positions) = reader.read_positions() else {
+            continue;
+        };
+        for [x, y, z] in positions {
+            let height_x = (x / HEIGHT_SPACING).round() as i32;
+            let height_y = (z / HEIGHT_SPACING).round() as i32;
+            if !(0..him.width).contains(&height_x) || !(0..him.length).contains(&height_y) {
+                continue;
+            }
+
+            let index = (height_y * him.width + height_x) as usize;
+            him.heights[index] = original_heights
+                .get(index)
+                .copied()
+                .filter(|height| height / 100.0 == y)
+                .unwrap_or(y * 100.0);
+        }
+    }
+
+    him.min_height = him.heights.iter().copied().fold(f32::NAN, f32::min);
+    him.max_height = him.heights.iter().copied().fold(f32::NAN, f32::max);
+    Some((format!("{}_{}", block_x, block_y), him))
+}
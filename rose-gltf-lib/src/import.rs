@@ -0,0 +1,145 @@
+use std::io::{Cursor, Read, Seek};
+
+use anyhow::Context;
+use base64::Engine;
+
+use crate::{
+    error::{CategorizedError, ErrorCategory},
+    zsc_export::percent_decode,
+    GltfData,
+};
+
+fn read_uri<F>(uri: &str, resolver: &mut F) -> anyhow::Result<Vec<u8>>
+where
+    F: FnMut(&str) -> anyhow::Result<Vec<u8>>,
+{
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, encoded) = data
+            .split_once(";base64,")
+            .with_context(|| format!("Unsupported data URI {}", uri))?;
+        return base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .context("Failed to decode base64 data URI");
+    }
+
+    let path = uri
+        .strip_prefix("file://")
+        .or_else(|| uri.strip_prefix("file:"))
+        .unwrap_or(uri);
+    resolver(&percent_decode(path)).with_context(|| format!("Failed to resolve {}", uri))
+}
+
+fn decode_image(data: &[u8], mime_type: Option<&str>) -> anyhow::Result<gltf::image::Data> {
+    let image = match mime_type {
+        Some("image/png") => image::load_from_memory_with_format(data, image::ImageFormat::Png),
+        Some("image/jpeg") => image::load_from_memory_with_format(data, image::ImageFormat::Jpeg),
+        _ => image::load_from_memory(data),
+    }
+    .context("Failed to decode image")?;
+
+    let (width, height) = (image.width(), image.height());
+    let (format, pixels) = match image {
+        image::DynamicImage::ImageLuma8(image) => (gltf::image::Format::R8, image.into_raw()),
+        image::DynamicImage::ImageLumaA8(image) => (gltf::image::Format::R8G8, image.into_raw()),
+        image::DynamicImage::ImageRgb8(image) => (gltf::image::Format::R8G8B8, image.into_raw()),
+        image => (gltf::image::Format::R8G8B8A8, image.into_rgba8().into_raw()),
+    };
+
+    Ok(gltf::image::Data {
+        pixels,
+        format,
+        width,
+        height,
+    })
+}
+
+impl GltfData {
+    /// Reads a .gltf or .glb from memory, any buffers and images must be
+    /// embedded either in the GLB binary chunk or as base64 data URIs.
+    pub fn from_slice(slice: &[u8]) -> anyhow::Result<Self> {
+        Self::from_reader_with_resolver(Cursor::new(slice), |uri| {
+            anyhow::bail!(
+                "External reference {} can not be loaded when reading from a slice",
+                uri
+            )
+        })
+    }
+
+    /// Reads a .gltf or .glb, base64 data URIs are decoded and any other
+    /// buffer or image URI is loaded by calling the resolver with the percent
+    /// decoded URI, e.g. a path relative to the .gltf file.
+    pub fn from_reader_with_resolver<R, F>(reader: R, resolver: F) -> anyhow::Result<Self>
+    where
+        R: Read + Seek,
+        F: FnMut(&str) -> anyhow::Result<Vec<u8>>,
+    {
+        let gltf = gltf::Gltf::from_reader(reader).context("Failed to read GLTF")?;
+        Self::from_gltf_with_resolver(gltf, resolver)
+    }
+
+    /// Loads the buffers and decodes the images of a `gltf::Gltf`, such as
+    /// the output of `rose_to_gltf`, which must all be embedded.
+    pub fn from_gltf(gltf: gltf::Gltf) -> anyhow::Result<Self> {
+        Self::from_gltf_with_resolver(gltf, |uri| {
+            anyhow::bail!("External reference {} can not be loaded", uri)
+        })
+    }
+
+    fn from_gltf_with_resolver<F>(gltf: gltf::Gltf, mut resolver: F) -> anyhow::Result<Self>
+    where
+        F: FnMut(&str) -> anyhow::Result<Vec<u8>>,
+    {
+        let gltf::Gltf { document, mut blob } = gltf;
+
+        let mut buffers = Vec::with_capacity(document.buffers().len());
+        for buffer in document.buffers() {
+            let mut data = match buffer.source() {
+                gltf::buffer::Source::Bin => blob
+                    .take()
+                    .context("GLTF references a binary chunk but has none")?,
+                gltf::buffer::Source::Uri(uri) => read_uri(uri, &mut resolver)?,
+            };
+            if data.len() < buffer.length() {
+                return Err(CategorizedError::error(
+                    ErrorCategory::Validation,
+                    format!(
+                        "Buffer {} is {} bytes but should be {} bytes",
+                        buffer.index(),
+                        data.len(),
+                        buffer.length()
+                    ),
+                ));
+            }
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            buffers.push(gltf::buffer::Data(data));
+        }
+
+        let mut images = Vec::with_capacity(document.images().len());
+        for image in document.images() {
+            let data = match image.source() {
+                gltf::image::Source::View { view, mime_type } => {
+                    let buffer = &buffers[view.buffer().index()];
+                    let data = buffer
+                        .get(view.offset()..view.offset() + view.length())
+                        .with_context(|| {
+                            format!("Image {} buffer view is out of range", image.index())
+                        })?;
+                    decode_image(data, Some(mime_type))
+                }
+                gltf::image::Source::Uri { uri, mime_type } => {
+                    decode_image(&read_uri(uri, &mut resolver)?, mime_type)
+                }
+            }
+            .with_context(|| format!("Failed to load image {}", image.index()))?;
+            images.push(data);
+        }
+
+        Ok(Self {
+            document,
+            buffers,
+            images,
+        })
+    }
+}
@@ -0,0 +1,302 @@
+//! Incremental export of a zone, only the blocks whose files changed since
+//! the previous export are converted again and the zone is assembled from
+//! the converted blocks kept in a cache directory.
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use rose_file_lib::{
+    files::{zsc, IFO, ZON, ZSC},
+    io::RoseFile,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::{normalize_asset_path, AssetLocator, FsAssetLocator},
+    cache::load_cached,
+    cancel::check_cancelled,
+    error::{CategoryContext, ErrorCategory},
+    merge::merge_gltf,
+    region::ZoneContent,
+    report::info,
+    rose_to_gltf, save_gltf,
+    zone::{is_object_disabled, sun_gltf, terrain_lightmap_path},
+    zone_lookup::find_zone_object_lists,
+    zones::{zone_blocks, DEFAULT_CACHE_CAPACITY},
+    AssetCache, GltfData, GltfFormat, RoseGltfConvOptions,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IncrementalBlock {
+    pub block_x: i32,
+    pub block_y: i32,
+
+    /// Hash of the files and options the block was converted from
+    pub hash: String,
+
+    /// The converted block, relative to the cache directory
+    pub segment: PathBuf,
+}
+
+/// The blocks converted by `export_zone_incremental`, written to
+/// incremental.json in the cache directory.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IncrementalManifest {
+    pub blocks: Vec<IncrementalBlock>,
+}
+
+impl IncrementalManifest {
+    pub const FILE_NAME: &'static str = "incremental.json";
+}
+
+/// Which blocks `export_zone_incremental` converted, as `[block_x, block_y]`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IncrementalExport {
+    /// Blocks which were converted because they changed or were not cached
+    pub rebuilt: Vec<[i32; 2]>,
+
+    /// Blocks whose cached conversion was used
+    pub reused: Vec<[i32; 2]>,
+}
+
+/// Hashes the files a block is converted from, the hash of each file is kept
+/// as most assets are used by many blocks
+struct InputHasher<'a> {
+    assets: &'a dyn AssetLocator,
+    file_hashes: HashMap<PathBuf, blake3::Hash>,
+}
+
+impl InputHasher<'_> {
+    fn file_hash(&mut self, path: &Path) -> blake3::Hash {
+        *self
+            .file_hashes
+            .entry(path.to_path_buf())
+            .or_insert_with(|| match std::fs::read(path) {
+                Ok(data) => blake3::hash(&data),
+                Err(_) => blake3::hash(b"missing"),
+            })
+    }
+
+    fn add_file(&mut self, hasher: &mut blake3::Hasher, path: &Path) {
+        let hash = self.file_hash(path);
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update(hash.as_bytes());
+    }
+
+    fn add_asset(&mut self, hasher: &mut blake3::Hasher, path: &str) {
+        hasher.update(normalize_asset_path(path).as_bytes());
+        match self.assets.locate(path) {
+            Some(file_path) => {
+                let hash = self.file_hash(&file_path);
+                hasher.update(hash.as_bytes());
+            }
+            None => {
+                hasher.update(b"missing");
+            }
+        }
+    }
+
+    fn add_model(&mut self, hasher: &mut blake3::Hasher, model: &zsc::Model) {
+        for part in model.parts.iter() {
+            self.add_asset(hasher, &part.mesh_path);
+            if let Some(material) = part.material.as_ref() {
+                self.add_asset(hasher, &material.path);
+            }
+            if let Some(animation_path) = part.animation_path.as_ref() {
+                self.add_asset(hasher, animation_path);
+            }
+        }
+        for dummy in model.dummy_points.iter() {
+            if let Some(zsc::ModelDummyAttachment::Light { name }) = dummy.attachment.as_ref() {
+                self.add_asset(hasher, name);
+            }
+        }
+    }
+}
+
+/// Converts a zone to `output`, keeping each converted block in `cache_dir`
+/// and only converting the blocks whose inputs changed since the previous
+/// export to the same cache directory.
+///
+/// The inputs of a block are its HIM, TIL and IFO and its terrain lightmap,
+/// the meshes, textures, animations and light containers of its objects, its
+/// effects, and the ZON, list_zone.stb, ZSCs, tile textures and options
+/// shared by every block. The zone is assembled by merging the converted
+/// blocks, with the sun added once.
+pub fn export_zone_incremental(
+    zon_path: &Path,
+    output: &Path,
+    cache_dir: &Path,
+    format: &GltfFormat,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<IncrementalExport> {
+    let map_path = zon_path.parent().context("Could not find map path")?;
+    let mut options = options.clone();
+    if options.asset_cache.is_none() {
+        options.asset_cache = Some(Arc::new(AssetCache::new(DEFAULT_CACHE_CAPACITY)));
+    }
+    let assets: Arc<dyn AssetLocator> = match &options.asset_locator {
+        Some(asset_locator) => asset_locator.clone(),
+        None => Arc::new(FsAssetLocator::for_file(zon_path, &options.asset_roots)),
+    };
+    options.asset_locator = Some(assets.clone());
+
+    let relative_zon_path = assets
+        .asset_path(zon_path)
+        .category_context(ErrorCategory::MissingDependency, || {
+            format!("Could not find root assets path of {}", zon_path.display())
+        })?;
+    let asset_cache = options.asset_cache.as_deref();
+    let zone_object_lists =
+        find_zone_object_lists(zon_path, &relative_zon_path, assets.as_ref(), &options)?;
+    let zon = ZON::from_path(zon_path).category_context(ErrorCategory::InputParse, || {
+        format!("Failed to load {}", zon_path.display())
+    })?;
+    let zsc_paths = [&zone_object_lists.deco, &zone_object_lists.cnst];
+    let object_lists =
+        zsc_paths.map(|zsc_path| load_cached::<ZSC>(asset_cache, &assets.resolve(zsc_path)).ok());
+
+    // Everything which is shared by every block
+    let mut input_hasher = InputHasher {
+        assets: assets.as_ref(),
+        file_hashes: HashMap::new(),
+    };
+    let mut zone_hasher = blake3::Hasher::new();
+    zone_hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+    let hashed_options = RoseGltfConvOptions {
+        filter_block_x: None,
+        filter_block_y: None,
+        ..options.clone()
+    };
+    zone_hasher.update(serde_json::to_string(&hashed_options)?.as_bytes());
+    input_hasher.add_file(&mut zone_hasher, zon_path);
+    input_hasher.add_asset(&mut zone_hasher, "3ddata/stb/list_zone.stb");
+    for zsc_path in zsc_paths {
+        input_hasher.add_asset(&mut zone_hasher, zsc_path);
+    }
+    for tile_texture_path in zon.textures.iter() {
+        if tile_texture_path == "end" {
+            break;
+        }
+        input_hasher.add_asset(&mut zone_hasher, tile_texture_path);
+    }
+
+    let previous: IncrementalManifest =
+        std::fs::read(cache_dir.join(IncrementalManifest::FILE_NAME))
+            .ok()
+            .and_then(|manifest| serde_json::from_slice(&manifest).ok())
+            .unwrap_or_default();
+    std::fs::create_dir_all(cache_dir)
+        .with_context(|| format!("Failed to create {}", cache_dir.display()))?;
+
+    let mut segment_options = options.clone();
+    segment_options.zone_content.exclude.push(ZoneContent::Sun);
+
+    let mut manifest = IncrementalManifest::default();
+    let mut export = IncrementalExport::default();
+    let mut segments = Vec::new();
+    for (block_x, block_y, _) in zone_blocks(map_path, &options) {
+        check_cancelled(options.cancellation.as_ref())?;
+        let block_name = format!("{}_{}", block_x, block_y);
+        let mut hasher = zone_hasher.clone();
+        for extension in ["him", "til", "ifo"] {
+            input_hasher.add_file(
+                &mut hasher,
+                &map_path.join(format!("{}.{}", block_name, extension)),
+            );
+        }
+        input_hasher.add_file(
+            &mut hasher,
+            &map_path.join(terrain_lightmap_path(block_x, block_y)),
+        );
+        let ifo = IFO::from_path(&map_path.join(format!("{}.ifo", block_name)))
+            .category_context(ErrorCategory::InputParse, || {
+                format!("Failed to load {}.ifo", block_name)
+            })?;
+        for (zsc, objects) in object_lists.iter().zip([&ifo.objects, &ifo.buildings]) {
+            let Some(zsc) = zsc else {
+                continue;
+            };
+            for object in objects.iter() {
+                if !options.include_disabled_objects && is_object_disabled(object) {
+                    continue;
+                }
+                if let Some(model) = zsc
+                    .models
+                    .get(object.object_id as usize)
+                    .and_then(|model| model.as_ref())
+                {
+                    input_hasher.add_model(&mut hasher, model);
+                }
+            }
+        }
+        for effect in ifo.effects.iter() {
+            input_hasher.add_asset(&mut hasher, &effect.file);
+        }
+        let hash = hasher.finalize().to_hex().to_string();
+
+        let segment = PathBuf::from(&block_name).with_extension("glb");
+        let segment_path = cache_dir.join(&segment);
+        let cached = previous.blocks.iter().any(|block| {
+            (block.block_x, block.block_y) == (block_x, block_y)
+                && block.hash == hash
+                && block.segment == segment
+        });
+        if cached && segment_path.exists() {
+            export.reused.push([block_x, block_y]);
+        } else {
+            let block_options = RoseGltfConvOptions {
+                filter_block_x: Some(block_x),
+                filter_block_y: Some(block_y),
+                ..segment_options.clone()
+            };
+            let gltf = rose_to_gltf(&[zon_path.to_path_buf()], &block_options)
+                .with_context(|| format!("Failed to export block {}", block_name))?;
+            save_gltf(&gltf, &segment_path, &GltfFormat::Binary)?;
+            info(format!("Converted block {}", block_name));
+            export.rebuilt.push([block_x, block_y]);
+        }
+
+        segments.push(segment_path);
+        manifest.blocks.push(IncrementalBlock {
+            block_x,
+            block_y,
+            hash,
+            segment,
+        });
+    }
+
+    // Segments of blocks which no longer exist are left out
+    for block in previous.blocks.iter() {
+        if !manifest
+            .blocks
+            .iter()
+            .any(|current| current.segment == block.segment)
+        {
+            let _ = std::fs::remove_file(cache_dir.join(&block.segment));
+        }
+    }
+
+    let manifest_path = cache_dir.join(IncrementalManifest::FILE_NAME);
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+
+    let mut inputs = Vec::with_capacity(segments.len() + 1);
+    for segment_path in segments.iter() {
+        let data = std::fs::read(segment_path)
+            .with_context(|| format!("Failed to read {}", segment_path.display()))?;
+        inputs.push(
+            GltfData::from_slice(&data)
+                .with_context(|| format!("Failed to read {}", segment_path.display()))?,
+        );
+    }
+    if options.zone_content.includes(ZoneContent::Sun) {
+        inputs.push(GltfData::from_gltf(sun_gltf()?)?);
+    }
+    let gltf = merge_gltf(&inputs)?;
+    save_gltf(&gltf, output, format)?;
+    Ok(export)
+}
@@ -1,21 +1,21 @@
 use std::{
     borrow::Cow,
     collections::{HashMap, HashSet},
-    ffi::OsStr,
     fs,
     io::Write,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use anyhow::Context;
-use bytes::{BufMut, BytesMut};
-use glam::{Quat, Vec3};
+use bytes::BufMut;
+use glam::{Mat4, Quat, Vec2, Vec3};
 use gltf::{
     animation::{
         util::{ReadOutputs, Rotations},
         Interpolation,
     },
-    mesh::util::{ReadColors, ReadIndices, ReadJoints, ReadTexCoords, ReadWeights},
+    mesh::util::{ReadColors, ReadIndices, ReadTexCoords},
 };
 use gltf_json::{
     buffer, scene, texture,
@@ -24,30 +24,173 @@ use gltf_json::{
 };
 use rose_file_lib::{
     files::{
-        zmd::Bone,
-        zms::{Vertex, VertexFormat},
-        STB, ZMD, ZMO, ZMS, ZON, ZSC,
+        zmd::{Bone, SkeletonVersion},
+        zms::{Vertex, VertexFormat, VertexLayout},
+        HIM, ZMD, ZMO, ZMS, ZON, ZSC, ZSCTXT,
     },
     io::RoseFile,
-    utils::{Quaternion, Vector3},
+    utils::{Quaternion, Vector3, Vector4},
 };
 
 mod object_list;
-use object_list::ObjectList;
+pub use object_list::{GlassMaterialRules, ObjectList, ObjectPartFiles};
 
 mod mesh_builder;
+use mesh_builder::deinterleave_vertex_buffers;
+pub use mesh_builder::{MeshData, VertexBufferLayout};
 
 mod mesh;
 use mesh::load_mesh;
 
+mod cleanup;
+use cleanup::{compact_vertices, remove_degenerate_triangles, report_removed};
+
+mod winding;
+use winding::flip_triangles;
+pub use winding::WindingCorrection;
+
 mod animation;
+use animation::{
+    concatenate_zmos, read_passthrough_channels, read_recorded_fps, retime_animations,
+    retime_factor, slice_frames,
+};
+mod animation_loop;
+use animation_loop::{set_animation_loop, trim_last_duplicate_frame, CompiledLoopRules};
+pub use animation_loop::{AnimationLoop, AnimationLoopRule};
 mod skeletal_animation;
 use serde::{Deserialize, Serialize};
-use serde_json::value::RawValue;
-use skeletal_animation::{load_skeletal_animation, load_skeleton};
+use serde_json::{json, value::RawValue};
+pub use skeletal_animation::BoneNaming;
+use skeletal_animation::{
+    dummy_name_index, load_bind_pose_animation, load_skeletal_animation, load_skeleton,
+    strip_duplicate_suffix,
+};
 
 mod zone;
 use zone::load_zone;
+pub use zone::{TerrainLightmap, TerrainUvOptions, WaterMaterialOptions};
+
+mod billboard;
+
+mod spawn_volume;
+pub use spawn_volume::SpawnVolumeShape;
+
+mod vegetation;
+pub use vegetation::{VegetationMode, VegetationOptions, VegetationRule};
+
+mod assembly;
+pub use assembly::{assemble_model, AssemblyPart, ModelAssembly, TextureVariant};
+
+mod incremental;
+pub use incremental::{
+    export_zone_incremental, IncrementalBlock, IncrementalExport, IncrementalManifest,
+};
+
+mod zones;
+pub use zones::{
+    export_zone_blocks, export_zones, BlockManifest, BlockManifestEntry, ZoneManifest,
+    ZoneManifestEntry,
+};
+
+mod cache;
+use cache::load_cached;
+pub use cache::{AssetCache, AssetCacheStats};
+
+mod assets;
+pub use assets::{find_assets_root_path, normalize_asset_path, AssetLocator, FsAssetLocator};
+
+mod pose;
+pub use pose::bake_pose;
+
+mod morph_animation;
+use morph_animation::{load_morph_target_animation, load_morph_target_mesh};
+
+mod engine_hints;
+pub use engine_hints::EngineHints;
+
+mod embedded_image;
+use embedded_image::apply_texture_budget;
+pub use embedded_image::{EmbeddedImageOptions, ImageEncoding};
+
+mod missing_assets;
+pub use missing_assets::{find_missing_zone_assets, MissingAsset};
+
+mod zone_stats;
+pub use zone_stats::{
+    zone_stats, BlockStats, ObjectStats, TextureStats, ZoneStats, ZoneStatsTable,
+};
+
+mod skeleton_discovery;
+pub use skeleton_discovery::discover_skeleton;
+
+mod mirror;
+pub use mirror::{mirror_animation, mirrored_bone_name};
+
+mod uv;
+pub use uv::UvMode;
+
+mod heightmap;
+
+mod region;
+pub use region::{HeightRange, RadiusFilter, ZoneContent, ZoneContentFilter};
+
+mod zsc_export;
+pub use zsc_export::TexturePathRules;
+
+mod zsc_text;
+use zsc_text::load_model_text;
+
+mod light_container;
+
+mod zone_lookup;
+use zone_lookup::find_zone_object_lists;
+
+mod import;
+
+mod analyze;
+pub use analyze::{analyze_gltf, BufferViewBytes, GltfAnalysis, MeshSize};
+
+pub mod test_assets;
+
+mod error;
+pub use error::ErrorCategory;
+use error::{CategorizedError, CategoryContext};
+
+mod cancel;
+use cancel::check_cancelled;
+pub use cancel::CancellationToken;
+
+mod report;
+pub use report::{collect_timings, collect_warnings, PhaseTiming};
+use report::{info, time_phase, warn};
+
+mod thumbnail;
+pub use thumbnail::render_thumbnail;
+
+mod merge;
+pub use merge::merge_gltf;
+
+mod post_process;
+pub use post_process::{post_process, PostProcessOptions};
+
+mod prune;
+
+mod spill;
+use spill::BinaryData;
+
+mod dedup;
+
+mod node_order;
+use node_order::renumber_nodes_depth_first;
+
+mod provenance;
+use provenance::{add_asset_provenance, add_node_provenance, set_asset_info};
+
+mod bone_palette;
+use bone_palette::{apply_bone_palette, partition_mesh_bones, validate_bone_palette};
+
+mod text;
+pub use text::{build_rose_file, dump_rose_file, TextFormat};
 
 // Exports
 pub use rose_file_lib;
@@ -59,6 +202,7 @@ pub struct GltfData {
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RoseGltfConvOptions {
     /// When converting a zon, only use blocks with this x value.
     pub filter_block_x: Option<i32>,
@@ -66,56 +210,254 @@ pub struct RoseGltfConvOptions {
     /// When converting a zon, only use blocks with this y value.
     pub filter_block_y: Option<i32>,
 
+    /// When converting a zon, only export the terrain tiles, objects, effects,
+    /// sounds and ocean patches within a radius of a point. Blocks are
+    /// included wherever the radius reaches, so it can cross block
+    /// boundaries.
+    pub radius_filter: Option<RadiusFilter>,
+
+    /// When converting a zon, only export the objects, effects and sounds
+    /// whose height is within this range, e.g. to extract one floor of a
+    /// dungeon whose floors are stacked vertically. The terrain and water
+    /// are kept.
+    pub height_range: HeightRange,
+
+    /// When converting a zon, which kinds of content are exported, e.g. only
+    /// the buildings or only the vegetation.
+    pub zone_content: ZoneContentFilter,
+
     /// Choose better triangulation for heightmaps, though it may not match your ROSE client.
     pub use_better_heightmap_triangles: bool,
+
+    /// Ignore any ZMS inputs, e.g. to export only a skeleton from a ZMD.
+    pub skip_meshes: bool,
+
+    /// Ignore any ZMO inputs.
+    pub skip_animations: bool,
+
+    /// Do not look for the ZMD of ZMO and skinned ZMS inputs when no ZMD is
+    /// given, see `discover_skeleton`.
+    pub skip_auto_skeleton: bool,
+
+    /// Instead of exporting animations, bake this frame of the first ZMO into
+    /// the skinned meshes and export them without a skin.
+    pub bake_pose_frame: Option<u32>,
+
+    /// Instead of exporting animations, bake every frame of the first ZMO into
+    /// the skinned meshes as morph targets and export them without a skin,
+    /// with an animation of the morph target weights, for viewers which do
+    /// not support skinning.
+    pub morph_target_animation: bool,
+
+    /// When converting a zon, also export the terrain textures of these zones as
+    /// KHR_materials_variants, e.g. for event or seasonal versions of the zone.
+    pub material_variants: Vec<MaterialVariant>,
+
+    /// Generate MikkTSpace tangents for meshes which have normals and uv0 but no tangents.
+    pub generate_missing_tangents: bool,
+
+    /// Mirror the V coordinate of all UVs, for meshes whose textures appear upside down.
+    pub flip_v: bool,
+
+    /// Write vertex colors as f32 instead of normalized u8, which takes four
+    /// times the space.
+    pub f32_vertex_colors: bool,
+
+    /// Reverse the winding of meshes which appear inside-out, should match
+    /// the option used when converting back to ROSE so round trips are
+    /// consistent.
+    pub winding: WindingCorrection,
+
+    /// Split the vertices of faces which meet at more than this angle in
+    /// degrees and recompute their normals, so hard edges of buildings are
+    /// not smoothed. 0 flat shades every face.
+    pub hard_edge_angle: Option<f32>,
+
+    /// When converting a zon, also export objects which are hidden by default,
+    /// those with a zero scale.
+    pub include_disabled_objects: bool,
+
+    /// When converting a zon, use placeholder meshes and textures for ones
+    /// which could not be found instead of failing, see
+    /// `find_missing_zone_assets`.
+    pub ignore_missing_assets: bool,
+
+    /// When converting a zon, the deco ZSC to use instead of the one in
+    /// list_zone.stb, as a path relative to the assets root or on disk. Zones
+    /// which are not in list_zone.stb otherwise use a ZSC beside the ZON with
+    /// `deco` in its name.
+    pub deco_zsc: Option<String>,
+
+    /// When converting a zon, the cnst ZSC to use instead of the one in
+    /// list_zone.stb, see `deco_zsc`.
+    pub cnst_zsc: Option<String>,
+
+    /// Format and maximum size of the embedded textures, such as the
+    /// materials of objects and the baked terrain textures.
+    pub embedded_images: EmbeddedImageOptions,
+
+    /// Replace textures which are missing or can not be read with a
+    /// checkerboard colored by the texture path, and record the path in the
+    /// extras of the material as `placeholder_texture`.
+    pub placeholder_textures: bool,
+
+    /// When converting a zon, add the path of the sound file on disk to the
+    /// extras of sound emitter nodes.
+    pub resolve_sound_paths: bool,
+
+    /// When converting a zon, give effect marker nodes a quad mesh textured
+    /// with the primary texture of the effect as a visual stand-in.
+    pub effect_billboards: bool,
+
+    /// When converting a zon, move the mesh of billboarded object parts to a
+    /// child node rotated so the card faces +Z, so engines can turn the part
+    /// node to face the camera.
+    pub billboard_pre_rotation: bool,
+
+    /// When converting a zon, plant vegetation on the terrain tiles matched
+    /// by these rules.
+    pub vegetation: Option<VegetationOptions>,
+
+    /// When converting a zon, add a translucent volume of this shape for each
+    /// monster spawn area, scaled to its range, with the monsters it spawns in
+    /// the `rose_spawn` extras of its node.
+    pub spawn_volumes: Option<SpawnVolumeShape>,
+
+    /// When converting a zon, the material of oceans and water planes
+    pub water_material: WaterMaterialOptions,
+
+    /// When converting a zon, export the baked lighting of the terrain from
+    /// the planelightingmap of each block.
+    pub terrain_lightmap: TerrainLightmap,
+
+    /// When converting a zon, the texture coordinates of the terrain for
+    /// detail textures and lightmaps.
+    pub terrain_uvs: TerrainUvOptions,
+
+    /// Write animation rotations as normalized i16 instead of f32. The glTF
+    /// spec only allows quantized rotations, so translations and scales stay f32.
+    pub quantize_animations: bool,
+
+    /// When converting a zon, export alpha blended materials which match these
+    /// rules using KHR_materials_transmission and KHR_materials_ior.
+    pub glass_materials: Option<GlassMaterialRules>,
+
+    /// Changes made to the glTF after it is converted.
+    pub post_process: PostProcessOptions,
+
+    /// Do not record the source files, zone, block and converter version in
+    /// the asset and node extras.
+    pub skip_provenance: bool,
+
+    /// Whether the vertex attributes of each mesh are interleaved in one
+    /// buffer view or each in their own buffer view.
+    pub vertex_buffer_layout: VertexBufferLayout,
+
+    /// Game engine whose importer conventions are used to mark the colliding
+    /// nodes and material settings of zones.
+    pub engine_hints: EngineHints,
+
+    /// Group the nodes of each zone block under a node named after the
+    /// block, with the terrain first, then buildings, decos and everything
+    /// else, and number the nodes in the order of the hierarchy.
+    pub sort_nodes: bool,
+
+    /// Keep the heights of each zone block in the `rose_him` extras of its
+    /// heightmap node, so unedited terrain converts back to an identical HIM
+    /// with `GltfRoseConvOptions::generate_him`.
+    pub heightmap_extras: bool,
+
+    /// Add a single frame `bind_pose` animation for each skin which resets
+    /// the joints to the pose of the ZMD.
+    pub bind_pose_animation: bool,
+
+    /// Export these frame ranges of each ZMO as separate animations instead
+    /// of the whole ZMO. When there are several ZMOs the animations are
+    /// prefixed by the ZMO file name.
+    pub animation_splits: Vec<AnimationSplit>,
+
+    /// Multiplies the keyframe times of animations, e.g. 2 plays them at half
+    /// speed.
+    pub retime: Option<f32>,
+
+    /// Add a mirrored copy of each skeletal animation named `<name>_mirrored`,
+    /// left and right bones are paired by name.
+    pub mirror_animations: bool,
+
+    /// Loop mode written to the extras of animations whose name matches, the
+    /// first matching rule is used.
+    pub animation_loops: Vec<AnimationLoopRule>,
+
+    /// Remove the last frame of looped animations when it repeats the first.
+    pub trim_last_duplicate_frame: bool,
+
+    /// Whether joints keep the bone names of the ZMD or are named by index.
+    pub bone_naming: BoneNaming,
+
+    /// Copyright message of the asset, e.g. to credit the original game.
+    pub copyright: Option<String>,
+
+    /// Generator of the asset, defaults to `rose-gltf <version>`.
+    pub generator: Option<String>,
+
+    /// Directories containing `3ddata` to search for assets, before the one
+    /// found above the input file.
+    pub asset_roots: Vec<PathBuf>,
+
+    /// Overrides how asset paths are resolved, replacing `asset_roots`.
+    #[serde(skip)]
+    pub asset_locator: Option<Arc<dyn AssetLocator>>,
+    /// Cache of parsed files, share it between conversions to avoid parsing
+    /// the same files again.
+    #[serde(skip)]
+    pub asset_cache: Option<Arc<AssetCache>>,
+
+    /// Reduce the memory used to convert large zones: decoded images are
+    /// dropped once embedded instead of kept in `asset_cache`, lightmaps are
+    /// only loaded while their block is converted and the buffer is written to
+    /// a temporary file as it is built, so only the compacted buffer of the
+    /// result is held in memory whole.
+    pub low_memory: bool,
+
+    /// Stops the conversion with a `Cancelled` error once cancelled.
+    #[serde(skip)]
+    pub cancellation: Option<CancellationToken>,
 }
 
-fn pad_align(binary_data: &mut BytesMut) {
-    while binary_data.len() % 4 != 0 {
-        binary_data.put_u8(0);
+impl RoseGltfConvOptions {
+    /// The cache for decoded images, which are not cached in `low_memory`
+    /// mode
+    pub(crate) fn image_cache(&self) -> Option<&AssetCache> {
+        self.asset_cache.as_deref().filter(|_| !self.low_memory)
     }
 }
 
-fn find_assets_root_path(file_path: &Path) -> Option<PathBuf> {
-    let mut path = file_path;
-    while let Some(parent_path) = path.parent() {
-        if parent_path
-            .file_name()
-            .is_some_and(|s| OsStr::new("3ddata").eq_ignore_ascii_case(s))
-        {
-            return parent_path.parent().map(|p| p.to_path_buf());
-        }
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum MaterialVariantSource {
+    /// Path to a ZON, relative paths are relative to the assets root
+    ZonPath(PathBuf),
 
-        path = parent_path;
-    }
+    /// Row of list_zone.stb whose ZON should be used
+    ListZoneRow(usize),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MaterialVariant {
+    /// Name of the variant in the KHR_materials_variants extension
+    pub name: String,
 
-    None
+    /// The zone which provides the textures for this variant
+    pub source: MaterialVariantSource,
 }
 
-pub fn rose_to_gltf(
-    input_files: &[PathBuf],
-    options: &RoseGltfConvOptions,
-) -> anyhow::Result<gltf::Gltf> {
-    // Sort the files so we always load skeletons first so we have skeleton first
-    let mut input_files = input_files.to_vec();
-    input_files.sort_by(|a, b| {
-        let ext_a = a.extension().and_then(|ext| ext.to_str()).unwrap_or("");
-        let ext_b = b.extension().and_then(|ext| ext.to_str()).unwrap_or("");
-        match (ext_a, ext_b) {
-            ("zmd", "zmd") => std::cmp::Ordering::Equal,
-            ("zmd", _) => std::cmp::Ordering::Less,
-            (_, "zmd") => std::cmp::Ordering::Greater,
-            ("zmo", "zmo") => std::cmp::Ordering::Equal,
-            ("zmo", _) => std::cmp::Ordering::Less,
-            (_, "zmo") => std::cmp::Ordering::Greater,
-            ("zms", "zms") => std::cmp::Ordering::Equal,
-            ("zms", _) => std::cmp::Ordering::Less,
-            (_, "zms") => std::cmp::Ordering::Greater,
-            (ext_a, ext_b) => ext_a.cmp(ext_b),
-        }
-    });
+fn pad_align(binary_data: &mut BinaryData) {
+    while !binary_data.len().is_multiple_of(4) {
+        binary_data.put_u8(0);
+    }
+}
 
-    let mut binary_data = BytesMut::with_capacity(8 * 1024 * 1024);
+/// Creates an empty glTF root with the single scene every conversion uses.
+fn new_gltf_root() -> gltf_json::Root {
     let mut root = gltf_json::Root::default();
     root.scenes.push(gltf_json::Scene {
         name: None,
@@ -140,10 +482,148 @@ pub fn rose_to_gltf(
         ),
         nodes: Default::default(),
     });
+    root
+}
+
+/// Adds the buffer for `binary_data` and builds the glTF document.
+fn finish_gltf(mut root: gltf_json::Root, binary_data: BinaryData) -> anyhow::Result<gltf::Gltf> {
+    let _timer = time_phase("finish");
+    let mut binary_data = prune::prune_unused(&mut root, binary_data)?;
+    pad_align(&mut binary_data);
+    let binary_data = binary_data.into_bytes()?;
+
+    root.buffers.push(buffer::Buffer {
+        name: None,
+        byte_length: USize64::from(binary_data.len()),
+        extensions: Default::default(),
+        extras: Default::default(),
+        uri: None,
+    });
+
+    let gltf = gltf::Gltf {
+        document: gltf::Document::from_json(root)?,
+        blob: Some(binary_data.into()),
+    };
+
+    Ok(gltf)
+}
+
+/// Slices `zmo` into the animations of `options.animation_splits`, prefixing
+/// their names by `file_name` when `prefix` is set.
+fn split_animation(
+    zmo: &ZMO,
+    file_name: &str,
+    prefix: bool,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<Vec<(String, ZMO)>> {
+    options
+        .animation_splits
+        .iter()
+        .map(|split| {
+            if split.start_frame > split.end_frame || split.end_frame >= zmo.frames {
+                return Err(CategorizedError::error(
+                    ErrorCategory::Validation,
+                    format!(
+                        "Animation split {} of {} has frames {}-{} outside of its {} frames",
+                        split.name, file_name, split.start_frame, split.end_frame, zmo.frames
+                    ),
+                ));
+            }
+
+            let name = if prefix {
+                format!("{}_{}", file_name, split.name)
+            } else {
+                split.name.clone()
+            };
+            Ok((name, slice_frames(zmo, split.start_frame, split.end_frame)))
+        })
+        .collect()
+}
+
+pub fn rose_to_gltf(
+    input_files: &[PathBuf],
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<gltf::Gltf> {
+    // Sort the files so we always load skeletons first so we have skeleton first
+    let mut input_files = input_files.to_vec();
+    input_files.sort_by_cached_key(|path| {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let rank = match extension.as_str() {
+            "zmd" => 0,
+            "zmo" => 1,
+            "zms" => 2,
+            _ => 3,
+        };
+        (rank, extension)
+    });
+
+    if !options.skip_auto_skeleton
+        && !input_files.iter().any(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("zmd"))
+        })
+    {
+        let skeleton_path = input_files.iter().find_map(|file_path| {
+            let extension = file_path.extension().unwrap_or_default();
+            if (options.skip_animations && extension.eq_ignore_ascii_case("zmo"))
+                || (options.skip_meshes && extension.eq_ignore_ascii_case("zms"))
+            {
+                return None;
+            }
+            let skeleton_path = match &options.asset_locator {
+                Some(asset_locator) => discover_skeleton(file_path, asset_locator.as_ref()),
+                None => discover_skeleton(
+                    file_path,
+                    &FsAssetLocator::for_file(file_path, &options.asset_roots),
+                ),
+            }?;
+            info(format!(
+                "Using skeleton {} for {}",
+                skeleton_path.display(),
+                file_path.display()
+            ));
+            Some(skeleton_path)
+        });
+        input_files.splice(0..0, skeleton_path);
+    }
 
+    let mut binary_data = BinaryData::new(options.low_memory);
+    let mut root = new_gltf_root();
+
+    let animation_loops = CompiledLoopRules::new(&options.animation_loops)?;
+    let retime = retime_factor(options.retime)?;
+    let num_zmo_inputs = input_files
+        .iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("zmo"))
+        })
+        .count();
     let mut skin_index = None;
+    let mut skeleton = None;
+    let mut pose_motion = None;
+    let mut morph_target_nodes = Vec::new();
+    let mut sources = Vec::with_capacity(input_files.len());
+    let mut zone_ids = Vec::new();
 
     for file_path in input_files {
+        check_cancelled(options.cancellation.as_ref())?;
+        let first_node = root.nodes.len();
+        let source_path = match &options.asset_locator {
+            Some(asset_locator) => asset_locator.asset_path(&file_path),
+            None => {
+                FsAssetLocator::for_file(&file_path, &options.asset_roots).asset_path(&file_path)
+            }
+        }
+        .unwrap_or_else(|| file_path.to_string_lossy().to_string());
+        sources.push(source_path.clone());
+        let mut node_source = serde_json::Map::new();
+        node_source.insert("file".to_string(), json!(source_path));
+
         let file_name = file_path
             .file_stem()
             .unwrap_or_default()
@@ -161,28 +641,169 @@ pub fn rose_to_gltf(
 
         match file_extension.as_str() {
             "zmd" => {
-                let zmd = ZMD::from_path(&file_path).expect("Failed to load ZMD");
-
-                skin_index = Some(load_skeleton(&mut root, &mut binary_data, &file_name, &zmd));
-            }
-            "zmo" => {
-                let zmo = ZMO::from_path(&file_path).expect("Failed to load ZMO");
+                let zmd = ZMD::from_path(&file_path)
+                    .with_context(|| format!("Failed to load {}", file_path.display()))?;
 
-                if let Some(skin_index) = skin_index {
-                    load_skeletal_animation(
+                if options.bake_pose_frame.is_none() && !options.morph_target_animation {
+                    skin_index = Some(load_skeleton(
                         &mut root,
                         &mut binary_data,
                         &file_name,
-                        skin_index,
-                        &zmo,
-                    );
+                        &zmd,
+                        options.bone_naming,
+                    ));
+                    if options.bind_pose_animation {
+                        load_bind_pose_animation(
+                            &mut root,
+                            &mut binary_data,
+                            skin_index.unwrap(),
+                            &zmd,
+                            options.quantize_animations,
+                        );
+                    }
+                }
+                skeleton = Some((zmd, source_path.clone()));
+            }
+            "zmo" => {
+                if options.skip_animations {
+                    continue;
+                }
+
+                let zmo = ZMO::from_path(&file_path)
+                    .with_context(|| format!("Failed to load {}", file_path.display()))?;
+
+                if options.bake_pose_frame.is_some() || options.morph_target_animation {
+                    if pose_motion.is_none() {
+                        pose_motion = Some((file_name.clone(), zmo));
+                    }
+                } else {
+                    let skin_index = skin_index.ok_or_else(|| {
+                        CategorizedError::error(
+                            ErrorCategory::MissingDependency,
+                            format!(
+                                "{} is a skeletal animation but no skeleton was given, add the .ZMD it animates to the input files",
+                                source_path
+                            ),
+                        )
+                    })?;
+                    let animations = if options.animation_splits.is_empty() {
+                        vec![(file_name.clone(), zmo)]
+                    } else {
+                        split_animation(&zmo, &file_name, num_zmo_inputs > 1, options)?
+                    };
+                    for (name, mut zmo) in animations {
+                        let animation_loop = animation_loops.find(&name);
+                        if options.trim_last_duplicate_frame
+                            && animation_loop == Some(AnimationLoop::Loop)
+                        {
+                            trim_last_duplicate_frame(&mut zmo);
+                        }
+                        load_skeletal_animation(
+                            &mut root,
+                            &mut binary_data,
+                            &name,
+                            skin_index,
+                            &zmo,
+                            options.quantize_animations,
+                        );
+                        if let (Some(animation), Some(animation_loop)) =
+                            (root.animations.last_mut(), animation_loop)
+                        {
+                            set_animation_loop(animation, animation_loop);
+                        }
+
+                        if let Some((zmd, _)) =
+                            skeleton.as_ref().filter(|_| options.mirror_animations)
+                        {
+                            load_skeletal_animation(
+                                &mut root,
+                                &mut binary_data,
+                                &format!("{}_mirrored", name),
+                                skin_index,
+                                &mirror_animation(&zmo, zmd),
+                                options.quantize_animations,
+                            );
+                            if let (Some(animation), Some(animation_loop)) =
+                                (root.animations.last_mut(), animation_loop)
+                            {
+                                set_animation_loop(animation, animation_loop);
+                            }
+                        }
+                    }
                 }
             }
             "zms" => {
-                let zms = ZMS::from_path(&file_path).expect("Failed to load ZMS");
+                if options.skip_meshes {
+                    continue;
+                }
+
+                let mut zms = ZMS::from_path(&file_path)
+                    .with_context(|| format!("Failed to load {}", file_path.display()))?;
+                if zms.bones_enabled() {
+                    validate_bone_palette(
+                        &zms,
+                        &source_path,
+                        skeleton
+                            .as_ref()
+                            .map(|(zmd, zmd_path)| (zmd_path.as_str(), zmd.bones.len())),
+                    )?;
+                }
+
+                if let Some(frame) = options.bake_pose_frame {
+                    if zms.bones_enabled() {
+                        let (zmd, _) = skeleton
+                            .as_ref()
+                            .context("Baking a pose requires a ZMD input")?;
+                        let (_, zmo) = pose_motion
+                            .as_ref()
+                            .context("Baking a pose requires a ZMO input")?;
+                        zms = bake_pose(&zms, zmd, zmo, frame).with_context(|| {
+                            format!("Failed to bake pose for {}", file_path.display())
+                        })?;
+                    }
+                }
 
-                let mesh_index = load_mesh(&mut root, &mut binary_data, &file_name, &zms);
                 let node_index = root.nodes.len() as u32;
+                let mesh_index = if options.morph_target_animation
+                    && options.bake_pose_frame.is_none()
+                    && zms.bones_enabled()
+                {
+                    let (zmd, _) = skeleton
+                        .as_ref()
+                        .context("Baking morph targets requires a ZMD input")?;
+                    let (_, zmo) = pose_motion
+                        .as_ref()
+                        .context("Baking morph targets requires a ZMO input")?;
+                    morph_target_nodes.push(Index::new(node_index));
+                    load_morph_target_mesh(
+                        &mut root,
+                        &mut binary_data,
+                        &file_name,
+                        &zms,
+                        zmd,
+                        zmo,
+                        options.generate_missing_tangents,
+                        options.flip_v,
+                        options.f32_vertex_colors,
+                        options.winding,
+                        options.hard_edge_angle,
+                    )
+                    .with_context(|| {
+                        format!("Failed to bake morph targets for {}", file_path.display())
+                    })?
+                } else {
+                    load_mesh(
+                        &mut root,
+                        &mut binary_data,
+                        &file_name,
+                        &zms,
+                        options.generate_missing_tangents,
+                        options.flip_v,
+                        options.f32_vertex_colors,
+                        options.winding,
+                        options.hard_edge_angle,
+                    )
+                };
                 root.nodes.push(scene::Node {
                     name: Some(format!("{}_node", file_name)),
                     camera: None,
@@ -203,37 +824,63 @@ pub fn rose_to_gltf(
                 });
                 root.scenes[0].nodes.push(Index::new(node_index));
             }
+            "txt" => {
+                if options.skip_meshes {
+                    continue;
+                }
+
+                let model = ZSCTXT::from_path(&file_path)
+                    .with_context(|| format!("Failed to load {}", file_path.display()))?;
+                let assets: Arc<dyn AssetLocator> = match &options.asset_locator {
+                    Some(asset_locator) => asset_locator.clone(),
+                    None => Arc::new(FsAssetLocator::for_file(&file_path, &options.asset_roots)),
+                };
+                load_model_text(
+                    &mut root,
+                    &mut binary_data,
+                    &file_name,
+                    model,
+                    assets.as_ref(),
+                    options,
+                )?;
+            }
             "zon" => {
                 let map_path = file_path
                     .parent()
-                    .expect("Could not find map path")
+                    .category_context(ErrorCategory::MissingDependency, || {
+                        format!("Could not find map path of {}", file_path.display())
+                    })?
                     .to_path_buf();
-                let assets_path =
-                    find_assets_root_path(&file_path).expect("Could not find root assets path");
-                let relative_zon_path = file_path.strip_prefix(&assets_path).unwrap();
-
-                let list_zone = STB::from_path(&assets_path.join("3ddata/stb/list_zone.stb"))
-                    .expect("Failed to load list_zone.stb");
-                let zone_id = (|| {
-                    for row in 1..list_zone.rows() {
-                        if let Some(row_zon) = list_zone.value(row, 2) {
-                            if Path::new(&row_zon.to_ascii_lowercase()) == relative_zon_path {
-                                return Some(row);
-                            }
-                        }
-                    }
-                    None
-                })()
-                .expect("Could not find zone id");
-
-                let deco_models = ZSC::from_path(
-                    &assets_path.join(Path::new(list_zone.value(zone_id, 12).unwrap())),
-                )
-                .expect("Failed to read deco zsc");
-                let cnst_models = ZSC::from_path(
-                    &assets_path.join(Path::new(list_zone.value(zone_id, 13).unwrap())),
-                )
-                .expect("Failed to read cnst zsc");
+                let assets: Arc<dyn AssetLocator> = match &options.asset_locator {
+                    Some(asset_locator) => asset_locator.clone(),
+                    None => Arc::new(FsAssetLocator::for_file(&file_path, &options.asset_roots)),
+                };
+                let relative_zon_path = assets
+                    .asset_path(&file_path)
+                    .category_context(ErrorCategory::MissingDependency, || {
+                        format!("Could not find root assets path of {}", file_path.display())
+                    })?;
+
+                let asset_cache = options.asset_cache.as_deref();
+                let object_lists = find_zone_object_lists(
+                    &file_path,
+                    &relative_zon_path,
+                    assets.as_ref(),
+                    options,
+                )?;
+
+                let load_zsc = |path: &str| {
+                    load_cached::<ZSC>(asset_cache, &assets.resolve(path))
+                        .category_context(ErrorCategory::MissingDependency, || {
+                            format!("Failed to read {}", path)
+                        })
+                };
+                if let Some(zone_id) = object_lists.zone_id {
+                    zone_ids.push(zone_id);
+                    node_source.insert("zone_id".to_string(), json!(zone_id));
+                }
+                let deco_models = load_zsc(&object_lists.deco)?;
+                let cnst_models = load_zsc(&object_lists.cnst)?;
 
                 // Create a sampler for deco + cnst to use.
                 let sampler_index = Index::<texture::Sampler>::new(root.samplers.len() as u32);
@@ -247,47 +894,138 @@ pub fn rose_to_gltf(
                     extras: Default::default(),
                 });
 
-                let zon = ZON::from_path(&file_path).expect("Failed to load ZON");
+                let zon = ZON::from_path_mmap(&file_path)
+                    .with_context(|| format!("Failed to load {}", file_path.display()))?;
+                let mut variant_zons = Vec::with_capacity(options.material_variants.len());
+                for variant in options.material_variants.iter() {
+                    let variant_zon_path = match &variant.source {
+                        MaterialVariantSource::ZonPath(path) if path.is_absolute() => path.clone(),
+                        MaterialVariantSource::ZonPath(path) => {
+                            assets.resolve(&path.to_string_lossy())
+                        }
+                        MaterialVariantSource::ListZoneRow(row) => assets.resolve(
+                            object_lists
+                                .list_zone
+                                .as_ref()
+                                .context("Failed to load list_zone.stb")?
+                                .zon_path(*row)
+                                .with_context(|| format!("list_zone.stb row {} has no zon", row))?,
+                        ),
+                    };
+                    let variant_zon = ZON::from_path(&variant_zon_path).with_context(|| {
+                        format!(
+                            "Failed to load zon {} for material variant {}",
+                            variant_zon_path.display(),
+                            variant.name
+                        )
+                    })?;
+                    variant_zons.push((variant.name.clone(), variant_zon));
+                }
+
                 let mut deco = ObjectList::new(deco_models, sampler_index);
                 let mut cnst = ObjectList::new(cnst_models, sampler_index);
+                deco.generate_missing_tangents = options.generate_missing_tangents;
+                cnst.generate_missing_tangents = options.generate_missing_tangents;
+                deco.flip_v = options.flip_v;
+                cnst.flip_v = options.flip_v;
+                deco.glass_materials = options.glass_materials.clone();
+                cnst.glass_materials = options.glass_materials.clone();
+                deco.asset_cache = options.asset_cache.clone();
+                cnst.asset_cache = options.asset_cache.clone();
+                deco.low_memory = options.low_memory;
+                cnst.low_memory = options.low_memory;
+                deco.ignore_missing_assets = options.ignore_missing_assets;
+                cnst.ignore_missing_assets = options.ignore_missing_assets;
+                deco.placeholder_textures = options.placeholder_textures;
+                cnst.placeholder_textures = options.placeholder_textures;
+                deco.embedded_images = options.embedded_images;
+                cnst.embedded_images = options.embedded_images;
+                deco.engine_hints = options.engine_hints;
+                cnst.engine_hints = options.engine_hints;
+                deco.f32_vertex_colors = options.f32_vertex_colors;
+                cnst.f32_vertex_colors = options.f32_vertex_colors;
+                deco.winding = options.winding;
+                cnst.winding = options.winding;
+                deco.hard_edge_angle = options.hard_edge_angle;
+                cnst.hard_edge_angle = options.hard_edge_angle;
 
                 if let Err(e) = load_zone(
                     &mut root,
                     &mut binary_data,
                     &zon,
-                    assets_path,
+                    &variant_zons,
+                    assets.as_ref(),
                     map_path,
                     &mut deco,
                     &mut cnst,
-                    options.use_better_heightmap_triangles,
-                    options.filter_block_x,
-                    options.filter_block_y,
+                    options,
                 ) {
-                    eprintln!("{:?}", e);
+                    if ErrorCategory::of(&e) == ErrorCategory::Cancelled {
+                        return Err(e);
+                    }
+                    warn(format!(
+                        "Failed to load zone {}: {:?}",
+                        file_path.display(),
+                        e
+                    ));
                 }
             }
             _ => {
-                anyhow::bail!("Unsupported file extension {}", &file_path.display());
+                return Err(CategorizedError::error(
+                    ErrorCategory::Unsupported,
+                    format!("Unsupported file extension {}", file_path.display()),
+                ));
             }
         }
-    }
 
-    pad_align(&mut binary_data);
+        if !options.skip_provenance {
+            add_node_provenance(&mut root.nodes[first_node..], &node_source);
+        }
+    }
 
-    root.buffers.push(buffer::Buffer {
-        name: None,
-        byte_length: USize64::from(binary_data.len()),
-        extensions: Default::default(),
-        extras: Default::default(),
-        uri: None,
-    });
+    if let Some((name, zmo)) = pose_motion
+        .as_ref()
+        .filter(|_| options.morph_target_animation)
+    {
+        load_morph_target_animation(&mut root, &mut binary_data, name, zmo, &morph_target_nodes);
+    }
 
-    let gltf = gltf::Gltf {
-        document: gltf::Document::from_json(root)?,
-        blob: Some(binary_data.to_vec()),
-    };
+    set_asset_info(
+        &mut root,
+        options.copyright.as_deref(),
+        options.generator.as_deref(),
+    );
+    if !options.skip_provenance {
+        add_asset_provenance(&mut root, &sources, &zone_ids);
+    }
+    if retime != 1.0 {
+        retime_animations(&mut root, &mut binary_data, retime)?;
+    }
+    if options.vertex_buffer_layout == VertexBufferLayout::Planar {
+        deinterleave_vertex_buffers(&mut root, &mut binary_data)?;
+    }
+    if options.sort_nodes {
+        renumber_nodes_depth_first(&mut root);
+    }
+    post_process(&mut root, &options.post_process);
+    if let Some(budget) = options.embedded_images.budget {
+        apply_texture_budget(
+            &mut root,
+            &mut binary_data,
+            &options.embedded_images,
+            budget,
+        )?;
+    }
+    finish_gltf(root, binary_data)
+}
 
-    Ok(gltf)
+/// Same as `rose_to_gltf`, but with the buffers loaded and the images
+/// decoded like the input of `gltf_to_rose`.
+pub fn rose_to_gltf_data(
+    input_files: &[PathBuf],
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<GltfData> {
+    GltfData::from_gltf(rose_to_gltf(input_files, options)?)
 }
 
 #[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -363,10 +1101,169 @@ pub fn save_gltf(gltf: &gltf::Gltf, output_path: &Path, format: &GltfFormat) ->
     Ok(())
 }
 
+/// FPS of the generated ZMO when neither the options nor the animation give one
+const DEFAULT_ZMO_FPS: u32 = 30;
+
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct GltfRoseConvOptions {
-    /// FPS to use for ZMO
+    /// FPS to use for ZMO, when 0 the fps recorded in the animation extras
+    /// by `rose_to_gltf` is used, or 30 if there is none.
     pub zmo_fps: u32,
+
+    /// Per-animation overrides, the first override whose pattern matches the
+    /// animation name is used.
+    pub animation_overrides: Vec<AnimationOverride>,
+
+    /// Multiplies the duration of animations, e.g. 2 plays them at half speed.
+    /// The number of ZMO frames is recomputed for the fps.
+    pub retime: Option<f32>,
+
+    /// Append skeletal animations into a single ZMO instead of a ZMO each
+    pub animation_concat: Option<AnimationConcat>,
+
+    /// Only convert meshes whose name passes this filter
+    pub mesh_filter: NameFilter,
+
+    /// Only convert skins whose name passes this filter
+    pub skin_filter: NameFilter,
+
+    /// Only convert animations whose name passes this filter
+    pub animation_filter: NameFilter,
+
+    /// Do not generate any ZMS
+    pub skip_meshes: bool,
+
+    /// Do not generate any ZMD
+    pub skip_skins: bool,
+
+    /// Do not generate any ZMO
+    pub skip_animations: bool,
+
+    /// Mask of `VertexFormat` flags to keep in generated ZMS, all attributes are
+    /// kept when None. Positions are always kept.
+    pub keep_attributes: Option<i32>,
+
+    /// How to handle UVs outside of the [0, 1] range, per UV channel
+    pub uv_modes: [UvMode; 4],
+
+    /// Mirror the V coordinate of all UVs, should match the option used when
+    /// converting to glTF so round trips are consistent.
+    pub flip_v: bool,
+
+    /// Reverse the winding of meshes which appear inside-out, should match
+    /// the option used when converting to glTF so round trips are consistent.
+    pub winding: WindingCorrection,
+
+    /// Generate a non-overlapping UV2 for lightmapping on meshes without one
+    pub generate_lightmap_uvs: bool,
+
+    /// Generate a ZSC for each scene with a model per root node
+    pub generate_zsc: bool,
+
+    /// Write the generated models as a text ZSC (ZSCTXT) per root node
+    /// instead of a ZSC per scene, so they can be edited by hand
+    pub zsc_text: bool,
+
+    /// Generate a HIM for each terrain heightmap node, named after its block
+    pub generate_him: bool,
+
+    /// Directory used for the mesh paths in the generated ZSC, e.g. `3ddata\custom`
+    pub zsc_mesh_directory: String,
+
+    /// Rules for converting glTF image URIs into ZSC material texture paths
+    pub texture_path_rules: TexturePathRules,
+
+    /// Output the textures referenced by the generated ZSC materials
+    pub copy_textures: bool,
+
+    /// Vertex layout of generated ZMS, for clients which expect packed UVs or colors
+    pub vertex_layout: VertexLayout,
+
+    /// Version of generated ZMD, for older clients which only load ZMD0002
+    pub zmd_version: SkeletonVersion,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct NameFilter {
+    /// If not empty, only names matching at least one of these regexes pass
+    pub include: Vec<String>,
+
+    /// Names matching any of these regexes never pass
+    pub exclude: Vec<String>,
+}
+
+struct CompiledNameFilter {
+    include: Vec<regex::Regex>,
+    exclude: Vec<regex::Regex>,
+}
+
+impl CompiledNameFilter {
+    fn new(filter: &NameFilter) -> anyhow::Result<Self> {
+        let compile = |patterns: &[String]| -> anyhow::Result<Vec<regex::Regex>> {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    regex::Regex::new(pattern)
+                        .with_context(|| format!("Invalid name filter pattern: {}", pattern))
+                })
+                .collect()
+        };
+
+        Ok(Self {
+            include: compile(&filter.include)?,
+            exclude: compile(&filter.exclude)?,
+        })
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|regex| regex.is_match(name)))
+            && !self.exclude.iter().any(|regex| regex.is_match(name))
+    }
+}
+
+/// A named range of frames of a ZMO to export as its own animation
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct AnimationSplit {
+    pub name: String,
+
+    /// Index of the first frame
+    pub start_frame: u32,
+
+    /// Index of the last frame, inclusive
+    pub end_frame: u32,
+}
+
+/// Animations to append end to end into a single ZMO
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct AnimationConcat {
+    /// Name of the generated ZMO
+    pub name: String,
+
+    /// Names of the glTF animations in the order they are appended, all
+    /// skeletal animations in the order of the glTF when empty
+    pub animations: Vec<String>,
+
+    /// Frames inserted between each animation
+    pub gap_frames: u32,
+
+    /// Interpolate from the last frame of an animation to the first frame of
+    /// the next during the gap, instead of holding the last frame
+    pub blend: bool,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct AnimationOverride {
+    /// Regex matched against the glTF animation name
+    pub pattern: String,
+
+    /// FPS to use for the generated ZMO instead of `zmo_fps`
+    pub fps: Option<u32>,
+
+    /// Time in seconds of the first frame to export
+    pub start_time: Option<f32>,
+
+    /// Time in seconds of the last frame to export
+    pub end_time: Option<f32>,
 }
 
 #[derive(Default)]
@@ -374,28 +1271,37 @@ pub struct GltfRoseResult {
     pub zms: Vec<(String, ZMS)>,
     pub zmd: Vec<(String, ZMD)>,
     pub zmo: Vec<(String, ZMO)>,
+    pub zsc: Vec<(String, ZSC)>,
+    pub zsc_text: Vec<(String, ZSCTXT)>,
+    pub him: Vec<(String, HIM)>,
+
+    /// Textures referenced by the generated ZSC, keyed by their texture path
+    pub textures: Vec<(String, image::RgbaImage)>,
+}
+
+fn sanitize_name(name: &str) -> String {
+    let invalid_chars: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|', '\0', '.'];
+    name.chars()
+        .map(|c| if invalid_chars.contains(&c) { '_' } else { c })
+        .collect()
 }
 
 impl GltfRoseResult {
-    pub fn save_to_dir(&mut self, output: &Path) -> anyhow::Result<()> {
+    /// Writes each file to `output` and returns the paths written.
+    pub fn save_to_dir(&mut self, output: &Path) -> anyhow::Result<Vec<PathBuf>> {
         fs::create_dir_all(output).context(format!(
             "Failed to create intermediate dirs: {}",
             output.display()
         ))?;
 
-        let sanitize_name = |name: &str| -> String {
-            let invalid_chars: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|', '\0', '.'];
-            name.chars()
-                .map(|c| if invalid_chars.contains(&c) { '_' } else { c })
-                .collect()
-        };
-
+        let mut written = Vec::new();
         for (zms_name, zms) in self.zms.iter_mut() {
             let p = output.join(sanitize_name(zms_name)).with_extension("zms");
             let f = fs::File::create(&p)
                 .context(format!("Failed to create zms file: {}", p.display()))?;
             zms.write_to_file(&f)
                 .context(format!("Failed to write zms file: {}", p.display()))?;
+            written.push(p);
         }
 
         for (zmo_name, zmo) in self.zmo.iter_mut() {
@@ -404,6 +1310,7 @@ impl GltfRoseResult {
                 .context(format!("Failed to create zmo file: {}", p.display()))?;
             zmo.write_to_file(&f)
                 .context(format!("Failed to write zmo file: {}", p.display()))?;
+            written.push(p);
         }
 
         for (zmd_name, zmd) in self.zmd.iter_mut() {
@@ -412,9 +1319,65 @@ impl GltfRoseResult {
                 .context(format!("Failed to create zmd file: {}", p.display()))?;
             zmd.write_to_file(&f)
                 .context(format!("Failed to write zmd file: {}", p.display()))?;
+            written.push(p);
         }
 
-        Ok(())
+        for (zsc_name, zsc) in self.zsc.iter_mut() {
+            let p = output.join(sanitize_name(zsc_name)).with_extension("zsc");
+            let f = fs::File::create(&p)
+                .context(format!("Failed to create zsc file: {}", p.display()))?;
+            zsc.write_to_file(&f)
+                .context(format!("Failed to write zsc file: {}", p.display()))?;
+            written.push(p);
+        }
+
+        for (model_name, model) in self.zsc_text.iter_mut() {
+            let p = output.join(sanitize_name(model_name)).with_extension("txt");
+            let f = fs::File::create(&p)
+                .context(format!("Failed to create zsc text file: {}", p.display()))?;
+            model
+                .write_to_file(&f)
+                .context(format!("Failed to write zsc text file: {}", p.display()))?;
+            written.push(p);
+        }
+
+        for (him_name, him) in self.him.iter_mut() {
+            let p = output.join(sanitize_name(him_name)).with_extension("him");
+            let f = fs::File::create(&p)
+                .context(format!("Failed to create him file: {}", p.display()))?;
+            him.write_to_file(&f)
+                .context(format!("Failed to write him file: {}", p.display()))?;
+            written.push(p);
+        }
+
+        for (texture_path, image) in self.textures.iter() {
+            // The image crate can not encode DDS, so those are written as PNG
+            // next to where the DDS is expected and must be converted afterwards.
+            let mut p = output.join(texture_path.replace('\\', "/"));
+            if p.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("dds"))
+            {
+                p.set_extension("png");
+                warn(format!(
+                    "Texture {} was written as {}, convert it to DDS before use",
+                    texture_path,
+                    p.display()
+                ));
+            }
+
+            if let Some(parent) = p.parent() {
+                fs::create_dir_all(parent).context(format!(
+                    "Failed to create intermediate dirs: {}",
+                    parent.display()
+                ))?;
+            }
+            image
+                .save(&p)
+                .context(format!("Failed to write texture file: {}", p.display()))?;
+            written.push(p);
+        }
+
+        Ok(written)
     }
 }
 
@@ -424,26 +1387,110 @@ enum ZmdBoneIndex {
     Dummy(usize),
 }
 
+/// Replaces the skeletal ZMOs chosen by `animation_concat`, whose indices in
+/// `zmos` are `skeletal_zmos`, by a single ZMO appending them.
+fn concatenate_animations(
+    zmos: &mut Vec<(String, ZMO)>,
+    skeletal_zmos: &[usize],
+    animation_concat: &AnimationConcat,
+) -> anyhow::Result<()> {
+    let mut selected = Vec::new();
+    if animation_concat.animations.is_empty() {
+        selected.extend_from_slice(skeletal_zmos);
+    } else {
+        for name in animation_concat.animations.iter() {
+            let index = skeletal_zmos
+                .iter()
+                .copied()
+                .find(|&index| &zmos[index].0 == name)
+                .ok_or_else(|| {
+                    CategorizedError::error(
+                        ErrorCategory::Validation,
+                        format!("Animation {} to concatenate was not found", name),
+                    )
+                })?;
+            selected.push(index);
+        }
+    }
+
+    let animations: Vec<(&str, &ZMO)> = selected
+        .iter()
+        .map(|&index| (zmos[index].0.as_str(), &zmos[index].1))
+        .collect();
+    let zmo = concatenate_zmos(
+        &animations,
+        animation_concat.gap_frames,
+        animation_concat.blend,
+    )
+    .with_context(|| {
+        format!(
+            "Failed to concatenate animations into {}",
+            animation_concat.name
+        )
+    })?;
+
+    let mut index = 0;
+    zmos.retain(|_| {
+        index += 1;
+        !selected.contains(&(index - 1))
+    });
+    zmos.push((animation_concat.name.clone(), zmo));
+    Ok(())
+}
+
 pub fn gltf_to_rose(
     gltf_data: &GltfData,
     options: &GltfRoseConvOptions,
 ) -> anyhow::Result<GltfRoseResult> {
     let mut result = GltfRoseResult::default();
 
-    let animation_fps = options.zmo_fps;
+    let animation_overrides = options
+        .animation_overrides
+        .iter()
+        .map(|animation_override| {
+            regex::Regex::new(&animation_override.pattern)
+                .with_context(|| {
+                    format!(
+                        "Invalid animation override pattern: {}",
+                        animation_override.pattern
+                    )
+                })
+                .map(|regex| (regex, animation_override))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let retime = retime_factor(options.retime)?;
+    let mesh_filter = CompiledNameFilter::new(&options.mesh_filter)?;
+    let skin_filter = CompiledNameFilter::new(&options.skin_filter)?;
+    let animation_filter = CompiledNameFilter::new(&options.animation_filter)?;
 
     let mut dummy_nodes = Vec::new();
+    let mut skeletal_zmos = Vec::new();
     let mut processed_meshes = HashSet::new();
+    let mut node_zmo_paths: HashMap<usize, String> = HashMap::new();
+    let mut mesh_zms_paths: HashMap<usize, Vec<String>> = HashMap::new();
+    let mut zms_mesh_path = |mesh_index: usize, zms_name: &str| {
+        mesh_zms_paths
+            .entry(mesh_index)
+            .or_default()
+            .push(zsc_export::join_rose_path(
+                &options.zsc_mesh_directory,
+                &format!("{}.zms", sanitize_name(zms_name)),
+            ));
+    };
 
     for node in gltf_data.document.nodes() {
         // Skip dummy nodes but save them to be used in ZMD later
         if let Some(name) = node.name() {
-            if name.starts_with("dummy_") {
+            if dummy_name_index(name).is_some() {
                 dummy_nodes.push(node);
                 continue;
             }
         }
 
+        if options.skip_meshes {
+            continue;
+        }
+
         // Skip anything that doesn't have a mesh
         let Some(mesh) = node.mesh() else {
             continue;
@@ -454,6 +1501,14 @@ pub fn gltf_to_rose(
             continue;
         }
 
+        let mesh_name = mesh
+            .name()
+            .map(|s| s.to_string())
+            .unwrap_or(format!("mesh_{}", mesh.index()));
+        if !mesh_filter.matches(&mesh_name) {
+            continue;
+        }
+
         let primitive = mesh.primitives().next().context(format!(
             "Expected mesh to have 1 primitive. Index: {}, name: {}",
             mesh.index(),
@@ -461,6 +1516,7 @@ pub fn gltf_to_rose(
         ))?;
 
         let mut zms = ZMS::new();
+        zms.layout = options.vertex_layout;
         let reader = primitive.reader(|buffer| Some(&gltf_data.buffers[buffer.index()]));
 
         if let Some(iter) = reader.read_positions() {
@@ -476,20 +1532,6 @@ pub fn gltf_to_rose(
                     ..Default::default()
                 });
             }
-
-            let mut min_pos = zms.vertices[0].position;
-            let mut max_pos = zms.vertices[0].position;
-            for vertex in zms.vertices.iter() {
-                min_pos.x = min_pos.x.min(vertex.position.x);
-                min_pos.y = min_pos.y.min(vertex.position.y);
-                min_pos.z = min_pos.z.min(vertex.position.z);
-
-                max_pos.x = max_pos.x.max(vertex.position.x);
-                max_pos.y = max_pos.y.max(vertex.position.y);
-                max_pos.z = max_pos.z.max(vertex.position.z);
-            }
-            zms.bounding_box.min = min_pos;
-            zms.bounding_box.max = max_pos;
         }
 
         if let Some(iter) = reader.read_normals() {
@@ -667,115 +1709,84 @@ pub fn gltf_to_rose(
             }
         }
 
-        if let Some(read_joints) = reader.read_joints(0) {
-            zms.format |= VertexFormat::BoneIndex as i32;
-
-            match read_joints {
-                ReadJoints::U8(iter) => {
-                    for (i, joints) in iter.enumerate() {
-                        zms.vertices[i].bone_indices.x = joints[0] as i16;
-                        zms.vertices[i].bone_indices.y = joints[1] as i16;
-                        zms.vertices[i].bone_indices.z = joints[2] as i16;
-                        zms.vertices[i].bone_indices.w = joints[3] as i16;
+        // Gather the influences from every JOINTS_n / WEIGHTS_n set, a ZMS
+        // vertex can only hold 4 of them so we keep the largest weights.
+        let mut influences: Vec<Vec<(i16, f32)>> = vec![Vec::new(); zms.vertices.len()];
+        let mut num_influence_sets = 0;
+        while let (Some(read_joints), Some(read_weights)) = (
+            reader.read_joints(num_influence_sets),
+            reader.read_weights(num_influence_sets),
+        ) {
+            for (vertex_influences, (joints, weights)) in influences
+                .iter_mut()
+                .zip(read_joints.into_u16().zip(read_weights.into_f32()))
+            {
+                for (joint, weight) in joints.into_iter().zip(weights) {
+                    if weight > 0.0 {
+                        vertex_influences.push((joint as i16, weight));
                     }
                 }
-                ReadJoints::U16(iter) => {
-                    for (i, joints) in iter.enumerate() {
-                        zms.vertices[i].bone_indices.x = joints[0] as i16;
-                        zms.vertices[i].bone_indices.y = joints[1] as i16;
-                        zms.vertices[i].bone_indices.z = joints[2] as i16;
-                        zms.vertices[i].bone_indices.w = joints[3] as i16;
-                    }
-                }
-            }
-
-            // Skeleton can contain more than 48 bones but mesh should not
-            // exceed this number so we narrow down the bone list to only what
-            // the mesh actually uses.
-            let mut bones_used = HashSet::new();
-            for vertex in zms.vertices.iter_mut() {
-                bones_used.insert(vertex.bone_indices.x);
-                bones_used.insert(vertex.bone_indices.y);
-                bones_used.insert(vertex.bone_indices.z);
-                bones_used.insert(vertex.bone_indices.w);
             }
 
-            if bones_used.len() > 48 {
-                anyhow::bail!("A mesh can only bind to a maximum of 48 bones");
-            }
+            num_influence_sets += 1;
+        }
 
-            if node.skin().is_none() {
-                anyhow::bail!("Mesh has bone weights but is not assocated with a skin");
-            };
+        if num_influence_sets > 0 {
+            zms.format |= VertexFormat::BoneWeight as i32 | VertexFormat::BoneIndex as i32;
 
-            // Map from the bone index in the skeleton to the index of the bone
-            // indices list in the mesh
-            let mut bone_map = HashMap::new();
-            for bone_idx in bones_used {
-                bone_map.insert(bone_idx, zms.bones.len());
-                zms.bones.push(bone_idx);
-            }
+            let mut num_pruned_vertices = 0;
+            let mut max_pruned_weight = 0.0f32;
+            let mut total_pruned_weight = 0.0f32;
 
-            for vertex in zms.vertices.iter_mut() {
-                if let Some(new_idx) = bone_map.get(&vertex.bone_indices.x) {
-                    vertex.bone_indices.x = *new_idx as i16;
-                } else {
-                    vertex.bone_indices.x = 0;
-                    vertex.bone_weights.x = 0.0;
-                }
+            for (vertex, mut vertex_influences) in zms.vertices.iter_mut().zip(influences) {
+                if vertex_influences.len() > 4 {
+                    vertex_influences.sort_by(|a, b| b.1.total_cmp(&a.1));
 
-                if let Some(new_idx) = bone_map.get(&vertex.bone_indices.y) {
-                    vertex.bone_indices.y = *new_idx as i16;
-                } else {
-                    vertex.bone_indices.y = 0;
-                    vertex.bone_weights.y = 0.0;
-                }
+                    let pruned_weight: f32 = vertex_influences[4..].iter().map(|(_, w)| w).sum();
+                    num_pruned_vertices += 1;
+                    max_pruned_weight = max_pruned_weight.max(pruned_weight);
+                    total_pruned_weight += pruned_weight;
 
-                if let Some(new_idx) = bone_map.get(&vertex.bone_indices.z) {
-                    vertex.bone_indices.z = *new_idx as i16;
-                } else {
-                    vertex.bone_indices.z = 0;
-                    vertex.bone_weights.z = 0.0;
+                    vertex_influences.truncate(4);
+                    let remaining_weight: f32 = vertex_influences.iter().map(|(_, w)| w).sum();
+                    if remaining_weight > 0.0 {
+                        for (_, weight) in vertex_influences.iter_mut() {
+                            *weight /= remaining_weight;
+                        }
+                    }
                 }
 
-                if let Some(new_idx) = bone_map.get(&vertex.bone_indices.w) {
-                    vertex.bone_indices.w = *new_idx as i16;
-                } else {
-                    vertex.bone_indices.w = 0;
-                    vertex.bone_weights.w = 0.0;
-                }
+                vertex_influences.resize(4, (0, 0.0));
+                vertex.bone_indices = Vector4 {
+                    x: vertex_influences[0].0,
+                    y: vertex_influences[1].0,
+                    z: vertex_influences[2].0,
+                    w: vertex_influences[3].0,
+                };
+                vertex.bone_weights = Vector4 {
+                    x: vertex_influences[0].1,
+                    y: vertex_influences[1].1,
+                    z: vertex_influences[2].1,
+                    w: vertex_influences[3].1,
+                };
             }
-        }
-
-        if let Some(read_weights) = reader.read_weights(0) {
-            zms.format |= VertexFormat::BoneWeight as i32;
 
-            match read_weights {
-                ReadWeights::U8(iter) => {
-                    for (i, weights) in iter.enumerate() {
-                        zms.vertices[i].bone_weights.x = weights[0] as f32 / 255.0;
-                        zms.vertices[i].bone_weights.y = weights[1] as f32 / 255.0;
-                        zms.vertices[i].bone_weights.z = weights[2] as f32 / 255.0;
-                        zms.vertices[i].bone_weights.w = weights[3] as f32 / 255.0;
-                    }
-                }
-                ReadWeights::U16(iter) => {
-                    for (i, weights) in iter.enumerate() {
-                        zms.vertices[i].bone_weights.x = weights[0] as f32 / 65535.0;
-                        zms.vertices[i].bone_weights.y = weights[1] as f32 / 65535.0;
-                        zms.vertices[i].bone_weights.z = weights[2] as f32 / 65535.0;
-                        zms.vertices[i].bone_weights.w = weights[3] as f32 / 65535.0;
-                    }
-                }
-                ReadWeights::F32(iter) => {
-                    for (i, weights) in iter.enumerate() {
-                        zms.vertices[i].bone_weights.x = weights[0];
-                        zms.vertices[i].bone_weights.y = weights[1];
-                        zms.vertices[i].bone_weights.z = weights[2];
-                        zms.vertices[i].bone_weights.w = weights[3];
-                    }
-                }
+            if num_pruned_vertices > 0 {
+                warn(format!(
+                    "Mesh {}: pruned bone influences beyond 4 from {} vertices (max pruned weight {:.3}, average {:.3})",
+                    mesh_name,
+                    num_pruned_vertices,
+                    max_pruned_weight,
+                    total_pruned_weight / num_pruned_vertices as f32
+                ));
             }
+
+            if node.skin().is_none() {
+                return Err(CategorizedError::error(
+                    ErrorCategory::Validation,
+                    "Mesh has bone weights but is not assocated with a skin".to_string(),
+                ));
+            };
         }
 
         if let Some(read_indices) = reader.read_indices() {
@@ -799,24 +1810,178 @@ pub fn gltf_to_rose(
                 }
             }
 
-            for triangle in indices.chunks_exact(3) {
+            let mut triangles: Vec<_> = indices
+                .chunks_exact(3)
+                .map(|triangle| [0, 1, 2].map(|corner| triangle[corner] as u16 as usize))
+                .collect();
+            let positions: Vec<_> = zms
+                .vertices
+                .iter()
+                .map(|vertex| Vec3::new(vertex.position.x, vertex.position.y, vertex.position.z))
+                .collect();
+            if options.winding.should_flip(&triangles, &positions) {
+                if options.winding == WindingCorrection::Auto {
+                    warn(format!(
+                        "Mesh {}: faces inwards, reversed its winding",
+                        mesh_name
+                    ));
+                }
+                flip_triangles(&mut triangles);
+            }
+
+            let (degenerate_triangles, kept_vertices) =
+                remove_degenerate_triangles(&mut triangles, &positions);
+            let unused_vertices = zms.vertices.len() - kept_vertices.len();
+            report_removed(&mesh_name, degenerate_triangles, unused_vertices);
+            if unused_vertices > 0 {
+                compact_vertices(&mut zms.vertices, &kept_vertices);
+            }
+
+            for triangle in triangles {
                 zms.indices.push(Vector3 {
-                    x: triangle[0],
-                    y: triangle[1],
-                    z: triangle[2],
+                    x: triangle[0] as i16,
+                    y: triangle[1] as i16,
+                    z: triangle[2] as i16,
                 });
             }
         }
 
-        result.zms.push((
-            mesh.name()
-                .map(|s| s.to_string())
-                .unwrap_or(format!("mesh_{}", mesh.index())),
-            zms,
-        ));
+        if let Some(first_vertex) = zms.vertices.first() {
+            let mut min_pos = first_vertex.position;
+            let mut max_pos = first_vertex.position;
+            for vertex in zms.vertices.iter() {
+                min_pos.x = min_pos.x.min(vertex.position.x);
+                min_pos.y = min_pos.y.min(vertex.position.y);
+                min_pos.z = min_pos.z.min(vertex.position.z);
+
+                max_pos.x = max_pos.x.max(vertex.position.x);
+                max_pos.y = max_pos.y.max(vertex.position.y);
+                max_pos.z = max_pos.z.max(vertex.position.z);
+            }
+            zms.bounding_box.min = min_pos;
+            zms.bounding_box.max = max_pos;
+        }
+
+        // ROSE has no texture transforms, so bake the base color texture
+        // transform into the UV channel it uses
+        if let Some(info) = primitive
+            .material()
+            .pbr_metallic_roughness()
+            .base_color_texture()
+        {
+            if let Some(transform) = info.texture_transform() {
+                let channel = transform.tex_coord().unwrap_or(info.tex_coord()) as usize;
+                if channel < 4 && zms.format & (VertexFormat::UV1 as i32) << channel != 0 {
+                    uv::apply_texture_transform(
+                        &mut zms,
+                        channel,
+                        Vec2::from_array(transform.offset()),
+                        transform.rotation(),
+                        Vec2::from_array(transform.scale()),
+                    );
+                } else {
+                    warn(format!(
+                        "Mesh {}: KHR_texture_transform references missing UV{}, ignored",
+                        mesh_name,
+                        channel + 1
+                    ));
+                }
+            }
+        }
+
+        for (channel, uv_mode) in options.uv_modes.iter().enumerate() {
+            if zms.format & (VertexFormat::UV1 as i32) << channel == 0 {
+                continue;
+            }
+
+            if options.flip_v {
+                uv::flip_v(&mut zms, channel);
+            }
+
+            if let Some((min, max)) = uv::normalize_uvs(&mut zms, channel, *uv_mode) {
+                warn(format!(
+                    "Mesh {}: UV{} extents ({}, {}) - ({}, {}) are outside of [0, 1], {:?} applied",
+                    mesh_name,
+                    channel + 1,
+                    min.x,
+                    min.y,
+                    max.x,
+                    max.y,
+                    uv_mode
+                ));
+            }
+        }
+
+        if options.generate_lightmap_uvs && !zms.uv2_enabled() && !zms.indices.is_empty() {
+            if uv::generate_lightmap_uvs(&mut zms) {
+                zms.format |= VertexFormat::UV2 as i32;
+            } else {
+                warn(format!(
+                    "Mesh {}: too many vertices to split into lightmap UV charts, UV2 not generated",
+                    mesh_name
+                ));
+            }
+        }
+
+        if let Some(keep_attributes) = options.keep_attributes {
+            zms.format &= keep_attributes | VertexFormat::Position as i32;
+
+            if !zms.bones_enabled() {
+                zms.format &= !(VertexFormat::BoneWeight as i32 | VertexFormat::BoneIndex as i32);
+            }
+        }
+
+        if zms.bones_enabled() {
+            // Skeleton can contain more than 48 bones but a mesh can not, so
+            // split the mesh up if necessary and narrow down the bone list to
+            // only what each mesh actually uses.
+            let partitions = partition_mesh_bones(zms);
+
+            if partitions.len() > 1 {
+                warn(format!(
+                    "Mesh {} binds to more than {} bones, splitting into {} meshes",
+                    mesh_name,
+                    bone_palette::MAX_MESH_BONES,
+                    partitions.len()
+                ));
+            }
+
+            let num_partitions = partitions.len();
+            for (partition_index, mut partition) in partitions.into_iter().enumerate() {
+                apply_bone_palette(&mut partition)?;
+
+                let zms_name = if num_partitions == 1 {
+                    mesh_name.clone()
+                } else {
+                    format!("{}_{}", mesh_name, partition_index)
+                };
+                zms_mesh_path(mesh.index(), &zms_name);
+                result.zms.push((zms_name, partition));
+            }
+        } else {
+            zms_mesh_path(mesh.index(), &mesh_name);
+            result.zms.push((mesh_name, zms));
+        }
     }
 
     for (animation_index, animation) in gltf_data.document.animations().enumerate() {
+        if options.skip_animations {
+            break;
+        }
+
+        let animation_name = animation
+            .name()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("animation_{}", animation_index));
+        if !animation_filter.matches(&animation_name) {
+            continue;
+        }
+
+        let animation_override = animation_overrides
+            .iter()
+            .find(|(regex, _)| regex.is_match(&animation_name))
+            .map(|(_, animation_override)| *animation_override);
+
         let mut zmo = ZMO::new();
         let mut max_keyframe_time = 0.0f32;
 
@@ -827,16 +1992,59 @@ pub fn gltf_to_rose(
             }
         }
 
-        let num_frames = (max_keyframe_time * animation_fps as f32).ceil() as u32;
+        // Animations converted from a ZMO record its fps
+        let recorded_fps =
+            read_recorded_fps(&gltf_data.document.as_json().animations[animation.index()].extras);
+        let animation_fps = animation_override
+            .and_then(|o| o.fps)
+            .or((options.zmo_fps != 0).then_some(options.zmo_fps))
+            .or(recorded_fps)
+            .unwrap_or(DEFAULT_ZMO_FPS);
+        let start_time = animation_override
+            .and_then(|o| o.start_time)
+            .unwrap_or(0.0)
+            .clamp(0.0, max_keyframe_time);
+        let end_time = animation_override
+            .and_then(|o| o.end_time)
+            .unwrap_or(max_keyframe_time)
+            .clamp(start_time, max_keyframe_time);
+
+        if animation_fps == 0 {
+            return Err(CategorizedError::error(
+                ErrorCategory::Validation,
+                format!("Animation {} has an fps of 0", animation_name),
+            ));
+        }
+
+        // Frames are sampled at exact multiples of 1 / fps and include a frame
+        // at the end time, so the frames of a ZMO converted at the same fps
+        // are reproduced. Retiming changes how many frames a second of the
+        // glTF animation lasts.
+        let sample_rate = animation_fps as f32 * retime;
+        let num_frames = ((end_time - start_time) * sample_rate - 1.0e-3).ceil() as u32 + 1;
         zmo.identifier = "ZMO0002".into();
         zmo.fps = animation_fps;
         zmo.frames = num_frames;
 
+        // Channels which target a node which is not a joint, such as a ZSC
+        // part, are written to a ZMO per node with a single channel index of 0
+        // as expected by the animation_path of a part.
+        let mut node_zmos: Vec<(gltf::Node, ZMO)> = Vec::new();
+
         for channel in animation.channels() {
             let reader = channel.reader(|buffer| Some(&gltf_data.buffers[buffer.index()]));
             let outputs = reader.read_outputs().unwrap();
             let inputs = reader.read_inputs().unwrap();
             let interpolation = channel.sampler().interpolation();
+            if interpolation == Interpolation::CubicSpline {
+                return Err(CategorizedError::error(
+                    ErrorCategory::Unsupported,
+                    format!(
+                        "Animation {} uses cubic spline interpolation",
+                        animation_name
+                    ),
+                ));
+            }
             let target_node = channel.target().node();
 
             let target_bone_index = gltf_data
@@ -847,8 +2055,29 @@ pub fn gltf_to_rose(
                     (target_node.index() == joint_node.index()).then_some(joint_index as u32)
                 });
 
-            let Some(target_bone_index) = target_bone_index else {
-                continue;
+            let (channel_zmo, channel_index) = match target_bone_index {
+                Some(target_bone_index) => (&mut zmo, target_bone_index),
+                None => {
+                    let node_zmo_index = match node_zmos
+                        .iter()
+                        .position(|(node, _)| node.index() == target_node.index())
+                    {
+                        Some(node_zmo_index) => node_zmo_index,
+                        None => {
+                            node_zmos.push((
+                                target_node.clone(),
+                                ZMO {
+                                    identifier: zmo.identifier.clone(),
+                                    fps: zmo.fps,
+                                    frames: zmo.frames,
+                                    channels: Vec::new(),
+                                },
+                            ));
+                            node_zmos.len() - 1
+                        }
+                    };
+                    (&mut node_zmos[node_zmo_index].1, 0)
+                }
             };
 
             match outputs {
@@ -858,7 +2087,7 @@ pub fn gltf_to_rose(
                     let mut rasterized_frames = Vec::with_capacity(num_frames as usize);
 
                     for frame_index in 0..num_frames {
-                        let frame_time = frame_index as f32 / animation_fps as f32;
+                        let frame_time = start_time + frame_index as f32 / sample_rate;
 
                         let keyframe_before = keyframes
                             .iter()
@@ -869,18 +2098,14 @@ pub fn gltf_to_rose(
                             .find(|(t, _)| *t >= frame_time)
                             .unwrap_or_else(|| keyframes.last().unwrap());
 
-                        let value = match interpolation {
-                            Interpolation::Linear => {
-                                if keyframe_before == keyframe_after {
-                                    keyframe_before.1
-                                } else {
-                                    let lerp_factor = (frame_time - keyframe_before.0)
-                                        / (keyframe_after.0 - keyframe_before.0);
-                                    keyframe_before.1.lerp(keyframe_after.1, lerp_factor)
-                                }
-                            }
-                            Interpolation::Step => keyframe_before.1,
-                            Interpolation::CubicSpline => todo!(),
+                        let value = if interpolation == Interpolation::Step
+                            || keyframe_before == keyframe_after
+                        {
+                            keyframe_before.1
+                        } else {
+                            let lerp_factor = (frame_time - keyframe_before.0)
+                                / (keyframe_after.0 - keyframe_before.0);
+                            keyframe_before.1.lerp(keyframe_after.1, lerp_factor)
                         } * 100.0;
 
                         rasterized_frames.push(Vector3 {
@@ -890,11 +2115,15 @@ pub fn gltf_to_rose(
                         });
                     }
 
-                    zmo.channels.push(rose_file_lib::files::zmo::Channel {
-                        typ: rose_file_lib::files::zmo::ChannelType::Position,
-                        index: target_bone_index,
-                        frames: rose_file_lib::files::zmo::ChannelData::Position(rasterized_frames),
-                    });
+                    channel_zmo
+                        .channels
+                        .push(rose_file_lib::files::zmo::Channel {
+                            typ: rose_file_lib::files::zmo::ChannelType::Position,
+                            index: channel_index,
+                            frames: rose_file_lib::files::zmo::ChannelData::Position(
+                                rasterized_frames,
+                            ),
+                        });
                 }
                 ReadOutputs::Rotations(rotations) => {
                     let rotations: Vec<glam::Quat> = match rotations {
@@ -921,7 +2150,7 @@ pub fn gltf_to_rose(
                     let mut rasterized_frames = Vec::with_capacity(num_frames as usize);
 
                     for frame_index in 0..num_frames {
-                        let frame_time = frame_index as f32 / animation_fps as f32;
+                        let frame_time = start_time + frame_index as f32 / sample_rate;
 
                         let keyframe_before = keyframes
                             .iter()
@@ -932,18 +2161,14 @@ pub fn gltf_to_rose(
                             .find(|(t, _)| *t >= frame_time)
                             .unwrap_or_else(|| keyframes.last().unwrap());
 
-                        let value = match interpolation {
-                            Interpolation::Linear => {
-                                if keyframe_before == keyframe_after {
-                                    keyframe_before.1
-                                } else {
-                                    let lerp_factor = (frame_time - keyframe_before.0)
-                                        / (keyframe_after.0 - keyframe_before.0);
-                                    keyframe_before.1.slerp(keyframe_after.1, lerp_factor)
-                                }
-                            }
-                            Interpolation::Step => keyframe_before.1,
-                            Interpolation::CubicSpline => todo!(),
+                        let value = if interpolation == Interpolation::Step
+                            || keyframe_before == keyframe_after
+                        {
+                            keyframe_before.1
+                        } else {
+                            let lerp_factor = (frame_time - keyframe_before.0)
+                                / (keyframe_after.0 - keyframe_before.0);
+                            keyframe_before.1.slerp(keyframe_after.1, lerp_factor)
                         };
                         let value =
                             glam::Quat::from_xyzw(value.x, -value.z, value.y, value.w).normalize();
@@ -956,18 +2181,22 @@ pub fn gltf_to_rose(
                         });
                     }
 
-                    zmo.channels.push(rose_file_lib::files::zmo::Channel {
-                        typ: rose_file_lib::files::zmo::ChannelType::Rotation,
-                        index: target_bone_index,
-                        frames: rose_file_lib::files::zmo::ChannelData::Rotation(rasterized_frames),
-                    });
+                    channel_zmo
+                        .channels
+                        .push(rose_file_lib::files::zmo::Channel {
+                            typ: rose_file_lib::files::zmo::ChannelType::Rotation,
+                            index: channel_index,
+                            frames: rose_file_lib::files::zmo::ChannelData::Rotation(
+                                rasterized_frames,
+                            ),
+                        });
                 }
                 ReadOutputs::Scales(scales) => {
                     let keyframes: Vec<_> = inputs.zip(scales.map(glam::Vec3::from)).collect();
                     let mut rasterized_frames = Vec::with_capacity(num_frames as usize);
 
                     for frame_index in 0..num_frames {
-                        let frame_time = frame_index as f32 / animation_fps as f32;
+                        let frame_time = start_time + frame_index as f32 / sample_rate;
 
                         let keyframe_before = keyframes
                             .iter()
@@ -978,43 +2207,149 @@ pub fn gltf_to_rose(
                             .find(|(t, _)| *t >= frame_time)
                             .unwrap_or_else(|| keyframes.last().unwrap());
 
-                        let value = match interpolation {
-                            Interpolation::Linear => {
-                                let lerp_factor = (frame_time - keyframe_before.0)
-                                    / (keyframe_after.0 - keyframe_before.0);
-                                keyframe_before.1.lerp(keyframe_after.1, lerp_factor)
-                            }
-                            Interpolation::Step => keyframe_before.1,
-                            Interpolation::CubicSpline => todo!(),
+                        let value = if interpolation == Interpolation::Step
+                            || keyframe_before == keyframe_after
+                        {
+                            keyframe_before.1
+                        } else {
+                            let lerp_factor = (frame_time - keyframe_before.0)
+                                / (keyframe_after.0 - keyframe_before.0);
+                            keyframe_before.1.lerp(keyframe_after.1, lerp_factor)
                         };
 
                         rasterized_frames.push((value.x + value.y + value.z) / 3.0);
                     }
 
-                    zmo.channels.push(rose_file_lib::files::zmo::Channel {
-                        typ: rose_file_lib::files::zmo::ChannelType::Scale,
-                        index: target_bone_index,
-                        frames: rose_file_lib::files::zmo::ChannelData::Scale(rasterized_frames),
-                    });
+                    channel_zmo
+                        .channels
+                        .push(rose_file_lib::files::zmo::Channel {
+                            typ: rose_file_lib::files::zmo::ChannelType::Scale,
+                            index: channel_index,
+                            frames: rose_file_lib::files::zmo::ChannelData::Scale(
+                                rasterized_frames,
+                            ),
+                        });
                 }
                 _ => {}
             }
         }
 
-        result.zmo.push((
-            animation
-                .name()
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| format!("animation_{}", animation_index)),
-            zmo,
-        ));
+        // Channels glTF can not animate, such as alpha and UV channels, are
+        // restored from the ZMO the animation was converted from
+        let passthrough_channels = read_passthrough_channels(
+            &gltf_data.document.as_json().animations[animation.index()].extras,
+            start_time,
+            sample_rate,
+            num_frames,
+        );
+        match node_zmos.as_mut_slice() {
+            [(_, node_zmo)] if zmo.channels.is_empty() => {
+                node_zmo.channels.extend(passthrough_channels)
+            }
+            _ => zmo.channels.extend(passthrough_channels),
+        }
+
+        let single_node_zmo = node_zmos.len() == 1 && zmo.channels.is_empty();
+        if !zmo.channels.is_empty() || node_zmos.is_empty() {
+            skeletal_zmos.push(result.zmo.len());
+            result.zmo.push((animation_name.clone(), zmo));
+        }
+
+        for (node, node_zmo) in node_zmos {
+            let zmo_name = if single_node_zmo {
+                animation_name.clone()
+            } else {
+                format!(
+                    "{}_{}",
+                    animation_name,
+                    node.name()
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| format!("node_{}", node.index()))
+                )
+            };
+
+            // A part can only reference one animation, the first one is used
+            node_zmo_paths.entry(node.index()).or_insert_with(|| {
+                zsc_export::join_rose_path(
+                    &options.zsc_mesh_directory,
+                    &format!("{}.zmo", sanitize_name(&zmo_name)),
+                )
+            });
+            result.zmo.push((zmo_name, node_zmo));
+        }
     }
 
-    let node_to_bone = |node: &gltf::Node, name: &str| -> Bone {
-        let (translation, rotation, _scale) = node.transform().decomposed();
+    if let Some(animation_concat) = options.animation_concat.as_ref() {
+        concatenate_animations(&mut result.zmo, &skeletal_zmos, animation_concat)?;
+    }
 
-        let translation = Vec3::from_array(translation) * 100.0;
-        let rotation = Quat::from_array(rotation);
+    if options.generate_him {
+        for node in gltf_data.document.nodes() {
+            if let Some(him) = heightmap::heightmap_to_him(&node, &gltf_data.buffers) {
+                result.him.push(him);
+            }
+        }
+    }
+
+    if options.generate_zsc {
+        for (scene_index, scene) in gltf_data.document.scenes().enumerate() {
+            if options.zsc_text {
+                result.zsc_text.extend(zsc_export::generate_models(
+                    &scene,
+                    &gltf_data.buffers,
+                    &mesh_zms_paths,
+                    &node_zmo_paths,
+                    &options.texture_path_rules,
+                ));
+                continue;
+            }
+
+            let zsc = zsc_export::generate_zsc(
+                &scene,
+                &gltf_data.buffers,
+                &mesh_zms_paths,
+                &node_zmo_paths,
+                &options.texture_path_rules,
+            );
+            if zsc.models.is_empty() {
+                continue;
+            }
+
+            result.zsc.push((
+                scene
+                    .name()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("scene_{}", scene_index)),
+                zsc,
+            ));
+        }
+
+        if options.copy_textures {
+            let mut copied_textures = HashSet::new();
+            for material in gltf_data.document.materials() {
+                let Some((image_index, uri)) = zsc_export::material_texture_uri(&material) else {
+                    continue;
+                };
+
+                let texture_path = options.texture_path_rules.rewrite(&uri);
+                if !copied_textures.insert(texture_path.clone()) {
+                    continue;
+                }
+
+                match gltf_data
+                    .images
+                    .get(image_index)
+                    .and_then(zsc_export::image_data_to_rgba)
+                {
+                    Some(image) => result.textures.push((texture_path, image)),
+                    None => warn(format!("Unsupported image format for texture {}", uri)),
+                }
+            }
+        }
+    }
+
+    let transform_to_bone = |translation: Vec3, rotation: Quat, name: &str| -> Bone {
+        let translation = translation * 100.0;
 
         Bone {
             parent: 0,
@@ -1033,8 +2368,30 @@ pub fn gltf_to_rose(
         }
     };
 
+    let node_to_bone = |node: &gltf::Node, name: &str| -> Bone {
+        let (translation, rotation, _scale) = node.transform().decomposed();
+        transform_to_bone(
+            Vec3::from_array(translation),
+            Quat::from_array(rotation),
+            name,
+        )
+    };
+
     for (skin_index, skin) in gltf_data.document.skins().enumerate() {
+        if options.skip_skins {
+            break;
+        }
+
+        let skin_name = skin
+            .name()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("skeleton_{}", skin_index));
+        if !skin_filter.matches(&skin_name) {
+            continue;
+        }
+
         let mut zmd = ZMD::new();
+        zmd.version = options.zmd_version;
 
         let joints: Vec<gltf::Node> = skin.joints().collect();
         let mut node_to_zmd_idx = HashMap::new();
@@ -1042,7 +2399,7 @@ pub fn gltf_to_rose(
         for joint in joints.iter() {
             let bone_name = joint
                 .name()
-                .map(|s| s.to_string())
+                .map(|s| strip_duplicate_suffix(s).to_string())
                 .unwrap_or(format!("bone_{}", zmd.bones.len()));
 
             let bone = node_to_bone(joint, &bone_name);
@@ -1054,7 +2411,7 @@ pub fn gltf_to_rose(
         for dummy_node in &dummy_nodes {
             let bone_name = dummy_node
                 .name()
-                .map(|s| s.to_string())
+                .map(|s| strip_duplicate_suffix(s).to_string())
                 .unwrap_or(format!("dummy_{}", zmd.dummy_bones.len()));
 
             let bone = node_to_bone(dummy_node, &bone_name);
@@ -1070,7 +2427,12 @@ pub fn gltf_to_rose(
             for child in parent.children() {
                 let parent_idx = match node_to_zmd_idx.get(&parent.index()) {
                     Some(ZmdBoneIndex::Bone(idx)) => *idx,
-                    _ => anyhow::bail!("Dummy bones should not have children"),
+                    _ => {
+                        return Err(CategorizedError::error(
+                            ErrorCategory::Unsupported,
+                            "Dummy bones should not have children".to_string(),
+                        ))
+                    }
                 };
 
                 let Some(child_index) = node_to_zmd_idx.get(&child.index()) else {
@@ -1088,31 +2450,77 @@ pub fn gltf_to_rose(
             }
         }
 
+        // The joint node transforms are not guaranteed to be in bind pose, e.g.
+        // when the armature was posed or transformed, so prefer deriving the
+        // bone transforms from the inverse bind matrices when available.
+        let reader = skin.reader(|buffer| Some(&gltf_data.buffers[buffer.index()]));
+        if let Some(inverse_bind_matrices) = reader.read_inverse_bind_matrices() {
+            let bind_poses: Vec<Mat4> = inverse_bind_matrices
+                .map(|matrix| Mat4::from_cols_array_2d(&matrix).inverse())
+                .collect();
+            if bind_poses.len() != zmd.bones.len() {
+                return Err(CategorizedError::error(
+                    ErrorCategory::Validation,
+                    format!(
+                        "Skin {} has {} inverse bind matrices but {} joints",
+                        skin_name,
+                        bind_poses.len(),
+                        zmd.bones.len()
+                    ),
+                ));
+            }
+
+            let mut num_mismatched_bones = 0;
+            for (bone_index, bone) in zmd.bones.iter_mut().enumerate() {
+                let parent_index = bone.parent as usize;
+                let local_transform = if parent_index == bone_index {
+                    bind_poses[bone_index]
+                } else {
+                    bind_poses[parent_index].inverse() * bind_poses[bone_index]
+                };
+                let (_scale, rotation, translation) =
+                    local_transform.to_scale_rotation_translation();
+
+                let mut ibm_bone = transform_to_bone(translation, rotation.normalize(), &bone.name);
+                ibm_bone.parent = bone.parent;
+
+                let position_delta = Vec3::new(
+                    ibm_bone.position.x - bone.position.x,
+                    ibm_bone.position.y - bone.position.y,
+                    ibm_bone.position.z - bone.position.z,
+                );
+                let rotation_dot = Quat::from_xyzw(
+                    ibm_bone.rotation.x,
+                    ibm_bone.rotation.y,
+                    ibm_bone.rotation.z,
+                    ibm_bone.rotation.w,
+                )
+                .dot(Quat::from_xyzw(
+                    bone.rotation.x,
+                    bone.rotation.y,
+                    bone.rotation.z,
+                    bone.rotation.w,
+                ));
+                if position_delta.length() > 0.01 || rotation_dot.abs() < 0.9999 {
+                    num_mismatched_bones += 1;
+                }
+
+                *bone = ibm_bone;
+            }
+
+            if num_mismatched_bones > 0 {
+                warn(format!(
+                    "Skin {}: {} joint node transforms differ from the inverse bind matrices, using the inverse bind matrices",
+                    skin_name, num_mismatched_bones
+                ));
+            }
+        }
+
         // Best effort sort dummy bones by name
-        zmd.dummy_bones.sort_by(|a, b| {
-            let a_num = a
-                .name
-                .split('_')
-                .nth(1)
-                .unwrap_or_default()
-                .parse::<u32>()
-                .unwrap_or(0);
-            let b_num = b
-                .name
-                .split('_')
-                .nth(1)
-                .unwrap_or_default()
-                .parse::<u32>()
-                .unwrap_or(0);
-            a_num.cmp(&b_num)
-        });
+        zmd.dummy_bones
+            .sort_by_key(|bone| dummy_name_index(&bone.name).unwrap_or(0));
 
-        result.zmd.push((
-            skin.name()
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| format!("skeleton_{}", skin_index)),
-            zmd,
-        ));
+        result.zmd.push((skin_name, zmd));
     }
 
     Ok(result)
@@ -0,0 +1,119 @@
+//! Lights of the light container dummy points of ZSC models, as
+//! KHR_lights_punctual lights on nodes placed relative to the dummy point.
+use glam::{Quat, Vec3};
+use gltf_json::{
+    extensions::{self, scene::khr_lights_punctual},
+    scene::{self, UnitQuaternion},
+    validation::Checked,
+    Index,
+};
+use rose_file_lib::{
+    files::{
+        lct::{Light, LightType},
+        LCT,
+    },
+    io::RoseFile,
+};
+
+use crate::{assets::AssetLocator, report::warn, zone::convert_position};
+
+/// Adds a light to the KHR_lights_punctual lights of the document
+pub(crate) fn add_punctual_light(
+    root: &mut gltf_json::Root,
+    light: khr_lights_punctual::Light,
+) -> Index<khr_lights_punctual::Light> {
+    if !root
+        .extensions_used
+        .iter()
+        .any(|extension| extension == "KHR_lights_punctual")
+    {
+        root.extensions_used.push("KHR_lights_punctual".to_string());
+    }
+
+    let lights = &mut root
+        .extensions
+        .get_or_insert_with(Default::default)
+        .khr_lights_punctual
+        .get_or_insert_with(|| extensions::root::KhrLightsPunctual { lights: Vec::new() })
+        .lights;
+    lights.push(light);
+    Index::new(lights.len() as u32 - 1)
+}
+
+/// Rotation turning the -Z axis, which glTF spot lights point along, to the
+/// direction of the light
+fn spot_rotation(light: &Light) -> Option<UnitQuaternion> {
+    let direction = Vec3::from(convert_position(light.direction)).normalize_or_zero();
+    if direction == Vec3::ZERO {
+        return None;
+    }
+    Some(UnitQuaternion(
+        Quat::from_rotation_arc(Vec3::NEG_Z, direction).to_array(),
+    ))
+}
+
+/// Adds the lights of the light container at `path` and a node for each of
+/// them named `{name}_light_{i}`, returns the nodes to place below the dummy
+/// point. Light containers which can not be read are left out with a warning.
+pub(crate) fn load_light_container(
+    root: &mut gltf_json::Root,
+    name: &str,
+    path: &str,
+    assets: &dyn AssetLocator,
+) -> Vec<Index<scene::Node>> {
+    let lct = match LCT::from_path(&assets.resolve(path)) {
+        Ok(lct) => lct,
+        Err(error) => {
+            warn(format!(
+                "Failed to read light container {} with error {}",
+                path, error
+            ));
+            return Vec::new();
+        }
+    };
+
+    let mut nodes = Vec::with_capacity(lct.lights.len());
+    for (light_index, light) in lct.lights.iter().enumerate() {
+        let light_name = format!("{}_light_{}", name, light_index);
+        let (type_, spot, rotation) = match light.light_type {
+            LightType::Point => (khr_lights_punctual::Type::Point, None, None),
+            LightType::Spot => (
+                khr_lights_punctual::Type::Spot,
+                Some(khr_lights_punctual::Spot {
+                    inner_cone_angle: light.inner_cone_angle.to_radians(),
+                    outer_cone_angle: light.outer_cone_angle.to_radians(),
+                }),
+                spot_rotation(light),
+            ),
+        };
+        let light_index = add_punctual_light(
+            root,
+            khr_lights_punctual::Light {
+                name: Some(light_name.clone()),
+                color: [light.color.r, light.color.g, light.color.b],
+                intensity: light.intensity,
+                type_: Checked::Valid(type_),
+                range: (light.range > 0.0).then_some(light.range / 100.0),
+                spot,
+                extensions: Default::default(),
+                extras: Default::default(),
+            },
+        );
+
+        let node_index = Index::new(root.nodes.len() as u32);
+        root.nodes.push(scene::Node {
+            name: Some(light_name),
+            extensions: Some(extensions::scene::Node {
+                khr_lights_punctual: Some(khr_lights_punctual::KhrLightsPunctual {
+                    light: light_index,
+                }),
+                ..Default::default()
+            }),
+            rotation,
+            translation: Some(convert_position(light.position)),
+            ..Default::default()
+        });
+        nodes.push(node_index);
+    }
+    nodes
+}
@@ -0,0 +1,349 @@
+//! Merging separately converted glTFs into one, e.g. to assemble a scene
+//! from a zone and the models placed in it.
+use std::collections::{HashMap, HashSet};
+
+use bytes::BufMut;
+use gltf_json::{buffer, extensions, mesh, scene, validation::USize64, Index, Root};
+
+use crate::{
+    dedup::dedup_key,
+    finish_gltf, pad_align,
+    prune::{map_instance_accessors, material_textures},
+    spill::BinaryData,
+    GltfData,
+};
+
+fn offset<T>(index: Index<T>, offset: usize) -> Index<T> {
+    Index::new((index.value() + offset) as u32)
+}
+
+fn remap<T>(index: Index<T>, map: &[u32]) -> Index<T> {
+    Index::new(map[index.value()])
+}
+
+/// Returns `name`, or `name` with the lowest numbered suffix which is not
+/// already used
+fn unique_name(used_names: &mut HashSet<String>, name: &str) -> String {
+    let mut unique = name.to_string();
+    let mut suffix = 1;
+    while used_names.contains(&unique) {
+        unique = format!("{}_{}", name, suffix);
+        suffix += 1;
+    }
+    used_names.insert(unique.clone());
+    unique
+}
+
+/// Merges glTFs into a single glTF with one buffer, whose scene contains the
+/// nodes of the default scene of each input.
+///
+/// Images with the same content, and samplers, textures and materials which
+/// only differ by name, are only added once. Nodes whose name is already used
+/// by an earlier input are renamed with a numbered suffix.
+pub fn merge_gltf(inputs: &[GltfData]) -> anyhow::Result<gltf::Gltf> {
+    let mut root = Root::default();
+    let mut binary_data = BinaryData::new(false);
+    let mut scene_nodes = Vec::new();
+
+    let mut image_keys = HashMap::new();
+    let mut sampler_keys = HashMap::new();
+    let mut texture_keys = HashMap::new();
+    let mut material_keys = HashMap::new();
+    let mut node_names = HashSet::new();
+
+    // The first input's copyright and generator are kept
+    if let Some(first) = inputs.first() {
+        let asset = &first.document.as_json().asset;
+        root.asset.copyright = asset.copyright.clone();
+        root.asset.generator = asset.generator.clone();
+    }
+
+    for input in inputs {
+        let json = input.document.as_json();
+
+        // Images are deduplicated first so the buffer views of duplicates can
+        // be left out
+        let mut image_map = Vec::with_capacity(json.images.len());
+        let mut new_images = Vec::new();
+        for image in json.images.iter() {
+            let key = match (image.buffer_view, image.uri.as_ref()) {
+                (Some(view), _) => {
+                    let view = &json.buffer_views[view.value()];
+                    let start = view.byte_offset.map_or(0, |offset| offset.0 as usize);
+                    let data = input.buffers[view.buffer.value()]
+                        .get(start..start + view.byte_length.0 as usize)
+                        .unwrap_or_default();
+                    blake3::hash(data).to_hex().to_string()
+                }
+                (None, Some(uri)) => uri.clone(),
+                (None, None) => String::new(),
+            };
+            let index = *image_keys.entry(key).or_insert_with(|| {
+                new_images.push(image.clone());
+                (root.images.len() + new_images.len() - 1) as u32
+            });
+            image_map.push(index);
+        }
+
+        // Copy each buffer view which is not only used by a duplicate image
+        let mut used_views = HashSet::new();
+        for accessor in json.accessors.iter() {
+            used_views.extend(accessor.buffer_view.map(|view| view.value()));
+            if let Some(sparse) = accessor.sparse.as_ref() {
+                used_views.insert(sparse.indices.buffer_view.value());
+                used_views.insert(sparse.values.buffer_view.value());
+            }
+        }
+        used_views.extend(
+            new_images
+                .iter()
+                .filter_map(|image| image.buffer_view.map(|view| view.value())),
+        );
+        let mut view_map = vec![0; json.buffer_views.len()];
+        for (view_index, view) in json.buffer_views.iter().enumerate() {
+            if !used_views.contains(&view_index) {
+                continue;
+            }
+
+            let start = view.byte_offset.map_or(0, |offset| offset.0 as usize);
+            let data = input.buffers[view.buffer.value()]
+                .get(start..start + view.byte_length.0 as usize)
+                .ok_or_else(|| anyhow::anyhow!("Buffer view {} is out of range", view_index))?;
+            pad_align(&mut binary_data);
+            let byte_offset = binary_data.len();
+            binary_data.put_slice(data);
+
+            view_map[view_index] = root.buffer_views.len() as u32;
+            root.buffer_views.push(buffer::View {
+                buffer: Index::new(0),
+                byte_offset: Some(USize64::from(byte_offset)),
+                ..view.clone()
+            });
+        }
+
+        for mut image in new_images {
+            image.buffer_view = image
+                .buffer_view
+                .map(|view| Index::new(view_map[view.value()]));
+            root.images.push(image);
+        }
+
+        let mut sampler_map = Vec::with_capacity(json.samplers.len());
+        for sampler in json.samplers.iter() {
+            let key = dedup_key(sampler, |sampler| sampler.name = None);
+            sampler_map.push(*sampler_keys.entry(key).or_insert_with(|| {
+                root.samplers.push(sampler.clone());
+                (root.samplers.len() - 1) as u32
+            }));
+        }
+
+        let mut texture_map = Vec::with_capacity(json.textures.len());
+        for texture in json.textures.iter() {
+            let mut texture = texture.clone();
+            texture.sampler = texture.sampler.map(|sampler| remap(sampler, &sampler_map));
+            texture.source = remap(texture.source, &image_map);
+            let key = dedup_key(&texture, |texture| texture.name = None);
+            texture_map.push(*texture_keys.entry(key).or_insert_with(|| {
+                root.textures.push(texture);
+                (root.textures.len() - 1) as u32
+            }));
+        }
+
+        let mut material_map = Vec::with_capacity(json.materials.len());
+        for material in json.materials.iter() {
+            let mut material = material.clone();
+            for texture in material_textures(&mut material) {
+                *texture = remap(*texture, &texture_map);
+            }
+            let key = dedup_key(&material, |material| material.name = None);
+            material_map.push(*material_keys.entry(key).or_insert_with(|| {
+                root.materials.push(material);
+                (root.materials.len() - 1) as u32
+            }));
+        }
+
+        // Variants with the same name are merged
+        let root_extensions = json.extensions.as_ref();
+        let mut variant_map = Vec::new();
+        for variant in root_extensions
+            .and_then(|extensions| extensions.khr_materials_variants.as_ref())
+            .map_or(&[][..], |variants| &variants.variants)
+        {
+            let variants = &mut root
+                .extensions
+                .get_or_insert_with(Default::default)
+                .khr_materials_variants
+                .get_or_insert_with(|| extensions::root::KhrMaterialsVariants {
+                    variants: Vec::new(),
+                })
+                .variants;
+            let index = match variants
+                .iter()
+                .position(|existing| existing.name == variant.name)
+            {
+                Some(index) => index,
+                None => {
+                    variants.push(variant.clone());
+                    variants.len() - 1
+                }
+            };
+            variant_map.push(index as u32);
+        }
+
+        let light_offset = root
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.khr_lights_punctual.as_ref())
+            .map_or(0, |lights| lights.lights.len());
+        if let Some(lights) =
+            root_extensions.and_then(|extensions| extensions.khr_lights_punctual.as_ref())
+        {
+            root.extensions
+                .get_or_insert_with(Default::default)
+                .khr_lights_punctual
+                .get_or_insert_with(|| extensions::root::KhrLightsPunctual { lights: Vec::new() })
+                .lights
+                .extend(lights.lights.iter().cloned());
+        }
+
+        let accessor_offset = root.accessors.len();
+        for accessor in json.accessors.iter() {
+            let mut accessor = accessor.clone();
+            accessor.buffer_view = accessor
+                .buffer_view
+                .map(|view| Index::new(view_map[view.value()]));
+            if let Some(sparse) = accessor.sparse.as_mut() {
+                sparse.indices.buffer_view = remap(sparse.indices.buffer_view, &view_map);
+                sparse.values.buffer_view = remap(sparse.values.buffer_view, &view_map);
+            }
+            root.accessors.push(accessor);
+        }
+
+        let mesh_offset = root.meshes.len();
+        for mesh in json.meshes.iter() {
+            let mut mesh = mesh.clone();
+            for primitive in mesh.primitives.iter_mut() {
+                for accessor in primitive.attributes.values_mut() {
+                    *accessor = offset(*accessor, accessor_offset);
+                }
+                primitive.indices = primitive
+                    .indices
+                    .map(|indices| offset(indices, accessor_offset));
+                primitive.material = primitive
+                    .material
+                    .map(|material| remap(material, &material_map));
+                for target in primitive.targets.iter_mut().flatten() {
+                    let mesh::MorphTarget {
+                        positions,
+                        normals,
+                        tangents,
+                    } = target;
+                    for accessor in [positions, normals, tangents].into_iter().flatten() {
+                        *accessor = offset(*accessor, accessor_offset);
+                    }
+                }
+                if let Some(variants) = primitive
+                    .extensions
+                    .as_mut()
+                    .and_then(|extensions| extensions.khr_materials_variants.as_mut())
+                {
+                    for mapping in variants.mappings.iter_mut() {
+                        mapping.material = material_map[mapping.material as usize];
+                        for variant in mapping.variants.iter_mut() {
+                            *variant = variant_map[*variant as usize];
+                        }
+                    }
+                }
+            }
+            root.meshes.push(mesh);
+        }
+
+        let camera_offset = root.cameras.len();
+        root.cameras.extend(json.cameras.iter().cloned());
+
+        let node_offset = root.nodes.len();
+        let skin_offset = root.skins.len();
+        for node in json.nodes.iter() {
+            let mut node = node.clone();
+            node.name = node.name.map(|name| unique_name(&mut node_names, &name));
+            node.camera = node.camera.map(|camera| offset(camera, camera_offset));
+            node.mesh = node.mesh.map(|mesh| offset(mesh, mesh_offset));
+            node.skin = node.skin.map(|skin| offset(skin, skin_offset));
+            map_instance_accessors(&mut node, |accessor| accessor + accessor_offset);
+            for child in node.children.iter_mut().flatten() {
+                *child = offset(*child, node_offset);
+            }
+            if let Some(light) = node
+                .extensions
+                .as_mut()
+                .and_then(|extensions| extensions.khr_lights_punctual.as_mut())
+            {
+                light.light = offset(light.light, light_offset);
+            }
+            root.nodes.push(node);
+        }
+
+        for skin in json.skins.iter() {
+            let mut skin = skin.clone();
+            skin.inverse_bind_matrices = skin
+                .inverse_bind_matrices
+                .map(|accessor| offset(accessor, accessor_offset));
+            for joint in skin.joints.iter_mut() {
+                *joint = offset(*joint, node_offset);
+            }
+            skin.skeleton = skin.skeleton.map(|node| offset(node, node_offset));
+            root.skins.push(skin);
+        }
+
+        for animation in json.animations.iter() {
+            let mut animation = animation.clone();
+            for channel in animation.channels.iter_mut() {
+                channel.target.node = offset(channel.target.node, node_offset);
+            }
+            for sampler in animation.samplers.iter_mut() {
+                sampler.input = offset(sampler.input, accessor_offset);
+                sampler.output = offset(sampler.output, accessor_offset);
+            }
+            root.animations.push(animation);
+        }
+
+        let scene = json
+            .scene
+            .and_then(|scene| json.scenes.get(scene.value()))
+            .or(json.scenes.first());
+        if let Some(scene) = scene {
+            if root.scenes.is_empty() {
+                // The first scene keeps its name and extras
+                root.scenes.push(scene::Scene {
+                    nodes: Vec::new(),
+                    ..scene.clone()
+                });
+            }
+            scene_nodes.extend(scene.nodes.iter().map(|node| offset(*node, node_offset)));
+        }
+
+        for extension in json.extensions_used.iter() {
+            if !root.extensions_used.contains(extension) {
+                root.extensions_used.push(extension.clone());
+            }
+        }
+        for extension in json.extensions_required.iter() {
+            if !root.extensions_required.contains(extension) {
+                root.extensions_required.push(extension.clone());
+            }
+        }
+    }
+
+    if root.scenes.is_empty() {
+        root.scenes.push(scene::Scene {
+            name: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+            nodes: Vec::new(),
+        });
+    }
+    root.scenes[0].nodes = scene_nodes;
+    root.scene = Some(Index::new(0));
+
+    finish_gltf(root, binary_data)
+}
@@ -1,18 +1,69 @@
-use bytes::BytesMut;
 use glam::{Vec2, Vec3, Vec4};
 use gltf_json::{mesh, validation::Checked};
-use rose_file_lib::files::ZMS;
+use rose_file_lib::{files::ZMS, utils::Vector2};
 
-use crate::mesh_builder::{MeshBuilder, MeshData};
+use crate::{
+    cleanup::report_removed,
+    mesh_builder::{MeshBuilder, MeshData, MorphTargetDeltas},
+    report::{time_phase, warn},
+    spill::BinaryData,
+    winding::WindingCorrection,
+};
 
+#[allow(clippy::too_many_arguments)]
 pub fn load_mesh_data(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
+    binary_data: &mut BinaryData,
     name: &str,
     zms: &ZMS,
     regenerate_normals: bool,
+    generate_missing_tangents: bool,
+    flip_v: bool,
+    f32_colors: bool,
+    winding: WindingCorrection,
+    hard_edge_angle: Option<f32>,
 ) -> MeshData {
+    load_mesh_data_with_targets(
+        root,
+        binary_data,
+        name,
+        zms,
+        regenerate_normals,
+        generate_missing_tangents,
+        flip_v,
+        f32_colors,
+        winding,
+        hard_edge_angle,
+        Vec::new(),
+    )
+}
+
+/// `load_mesh_data` with morph targets which displace the vertices of `zms`
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn load_mesh_data_with_targets(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    name: &str,
+    zms: &ZMS,
+    regenerate_normals: bool,
+    generate_missing_tangents: bool,
+    flip_v: bool,
+    f32_colors: bool,
+    winding: WindingCorrection,
+    hard_edge_angle: Option<f32>,
+    morph_targets: Vec<MorphTargetDeltas>,
+) -> MeshData {
+    let _timer = time_phase("mesh");
+    let convert_uv = |uv: &Vector2<f32>| {
+        if flip_v {
+            Vec2::new(uv.x, 1.0 - uv.y)
+        } else {
+            Vec2::new(uv.x, uv.y)
+        }
+    };
+
     let mut mesh_builder = MeshBuilder::new();
+    mesh_builder.set_f32_colors(f32_colors);
     mesh_builder.add_indices(
         zms.indices
             .iter()
@@ -27,6 +78,13 @@ pub fn load_mesh_data(
             .collect(),
     );
 
+    if mesh_builder.correct_winding(winding) && winding == WindingCorrection::Auto {
+        warn(format!(
+            "Mesh {}: faces inwards, reversed its winding",
+            name
+        ));
+    }
+
     if !regenerate_normals && zms.normals_enabled() {
         mesh_builder.add_normals(
             zms.vertices
@@ -67,7 +125,7 @@ pub fn load_mesh_data(
         mesh_builder.add_uv0(
             zms.vertices
                 .iter()
-                .map(|vertex| Vec2::new(vertex.uv1.x, vertex.uv1.y))
+                .map(|vertex| convert_uv(&vertex.uv1))
                 .collect(),
         );
     }
@@ -76,7 +134,7 @@ pub fn load_mesh_data(
         mesh_builder.add_uv1(
             zms.vertices
                 .iter()
-                .map(|vertex| Vec2::new(vertex.uv2.x, vertex.uv2.y))
+                .map(|vertex| convert_uv(&vertex.uv2))
                 .collect(),
         );
     }
@@ -85,7 +143,7 @@ pub fn load_mesh_data(
         mesh_builder.add_uv2(
             zms.vertices
                 .iter()
-                .map(|vertex| Vec2::new(vertex.uv3.x, vertex.uv3.y))
+                .map(|vertex| convert_uv(&vertex.uv3))
                 .collect(),
         );
     }
@@ -94,7 +152,7 @@ pub fn load_mesh_data(
         mesh_builder.add_uv3(
             zms.vertices
                 .iter()
-                .map(|vertex| Vec2::new(vertex.uv4.x, vertex.uv4.y))
+                .map(|vertex| convert_uv(&vertex.uv4))
                 .collect(),
         );
     }
@@ -144,16 +202,53 @@ pub fn load_mesh_data(
         )
     }
 
+    for morph_target in morph_targets {
+        mesh_builder.add_morph_target(morph_target);
+    }
+
+    let (degenerate_triangles, unused_vertices) = mesh_builder.remove_degenerates();
+    report_removed(name, degenerate_triangles, unused_vertices);
+
+    if let Some(angle) = hard_edge_angle {
+        if !mesh_builder.split_hard_edges(angle.to_radians()) {
+            warn(format!(
+                "Mesh {}: too many vertices to split its hard edges",
+                name
+            ));
+        }
+    }
+
+    if generate_missing_tangents && !mesh_builder.has_tangents() {
+        mesh_builder.generate_tangents();
+    }
+
     mesh_builder.build(root, binary_data, name)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn load_mesh(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
+    binary_data: &mut BinaryData,
     name: &str,
     zms: &ZMS,
+    generate_missing_tangents: bool,
+    flip_v: bool,
+    f32_colors: bool,
+    winding: WindingCorrection,
+    hard_edge_angle: Option<f32>,
 ) -> u32 {
-    let mesh_data = load_mesh_data(root, binary_data, name, zms, false);
+    let mesh_data = load_mesh_data(
+        root,
+        binary_data,
+        name,
+        zms,
+        false,
+        generate_missing_tangents,
+        flip_v,
+        f32_colors,
+        winding,
+        hard_edge_angle,
+    );
     let mesh_index = root.meshes.len() as u32;
     root.meshes.push(mesh::Mesh {
         name: Some(name.into()),
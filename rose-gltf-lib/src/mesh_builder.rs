@@ -1,16 +1,36 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
-use bytes::{BufMut, BytesMut};
+use anyhow::Context;
+use bytes::BufMut;
 use glam::{Vec2, Vec3, Vec4};
 use gltf_json::{
     accessor, buffer,
-    mesh::Semantic,
+    mesh::{MorphTarget, Semantic},
     validation::{Checked, USize64},
     Index,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use crate::pad_align;
+use crate::{
+    cleanup::{compact_vertices, remove_degenerate_triangles},
+    pad_align,
+    spill::BinaryData,
+    winding::{flip_triangles, WindingCorrection},
+};
+
+/// How the vertex attributes of meshes are stored in buffer views
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VertexBufferLayout {
+    /// One buffer view per mesh with the attributes of each vertex next to
+    /// each other
+    #[default]
+    Interleaved,
+
+    /// One tightly packed buffer view per attribute, for engines which update
+    /// attributes separately
+    Planar,
+}
 
 #[derive(Default)]
 pub struct MeshBuilder {
@@ -19,22 +39,35 @@ pub struct MeshBuilder {
     position_max: Vec3,
     indices: Vec<u16>,
     normal: Vec<Vec3>,
-    tangent: Vec<Vec3>,
+    tangent: Vec<Vec4>,
     uv0: Vec<Vec2>,
     uv1: Vec<Vec2>,
     uv2: Vec<Vec2>,
     uv3: Vec<Vec2>,
     color: Vec<Vec4>,
+    f32_colors: bool,
     bone_weight: Vec<Vec4>,
     bone_index: Vec<[u16; 4]>,
+    morph_targets: Vec<MorphTargetDeltas>,
+}
+
+/// Displacements of each vertex for one morph target
+#[derive(Clone, Default)]
+pub struct MorphTargetDeltas {
+    pub position: Vec<Vec3>,
+
+    /// Empty when the target does not change the normals
+    pub normal: Vec<Vec3>,
 }
 
 #[derive(Clone)]
 pub struct MeshData {
     pub attributes: BTreeMap<Checked<Semantic>, Index<accessor::Accessor>>,
+    pub targets: Vec<MorphTarget>,
     pub indices: Index<accessor::Accessor>,
     pub surface_area: f32,
     pub num_faces: usize,
+    pub plane_normal: Option<Vec3>,
 }
 
 impl MeshBuilder {
@@ -66,10 +99,10 @@ impl MeshBuilder {
     }
 
     pub fn add_tangents(&mut self, tangents: Vec<Vec3>) {
-        self.tangent = tangents;
-        for tangent in self.tangent.iter_mut() {
-            *tangent = tangent.normalize();
-        }
+        self.tangent = tangents
+            .into_iter()
+            .map(|tangent| tangent.normalize().extend(1.0))
+            .collect();
     }
 
     pub fn add_uv0(&mut self, uv0: Vec<Vec2>) {
@@ -92,6 +125,11 @@ impl MeshBuilder {
         self.color = color;
     }
 
+    /// Write colors as f32 instead of normalized u8
+    pub fn set_f32_colors(&mut self, f32_colors: bool) {
+        self.f32_colors = f32_colors;
+    }
+
     pub fn add_bone_weight(&mut self, bone_weight: Vec<Vec4>) {
         self.bone_weight = bone_weight;
     }
@@ -100,6 +138,144 @@ impl MeshBuilder {
         self.bone_index = bone_index;
     }
 
+    pub fn add_morph_target(&mut self, morph_target: MorphTargetDeltas) {
+        self.morph_targets.push(morph_target);
+    }
+
+    fn compact_morph_targets(&mut self, kept_vertices: &[usize]) {
+        for morph_target in self.morph_targets.iter_mut() {
+            compact_vertices(&mut morph_target.position, kept_vertices);
+            compact_vertices(&mut morph_target.normal, kept_vertices);
+        }
+    }
+
+    fn triangles(&self) -> Vec<[usize; 3]> {
+        self.indices
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|corner| triangle[corner] as usize))
+            .collect()
+    }
+
+    /// Reverses the winding of the triangles if `winding` says so, returns
+    /// whether they were reversed.
+    pub fn correct_winding(&mut self, winding: WindingCorrection) -> bool {
+        let mut triangles = self.triangles();
+        if !winding.should_flip(&triangles, &self.position) {
+            return false;
+        }
+
+        flip_triangles(&mut triangles);
+        self.indices = triangles
+            .into_iter()
+            .flatten()
+            .map(|index| index as u16)
+            .collect();
+        true
+    }
+
+    /// Removes degenerate triangles and the vertices no triangle uses,
+    /// returns how many of each were removed.
+    pub fn remove_degenerates(&mut self) -> (usize, usize) {
+        let mut triangles = self.triangles();
+        let (degenerate_triangles, kept_vertices) =
+            remove_degenerate_triangles(&mut triangles, &self.position);
+        let unused_vertices = self.position.len() - kept_vertices.len();
+        if degenerate_triangles == 0 && unused_vertices == 0 {
+            return (0, 0);
+        }
+
+        self.indices = triangles
+            .into_iter()
+            .flatten()
+            .map(|index| index as u16)
+            .collect();
+        let mut position = std::mem::take(&mut self.position);
+        compact_vertices(&mut position, &kept_vertices);
+        self.add_positions(position);
+        compact_vertices(&mut self.normal, &kept_vertices);
+        compact_vertices(&mut self.tangent, &kept_vertices);
+        compact_vertices(&mut self.uv0, &kept_vertices);
+        compact_vertices(&mut self.uv1, &kept_vertices);
+        compact_vertices(&mut self.uv2, &kept_vertices);
+        compact_vertices(&mut self.uv3, &kept_vertices);
+        compact_vertices(&mut self.color, &kept_vertices);
+        compact_vertices(&mut self.bone_weight, &kept_vertices);
+        compact_vertices(&mut self.bone_index, &kept_vertices);
+        self.compact_morph_targets(&kept_vertices);
+        (degenerate_triangles, unused_vertices)
+    }
+
+    /// Splits the vertices of faces which meet at more than `angle` radians,
+    /// so the edges between them stay hard, and gives each vertex the average
+    /// normal of the faces around its position within `angle` of its face.
+    /// Returns false and leaves the mesh as it is if it would have too many
+    /// vertices for u16 indices.
+    pub fn split_hard_edges(&mut self, angle: f32) -> bool {
+        let triangles = self.triangles();
+        let face_normals: Vec<Vec3> = triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let vertex_a = self.position[a];
+                (self.position[b] - vertex_a).cross(self.position[c] - vertex_a)
+            })
+            .collect();
+
+        // Faces around each position, vertices at UV seams share a position
+        // but not an index
+        let mut position_faces: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+        for (face, triangle) in triangles.iter().enumerate() {
+            for &vertex in triangle {
+                let key = self.position[vertex].to_array().map(f32::to_bits);
+                position_faces.entry(key).or_default().push(face);
+            }
+        }
+
+        let min_cos = angle.cos();
+        let mut split_vertices = HashMap::new();
+        let mut kept_vertices = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::with_capacity(self.indices.len());
+        for (face, triangle) in triangles.iter().enumerate() {
+            let face_normal = face_normals[face].normalize_or_zero();
+            for &vertex in triangle {
+                let key = self.position[vertex].to_array().map(f32::to_bits);
+                let normal = position_faces[&key]
+                    .iter()
+                    .map(|&other_face| face_normals[other_face])
+                    .filter(|other_normal| {
+                        other_normal.normalize_or_zero().dot(face_normal) >= min_cos
+                    })
+                    .sum::<Vec3>()
+                    .normalize_or_zero();
+                let index = *split_vertices
+                    .entry((vertex, normal.to_array().map(f32::to_bits)))
+                    .or_insert_with(|| {
+                        kept_vertices.push(vertex);
+                        normals.push(normal);
+                        kept_vertices.len() - 1
+                    });
+                indices.push(index);
+            }
+        }
+        if kept_vertices.len() > u16::MAX as usize + 1 {
+            return false;
+        }
+
+        self.indices = indices.into_iter().map(|index| index as u16).collect();
+        compact_vertices(&mut self.position, &kept_vertices);
+        self.normal = normals;
+        compact_vertices(&mut self.tangent, &kept_vertices);
+        compact_vertices(&mut self.uv0, &kept_vertices);
+        compact_vertices(&mut self.uv1, &kept_vertices);
+        compact_vertices(&mut self.uv2, &kept_vertices);
+        compact_vertices(&mut self.uv3, &kept_vertices);
+        compact_vertices(&mut self.color, &kept_vertices);
+        compact_vertices(&mut self.bone_weight, &kept_vertices);
+        compact_vertices(&mut self.bone_index, &kept_vertices);
+        self.compact_morph_targets(&kept_vertices);
+        true
+    }
+
     pub fn generate_normals(&mut self) {
         let mut normals = vec![Vec3::ZERO; self.position.len()];
 
@@ -125,6 +301,89 @@ impl MeshBuilder {
         self.normal = normals;
     }
 
+    pub fn has_tangents(&self) -> bool {
+        !self.tangent.is_empty()
+    }
+
+    /// Generates MikkTSpace tangents, requires normals and uv0.
+    pub fn generate_tangents(&mut self) -> bool {
+        struct Geometry<'a> {
+            builder: &'a MeshBuilder,
+            tangents: Vec<Vec4>,
+        }
+
+        impl Geometry<'_> {
+            fn vertex_index(&self, face: usize, vert: usize) -> usize {
+                self.builder.indices[face * 3 + vert] as usize
+            }
+        }
+
+        impl bevy_mikktspace::Geometry for Geometry<'_> {
+            fn num_faces(&self) -> usize {
+                self.builder.indices.len() / 3
+            }
+
+            fn num_vertices_of_face(&self, _face: usize) -> usize {
+                3
+            }
+
+            fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+                self.builder.position[self.vertex_index(face, vert)].to_array()
+            }
+
+            fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+                self.builder.normal[self.vertex_index(face, vert)].to_array()
+            }
+
+            fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+                self.builder.uv0[self.vertex_index(face, vert)].to_array()
+            }
+
+            fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+                let vertex_index = self.vertex_index(face, vert);
+                self.tangents[vertex_index] = Vec4::from_array(tangent);
+            }
+        }
+
+        if self.normal.is_empty() || self.uv0.is_empty() {
+            return false;
+        }
+
+        let mut geometry = Geometry {
+            builder: self,
+            tangents: vec![Vec4::new(1.0, 0.0, 0.0, 1.0); self.position.len()],
+        };
+        if !bevy_mikktspace::generate_tangents(&mut geometry) {
+            return false;
+        }
+
+        self.tangent = geometry.tangents;
+        true
+    }
+
+    /// Returns the normal of the plane containing every vertex, or None if
+    /// the mesh is not flat, e.g. for tree and grass cards.
+    pub fn calculate_plane_normal(&self) -> Option<Vec3> {
+        let normal = self
+            .indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let vertex_a = self.position[triangle[0] as usize];
+                let edge_a_b = self.position[triangle[1] as usize] - vertex_a;
+                let edge_a_c = self.position[triangle[2] as usize] - vertex_a;
+                edge_a_b.cross(edge_a_c)
+            })
+            .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))?
+            .try_normalize()?;
+
+        let origin = *self.position.first()?;
+        let tolerance = (self.position_max - self.position_min).length() * 0.01;
+        self.position
+            .iter()
+            .all(|position| normal.dot(*position - origin).abs() <= tolerance)
+            .then_some(normal)
+    }
+
     pub fn calculate_surface_area(&self) -> f32 {
         let mut sum = 0.0;
 
@@ -145,7 +404,7 @@ impl MeshBuilder {
     pub fn build(
         self,
         root: &mut gltf_json::Root,
-        binary_data: &mut BytesMut,
+        binary_data: &mut BinaryData,
         name: &str,
     ) -> MeshData {
         let mut attributes = BTreeMap::new();
@@ -208,14 +467,14 @@ impl MeshBuilder {
                 )),
                 extensions: Default::default(),
                 extras: Default::default(),
-                type_: Checked::Valid(accessor::Type::Vec3),
+                type_: Checked::Valid(accessor::Type::Vec4),
                 min: None,
                 max: None,
                 normalized: false,
                 sparse: None,
             });
             attributes.insert(Checked::Valid(Semantic::Tangents), accessor);
-            vertex_data_stride += 4 * 3;
+            vertex_data_stride += 4 * 4;
         }
 
         if !self.color.is_empty() {
@@ -226,18 +485,22 @@ impl MeshBuilder {
                 byte_offset: Some(USize64::from(vertex_data_stride)),
                 count: USize64::from(vertex_count),
                 component_type: Checked::Valid(accessor::GenericComponentType(
-                    accessor::ComponentType::F32,
+                    if self.f32_colors {
+                        accessor::ComponentType::F32
+                    } else {
+                        accessor::ComponentType::U8
+                    },
                 )),
                 extensions: Default::default(),
                 extras: Default::default(),
                 type_: Checked::Valid(accessor::Type::Vec4),
                 min: None,
                 max: None,
-                normalized: false,
+                normalized: !self.f32_colors,
                 sparse: None,
             });
             attributes.insert(Checked::Valid(Semantic::Colors(0)), accessor);
-            vertex_data_stride += 4 * 4;
+            vertex_data_stride += if self.f32_colors { 4 * 4 } else { 4 };
         }
 
         if !self.uv0.is_empty() {
@@ -389,13 +652,20 @@ impl MeshBuilder {
                 binary_data.put_f32_le(self.tangent[i].x);
                 binary_data.put_f32_le(self.tangent[i].y);
                 binary_data.put_f32_le(self.tangent[i].z);
+                binary_data.put_f32_le(self.tangent[i].w);
             }
 
             if !self.color.is_empty() {
-                binary_data.put_f32_le(self.color[i].x);
-                binary_data.put_f32_le(self.color[i].y);
-                binary_data.put_f32_le(self.color[i].z);
-                binary_data.put_f32_le(self.color[i].w);
+                if self.f32_colors {
+                    binary_data.put_f32_le(self.color[i].x);
+                    binary_data.put_f32_le(self.color[i].y);
+                    binary_data.put_f32_le(self.color[i].z);
+                    binary_data.put_f32_le(self.color[i].w);
+                } else {
+                    for component in self.color[i].to_array() {
+                        binary_data.put_u8((component.clamp(0.0, 1.0) * 255.0).round() as u8);
+                    }
+                }
             }
 
             if !self.uv0.is_empty() {
@@ -483,11 +753,160 @@ impl MeshBuilder {
             sparse: None,
         });
 
+        let mut targets = Vec::with_capacity(self.morph_targets.len());
+        for (target_index, morph_target) in self.morph_targets.iter().enumerate() {
+            let has_normals = !morph_target.normal.is_empty();
+            let stride = if has_normals { 4 * 6 } else { 4 * 3 };
+            let mut position_min = morph_target.position[0];
+            let mut position_max = morph_target.position[0];
+            let target_data_start = binary_data.len();
+            for i in 0..vertex_count {
+                let position = morph_target.position[i];
+                position_min = position_min.min(position);
+                position_max = position_max.max(position);
+                binary_data.put_f32_le(position.x);
+                binary_data.put_f32_le(position.y);
+                binary_data.put_f32_le(position.z);
+
+                if has_normals {
+                    binary_data.put_f32_le(morph_target.normal[i].x);
+                    binary_data.put_f32_le(morph_target.normal[i].y);
+                    binary_data.put_f32_le(morph_target.normal[i].z);
+                }
+            }
+            let target_data_length = binary_data.len() - target_data_start;
+
+            let target_buffer_view = Index::new(root.buffer_views.len() as u32);
+            root.buffer_views.push(buffer::View {
+                name: Some(format!("{}_target{}_vbuffer", name, target_index)),
+                buffer: Index::new(0),
+                byte_length: USize64::from(target_data_length),
+                byte_offset: Some(USize64::from(target_data_start)),
+                byte_stride: Some(buffer::Stride(stride)),
+                extensions: Default::default(),
+                extras: Default::default(),
+                target: Some(Checked::Valid(buffer::Target::ArrayBuffer)),
+            });
+
+            let positions = Index::new(root.accessors.len() as u32);
+            root.accessors.push(accessor::Accessor {
+                name: Some(format!("{}_target{}_position", name, target_index)),
+                buffer_view: Some(target_buffer_view),
+                byte_offset: Some(USize64(0)),
+                count: USize64::from(vertex_count),
+                component_type: Checked::Valid(accessor::GenericComponentType(
+                    accessor::ComponentType::F32,
+                )),
+                extensions: Default::default(),
+                extras: Default::default(),
+                type_: Checked::Valid(accessor::Type::Vec3),
+                min: Some(json!(position_min.to_array())),
+                max: Some(json!(position_max.to_array())),
+                normalized: false,
+                sparse: None,
+            });
+
+            let normals = has_normals.then(|| {
+                let normals = Index::new(root.accessors.len() as u32);
+                root.accessors.push(accessor::Accessor {
+                    name: Some(format!("{}_target{}_normal", name, target_index)),
+                    buffer_view: Some(target_buffer_view),
+                    byte_offset: Some(USize64(4 * 3)),
+                    count: USize64::from(vertex_count),
+                    component_type: Checked::Valid(accessor::GenericComponentType(
+                        accessor::ComponentType::F32,
+                    )),
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                    type_: Checked::Valid(accessor::Type::Vec3),
+                    min: None,
+                    max: None,
+                    normalized: false,
+                    sparse: None,
+                });
+                normals
+            });
+
+            targets.push(MorphTarget {
+                positions: Some(positions),
+                normals,
+                tangents: None,
+            });
+        }
+
         MeshData {
             attributes,
+            targets,
             indices: index_buffer_accessor,
             surface_area: self.calculate_surface_area(),
             num_faces: self.indices.len() / 3,
+            plane_normal: self.calculate_plane_normal(),
         }
     }
 }
+
+/// Copies each attribute of the interleaved vertex buffer views built by
+/// `MeshBuilder` into its own buffer view. The interleaved buffer views are
+/// left unused for `prune_unused` to remove.
+pub(crate) fn deinterleave_vertex_buffers(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+) -> anyhow::Result<()> {
+    for accessor in root.accessors.iter_mut() {
+        let Some(view_index) = accessor.buffer_view else {
+            continue;
+        };
+        let view = &root.buffer_views[view_index.value()];
+        let (Some(buffer::Stride(stride)), Some(Checked::Valid(buffer::Target::ArrayBuffer))) =
+            (view.byte_stride, view.target)
+        else {
+            continue;
+        };
+        let (Checked::Valid(component_type), Checked::Valid(type_)) =
+            (accessor.component_type, accessor.type_)
+        else {
+            continue;
+        };
+
+        // The elements of vertex attributes must be aligned to 4 bytes, so
+        // only elements of other sizes need padding and a stride
+        let element_size = component_type.0.size() * type_.multiplicity();
+        let aligned_size = element_size.next_multiple_of(4);
+        let data_start = view.byte_offset.map_or(0, |offset| offset.0 as usize)
+            + accessor.byte_offset.map_or(0, |offset| offset.0 as usize);
+        let count = accessor.count.0 as usize;
+
+        let Some(interleaved) = binary_data
+            .read(data_start..data_start + (count.max(1) - 1) * stride + element_size)
+            .context("Failed to read temporary file")?
+        else {
+            continue;
+        };
+        let mut data = Vec::with_capacity(count * aligned_size);
+        for element in interleaved.chunks(stride).take(count) {
+            data.extend_from_slice(&element[..element_size]);
+            data.resize(data.len() + aligned_size - element_size, 0);
+        }
+
+        pad_align(binary_data);
+        let planar_view = Index::new(root.buffer_views.len() as u32);
+        root.buffer_views.push(buffer::View {
+            name: accessor
+                .name
+                .as_ref()
+                .map(|name| format!("{}_buffer", name)),
+            buffer: Index::new(0),
+            byte_length: USize64::from(data.len()),
+            byte_offset: Some(USize64::from(binary_data.len())),
+            byte_stride: (aligned_size != element_size).then_some(buffer::Stride(aligned_size)),
+            extensions: Default::default(),
+            extras: Default::default(),
+            target: Some(Checked::Valid(buffer::Target::ArrayBuffer)),
+        });
+        binary_data.put_slice(&data);
+
+        accessor.buffer_view = Some(planar_view);
+        accessor.byte_offset = Some(USize64(0));
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//! Mirroring skeletal animations from one side to the other, e.g. to create a
+//! left handed attack from a right handed one.
+use glam::{Mat4, Vec3};
+use rose_file_lib::{
+    files::{zmo, ZMD, ZMO},
+    utils::{Quaternion, Vector3},
+};
+
+use crate::{
+    animation::slice_frames,
+    pose::{bind_pose_transforms, posed_transforms},
+};
+
+/// Swaps a left or right token of a bone name, e.g. `b1_l_thigh` and
+/// `Bip01 R Hand`. Tokens are separated by `_`, `-`, `.` or spaces, and may be
+/// lower, title or upper case.
+pub fn mirrored_bone_name(name: &str) -> Option<String> {
+    let mut mirrored = String::with_capacity(name.len());
+    let mut swapped = false;
+    for token in name.split_inclusive(['_', '-', '.', ' ']) {
+        let (word, separator) = match token.char_indices().last() {
+            Some((index, '_' | '-' | '.' | ' ')) => token.split_at(index),
+            _ => (token, ""),
+        };
+        let replacement = match word {
+            "l" => Some("r"),
+            "r" => Some("l"),
+            "L" => Some("R"),
+            "R" => Some("L"),
+            "left" => Some("right"),
+            "right" => Some("left"),
+            "Left" => Some("Right"),
+            "Right" => Some("Left"),
+            "LEFT" => Some("RIGHT"),
+            "RIGHT" => Some("LEFT"),
+            _ => None,
+        };
+        match replacement {
+            Some(replacement) if !swapped => {
+                mirrored.push_str(replacement);
+                swapped = true;
+            }
+            _ => mirrored.push_str(word),
+        }
+        mirrored.push_str(separator);
+    }
+    swapped.then_some(mirrored)
+}
+
+/// The bone each bone takes its mirrored motion from, bones without a pair
+/// mirror themselves.
+fn bone_pairs(zmd: &ZMD) -> Vec<usize> {
+    zmd.bones
+        .iter()
+        .enumerate()
+        .map(|(bone_index, bone)| {
+            mirrored_bone_name(&bone.name)
+                .and_then(|mirrored| {
+                    zmd.bones
+                        .iter()
+                        .position(|other| other.name.eq_ignore_ascii_case(&mirrored))
+                })
+                .unwrap_or(bone_index)
+        })
+        .collect()
+}
+
+/// Mirrors a skeletal animation of `zmd` across its X axis, each bone moves
+/// like its left or right counterpart would in a mirror.
+pub fn mirror_animation(zmo: &ZMO, zmd: &ZMD) -> ZMO {
+    let pairs = bone_pairs(zmd);
+    let mirror = Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0));
+    let bind_pose = bind_pose_transforms(zmd);
+
+    // Corrects for the bind pose of a bone not being the mirror of its pair
+    let corrections: Vec<Mat4> = pairs
+        .iter()
+        .enumerate()
+        .map(|(bone_index, &pair)| {
+            (mirror * bind_pose[pair] * mirror).inverse() * bind_pose[bone_index]
+        })
+        .collect();
+
+    let mut local_frames = vec![Vec::with_capacity(zmo.frames as usize); zmd.bones.len()];
+    for frame in 0..zmo.frames {
+        let posed = posed_transforms(zmd, zmo, frame);
+        let mirrored: Vec<Mat4> = pairs
+            .iter()
+            .enumerate()
+            .map(|(bone_index, &pair)| mirror * posed[pair] * mirror * corrections[bone_index])
+            .collect();
+
+        for (bone_index, bone) in zmd.bones.iter().enumerate() {
+            let parent = bone.parent as usize;
+            let local = if parent == bone_index || parent >= zmd.bones.len() {
+                mirrored[bone_index]
+            } else {
+                mirrored[parent].inverse() * mirrored[bone_index]
+            };
+            let (_, rotation, translation) = local.to_scale_rotation_translation();
+            local_frames[bone_index].push((
+                Vector3::new(
+                    translation.x * 100.0,
+                    -translation.z * 100.0,
+                    translation.y * 100.0,
+                ),
+                Quaternion::new(rotation.x, -rotation.z, rotation.y, rotation.w),
+            ));
+        }
+    }
+
+    // Slicing copies the channels, which ZMO can not clone
+    let mut mirrored = slice_frames(zmo, 0, zmo.frames.saturating_sub(1));
+    mirrored.frames = zmo.frames;
+    for channel in mirrored.channels.iter_mut() {
+        let Some(&bone_index) = pairs.get(channel.index as usize) else {
+            continue;
+        };
+        match &mut channel.frames {
+            zmo::ChannelData::Position(positions) => {
+                *positions = local_frames[bone_index]
+                    .iter()
+                    .map(|frame| frame.0)
+                    .collect();
+            }
+            zmo::ChannelData::Rotation(rotations) => {
+                *rotations = local_frames[bone_index]
+                    .iter()
+                    .map(|frame| frame.1)
+                    .collect();
+            }
+            _ => {}
+        }
+        channel.index = bone_index as u32;
+    }
+    mirrored
+}
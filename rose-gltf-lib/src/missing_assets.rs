@@ -0,0 +1,168 @@
+//! Finding the assets a zone references which are missing, before converting
+//! it, so every missing file is listed at once instead of the conversion
+//! failing on the first one.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::Arc,
+};
+
+use rose_file_lib::{
+    files::{IFO, ZON, ZSC},
+    io::RoseFile,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::{normalize_asset_path, AssetLocator, FsAssetLocator},
+    cache::load_cached,
+    error::{CategoryContext, ErrorCategory},
+    zone::is_object_disabled,
+    zone_lookup::find_zone_object_lists,
+    RoseGltfConvOptions,
+};
+
+/// A file referenced by a zone which could not be found
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MissingAsset {
+    /// Path of the file, relative to the assets root
+    pub path: String,
+
+    /// What references the file, e.g. `deco object 12 in block 31_32`
+    pub referenced_by: Vec<String>,
+}
+
+/// Resolves the tile textures of the zone and the meshes and textures of the
+/// objects in the blocks selected by `options`, returning the ones which
+/// could not be found.
+pub fn find_missing_zone_assets(
+    zon_path: &Path,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<Vec<MissingAsset>> {
+    let assets: Arc<dyn AssetLocator> = match &options.asset_locator {
+        Some(asset_locator) => asset_locator.clone(),
+        None => Arc::new(FsAssetLocator::for_file(zon_path, &options.asset_roots)),
+    };
+    let map_path = zon_path
+        .parent()
+        .category_context(ErrorCategory::MissingDependency, || {
+            format!("Could not find map path of {}", zon_path.display())
+        })?;
+    let relative_zon_path = assets
+        .asset_path(zon_path)
+        .category_context(ErrorCategory::MissingDependency, || {
+            format!("Could not find root assets path of {}", zon_path.display())
+        })?;
+
+    let asset_cache = options.asset_cache.as_deref();
+    let zone_object_lists =
+        find_zone_object_lists(zon_path, &relative_zon_path, assets.as_ref(), options)?;
+    let zon = ZON::from_path(zon_path).category_context(ErrorCategory::InputParse, || {
+        format!("Failed to load {}", zon_path.display())
+    })?;
+
+    let mut missing: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut check = |path: &str, referenced_by: &dyn Fn() -> String| {
+        if assets.locate(path).is_none() {
+            missing
+                .entry(normalize_asset_path(path))
+                .or_default()
+                .insert(referenced_by());
+        }
+    };
+
+    for (tile_texture_index, tile_texture_path) in zon.textures.iter().enumerate() {
+        if tile_texture_path == "end" {
+            break;
+        }
+        check(tile_texture_path, &|| {
+            format!(
+                "tile texture {} of {}",
+                tile_texture_index, relative_zon_path
+            )
+        });
+    }
+
+    let mut object_lists = Vec::with_capacity(2);
+    for (kind, zsc_path, zsc_source) in [
+        (
+            "deco",
+            &zone_object_lists.deco,
+            &zone_object_lists.deco_source,
+        ),
+        (
+            "cnst",
+            &zone_object_lists.cnst,
+            &zone_object_lists.cnst_source,
+        ),
+    ] {
+        let zsc = match assets.locate(zsc_path) {
+            Some(path) => Some(
+                load_cached::<ZSC>(asset_cache, &path)
+                    .category_context(ErrorCategory::InputParse, || {
+                        format!("Failed to read {}", zsc_path)
+                    })?,
+            ),
+            None => {
+                check(zsc_path, &|| zsc_source.clone());
+                None
+            }
+        };
+        object_lists.push((kind, zsc));
+    }
+
+    for block_y in 0..64 {
+        for block_x in 0..64 {
+            if options.filter_block_x.is_some_and(|x| x != block_x)
+                || options.filter_block_y.is_some_and(|y| y != block_y)
+            {
+                continue;
+            }
+
+            let Ok(ifo) = IFO::from_path(&map_path.join(format!("{}_{}.ifo", block_x, block_y)))
+            else {
+                continue;
+            };
+
+            for ((kind, zsc), objects) in object_lists.iter().zip([&ifo.objects, &ifo.buildings]) {
+                let Some(zsc) = zsc else {
+                    continue;
+                };
+
+                for object in objects.iter() {
+                    if !options.include_disabled_objects && is_object_disabled(object) {
+                        continue;
+                    }
+
+                    let Some(model) = zsc
+                        .models
+                        .get(object.object_id as usize)
+                        .and_then(|model| model.as_ref())
+                    else {
+                        continue;
+                    };
+                    let referenced_by = || {
+                        format!(
+                            "{} object {} in block {}_{}",
+                            kind, object.object_id, block_x, block_y
+                        )
+                    };
+                    for part in model.parts.iter() {
+                        check(&part.mesh_path, &referenced_by);
+                        if let Some(material) = part.material.as_ref() {
+                            check(&material.path, &referenced_by);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(missing
+        .into_iter()
+        .map(|(path, referenced_by)| MissingAsset {
+            path,
+            referenced_by: referenced_by.into_iter().collect(),
+        })
+        .collect())
+}
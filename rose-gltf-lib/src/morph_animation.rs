@@ -0,0 +1,225 @@
+//! Skeletal animations baked into morph targets, one target per frame, for
+//! viewers which do not support skinning and for thumbnail renders.
+use bytes::BufMut;
+use glam::Vec3;
+use gltf_json::{
+    accessor, animation, buffer, mesh,
+    validation::{Checked, USize64},
+    Index, Node,
+};
+use rose_file_lib::{
+    files::{zms::VertexFormat, ZMD, ZMO, ZMS},
+    utils::Vector3,
+};
+use serde_json::json;
+
+use crate::{
+    mesh::load_mesh_data_with_targets, mesh_builder::MorphTargetDeltas, pad_align, pose::bake_pose,
+    spill::BinaryData, winding::WindingCorrection,
+};
+
+fn convert_vector(vector: &Vector3<f32>) -> Vec3 {
+    Vec3::new(vector.x, vector.z, -vector.y)
+}
+
+/// The displacement of every vertex of `zms` from its bind pose at each frame
+/// of `zmo`
+fn frame_morph_targets(zms: &ZMS, zmd: &ZMD, zmo: &ZMO) -> anyhow::Result<Vec<MorphTargetDeltas>> {
+    let mut morph_targets = Vec::with_capacity(zmo.frames as usize);
+    for frame in 0..zmo.frames {
+        let baked = bake_pose(zms, zmd, zmo, frame)?;
+        let position = zms
+            .vertices
+            .iter()
+            .zip(baked.vertices.iter())
+            .map(|(vertex, baked_vertex)| {
+                convert_vector(&baked_vertex.position) - convert_vector(&vertex.position)
+            })
+            .collect();
+        let normal = if zms.normals_enabled() {
+            zms.vertices
+                .iter()
+                .zip(baked.vertices.iter())
+                .map(|(vertex, baked_vertex)| {
+                    convert_vector(&baked_vertex.normal).normalize_or_zero()
+                        - convert_vector(&vertex.normal).normalize_or_zero()
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        morph_targets.push(MorphTargetDeltas { position, normal });
+    }
+    Ok(morph_targets)
+}
+
+/// Adds a mesh of the skinned `zms` in its bind pose without a skin, with a
+/// morph target named `frame_{i}` for each frame of `zmo`. Returns the index
+/// of the mesh.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn load_morph_target_mesh(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    name: &str,
+    zms: &ZMS,
+    zmd: &ZMD,
+    zmo: &ZMO,
+    generate_missing_tangents: bool,
+    flip_v: bool,
+    f32_colors: bool,
+    winding: WindingCorrection,
+    hard_edge_angle: Option<f32>,
+) -> anyhow::Result<u32> {
+    let morph_targets = frame_morph_targets(zms, zmd, zmo)?;
+    let mut bind_pose = zms.clone();
+    bind_pose.format &= !(VertexFormat::BoneWeight as i32 | VertexFormat::BoneIndex as i32);
+    bind_pose.bones.clear();
+
+    let mesh_data = load_mesh_data_with_targets(
+        root,
+        binary_data,
+        name,
+        &bind_pose,
+        false,
+        generate_missing_tangents,
+        flip_v,
+        f32_colors,
+        winding,
+        hard_edge_angle,
+        morph_targets,
+    );
+    let target_names: Vec<String> = (0..mesh_data.targets.len())
+        .map(|frame| format!("frame_{}", frame))
+        .collect();
+    let mesh_index = root.meshes.len() as u32;
+    root.meshes.push(mesh::Mesh {
+        name: Some(name.into()),
+        extensions: Default::default(),
+        extras: serde_json::value::to_raw_value(&json!({ "targetNames": target_names })).ok(),
+        weights: Some(vec![0.0; mesh_data.targets.len()]),
+        primitives: vec![mesh::Primitive {
+            attributes: mesh_data.attributes,
+            extensions: Default::default(),
+            extras: Default::default(),
+            indices: Some(mesh_data.indices),
+            material: None,
+            mode: Checked::Valid(mesh::Mode::Triangles),
+            targets: Some(mesh_data.targets),
+        }],
+    });
+    Ok(mesh_index)
+}
+
+/// Adds an animation named `name` which shows each frame target of the meshes
+/// added by `load_morph_target_mesh` in turn, blending linearly between
+/// neighbouring frames.
+pub(crate) fn load_morph_target_animation(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    name: &str,
+    zmo: &ZMO,
+    nodes: &[Index<Node>],
+) {
+    if nodes.is_empty() || zmo.frames == 0 {
+        return;
+    }
+
+    pad_align(binary_data);
+    let fps = zmo.fps.max(1) as f32;
+    let keyframe_time_start = binary_data.len();
+    for frame in 0..zmo.frames {
+        binary_data.put_f32_le(frame as f32 / fps);
+    }
+    let keyframe_time_length = binary_data.len() - keyframe_time_start;
+
+    let weights_start = binary_data.len();
+    for frame in 0..zmo.frames {
+        for target in 0..zmo.frames {
+            binary_data.put_f32_le(if frame == target { 1.0 } else { 0.0 });
+        }
+    }
+    let weights_length = binary_data.len() - weights_start;
+
+    let mut add_accessor = |view_name: String,
+                            start: usize,
+                            length: usize,
+                            count: u32,
+                            min: Option<serde_json::Value>,
+                            max: Option<serde_json::Value>| {
+        let buffer_view = Index::new(root.buffer_views.len() as u32);
+        root.buffer_views.push(buffer::View {
+            name: Some(format!("{}BufferView", view_name)),
+            buffer: Index::new(0),
+            byte_length: USize64::from(length),
+            byte_offset: Some(USize64::from(start)),
+            byte_stride: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+            target: None,
+        });
+
+        let accessor_index = Index::new(root.accessors.len() as u32);
+        root.accessors.push(accessor::Accessor {
+            name: Some(format!("{}Accessor", view_name)),
+            buffer_view: Some(buffer_view),
+            byte_offset: Some(USize64(0)),
+            count: USize64::from(count as usize),
+            component_type: Checked::Valid(accessor::GenericComponentType(
+                accessor::ComponentType::F32,
+            )),
+            extensions: Default::default(),
+            extras: Default::default(),
+            type_: Checked::Valid(accessor::Type::Scalar),
+            min,
+            max,
+            normalized: false,
+            sparse: None,
+        });
+        accessor_index
+    };
+    let keyframe_times = add_accessor(
+        format!("{}_KeyframeTimes", name),
+        keyframe_time_start,
+        keyframe_time_length,
+        zmo.frames,
+        Some(json!([0.0])),
+        Some(json!([(zmo.frames - 1) as f32 / fps])),
+    );
+    let weights = add_accessor(
+        format!("{}_Weights", name),
+        weights_start,
+        weights_length,
+        zmo.frames * zmo.frames,
+        None,
+        None,
+    );
+
+    let channels = nodes
+        .iter()
+        .map(|node| animation::Channel {
+            sampler: Index::new(0),
+            target: animation::Target {
+                node: *node,
+                path: Checked::Valid(animation::Property::MorphTargetWeights),
+                extensions: Default::default(),
+                extras: Default::default(),
+            },
+            extensions: Default::default(),
+            extras: Default::default(),
+        })
+        .collect();
+
+    root.animations.push(animation::Animation {
+        name: Some(name.to_string()),
+        channels,
+        samplers: vec![animation::Sampler {
+            input: keyframe_times,
+            interpolation: Checked::Valid(animation::Interpolation::Linear),
+            output: weights,
+            extensions: Default::default(),
+            extras: Default::default(),
+        }],
+        extensions: Default::default(),
+        extras: Default::default(),
+    });
+}
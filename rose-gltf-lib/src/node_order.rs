@@ -0,0 +1,55 @@
+//! Reordering nodes so their indices follow the scene hierarchy, which is the
+//! order importers such as Blender list them in.
+use gltf_json::{Index, Root};
+
+/// Renumbers the nodes in depth first order of the scenes, each node followed
+/// by its children. Nodes which are in no scene are moved to the end.
+pub(crate) fn renumber_nodes_depth_first(root: &mut Root) {
+    let num_nodes = root.nodes.len();
+    let mut order = Vec::with_capacity(num_nodes);
+    let mut visited = vec![false; num_nodes];
+    for scene in root.scenes.iter() {
+        let mut stack: Vec<usize> = scene.nodes.iter().rev().map(|node| node.value()).collect();
+        while let Some(node_index) = stack.pop() {
+            if std::mem::replace(&mut visited[node_index], true) {
+                continue;
+            }
+            order.push(node_index);
+            if let Some(children) = root.nodes[node_index].children.as_ref() {
+                stack.extend(children.iter().rev().map(|child| child.value()));
+            }
+        }
+    }
+    order.extend((0..num_nodes).filter(|&node_index| !visited[node_index]));
+
+    let mut new_indices = vec![0; num_nodes];
+    for (new_index, &old_index) in order.iter().enumerate() {
+        new_indices[old_index] = new_index as u32;
+    }
+    let remap = |node: &mut Index<gltf_json::Node>| *node = Index::new(new_indices[node.value()]);
+
+    let mut nodes: Vec<_> = std::mem::take(&mut root.nodes)
+        .into_iter()
+        .map(Some)
+        .collect();
+    root.nodes = order
+        .iter()
+        .map(|&old_index| nodes[old_index].take().unwrap())
+        .collect();
+
+    for node in root.nodes.iter_mut() {
+        node.children.iter_mut().flatten().for_each(remap);
+    }
+    for scene in root.scenes.iter_mut() {
+        scene.nodes.iter_mut().for_each(remap);
+    }
+    for skin in root.skins.iter_mut() {
+        skin.joints.iter_mut().for_each(remap);
+        skin.skeleton.iter_mut().for_each(remap);
+    }
+    for animation in root.animations.iter_mut() {
+        for channel in animation.channels.iter_mut() {
+            remap(&mut channel.target.node);
+        }
+    }
+}
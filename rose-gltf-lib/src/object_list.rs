@@ -1,44 +1,361 @@
-use std::{collections::HashMap, io::Cursor, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::Context;
-use bytes::{BufMut, BytesMut};
+use bytes::BufMut;
+use glam::{Vec2, Vec3};
 use gltf_json::{
     buffer, material, texture,
     validation::{Checked, USize64},
     Index,
 };
-use image::{DynamicImage, ImageBuffer, Rgba};
+use image::{DynamicImage, Rgba, RgbaImage};
 use rose_file_lib::{
     files::{zsc, ZMS, ZSC},
     io::RoseFile,
 };
+use serde::{Deserialize, Serialize};
+use serde_json::{json, value::RawValue};
+
+use crate::{
+    assets::AssetLocator,
+    bone_palette::validate_bone_palette,
+    cache::{load_cached, load_cached_image, AssetCache},
+    embedded_image::EmbeddedImageOptions,
+    engine_hints::EngineHints,
+    error::{CategoryContext, ErrorCategory},
+    mesh::load_mesh_data,
+    mesh_builder::{MeshBuilder, MeshData},
+    pad_align,
+    report::warn,
+    spill::BinaryData,
+    winding::WindingCorrection,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GlassMaterialRules {
+    /// Treat every alpha blended material with specular enabled as glass
+    pub match_specular: bool,
+
+    /// Treat alpha blended materials whose texture path contains any of these
+    /// words as glass, matched case insensitively
+    pub keywords: Vec<String>,
+
+    /// Value of KHR_materials_transmission transmissionFactor
+    pub transmission: f32,
+
+    /// Value of KHR_materials_ior ior
+    pub ior: f32,
+
+    /// Roughness of glass materials, the default roughness of 1.0 makes glass look frosted
+    pub roughness: f32,
+}
+
+impl Default for GlassMaterialRules {
+    fn default() -> Self {
+        Self {
+            match_specular: true,
+            keywords: vec!["glass".to_string(), "crystal".to_string()],
+            transmission: 1.0,
+            ior: 1.5,
+            roughness: 0.1,
+        }
+    }
+}
+
+impl GlassMaterialRules {
+    pub fn matches(&self, material: &zsc::ModelMaterial) -> bool {
+        if !material.alpha_enabled || material.alpha_test.is_some() {
+            return false;
+        }
+
+        let path = material.path.to_ascii_lowercase();
+        (self.match_specular && material.specular_enabled)
+            || self
+                .keywords
+                .iter()
+                .any(|keyword| path.contains(&keyword.to_ascii_lowercase()))
+    }
+}
+
+/// Embeds an image and adds a texture for it, the buffer view, image and
+/// texture are named after `name`. `keep_alpha` is set for the textures of
+/// materials which are not opaque.
+pub(crate) fn push_texture(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    name: &str,
+    img: &DynamicImage,
+    sampler: Index<texture::Sampler>,
+    embedded_images: &EmbeddedImageOptions,
+    keep_alpha: bool,
+) -> Index<texture::Texture> {
+    let (image_data, mime_type) = embedded_images.encode(img, keep_alpha);
+
+    pad_align(binary_data);
+    let texture_data_start = binary_data.len();
+    binary_data.put_slice(&image_data);
+    pad_align(binary_data);
+
+    let buffer_index = Index::new(root.buffer_views.len() as u32);
+    root.buffer_views.push(buffer::View {
+        name: Some(format!("{}_image_buffer", name)),
+        buffer: Index::new(0),
+        byte_length: USize64::from(image_data.len()),
+        byte_offset: Some(USize64::from(texture_data_start)),
+        byte_stride: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+        target: None,
+    });
 
-use crate::{mesh::load_mesh_data, mesh_builder::MeshData, pad_align};
+    let image_index = Index::new(root.images.len() as u32);
+    root.images.push(gltf_json::Image {
+        name: Some(format!("{}_image", name)),
+        buffer_view: Some(buffer_index),
+        mime_type: Some(gltf_json::image::MimeType(mime_type.into())),
+        uri: None,
+        extensions: None,
+        extras: Default::default(),
+    });
+
+    let texture_index = Index::new(root.textures.len() as u32);
+    root.textures.push(texture::Texture {
+        name: Some(format!("{}_texture", name)),
+        sampler: Some(sampler),
+        source: image_index,
+        extensions: None,
+        extras: Default::default(),
+    });
+    texture_index
+}
+
+/// Color of the textures standing in for ones which could not be loaded
+pub(crate) const PLACEHOLDER_COLOR: [u8; 4] = [255, 1, 255, 0];
+
+/// Texture standing in for `path` when it could not be loaded. With
+/// `checkerboard` it is a checkerboard of gray and a color picked from the
+/// path, so each missing texture can be told apart, otherwise it is plain
+/// `PLACEHOLDER_COLOR`.
+pub(crate) fn placeholder_texture(path: &str, size: u32, checkerboard: bool) -> RgbaImage {
+    if !checkerboard {
+        return RgbaImage::from_pixel(size, size, Rgba(PLACEHOLDER_COLOR));
+    }
+
+    // FNV-1a, so the color of a path is the same every run
+    let hash = path.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte.to_ascii_lowercase() as u32).wrapping_mul(0x01000193)
+    });
+    let hue = (hash % 360) as f32 / 60.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let color = Rgba([(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8, 255]);
+    let gray = Rgba([64, 64, 64, 255]);
+
+    let square_size = (size / 8).max(1);
+    RgbaImage::from_fn(size, size, |x, y| {
+        if (x / square_size + y / square_size).is_multiple_of(2) {
+            color
+        } else {
+            gray
+        }
+    })
+}
+
+/// A 1m cube standing in for a mesh which could not be found.
+fn placeholder_mesh(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    name: &str,
+) -> MeshData {
+    let mut positions = Vec::with_capacity(24);
+    let mut normals = Vec::with_capacity(24);
+    let mut uvs = Vec::with_capacity(24);
+    let mut indices = Vec::with_capacity(36);
+    for axis in 0..3 {
+        let u = Vec3::AXES[(axis + 1) % 3];
+        let v = Vec3::AXES[(axis + 2) % 3];
+        for sign in [1.0, -1.0] {
+            let normal = Vec3::AXES[axis] * sign;
+            let first = positions.len() as u16;
+            for (x, y) in [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)] {
+                positions.push(normal * 0.5 + u * x + v * y);
+                normals.push(normal);
+                uvs.push(Vec2::new(x + 0.5, 0.5 - y));
+            }
+            if sign > 0.0 {
+                indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+            } else {
+                indices.extend([first, first + 2, first + 1, first, first + 3, first + 2]);
+            }
+        }
+    }
+
+    let mut mesh_builder = MeshBuilder::new();
+    mesh_builder.add_positions(positions);
+    mesh_builder.add_normals(normals);
+    mesh_builder.add_uv0(uvs);
+    mesh_builder.add_indices(indices);
+    mesh_builder.build(root, binary_data, name)
+}
+
+/// The files used by a part of an object, as found by an `AssetLocator`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ObjectPartFiles {
+    /// Path of the ZMS as referenced by the ZSC
+    pub mesh_path: String,
+
+    /// The ZMS on disk, None when it could not be found
+    pub mesh_file: Option<PathBuf>,
+
+    /// Path of the texture as referenced by the material of the part
+    pub texture_path: Option<String>,
+
+    /// The texture on disk, None when it could not be found
+    pub texture_file: Option<PathBuf>,
+}
 
+/// The models of a ZSC and the glTF meshes and materials loaded for them.
+///
+/// The query methods, such as `part_files` and `part_mesh`, look up the
+/// assets of an object without converting it.
 pub struct ObjectList {
-    pub zsc: ZSC,
+    pub zsc: Arc<ZSC>,
     pub materials: HashMap<zsc::ModelMaterial, Index<material::Material>>,
     pub meshes: HashMap<String, MeshData>,
     pub sampler: Index<texture::Sampler>,
+    pub generate_missing_tangents: bool,
+    pub flip_v: bool,
+    pub f32_vertex_colors: bool,
+    pub winding: WindingCorrection,
+    pub hard_edge_angle: Option<f32>,
+    pub glass_materials: Option<GlassMaterialRules>,
+    pub asset_cache: Option<Arc<AssetCache>>,
+
+    /// Do not keep decoded textures in `asset_cache`
+    pub low_memory: bool,
+
+    /// Use a placeholder cube for meshes which could not be found instead
+    /// of failing.
+    pub ignore_missing_assets: bool,
+
+    /// Use a checkerboard colored by the texture path for textures which
+    /// could not be loaded, instead of plain `PLACEHOLDER_COLOR`.
+    pub placeholder_textures: bool,
+
+    /// How the textures of materials are embedded
+    pub embedded_images: EmbeddedImageOptions,
+
+    /// Adds the material settings of a game engine to the material extras
+    pub engine_hints: EngineHints,
+
+    /// Path and number of bones of the skeleton which skinned meshes are
+    /// bound to, their bone palettes are checked against it.
+    pub skeleton: Option<(String, usize)>,
 }
 
 impl ObjectList {
-    pub fn new(zsc: ZSC, sampler: Index<texture::Sampler>) -> Self {
+    pub fn new(zsc: Arc<ZSC>, sampler: Index<texture::Sampler>) -> Self {
         Self {
             materials: HashMap::new(),
             meshes: HashMap::new(),
             zsc,
             sampler,
+            generate_missing_tangents: false,
+            flip_v: false,
+            f32_vertex_colors: false,
+            winding: WindingCorrection::None,
+            hard_edge_angle: None,
+            glass_materials: None,
+            asset_cache: None,
+            low_memory: false,
+            ignore_missing_assets: false,
+            placeholder_textures: false,
+            embedded_images: Default::default(),
+            engine_hints: Default::default(),
+            skeleton: None,
         }
     }
 
-    pub fn load_object(
+    /// Reads the ZSC at `zsc_path`. The sampler is the first of the document,
+    /// set `sampler` before loading objects into another document.
+    pub fn from_path(zsc_path: &Path) -> anyhow::Result<Self> {
+        let zsc = ZSC::from_path(zsc_path).category_context(ErrorCategory::InputParse, || {
+            format!("Failed to read {}", zsc_path.display())
+        })?;
+        Ok(Self::new(Arc::new(zsc), Index::new(0)))
+    }
+
+    /// The model of an object, None for ids which are out of range or unused
+    pub fn object(&self, object_id: usize) -> Option<&zsc::Model> {
+        self.zsc.models.get(object_id).and_then(Option::as_ref)
+    }
+
+    pub fn part(&self, object_id: usize, part_index: usize) -> Option<&zsc::ModelPart> {
+        self.object(object_id)?.parts.get(part_index)
+    }
+
+    pub fn part_material(
+        &self,
+        object_id: usize,
+        part_index: usize,
+    ) -> Option<&zsc::ModelMaterial> {
+        self.part(object_id, part_index)?.material.as_ref()
+    }
+
+    /// The ZMS and texture of a part and where `assets` finds them
+    pub fn part_files(
+        &self,
+        object_id: usize,
+        part_index: usize,
+        assets: &dyn AssetLocator,
+    ) -> Option<ObjectPartFiles> {
+        let part = self.part(object_id, part_index)?;
+        let texture_path = part.material.as_ref().map(|material| material.path.clone());
+        Some(ObjectPartFiles {
+            mesh_path: part.mesh_path.clone(),
+            mesh_file: assets.locate(&part.mesh_path),
+            texture_file: texture_path.as_deref().and_then(|path| assets.locate(path)),
+            texture_path,
+        })
+    }
+
+    /// Reads the ZMS of a part, from `asset_cache` when it is set
+    pub fn part_mesh(
+        &self,
+        object_id: usize,
+        part_index: usize,
+        assets: &dyn AssetLocator,
+    ) -> anyhow::Result<Arc<ZMS>> {
+        let part = self
+            .part(object_id, part_index)
+            .with_context(|| format!("Invalid object id: {} part: {}", object_id, part_index))?;
+        load_cached::<ZMS>(
+            self.asset_cache.as_deref(),
+            &assets.resolve(&part.mesh_path),
+        )
+        .category_context(ErrorCategory::MissingDependency, || {
+            format!("Failed to load {}", part.mesh_path)
+        })
+    }
+
+    pub(crate) fn load_object(
         &mut self,
         name_prefix: &str,
         object_id: usize,
         root: &mut gltf_json::Root,
-        binary_data: &mut BytesMut,
-        assets_path: &Path,
+        binary_data: &mut BinaryData,
+        assets: &dyn AssetLocator,
     ) -> anyhow::Result<()> {
         let object = self
             .zsc
@@ -52,14 +369,14 @@ impl ObjectList {
         {
             if let Some(material) = part.material.as_ref() {
                 if let Some(material_data) =
-                    self.load_material(name_prefix, material, root, binary_data, assets_path)
+                    self.load_material(name_prefix, material, root, binary_data, assets)
                 {
                     self.materials.insert(material.clone(), material_data);
                 }
             }
 
             if let Some(mesh_data) =
-                self.load_mesh(name_prefix, &part.mesh_path, root, binary_data, assets_path)
+                self.load_mesh(name_prefix, &part.mesh_path, root, binary_data, assets)?
             {
                 self.meshes.insert(part.mesh_path.clone(), mesh_data);
             }
@@ -68,37 +385,64 @@ impl ObjectList {
         Ok(())
     }
 
-    pub fn load_mesh(
+    pub(crate) fn load_mesh(
         &self,
         name_prefix: &str,
         mesh_path: &str,
         root: &mut gltf_json::Root,
-        binary_data: &mut BytesMut,
-        assets_path: &Path,
-    ) -> Option<MeshData> {
+        binary_data: &mut BinaryData,
+        assets: &dyn AssetLocator,
+    ) -> anyhow::Result<Option<MeshData>> {
         if self.meshes.contains_key(mesh_path) {
             // Already loaded
-            return None;
+            return Ok(None);
         }
 
-        let zms = ZMS::from_path(&assets_path.join(mesh_path)).expect("Failed to load ZMS");
+        if self.ignore_missing_assets && assets.locate(mesh_path).is_none() {
+            warn(format!("Missing mesh {}, using a placeholder", mesh_path));
+            let mesh_id = self.meshes.len();
+            return Ok(Some(placeholder_mesh(
+                root,
+                binary_data,
+                &format!("{}_mesh_{}", name_prefix, mesh_id),
+            )));
+        }
+
+        let zms = load_cached::<ZMS>(self.asset_cache.as_deref(), &assets.resolve(mesh_path))
+            .category_context(ErrorCategory::MissingDependency, || {
+                format!("Failed to load {}", mesh_path)
+            })?;
+        if zms.bones_enabled() {
+            validate_bone_palette(
+                &zms,
+                mesh_path,
+                self.skeleton
+                    .as_ref()
+                    .map(|(skeleton_path, num_bones)| (skeleton_path.as_str(), *num_bones)),
+            )?;
+        }
         let mesh_id = self.meshes.len();
-        Some(load_mesh_data(
+        Ok(Some(load_mesh_data(
             root,
             binary_data,
             &format!("{}_mesh_{}", name_prefix, mesh_id),
             &zms,
             true, // Seems like lots of objects have busted normals
-        ))
+            self.generate_missing_tangents,
+            self.flip_v,
+            self.f32_vertex_colors,
+            self.winding,
+            self.hard_edge_angle,
+        )))
     }
 
-    pub fn load_material(
+    pub(crate) fn load_material(
         &self,
         name_prefix: &str,
         material: &zsc::ModelMaterial,
         root: &mut gltf_json::Root,
-        binary_data: &mut BytesMut,
-        assets_path: &Path,
+        binary_data: &mut BinaryData,
+        assets: &dyn AssetLocator,
     ) -> Option<Index<material::Material>> {
         if self.materials.contains_key(material) {
             // Already loaded
@@ -106,59 +450,51 @@ impl ObjectList {
         }
 
         let material_id = self.materials.len();
-        let img = match image::open(assets_path.join(&material.path)) {
+        let mut placeholder = false;
+        let image_cache = self.asset_cache.as_deref().filter(|_| !self.low_memory);
+        let img = match load_cached_image(image_cache, &assets.resolve(&material.path)) {
             Ok(img) => img,
             Err(error) => {
-                println!("Failed to read {} with error {}", material.path, error);
-                DynamicImage::ImageRgba8(ImageBuffer::from_pixel(
-                    4,
-                    4,
-                    Rgba([255u8, 1u8, 255u8, 0u8]),
-                ))
+                warn(format!(
+                    "Failed to read {} with error {}",
+                    material.path, error
+                ));
+                placeholder = true;
+                let size = if self.placeholder_textures { 64 } else { 4 };
+                Arc::new(DynamicImage::ImageRgba8(placeholder_texture(
+                    &material.path,
+                    size,
+                    self.placeholder_textures,
+                )))
             }
         };
-        let mut png_buffer: Vec<u8> = Vec::new();
-        img.write_to(&mut Cursor::new(&mut png_buffer), image::ImageFormat::Png)
-            .expect("Failed to write PNG");
-
-        pad_align(binary_data);
-        let texture_data_start = binary_data.len();
-        binary_data.put_slice(&png_buffer);
-        pad_align(binary_data);
-
-        let buffer_index = Index::new(root.buffer_views.len() as u32);
-        root.buffer_views.push(buffer::View {
-            name: Some(format!(
-                "{}_material_{}_image_buffer",
-                name_prefix, material_id
-            )),
-            buffer: Index::new(0),
-            byte_length: USize64::from(png_buffer.len()),
-            byte_offset: Some(USize64::from(texture_data_start)),
-            byte_stride: None,
-            extensions: Default::default(),
-            extras: Default::default(),
-            target: None,
-        });
+        let texture_index = push_texture(
+            root,
+            binary_data,
+            &format!("{}_material_{}", name_prefix, material_id),
+            &img,
+            self.sampler,
+            &self.embedded_images,
+            material.alpha_enabled || material.alpha_test.is_some(),
+        );
 
-        let image_index = Index::new(root.images.len() as u32);
-        root.images.push(gltf_json::Image {
-            name: Some(format!("{}_material_{}_image", name_prefix, material_id)),
-            buffer_view: Some(buffer_index),
-            mime_type: Some(gltf_json::image::MimeType("image/png".into())),
-            uri: None,
-            extensions: None,
-            extras: Default::default(),
-        });
+        let glass = self
+            .glass_materials
+            .as_ref()
+            .filter(|rules| rules.matches(material));
+        if glass.is_some() {
+            for extension in ["KHR_materials_transmission", "KHR_materials_ior"] {
+                if !root.extensions_used.iter().any(|used| used == extension) {
+                    root.extensions_used.push(extension.to_string());
+                }
+            }
+        }
 
-        let texture_index = Index::new(root.textures.len() as u32);
-        root.textures.push(texture::Texture {
-            name: Some(format!("{}_material_{}_texture", name_prefix, material_id)),
-            sampler: Some(self.sampler),
-            source: image_index,
-            extensions: None,
-            extras: Default::default(),
-        });
+        let mut extras = json!({});
+        if placeholder && self.placeholder_textures {
+            extras["placeholder_texture"] = json!(material.path);
+        }
+        self.engine_hints.add_material_extras(&mut extras, material);
 
         let material_index = Index::new(root.materials.len() as u32);
         root.materials.push(material::Material {
@@ -183,7 +519,9 @@ impl ObjectList {
                     extras: Default::default(),
                 }),
                 metallic_factor: material::StrengthFactor(0.0),
-                roughness_factor: material::StrengthFactor(1.0),
+                roughness_factor: material::StrengthFactor(
+                    glass.map_or(1.0, |rules| rules.roughness),
+                ),
                 metallic_roughness_texture: None,
                 extensions: None,
                 extras: Default::default(),
@@ -192,8 +530,22 @@ impl ObjectList {
             occlusion_texture: None,
             emissive_texture: None,
             emissive_factor: material::EmissiveFactor([0.0, 0.0, 0.0]),
-            extensions: None,
-            extras: Default::default(),
+            extensions: glass.map(|rules| gltf_json::extensions::material::Material {
+                transmission: Some(gltf_json::extensions::material::Transmission {
+                    transmission_factor: gltf_json::extensions::material::TransmissionFactor(
+                        rules.transmission,
+                    ),
+                    transmission_texture: None,
+                    extras: Default::default(),
+                }),
+                ior: Some(gltf_json::extensions::material::Ior {
+                    ior: gltf_json::extensions::material::IndexOfRefraction(rules.ior),
+                    extras: Default::default(),
+                }),
+                ..Default::default()
+            }),
+            extras: (extras != json!({}))
+                .then(|| RawValue::from_string(extras.to_string()).unwrap()),
         });
         Some(material_index)
     }
@@ -0,0 +1,215 @@
+use glam::{Mat4, Quat, Vec3};
+use rose_file_lib::{
+    files::{zmd::Bone, zmo, zms::VertexFormat, ZMD, ZMO, ZMS},
+    utils::Vector3,
+};
+
+fn bone_translation(bone: &Bone) -> Vec3 {
+    Vec3::new(bone.position.x, bone.position.z, -bone.position.y) / 100.0
+}
+
+fn bone_rotation(bone: &Bone) -> Quat {
+    Quat::from_xyzw(
+        bone.rotation.x,
+        bone.rotation.z,
+        -bone.rotation.y,
+        bone.rotation.w,
+    )
+    .normalize()
+}
+
+fn world_transforms(zmd: &ZMD, local_transforms: &[Mat4]) -> Vec<Mat4> {
+    fn resolve(
+        zmd: &ZMD,
+        local_transforms: &[Mat4],
+        world_transforms: &mut [Option<Mat4>],
+        bone_index: usize,
+        depth: usize,
+    ) -> Mat4 {
+        if let Some(transform) = world_transforms[bone_index] {
+            return transform;
+        }
+
+        let parent = zmd.bones[bone_index].parent as usize;
+        let transform =
+            if parent == bone_index || parent >= zmd.bones.len() || depth > zmd.bones.len() {
+                local_transforms[bone_index]
+            } else {
+                resolve(zmd, local_transforms, world_transforms, parent, depth + 1)
+                    * local_transforms[bone_index]
+            };
+
+        world_transforms[bone_index] = Some(transform);
+        transform
+    }
+
+    let mut transforms = vec![None; zmd.bones.len()];
+    (0..zmd.bones.len())
+        .map(|bone_index| resolve(zmd, local_transforms, &mut transforms, bone_index, 0))
+        .collect()
+}
+
+/// Calculates the glTF space world transform of every bone in the skeleton bind pose.
+pub fn bind_pose_transforms(zmd: &ZMD) -> Vec<Mat4> {
+    let local_transforms: Vec<Mat4> = zmd
+        .bones
+        .iter()
+        .map(|bone| Mat4::from_rotation_translation(bone_rotation(bone), bone_translation(bone)))
+        .collect();
+    world_transforms(zmd, &local_transforms)
+}
+
+/// Calculates the glTF space world transform of every bone in the skeleton when
+/// posed at the given frame of the motion.
+pub fn posed_transforms(zmd: &ZMD, zmo: &ZMO, frame: u32) -> Vec<Mat4> {
+    let mut translations: Vec<Vec3> = zmd.bones.iter().map(bone_translation).collect();
+    let mut rotations: Vec<Quat> = zmd.bones.iter().map(bone_rotation).collect();
+    let mut scales = vec![1.0f32; zmd.bones.len()];
+    let frame = frame.min(zmo.frames.saturating_sub(1)) as usize;
+
+    for channel in zmo.channels.iter() {
+        let bone_index = channel.index as usize;
+        if bone_index >= zmd.bones.len() {
+            continue;
+        }
+
+        match &channel.frames {
+            zmo::ChannelData::Position(positions) => {
+                if let Some(position) = positions.get(frame) {
+                    translations[bone_index] =
+                        Vec3::new(position.x, position.z, -position.y) / 100.0;
+                }
+            }
+            zmo::ChannelData::Rotation(rotations_data) => {
+                if let Some(rotation) = rotations_data.get(frame) {
+                    rotations[bone_index] =
+                        Quat::from_xyzw(rotation.x, rotation.z, -rotation.y, rotation.w)
+                            .normalize();
+                }
+            }
+            zmo::ChannelData::Scale(scales_data) => {
+                if let Some(scale) = scales_data.get(frame) {
+                    scales[bone_index] = *scale;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let local_transforms: Vec<Mat4> = (0..zmd.bones.len())
+        .map(|bone_index| {
+            Mat4::from_scale_rotation_translation(
+                Vec3::splat(scales[bone_index]),
+                rotations[bone_index],
+                translations[bone_index],
+            )
+        })
+        .collect();
+    world_transforms(zmd, &local_transforms)
+}
+
+/// Applies the skinning of a mesh for the given frame of a motion, returning a
+/// copy of the mesh without any bone data.
+pub fn bake_pose(zms: &ZMS, zmd: &ZMD, zmo: &ZMO, frame: u32) -> anyhow::Result<ZMS> {
+    let bind_pose = bind_pose_transforms(zmd);
+    let posed = posed_transforms(zmd, zmo, frame);
+    let skinning: Vec<Mat4> = posed
+        .iter()
+        .zip(bind_pose.iter())
+        .map(|(posed, bind)| *posed * bind.inverse())
+        .collect();
+
+    let mut baked = zms.clone();
+    baked.format &= !(VertexFormat::BoneWeight as i32 | VertexFormat::BoneIndex as i32);
+    baked.bones.clear();
+
+    if !zms.bones_enabled() {
+        return Ok(baked);
+    }
+
+    for (vertex, baked_vertex) in zms.vertices.iter().zip(baked.vertices.iter_mut()) {
+        baked_vertex.bone_weights = Default::default();
+        baked_vertex.bone_indices = Default::default();
+
+        let mut transform = Mat4::ZERO;
+        let weights = [
+            (vertex.bone_weights.x, vertex.bone_indices.x),
+            (vertex.bone_weights.y, vertex.bone_indices.y),
+            (vertex.bone_weights.z, vertex.bone_indices.z),
+            (vertex.bone_weights.w, vertex.bone_indices.w),
+        ];
+        for (weight, palette_index) in weights {
+            if weight == 0.0 {
+                continue;
+            }
+
+            let bone_index = zms
+                .bones
+                .get(palette_index as usize)
+                .map(|bone_index| *bone_index as usize)
+                .filter(|bone_index| *bone_index < skinning.len())
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Mesh bone palette index {} does not map to a skeleton bone",
+                        palette_index
+                    )
+                })?;
+            transform += skinning[bone_index] * weight;
+        }
+
+        let position = transform.transform_point3(Vec3::new(
+            vertex.position.x,
+            vertex.position.z,
+            -vertex.position.y,
+        ));
+        baked_vertex.position = Vector3 {
+            x: position.x,
+            y: -position.z,
+            z: position.y,
+        };
+
+        let normal = transform
+            .transform_vector3(Vec3::new(
+                vertex.normal.x,
+                vertex.normal.z,
+                -vertex.normal.y,
+            ))
+            .normalize_or_zero();
+        baked_vertex.normal = Vector3 {
+            x: normal.x,
+            y: -normal.z,
+            z: normal.y,
+        };
+
+        let tangent = transform
+            .transform_vector3(Vec3::new(
+                vertex.tangent.x,
+                vertex.tangent.z,
+                -vertex.tangent.y,
+            ))
+            .normalize_or_zero();
+        baked_vertex.tangent = Vector3 {
+            x: tangent.x,
+            y: -tangent.z,
+            z: tangent.y,
+        };
+    }
+
+    if let Some(first) = baked.vertices.first() {
+        let mut min_pos = first.position;
+        let mut max_pos = first.position;
+        for vertex in baked.vertices.iter() {
+            min_pos.x = min_pos.x.min(vertex.position.x);
+            min_pos.y = min_pos.y.min(vertex.position.y);
+            min_pos.z = min_pos.z.min(vertex.position.z);
+
+            max_pos.x = max_pos.x.max(vertex.position.x);
+            max_pos.y = max_pos.y.max(vertex.position.y);
+            max_pos.z = max_pos.z.max(vertex.position.z);
+        }
+        baked.bounding_box.min = min_pos;
+        baked.bounding_box.max = max_pos;
+    }
+
+    Ok(baked)
+}
@@ -0,0 +1,120 @@
+//! Changes made to a converted glTF before it is saved, to prepare exports
+//! for engines with strict importers.
+use gltf_json::{extras::Extras, mesh, validation::Checked, Root};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PostProcessOptions {
+    /// Remove the extras of every object, for importers which reject or
+    /// choke on unknown properties.
+    pub strip_extras: bool,
+
+    /// Remove the `TLM_` extras read by The Lightmapper Blender addon, and
+    /// keep the rest.
+    pub strip_lightmapper_extras: bool,
+
+    /// Remove every animation.
+    pub strip_animations: bool,
+
+    /// Remove every skin along with the joints and weights of skinned meshes,
+    /// which are left in their bind pose.
+    pub strip_skins: bool,
+
+    /// Added to the start of the name of every node, e.g. to avoid name
+    /// clashes when several exports are imported into one scene.
+    pub node_name_prefix: Option<String>,
+}
+
+fn strip_extras(root: &mut Root) {
+    root.extras = None;
+    root.asset.extras = None;
+    root.accessors.iter_mut().for_each(|x| x.extras = None);
+    root.buffers.iter_mut().for_each(|x| x.extras = None);
+    root.buffer_views.iter_mut().for_each(|x| x.extras = None);
+    root.cameras.iter_mut().for_each(|x| x.extras = None);
+    root.images.iter_mut().for_each(|x| x.extras = None);
+    root.materials.iter_mut().for_each(|x| x.extras = None);
+    root.nodes.iter_mut().for_each(|x| x.extras = None);
+    root.samplers.iter_mut().for_each(|x| x.extras = None);
+    root.scenes.iter_mut().for_each(|x| x.extras = None);
+    root.skins.iter_mut().for_each(|x| x.extras = None);
+    root.textures.iter_mut().for_each(|x| x.extras = None);
+    for animation in root.animations.iter_mut() {
+        animation.extras = None;
+        animation.channels.iter_mut().for_each(|x| x.extras = None);
+        animation.samplers.iter_mut().for_each(|x| x.extras = None);
+    }
+    for mesh in root.meshes.iter_mut() {
+        mesh.extras = None;
+        mesh.primitives.iter_mut().for_each(|x| x.extras = None);
+    }
+}
+
+/// Removes the `TLM_` properties of `extras`, dropping it when nothing else
+/// is left
+fn strip_lightmapper_properties(extras: &mut Extras) {
+    let Some(raw) = extras.as_ref() else {
+        return;
+    };
+    let Ok(mut properties) = serde_json::from_str::<Map<String, Value>>(raw.get()) else {
+        return;
+    };
+    if !properties.keys().any(|key| key.starts_with("TLM_")) {
+        return;
+    }
+
+    properties.retain(|key, _| !key.starts_with("TLM_"));
+    *extras = if properties.is_empty() {
+        None
+    } else {
+        serde_json::value::to_raw_value(&properties).ok()
+    };
+}
+
+fn strip_skins(root: &mut Root) {
+    root.skins.clear();
+    for node in root.nodes.iter_mut() {
+        node.skin = None;
+    }
+    for primitive in root
+        .meshes
+        .iter_mut()
+        .flat_map(|mesh| mesh.primitives.iter_mut())
+    {
+        primitive.attributes.retain(|semantic, _| {
+            !matches!(
+                semantic,
+                Checked::Valid(mesh::Semantic::Joints(_) | mesh::Semantic::Weights(_))
+            )
+        });
+    }
+}
+
+/// Applies `options` to a converted glTF
+pub fn post_process(root: &mut Root, options: &PostProcessOptions) {
+    if options.strip_animations {
+        root.animations.clear();
+    }
+
+    if options.strip_skins {
+        strip_skins(root);
+    }
+
+    if options.strip_extras {
+        strip_extras(root);
+    } else if options.strip_lightmapper_extras {
+        root.scenes
+            .iter_mut()
+            .for_each(|x| strip_lightmapper_properties(&mut x.extras));
+        root.nodes
+            .iter_mut()
+            .for_each(|x| strip_lightmapper_properties(&mut x.extras));
+    }
+
+    if let Some(prefix) = options.node_name_prefix.as_ref() {
+        for name in root.nodes.iter_mut().filter_map(|node| node.name.as_mut()) {
+            name.insert_str(0, prefix);
+        }
+    }
+}
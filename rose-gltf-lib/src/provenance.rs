@@ -0,0 +1,66 @@
+//! Recording which ROSE files a glTF was converted from, so a file found
+//! later can be traced back to its exact source data.
+use gltf_json::{scene, Root};
+use serde_json::{json, Map, Value};
+
+/// Parses extras which are a JSON object, other extras are replaced.
+pub(crate) fn extras_object(extras: &gltf_json::Extras) -> Map<String, Value> {
+    extras
+        .as_ref()
+        .and_then(|extras| serde_json::from_str::<Value>(extras.get()).ok())
+        .and_then(|extras| match extras {
+            Value::Object(object) => Some(object),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn set_extras(extras: &mut gltf_json::Extras, object: Map<String, Value>) {
+    *extras = serde_json::value::to_raw_value(&Value::Object(object)).ok();
+}
+
+/// Sets the copyright and generator of the asset, the generator defaults to
+/// the converter name and version.
+pub(crate) fn set_asset_info(root: &mut Root, copyright: Option<&str>, generator: Option<&str>) {
+    root.asset.copyright = copyright.map(str::to_string);
+    root.asset.generator = Some(match generator {
+        Some(generator) => generator.to_string(),
+        None => format!("rose-gltf {}", env!("CARGO_PKG_VERSION")),
+    });
+}
+
+/// Sets `rose_provenance` in the asset extras to the converter version and
+/// the paths of the source files.
+pub(crate) fn add_asset_provenance(root: &mut Root, sources: &[String], zone_ids: &[usize]) {
+    let mut provenance = json!({
+        "converter": "rose-gltf",
+        "version": env!("CARGO_PKG_VERSION"),
+        "sources": sources,
+    });
+    if !zone_ids.is_empty() {
+        provenance["zone_ids"] = json!(zone_ids);
+    }
+
+    let mut extras = extras_object(&root.asset.extras);
+    extras.insert("rose_provenance".to_string(), provenance);
+    set_extras(&mut root.asset.extras, extras);
+}
+
+/// Adds the fields of `source` to `rose_source` in the extras of each node,
+/// fields which the node already has are kept.
+pub(crate) fn add_node_provenance(nodes: &mut [scene::Node], source: &Map<String, Value>) {
+    for node in nodes.iter_mut() {
+        let mut extras = extras_object(&node.extras);
+        let rose_source = extras
+            .entry("rose_source")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(rose_source) = rose_source {
+            for (key, value) in source.iter() {
+                rose_source
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+        set_extras(&mut node.extras, extras);
+    }
+}
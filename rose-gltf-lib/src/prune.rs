@@ -0,0 +1,313 @@
+//! Removing objects from a glTF which nothing references, such as the data of
+//! filtered blocks or of objects which failed to convert.
+use std::{io, ops::Range};
+
+use anyhow::Context;
+use bytes::BufMut;
+use gltf_json::{material, mesh, scene, texture, validation::USize64, Index, Root};
+
+use crate::{dedup::share_duplicates, pad_align, spill::BinaryData};
+
+/// Removes the items which are not `used`, returning the new index of each
+/// item which was kept.
+fn retain_used<T>(items: &mut Vec<T>, used: &[bool]) -> Vec<Option<u32>> {
+    let mut new_indices = Vec::with_capacity(items.len());
+    let mut next_index = 0;
+    for &used in used.iter() {
+        new_indices.push(used.then(|| {
+            next_index += 1;
+            next_index - 1
+        }));
+    }
+
+    let mut index = 0;
+    items.retain(|_| {
+        index += 1;
+        used[index - 1]
+    });
+    new_indices
+}
+
+fn remap<T>(index: Index<T>, new_indices: &[Option<u32>]) -> Index<T> {
+    Index::new(new_indices[index.value()].expect("Used index was removed"))
+}
+
+fn primitive_accessors(primitive: &mut mesh::Primitive) -> Vec<&mut Index<gltf_json::Accessor>> {
+    let mut accessors = primitive.attributes.values_mut().collect::<Vec<_>>();
+    accessors.extend(primitive.indices.as_mut());
+    for target in primitive.targets.iter_mut().flatten() {
+        accessors.extend(target.positions.as_mut());
+        accessors.extend(target.normals.as_mut());
+        accessors.extend(target.tangents.as_mut());
+    }
+    accessors
+}
+
+/// Replaces each accessor used by the EXT_mesh_gpu_instancing extension of a
+/// node with the result of `f`, which is stored as JSON as the extension is
+/// not known to gltf_json
+pub(crate) fn map_instance_accessors(node: &mut scene::Node, mut f: impl FnMut(usize) -> usize) {
+    let attributes = node
+        .extensions
+        .as_mut()
+        .and_then(|extensions| extensions.others.get_mut("EXT_mesh_gpu_instancing"))
+        .and_then(|instancing| instancing.get_mut("attributes"))
+        .and_then(|attributes| attributes.as_object_mut());
+    for accessor in attributes
+        .into_iter()
+        .flat_map(|attributes| attributes.values_mut())
+    {
+        if let Some(index) = accessor.as_u64() {
+            *accessor = (f(index as usize) as u64).into();
+        }
+    }
+}
+
+/// The textures used by a material
+pub(crate) fn material_textures(
+    material: &mut material::Material,
+) -> Vec<&mut Index<texture::Texture>> {
+    let pbr = &mut material.pbr_metallic_roughness;
+    let mut textures = Vec::new();
+    textures.extend(pbr.base_color_texture.as_mut().map(|info| &mut info.index));
+    textures.extend(
+        pbr.metallic_roughness_texture
+            .as_mut()
+            .map(|info| &mut info.index),
+    );
+    textures.extend(material.normal_texture.as_mut().map(|info| &mut info.index));
+    textures.extend(
+        material
+            .occlusion_texture
+            .as_mut()
+            .map(|info| &mut info.index),
+    );
+    textures.extend(
+        material
+            .emissive_texture
+            .as_mut()
+            .map(|info| &mut info.index),
+    );
+    textures.extend(
+        material
+            .extensions
+            .as_mut()
+            .and_then(|extensions| extensions.transmission.as_mut())
+            .and_then(|transmission| transmission.transmission_texture.as_mut())
+            .map(|info| &mut info.index),
+    );
+    textures
+}
+
+fn prune_materials(root: &mut Root) {
+    let mut used = vec![false; root.materials.len()];
+    for primitive in root.meshes.iter().flat_map(|mesh| mesh.primitives.iter()) {
+        if let Some(material) = primitive.material {
+            used[material.value()] = true;
+        }
+        if let Some(variants) = primitive
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.khr_materials_variants.as_ref())
+        {
+            for mapping in variants.mappings.iter() {
+                used[mapping.material as usize] = true;
+            }
+        }
+    }
+
+    let new_indices = retain_used(&mut root.materials, &used);
+    for primitive in root
+        .meshes
+        .iter_mut()
+        .flat_map(|mesh| mesh.primitives.iter_mut())
+    {
+        primitive.material = primitive
+            .material
+            .map(|material| remap(material, &new_indices));
+        if let Some(variants) = primitive
+            .extensions
+            .as_mut()
+            .and_then(|extensions| extensions.khr_materials_variants.as_mut())
+        {
+            for mapping in variants.mappings.iter_mut() {
+                mapping.material =
+                    new_indices[mapping.material as usize].expect("Used index was removed");
+            }
+        }
+    }
+}
+
+fn prune_textures(root: &mut Root) {
+    let mut used = vec![false; root.textures.len()];
+    for material in root.materials.iter_mut() {
+        for texture in material_textures(material) {
+            used[texture.value()] = true;
+        }
+    }
+
+    let new_indices = retain_used(&mut root.textures, &used);
+    for material in root.materials.iter_mut() {
+        for texture in material_textures(material) {
+            *texture = remap(*texture, &new_indices);
+        }
+    }
+}
+
+fn prune_samplers_and_images(root: &mut Root) {
+    let mut used_samplers = vec![false; root.samplers.len()];
+    let mut used_images = vec![false; root.images.len()];
+    for texture in root.textures.iter() {
+        if let Some(sampler) = texture.sampler {
+            used_samplers[sampler.value()] = true;
+        }
+        used_images[texture.source.value()] = true;
+    }
+
+    let new_samplers = retain_used(&mut root.samplers, &used_samplers);
+    let new_images = retain_used(&mut root.images, &used_images);
+    for texture in root.textures.iter_mut() {
+        texture.sampler = texture.sampler.map(|sampler| remap(sampler, &new_samplers));
+        texture.source = remap(texture.source, &new_images);
+    }
+}
+
+fn prune_accessors(root: &mut Root) {
+    let mut used = vec![false; root.accessors.len()];
+    for mesh in root.meshes.iter_mut() {
+        for primitive in mesh.primitives.iter_mut() {
+            for accessor in primitive_accessors(primitive) {
+                used[accessor.value()] = true;
+            }
+        }
+    }
+    for node in root.nodes.iter_mut() {
+        map_instance_accessors(node, |accessor| {
+            used[accessor] = true;
+            accessor
+        });
+    }
+    for skin in root.skins.iter() {
+        if let Some(accessor) = skin.inverse_bind_matrices {
+            used[accessor.value()] = true;
+        }
+    }
+    for animation in root.animations.iter() {
+        for sampler in animation.samplers.iter() {
+            used[sampler.input.value()] = true;
+            used[sampler.output.value()] = true;
+        }
+    }
+
+    let new_indices = retain_used(&mut root.accessors, &used);
+    for mesh in root.meshes.iter_mut() {
+        for primitive in mesh.primitives.iter_mut() {
+            for accessor in primitive_accessors(primitive) {
+                *accessor = remap(*accessor, &new_indices);
+            }
+        }
+    }
+    for node in root.nodes.iter_mut() {
+        map_instance_accessors(node, |accessor| {
+            new_indices[accessor].expect("Used index was removed") as usize
+        });
+    }
+    for skin in root.skins.iter_mut() {
+        skin.inverse_bind_matrices = skin
+            .inverse_bind_matrices
+            .map(|accessor| remap(accessor, &new_indices));
+    }
+    for animation in root.animations.iter_mut() {
+        for sampler in animation.samplers.iter_mut() {
+            sampler.input = remap(sampler.input, &new_indices);
+            sampler.output = remap(sampler.output, &new_indices);
+        }
+    }
+}
+
+/// Removes unused buffer views, which must all be in one buffer, and moves
+/// the rest next to each other. Returns the range of the old buffer each
+/// kept view had, for `compact_buffer`, or None if every view is used.
+fn prune_buffer_views(root: &mut Root) -> Option<Vec<Range<usize>>> {
+    let mut used = vec![false; root.buffer_views.len()];
+    for accessor in root.accessors.iter() {
+        if let Some(view) = accessor.buffer_view {
+            used[view.value()] = true;
+        }
+        if let Some(sparse) = accessor.sparse.as_ref() {
+            used[sparse.indices.buffer_view.value()] = true;
+            used[sparse.values.buffer_view.value()] = true;
+        }
+    }
+    for image in root.images.iter() {
+        if let Some(view) = image.buffer_view {
+            used[view.value()] = true;
+        }
+    }
+    if used.iter().all(|&used| used) {
+        return None;
+    }
+
+    let new_indices = retain_used(&mut root.buffer_views, &used);
+    let mut ranges = Vec::with_capacity(root.buffer_views.len());
+    let mut compacted_len = 0usize;
+    for view in root.buffer_views.iter_mut() {
+        let start = view.byte_offset.map_or(0, |offset| offset.0 as usize);
+        ranges.push(start..start + view.byte_length.0 as usize);
+
+        // Aligned as by `pad_align`
+        compacted_len = compacted_len.next_multiple_of(4);
+        view.byte_offset = Some(USize64::from(compacted_len));
+        compacted_len += view.byte_length.0 as usize;
+    }
+
+    for accessor in root.accessors.iter_mut() {
+        accessor.buffer_view = accessor.buffer_view.map(|view| remap(view, &new_indices));
+        if let Some(sparse) = accessor.sparse.as_mut() {
+            sparse.indices.buffer_view = remap(sparse.indices.buffer_view, &new_indices);
+            sparse.values.buffer_view = remap(sparse.values.buffer_view, &new_indices);
+        }
+    }
+    for image in root.images.iter_mut() {
+        image.buffer_view = image.buffer_view.map(|view| remap(view, &new_indices));
+    }
+    Some(ranges)
+}
+
+/// Copies the `ranges` of `binary_data` one after another, at the offsets
+/// given to their buffer views by `prune_buffer_views`. A spilling buffer is
+/// compacted into another spilling buffer, so the data is never in memory
+/// twice.
+fn compact_buffer(binary_data: &BinaryData, ranges: &[Range<usize>]) -> io::Result<BinaryData> {
+    let mut compacted = binary_data.new_like();
+    for range in ranges.iter() {
+        pad_align(&mut compacted);
+        compacted.put_slice(&binary_data.read(range.clone())?.unwrap_or_default());
+    }
+    Ok(compacted)
+}
+
+/// Shares duplicate images, textures and materials, then removes materials,
+/// textures, samplers, images and accessors which are not referenced.
+fn prune_references(root: &mut Root, binary_data: &BinaryData) -> io::Result<()> {
+    share_duplicates(root, binary_data)?;
+    prune_materials(root);
+    prune_textures(root);
+    prune_samplers_and_images(root);
+    prune_accessors(root);
+    Ok(())
+}
+
+/// Shares duplicate images, textures and materials, then removes materials,
+/// textures, samplers, images, accessors and buffer views which are not
+/// referenced, and compacts `binary_data` to only contain the buffer views
+/// which are left.
+pub(crate) fn prune_unused(root: &mut Root, binary_data: BinaryData) -> anyhow::Result<BinaryData> {
+    prune_references(root, &binary_data).context("Failed to read temporary file")?;
+    match prune_buffer_views(root) {
+        Some(ranges) => {
+            compact_buffer(&binary_data, &ranges).context("Failed to read temporary file")
+        }
+        None => Ok(binary_data),
+    }
+}
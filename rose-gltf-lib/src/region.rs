@@ -0,0 +1,140 @@
+//! Exporting only part of a zone, such as the area within a radius of a quest
+//! or dungeon entrance, or one floor of a dungeon whose floors are stacked
+//! vertically.
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::zone::{block_translation, BLOCK_SIZE};
+
+/// ROSE world coordinates in meters of the origin of the glTF, the center of
+/// the 64x64 block map
+const WORLD_ORIGIN: f32 = 5200.0;
+
+/// Size of a terrain tile in glTF units, 16 per block
+const TILE_SIZE: f32 = BLOCK_SIZE / 16.0;
+
+/// Limits a zone to the terrain and objects within `radius` of `center`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RadiusFilter {
+    /// ROSE world coordinates in meters, x increasing east and y increasing
+    /// north, e.g. 5200,5200 is the center of the block map
+    pub center: [f32; 2],
+
+    /// Radius in meters
+    pub radius: f32,
+}
+
+impl RadiusFilter {
+    /// The center in the glTF x,z plane
+    fn gltf_center(&self) -> Vec2 {
+        Vec2::new(self.center[0] - WORLD_ORIGIN, WORLD_ORIGIN - self.center[1])
+    }
+
+    /// Whether a glTF translation is within the radius, ignoring its height
+    pub(crate) fn contains(&self, [x, _, z]: [f32; 3]) -> bool {
+        Vec2::new(x, z).distance(self.gltf_center()) <= self.radius
+    }
+
+    /// Whether any of the rectangle between two corners in the glTF x,z
+    /// plane is within the radius
+    pub(crate) fn intersects(&self, corner_a: Vec2, corner_b: Vec2) -> bool {
+        let center = self.gltf_center();
+        let closest = center.clamp(corner_a.min(corner_b), corner_a.max(corner_b));
+        closest.distance(center) <= self.radius
+    }
+
+    /// Whether any of the terrain of a block is within the radius
+    pub(crate) fn intersects_block(&self, block_x: i32, block_y: i32) -> bool {
+        let [x, _, z] = block_translation(block_x, block_y);
+        let min = Vec2::new(x, z);
+        self.intersects(min, min + Vec2::splat(BLOCK_SIZE))
+    }
+
+    /// The tiles of a block which are entirely outside the radius, indexed by
+    /// `[tile_y][tile_x]` like `terrain_holes`
+    pub(crate) fn tiles_outside(&self, block_x: i32, block_y: i32) -> [[bool; 16]; 16] {
+        let [x, _, z] = block_translation(block_x, block_y);
+        std::array::from_fn(|tile_y| {
+            std::array::from_fn(|tile_x| {
+                let min = Vec2::new(x, z) + Vec2::new(tile_x as f32, tile_y as f32) * TILE_SIZE;
+                !self.intersects(min, min + Vec2::splat(TILE_SIZE))
+            })
+        })
+    }
+}
+
+/// Limits the objects of a zone to those whose origin is within a range of
+/// world heights in meters, either bound can be left open
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeightRange {
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl HeightRange {
+    /// Whether the height of a glTF translation is within the range
+    pub(crate) fn contains(&self, [_, y, _]: [f32; 3]) -> bool {
+        self.min.is_none_or(|min| y >= min) && self.max.is_none_or(|max| y <= max)
+    }
+}
+
+/// A kind of content of a zone, for `ZoneContentFilter`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ZoneContent {
+    /// The heightmap of each block and its materials
+    Terrain,
+
+    /// Buildings, the objects of the cnst ZSC
+    Cnst,
+
+    /// Decorations, the objects of the deco ZSC
+    Deco,
+
+    Ocean,
+
+    /// Water planes, such as rivers and ponds
+    Water,
+
+    /// Effect markers
+    Effects,
+
+    /// Sound emitters
+    Sounds,
+
+    /// Vegetation planted by `RoseGltfConvOptions::vegetation`
+    Vegetation,
+
+    /// Monster spawn areas, added by `RoseGltfConvOptions::spawn_volumes`
+    Spawns,
+
+    /// The directional light of the sun and the lights of light container
+    /// dummy points of objects
+    Lights,
+
+    /// Only the directional light of the sun, which is also chosen by `Lights`
+    Sun,
+}
+
+/// Which kinds of content of a zone are exported, e.g. only the buildings
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZoneContentFilter {
+    /// If not empty, only these kinds of content are exported
+    pub include: Vec<ZoneContent>,
+
+    /// These kinds of content are never exported
+    pub exclude: Vec<ZoneContent>,
+}
+
+impl ZoneContentFilter {
+    pub(crate) fn includes(&self, content: ZoneContent) -> bool {
+        // The sun is one of the lights
+        let kinds: &[ZoneContent] = match content {
+            ZoneContent::Sun => &[ZoneContent::Sun, ZoneContent::Lights],
+            _ => &[content],
+        };
+        (self.include.is_empty() || kinds.iter().any(|kind| self.include.contains(kind)))
+            && !kinds.iter().any(|kind| self.exclude.contains(kind))
+    }
+}
@@ -0,0 +1,90 @@
+//! Warnings from conversions, which are printed unless the caller collects
+//! them to report in another form, such as the JSON report of the CLI, and
+//! the time spent in each phase of conversions.
+use std::{cell::RefCell, collections::BTreeMap, time::Instant};
+
+use serde::Serialize;
+
+thread_local! {
+    static COLLECTED_WARNINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+    static COLLECTED_TIMINGS: RefCell<Option<BTreeMap<String, PhaseTiming>>> =
+        const { RefCell::new(None) };
+}
+
+/// Prints a warning, or adds it to the warnings being collected on this
+/// thread by `collect_warnings`.
+pub(crate) fn warn(message: String) {
+    COLLECTED_WARNINGS.with_borrow_mut(|warnings| match warnings {
+        Some(warnings) => warnings.push(message),
+        None => println!("{}", message),
+    });
+}
+
+/// Prints a progress message. While collecting warnings it is printed to
+/// stderr instead, so stdout is left for the caller's report.
+pub(crate) fn info(message: String) {
+    COLLECTED_WARNINGS.with_borrow(|warnings| match warnings {
+        Some(_) => eprintln!("{}", message),
+        None => println!("{}", message),
+    });
+}
+
+/// Runs `f` and returns the warnings of conversions it ran on this thread,
+/// instead of printing them.
+pub fn collect_warnings<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let previous = COLLECTED_WARNINGS.replace(Some(Vec::new()));
+    let result = f();
+    let warnings = COLLECTED_WARNINGS.replace(previous).unwrap_or_default();
+    (result, warnings)
+}
+
+/// Time spent in a phase of conversions, such as building meshes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct PhaseTiming {
+    /// Number of times the phase ran
+    pub count: usize,
+    pub seconds: f64,
+}
+
+/// Adds the time from its creation to when it is dropped to a phase, while
+/// timings are collected on this thread.
+pub(crate) struct PhaseTimer {
+    phase: &'static str,
+    start: Option<Instant>,
+}
+
+/// Times a phase until the returned timer is dropped. Phases can run inside
+/// other phases, e.g. decoding images while generating terrain materials, so
+/// their times do not add up to the time of the conversion.
+pub(crate) fn time_phase(phase: &'static str) -> PhaseTimer {
+    let collecting = COLLECTED_TIMINGS.with_borrow(|timings| timings.is_some());
+    PhaseTimer {
+        phase,
+        start: collecting.then(Instant::now),
+    }
+}
+
+impl Drop for PhaseTimer {
+    fn drop(&mut self) {
+        let Some(start) = self.start else {
+            return;
+        };
+        let seconds = start.elapsed().as_secs_f64();
+        COLLECTED_TIMINGS.with_borrow_mut(|timings| {
+            if let Some(timings) = timings {
+                let timing = timings.entry(self.phase.to_string()).or_default();
+                timing.count += 1;
+                timing.seconds += seconds;
+            }
+        });
+    }
+}
+
+/// Runs `f` and returns the time spent in each phase of conversions it ran
+/// on this thread, by phase name.
+pub fn collect_timings<T>(f: impl FnOnce() -> T) -> (T, BTreeMap<String, PhaseTiming>) {
+    let previous = COLLECTED_TIMINGS.replace(Some(BTreeMap::new()));
+    let result = f();
+    let timings = COLLECTED_TIMINGS.replace(previous).unwrap_or_default();
+    (result, timings)
+}
@@ -1,6 +1,13 @@
-use bytes::{BufMut, BytesMut};
+use std::collections::HashSet;
+
+use bytes::BufMut;
 use glam::{Mat4, Quat, Vec3};
-use rose_file_lib::files::{zmd::Bone, ZMD, ZMO};
+use rose_file_lib::files::{
+    zmd::Bone,
+    zmo::{Channel, ChannelData, ChannelType},
+    ZMD, ZMO,
+};
+use serde::{Deserialize, Serialize};
 
 use gltf_json::{
     accessor, buffer,
@@ -12,8 +19,45 @@ use gltf_json::{
 use crate::{
     animation::{load_animation, GetAnimationChannelNode},
     pad_align,
+    report::warn,
+    spill::BinaryData,
 };
 
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BoneNaming {
+    /// Name joints after the bones of the ZMD
+    #[default]
+    Original,
+
+    /// Name joints `{skeleton}_Bone_{index}`
+    Indexed,
+}
+
+/// Strips the `.001` style suffix Blender adds to duplicate names.
+pub(crate) fn strip_duplicate_suffix(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((base, suffix))
+            if !base.is_empty()
+                && !suffix.is_empty()
+                && suffix.bytes().all(|c| c.is_ascii_digit()) =>
+        {
+            base
+        }
+        _ => name,
+    }
+}
+
+/// The index of a `dummy_N` node name, ignoring case and duplicate suffixes.
+pub(crate) fn dummy_name_index(name: &str) -> Option<u32> {
+    let name = strip_duplicate_suffix(name);
+    let prefix = name.get(..6)?;
+    if !prefix.eq_ignore_ascii_case("dummy_") {
+        return None;
+    }
+    let index = name[6..].split('_').next().unwrap_or_default();
+    Some(index.parse().unwrap_or(0))
+}
+
 fn transform_children(zmd: &ZMD, bone_transforms: &mut Vec<Mat4>, bone_index: usize) {
     for (child_id, child_bone) in zmd.bones.iter().enumerate() {
         if child_id == bone_index || child_bone.parent as usize != bone_index {
@@ -56,11 +100,58 @@ fn bone_to_node(bone: &Bone) -> (Node, glam::Mat4) {
     (node, bind_pose)
 }
 
+fn node_local_transform(node: &Node) -> Mat4 {
+    let translation = node.translation.map(Vec3::from_array).unwrap_or(Vec3::ZERO);
+    let rotation = node
+        .rotation
+        .map(|rotation| Quat::from_array(rotation.0))
+        .unwrap_or(Quat::IDENTITY);
+    let scale = node.scale.map(Vec3::from_array).unwrap_or(Vec3::ONE);
+    Mat4::from_scale_rotation_translation(scale, rotation, translation)
+}
+
+/// Checks the inverse bind matrices against the joint transforms an importer
+/// would calculate from the node hierarchy, returning the number of mismatches.
+fn verify_inverse_bind_matrices(
+    root: &gltf_json::Root,
+    joints: &[Index<Node>],
+    inverse_bind_matrices: &[Mat4],
+) -> usize {
+    let mut node_parents = vec![None; root.nodes.len()];
+    for (node_index, node) in root.nodes.iter().enumerate() {
+        for child in node.children.iter().flatten() {
+            node_parents[child.value()] = Some(node_index);
+        }
+    }
+
+    joints
+        .iter()
+        .zip(inverse_bind_matrices)
+        .filter(|(joint, inverse_bind_matrix)| {
+            let mut transform = node_local_transform(&root.nodes[joint.value()]);
+            let mut parent = node_parents[joint.value()];
+            let mut depth = 0;
+            while let Some(parent_index) = parent {
+                transform = node_local_transform(&root.nodes[parent_index]) * transform;
+                parent = node_parents[parent_index];
+
+                depth += 1;
+                if depth > root.nodes.len() {
+                    return true;
+                }
+            }
+
+            !(transform * **inverse_bind_matrix).abs_diff_eq(Mat4::IDENTITY, 1.0e-3)
+        })
+        .count()
+}
+
 pub fn load_skeleton(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
+    binary_data: &mut BinaryData,
     name: &str,
     zmd: &ZMD,
+    bone_naming: BoneNaming,
 ) -> Index<Skin> {
     let bone_node_index_start = root.nodes.len();
     let mut joints = Vec::new();
@@ -73,9 +164,23 @@ pub fn load_skeleton(
         .nodes
         .push(Index::new(root.nodes.len() as u32));
 
-    // Create nodes for each bone
+    // Create nodes for each bone, unnamed and duplicate bones get their index
+    // appended so every joint has a unique name
+    let mut bone_names = HashSet::new();
     for (bone_index, bone) in zmd.bones.iter().enumerate() {
-        let (node, bind_pose) = bone_to_node(bone);
+        let (mut node, bind_pose) = bone_to_node(bone);
+        let bone_name = match bone_naming {
+            BoneNaming::Original if bone.name.is_empty() => format!("bone_{}", bone_index),
+            BoneNaming::Original => bone.name.clone(),
+            BoneNaming::Indexed => format!("{}_Bone_{}", name, bone_index),
+        };
+        if bone_names.insert(bone_name.clone()) {
+            node.name = Some(bone_name);
+        } else {
+            let unique_name = format!("{}_{}", bone_name, bone_index);
+            bone_names.insert(unique_name.clone());
+            node.name = Some(unique_name);
+        }
 
         root.nodes.push(node);
         joints.push(Index::new(bone_node_index_start as u32 + bone_index as u32));
@@ -114,6 +219,14 @@ pub fn load_skeleton(
     transform_children(zmd, &mut bind_poses, 0);
     let inverse_bind_pose: Vec<Mat4> = bind_poses.iter().map(|x| x.inverse()).collect();
 
+    let num_mismatched_joints = verify_inverse_bind_matrices(root, &joints, &inverse_bind_pose);
+    if num_mismatched_joints > 0 {
+        warn(format!(
+            "Skeleton {}: {} inverse bind matrices do not match the exported joint hierarchy",
+            name, num_mismatched_joints
+        ));
+    }
+
     let skeleton_data_start = binary_data.len();
     for mtx in inverse_bind_pose.iter() {
         for f in mtx.to_cols_array() {
@@ -172,10 +285,48 @@ impl GetAnimationChannelNode for Index<Skin> {
 
 pub fn load_skeletal_animation(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
+    binary_data: &mut BinaryData,
     name: &str,
     skin_index: Index<Skin>,
     zmo: &ZMO,
+    quantize_rotations: bool,
 ) {
-    load_animation(root, binary_data, zmo, name, skin_index)
+    load_animation(root, binary_data, zmo, name, skin_index, quantize_rotations)
+}
+
+/// Adds a single frame `bind_pose` animation which moves every joint of the
+/// skin back to its ZMD transform.
+pub fn load_bind_pose_animation(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    skin_index: Index<Skin>,
+    zmd: &ZMD,
+    quantize_rotations: bool,
+) {
+    let mut zmo = ZMO {
+        identifier: "ZMO0002".to_string(),
+        fps: 30,
+        frames: 1,
+        channels: Vec::new(),
+    };
+    for (bone_index, bone) in zmd.bones.iter().enumerate() {
+        zmo.channels.push(Channel {
+            typ: ChannelType::Position,
+            index: bone_index as u32,
+            frames: ChannelData::Position(vec![bone.position]),
+        });
+        zmo.channels.push(Channel {
+            typ: ChannelType::Rotation,
+            index: bone_index as u32,
+            frames: ChannelData::Rotation(vec![bone.rotation]),
+        });
+    }
+    load_animation(
+        root,
+        binary_data,
+        &zmo,
+        "bind_pose",
+        skin_index,
+        quantize_rotations,
+    )
 }
@@ -0,0 +1,130 @@
+//! Finding the ZMD of character meshes and motions which were converted
+//! without one, from the NPC list or the layout of the `3ddata` directory.
+use std::path::{Path, PathBuf};
+
+use rose_file_lib::{
+    files::{CHR, ZMS, ZSC},
+    io::RoseFile,
+};
+
+use crate::assets::{normalize_asset_path, AssetLocator};
+
+const NPC_CHR_PATH: &str = "3ddata/npc/list_npc.chr";
+const NPC_ZSC_PATH: &str = "3ddata/npc/part_npc.zsc";
+
+/// Finds the ZMD animating the ZMO or skinned ZMS at `file_path`, returns
+/// None for other files or when no skeleton could be found.
+///
+/// NPCs are looked up in list_npc.chr, avatar meshes and motions use
+/// male.zmd or female.zmd, and anything else uses the only ZMD in the same
+/// directory.
+pub fn discover_skeleton(file_path: &Path, assets: &dyn AssetLocator) -> Option<PathBuf> {
+    let extension = file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let is_motion = match extension.as_str() {
+        "zmo" => true,
+        "zms" => false,
+        _ => return None,
+    };
+    if !is_motion
+        && !ZMS::from_path(file_path)
+            .ok()
+            .is_some_and(|zms| zms.bones_enabled())
+    {
+        return None;
+    }
+
+    if let Some(asset_path) = assets.asset_path(file_path) {
+        let skeleton_path = npc_skeleton(&asset_path, is_motion, assets)
+            .or_else(|| avatar_skeleton(&asset_path))
+            .and_then(|skeleton_path| assets.locate(&skeleton_path));
+        if skeleton_path.is_some() {
+            return skeleton_path;
+        }
+    }
+    sibling_skeleton(file_path)
+}
+
+/// The skeleton of the NPC in list_npc.chr which uses the motion or mesh at
+/// `asset_path`.
+fn npc_skeleton(asset_path: &str, is_motion: bool, assets: &dyn AssetLocator) -> Option<String> {
+    let chr = CHR::from_path(&assets.locate(NPC_CHR_PATH)?).ok()?;
+    let mut characters = chr.models.iter().flatten();
+
+    if is_motion {
+        return characters
+            .find(|character| {
+                character
+                    .motions
+                    .values()
+                    .any(|motion| normalize_asset_path(motion) == asset_path)
+            })
+            .map(|character| character.skeleton_path.clone());
+    }
+
+    let zsc = ZSC::from_path(&assets.locate(NPC_ZSC_PATH)?).ok()?;
+    characters
+        .find(|character| {
+            character.models.iter().any(|&model_id| {
+                zsc.models
+                    .get(model_id as usize)
+                    .and_then(|model| model.as_ref())
+                    .is_some_and(|model| {
+                        model
+                            .parts
+                            .iter()
+                            .any(|part| normalize_asset_path(&part.mesh_path) == asset_path)
+                    })
+            })
+        })
+        .map(|character| character.skeleton_path.clone())
+}
+
+/// Avatar meshes are numbered 1 for male and 2 for female, e.g.
+/// `body1_00100.zms`, and avatar motions end in `_m1` or `_f1`, e.g.
+/// `empty_run_m1.zmo`.
+fn avatar_skeleton(asset_path: &str) -> Option<String> {
+    let file_stem = Path::new(asset_path).file_stem()?.to_str()?;
+    let male = if asset_path.starts_with("3ddata/motion/avatar/") {
+        let (_, suffix) = file_stem.rsplit_once('_')?;
+        match suffix.chars().next()? {
+            'm' => true,
+            'f' => false,
+            _ => return None,
+        }
+    } else if asset_path.starts_with("3ddata/avatar/") {
+        let prefix = file_stem.split('_').next()?;
+        match prefix.trim_start_matches(|c: char| c.is_ascii_alphabetic()) {
+            "1" => true,
+            "2" => false,
+            _ => return None,
+        }
+    } else {
+        return None;
+    };
+
+    Some(if male {
+        "3ddata/avatar/male.zmd".to_string()
+    } else {
+        "3ddata/avatar/female.zmd".to_string()
+    })
+}
+
+/// The only ZMD in the directory of `file_path`, as NPCs keep their skeleton
+/// next to their meshes.
+fn sibling_skeleton(file_path: &Path) -> Option<PathBuf> {
+    let directory = file_path.parent()?;
+    let mut skeletons = std::fs::read_dir(directory)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("zmd"))
+        });
+    let skeleton = skeletons.next()?;
+    skeletons.next().is_none().then_some(skeleton)
+}
@@ -0,0 +1,197 @@
+//! Monster spawn areas of zones as translucent volumes, so the spawn layout
+//! can be seen in a 3D viewer.
+use glam::Vec3;
+use gltf_json::{material, mesh, scene, validation::Checked, Index};
+use rose_file_lib::files::ifo::MonsterSpawn;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, value::RawValue};
+
+use crate::{
+    mesh_builder::MeshBuilder,
+    spill::BinaryData,
+    zone::{convert_position, convert_rotation},
+};
+
+/// Shape of the volumes of monster spawn areas
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpawnVolumeShape {
+    #[default]
+    Cylinder,
+
+    Box,
+}
+
+/// Height of the volumes in glTF units, centered on the spawn point
+const VOLUME_HEIGHT: f32 = 10.0;
+
+/// Number of sides of the cylinder volumes
+const CYLINDER_SEGMENTS: u16 = 32;
+
+/// The mesh shared by every spawn volume, scaled by the range of each spawn
+pub(crate) struct SpawnVolumes {
+    mesh: Index<mesh::Mesh>,
+}
+
+impl SpawnVolumes {
+    pub fn new(
+        root: &mut gltf_json::Root,
+        binary_data: &mut BinaryData,
+        shape: SpawnVolumeShape,
+    ) -> Self {
+        let (positions, normals, indices) = match shape {
+            SpawnVolumeShape::Cylinder => cylinder(),
+            SpawnVolumeShape::Box => cuboid(),
+        };
+        let mut mesh_builder = MeshBuilder::new();
+        mesh_builder.add_positions(positions);
+        mesh_builder.add_normals(normals);
+        mesh_builder.add_indices(indices);
+        let mesh_data = mesh_builder.build(root, binary_data, "spawn_volume");
+
+        let material_index = Index::new(root.materials.len() as u32);
+        root.materials.push(material::Material {
+            name: Some("spawn_volume_material".to_string()),
+            alpha_mode: Checked::Valid(material::AlphaMode::Blend),
+            double_sided: true,
+            pbr_metallic_roughness: material::PbrMetallicRoughness {
+                base_color_factor: material::PbrBaseColorFactor([1.0, 0.25, 0.1, 0.3]),
+                metallic_factor: material::StrengthFactor(0.0),
+                roughness_factor: material::StrengthFactor(1.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let mesh = Index::new(root.meshes.len() as u32);
+        root.meshes.push(mesh::Mesh {
+            name: Some("spawn_volume_mesh".to_string()),
+            extensions: Default::default(),
+            extras: Default::default(),
+            primitives: vec![mesh::Primitive {
+                attributes: mesh_data.attributes,
+                extensions: Default::default(),
+                extras: Default::default(),
+                indices: Some(mesh_data.indices),
+                material: Some(material_index),
+                mode: Checked::Valid(mesh::Mode::Triangles),
+                targets: None,
+            }],
+            weights: None,
+        });
+        Self { mesh }
+    }
+
+    /// Adds a node for a spawn area, scaled to its range in meters, with the
+    /// monsters it spawns in the `rose_spawn` extras.
+    pub fn add_spawn(
+        &self,
+        root: &mut gltf_json::Root,
+        block_x: i32,
+        block_y: i32,
+        spawn_index: usize,
+        spawn: &MonsterSpawn,
+    ) {
+        let range = spawn.range.max(1) as f32;
+        let extras = json!({
+            "rose_spawn": {
+                "name": spawn.name,
+                "basic": spawn.basic_spawns,
+                "tactical": spawn.tactical_spawns,
+                "interval": spawn.interval,
+                "limit": spawn.limit,
+                "range": spawn.range,
+                "tactical_variable": spawn.tactical_variable,
+            }
+        });
+
+        let node_index = Index::new(root.nodes.len() as u32);
+        root.nodes.push(scene::Node {
+            name: Some(format!("{}_{}_spawn_{}", block_x, block_y, spawn_index)),
+            camera: None,
+            children: None,
+            extensions: Default::default(),
+            extras: Some(RawValue::from_string(extras.to_string()).unwrap()),
+            matrix: None,
+            mesh: Some(self.mesh),
+            rotation: Some(convert_rotation(spawn.data.rotation)),
+            scale: Some([range, 1.0, range]),
+            translation: Some(convert_position(spawn.data.position)),
+            skin: None,
+            weights: None,
+        });
+        root.scenes[0].nodes.push(node_index);
+    }
+}
+
+/// A cylinder of radius 1 and `VOLUME_HEIGHT` around the y axis, with caps
+fn cylinder() -> (Vec<Vec3>, Vec<Vec3>, Vec<u16>) {
+    let half_height = VOLUME_HEIGHT / 2.0;
+    let ring: Vec<Vec3> = (0..CYLINDER_SEGMENTS)
+        .map(|segment| {
+            let angle = segment as f32 / CYLINDER_SEGMENTS as f32 * std::f32::consts::TAU;
+            Vec3::new(angle.cos(), 0.0, angle.sin())
+        })
+        .collect();
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices = Vec::new();
+
+    // Sides, a bottom and top vertex for each point of the ring
+    for point in ring.iter() {
+        positions.push(*point - Vec3::Y * half_height);
+        positions.push(*point + Vec3::Y * half_height);
+        normals.extend([*point, *point]);
+    }
+    for segment in 0..CYLINDER_SEGMENTS {
+        let next = (segment + 1) % CYLINDER_SEGMENTS;
+        let [bottom, top] = [segment * 2, segment * 2 + 1];
+        let [next_bottom, next_top] = [next * 2, next * 2 + 1];
+        indices.extend([bottom, next_top, next_bottom, bottom, top, next_top]);
+    }
+
+    // Caps, a fan around the center of each
+    for normal in [Vec3::Y, Vec3::NEG_Y] {
+        let center = positions.len() as u16;
+        positions.push(normal * half_height);
+        positions.extend(ring.iter().map(|point| *point + normal * half_height));
+        normals.extend(std::iter::repeat_n(normal, ring.len() + 1));
+        for segment in 0..CYLINDER_SEGMENTS {
+            let point = center + 1 + segment;
+            let next_point = center + 1 + (segment + 1) % CYLINDER_SEGMENTS;
+            if normal == Vec3::Y {
+                indices.extend([center, next_point, point]);
+            } else {
+                indices.extend([center, point, next_point]);
+            }
+        }
+    }
+
+    (positions, normals, indices)
+}
+
+/// A box from -1 to 1 along x and z and of `VOLUME_HEIGHT` along y
+fn cuboid() -> (Vec<Vec3>, Vec<Vec3>, Vec<u16>) {
+    let size = Vec3::new(1.0, VOLUME_HEIGHT / 2.0, 1.0);
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices = Vec::new();
+
+    // Each face is spanned by two axes whose cross product is its normal, so
+    // its corners are in counter clockwise order
+    for (normal, u, v) in [
+        (Vec3::X, Vec3::Y, Vec3::Z),
+        (Vec3::NEG_X, Vec3::Z, Vec3::Y),
+        (Vec3::Y, Vec3::Z, Vec3::X),
+        (Vec3::NEG_Y, Vec3::X, Vec3::Z),
+        (Vec3::Z, Vec3::X, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::Y, Vec3::X),
+    ] {
+        let first = positions.len() as u16;
+        positions.extend([-u - v, u - v, u + v, v - u].map(|corner| (normal + corner) * size));
+        normals.extend([normal; 4]);
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    (positions, normals, indices)
+}
@@ -0,0 +1,205 @@
+//! The buffer of a conversion, which in `low_memory` mode is written to a
+//! temporary file as it is appended so only its most recent data is kept in
+//! memory.
+use std::{
+    borrow::Cow,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::Context;
+use bytes::{buf::UninitSlice, BufMut, BytesMut};
+
+/// Bytes kept in memory by a spilling buffer before they are written to its
+/// temporary file
+const SPILL_THRESHOLD: usize = 64 * 1024;
+
+/// A file in the temporary directory which is removed when dropped
+struct TempFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl TempFile {
+    fn create() -> io::Result<Self> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "rose-gltf-{}-{}.bin",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(Self { path, file })
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The binary buffer of a glTF being built, offsets into it are given by
+/// `len` as for a `BytesMut`.
+///
+/// A spilling buffer writes its data to a temporary file once more than
+/// `SPILL_THRESHOLD` bytes are in memory. Data which was written to the file
+/// is read back by `read` and changed by `write_at`.
+pub(crate) struct BinaryData {
+    /// The data after the first `spilled_len` bytes
+    memory: BytesMut,
+    spilling: bool,
+    spill_file: Option<TempFile>,
+    spilled_len: usize,
+    /// The first error writing to the temporary file, returned by
+    /// `into_bytes` as `BufMut` can not fail
+    spill_error: Option<io::Error>,
+}
+
+impl BinaryData {
+    /// An empty buffer, which spills to a temporary file when `spilling`
+    pub(crate) fn new(spilling: bool) -> Self {
+        Self {
+            memory: BytesMut::with_capacity(if spilling {
+                SPILL_THRESHOLD
+            } else {
+                8 * 1024 * 1024
+            }),
+            spilling,
+            spill_file: None,
+            spilled_len: 0,
+            spill_error: None,
+        }
+    }
+
+    /// An empty buffer which spills like this one
+    pub(crate) fn new_like(&self) -> Self {
+        Self::new(self.spilling)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.spilled_len + self.memory.len()
+    }
+
+    /// Writes the data in memory followed by `data` to the end of the
+    /// temporary file, keeping them in memory if that fails.
+    fn spill(&mut self, data: &[u8]) {
+        if self.spill_error.is_none() {
+            let memory = &self.memory;
+            let spill_file = &mut self.spill_file;
+            let result = (|| {
+                let temp_file = match spill_file.as_mut() {
+                    Some(temp_file) => temp_file,
+                    None => spill_file.insert(TempFile::create()?),
+                };
+                temp_file.file.seek(SeekFrom::End(0))?;
+                temp_file.file.write_all(memory)?;
+                temp_file.file.write_all(data)
+            })();
+            match result {
+                Ok(()) => {
+                    self.spilled_len += self.memory.len() + data.len();
+                    self.memory.clear();
+                    return;
+                }
+                Err(error) => self.spill_error = Some(error),
+            }
+        }
+        self.memory.put_slice(data);
+    }
+
+    /// The bytes of `range`, or `None` when it is past the end
+    pub(crate) fn read(&self, range: Range<usize>) -> io::Result<Option<Cow<'_, [u8]>>> {
+        if range.start > range.end || range.end > self.len() {
+            return Ok(None);
+        }
+        if range.start >= self.spilled_len {
+            let start = range.start - self.spilled_len;
+            let end = range.end - self.spilled_len;
+            return Ok(Some(Cow::Borrowed(&self.memory[start..end])));
+        }
+
+        // Some of the data was written to `spill_file`
+        let mut data = vec![0; range.len()];
+        let file_end = range.end.min(self.spilled_len);
+        let (from_file, from_memory) = data.split_at_mut(file_end - range.start);
+        let mut file = &self.spill_file.as_ref().unwrap().file;
+        file.seek(SeekFrom::Start(range.start as u64))?;
+        file.read_exact(from_file)?;
+        from_memory.copy_from_slice(&self.memory[..from_memory.len()]);
+        Ok(Some(Cow::Owned(data)))
+    }
+
+    /// Replaces the bytes at `offset` by `data`, which must not go past the
+    /// end.
+    pub(crate) fn write_at(&mut self, offset: usize, data: &[u8]) -> io::Result<()> {
+        assert!(offset + data.len() <= self.len());
+        let file_end = (offset + data.len()).min(self.spilled_len).max(offset);
+        let (to_file, to_memory) = data.split_at(file_end - offset);
+        if !to_file.is_empty() {
+            let file = &mut self.spill_file.as_mut().unwrap().file;
+            file.seek(SeekFrom::Start(offset as u64))?;
+            file.write_all(to_file)?;
+        }
+        if !to_memory.is_empty() {
+            let memory_offset = offset + to_file.len() - self.spilled_len;
+            self.memory[memory_offset..memory_offset + to_memory.len()].copy_from_slice(to_memory);
+        }
+        Ok(())
+    }
+
+    /// Reads the whole buffer into memory
+    pub(crate) fn into_bytes(mut self) -> anyhow::Result<BytesMut> {
+        if let Some(error) = self.spill_error.take() {
+            return Err(error).context("Failed to write temporary file");
+        }
+        let Some(temp_file) = self.spill_file.take() else {
+            return Ok(self.memory);
+        };
+
+        let mut data = BytesMut::zeroed(self.spilled_len);
+        let mut file = &temp_file.file;
+        file.seek(SeekFrom::Start(0))
+            .and_then(|_| file.read_exact(&mut data))
+            .context("Failed to read temporary file")?;
+        drop(temp_file);
+        data.put_slice(&self.memory);
+        Ok(data)
+    }
+}
+
+// SAFETY: delegates to `memory`, which is only spilled after bytes were
+// initialised and advanced over
+unsafe impl BufMut for BinaryData {
+    fn remaining_mut(&self) -> usize {
+        self.memory.remaining_mut()
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        self.memory.advance_mut(cnt);
+        if self.spilling && self.memory.len() >= SPILL_THRESHOLD {
+            self.spill(&[]);
+        }
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        self.memory.chunk_mut()
+    }
+
+    fn put_slice(&mut self, src: &[u8]) {
+        // Large data is written straight to the file instead of growing
+        // `memory` to hold it
+        if self.spilling && self.memory.len() + src.len() >= SPILL_THRESHOLD {
+            self.spill(src);
+        } else {
+            self.memory.put_slice(src);
+        }
+    }
+}
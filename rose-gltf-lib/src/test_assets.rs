@@ -0,0 +1,292 @@
+//! Tiny hand-crafted ROSE files for testing conversions against golden glTF
+//! snapshots.
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use rose_file_lib::{
+    files::{
+        ifo::{ObjectData, Ocean, OceanPatch},
+        stb::DataTableColumn,
+        til::Tile,
+        zmd::Bone,
+        zmo::{Channel, ChannelData, ChannelType},
+        zms::{Vertex, VertexFormat},
+        zon::{ZoneTile, ZoneTileRotation},
+        zsc::{Model, ModelMaterial, ModelPart},
+        HIM, IFO, STB, TIL, ZMD, ZMO, ZMS, ZON, ZSC,
+    },
+    io::RoseFile,
+    utils::{BoundingBox, Quaternion, Vector2, Vector3, Vector4},
+};
+
+/// Paths of the files written by `write_test_assets`.
+pub struct TestAssets {
+    /// Root of the assets, the directory containing `3ddata`
+    pub assets_path: PathBuf,
+
+    /// Static quad mesh, see `quad_zms`
+    pub zms: PathBuf,
+
+    /// Quad mesh skinned to the skeleton, see `skinned_quad_zms`
+    pub skinned_zms: PathBuf,
+
+    /// Two bone skeleton, see `two_bone_zmd`
+    pub zmd: PathBuf,
+
+    /// Two frame animation of the skeleton, see `two_bone_zmo`
+    pub zmo: PathBuf,
+
+    /// Zone with a single block containing one deco object, one building and an ocean
+    pub zon: PathBuf,
+}
+
+fn quad_vertices() -> Vec<Vertex> {
+    [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+        .into_iter()
+        .map(|(x, y)| Vertex {
+            position: Vector3::new(x, y, 0.0),
+            normal: Vector3::new(0.0, 0.0, 1.0),
+            uv1: Vector2::new(x, 1.0 - y),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// A 1x1 quad in the XY plane with normals and uv1.
+pub fn quad_zms() -> ZMS {
+    ZMS {
+        format: VertexFormat::Position as i32
+            | VertexFormat::Normal as i32
+            | VertexFormat::UV1 as i32,
+        bounding_box: BoundingBox {
+            min: Vector3::new(0.0, 0.0, 0.0),
+            max: Vector3::new(1.0, 1.0, 0.0),
+        },
+        vertices: quad_vertices(),
+        indices: vec![Vector3::new(0, 1, 2), Vector3::new(0, 2, 3)],
+        ..Default::default()
+    }
+}
+
+/// The quad of `quad_zms` with the bottom edge bound to bone 0 and the top
+/// edge bound to bone 1 of `two_bone_zmd`.
+pub fn skinned_quad_zms() -> ZMS {
+    let mut zms = quad_zms();
+    zms.format |= VertexFormat::BoneWeight as i32 | VertexFormat::BoneIndex as i32;
+    zms.bones = vec![0, 1];
+    for vertex in zms.vertices.iter_mut() {
+        vertex.bone_weights = Vector4::new(1.0, 0.0, 0.0, 0.0);
+        vertex.bone_indices = Vector4::new(if vertex.position.y > 0.5 { 1 } else { 0 }, 0, 0, 0);
+    }
+    zms
+}
+
+/// A root bone with a single child bone 1 unit above it.
+pub fn two_bone_zmd() -> ZMD {
+    ZMD {
+        bones: vec![
+            Bone {
+                parent: 0,
+                name: "b1_root".to_string(),
+                position: Vector3::new(0.0, 0.0, 0.0),
+                rotation: Quaternion::IDENTITY,
+            },
+            Bone {
+                parent: 0,
+                name: "b1_top".to_string(),
+                position: Vector3::new(0.0, 100.0, 0.0),
+                rotation: Quaternion::IDENTITY,
+            },
+        ],
+        dummy_bones: Vec::new(),
+        ..Default::default()
+    }
+}
+
+/// Two frames which move the root bone and rotate the child bone 90 degrees
+/// around Z.
+pub fn two_bone_zmo() -> ZMO {
+    let half_sqrt2 = std::f32::consts::FRAC_1_SQRT_2;
+    ZMO {
+        identifier: "ZMO0002".to_string(),
+        fps: 30,
+        frames: 2,
+        channels: vec![
+            Channel {
+                typ: ChannelType::Position,
+                index: 0,
+                frames: ChannelData::Position(vec![
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, 50.0),
+                ]),
+            },
+            Channel {
+                typ: ChannelType::Rotation,
+                index: 1,
+                frames: ChannelData::Rotation(vec![
+                    Quaternion::IDENTITY,
+                    Quaternion::new(0.0, 0.0, half_sqrt2, half_sqrt2),
+                ]),
+            },
+        ],
+    }
+}
+
+fn object_data(object_id: i32, position: Vector3<f32>) -> ObjectData {
+    ObjectData {
+        object_id,
+        position,
+        rotation: Quaternion::IDENTITY,
+        scale: Vector3::ONE,
+        ..Default::default()
+    }
+}
+
+fn write_zone(assets_path: &Path, zms_path: &str) -> anyhow::Result<PathBuf> {
+    let block_x = 32;
+    let block_y = 32;
+    let block_position = |x: f32, y: f32| {
+        Vector3::new(
+            (block_x as f32 * 160.0 + x) * 100.0,
+            ((65 - block_y) as f32 * 160.0 - y) * 100.0,
+            0.0,
+        )
+    };
+
+    // A single tile texture, the zone blends layer1 and layer2 of each tile
+    image::RgbaImage::from_fn(64, 64, |x, y| {
+        image::Rgba([(x * 4) as u8, (y * 4) as u8, 128, 255])
+    })
+    .save(assets_path.join("3ddata/test/tile.png"))
+    .context("Failed to write tile texture")?;
+    image::RgbaImage::from_pixel(4, 4, image::Rgba([200, 100, 50, 255]))
+        .save(assets_path.join("3ddata/test/quad.png"))
+        .context("Failed to write object texture")?;
+
+    let mut objects = ZSC::new();
+    objects.models.push(Some(Model {
+        parts: vec![ModelPart {
+            mesh_path: zms_path.to_string(),
+            material: Some(ModelMaterial {
+                path: "3ddata/test/quad.png".to_string(),
+                ..Default::default()
+            }),
+            use_lightmap: true,
+            ..Default::default()
+        }],
+        ..Default::default()
+    }));
+    objects.write_to_path(&assets_path.join("3ddata/test/deco.zsc"))?;
+    objects.write_to_path(&assets_path.join("3ddata/test/cnst.zsc"))?;
+
+    let zon_path = "3ddata/maps/test/test.zon";
+    let mut list_zone = STB::new();
+    list_zone.headers = (0..14)
+        .map(|_| DataTableColumn {
+            name: String::new(),
+            width: 100,
+        })
+        .collect();
+    list_zone.data = vec![vec![String::new(); 14]; 2];
+    list_zone.data[1][2] = zon_path.to_string();
+    list_zone.data[1][12] = "3ddata/test/deco.zsc".to_string();
+    list_zone.data[1][13] = "3ddata/test/cnst.zsc".to_string();
+    list_zone.write_to_path(&assets_path.join("3ddata/stb/list_zone.stb"))?;
+
+    let mut zon = ZON {
+        width: 1,
+        height: 1,
+        grid_count: 4,
+        grid_size: 250.0,
+        positions: vec![vec![Default::default()]],
+        textures: vec!["3ddata/test/tile.png".to_string()],
+        tiles: vec![ZoneTile {
+            layer1: 0,
+            layer2: 0,
+            offset1: 0,
+            offset2: 0,
+            blend: false,
+            rotation: ZoneTileRotation::None,
+            tile_type: 0,
+        }],
+        ..Default::default()
+    };
+    zon.write_to_path(&assets_path.join(zon_path))?;
+
+    let map_path = assets_path.join("3ddata/maps/test");
+    let block_name = format!("{}_{}", block_x, block_y);
+
+    // A gentle slope along x
+    let mut him = HIM {
+        width: 65,
+        length: 65,
+        grid_count: 4,
+        scale: 250.0,
+        ..Default::default()
+    };
+    him.heights = (0..65 * 65).map(|i| (i % 65) as f32 * 10.0).collect();
+    him.write_to_path(&map_path.join(format!("{}.him", block_name)))?;
+
+    let mut til = TIL {
+        width: 16,
+        height: 16,
+        tiles: vec![vec![Tile::default(); 16]; 16],
+    };
+    til.write_to_path(&map_path.join(format!("{}.til", block_name)))?;
+
+    let mut ifo = IFO {
+        map_pos: Vector2::new(16, 16),
+        zone_pos: Vector2::new(block_x, block_y),
+        objects: vec![object_data(0, block_position(40.0, 40.0))],
+        buildings: vec![object_data(0, block_position(120.0, 120.0))],
+        oceans: vec![Ocean {
+            size: 1000.0,
+            patches: vec![OceanPatch {
+                start: block_position(0.0, 80.0),
+                end: block_position(160.0, 160.0),
+            }],
+        }],
+        ..Default::default()
+    };
+    ifo.write_to_path(&map_path.join(format!("{}.ifo", block_name)))?;
+
+    Ok(assets_path.join(zon_path))
+}
+
+/// Writes every test asset into `3ddata` below the directory, so they can be
+/// converted with `rose_to_gltf` like files from a real client.
+pub fn write_test_assets(directory: &Path) -> anyhow::Result<TestAssets> {
+    for subdirectory in ["3ddata/test", "3ddata/stb", "3ddata/maps/test"] {
+        std::fs::create_dir_all(directory.join(subdirectory))
+            .with_context(|| format!("Failed to create {}", subdirectory))?;
+    }
+
+    let test_path = directory.join("3ddata/test");
+    let zms = test_path.join("quad.zms");
+    quad_zms().write_to_path(&zms)?;
+    let skinned_zms = test_path.join("skinned_quad.zms");
+    skinned_quad_zms().write_to_path(&skinned_zms)?;
+    let zmd = test_path.join("two_bone.zmd");
+    two_bone_zmd().write_to_path(&zmd)?;
+    let zmo = test_path.join("two_bone.zmo");
+    two_bone_zmo().write_to_path(&zmo)?;
+    let zon = write_zone(directory, "3ddata/test/quad.zms")?;
+
+    Ok(TestAssets {
+        assets_path: directory.to_path_buf(),
+        zms,
+        skinned_zms,
+        zmd,
+        zmo,
+        zon,
+    })
+}
+
+/// Serializes the JSON part of a converted glTF for comparing against a
+/// golden snapshot, binary data is only represented by buffer lengths.
+pub fn gltf_json_snapshot(gltf: &gltf::Gltf) -> anyhow::Result<String> {
+    let mut json = serde_json::to_string_pretty(gltf.document.as_json())
+        .context("Failed to serialize GLTF")?;
+    json.push('\n');
+    Ok(json)
+}
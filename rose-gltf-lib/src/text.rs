@@ -0,0 +1,99 @@
+//! JSON and YAML representations of ROSE files, for inspecting and editing
+//! assets as text.
+use std::path::Path;
+
+use anyhow::Context;
+use rose_file_lib::{
+    files::{read_any, CHR, EFT, HIM, IFO, LIT, STB, STL, TIL, TSI, ZCA, ZMD, ZMO, ZMS, ZON, ZSC},
+    io::RoseFile,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::{CategorizedError, ErrorCategory};
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TextFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl TextFormat {
+    /// Guess the format from a file extension, anything but .yaml or .yml is
+    /// assumed to be JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .map(|extension| extension.to_ascii_lowercase())
+        {
+            Some(extension) if extension == "yaml" || extension == "yml" => TextFormat::Yaml,
+            _ => TextFormat::Json,
+        }
+    }
+}
+
+fn file_extension(path: &Path) -> anyhow::Result<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase())
+        .with_context(|| format!("{} has no file extension", path.display()))
+}
+
+/// Reads a ROSE file and serializes it as text, the file type is chosen by
+/// the file extension.
+pub fn dump_rose_file(path: &Path, format: TextFormat) -> anyhow::Result<String> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let file = read_any(&bytes, &file_extension(path)?)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    match format {
+        TextFormat::Json => {
+            let mut text =
+                serde_json::to_string_pretty(&file).context("Failed to serialize as JSON")?;
+            text.push('\n');
+            Ok(text)
+        }
+        TextFormat::Yaml => serde_yaml::to_string(&file).context("Failed to serialize as YAML"),
+    }
+}
+
+fn build<T: RoseFile + DeserializeOwned>(
+    text: &str,
+    format: TextFormat,
+    output: &Path,
+) -> anyhow::Result<()> {
+    let mut file: T = match format {
+        TextFormat::Json => serde_json::from_str(text).context("Failed to parse JSON")?,
+        TextFormat::Yaml => serde_yaml::from_str(text).context("Failed to parse YAML")?,
+    };
+    file.write_to_path(output)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+    Ok(())
+}
+
+/// Deserializes text produced by `dump_rose_file` and writes it as a ROSE
+/// file, the file type is chosen by the extension of the output path.
+pub fn build_rose_file(text: &str, format: TextFormat, output: &Path) -> anyhow::Result<()> {
+    match file_extension(output)?.as_str() {
+        "chr" => build::<CHR>(text, format, output),
+        "eft" => build::<EFT>(text, format, output),
+        "him" => build::<HIM>(text, format, output),
+        "ifo" => build::<IFO>(text, format, output),
+        "lit" => build::<LIT>(text, format, output),
+        "stb" => build::<STB>(text, format, output),
+        "stl" => build::<STL>(text, format, output),
+        "til" => build::<TIL>(text, format, output),
+        "tsi" => build::<TSI>(text, format, output),
+        "zca" => build::<ZCA>(text, format, output),
+        "zmd" => build::<ZMD>(text, format, output),
+        "zmo" => build::<ZMO>(text, format, output),
+        "zms" => build::<ZMS>(text, format, output),
+        "zon" => build::<ZON>(text, format, output),
+        "zsc" => build::<ZSC>(text, format, output),
+        extension => Err(CategorizedError::error(
+            ErrorCategory::Unsupported,
+            format!("Unsupported file extension: {}", extension),
+        )),
+    }
+}
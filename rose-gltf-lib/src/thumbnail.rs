@@ -0,0 +1,305 @@
+//! Rendering a preview image of a glTF without a GPU, for thumbnails in asset
+//! browsers.
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use image::RgbaImage;
+
+use crate::GltfData;
+
+/// Vertical field of view of the thumbnail camera
+const FIELD_OF_VIEW: f32 = std::f32::consts::FRAC_PI_4;
+
+/// Direction from the target to the thumbnail camera, looking down at the
+/// front right of the model
+const CAMERA_DIRECTION: Vec3 = Vec3::new(1.0, 0.8, 1.3);
+
+struct Triangle {
+    positions: [Vec3; 3],
+    uvs: [Vec2; 3],
+    material: Option<usize>,
+}
+
+struct Material<'a> {
+    base_color: Vec4,
+    texture: Option<&'a gltf::image::Data>,
+    alpha_mode: gltf::material::AlphaMode,
+    alpha_cutoff: f32,
+}
+
+impl Material<'_> {
+    fn color(&self, uv: Vec2) -> Vec4 {
+        match self.texture {
+            Some(texture) => self.base_color * sample_texture(texture, uv),
+            None => self.base_color,
+        }
+    }
+}
+
+/// Nearest sample of a texture with repeat wrapping
+fn sample_texture(texture: &gltf::image::Data, uv: Vec2) -> Vec4 {
+    use gltf::image::Format;
+
+    let channels = match texture.format {
+        Format::R8 => 1,
+        Format::R8G8 => 2,
+        Format::R8G8B8 => 3,
+        Format::R8G8B8A8 => 4,
+        _ => return Vec4::ONE,
+    };
+    if texture.width == 0 || texture.height == 0 {
+        return Vec4::ONE;
+    }
+
+    let x = (uv.x.rem_euclid(1.0) * texture.width as f32) as u32;
+    let y = (uv.y.rem_euclid(1.0) * texture.height as f32) as u32;
+    let offset =
+        (y.min(texture.height - 1) * texture.width + x.min(texture.width - 1)) as usize * channels;
+    let Some(pixel) = texture.pixels.get(offset..offset + channels) else {
+        return Vec4::ONE;
+    };
+    let value = |index: usize| pixel[index] as f32 / 255.0;
+    match channels {
+        1 => Vec4::new(value(0), value(0), value(0), 1.0),
+        2 => Vec4::new(value(0), value(0), value(0), value(1)),
+        3 => Vec4::new(value(0), value(1), value(2), 1.0),
+        _ => Vec4::new(value(0), value(1), value(2), value(3)),
+    }
+}
+
+fn load_materials(gltf: &GltfData) -> Vec<Material<'_>> {
+    gltf.document
+        .materials()
+        .map(|material| {
+            let pbr = material.pbr_metallic_roughness();
+            Material {
+                base_color: Vec4::from_array(pbr.base_color_factor()),
+                texture: pbr
+                    .base_color_texture()
+                    .and_then(|info| gltf.images.get(info.texture().source().index())),
+                alpha_mode: material.alpha_mode(),
+                alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+            }
+        })
+        .collect()
+}
+
+fn collect_triangles(
+    gltf: &GltfData,
+    node: gltf::Node,
+    parent_transform: Mat4,
+    triangles: &mut Vec<Triangle>,
+) {
+    let transform = parent_transform * Mat4::from_cols_array_2d(&node.transform().matrix());
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                continue;
+            }
+
+            let reader =
+                primitive.reader(|buffer| gltf.buffers.get(buffer.index()).map(|b| &b[..]));
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+            let positions = positions
+                .map(|position| transform.transform_point3(Vec3::from_array(position)))
+                .collect::<Vec<_>>();
+            let uvs = reader
+                .read_tex_coords(0)
+                .map(|uvs| uvs.into_f32().map(Vec2::from_array).collect::<Vec<_>>())
+                .unwrap_or_default();
+            let indices = reader
+                .read_indices()
+                .map(|indices| indices.into_u32().map(|index| index as usize).collect())
+                .unwrap_or_else(|| (0..positions.len()).collect::<Vec<_>>());
+
+            for triangle in indices.chunks_exact(3) {
+                let triangle = [triangle[0], triangle[1], triangle[2]];
+                if triangle.iter().any(|&index| index >= positions.len()) {
+                    continue;
+                }
+                triangles.push(Triangle {
+                    positions: triangle.map(|index| positions[index]),
+                    uvs: triangle.map(|index| uvs.get(index).copied().unwrap_or_default()),
+                    material: primitive.material().index(),
+                });
+            }
+        }
+    }
+
+    for child in node.children() {
+        collect_triangles(gltf, child, transform, triangles);
+    }
+}
+
+/// Renders the default scene of a glTF from a fixed camera which frames its
+/// bounding box. Only the base color of materials is used, lit by a light
+/// from the camera, and pixels not covered by a mesh are transparent.
+pub fn render_thumbnail(gltf: &GltfData, width: u32, height: u32) -> RgbaImage {
+    let mut image = RgbaImage::new(width, height);
+    let Some(scene) = gltf
+        .document
+        .default_scene()
+        .or_else(|| gltf.document.scenes().next())
+    else {
+        return image;
+    };
+
+    let mut triangles = Vec::new();
+    for node in scene.nodes() {
+        collect_triangles(gltf, node, Mat4::IDENTITY, &mut triangles);
+    }
+    let Some((min, max)) = triangles
+        .iter()
+        .flat_map(|triangle| triangle.positions)
+        .fold(None, |bounds: Option<(Vec3, Vec3)>, position| {
+            Some(match bounds {
+                Some((min, max)) => (min.min(position), max.max(position)),
+                None => (position, position),
+            })
+        })
+    else {
+        return image;
+    };
+
+    // Move the camera back until the bounding sphere fits the narrowest
+    // field of view
+    let center = (min + max) / 2.0;
+    let radius = ((max - min).length() / 2.0).max(0.001);
+    let aspect = width as f32 / height.max(1) as f32;
+    let fov = if aspect < 1.0 {
+        2.0 * ((FIELD_OF_VIEW / 2.0).tan() * aspect).atan()
+    } else {
+        FIELD_OF_VIEW
+    };
+    let direction = CAMERA_DIRECTION.normalize();
+    let eye = center + direction * radius / (fov / 2.0).sin();
+    let view = Mat4::look_at_rh(eye, center, Vec3::Y);
+    let projection = Mat4::perspective_rh(
+        FIELD_OF_VIEW,
+        aspect,
+        radius * 0.01,
+        (eye - center).length() + radius * 2.0,
+    );
+    let view_projection = projection * view;
+
+    let materials = load_materials(gltf);
+    let default_material = Material {
+        base_color: Vec4::ONE,
+        texture: None,
+        alpha_mode: gltf::material::AlphaMode::Opaque,
+        alpha_cutoff: 0.5,
+    };
+
+    // Draw blended triangles last so they are blended over what is behind
+    let is_blended = |triangle: &Triangle| {
+        triangle
+            .material
+            .and_then(|material| materials.get(material))
+            .is_some_and(|material| material.alpha_mode == gltf::material::AlphaMode::Blend)
+    };
+    triangles.sort_by_key(is_blended);
+
+    let mut depth = vec![f32::INFINITY; (width * height) as usize];
+    for triangle in triangles.iter() {
+        let material = triangle
+            .material
+            .and_then(|material| materials.get(material))
+            .unwrap_or(&default_material);
+
+        // Light from the camera, both sides of a face are lit the same
+        let normal = (triangle.positions[1] - triangle.positions[0])
+            .cross(triangle.positions[2] - triangle.positions[0])
+            .normalize_or_zero();
+        let lighting = 0.35 + 0.65 * normal.dot(direction).abs();
+
+        let clip = triangle
+            .positions
+            .map(|position| view_projection * position.extend(1.0));
+        if clip.iter().any(|clip| clip.w <= 0.0) {
+            continue;
+        }
+        let screen = clip.map(|clip| {
+            Vec3::new(
+                (clip.x / clip.w * 0.5 + 0.5) * width as f32,
+                (0.5 - clip.y / clip.w * 0.5) * height as f32,
+                clip.z / clip.w,
+            )
+        });
+
+        let area = (screen[1].x - screen[0].x) * (screen[2].y - screen[0].y)
+            - (screen[2].x - screen[0].x) * (screen[1].y - screen[0].y);
+        if area.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let min_x = screen
+            .iter()
+            .map(|p| p.x)
+            .fold(f32::INFINITY, f32::min)
+            .max(0.0) as u32;
+        let min_y = screen
+            .iter()
+            .map(|p| p.y)
+            .fold(f32::INFINITY, f32::min)
+            .max(0.0) as u32;
+        let max_x = (screen.iter().map(|p| p.x).fold(0.0, f32::max).ceil() as u32).min(width);
+        let max_y = (screen.iter().map(|p| p.y).fold(0.0, f32::max).ceil() as u32).min(height);
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let point = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let edge = |a: Vec3, b: Vec3| {
+                    ((b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x)) / area
+                };
+                let weights = Vec3::new(
+                    edge(screen[1], screen[2]),
+                    edge(screen[2], screen[0]),
+                    edge(screen[0], screen[1]),
+                );
+                if weights.min_element() < 0.0 {
+                    continue;
+                }
+
+                let z = weights.dot(Vec3::new(screen[0].z, screen[1].z, screen[2].z));
+                let pixel_index = (y * width + x) as usize;
+                if z >= depth[pixel_index] {
+                    continue;
+                }
+
+                // Perspective correct interpolation of the UVs
+                let perspective = weights / Vec3::new(clip[0].w, clip[1].w, clip[2].w);
+                let perspective = perspective / perspective.element_sum();
+                let uv = triangle.uvs[0] * perspective.x
+                    + triangle.uvs[1] * perspective.y
+                    + triangle.uvs[2] * perspective.z;
+                let color = material.color(uv);
+
+                let destination = image.get_pixel_mut(x, y);
+                let lit = (color.truncate() * lighting).min(Vec3::ONE);
+                match material.alpha_mode {
+                    gltf::material::AlphaMode::Blend => {
+                        let previous = Vec4::from_array(destination.0.map(|c| c as f32 / 255.0));
+                        let alpha = color.w;
+                        let blended = lit * alpha + previous.truncate() * (1.0 - alpha);
+                        let coverage = alpha + previous.w * (1.0 - alpha);
+                        destination.0 = blended
+                            .extend(coverage)
+                            .to_array()
+                            .map(|c| (c * 255.0) as u8);
+                    }
+                    alpha_mode => {
+                        if alpha_mode == gltf::material::AlphaMode::Mask
+                            && color.w < material.alpha_cutoff
+                        {
+                            continue;
+                        }
+                        destination.0 = lit.extend(1.0).to_array().map(|c| (c * 255.0) as u8);
+                        depth[pixel_index] = z;
+                    }
+                }
+            }
+        }
+    }
+
+    image
+}
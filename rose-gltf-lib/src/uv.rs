@@ -0,0 +1,281 @@
+use glam::Vec2;
+use rose_file_lib::{
+    files::{zms::Vertex, ZMS},
+    utils::Vector2,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum UvMode {
+    /// Pass through UVs unchanged
+    #[default]
+    Keep,
+
+    /// Wrap UVs into the [0, 1] range
+    Wrap,
+
+    /// Clamp UVs into the [0, 1] range
+    Clamp,
+
+    /// Scale and offset UVs so their extents fit into the [0, 1] range
+    Rescale,
+}
+
+pub fn uv_channel(vertex: &mut Vertex, channel: usize) -> &mut Vector2<f32> {
+    match channel {
+        0 => &mut vertex.uv1,
+        1 => &mut vertex.uv2,
+        2 => &mut vertex.uv3,
+        _ => &mut vertex.uv4,
+    }
+}
+
+/// Bakes a KHR_texture_transform offset, rotation and scale into a UV channel
+/// of the mesh.
+pub fn apply_texture_transform(
+    zms: &mut ZMS,
+    channel: usize,
+    offset: Vec2,
+    rotation: f32,
+    scale: Vec2,
+) {
+    let (sin, cos) = rotation.sin_cos();
+    for vertex in zms.vertices.iter_mut() {
+        let uv = uv_channel(vertex, channel);
+        let scaled = Vec2::new(uv.x, uv.y) * scale;
+        uv.x = cos * scaled.x + sin * scaled.y + offset.x;
+        uv.y = -sin * scaled.x + cos * scaled.y + offset.y;
+    }
+}
+
+/// Mirrors the V coordinate of a UV channel of the mesh.
+pub fn flip_v(zms: &mut ZMS, channel: usize) {
+    for vertex in zms.vertices.iter_mut() {
+        let uv = uv_channel(vertex, channel);
+        uv.y = 1.0 - uv.y;
+    }
+}
+
+/// Applies the UV mode to a UV channel of the mesh, returning the original
+/// extents of the channel if any UV was outside of the [0, 1] range.
+pub fn normalize_uvs(zms: &mut ZMS, channel: usize, mode: UvMode) -> Option<(Vec2, Vec2)> {
+    let mut min = Vec2::splat(f32::MAX);
+    let mut max = Vec2::splat(f32::MIN);
+    for vertex in zms.vertices.iter_mut() {
+        let uv = uv_channel(vertex, channel);
+        min = min.min(Vec2::new(uv.x, uv.y));
+        max = max.max(Vec2::new(uv.x, uv.y));
+    }
+
+    if zms.vertices.is_empty() || (min.cmpge(Vec2::ZERO).all() && max.cmple(Vec2::ONE).all()) {
+        return None;
+    }
+
+    let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+    for vertex in zms.vertices.iter_mut() {
+        let uv = uv_channel(vertex, channel);
+        let value = Vec2::new(uv.x, uv.y);
+        let value = match mode {
+            UvMode::Keep => value,
+            UvMode::Wrap => Vec2::select(
+                value.cmpge(Vec2::ZERO) & value.cmple(Vec2::ONE),
+                value,
+                value - value.floor(),
+            ),
+            UvMode::Clamp => value.clamp(Vec2::ZERO, Vec2::ONE),
+            UvMode::Rescale => (value - min) / extent,
+        };
+        uv.x = value.x;
+        uv.y = value.y;
+    }
+
+    Some((min, max))
+}
+
+/// Generates a non-overlapping UV2 channel suitable for lightmapping.
+///
+/// Triangles are grouped into charts of edge connected triangles facing the
+/// same dominant axis, each chart is planar projected along that axis and the
+/// charts are then shelf packed into the unit square. Vertices shared between
+/// charts are duplicated. Returns false and leaves the mesh unchanged when
+/// the duplicates would take it past the vertices 16 bit indices can address.
+pub fn generate_lightmap_uvs(zms: &mut ZMS) -> bool {
+    use std::collections::HashMap;
+
+    let position = |vertex_index: i16| -> glam::Vec3 {
+        let vertex = &zms.vertices[vertex_index as u16 as usize];
+        glam::Vec3::new(vertex.position.x, vertex.position.y, vertex.position.z)
+    };
+
+    // Assign each triangle the dominant axis of its face normal
+    let triangle_axes: Vec<usize> = zms
+        .indices
+        .iter()
+        .map(|triangle| {
+            let a = position(triangle.x);
+            let normal = (position(triangle.y) - a).cross(position(triangle.z) - a);
+            let abs_normal = normal.abs();
+            let axis = if abs_normal.x >= abs_normal.y && abs_normal.x >= abs_normal.z {
+                0
+            } else if abs_normal.y >= abs_normal.z {
+                1
+            } else {
+                2
+            };
+            axis * 2 + (normal[axis] < 0.0) as usize
+        })
+        .collect();
+
+    // Group triangles into charts by flood filling across shared edges
+    let mut edge_triangles: HashMap<(i16, i16), Vec<usize>> = HashMap::new();
+    for (triangle_index, triangle) in zms.indices.iter().enumerate() {
+        for (a, b) in [
+            (triangle.x, triangle.y),
+            (triangle.y, triangle.z),
+            (triangle.z, triangle.x),
+        ] {
+            edge_triangles
+                .entry((a.min(b), a.max(b)))
+                .or_default()
+                .push(triangle_index);
+        }
+    }
+
+    let mut triangle_charts = vec![usize::MAX; zms.indices.len()];
+    let mut charts: Vec<Vec<usize>> = Vec::new();
+    for start_triangle in 0..zms.indices.len() {
+        if triangle_charts[start_triangle] != usize::MAX {
+            continue;
+        }
+
+        let chart_index = charts.len();
+        let mut chart = Vec::new();
+        let mut stack = vec![start_triangle];
+        triangle_charts[start_triangle] = chart_index;
+
+        while let Some(triangle_index) = stack.pop() {
+            chart.push(triangle_index);
+
+            let triangle = &zms.indices[triangle_index];
+            for (a, b) in [
+                (triangle.x, triangle.y),
+                (triangle.y, triangle.z),
+                (triangle.z, triangle.x),
+            ] {
+                for &neighbour in &edge_triangles[&(a.min(b), a.max(b))] {
+                    if triangle_charts[neighbour] == usize::MAX
+                        && triangle_axes[neighbour] == triangle_axes[start_triangle]
+                    {
+                        triangle_charts[neighbour] = chart_index;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+
+        charts.push(chart);
+    }
+
+    let chart_vertex_count = zms
+        .indices
+        .iter()
+        .zip(triangle_charts.iter())
+        .flat_map(|(triangle, &chart_index)| {
+            [triangle.x, triangle.y, triangle.z].map(|index| (chart_index, index))
+        })
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+    if chart_vertex_count.max(zms.vertices.len()) > u16::MAX as usize + 1 {
+        return false;
+    }
+
+    // Project each chart, duplicating vertices which are used by multiple charts
+    let mut chart_vertices: HashMap<(usize, i16), i16> = HashMap::new();
+    let mut chart_bounds = vec![(Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)); charts.len()];
+    let mut lightmap_uvs: Vec<Vec2> = vec![Vec2::ZERO; zms.vertices.len()];
+    let mut vertex_charts: Vec<Option<usize>> = vec![None; zms.vertices.len()];
+
+    for (triangle_index, &chart_index) in triangle_charts.iter().enumerate() {
+        let axis = triangle_axes[charts[chart_index][0]] / 2;
+
+        let triangle = zms.indices[triangle_index];
+        let mut remapped = [triangle.x, triangle.y, triangle.z];
+        for vertex_index in remapped.iter_mut() {
+            let original_index = *vertex_index;
+            *vertex_index = *chart_vertices
+                .entry((chart_index, original_index))
+                .or_insert_with(|| {
+                    let original = original_index as u16 as usize;
+                    if vertex_charts[original].is_none() {
+                        vertex_charts[original] = Some(chart_index);
+                        original_index
+                    } else {
+                        zms.vertices.push(zms.vertices[original].clone());
+                        lightmap_uvs.push(Vec2::ZERO);
+                        vertex_charts.push(Some(chart_index));
+                        (zms.vertices.len() - 1) as u16 as i16
+                    }
+                });
+
+            let vertex = &zms.vertices[*vertex_index as u16 as usize];
+            let projected = match axis {
+                0 => Vec2::new(vertex.position.y, vertex.position.z),
+                1 => Vec2::new(vertex.position.x, vertex.position.z),
+                _ => Vec2::new(vertex.position.x, vertex.position.y),
+            };
+            lightmap_uvs[*vertex_index as u16 as usize] = projected;
+
+            let bounds = &mut chart_bounds[chart_index];
+            bounds.0 = bounds.0.min(projected);
+            bounds.1 = bounds.1.max(projected);
+        }
+
+        zms.indices[triangle_index].x = remapped[0];
+        zms.indices[triangle_index].y = remapped[1];
+        zms.indices[triangle_index].z = remapped[2];
+    }
+
+    // Shelf pack the charts, largest first
+    let total_area: f32 = chart_bounds
+        .iter()
+        .map(|(min, max)| (*max - *min).x * (*max - *min).y)
+        .sum();
+    let padding = total_area.sqrt().max(f32::EPSILON) * 0.01;
+    let shelf_width = total_area.sqrt() * 1.25 + padding;
+
+    let mut chart_order: Vec<usize> = (0..charts.len()).collect();
+    chart_order.sort_by(|a, b| {
+        let height_a = chart_bounds[*a].1.y - chart_bounds[*a].0.y;
+        let height_b = chart_bounds[*b].1.y - chart_bounds[*b].0.y;
+        height_b.total_cmp(&height_a)
+    });
+
+    let mut chart_offsets = vec![Vec2::ZERO; charts.len()];
+    let mut cursor = Vec2::splat(padding);
+    let mut shelf_height = 0.0f32;
+    let mut packed_size = Vec2::ZERO;
+    for chart_index in chart_order {
+        let size = chart_bounds[chart_index].1 - chart_bounds[chart_index].0;
+        if cursor.x > padding && cursor.x + size.x + padding > shelf_width {
+            cursor.x = padding;
+            cursor.y += shelf_height + padding;
+            shelf_height = 0.0;
+        }
+
+        chart_offsets[chart_index] = cursor - chart_bounds[chart_index].0;
+        cursor.x += size.x + padding;
+        shelf_height = shelf_height.max(size.y);
+        packed_size = packed_size.max(Vec2::new(cursor.x, cursor.y + shelf_height + padding));
+    }
+
+    let scale = 1.0 / packed_size.max_element().max(f32::EPSILON);
+    for (vertex_index, vertex) in zms.vertices.iter_mut().enumerate() {
+        let Some(chart_index) = vertex_charts[vertex_index] else {
+            continue;
+        };
+
+        let uv = (lightmap_uvs[vertex_index] + chart_offsets[chart_index]) * scale;
+        vertex.uv2 = Vector2 { x: uv.x, y: uv.y };
+    }
+    true
+}
@@ -0,0 +1,524 @@
+//! Planting grass and other vegetation on zone terrain.
+//!
+//! The client scatters vegetation over terrain tiles by their texture, the
+//! rules for which textures are planted with what are given by a
+//! `VegetationOptions` file.
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use bytes::BufMut;
+use glam::{Quat, Vec2, Vec3};
+use gltf_json::{
+    accessor, buffer, material, mesh, scene,
+    validation::{Checked, USize64},
+    Index,
+};
+use rose_file_lib::files::{him::Heightmap, til::Tilemap, zon, zsc, ZSC};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, value::RawValue};
+
+use crate::{
+    assets::AssetLocator,
+    cache::AssetCache,
+    embedded_image::EmbeddedImageOptions,
+    mesh_builder::{MeshBuilder, MeshData},
+    object_list::ObjectList,
+    pad_align,
+    spill::BinaryData,
+    text::TextFormat,
+    zone::block_translation,
+};
+
+/// Number of terrain tiles along each side of a block
+const BLOCK_TILES: usize = 16;
+
+/// Size of a terrain tile in glTF units
+const TILE_SIZE: f32 = 10.0;
+
+/// Distance between heightmap samples in glTF units
+const HEIGHTMAP_SPACING: f32 = 2.5;
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum VegetationMode {
+    /// Scatter cards over the terrain with EXT_mesh_gpu_instancing
+    #[default]
+    Instanced,
+
+    /// Add the density of each rule per terrain tile to the extras of a node
+    /// per block, for engines which plant their own vegetation
+    DensityMap,
+}
+
+/// Plants cards with a texture on terrain tiles with a matching texture
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VegetationRule {
+    /// Terrain textures to plant on, matched case insensitively against the
+    /// texture paths in the ZON
+    pub terrain_textures: Vec<String>,
+
+    /// Texture of the cards, relative to the assets root
+    pub texture: String,
+
+    /// Cards per square glTF unit
+    pub density: f32,
+
+    /// Width of a card in glTF units
+    pub width: f32,
+
+    /// Height of a card in glTF units
+    pub height: f32,
+}
+
+impl VegetationRule {
+    fn matches(&self, terrain_texture: &str) -> bool {
+        let terrain_texture = terrain_texture.replace('\\', "/").to_ascii_lowercase();
+        self.terrain_textures.iter().any(|texture| {
+            terrain_texture.contains(&texture.replace('\\', "/").to_ascii_lowercase())
+        })
+    }
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct VegetationOptions {
+    #[serde(default)]
+    pub mode: VegetationMode,
+
+    /// Seed for the placement of cards, the same seed always plants the same
+    /// cards
+    #[serde(default)]
+    pub seed: u64,
+
+    pub rules: Vec<VegetationRule>,
+}
+
+impl VegetationOptions {
+    /// Reads the options from a JSON or YAML file, chosen by its extension.
+    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        match TextFormat::from_path(path) {
+            TextFormat::Json => serde_json::from_str(&text).context("Failed to parse JSON"),
+            TextFormat::Yaml => serde_yaml::from_str(&text).context("Failed to parse YAML"),
+        }
+    }
+}
+
+/// Deterministic xorshift generator, so exports are reproducible
+struct Random(u64);
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    /// Returns a value in [0, 1)
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Height of the terrain at a position relative to the block's translation
+fn terrain_height(him: &Heightmap, x: f32, z: f32) -> f32 {
+    let get_height = |x: i32, y: i32| {
+        let x = i32::clamp(x, 0, him.width - 1) as usize;
+        let y = i32::clamp(y, 0, him.length - 1) as usize;
+        him.heights
+            .get(y * him.width as usize + x)
+            .map_or(0.0, |height| height / 100.0)
+    };
+
+    let x = x / HEIGHTMAP_SPACING;
+    let z = z / HEIGHTMAP_SPACING;
+    let (x0, z0) = (x.floor() as i32, z.floor() as i32);
+    let (fx, fz) = (x.fract(), z.fract());
+    let top = get_height(x0, z0) * (1.0 - fx) + get_height(x0 + 1, z0) * fx;
+    let bottom = get_height(x0, z0 + 1) * (1.0 - fx) + get_height(x0 + 1, z0 + 1) * fx;
+    top * (1.0 - fz) + bottom * fz
+}
+
+/// Two crossed quads standing on the origin
+fn card_mesh(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    name: &str,
+    width: f32,
+    height: f32,
+) -> MeshData {
+    let half_width = width / 2.0;
+    let mut positions = Vec::with_capacity(8);
+    let mut uvs = Vec::with_capacity(8);
+    for direction in [Vec3::X, Vec3::Z] {
+        let side = direction * half_width;
+        positions.extend([
+            -side,
+            side,
+            side + Vec3::Y * height,
+            -side + Vec3::Y * height,
+        ]);
+        uvs.extend([
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 0.0),
+        ]);
+    }
+
+    // Point the normals up so the cards are lit like the ground below them
+    let mut mesh_builder = MeshBuilder::new();
+    mesh_builder.add_positions(positions);
+    mesh_builder.add_normals(vec![Vec3::Y; 8]);
+    mesh_builder.add_uv0(uvs);
+    mesh_builder.add_indices(vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7]);
+    mesh_builder.build(root, binary_data, name)
+}
+
+fn push_f32_accessor(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    name: &str,
+    type_: accessor::Type,
+    values: &[f32],
+) -> Index<accessor::Accessor> {
+    pad_align(binary_data);
+    let data_start = binary_data.len();
+    for value in values {
+        binary_data.put_f32_le(*value);
+    }
+
+    let buffer_view = Index::new(root.buffer_views.len() as u32);
+    root.buffer_views.push(buffer::View {
+        name: Some(format!("{}_buffer", name)),
+        buffer: Index::new(0),
+        byte_length: USize64::from(binary_data.len() - data_start),
+        byte_offset: Some(USize64::from(data_start)),
+        byte_stride: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+        target: None,
+    });
+
+    let accessor_index = Index::new(root.accessors.len() as u32);
+    root.accessors.push(accessor::Accessor {
+        name: Some(name.to_string()),
+        buffer_view: Some(buffer_view),
+        byte_offset: Some(USize64(0)),
+        count: USize64::from(values.len() / type_.multiplicity()),
+        component_type: Checked::Valid(accessor::GenericComponentType(
+            accessor::ComponentType::F32,
+        )),
+        extensions: Default::default(),
+        extras: Default::default(),
+        type_: Checked::Valid(type_),
+        min: None,
+        max: None,
+        normalized: false,
+        sparse: None,
+    });
+    accessor_index
+}
+
+/// Plants the vegetation of each block of a zone
+pub(crate) struct Vegetation<'a> {
+    options: &'a VegetationOptions,
+    zon: &'a zon::Zone,
+
+    /// Mesh of each rule, only used for `VegetationMode::Instanced`
+    meshes: Vec<Index<mesh::Mesh>>,
+}
+
+impl<'a> Vegetation<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        root: &mut gltf_json::Root,
+        binary_data: &mut BinaryData,
+        options: &'a VegetationOptions,
+        zon: &'a zon::Zone,
+        assets: &dyn AssetLocator,
+        asset_cache: Option<Arc<AssetCache>>,
+        low_memory: bool,
+        embedded_images: EmbeddedImageOptions,
+        sampler: Index<gltf_json::texture::Sampler>,
+    ) -> Self {
+        let mut meshes = Vec::new();
+        if options.mode == VegetationMode::Instanced {
+            let mut object_list = ObjectList::new(Arc::new(ZSC::default()), sampler);
+            object_list.asset_cache = asset_cache;
+            object_list.low_memory = low_memory;
+            object_list.embedded_images = embedded_images;
+
+            for (rule_index, rule) in options.rules.iter().enumerate() {
+                let name = format!("vegetation_{}", rule_index);
+                let card_material = zsc::ModelMaterial {
+                    path: rule.texture.clone(),
+                    alpha_test: Some(128),
+                    two_sided: true,
+                    ..Default::default()
+                };
+                let material: Option<Index<material::Material>> = object_list
+                    .load_material("vegetation", &card_material, root, binary_data, assets)
+                    .or_else(|| object_list.materials.get(&card_material).copied());
+                if let Some(material) = material {
+                    object_list.materials.insert(card_material, material);
+                }
+
+                let mesh_data = card_mesh(
+                    root,
+                    binary_data,
+                    &format!("{}_mesh", name),
+                    rule.width,
+                    rule.height,
+                );
+                meshes.push(Index::new(root.meshes.len() as u32));
+                root.meshes.push(mesh::Mesh {
+                    name: Some(format!("{}_mesh", name)),
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                    primitives: vec![mesh::Primitive {
+                        attributes: mesh_data.attributes,
+                        extensions: Default::default(),
+                        extras: Default::default(),
+                        indices: Some(mesh_data.indices),
+                        material,
+                        mode: Checked::Valid(mesh::Mode::Triangles),
+                        targets: None,
+                    }],
+                    weights: None,
+                });
+            }
+
+            if !options.rules.is_empty() {
+                root.extensions_used
+                    .push("EXT_mesh_gpu_instancing".to_string());
+            }
+        }
+
+        Self {
+            options,
+            zon,
+            meshes,
+        }
+    }
+
+    /// Density of each rule for each tile of a block, indexed by
+    /// `[rule][tile_y][tile_x]`
+    fn tile_densities(&self, til: &Tilemap) -> Vec<[[f32; BLOCK_TILES]; BLOCK_TILES]> {
+        let mut densities = vec![[[0.0; BLOCK_TILES]; BLOCK_TILES]; self.options.rules.len()];
+        for tile_y in 0..BLOCK_TILES {
+            for tile_x in 0..BLOCK_TILES {
+                let Some(tile) = til
+                    .tiles
+                    .get(tile_y)
+                    .and_then(|row| row.get(tile_x))
+                    .and_then(|tile| self.zon.tiles.get(tile.tile_id as usize))
+                else {
+                    continue;
+                };
+
+                let textures = [tile.layer1 + tile.offset1, tile.layer2 + tile.offset2]
+                    .into_iter()
+                    .filter_map(|index| usize::try_from(index).ok())
+                    .filter_map(|index| self.zon.textures.get(index))
+                    .collect::<Vec<_>>();
+                for (rule, rule_densities) in self.options.rules.iter().zip(densities.iter_mut()) {
+                    if textures.iter().any(|texture| rule.matches(texture)) {
+                        rule_densities[tile_y][tile_x] = rule.density;
+                    }
+                }
+            }
+        }
+        densities
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn plant_block(
+        &self,
+        root: &mut gltf_json::Root,
+        binary_data: &mut BinaryData,
+        block_x: i32,
+        block_y: i32,
+        him: &Heightmap,
+        til: &Tilemap,
+        excluded_tiles: Option<&[[bool; BLOCK_TILES]; BLOCK_TILES]>,
+    ) {
+        let mut densities = self.tile_densities(til);
+        if let Some(excluded_tiles) = excluded_tiles {
+            for rule_densities in densities.iter_mut() {
+                for (density, &excluded) in rule_densities
+                    .iter_mut()
+                    .flatten()
+                    .zip(excluded_tiles.iter().flatten())
+                {
+                    if excluded {
+                        *density = 0.0;
+                    }
+                }
+            }
+        }
+        if densities
+            .iter()
+            .all(|rule_densities| rule_densities.iter().flatten().all(|&d| d <= 0.0))
+        {
+            return;
+        }
+
+        match self.options.mode {
+            VegetationMode::Instanced => {
+                for (rule_index, rule_densities) in densities.iter().enumerate() {
+                    self.plant_instances(
+                        root,
+                        binary_data,
+                        block_x,
+                        block_y,
+                        him,
+                        rule_index,
+                        rule_densities,
+                    );
+                }
+            }
+            VegetationMode::DensityMap => {
+                let layers = self
+                    .options
+                    .rules
+                    .iter()
+                    .zip(densities.iter())
+                    .map(|(rule, rule_densities)| {
+                        json!({
+                            "texture": rule.texture,
+                            "width": rule.width,
+                            "height": rule.height,
+                            "density": rule_densities,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let node_index = Index::new(root.nodes.len() as u32);
+                root.nodes.push(scene::Node {
+                    name: Some(format!("{}_{}_vegetation", block_x, block_y)),
+                    camera: None,
+                    children: None,
+                    extensions: Default::default(),
+                    extras: Some(
+                        RawValue::from_string(
+                            json!({
+                                "rose_vegetation": {
+                                    "tile_size": TILE_SIZE,
+                                    "layers": layers,
+                                }
+                            })
+                            .to_string(),
+                        )
+                        .unwrap(),
+                    ),
+                    matrix: None,
+                    mesh: None,
+                    rotation: None,
+                    scale: None,
+                    translation: Some(block_translation(block_x, block_y)),
+                    skin: None,
+                    weights: None,
+                });
+                root.scenes[0].nodes.push(node_index);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn plant_instances(
+        &self,
+        root: &mut gltf_json::Root,
+        binary_data: &mut BinaryData,
+        block_x: i32,
+        block_y: i32,
+        him: &Heightmap,
+        rule_index: usize,
+        densities: &[[f32; BLOCK_TILES]; BLOCK_TILES],
+    ) {
+        let mut random = Random::new(
+            self.options.seed
+                ^ ((block_x as u64) << 32)
+                ^ ((block_y as u64) << 16)
+                ^ rule_index as u64,
+        );
+        let mut translations = Vec::new();
+        let mut rotations = Vec::new();
+        let mut scales = Vec::new();
+        for (tile_y, row) in densities.iter().enumerate() {
+            for (tile_x, density) in row.iter().enumerate() {
+                // Plant the expected number of cards, the fraction is planted
+                // with the matching probability
+                let expected = density * TILE_SIZE * TILE_SIZE;
+                let count =
+                    expected.floor() as usize + (random.next_f32() < expected.fract()) as usize;
+                for _ in 0..count {
+                    let x = (tile_x as f32 + random.next_f32()) * TILE_SIZE;
+                    let z = (tile_y as f32 + random.next_f32()) * TILE_SIZE;
+                    translations.extend([x, terrain_height(him, x, z), z]);
+                    rotations.extend(
+                        Quat::from_rotation_y(random.next_f32() * std::f32::consts::TAU).to_array(),
+                    );
+                    let scale = 0.8 + random.next_f32() * 0.4;
+                    scales.extend([scale, scale, scale]);
+                }
+            }
+        }
+        if translations.is_empty() {
+            return;
+        }
+
+        let name = format!("{}_{}_vegetation_{}", block_x, block_y, rule_index);
+        let translation = push_f32_accessor(
+            root,
+            binary_data,
+            &format!("{}_translation", name),
+            accessor::Type::Vec3,
+            &translations,
+        );
+        let rotation = push_f32_accessor(
+            root,
+            binary_data,
+            &format!("{}_rotation", name),
+            accessor::Type::Vec4,
+            &rotations,
+        );
+        let scale = push_f32_accessor(
+            root,
+            binary_data,
+            &format!("{}_scale", name),
+            accessor::Type::Vec3,
+            &scales,
+        );
+
+        let mut extensions = gltf_json::extensions::scene::Node::default();
+        extensions.others.insert(
+            "EXT_mesh_gpu_instancing".to_string(),
+            json!({
+                "attributes": {
+                    "TRANSLATION": translation.value(),
+                    "ROTATION": rotation.value(),
+                    "SCALE": scale.value(),
+                }
+            }),
+        );
+
+        let node_index = Index::new(root.nodes.len() as u32);
+        root.nodes.push(scene::Node {
+            name: Some(name),
+            camera: None,
+            children: None,
+            extensions: Some(extensions),
+            extras: Default::default(),
+            matrix: None,
+            mesh: Some(self.meshes[rule_index]),
+            rotation: None,
+            scale: None,
+            translation: Some(block_translation(block_x, block_y)),
+            skin: None,
+            weights: None,
+        });
+        root.scenes[0].nodes.push(node_index);
+    }
+}
@@ -0,0 +1,62 @@
+//! Correcting the winding of meshes which appear inside-out, with their back
+//! faces towards the viewer.
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+/// Which meshes have the winding of their triangles reversed when converting
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindingCorrection {
+    /// Keep the winding of every mesh
+    #[default]
+    None,
+
+    /// Reverse meshes which enclose a negative volume, those whose
+    /// triangles face inwards. Open meshes such as planes enclose no volume
+    /// and are kept as they are.
+    Auto,
+
+    /// Reverse every mesh
+    Flip,
+}
+
+impl WindingCorrection {
+    /// Whether the winding of a mesh should be reversed. ROSE to glTF is a
+    /// rotation, so the answer is the same in both coordinate systems.
+    pub(crate) fn should_flip(self, triangles: &[[usize; 3]], positions: &[Vec3]) -> bool {
+        match self {
+            WindingCorrection::None => false,
+            WindingCorrection::Flip => true,
+            WindingCorrection::Auto => {
+                let (min, max) = positions
+                    .iter()
+                    .fold((Vec3::MAX, Vec3::MIN), |(min, max), position| {
+                        (min.min(*position), max.max(*position))
+                    });
+                let size = (max - min).length();
+                signed_volume(triangles, positions) < -1e-4 * size * size * size
+            }
+        }
+    }
+}
+
+/// Volume enclosed by the triangles, negative when they face inwards
+fn signed_volume(triangles: &[[usize; 3]], positions: &[Vec3]) -> f32 {
+    // Relative to the centroid to keep the precision of meshes far from the
+    // origin
+    let centroid = positions.iter().sum::<Vec3>() / positions.len().max(1) as f32;
+    let volume: f64 = triangles
+        .iter()
+        .filter_map(|triangle| {
+            let [a, b, c] = triangle.map(|index| positions.get(index).map(|p| *p - centroid));
+            Some(a?.dot(b?.cross(c?)) as f64)
+        })
+        .sum();
+    (volume / 6.0) as f32
+}
+
+/// Reverses the winding of each triangle
+pub(crate) fn flip_triangles(triangles: &mut [[usize; 3]]) {
+    for triangle in triangles.iter_mut() {
+        triangle.swap(1, 2);
+    }
+}
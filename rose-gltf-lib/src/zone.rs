@@ -1,11 +1,12 @@
 use std::{
-    io::Cursor,
+    collections::HashMap,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use anyhow::Context;
-use bytes::{BufMut, BytesMut};
-use glam::{EulerRot, Quat, Vec2, Vec3};
+use bytes::BufMut;
+use glam::{EulerRot, Quat, Vec2, Vec3, Vec4};
 use gltf_json::{
     buffer, extensions, material, mesh,
     scene::{self, UnitQuaternion},
@@ -13,17 +14,42 @@ use gltf_json::{
     validation::{Checked, USize64},
     Index,
 };
+use image::DynamicImage;
 use rose_file_lib::{
-    files::{him::Heightmap, ifo::MapData, til::Tilemap, zon, HIM, IFO, TIL, ZMO},
+    files::{
+        him::Heightmap,
+        ifo::{EffectData, MapData, ObjectData, SoundData, WaterData},
+        til::Tilemap,
+        zon, zsc, EFT, HIM, IFO, TIL, ZMO, ZSC,
+    },
     io::RoseFile,
 };
-use serde_json::value::RawValue;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, value::RawValue};
 
 use crate::{
     animation::{load_animation, GetAnimationChannelNode},
+    assets::AssetLocator,
+    billboard::Billboard,
+    cache::{load_cached_image, AssetCache},
+    cancel::check_cancelled,
+    embedded_image::EmbeddedImageOptions,
+    engine_hints::{part_collider, EngineHints},
+    error::{CategorizedError, CategoryContext, ErrorCategory},
+    finish_gltf,
+    heightmap::him_extras,
+    light_container::{add_punctual_light, load_light_container},
     mesh_builder::{MeshBuilder, MeshData},
-    object_list::ObjectList,
+    new_gltf_root,
+    object_list::{placeholder_texture, push_texture, ObjectList},
     pad_align,
+    provenance::add_node_provenance,
+    region::ZoneContent,
+    report::{time_phase, warn},
+    spawn_volume::SpawnVolumes,
+    spill::BinaryData,
+    vegetation::Vegetation,
+    RoseGltfConvOptions,
 };
 
 struct BlockData {
@@ -32,27 +58,135 @@ struct BlockData {
     pub ifo: MapData,
     pub him: Heightmap,
     pub til: Tilemap,
+
+    /// The block's planelightingmap, only loaded when a `TerrainLightmap`
+    /// mode is chosen
+    pub lightmap: Option<Arc<DynamicImage>>,
+}
+
+/// How the baked terrain lighting of each block is exported
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TerrainLightmap {
+    /// Do not export the lightmaps
+    #[default]
+    None,
+
+    /// Sample the lightmap into the COLOR_0 of the terrain mesh
+    VertexColor,
+
+    /// Add the lightmap as the occlusion texture of the terrain material,
+    /// using TEXCOORD_0, or TEXCOORD_2 with `TerrainUvOptions::lightmap_uv`
+    Texture,
+}
+
+/// The texture coordinates of the terrain besides TEXCOORD_0, which covers
+/// each block once for its baked texture
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TerrainUvOptions {
+    /// How many times a detail texture using TEXCOORD_1 repeats across a
+    /// block, by default once per tile like the tile textures of the client
+    pub detail_repeat: f32,
+
+    /// Add TEXCOORD_2 covering each block once for lightmaps, for engines
+    /// which expect lightmaps to have their own texture coordinates
+    pub lightmap_uv: bool,
+}
+
+impl Default for TerrainUvOptions {
+    fn default() -> Self {
+        Self {
+            detail_repeat: 16.0,
+            lightmap_uv: false,
+        }
+    }
+}
+
+/// Path of the baked terrain lighting of a block, relative to the map
+pub(crate) fn terrain_lightmap_path(block_x: i32, block_y: i32) -> String {
+    format!("{0}_{1}/{0}_{1}_planelightingmap.dds", block_x, block_y)
+}
+
+/// Loads the lightmap of a block for the `TerrainLightmap` mode, if there is
+/// one
+fn load_block_lightmap(
+    map_path: &Path,
+    block_x: i32,
+    block_y: i32,
+    options: &RoseGltfConvOptions,
+) -> Option<Arc<DynamicImage>> {
+    if options.terrain_lightmap == TerrainLightmap::None {
+        return None;
+    }
+
+    let lightmap_path = map_path.join(terrain_lightmap_path(block_x, block_y));
+    match load_cached_image(options.image_cache(), &lightmap_path) {
+        Ok(lightmap) => Some(lightmap),
+        Err(error) => {
+            warn(format!(
+                "Failed to load lightmap {}: {}",
+                lightmap_path.display(),
+                error
+            ));
+            None
+        }
+    }
+}
+
+/// The lightmap of a block, which in `low_memory` mode is loaded each time it
+/// is used instead of kept with the block
+fn block_lightmap(
+    block: &BlockData,
+    map_path: &Path,
+    options: &RoseGltfConvOptions,
+) -> Option<Arc<DynamicImage>> {
+    match block.lightmap.as_ref() {
+        Some(lightmap) => Some(lightmap.clone()),
+        None if options.low_memory => {
+            load_block_lightmap(map_path, block.block_x, block.block_y, options)
+        }
+        None => None,
+    }
+}
+
+/// Color of the lightmap at a UV of the terrain mesh
+fn sample_lightmap(lightmap: &image::RgbaImage, uv: Vec2) -> Vec4 {
+    let color = image::imageops::sample_bilinear(lightmap, uv.x, uv.y)
+        .unwrap_or(image::Rgba([255, 255, 255, 255]));
+    Vec4::new(
+        color[0] as f32 / 255.0,
+        color[1] as f32 / 255.0,
+        color[2] as f32 / 255.0,
+        1.0,
+    )
 }
 
-fn convert_position(position: rose_file_lib::utils::Vector3<f32>) -> [f32; 3] {
+pub(crate) fn convert_position(position: rose_file_lib::utils::Vector3<f32>) -> [f32; 3] {
     [position.x / 100.0, position.z / 100.0, -position.y / 100.0]
 }
 
-fn convert_scale(scale: rose_file_lib::utils::Vector3<f32>) -> [f32; 3] {
+pub(crate) fn convert_scale(scale: rose_file_lib::utils::Vector3<f32>) -> [f32; 3] {
     [scale.x, scale.z, scale.y]
 }
 
-fn convert_rotation(rotation: rose_file_lib::utils::Quaternion) -> UnitQuaternion {
+pub(crate) fn convert_rotation(rotation: rose_file_lib::utils::Quaternion) -> UnitQuaternion {
     UnitQuaternion([rotation.x, rotation.z, -rotation.y, rotation.w])
 }
 
+#[allow(clippy::too_many_arguments)]
 fn generate_terrain_materials(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
+    binary_data: &mut BinaryData,
     zon: &zon::Zone,
-    assets_path: &Path,
+    assets: &dyn AssetLocator,
+    asset_cache: Option<&AssetCache>,
     blocks: &[BlockData],
-) -> Vec<Index<material::Material>> {
+    sampler_index: Index<texture::Sampler>,
+    name_prefix: &str,
+    ignore_missing_assets: bool,
+    placeholder_textures: bool,
+    embedded_images: &EmbeddedImageOptions,
+) -> anyhow::Result<Vec<Index<material::Material>>> {
+    let _timer = time_phase("terrain_materials");
     let texture_size = 1024;
     let texture_tile_size = texture_size / 16;
     let mut tile_images = Vec::with_capacity(zon.textures.len());
@@ -62,36 +196,61 @@ fn generate_terrain_materials(
             break;
         }
 
-        let mut tile_image =
-            image::open(assets_path.join(tile_texure_path)).expect("Failed to load DDS");
-        if tile_image.width() != texture_tile_size {
-            tile_image = tile_image.resize(
+        if ignore_missing_assets && assets.locate(tile_texure_path).is_none() {
+            warn(format!(
+                "Missing tile texture {}, using a placeholder",
+                tile_texure_path
+            ));
+            tile_images.push(placeholder_texture(
+                tile_texure_path,
                 texture_tile_size,
-                texture_tile_size,
-                image::imageops::FilterType::Triangle,
+                placeholder_textures,
+            ));
+            continue;
+        }
+
+        let tile_image = match load_cached_image(asset_cache, &assets.resolve(tile_texure_path)) {
+            Err(error) if placeholder_textures => {
+                warn(format!(
+                    "Failed to read {} with error {}, using a placeholder",
+                    tile_texure_path, error
+                ));
+                tile_images.push(placeholder_texture(
+                    tile_texure_path,
+                    texture_tile_size,
+                    true,
+                ));
+                continue;
+            }
+            result => result.category_context(ErrorCategory::MissingDependency, || {
+                format!("Failed to load {}", tile_texure_path)
+            })?,
+        };
+        if tile_image.width() != texture_tile_size {
+            tile_images.push(
+                tile_image
+                    .resize(
+                        texture_tile_size,
+                        texture_tile_size,
+                        image::imageops::FilterType::Triangle,
+                    )
+                    .to_rgba8(),
             );
+        } else {
+            tile_images.push(tile_image.to_rgba8());
         }
-        tile_images.push(tile_image.to_rgba8());
     }
 
-    let sampler_index = Index::<texture::Sampler>::new(root.samplers.len() as u32);
-    root.samplers.push(texture::Sampler {
-        name: Some("terrain_sampler".to_string()),
-        mag_filter: Some(Checked::Valid(texture::MagFilter::Linear)),
-        min_filter: Some(Checked::Valid(texture::MinFilter::LinearMipmapLinear)),
-        wrap_s: Checked::Valid(texture::WrappingMode::ClampToEdge),
-        wrap_t: Checked::Valid(texture::WrappingMode::ClampToEdge),
-        extensions: None,
-        extras: Default::default(),
-    });
-
     let mut block_materials = Vec::new();
     for block in blocks.iter() {
         let mut image = image::RgbImage::new(texture_size, texture_size);
 
-        // Rasterise the tilemap to a single image
+        // Rasterise the tilemap to a single image, holes are left black
         for tile_x in 0..16 {
             for tile_y in 0..16 {
+                if is_terrain_hole(zon, &block.til, tile_x, tile_y) {
+                    continue;
+                }
                 let tile = &zon.tiles[block.til.tiles[tile_y][tile_x].tile_id as usize];
                 let tile_index1 = (tile.layer1 + tile.offset1) as usize;
                 let tile_index2 = (tile.layer2 + tile.offset2) as usize;
@@ -214,16 +373,15 @@ fn generate_terrain_materials(
             }
         }
 
-        let (texture_data_start, texture_data_length) = {
-            let mut buffer: Vec<u8> = Vec::new();
-            image
-                .write_to(&mut Cursor::new(&mut buffer), image::ImageFormat::Png)
-                .expect("Failed to write PNG");
+        let (mime_type, texture_data_start, texture_data_length) = {
+            let (buffer, mime_type) =
+                embedded_images.encode(&DynamicImage::ImageRgb8(image), false);
             pad_align(binary_data);
             let texture_data_start = binary_data.len() as u32;
             binary_data.put_slice(&buffer);
             pad_align(binary_data);
             (
+                mime_type,
                 texture_data_start,
                 binary_data.len() as u32 - texture_data_start,
             )
@@ -232,8 +390,8 @@ fn generate_terrain_materials(
         let buffer_index = Index::new(root.buffer_views.len() as u32);
         root.buffer_views.push(buffer::View {
             name: Some(format!(
-                "{}_{}_tilemap_image_buffer",
-                block.block_x, block.block_y,
+                "{}{}_{}_tilemap_image_buffer",
+                name_prefix, block.block_x, block.block_y,
             )),
             buffer: Index::new(0),
             byte_length: USize64::from(texture_data_length as usize),
@@ -246,9 +404,12 @@ fn generate_terrain_materials(
 
         let image_index = Index::new(root.images.len() as u32);
         root.images.push(gltf_json::image::Image {
-            name: Some(format!("{}_{}_tilemap_image", block.block_x, block.block_y,)),
+            name: Some(format!(
+                "{}{}_{}_tilemap_image",
+                name_prefix, block.block_x, block.block_y,
+            )),
             buffer_view: Some(buffer_index),
-            mime_type: Some(gltf_json::image::MimeType("image/png".into())),
+            mime_type: Some(gltf_json::image::MimeType(mime_type.into())),
             uri: None,
             extensions: None,
             extras: Default::default(),
@@ -257,8 +418,8 @@ fn generate_terrain_materials(
         let texture_index = Index::new(root.textures.len() as u32);
         root.textures.push(texture::Texture {
             name: Some(format!(
-                "{}_{}_tilemap_texture",
-                block.block_x, block.block_y,
+                "{}{}_{}_tilemap_texture",
+                name_prefix, block.block_x, block.block_y,
             )),
             sampler: Some(sampler_index),
             source: image_index,
@@ -269,8 +430,8 @@ fn generate_terrain_materials(
         let material_index = Index::<material::Material>::new(root.materials.len() as u32);
         root.materials.push(material::Material {
             name: Some(format!(
-                "{}_{}_tilemap_material",
-                block.block_x, block.block_y,
+                "{}{}_{}_tilemap_material",
+                name_prefix, block.block_x, block.block_y,
             )),
             alpha_cutoff: None,
             alpha_mode: Checked::Valid(material::AlphaMode::Opaque),
@@ -300,15 +461,57 @@ fn generate_terrain_materials(
         block_materials.push(material_index);
     }
 
-    block_materials
+    Ok(block_materials)
+}
+
+/// Size of a block's terrain in glTF units
+pub(crate) const BLOCK_SIZE: f32 = 160.0;
+
+/// Translation of a block's terrain in glTF coordinates, the terrain covers
+/// `BLOCK_SIZE` along +x and +z from here.
+pub(crate) fn block_translation(block_x: i32, block_y: i32) -> [f32; 3] {
+    let offset_x = (BLOCK_SIZE * block_x as f32) - 5200.0;
+    let offset_y = (BLOCK_SIZE * (65.0 - block_y as f32)) - 5200.0;
+    [offset_x, 0.0, -offset_y]
+}
+
+/// A tile is a hole in the terrain when it is flagged as one in the TIL, or
+/// when its zone tile has no texture.
+pub(crate) fn is_terrain_hole(
+    zon: &zon::Zone,
+    til: &Tilemap,
+    tile_x: usize,
+    tile_y: usize,
+) -> bool {
+    match til.tiles.get(tile_y).and_then(|row| row.get(tile_x)) {
+        Some(tile) if !tile.is_hole() => zon
+            .tiles
+            .get(tile.tile_id as usize)
+            .is_none_or(|zone_tile| zone_tile.layer1 + zone_tile.offset1 < 0),
+        _ => true,
+    }
+}
+
+/// Finds the tiles of a block which are holes in the terrain, indexed by
+/// `[tile_y][tile_x]`
+fn terrain_holes(zon: &zon::Zone, til: &Tilemap) -> [[bool; 16]; 16] {
+    std::array::from_fn(|tile_y| {
+        std::array::from_fn(|tile_x| is_terrain_hole(zon, til, tile_x, tile_y))
+    })
 }
 
+#[allow(clippy::too_many_arguments)]
 fn generate_terrain_mesh(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
+    binary_data: &mut BinaryData,
     block: &BlockData,
+    holes: &[[bool; 16]; 16],
     use_better_heightmap_triangles: bool,
+    vertex_lightmap: Option<&DynamicImage>,
+    f32_colors: bool,
+    terrain_uvs: &TerrainUvOptions,
 ) -> MeshData {
+    let _timer = time_phase("terrain_mesh");
     let mut positions = Vec::new();
     let mut normals = Vec::new();
     let mut uvs = Vec::new();
@@ -316,6 +519,10 @@ fn generate_terrain_mesh(
 
     for tile_x in 0..16 {
         for tile_y in 0..16 {
+            if holes[tile_y as usize][tile_x as usize] {
+                continue;
+            }
+
             let tile_indices_base = positions.len() as u16;
             let tile_offset_x = tile_x as f32 * 4.0 * 2.5;
             let tile_offset_y = tile_y as f32 * 4.0 * 2.5;
@@ -398,10 +605,26 @@ fn generate_terrain_mesh(
     }
 
     let mut mesh_builder = MeshBuilder::new();
+    mesh_builder.set_f32_colors(f32_colors);
     mesh_builder.add_positions(positions);
     mesh_builder.add_normals(normals);
     mesh_builder.add_uv0(uvs.clone());
-    mesh_builder.add_uv1(uvs.clone());
+    mesh_builder.add_uv1(
+        uvs.iter()
+            .map(|&uv| uv * terrain_uvs.detail_repeat)
+            .collect(),
+    );
+    if terrain_uvs.lightmap_uv {
+        mesh_builder.add_uv2(uvs.clone());
+    }
+    if let Some(lightmap) = vertex_lightmap {
+        let lightmap = lightmap.to_rgba8();
+        mesh_builder.add_color(
+            uvs.iter()
+                .map(|&uv| sample_lightmap(&lightmap, uv))
+                .collect(),
+        );
+    }
     mesh_builder.add_indices(indices);
     mesh_builder.build(
         root,
@@ -410,35 +633,29 @@ fn generate_terrain_mesh(
     )
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn load_zone(
-    root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
-    zon: &zon::Zone,
-    assets_path: PathBuf,
-    map_path: PathBuf,
-    deco: &mut ObjectList,
-    cnst: &mut ObjectList,
-    use_better_heightmap_triangles: bool,
-    filter_block_x: Option<i32>,
-    filter_block_y: Option<i32>,
-) -> anyhow::Result<()> {
-    // Add a directional light to the scene
-    root.extensions_used.push("KHR_lights_punctual".to_string());
-    root.extensions = Some(extensions::Root {
-        khr_lights_punctual: Some(extensions::root::KhrLightsPunctual {
-            lights: vec![extensions::scene::khr_lights_punctual::Light {
-                name: Some("the_sun".to_string()),
-                color: [0.88, 0.87, 0.84],
-                intensity: 4098.0,
-                type_: Checked::Valid(extensions::scene::khr_lights_punctual::Type::Directional),
-                range: None,
-                spot: None,
-                extensions: Default::default(),
-                extras: Default::default(),
-            }],
-        }),
-    });
+/// A glTF of only the light of the sun, for assembling a zone from blocks
+/// which were converted without it
+pub(crate) fn sun_gltf() -> anyhow::Result<gltf::Gltf> {
+    let mut root = new_gltf_root();
+    add_sun_light(&mut root);
+    finish_gltf(root, BinaryData::new(false))
+}
+
+/// Adds a directional light for the sun to the scene
+fn add_sun_light(root: &mut gltf_json::Root) {
+    let light = add_punctual_light(
+        root,
+        extensions::scene::khr_lights_punctual::Light {
+            name: Some("the_sun".to_string()),
+            color: [0.88, 0.87, 0.84],
+            intensity: 4098.0,
+            type_: Checked::Valid(extensions::scene::khr_lights_punctual::Type::Directional),
+            range: None,
+            spot: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+        },
+    );
     let light_direction = Quat::from_euler(
         EulerRot::ZYX,
         0.0,
@@ -449,8 +666,9 @@ pub fn load_zone(
     root.nodes.push(scene::Node {
         extensions: Some(extensions::scene::Node {
             khr_lights_punctual: Some(extensions::scene::khr_lights_punctual::KhrLightsPunctual {
-                light: Index::new(0),
+                light,
             }),
+            ..Default::default()
         }),
         camera: None,
         children: None,
@@ -465,6 +683,37 @@ pub fn load_zone(
         weights: None,
     });
     root.scenes[0].nodes.push(light_node);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn load_zone(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    zon: &zon::Zone,
+    variant_zons: &[(String, zon::Zone)],
+    assets: &dyn AssetLocator,
+    map_path: PathBuf,
+    deco: &mut ObjectList,
+    cnst: &mut ObjectList,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<()> {
+    let filter_block_x = options.filter_block_x;
+    let filter_block_y = options.filter_block_y;
+    let include_disabled_objects = options.include_disabled_objects;
+    let content = &options.zone_content;
+    let is_exported = |kind: ZoneContent, position: rose_file_lib::utils::Vector3<f32>| {
+        let position = convert_position(position);
+        content.includes(kind)
+            && options.height_range.contains(position)
+            && options
+                .radius_filter
+                .as_ref()
+                .is_none_or(|radius_filter| radius_filter.contains(position))
+    };
+
+    if content.includes(ZoneContent::Sun) {
+        add_sun_light(root);
+    }
 
     // Find all blocks
     let mut blocks = Vec::new();
@@ -477,17 +726,39 @@ pub fn load_zone(
             if filter_block_y.is_some() && Some(block_y) != filter_block_y {
                 continue;
             }
+            check_cancelled(options.cancellation.as_ref())?;
 
-            let ifo = IFO::from_path(&map_path.join(format!("{}_{}.ifo", block_x, block_y)));
-            let him = HIM::from_path(&map_path.join(format!("{}_{}.him", block_x, block_y)));
-            let til = TIL::from_path(&map_path.join(format!("{}_{}.til", block_x, block_y)));
+            let ifo = IFO::from_path_mmap(&map_path.join(format!("{}_{}.ifo", block_x, block_y)));
+            let him = HIM::from_path_mmap(&map_path.join(format!("{}_{}.him", block_x, block_y)));
+            let til = TIL::from_path_mmap(&map_path.join(format!("{}_{}.til", block_x, block_y)));
             if let (Ok(ifo), Ok(him), Ok(til)) = (ifo, him, til) {
+                // Objects can be placed outside the terrain of their block
+                if let Some(radius_filter) = options.radius_filter.as_ref() {
+                    if !radius_filter.intersects_block(block_x, block_y)
+                        && !ifo
+                            .objects
+                            .iter()
+                            .any(|object| is_exported(ZoneContent::Deco, object.position))
+                        && !ifo
+                            .buildings
+                            .iter()
+                            .any(|object| is_exported(ZoneContent::Cnst, object.position))
+                    {
+                        continue;
+                    }
+                }
+
                 blocks.push(BlockData {
                     block_x,
                     block_y,
                     ifo,
                     him,
                     til,
+                    lightmap: if options.low_memory {
+                        None
+                    } else {
+                        load_block_lightmap(&map_path, block_x, block_y, options)
+                    },
                 });
             }
         }
@@ -497,71 +768,275 @@ pub fn load_zone(
 
     // Load all meshes and materials from used objects
     for block in blocks.iter() {
-        if !block.ifo.oceans.is_empty() && ocean_material.is_none() {
-            ocean_material = Some(Index::new(root.materials.len() as u32));
-            root.materials.push(material::Material {
-                name: Some("ocean_material".to_string()),
-                alpha_cutoff: None,
-                alpha_mode: Checked::Valid(material::AlphaMode::Blend),
-                double_sided: true,
-                pbr_metallic_roughness: material::PbrMetallicRoughness {
-                    base_color_factor: material::PbrBaseColorFactor([0.32, 0.46, 0.7, 0.6]),
-                    base_color_texture: None,
-                    metallic_factor: material::StrengthFactor(0.5),
-                    roughness_factor: material::StrengthFactor(0.5),
-                    metallic_roughness_texture: None,
-                    extensions: None,
-                    extras: Default::default(),
-                },
-                normal_texture: None,
-                occlusion_texture: None,
-                emissive_texture: None,
-                emissive_factor: material::EmissiveFactor([0.0, 0.0, 0.0]),
-                extensions: None,
-                extras: Default::default(),
-            });
+        let has_water = !block.ifo.oceans.is_empty()
+            || block
+                .ifo
+                .waters
+                .iter()
+                .any(|water| water.has_water.contains(&true));
+        if has_water
+            && ocean_material.is_none()
+            && (content.includes(ZoneContent::Ocean) || content.includes(ZoneContent::Water))
+        {
+            ocean_material = Some(create_water_material(
+                root,
+                binary_data,
+                assets,
+                options.image_cache(),
+                &options.water_material,
+                &options.embedded_images,
+            ));
         }
 
         for block_objects in block.ifo.objects.iter() {
+            if !include_disabled_objects && is_object_disabled(block_objects) {
+                continue;
+            }
+            if !is_exported(ZoneContent::Deco, block_objects.position) {
+                continue;
+            }
+            check_cancelled(options.cancellation.as_ref())?;
+
             deco.load_object(
                 "deco",
                 block_objects.object_id as usize,
                 root,
                 binary_data,
-                &assets_path,
+                assets,
             )
             .context("Failed to load deco object")?;
         }
 
         for block_objects in block.ifo.buildings.iter() {
+            if !include_disabled_objects && is_object_disabled(block_objects) {
+                continue;
+            }
+            if !is_exported(ZoneContent::Cnst, block_objects.position) {
+                continue;
+            }
+            check_cancelled(options.cancellation.as_ref())?;
+
             cnst.load_object(
                 "cnst",
                 block_objects.object_id as usize,
                 root,
                 binary_data,
-                &assets_path,
+                assets,
             )
             .context("Failed to load deco object")?;
         }
     }
 
-    let block_terrain_materials =
-        generate_terrain_materials(root, binary_data, zon, &assets_path, &blocks);
+    let sampler_index = Index::<texture::Sampler>::new(root.samplers.len() as u32);
+    root.samplers.push(texture::Sampler {
+        name: Some("terrain_sampler".to_string()),
+        mag_filter: Some(Checked::Valid(texture::MagFilter::Linear)),
+        min_filter: Some(Checked::Valid(texture::MinFilter::LinearMipmapLinear)),
+        wrap_s: Checked::Valid(texture::WrappingMode::ClampToEdge),
+        wrap_t: Checked::Valid(texture::WrappingMode::ClampToEdge),
+        extensions: None,
+        extras: Default::default(),
+    });
 
-    // Spawn all block nodes
-    for (block, block_terrain_material) in blocks.iter().zip(block_terrain_materials.iter()) {
-        // Load heightmap
-        load_heightmap(
+    // Without the terrain, blocks have no terrain materials
+    let include_terrain = content.includes(ZoneContent::Terrain);
+    let block_terrain_materials = if include_terrain {
+        generate_terrain_materials(
             root,
             binary_data,
-            block,
-            use_better_heightmap_triangles,
-            block_terrain_material,
-        );
+            zon,
+            assets,
+            options.image_cache(),
+            &blocks,
+            sampler_index,
+            "",
+            options.ignore_missing_assets,
+            options.placeholder_textures,
+            &options.embedded_images,
+        )?
+    } else {
+        Vec::new()
+    };
+
+    // Generate the terrain materials for each variant, the first variant is
+    // the default zon so viewers are able to switch back to it.
+    let mut block_variant_materials = vec![Vec::new(); blocks.len()];
+    if !variant_zons.is_empty() && include_terrain {
+        let mut variants = vec![extensions::scene::khr_materials_variants::Variant {
+            name: "default".to_string(),
+        }];
+        for (block_materials, material) in block_variant_materials
+            .iter_mut()
+            .zip(block_terrain_materials.iter())
+        {
+            block_materials.push((*material, 0));
+        }
+
+        for (variant_name, variant_zon) in variant_zons.iter() {
+            if variant_zon.tiles.len() < zon.tiles.len() {
+                return Err(CategorizedError::error(
+                    ErrorCategory::Validation,
+                    format!(
+                        "Material variant {} has {} tiles but the zone uses {}",
+                        variant_name,
+                        variant_zon.tiles.len(),
+                        zon.tiles.len()
+                    ),
+                ));
+            }
+
+            let variant_index = variants.len() as u32;
+            variants.push(extensions::scene::khr_materials_variants::Variant {
+                name: variant_name.clone(),
+            });
+
+            let variant_materials = generate_terrain_materials(
+                root,
+                binary_data,
+                variant_zon,
+                assets,
+                options.image_cache(),
+                &blocks,
+                sampler_index,
+                &format!("{}_", variant_name),
+                options.ignore_missing_assets,
+                options.placeholder_textures,
+                &options.embedded_images,
+            )?;
+            for (block_materials, material) in
+                block_variant_materials.iter_mut().zip(variant_materials)
+            {
+                block_materials.push((material, variant_index));
+            }
+        }
+
+        root.extensions_used
+            .push("KHR_materials_variants".to_string());
+        root.extensions
+            .get_or_insert_with(Default::default)
+            .khr_materials_variants = Some(extensions::root::KhrMaterialsVariants { variants });
+    }
+
+    if options.terrain_lightmap == TerrainLightmap::Texture {
+        for ((block, block_terrain_material), block_variant_materials) in blocks
+            .iter()
+            .zip(block_terrain_materials.iter())
+            .zip(block_variant_materials.iter())
+        {
+            let Some(lightmap) = block_lightmap(block, &map_path, options) else {
+                continue;
+            };
+
+            let texture_index = push_texture(
+                root,
+                binary_data,
+                &format!("{}_{}_lightmap", block.block_x, block.block_y),
+                &lightmap,
+                sampler_index,
+                &options.embedded_images,
+                false,
+            );
+            let materials = std::iter::once(*block_terrain_material).chain(
+                block_variant_materials
+                    .iter()
+                    .map(|(material, _)| *material),
+            );
+            for material in materials {
+                root.materials[material.value()].occlusion_texture =
+                    Some(material::OcclusionTexture {
+                        index: texture_index,
+                        strength: material::StrengthFactor(1.0),
+                        tex_coord: if options.terrain_uvs.lightmap_uv {
+                            2
+                        } else {
+                            0
+                        },
+                        extensions: None,
+                        extras: Default::default(),
+                    });
+            }
+        }
+    }
+
+    let mut effect_billboards = (options.effect_billboards
+        && content.includes(ZoneContent::Effects))
+    .then(|| EffectBillboards::new(root, binary_data, deco.sampler, deco.embedded_images));
+    let vegetation = options
+        .vegetation
+        .as_ref()
+        .filter(|_| content.includes(ZoneContent::Vegetation))
+        .map(|vegetation| {
+            Vegetation::new(
+                root,
+                binary_data,
+                vegetation,
+                zon,
+                assets,
+                options.asset_cache.clone(),
+                options.low_memory,
+                deco.embedded_images,
+                deco.sampler,
+            )
+        });
+
+    let mut spawn_volumes = None;
+
+    // Spawn all block nodes
+    for (block_index, block) in blocks.iter().enumerate() {
+        check_cancelled(options.cancellation.as_ref())?;
+        let first_block_node = root.nodes.len();
+        let first_scene_node = root.scenes[0].nodes.len();
+
+        // Load heightmap
+        let tiles_outside = options
+            .radius_filter
+            .map(|radius_filter| radius_filter.tiles_outside(block.block_x, block.block_y));
+        if let Some(block_terrain_material) = block_terrain_materials.get(block_index) {
+            let vertex_lightmap = (options.terrain_lightmap == TerrainLightmap::VertexColor)
+                .then(|| block_lightmap(block, &map_path, options))
+                .flatten();
+            load_heightmap(
+                root,
+                binary_data,
+                zon,
+                block,
+                options.use_better_heightmap_triangles,
+                vertex_lightmap.as_deref(),
+                options.f32_vertex_colors,
+                block_terrain_material,
+                &block_variant_materials[block_index],
+                options.engine_hints,
+                options.heightmap_extras,
+                tiles_outside.as_ref(),
+                &options.terrain_uvs,
+            );
+        }
+        let first_other_node = root.scenes[0].nodes.len();
+
+        if let Some(vegetation) = vegetation.as_ref() {
+            vegetation.plant_block(
+                root,
+                binary_data,
+                block.block_x,
+                block.block_y,
+                &block.him,
+                &block.til,
+                tiles_outside.as_ref(),
+            );
+        }
 
         // Load ocean patch
-        for (ocean_index, ocean) in block.ifo.oceans.iter().enumerate() {
+        let oceans = block.ifo.oceans.iter().enumerate();
+        for (ocean_index, ocean) in oceans.filter(|_| content.includes(ZoneContent::Ocean)) {
             for (patch_index, patch) in ocean.patches.iter().enumerate() {
+                if let Some(radius_filter) = options.radius_filter.as_ref() {
+                    let start = Vec2::new(patch.start.x, -patch.start.z) / 100.0;
+                    let end = Vec2::new(patch.end.x, -patch.end.z) / 100.0;
+                    if !radius_filter.intersects(start, end) {
+                        continue;
+                    }
+                }
+
                 load_ocean_patch(
                     root,
                     binary_data,
@@ -569,64 +1044,329 @@ pub fn load_zone(
                     ocean_index,
                     patch_index,
                     patch,
+                    ocean.size,
                     ocean_material,
                 );
             }
         }
 
+        // Load water planes, such as rivers and ponds
+        let waters = block.ifo.waters.iter().enumerate();
+        for (water_index, water) in waters.filter(|_| content.includes(ZoneContent::Water)) {
+            load_water_plane(root, binary_data, block, water_index, water, ocean_material);
+        }
+
+        // Load all effect markers
+        for (effect_index, effect) in block.ifo.effects.iter().enumerate() {
+            if !is_exported(ZoneContent::Effects, effect.data.position) {
+                continue;
+            }
+            load_effect_marker(
+                root,
+                binary_data,
+                assets,
+                block,
+                effect_index,
+                effect,
+                effect_billboards.as_mut(),
+            );
+        }
+
+        // Load all sound emitters
+        for (sound_index, sound) in block.ifo.sounds.iter().enumerate() {
+            if !is_exported(ZoneContent::Sounds, sound.data.position) {
+                continue;
+            }
+            load_sound_emitter(
+                root,
+                assets,
+                block,
+                sound_index,
+                sound,
+                options.resolve_sound_paths,
+            );
+        }
+
+        // Load monster spawn areas
+        if let Some(shape) = options.spawn_volumes {
+            for (spawn_index, spawn) in block.ifo.monster_spawns.iter().enumerate() {
+                if !is_exported(ZoneContent::Spawns, spawn.data.position) {
+                    continue;
+                }
+
+                spawn_volumes
+                    .get_or_insert_with(|| SpawnVolumes::new(root, binary_data, shape))
+                    .add_spawn(root, block.block_x, block.block_y, spawn_index, spawn);
+            }
+        }
+
         // Load all deco objects
+        let first_deco_node = root.scenes[0].nodes.len();
         for (object_instance_index, object_instance) in block.ifo.objects.iter().enumerate() {
+            if !include_disabled_objects && is_object_disabled(object_instance) {
+                continue;
+            }
+            if !is_exported(ZoneContent::Deco, object_instance.position) {
+                continue;
+            }
+
             load_object_instance(
                 root,
                 binary_data,
-                &assets_path,
+                assets,
                 block,
                 deco,
                 "deco",
                 object_instance_index,
                 object_instance,
+                options.quantize_animations,
+                options.billboard_pre_rotation,
+                options.engine_hints,
+                content.includes(ZoneContent::Lights),
             );
         }
 
         // Load all cnst objects
+        let first_cnst_node = root.scenes[0].nodes.len();
         for (object_instance_index, object_instance) in block.ifo.buildings.iter().enumerate() {
+            if !include_disabled_objects && is_object_disabled(object_instance) {
+                continue;
+            }
+            if !is_exported(ZoneContent::Cnst, object_instance.position) {
+                continue;
+            }
+
             load_object_instance(
                 root,
                 binary_data,
-                &assets_path,
+                assets,
                 block,
                 cnst,
                 "cnst",
                 object_instance_index,
                 object_instance,
+                options.quantize_animations,
+                options.billboard_pre_rotation,
+                options.engine_hints,
+                content.includes(ZoneContent::Lights),
+            );
+        }
+
+        if options.sort_nodes {
+            // Terrain first, then buildings, decos and everything else
+            let scene_nodes = &root.scenes[0].nodes;
+            let children = [
+                &scene_nodes[first_scene_node..first_other_node],
+                &scene_nodes[first_cnst_node..],
+                &scene_nodes[first_deco_node..first_cnst_node],
+                &scene_nodes[first_other_node..first_deco_node],
+            ]
+            .concat();
+            root.scenes[0].nodes.truncate(first_scene_node);
+
+            let block_node = Index::new(root.nodes.len() as u32);
+            root.nodes.push(scene::Node {
+                name: Some(format!("{}_{}", block.block_x, block.block_y)),
+                children: Some(children),
+                ..Default::default()
+            });
+            root.scenes[0].nodes.push(block_node);
+        }
+
+        if !options.skip_provenance {
+            let ifo_path = map_path.join(format!("{}_{}.ifo", block.block_x, block.block_y));
+            let mut source = serde_json::Map::new();
+            source.insert(
+                "file".to_string(),
+                json!(assets
+                    .asset_path(&ifo_path)
+                    .unwrap_or_else(|| ifo_path.to_string_lossy().to_string())),
             );
+            source.insert("block".to_string(), json!([block.block_x, block.block_y]));
+            add_node_provenance(&mut root.nodes[first_block_node..], &source);
         }
     }
 
     Ok(())
 }
 
+/// Describes the material shared by oceans and water planes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WaterMaterialOptions {
+    /// Path of the frames of the animated water texture relative to the
+    /// assets root, `{}` is replaced by the frame number as two digits
+    /// starting from 01. Frame 01 is used as the base color texture, and the
+    /// paths of every frame are added to the material extras.
+    pub texture: Option<String>,
+
+    /// Number of frames of the water texture
+    pub frame_count: u32,
+
+    /// Frames per second of the water texture animation
+    pub fps: f32,
+
+    /// Base color of the water, defaults to white when the texture is found
+    /// and light blue otherwise
+    pub color: Option<[f32; 3]>,
+
+    pub opacity: f32,
+    pub roughness: f32,
+}
+
+impl Default for WaterMaterialOptions {
+    fn default() -> Self {
+        Self {
+            texture: Some("3ddata/junon/water/ocean01_{}.dds".to_string()),
+            frame_count: 25,
+            fps: 10.0,
+            color: None,
+            opacity: 0.6,
+            roughness: 0.5,
+        }
+    }
+}
+
+/// Size of the area covered by one repeat of the water texture, in glTF
+/// units, when an ocean does not give one
+const WATER_TEXTURE_SIZE: f32 = 10.0;
+
+fn create_water_material(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    assets: &dyn AssetLocator,
+    asset_cache: Option<&AssetCache>,
+    water: &WaterMaterialOptions,
+    embedded_images: &EmbeddedImageOptions,
+) -> Index<material::Material> {
+    let frames = water
+        .texture
+        .iter()
+        .flat_map(|texture| {
+            (1..=water.frame_count).map(|frame| texture.replace("{}", &format!("{:02}", frame)))
+        })
+        .collect::<Vec<_>>();
+    let image = frames
+        .first()
+        .and_then(|frame| assets.locate(frame))
+        .and_then(|path| match load_cached_image(asset_cache, &path) {
+            Ok(image) => Some(image),
+            Err(error) => {
+                warn(format!(
+                    "Failed to read {} with error {}",
+                    path.display(),
+                    error
+                ));
+                None
+            }
+        });
+    let texture = image.map(|image| {
+        let sampler = Index::new(root.samplers.len() as u32);
+        root.samplers.push(texture::Sampler {
+            name: Some("water_sampler".to_string()),
+            mag_filter: Some(Checked::Valid(texture::MagFilter::Linear)),
+            min_filter: Some(Checked::Valid(texture::MinFilter::LinearMipmapLinear)),
+            wrap_s: Checked::Valid(texture::WrappingMode::Repeat),
+            wrap_t: Checked::Valid(texture::WrappingMode::Repeat),
+            extensions: None,
+            extras: Default::default(),
+        });
+        push_texture(
+            root,
+            binary_data,
+            "ocean_material",
+            &image,
+            sampler,
+            embedded_images,
+            true,
+        )
+    });
+
+    let [red, green, blue] = water.color.unwrap_or(if texture.is_some() {
+        [1.0, 1.0, 1.0]
+    } else {
+        [0.32, 0.46, 0.7]
+    });
+    let material_index = Index::new(root.materials.len() as u32);
+    root.materials.push(material::Material {
+        name: Some("ocean_material".to_string()),
+        alpha_cutoff: None,
+        alpha_mode: Checked::Valid(material::AlphaMode::Blend),
+        double_sided: true,
+        pbr_metallic_roughness: material::PbrMetallicRoughness {
+            base_color_factor: material::PbrBaseColorFactor([red, green, blue, water.opacity]),
+            base_color_texture: texture.map(|index| texture::Info {
+                index,
+                tex_coord: 0,
+                extensions: None,
+                extras: Default::default(),
+            }),
+            metallic_factor: material::StrengthFactor(0.5),
+            roughness_factor: material::StrengthFactor(water.roughness),
+            metallic_roughness_texture: None,
+            extensions: None,
+            extras: Default::default(),
+        },
+        normal_texture: None,
+        occlusion_texture: None,
+        emissive_texture: None,
+        emissive_factor: material::EmissiveFactor([0.0, 0.0, 0.0]),
+        extensions: None,
+        extras: texture.map(|_| {
+            RawValue::from_string(
+                json!({
+                    "rose_water": {
+                        "frames": frames,
+                        "fps": water.fps,
+                    }
+                })
+                .to_string(),
+            )
+            .unwrap()
+        }),
+    });
+    material_index
+}
+
+/// Texture coordinates which tile the water texture in world space, so
+/// neighbouring water meshes line up
+fn water_uv(world_x: f32, world_z: f32, texture_size: f32) -> Vec2 {
+    Vec2::new(world_x / texture_size, world_z / texture_size)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn load_ocean_patch(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
+    binary_data: &mut BinaryData,
     block: &BlockData,
     ocean_index: usize,
     patch_index: usize,
     patch: &rose_file_lib::files::ifo::OceanPatch,
+    ocean_size: f32,
     ocean_material: Option<Index<gltf_json::Material>>,
 ) {
     let start = Vec3::new(patch.start.x, patch.start.y, -patch.start.z) / 100.0;
     let end = (Vec3::new(patch.end.x, patch.end.y, -patch.end.z) / 100.0) - start;
     let up = Vec3::new(0.0, 1.0, 0.0);
+    let texture_size = if ocean_size > 0.0 {
+        ocean_size / 100.0
+    } else {
+        WATER_TEXTURE_SIZE
+    };
 
-    let mut mesh_builder = MeshBuilder::new();
-    mesh_builder.add_positions(vec![
+    let positions = vec![
         Vec3::new(0.0, 0.0, end.z),
         Vec3::new(0.0, 0.0, 0.0),
         Vec3::new(end.x, 0.0, 0.0),
         Vec3::new(end.x, 0.0, end.z),
-    ]);
+    ];
+    let uvs = positions
+        .iter()
+        .map(|position| water_uv(start.x + position.x, start.z + position.z, texture_size))
+        .collect();
+    let mut mesh_builder = MeshBuilder::new();
+    mesh_builder.add_positions(positions);
     mesh_builder.add_normals(vec![up, up, up, up]);
+    mesh_builder.add_uv0(uvs);
     mesh_builder.add_indices(vec![0, 2, 1, 0, 3, 2]);
     let mesh_data = mesh_builder.build(
         root,
@@ -679,14 +1419,142 @@ fn load_ocean_patch(
     root.scenes[0].nodes.push(node_index);
 }
 
+/// Water planes cover the block with a grid of cells, each of which may have
+/// water at its own height.
+fn load_water_plane(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    block: &BlockData,
+    water_index: usize,
+    water: &WaterData,
+    water_material: Option<Index<gltf_json::Material>>,
+) {
+    if water.width == 0 || water.height == 0 {
+        return;
+    }
+
+    let cell_width = BLOCK_SIZE / water.width as f32;
+    let cell_length = BLOCK_SIZE / water.height as f32;
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let [block_x, _, block_z] = block_translation(block.block_x, block.block_y);
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+    for (cell_index, (has_water, height)) in
+        water.has_water.iter().zip(water.heights.iter()).enumerate()
+    {
+        if !has_water {
+            continue;
+        }
+
+        let x = (cell_index % water.width as usize) as f32 * cell_width;
+        let z = (cell_index / water.width as usize) as f32 * cell_length;
+        let y = height / 100.0;
+        let start = positions.len() as u16;
+        positions.extend([
+            Vec3::new(x, y, z),
+            Vec3::new(x + cell_width, y, z),
+            Vec3::new(x + cell_width, y, z + cell_length),
+            Vec3::new(x, y, z + cell_length),
+        ]);
+        indices.extend([start, start + 3, start + 2, start, start + 2, start + 1]);
+    }
+    if positions.is_empty() {
+        return;
+    }
+
+    let name = format!("{}_{}_water_{}", block.block_x, block.block_y, water_index);
+    let uvs = positions
+        .iter()
+        .map(|position| {
+            water_uv(
+                block_x + position.x,
+                block_z + position.z,
+                WATER_TEXTURE_SIZE,
+            )
+        })
+        .collect();
+    let mut mesh_builder = MeshBuilder::new();
+    mesh_builder.add_normals(vec![up; positions.len()]);
+    mesh_builder.add_positions(positions);
+    mesh_builder.add_uv0(uvs);
+    mesh_builder.add_indices(indices);
+    let mesh_data = mesh_builder.build(root, binary_data, &format!("{}_mesh", name));
+
+    let mesh_index = Index::new(root.meshes.len() as u32);
+    root.meshes.push(mesh::Mesh {
+        name: Some(format!("{}_mesh", name)),
+        extensions: Default::default(),
+        extras: Default::default(),
+        primitives: vec![mesh::Primitive {
+            attributes: mesh_data.attributes,
+            extensions: Default::default(),
+            extras: Default::default(),
+            indices: Some(mesh_data.indices),
+            material: water_material,
+            mode: Checked::Valid(mesh::Mode::Triangles),
+            targets: None,
+        }],
+        weights: None,
+    });
+
+    let node_index = Index::new(root.nodes.len() as u32);
+    root.nodes.push(scene::Node {
+        camera: None,
+        children: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+        matrix: None,
+        mesh: Some(mesh_index),
+        name: Some(name),
+        rotation: None,
+        scale: None,
+        translation: Some(block_translation(block.block_x, block.block_y)),
+        skin: None,
+        weights: None,
+    });
+    root.scenes[0].nodes.push(node_index);
+}
+
+#[allow(clippy::too_many_arguments)]
 fn load_heightmap(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
+    binary_data: &mut BinaryData,
+    zon: &zon::Zone,
     block: &BlockData,
     use_better_heightmap_triangles: bool,
+    vertex_lightmap: Option<&DynamicImage>,
+    f32_colors: bool,
     block_terrain_material: &Index<gltf_json::Material>,
+    block_variant_materials: &[(Index<gltf_json::Material>, u32)],
+    engine_hints: EngineHints,
+    heightmap_extras: bool,
+    tiles_outside: Option<&[[bool; 16]; 16]>,
+    terrain_uvs: &TerrainUvOptions,
 ) {
-    let mesh_data = generate_terrain_mesh(root, binary_data, block, use_better_heightmap_triangles);
+    let mut holes = terrain_holes(zon, &block.til);
+    if let Some(tiles_outside) = tiles_outside {
+        for (hole, &outside) in holes
+            .iter_mut()
+            .flatten()
+            .zip(tiles_outside.iter().flatten())
+        {
+            *hole |= outside;
+        }
+    }
+    if holes.iter().flatten().all(|&hole| hole) {
+        return;
+    }
+
+    let mesh_data = generate_terrain_mesh(
+        root,
+        binary_data,
+        block,
+        &holes,
+        use_better_heightmap_triangles,
+        vertex_lightmap,
+        f32_colors,
+        terrain_uvs,
+    );
 
     let heightmap_mesh = Index::new(root.meshes.len() as u32);
     root.meshes.push(mesh::Mesh {
@@ -698,7 +1566,22 @@ fn load_heightmap(
         extras: Default::default(),
         primitives: vec![mesh::Primitive {
             attributes: mesh_data.attributes,
-            extensions: Default::default(),
+            extensions: if block_variant_materials.is_empty() {
+                None
+            } else {
+                Some(extensions::mesh::Primitive {
+                    khr_materials_variants: Some(extensions::mesh::KhrMaterialsVariants {
+                        mappings: block_variant_materials
+                            .iter()
+                            .map(|(material, variant)| extensions::mesh::Mapping {
+                                material: material.value() as u32,
+                                variants: vec![*variant],
+                            })
+                            .collect(),
+                    }),
+                    ..Default::default()
+                })
+            },
             extras: Default::default(),
             indices: Some(mesh_data.indices),
             material: Some(*block_terrain_material),
@@ -708,33 +1591,36 @@ fn load_heightmap(
         weights: None,
     });
 
-    let offset_x = (160.0 * block.block_x as f32) - 5200.0;
-    let offset_y = (160.0 * (65.0 - block.block_y as f32)) - 5200.0;
+    let mut extras = json!({
+        "castShadows": true,
+        "receiveLightmap": true,
+        "TLM_ObjectProperties": {
+            "tlm_mesh_lightmap_use": 1,
+            "tlm_mesh_lightmap_resolution": 4,
+            "tlm_use_default_channel": 0,
+            "tlm_uv_channel": "UVMap.001"
+        }
+    });
+    engine_hints.add_node_extras(&mut extras, Some(zsc::ModelCollisionShape::Mesh));
+    if heightmap_extras {
+        extras["rose_him"] = him_extras(block.block_x, block.block_y, &block.him);
+    }
+
     let node_index = Index::new(root.nodes.len() as u32);
     root.nodes.push(scene::Node {
         camera: None,
         children: None,
         extensions: Default::default(),
-        extras: Some(
-            RawValue::from_string(format!(
-                r#"{{
-                "TLM_ObjectProperties": {{
-                    "tlm_mesh_lightmap_use": 1,
-                    "tlm_mesh_lightmap_resolution": {},
-                    "tlm_use_default_channel": 0,
-                    "tlm_uv_channel": "UVMap.001"
-                }}
-            }}"#,
-                4
-            ))
-            .unwrap(),
-        ),
+        extras: Some(RawValue::from_string(extras.to_string()).unwrap()),
         matrix: None,
         mesh: Some(heightmap_mesh),
-        name: Some(format!("{}_{}_heightmap", block.block_x, block.block_y,)),
+        name: Some(engine_hints.node_name(
+            format!("{}_{}_heightmap", block.block_x, block.block_y),
+            Some(zsc::ModelCollisionShape::Mesh),
+        )),
         rotation: Some(UnitQuaternion::default()),
         scale: Some([1.0, 1.0, 1.0]),
-        translation: Some([offset_x, 0.0, -offset_y]),
+        translation: Some(block_translation(block.block_x, block.block_y)),
         skin: None,
         weights: None,
     });
@@ -751,16 +1637,231 @@ impl GetAnimationChannelNode for Index<scene::Node> {
     }
 }
 
+struct EffectBillboards {
+    quad: MeshData,
+    materials: ObjectList,
+    meshes: HashMap<String, Index<mesh::Mesh>>,
+}
+
+impl EffectBillboards {
+    fn new(
+        root: &mut gltf_json::Root,
+        binary_data: &mut BinaryData,
+        sampler: Index<texture::Sampler>,
+        embedded_images: EmbeddedImageOptions,
+    ) -> Self {
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let mut mesh_builder = MeshBuilder::new();
+        mesh_builder.add_positions(vec![
+            Vec3::new(-0.5, 0.0, 0.0),
+            Vec3::new(0.5, 0.0, 0.0),
+            Vec3::new(0.5, 1.0, 0.0),
+            Vec3::new(-0.5, 1.0, 0.0),
+        ]);
+        mesh_builder.add_normals(vec![normal, normal, normal, normal]);
+        mesh_builder.add_uv0(vec![
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 0.0),
+        ]);
+        mesh_builder.add_indices(vec![0, 1, 2, 0, 2, 3]);
+
+        let mut materials = ObjectList::new(Arc::new(ZSC::new()), sampler);
+        materials.embedded_images = embedded_images;
+        Self {
+            quad: mesh_builder.build(root, binary_data, "effect_billboard"),
+            materials,
+            meshes: HashMap::new(),
+        }
+    }
+
+    /// Returns a quad mesh textured with the primary texture of the effect.
+    fn get_mesh(
+        &mut self,
+        root: &mut gltf_json::Root,
+        binary_data: &mut BinaryData,
+        assets: &dyn AssetLocator,
+        effect_path: &str,
+        effect: &EFT,
+    ) -> Index<mesh::Mesh> {
+        if let Some(mesh_index) = self.meshes.get(effect_path) {
+            return *mesh_index;
+        }
+
+        let material = effect
+            .primary_texture()
+            .map(|texture_path| zsc::ModelMaterial {
+                path: texture_path.to_string(),
+                alpha_enabled: true,
+                two_sided: true,
+                alpha: 1.0,
+                ..Default::default()
+            })
+            .and_then(|material| {
+                if let Some(material_index) =
+                    self.materials
+                        .load_material("effect", &material, root, binary_data, assets)
+                {
+                    self.materials
+                        .materials
+                        .insert(material.clone(), material_index);
+                }
+                self.materials.materials.get(&material).copied()
+            });
+
+        let mesh_index = Index::new(root.meshes.len() as u32);
+        root.meshes.push(mesh::Mesh {
+            name: Some(format!("effect_billboard_{}_mesh", self.meshes.len())),
+            extensions: Default::default(),
+            extras: Default::default(),
+            primitives: vec![mesh::Primitive {
+                attributes: self.quad.attributes.clone(),
+                extensions: Default::default(),
+                extras: Default::default(),
+                indices: Some(self.quad.indices),
+                material,
+                mode: Checked::Valid(mesh::Mode::Triangles),
+                targets: None,
+            }],
+            weights: None,
+        });
+        self.meshes.insert(effect_path.to_string(), mesh_index);
+        mesh_index
+    }
+}
+
+fn load_effect_marker(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    assets: &dyn AssetLocator,
+    block: &BlockData,
+    effect_index: usize,
+    effect_data: &EffectData,
+    billboards: Option<&mut EffectBillboards>,
+) {
+    let effect = EFT::from_path(&assets.resolve(&effect_data.file));
+    let mut extras = json!({
+        "path": effect_data.file,
+        "event_id": effect_data.data.event_id,
+        "warp_id": effect_data.data.warp_id,
+    });
+    match effect.as_ref() {
+        Ok(effect) => {
+            extras["sound"] = json!(effect.sound_path);
+            extras["particles"] = effect
+                .particles
+                .iter()
+                .map(|particle| json!(particle.particle_path))
+                .collect();
+            extras["meshes"] = effect
+                .meshes
+                .iter()
+                .map(|mesh| json!(mesh.mesh_path))
+                .collect();
+            extras["texture"] = json!(effect.primary_texture());
+        }
+        Err(error) => {
+            warn(format!(
+                "Failed to read effect {} with error {}",
+                effect_data.file, error
+            ));
+        }
+    }
+
+    let billboard_mesh = match (billboards, effect.as_ref()) {
+        (Some(billboards), Ok(effect)) => {
+            Some(billboards.get_mesh(root, binary_data, assets, &effect_data.file, effect))
+        }
+        _ => None,
+    };
+
+    let node_index = Index::new(root.nodes.len() as u32);
+    root.nodes.push(scene::Node {
+        name: Some(format!(
+            "{}_{}_effect_{}",
+            block.block_x, block.block_y, effect_index
+        )),
+        camera: None,
+        children: None,
+        extensions: Default::default(),
+        extras: Some(RawValue::from_string(json!({ "rose_effect": extras }).to_string()).unwrap()),
+        matrix: None,
+        mesh: billboard_mesh,
+        rotation: Some(convert_rotation(effect_data.data.rotation)),
+        scale: Some(convert_scale(effect_data.data.scale)),
+        translation: Some(convert_position(effect_data.data.position)),
+        skin: None,
+        weights: None,
+    });
+    root.scenes[0].nodes.push(node_index);
+}
+
+fn load_sound_emitter(
+    root: &mut gltf_json::Root,
+    assets: &dyn AssetLocator,
+    block: &BlockData,
+    sound_index: usize,
+    sound: &SoundData,
+    resolve_sound_paths: bool,
+) {
+    let mut extras = json!({
+        "path": sound.file,
+        "range": sound.range,
+        "interval": sound.interval,
+    });
+    if resolve_sound_paths {
+        if let Some(sound_path) = assets.locate(&sound.file) {
+            extras["file"] = json!(sound_path.to_string_lossy());
+        } else {
+            warn(format!("Missing sound file {}", sound.file));
+        }
+    }
+
+    let node_index = Index::new(root.nodes.len() as u32);
+    root.nodes.push(scene::Node {
+        name: Some(format!(
+            "{}_{}_sound_{}",
+            block.block_x, block.block_y, sound_index
+        )),
+        camera: None,
+        children: None,
+        extensions: Default::default(),
+        extras: Some(RawValue::from_string(json!({ "rose_sound": extras }).to_string()).unwrap()),
+        matrix: None,
+        mesh: None,
+        rotation: Some(convert_rotation(sound.data.rotation)),
+        scale: Some(convert_scale(sound.data.scale)),
+        translation: Some(convert_position(sound.data.position)),
+        skin: None,
+        weights: None,
+    });
+    root.scenes[0].nodes.push(node_index);
+}
+
+/// Objects with a zero scale are not visible in the client. Objects with an
+/// event id are kept, as whether the event hides them is up to the zone's
+/// scripts, and the id is in the `rose_object` extras instead.
+pub(crate) fn is_object_disabled(object_instance: &ObjectData) -> bool {
+    object_instance.scale.x == 0.0
+        || object_instance.scale.y == 0.0
+        || object_instance.scale.z == 0.0
+}
+
 #[allow(clippy::too_many_arguments)]
 fn load_object_instance(
     root: &mut gltf_json::Root,
-    binary_data: &mut BytesMut,
-    assets_path: &Path,
+    binary_data: &mut BinaryData,
+    assets: &dyn AssetLocator,
     block: &BlockData,
     object_list: &ObjectList,
     object_list_name: &str,
     object_instance_index: usize,
-    object_instance: &rose_file_lib::files::ifo::ObjectData,
+    object_instance: &ObjectData,
+    quantize_animations: bool,
+    billboard_pre_rotation: bool,
+    engine_hints: EngineHints,
+    lights: bool,
 ) {
     let mut children = Vec::new();
     let object_id = object_instance.object_id as usize;
@@ -814,32 +1915,75 @@ fn load_object_instance(
             0 // 32
         };
 
+        let part_name = format!(
+            "{}_{}_{}_{}_{}",
+            block.block_x, block.block_y, object_list_name, object_instance_index, part_index
+        );
+        // Alpha blended parts such as glass and light shafts do not cast
+        // shadows, alpha tested cards such as leaves do
+        let cast_shadows = part
+            .material
+            .as_ref()
+            .is_none_or(|material| !material.alpha_enabled || material.alpha_test.is_some());
+        let lightmap_extras = json!({
+            "castShadows": cast_shadows,
+            "receiveLightmap": part.use_lightmap,
+            "TLM_ObjectProperties": {
+                "tlm_mesh_lightmap_use": part.use_lightmap as i32,
+                "tlm_mesh_lightmap_resolution": lightmap_size,
+                "tlm_use_default_channel": 0,
+                "tlm_uv_channel": "UVMap.001"
+            }
+        });
+        let billboard = Billboard::detect(part, mesh_data);
+
+        // A pre-rotated billboard keeps its mesh in a child node, leaving the
+        // part node free to be turned towards the camera.
+        let billboard_node =
+            billboard
+                .as_ref()
+                .filter(|_| billboard_pre_rotation)
+                .map(|billboard| {
+                    let billboard_node = Index::new(root.nodes.len() as u32);
+                    root.nodes.push(scene::Node {
+                        name: Some(format!("{}_billboard", part_name)),
+                        camera: None,
+                        children: None,
+                        extensions: Default::default(),
+                        extras: Some(RawValue::from_string(lightmap_extras.to_string()).unwrap()),
+                        matrix: None,
+                        mesh: Some(Index::new(mesh_index)),
+                        rotation: Some(billboard.pre_rotation()),
+                        scale: None,
+                        translation: None,
+                        skin: None,
+                        weights: None,
+                    });
+                    billboard_node
+                });
+
+        let mut part_extras = match billboard_node {
+            Some(_) => json!({}),
+            None => lightmap_extras,
+        };
+        if let Some(billboard) = billboard.as_ref() {
+            part_extras["rose_billboard"] = billboard.extras(billboard_node.is_some());
+        }
+
+        // Billboards are turned towards the camera so they do not collide
+        let collider = part_collider(part).filter(|_| billboard.is_none());
+        engine_hints.add_node_extras(&mut part_extras, collider);
+
         let node_index = Index::new(root.nodes.len() as u32);
         children.push(node_index);
         root.nodes.push(scene::Node {
-            name: Some(format!(
-                "{}_{}_{}_{}_{}",
-                block.block_x, block.block_y, object_list_name, object_instance_index, part_index
-            )),
+            name: Some(engine_hints.node_name(part_name, collider)),
             camera: None,
-            children: None,
+            children: billboard_node.map(|billboard_node| vec![billboard_node]),
             extensions: Default::default(),
-            extras: Some(
-                RawValue::from_string(format!(
-                    r#"{{
-                        "TLM_ObjectProperties": {{
-                            "tlm_mesh_lightmap_use": 1,
-                            "tlm_mesh_lightmap_resolution": {},
-                            "tlm_use_default_channel": 0,
-                            "tlm_uv_channel": "UVMap.001"
-                        }}
-                    }}"#,
-                    lightmap_size
-                ))
-                .unwrap(),
-            ),
+            extras: Some(RawValue::from_string(part_extras.to_string()).unwrap()),
             matrix: None,
-            mesh: Some(Index::new(mesh_index)),
+            mesh: billboard_node.is_none().then_some(Index::new(mesh_index)),
             rotation: Some(convert_rotation(part.rotation)),
             scale: Some(convert_scale(part.scale)),
             translation: Some(convert_position(part.position)),
@@ -848,7 +1992,7 @@ fn load_object_instance(
         });
 
         if let Some(animation_path) = part.animation_path.as_ref() {
-            let animation_path = assets_path.join(animation_path);
+            let animation_path = assets.resolve(animation_path);
             if let Ok(zmo) = ZMO::from_path(&animation_path) {
                 let name = format!(
                     "{}_{}_{}_{}_{}_anim",
@@ -858,13 +2002,58 @@ fn load_object_instance(
                     object_instance_index,
                     part_index
                 );
-                load_animation(root, binary_data, &zmo, &name, node_index);
+                load_animation(
+                    root,
+                    binary_data,
+                    &zmo,
+                    &name,
+                    node_index,
+                    quantize_animations,
+                );
             } else {
-                println!("Failed to load {}", animation_path.to_string_lossy());
+                warn(format!(
+                    "Failed to load {}",
+                    animation_path.to_string_lossy()
+                ));
             }
         }
     }
 
+    // Spawn a node for each light container dummy point, below its part
+    let part_nodes = children.clone();
+    let dummy_points = object.dummy_points.iter().filter(|_| lights);
+    for (dummy_index, dummy) in dummy_points.enumerate() {
+        let Some(zsc::ModelDummyAttachment::Light { name: light_path }) = dummy.attachment.as_ref()
+        else {
+            continue;
+        };
+
+        let dummy_name = format!(
+            "{}_{}_{}_{}_dummy_{}",
+            block.block_x, block.block_y, object_list_name, object_instance_index, dummy_index
+        );
+        let light_nodes = load_light_container(root, &dummy_name, light_path, assets);
+        let node_index = Index::new(root.nodes.len() as u32);
+        root.nodes.push(scene::Node {
+            name: Some(dummy_name),
+            children: Some(light_nodes),
+            rotation: Some(convert_rotation(dummy.rotation)),
+            scale: Some(convert_scale(dummy.scale)),
+            translation: Some(convert_position(dummy.position)),
+            ..Default::default()
+        });
+        match dummy
+            .parent
+            .and_then(|parent| part_nodes.get(parent as usize))
+        {
+            Some(part_node) => root.nodes[part_node.value()]
+                .children
+                .get_or_insert_with(Vec::new)
+                .push(node_index),
+            None => children.push(node_index),
+        }
+    }
+
     // Spawn a node for building object
     let node_index = Index::new(root.nodes.len() as u32);
     root.nodes.push(scene::Node {
@@ -875,7 +2064,20 @@ fn load_object_instance(
         camera: None,
         children: Some(children),
         extensions: Default::default(),
-        extras: Default::default(),
+        extras: Some(
+            RawValue::from_string(
+                json!({
+                    "rose_object": {
+                        "object_id": object_instance.object_id,
+                        "event_id": object_instance.event_id,
+                        "warp_id": object_instance.warp_id,
+                        "disabled": is_object_disabled(object_instance),
+                    }
+                })
+                .to_string(),
+            )
+            .unwrap(),
+        ),
         matrix: None,
         mesh: None,
         rotation: Some(convert_rotation(object_instance.rotation)),
@@ -0,0 +1,151 @@
+//! Finding the deco and cnst ZSCs of a zone. They are usually listed in the
+//! zone's row of list_zone.stb, but custom zones are often missing from it
+//! and keep their ZSCs beside the ZON instead.
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rose_file_lib::files::ListZoneStb;
+
+use crate::{
+    assets::AssetLocator,
+    cache::load_cached,
+    error::{CategorizedError, ErrorCategory},
+    RoseGltfConvOptions,
+};
+
+/// The ZSCs of a zone and where they were found
+pub(crate) struct ZoneObjectLists {
+    /// list_zone.stb, when it could be loaded
+    pub list_zone: Option<Arc<ListZoneStb>>,
+
+    /// Row of the zone in list_zone.stb, when it is in it
+    pub zone_id: Option<usize>,
+
+    /// Paths of the deco and cnst ZSCs, relative to the assets root when
+    /// they are assets
+    pub deco: String,
+    pub cnst: String,
+
+    /// What the deco and cnst paths were found from, e.g.
+    /// `list_zone.stb row 12`
+    pub deco_source: String,
+    pub cnst_source: String,
+}
+
+/// Asset path of a file on disk, or its absolute path when it is outside
+/// the assets
+fn file_asset_path(assets: &dyn AssetLocator, path: &Path) -> String {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    assets
+        .asset_path(&path)
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+/// Finds the ZSC in the directory of the ZON whose name contains `kind`,
+/// preferring one which also contains the name of the ZON when there are
+/// several, e.g. `LIST_DECO_JDT01.ZSC`.
+fn find_zsc_beside_zon(zon_path: &Path, kind: &str) -> Option<PathBuf> {
+    let zon_name = zon_path.file_stem()?.to_string_lossy().to_ascii_lowercase();
+    let mut candidates: Vec<(PathBuf, String)> = std::fs::read_dir(zon_path.parent()?)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("zsc"))
+        })
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().to_ascii_lowercase();
+            name.contains(kind).then_some((path, name))
+        })
+        .collect();
+    if candidates.len() > 1 {
+        candidates.retain(|(_, name)| name.contains(&zon_name));
+    }
+    match candidates.as_slice() {
+        [(path, _)] => Some(path.clone()),
+        _ => None,
+    }
+}
+
+/// Finds the deco and cnst ZSCs of the zone at `zon_path`. Each is taken
+/// from `deco_zsc` and `cnst_zsc` in `options` when given, otherwise from the
+/// zone's row of list_zone.stb, otherwise from a ZSC beside the ZON with
+/// `deco` or `cnst` in its name.
+pub(crate) fn find_zone_object_lists(
+    zon_path: &Path,
+    relative_zon_path: &str,
+    assets: &dyn AssetLocator,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<ZoneObjectLists> {
+    let list_zone = load_cached::<ListZoneStb>(
+        options.asset_cache.as_deref(),
+        &assets.resolve("3ddata/stb/list_zone.stb"),
+    )
+    .ok();
+    let zone_id = list_zone
+        .as_ref()
+        .and_then(|list_zone| list_zone.find_zone_by_zon(Path::new(relative_zon_path)));
+
+    let find = |kind: &str, option: Option<&str>, row_path: Option<&str>| {
+        if let Some(path) = option {
+            let path = if Path::new(path).is_file() {
+                file_asset_path(assets, Path::new(path))
+            } else {
+                path.to_string()
+            };
+            return Ok((path, format!("the {} ZSC option", kind)));
+        }
+        if let (Some(path), Some(zone_id)) = (row_path, zone_id) {
+            return Ok((path.to_string(), format!("list_zone.stb row {}", zone_id)));
+        }
+        if let Some(path) = find_zsc_beside_zon(zon_path, kind) {
+            return Ok((
+                file_asset_path(assets, &path),
+                format!("the {} ZSC beside {}", kind, relative_zon_path),
+            ));
+        }
+
+        let reason = match (&list_zone, zone_id) {
+            (None, _) => "list_zone.stb could not be loaded".to_string(),
+            (Some(_), None) => format!("{} is not in list_zone.stb", relative_zon_path),
+            (Some(_), Some(zone_id)) => {
+                format!("list_zone.stb row {} has no {} ZSC", zone_id, kind)
+            }
+        };
+        Err(CategorizedError::error(
+            ErrorCategory::MissingDependency,
+            format!(
+                "Could not find the {kind} ZSC of {zon}: {reason} and there is no single ZSC with \
+                 {kind} in its name beside it. Add the zone to list_zone.stb, put the ZSC next to \
+                 the ZON or give its path with the {kind}_zsc option",
+                kind = kind,
+                zon = relative_zon_path,
+                reason = reason,
+            ),
+        ))
+    };
+    let row = list_zone.as_deref().zip(zone_id);
+    let (deco, deco_source) = find(
+        "deco",
+        options.deco_zsc.as_deref(),
+        row.and_then(|(list_zone, zone_id)| list_zone.zsc_deco(zone_id)),
+    )?;
+    let (cnst, cnst_source) = find(
+        "cnst",
+        options.cnst_zsc.as_deref(),
+        row.and_then(|(list_zone, zone_id)| list_zone.zsc_cnst(zone_id)),
+    )?;
+
+    Ok(ZoneObjectLists {
+        list_zone,
+        zone_id,
+        deco,
+        cnst,
+        deco_source,
+        cnst_source,
+    })
+}
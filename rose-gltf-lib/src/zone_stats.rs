@@ -0,0 +1,417 @@
+//! Statistics of the contents of a zone, read without converting it, so the
+//! blocks and objects which are heaviest to export can be found.
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+    path::Path,
+    sync::Arc,
+};
+
+use rose_file_lib::{
+    files::{IFO, TIL, ZMS, ZON, ZSC},
+    io::RoseFile,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::{normalize_asset_path, AssetLocator, FsAssetLocator},
+    cache::load_cached,
+    error::{CategoryContext, ErrorCategory},
+    report::warn,
+    zone::{is_object_disabled, is_terrain_hole},
+    zone_lookup::find_zone_object_lists,
+    RoseGltfConvOptions,
+};
+
+/// Vertices and triangles of each tile of the terrain mesh
+const TILE_VERTICES: u64 = 5 * 5;
+const TILE_TRIANGLES: usize = 4 * 4 * 2;
+
+/// Bytes of a terrain vertex, a position, a normal and two uvs, and the
+/// bytes of the optional lightmap uv
+const TERRAIN_VERTEX_BYTES: u64 = 12 + 12 + 8 + 8;
+const TERRAIN_LIGHTMAP_UV_BYTES: u64 = 8;
+
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlockStats {
+    pub block_x: i32,
+    pub block_y: i32,
+    pub terrain_triangles: usize,
+    pub deco_instances: usize,
+    pub cnst_instances: usize,
+
+    /// Triangles of every object instance in the block
+    pub object_triangles: usize,
+
+    /// Estimated size of the meshes and textures of the block when it is
+    /// exported on its own, without its baked terrain texture
+    pub estimated_bytes: u64,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ObjectStats {
+    /// The object list of the object, `deco` or `cnst`
+    pub kind: String,
+    pub object_id: usize,
+    pub instances: usize,
+
+    /// Triangles of one instance of the object
+    pub triangles: usize,
+
+    /// Estimated size of the meshes and textures of the object
+    pub estimated_bytes: u64,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextureStats {
+    /// Path of the texture, relative to the assets root
+    pub path: String,
+
+    /// Number of terrain tiles and object instances which use the texture
+    pub uses: usize,
+
+    /// Size of the texture file, 0 when it is missing
+    pub bytes: u64,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ZoneStats {
+    /// Row of the zone in list_zone.stb, None for zones which are not in it
+    pub zone_id: Option<usize>,
+    pub terrain_triangles: usize,
+    pub object_triangles: usize,
+
+    /// Estimated size of the meshes and textures of the whole zone, meshes
+    /// and textures shared by several blocks are counted once
+    pub estimated_bytes: u64,
+
+    pub blocks: Vec<BlockStats>,
+    pub objects: Vec<ObjectStats>,
+    pub textures: Vec<TextureStats>,
+}
+
+/// A table of `ZoneStats` which can be written as CSV
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ZoneStatsTable {
+    #[default]
+    Blocks,
+
+    Objects,
+
+    Textures,
+}
+
+/// Quotes a CSV field when it contains a separator, quote or newline
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+impl ZoneStats {
+    /// Writes one of the tables as CSV, with a header row
+    pub fn to_csv(&self, table: ZoneStatsTable) -> String {
+        let mut csv = String::new();
+        match table {
+            ZoneStatsTable::Blocks => {
+                csv.push_str(concat!(
+                    "block_x,block_y,terrain_triangles,deco_instances,cnst_instances,",
+                    "object_triangles,estimated_bytes\n"
+                ));
+                for block in self.blocks.iter() {
+                    writeln!(
+                        csv,
+                        "{},{},{},{},{},{},{}",
+                        block.block_x,
+                        block.block_y,
+                        block.terrain_triangles,
+                        block.deco_instances,
+                        block.cnst_instances,
+                        block.object_triangles,
+                        block.estimated_bytes
+                    )
+                    .unwrap();
+                }
+            }
+            ZoneStatsTable::Objects => {
+                csv.push_str("kind,object_id,instances,triangles,estimated_bytes\n");
+                for object in self.objects.iter() {
+                    writeln!(
+                        csv,
+                        "{},{},{},{},{}",
+                        csv_field(&object.kind),
+                        object.object_id,
+                        object.instances,
+                        object.triangles,
+                        object.estimated_bytes
+                    )
+                    .unwrap();
+                }
+            }
+            ZoneStatsTable::Textures => {
+                csv.push_str("path,uses,bytes\n");
+                for texture in self.textures.iter() {
+                    writeln!(
+                        csv,
+                        "{},{},{}",
+                        csv_field(&texture.path),
+                        texture.uses,
+                        texture.bytes
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        csv
+    }
+}
+
+/// Triangles and estimated glTF size of a ZMS
+#[derive(Clone, Copy)]
+struct MeshSize {
+    triangles: usize,
+    bytes: u64,
+}
+
+fn mesh_size(zms: &ZMS, f32_colors: bool) -> MeshSize {
+    let mut vertex_bytes = 0;
+    for (enabled, bytes) in [
+        (zms.positions_enabled(), 12),
+        (zms.normals_enabled(), 12),
+        (zms.colors_enabled(), if f32_colors { 16 } else { 4 }),
+        (zms.bones_enabled(), 16 + 8),
+        (zms.tangents_enabled(), 16),
+        (zms.uv1_enabled(), 8),
+        (zms.uv2_enabled(), 8),
+        (zms.uv3_enabled(), 8),
+        (zms.uv4_enabled(), 8),
+    ] {
+        if enabled {
+            vertex_bytes += bytes;
+        }
+    }
+
+    MeshSize {
+        triangles: zms.indices.len(),
+        bytes: zms.vertices.len() as u64 * vertex_bytes + zms.indices.len() as u64 * 3 * 2,
+    }
+}
+
+/// Reads the ZON, IFO and TIL files of the blocks selected by `options` and
+/// the object meshes they use, counting triangles, object instances and
+/// texture uses without converting the zone.
+///
+/// Sizes are estimates of the vertex and index data and of the texture files
+/// the exported glTF would contain.
+pub fn zone_stats(zon_path: &Path, options: &RoseGltfConvOptions) -> anyhow::Result<ZoneStats> {
+    let assets: Arc<dyn AssetLocator> = match &options.asset_locator {
+        Some(asset_locator) => asset_locator.clone(),
+        None => Arc::new(FsAssetLocator::for_file(zon_path, &options.asset_roots)),
+    };
+    let map_path = zon_path
+        .parent()
+        .category_context(ErrorCategory::MissingDependency, || {
+            format!("Could not find map path of {}", zon_path.display())
+        })?;
+    let relative_zon_path = assets
+        .asset_path(zon_path)
+        .category_context(ErrorCategory::MissingDependency, || {
+            format!("Could not find root assets path of {}", zon_path.display())
+        })?;
+
+    let asset_cache = options.asset_cache.as_deref();
+    let zone_object_lists =
+        find_zone_object_lists(zon_path, &relative_zon_path, assets.as_ref(), options)?;
+    let zon = ZON::from_path(zon_path).category_context(ErrorCategory::InputParse, || {
+        format!("Failed to load {}", zon_path.display())
+    })?;
+
+    let mut object_lists = Vec::with_capacity(2);
+    for (kind, zsc_path) in [
+        ("deco", &zone_object_lists.deco),
+        ("cnst", &zone_object_lists.cnst),
+    ] {
+        let zsc = load_cached::<ZSC>(asset_cache, &assets.resolve(zsc_path))
+            .category_context(ErrorCategory::MissingDependency, || {
+                format!("Failed to read {}", zsc_path)
+            })?;
+        object_lists.push((kind, zsc));
+    }
+
+    let mut mesh_sizes: HashMap<String, Option<MeshSize>> = HashMap::new();
+    let mut load_mesh_size = |path: &str| {
+        *mesh_sizes
+            .entry(normalize_asset_path(path))
+            .or_insert_with(
+                || match load_cached::<ZMS>(asset_cache, &assets.resolve(path)) {
+                    Ok(zms) => Some(mesh_size(&zms, options.f32_vertex_colors)),
+                    Err(error) => {
+                        warn(format!("Failed to load {} with error {}", path, error));
+                        None
+                    }
+                },
+            )
+    };
+    let mut texture_sizes: HashMap<String, u64> = HashMap::new();
+    let mut texture_bytes = |path: &str| {
+        *texture_sizes
+            .entry(normalize_asset_path(path))
+            .or_insert_with(|| {
+                assets
+                    .locate(path)
+                    .and_then(|path| std::fs::metadata(path).ok())
+                    .map_or(0, |metadata| metadata.len())
+            })
+    };
+
+    let mut stats = ZoneStats {
+        zone_id: zone_object_lists.zone_id,
+        ..Default::default()
+    };
+    let mut objects: BTreeMap<(&str, usize), ObjectStats> = BTreeMap::new();
+    let mut texture_uses: BTreeMap<String, usize> = BTreeMap::new();
+    let mut zone_meshes = BTreeSet::new();
+    let mut zone_textures = BTreeSet::new();
+
+    for block_y in 0..64 {
+        for block_x in 0..64 {
+            if options.filter_block_x.is_some_and(|x| x != block_x)
+                || options.filter_block_y.is_some_and(|y| y != block_y)
+            {
+                continue;
+            }
+
+            let (Ok(ifo), Ok(til)) = (
+                IFO::from_path(&map_path.join(format!("{}_{}.ifo", block_x, block_y))),
+                TIL::from_path(&map_path.join(format!("{}_{}.til", block_x, block_y))),
+            ) else {
+                continue;
+            };
+
+            let mut block = BlockStats {
+                block_x,
+                block_y,
+                ..Default::default()
+            };
+            let mut block_meshes = BTreeSet::new();
+            let mut block_textures = BTreeSet::new();
+            let terrain_vertex_bytes = if options.terrain_uvs.lightmap_uv {
+                TERRAIN_VERTEX_BYTES + TERRAIN_LIGHTMAP_UV_BYTES
+            } else {
+                TERRAIN_VERTEX_BYTES
+            };
+
+            for tile_y in 0..16 {
+                for tile_x in 0..16 {
+                    if is_terrain_hole(&zon, &til, tile_x, tile_y) {
+                        continue;
+                    }
+                    block.terrain_triangles += TILE_TRIANGLES;
+                    block.estimated_bytes +=
+                        TILE_VERTICES * terrain_vertex_bytes + TILE_TRIANGLES as u64 * 3 * 2;
+
+                    let tile = &zon.tiles[til.tiles[tile_y][tile_x].tile_id as usize];
+                    let layers =
+                        BTreeSet::from([tile.layer1 + tile.offset1, tile.layer2 + tile.offset2]);
+                    for layer in layers {
+                        if let Some(path) = zon.textures.get(layer as usize) {
+                            *texture_uses.entry(normalize_asset_path(path)).or_default() += 1;
+                        }
+                    }
+                }
+            }
+
+            for ((kind, zsc), instances) in object_lists.iter().zip([&ifo.objects, &ifo.buildings])
+            {
+                for instance in instances.iter() {
+                    if !options.include_disabled_objects && is_object_disabled(instance) {
+                        continue;
+                    }
+
+                    let object_id = instance.object_id as usize;
+                    let Some(model) = zsc.models.get(object_id).and_then(|model| model.as_ref())
+                    else {
+                        continue;
+                    };
+
+                    let object = objects.entry((*kind, object_id)).or_insert_with(|| {
+                        let mut object = ObjectStats {
+                            kind: kind.to_string(),
+                            object_id,
+                            ..Default::default()
+                        };
+                        let mut object_textures = BTreeSet::new();
+                        for part in model.parts.iter() {
+                            if let Some(size) = load_mesh_size(&part.mesh_path) {
+                                object.triangles += size.triangles;
+                                object.estimated_bytes += size.bytes;
+                            }
+                            if let Some(material) = part.material.as_ref() {
+                                object_textures.insert(normalize_asset_path(&material.path));
+                            }
+                        }
+                        object.estimated_bytes += object_textures
+                            .iter()
+                            .map(|path| texture_bytes(path))
+                            .sum::<u64>();
+                        object
+                    });
+                    object.instances += 1;
+
+                    match *kind {
+                        "deco" => block.deco_instances += 1,
+                        _ => block.cnst_instances += 1,
+                    }
+                    block.object_triangles += object.triangles;
+
+                    let mut instance_textures = BTreeSet::new();
+                    for part in model.parts.iter() {
+                        block_meshes.insert(normalize_asset_path(&part.mesh_path));
+                        if let Some(material) = part.material.as_ref() {
+                            instance_textures.insert(normalize_asset_path(&material.path));
+                        }
+                    }
+                    for path in instance_textures {
+                        *texture_uses.entry(path.clone()).or_default() += 1;
+                        block_textures.insert(path);
+                    }
+                }
+            }
+
+            // Terrain bytes so far, each block has its own terrain mesh
+            stats.estimated_bytes += block.estimated_bytes;
+            for path in block_meshes.iter() {
+                block.estimated_bytes += load_mesh_size(path).map_or(0, |size| size.bytes);
+            }
+            for path in block_textures.iter() {
+                block.estimated_bytes += texture_bytes(path);
+            }
+
+            stats.terrain_triangles += block.terrain_triangles;
+            stats.object_triangles += block.object_triangles;
+            zone_meshes.extend(block_meshes);
+            zone_textures.extend(block_textures);
+            stats.blocks.push(block);
+        }
+    }
+
+    for path in zone_meshes.iter() {
+        stats.estimated_bytes += load_mesh_size(path).map_or(0, |size| size.bytes);
+    }
+    for path in zone_textures.iter() {
+        stats.estimated_bytes += texture_bytes(path);
+    }
+    stats.objects = objects.into_values().collect();
+    stats.textures = texture_uses
+        .into_iter()
+        .map(|(path, uses)| TextureStats {
+            bytes: texture_bytes(&path),
+            path,
+            uses,
+        })
+        .collect();
+    Ok(stats)
+}
@@ -0,0 +1,352 @@
+//! Batch export of the zones in list_zone.stb, and of the blocks of a zone
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use rose_file_lib::{
+    files::{ListZoneStb, HIM},
+    io::RoseFile,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::load_cached,
+    cancel::check_cancelled,
+    error::ErrorCategory,
+    report::{info, warn},
+    rose_to_gltf, sanitize_name, save_gltf,
+    zone::{block_translation, BLOCK_SIZE},
+    AssetCache, AssetLocator, FsAssetLocator, GltfFormat, RoseGltfConvOptions,
+};
+
+/// Number of parsed files and textures kept in the cache created by
+/// `export_zones` and `export_zone_blocks` when the options do not provide
+/// one.
+pub(crate) const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ZoneManifestEntry {
+    /// Row of list_zone.stb
+    pub zone_id: usize,
+
+    /// Name column of list_zone.stb, usually a string table key
+    pub name: String,
+
+    /// Path of the ZON, relative to the assets root
+    pub zon: String,
+
+    /// The exported file, relative to the output directory
+    pub output: Option<PathBuf>,
+
+    /// Why the zone could not be exported
+    pub error: Option<String>,
+}
+
+/// Summary of an `export_zones` run, written to zones.json in the output
+/// directory.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ZoneManifest {
+    pub zones: Vec<ZoneManifestEntry>,
+}
+
+impl ZoneManifest {
+    pub const FILE_NAME: &'static str = "zones.json";
+}
+
+fn export_zone(
+    zon_path: &Path,
+    output_path: &Path,
+    format: &GltfFormat,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<()> {
+    let gltf = rose_to_gltf(&[zon_path.to_path_buf()], options)?;
+    save_gltf(&gltf, output_path, format)
+}
+
+/// Removes the files written for `outputs`, relative to `output`, and
+/// `output` itself when it was created for them and is left empty. Used when
+/// an export is cancelled, so it does not leave part of its output behind.
+fn remove_outputs<'a>(
+    output: &Path,
+    created_output: bool,
+    outputs: impl IntoIterator<Item = &'a PathBuf>,
+    format: &GltfFormat,
+) {
+    for file_name in outputs {
+        let path = output.join(file_name);
+        let _ = std::fs::remove_file(&path);
+        if *format == GltfFormat::Text {
+            let _ = std::fs::remove_file(path.with_extension("bin"));
+        }
+    }
+    if created_output {
+        let _ = std::fs::remove_dir(output);
+    }
+}
+
+/// Converts zones of list_zone.stb in `assets_root` to a glTF per zone in
+/// `output`, all zones are converted when `zone_ids` is None.
+///
+/// Parsed object lists, meshes and textures are shared between zones through
+/// the cache in `options`, or a new cache when there is none. A zone which
+/// fails to convert is recorded in the manifest instead of stopping the
+/// export. When the export is cancelled the zones already written are
+/// removed.
+pub fn export_zones(
+    assets_root: &Path,
+    zone_ids: Option<&[usize]>,
+    output: &Path,
+    format: &GltfFormat,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<ZoneManifest> {
+    let mut options = options.clone();
+    if options.asset_cache.is_none() {
+        options.asset_cache = Some(Arc::new(AssetCache::new(DEFAULT_CACHE_CAPACITY)));
+    }
+    let assets: Arc<dyn AssetLocator> = match &options.asset_locator {
+        Some(asset_locator) => asset_locator.clone(),
+        None => {
+            let mut roots = options.asset_roots.clone();
+            roots.push(assets_root.to_path_buf());
+            Arc::new(FsAssetLocator::new(roots))
+        }
+    };
+    options.asset_locator = Some(assets.clone());
+
+    let list_zone = load_cached::<ListZoneStb>(
+        options.asset_cache.as_deref(),
+        &assets.resolve("3ddata/stb/list_zone.stb"),
+    )
+    .context("Failed to load list_zone.stb")?;
+    let zone_ids = match zone_ids {
+        Some(zone_ids) => zone_ids.to_vec(),
+        None => (1..list_zone.rows())
+            .filter(|&row| list_zone.zon_path(row).is_some())
+            .collect(),
+    };
+
+    let created_output = !output.exists();
+    std::fs::create_dir_all(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+
+    let mut manifest = ZoneManifest::default();
+    let cancelled = |manifest: &ZoneManifest, error: anyhow::Error| {
+        remove_outputs(
+            output,
+            created_output,
+            manifest
+                .zones
+                .iter()
+                .filter_map(|entry| entry.output.as_ref()),
+            format,
+        );
+        Err(error)
+    };
+    for zone_id in zone_ids {
+        if let Err(error) = check_cancelled(options.cancellation.as_ref()) {
+            return cancelled(&manifest, error);
+        }
+        let zon = list_zone.zon_path(zone_id).unwrap_or_default();
+        let mut entry = ZoneManifestEntry {
+            zone_id,
+            name: list_zone.name(zone_id).unwrap_or_default().to_string(),
+            zon: zon.to_string(),
+            output: None,
+            error: None,
+        };
+
+        let result = (|| {
+            anyhow::ensure!(!zon.is_empty(), "list_zone.stb row {} has no zon", zone_id);
+            let zon_path = assets
+                .locate(zon)
+                .with_context(|| format!("Could not find {}", zon))?;
+            let stem = zon_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            let file_name = PathBuf::from(format!("{}_{}", zone_id, sanitize_name(&stem)))
+                .with_extension(format.file_extension());
+            export_zone(&zon_path, &output.join(&file_name), format, &options)?;
+            Ok(file_name)
+        })();
+        match result {
+            Ok(file_name) => {
+                info(format!(
+                    "Exported zone {} to {}",
+                    zone_id,
+                    file_name.display()
+                ));
+                entry.output = Some(file_name);
+            }
+            Err(error) if ErrorCategory::of(&error) == ErrorCategory::Cancelled => {
+                return cancelled(&manifest, error);
+            }
+            Err(error) => {
+                warn(format!("Failed to export zone {}: {:?}", zone_id, error));
+                entry.error = Some(format!("{:#}", error));
+            }
+        }
+        manifest.zones.push(entry);
+    }
+
+    let manifest_path = output.join(ZoneManifest::FILE_NAME);
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+    Ok(manifest)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockManifestEntry {
+    pub block_x: i32,
+    pub block_y: i32,
+
+    /// The exported file, relative to the output directory
+    pub output: PathBuf,
+
+    /// Translation of the block's terrain in glTF coordinates, the same as
+    /// the translation of its heightmap node.
+    pub translation: [f32; 3],
+
+    /// Minimum corner of the block's terrain bounds in glTF coordinates,
+    /// objects placed in the block may extend outside of it.
+    pub min: [f32; 3],
+
+    /// Maximum corner of the block's terrain bounds in glTF coordinates
+    pub max: [f32; 3],
+
+    /// The exported blocks touching this one, including diagonally, as
+    /// `[block_x, block_y]`
+    pub neighbors: Vec<[i32; 2]>,
+}
+
+/// Describes the blocks written by `export_zone_blocks`, written to
+/// blocks.json in the output directory.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BlockManifest {
+    /// Size of a block along x and z in glTF units
+    pub block_size: f32,
+    pub blocks: Vec<BlockManifestEntry>,
+}
+
+impl BlockManifest {
+    pub const FILE_NAME: &'static str = "blocks.json";
+}
+
+/// The blocks of the map in `map_path` selected by the `filter_block_x` and
+/// `filter_block_y` options, with their heightmaps. Blocks are only converted
+/// when all of their files exist.
+pub(crate) fn zone_blocks(map_path: &Path, options: &RoseGltfConvOptions) -> Vec<(i32, i32, HIM)> {
+    let mut blocks = Vec::new();
+    for block_y in 0..64 {
+        for block_x in 0..64 {
+            if options.filter_block_x.is_some_and(|x| x != block_x)
+                || options.filter_block_y.is_some_and(|y| y != block_y)
+            {
+                continue;
+            }
+
+            let block_name = format!("{}_{}", block_x, block_y);
+            if ["ifo", "til"].iter().any(|extension| {
+                !map_path
+                    .join(format!("{}.{}", block_name, extension))
+                    .exists()
+            }) {
+                continue;
+            }
+            if let Ok(him) = HIM::from_path_mmap(&map_path.join(format!("{}.him", block_name))) {
+                blocks.push((block_x, block_y, him));
+            }
+        }
+    }
+    blocks
+}
+
+/// Converts each block of a zone to its own glTF in `output` and writes a
+/// manifest describing where to place each block and which blocks are next
+/// to it.
+///
+/// The `filter_block_x` and `filter_block_y` options limit which blocks are
+/// exported, the same as when converting the whole zone. When the export is
+/// cancelled the blocks already written are removed.
+pub fn export_zone_blocks(
+    zon_path: &Path,
+    output: &Path,
+    format: &GltfFormat,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<BlockManifest> {
+    let map_path = zon_path.parent().context("Could not find map path")?;
+    let mut options = options.clone();
+    if options.asset_cache.is_none() {
+        options.asset_cache = Some(Arc::new(AssetCache::new(DEFAULT_CACHE_CAPACITY)));
+    }
+
+    let blocks = zone_blocks(map_path, &options);
+    let created_output = !output.exists();
+    std::fs::create_dir_all(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+
+    let mut manifest = BlockManifest {
+        block_size: BLOCK_SIZE,
+        blocks: Vec::with_capacity(blocks.len()),
+    };
+    for (block_x, block_y, him) in blocks.iter() {
+        let (block_x, block_y) = (*block_x, *block_y);
+        let file_name = PathBuf::from(format!("{}_{}", block_x, block_y))
+            .with_extension(format.file_extension());
+        let block_options = RoseGltfConvOptions {
+            filter_block_x: Some(block_x),
+            filter_block_y: Some(block_y),
+            ..options.clone()
+        };
+        let result = check_cancelled(options.cancellation.as_ref()).and_then(|_| {
+            export_zone(zon_path, &output.join(&file_name), format, &block_options)
+                .with_context(|| format!("Failed to export block {}_{}", block_x, block_y))
+        });
+        if let Err(error) = result {
+            if ErrorCategory::of(&error) == ErrorCategory::Cancelled {
+                remove_outputs(
+                    output,
+                    created_output,
+                    manifest.blocks.iter().map(|block| &block.output),
+                    format,
+                );
+            }
+            return Err(error);
+        }
+
+        let translation = block_translation(block_x, block_y);
+        let (min_height, max_height) = if him.heights.is_empty() {
+            (0.0, 0.0)
+        } else {
+            (him.min_height / 100.0, him.max_height / 100.0)
+        };
+        let neighbors = blocks
+            .iter()
+            .filter(|(x, y, _)| {
+                (*x, *y) != (block_x, block_y)
+                    && (x - block_x).abs() <= 1
+                    && (y - block_y).abs() <= 1
+            })
+            .map(|(x, y, _)| [*x, *y])
+            .collect();
+
+        manifest.blocks.push(BlockManifestEntry {
+            block_x,
+            block_y,
+            output: file_name,
+            translation,
+            min: [translation[0], min_height, translation[2]],
+            max: [
+                translation[0] + BLOCK_SIZE,
+                max_height,
+                translation[2] + BLOCK_SIZE,
+            ],
+            neighbors,
+        });
+    }
+
+    let manifest_path = output.join(BlockManifest::FILE_NAME);
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+    Ok(manifest)
+}
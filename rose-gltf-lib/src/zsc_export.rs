@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+
+use glam::{Mat4, Vec3};
+use rose_file_lib::{
+    files::{
+        zsc::{Model, ModelMaterial, ModelPart},
+        ZSC,
+    },
+    io::RoseFile,
+    utils::{Quaternion, Vector2, Vector3},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct TexturePathRules {
+    /// Pairs of (from, to) path prefixes, the first matching prefix is replaced.
+    /// Prefixes are matched case insensitively with either path separator.
+    pub prefix_mappings: Vec<(String, String)>,
+
+    /// Directory for textures which match no prefix mapping, e.g. `3ddata\custom`
+    pub default_directory: String,
+
+    /// Replace the texture extension with .dds
+    pub dds_extension: bool,
+
+    /// Lowercase the texture path
+    pub lowercase: bool,
+}
+
+pub fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(value) = std::str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(value);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+pub fn join_rose_path(directory: &str, file: &str) -> String {
+    let path = if directory.is_empty() {
+        file.to_string()
+    } else {
+        format!("{}\\{}", directory, file)
+    };
+
+    let mut joined = String::with_capacity(path.len());
+    for c in path.chars().map(|c| if c == '/' { '\\' } else { c }) {
+        if c == '\\' && joined.ends_with('\\') {
+            continue;
+        }
+        joined.push(c);
+    }
+    joined
+}
+
+impl TexturePathRules {
+    /// Rewrites a glTF image URI into a ROSE texture path, e.g.
+    /// `C:/Users/me/My%20Textures/Wall.png` into `3ddata\custom\wall.dds`
+    pub fn rewrite(&self, uri: &str) -> String {
+        let decoded = percent_decode(uri.strip_prefix("file://").unwrap_or(uri));
+        let normalized = decoded.replace('\\', "/");
+        let normalized_lowercase = normalized.to_ascii_lowercase();
+
+        let mapped = self.prefix_mappings.iter().find_map(|(from, to)| {
+            let from = from.replace('\\', "/").to_ascii_lowercase();
+            normalized_lowercase
+                .starts_with(&from)
+                .then(|| join_rose_path(to, normalized[from.len()..].trim_start_matches('/')))
+        });
+
+        let mut path = mapped.unwrap_or_else(|| {
+            let file_name = normalized.rsplit('/').next().unwrap_or(&normalized);
+            join_rose_path(&self.default_directory, file_name)
+        });
+
+        if self.dds_extension {
+            let file_start = path.rfind('\\').map_or(0, |i| i + 1);
+            if let Some(extension_start) = path[file_start..].rfind('.') {
+                path.truncate(file_start + extension_start);
+            }
+            path.push_str(".dds");
+        }
+
+        if self.lowercase {
+            path = path.to_lowercase();
+        }
+
+        path
+    }
+}
+
+/// Returns the URI of the base color texture of a material, or a generated
+/// name for images embedded in a buffer view.
+pub fn material_texture_uri(material: &gltf::Material) -> Option<(usize, String)> {
+    let texture = material
+        .pbr_metallic_roughness()
+        .base_color_texture()?
+        .texture();
+    let image = texture.source();
+    let uri = match image.source() {
+        gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => uri.to_string(),
+        gltf::image::Source::Uri { .. } | gltf::image::Source::View { .. } => {
+            format!(
+                "{}.png",
+                image
+                    .name()
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| format!("image_{}", image.index()))
+            )
+        }
+    };
+    Some((image.index(), uri))
+}
+
+fn convert_material(material: &gltf::Material, rules: &TexturePathRules) -> ModelMaterial {
+    ModelMaterial {
+        path: material_texture_uri(material)
+            .map(|(_, uri)| rules.rewrite(&uri))
+            .unwrap_or_default(),
+        alpha_enabled: material.alpha_mode() == gltf::material::AlphaMode::Blend,
+        two_sided: material.double_sided(),
+        alpha_test: (material.alpha_mode() == gltf::material::AlphaMode::Mask)
+            .then(|| (material.alpha_cutoff().unwrap_or(0.5) * 256.0).clamp(0.0, 255.0) as u8),
+        alpha: material.pbr_metallic_roughness().base_color_factor()[3],
+        ..Default::default()
+    }
+}
+
+fn add_parts(
+    model: &mut Model,
+    node: &gltf::Node,
+    transform: Mat4,
+    mesh_paths: &HashMap<usize, Vec<String>>,
+    animation_paths: &HashMap<usize, String>,
+    rules: &TexturePathRules,
+) {
+    if let Some(mesh) = node.mesh() {
+        let material = mesh
+            .primitives()
+            .next()
+            .map(|primitive| convert_material(&primitive.material(), rules));
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+
+        for mesh_path in mesh_paths.get(&mesh.index()).into_iter().flatten() {
+            model.parts.push(ModelPart {
+                mesh_path: mesh_path.clone(),
+                material: material.clone(),
+                position: Vector3 {
+                    x: translation.x * 100.0,
+                    y: -translation.z * 100.0,
+                    z: translation.y * 100.0,
+                },
+                rotation: Quaternion {
+                    x: rotation.x,
+                    y: -rotation.z,
+                    z: rotation.y,
+                    w: rotation.w,
+                },
+                scale: Vector3 {
+                    x: scale.x,
+                    y: scale.z,
+                    z: scale.y,
+                },
+                animation_path: animation_paths.get(&node.index()).cloned(),
+                ..Default::default()
+            });
+        }
+    }
+
+    for child in node.children() {
+        let child_transform = transform * Mat4::from_cols_array_2d(&child.transform().matrix());
+        add_parts(
+            model,
+            &child,
+            child_transform,
+            mesh_paths,
+            animation_paths,
+            rules,
+        );
+    }
+}
+
+/// Extends `bounds` with the vertices of the meshes which become parts in the
+/// node hierarchy, in ZSC coordinates relative to the model
+fn extend_bounds(
+    bounds: &mut Option<(Vec3, Vec3)>,
+    node: &gltf::Node,
+    transform: Mat4,
+    mesh_paths: &HashMap<usize, Vec<String>>,
+    buffers: &[gltf::buffer::Data],
+) {
+    if let Some(mesh) = node
+        .mesh()
+        .filter(|mesh| mesh_paths.contains_key(&mesh.index()))
+    {
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|b| &b[..]));
+            for position in reader.read_positions().into_iter().flatten() {
+                let position = transform.transform_point3(Vec3::from_array(position));
+                let position = Vec3::new(position.x, -position.z, position.y) * 100.0;
+                *bounds = Some(match *bounds {
+                    Some((min, max)) => (min.min(position), max.max(position)),
+                    None => (position, position),
+                });
+            }
+        }
+    }
+
+    for child in node.children() {
+        let child_transform = transform * Mat4::from_cols_array_2d(&child.transform().matrix());
+        extend_bounds(bounds, &child, child_transform, mesh_paths, buffers);
+    }
+}
+
+/// Generates a model for each root node of the scene, named after the node,
+/// every mesh in the node hierarchy becomes a part positioned relative to the
+/// root node. Parts of nodes in `animation_paths` reference the ZMO animating
+/// them. The bounds of the model are of the vertices of its parts, read from
+/// `buffers`.
+pub fn generate_models(
+    scene: &gltf::Scene,
+    buffers: &[gltf::buffer::Data],
+    mesh_paths: &HashMap<usize, Vec<String>>,
+    animation_paths: &HashMap<usize, String>,
+    rules: &TexturePathRules,
+) -> Vec<(String, Model)> {
+    let mut models = Vec::new();
+
+    for node in scene.nodes() {
+        let mut model = Model::default();
+        add_parts(
+            &mut model,
+            &node,
+            Mat4::IDENTITY,
+            mesh_paths,
+            animation_paths,
+            rules,
+        );
+        if model.parts.is_empty() {
+            continue;
+        }
+
+        let mut bounds = None;
+        extend_bounds(&mut bounds, &node, Mat4::IDENTITY, mesh_paths, buffers);
+        let (min, max) = bounds.unwrap_or_default();
+        model.bounding_box.min = Vector3 {
+            x: min.x,
+            y: min.y,
+            z: min.z,
+        };
+        model.bounding_box.max = Vector3 {
+            x: max.x,
+            y: max.y,
+            z: max.z,
+        };
+        let center = (min + max) / 2.0;
+        model.bounding_cylinder.center = Vector2 {
+            x: center.x.round() as i32,
+            y: center.y.round() as i32,
+        };
+        model.bounding_cylinder.radius = (max - min).truncate().length() / 2.0;
+
+        let name = node
+            .name()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("node_{}", node.index()));
+        models.push((name, model));
+    }
+
+    models
+}
+
+/// Generates a ZSC with a model for each root node of the scene, see
+/// [`generate_models`].
+pub fn generate_zsc(
+    scene: &gltf::Scene,
+    buffers: &[gltf::buffer::Data],
+    mesh_paths: &HashMap<usize, Vec<String>>,
+    animation_paths: &HashMap<usize, String>,
+    rules: &TexturePathRules,
+) -> ZSC {
+    let mut zsc = ZSC::new();
+    zsc.models = generate_models(scene, buffers, mesh_paths, animation_paths, rules)
+        .into_iter()
+        .map(|(_, model)| Some(model))
+        .collect();
+    zsc
+}
+
+/// Converts decoded glTF image data to RGBA8, returns None for formats which
+/// are not 8 bits per channel.
+pub fn image_data_to_rgba(data: &gltf::image::Data) -> Option<image::RgbaImage> {
+    let pixels: Vec<u8> = match data.format {
+        gltf::image::Format::R8 => data.pixels.iter().flat_map(|&r| [r, r, r, 255]).collect(),
+        gltf::image::Format::R8G8 => data
+            .pixels
+            .chunks_exact(2)
+            .flat_map(|rg| [rg[0], rg[1], 0, 255])
+            .collect(),
+        gltf::image::Format::R8G8B8 => data
+            .pixels
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        gltf::image::Format::R8G8B8A8 => data.pixels.clone(),
+        _ => return None,
+    };
+    image::RgbaImage::from_raw(data.width, data.height, pixels)
+}
@@ -0,0 +1,223 @@
+//! Converting text ZSC models (ZSCTXT), which hold a single model, so props
+//! can be previewed without converting a whole zone.
+use std::sync::Arc;
+
+use gltf_json::{mesh, scene, texture, validation::Checked, Index};
+use rose_file_lib::{
+    files::{
+        zsc::{Model, ModelDummyAttachment},
+        ZMO, ZSC,
+    },
+    io::RoseFile,
+};
+use serde_json::{json, value::RawValue};
+
+use crate::{
+    animation::load_animation,
+    assets::AssetLocator,
+    engine_hints::part_collider,
+    light_container::load_light_container,
+    object_list::ObjectList,
+    report::warn,
+    spill::BinaryData,
+    zone::{convert_position, convert_rotation, convert_scale},
+    RoseGltfConvOptions,
+};
+
+/// Extras of a dummy point, the effect or light attached to it
+fn dummy_extras(attachment: &ModelDummyAttachment) -> serde_json::Value {
+    match attachment {
+        ModelDummyAttachment::Effect {
+            path,
+            only_visible_at_night,
+        } => json!({
+            "rose_dummy": {
+                "effect": path,
+                "only_visible_at_night": only_visible_at_night,
+            }
+        }),
+        ModelDummyAttachment::Light { name } => json!({
+            "rose_dummy": {
+                "light": name,
+            }
+        }),
+    }
+}
+
+/// Adds a node named `name` for the model, with a child node for each of its
+/// parts and dummy points, placed below their parent part. The lights of light
+/// container dummy points are children of the dummy point node.
+pub(crate) fn load_model_text(
+    root: &mut gltf_json::Root,
+    binary_data: &mut BinaryData,
+    name: &str,
+    model: Model,
+    assets: &dyn AssetLocator,
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<()> {
+    let sampler = Index::<texture::Sampler>::new(root.samplers.len() as u32);
+    root.samplers.push(texture::Sampler {
+        name: Some(format!("{}_sampler", name)),
+        mag_filter: Some(Checked::Valid(texture::MagFilter::Linear)),
+        min_filter: Some(Checked::Valid(texture::MinFilter::LinearMipmapLinear)),
+        wrap_s: Checked::Valid(texture::WrappingMode::Repeat),
+        wrap_t: Checked::Valid(texture::WrappingMode::Repeat),
+        extensions: None,
+        extras: Default::default(),
+    });
+
+    let zsc = ZSC {
+        models: vec![Some(model)],
+    };
+    let mut object_list = ObjectList::new(Arc::new(zsc), sampler);
+    object_list.generate_missing_tangents = options.generate_missing_tangents;
+    object_list.flip_v = options.flip_v;
+    object_list.f32_vertex_colors = options.f32_vertex_colors;
+    object_list.winding = options.winding;
+    object_list.hard_edge_angle = options.hard_edge_angle;
+    object_list.glass_materials = options.glass_materials.clone();
+    object_list.asset_cache = options.asset_cache.clone();
+    object_list.low_memory = options.low_memory;
+    object_list.ignore_missing_assets = options.ignore_missing_assets;
+    object_list.placeholder_textures = options.placeholder_textures;
+    object_list.embedded_images = options.embedded_images;
+    object_list.engine_hints = options.engine_hints;
+    object_list.load_object(name, 0, root, binary_data, assets)?;
+    let model = object_list.zsc.models[0].as_ref().unwrap();
+
+    // Parts and dummy points without a parent part are children of the model
+    // node
+    let mut children = Vec::new();
+    let mut add_to_parent = |root: &mut gltf_json::Root,
+                             parent_node: Option<Index<scene::Node>>,
+                             node_index: Index<scene::Node>| {
+        match parent_node {
+            Some(parent_node) => root.nodes[parent_node.value()]
+                .children
+                .get_or_insert_with(Vec::new)
+                .push(node_index),
+            None => children.push(node_index),
+        }
+    };
+
+    let mut part_nodes = Vec::with_capacity(model.parts.len());
+    for (part_index, part) in model.parts.iter().enumerate() {
+        let mesh_data = object_list
+            .meshes
+            .get(&part.mesh_path)
+            .expect("Missing mesh");
+        let mesh_index = Index::new(root.meshes.len() as u32);
+        root.meshes.push(mesh::Mesh {
+            name: Some(format!("{}_{}_mesh", name, part_index)),
+            extensions: Default::default(),
+            extras: Default::default(),
+            primitives: vec![mesh::Primitive {
+                attributes: mesh_data.attributes.clone(),
+                extensions: Default::default(),
+                extras: Default::default(),
+                indices: Some(mesh_data.indices),
+                material: part
+                    .material
+                    .as_ref()
+                    .and_then(|material| object_list.materials.get(material).copied()),
+                mode: Checked::Valid(mesh::Mode::Triangles),
+                targets: None,
+            }],
+            weights: None,
+        });
+
+        let collider = part_collider(part);
+        let mut extras = json!({});
+        options.engine_hints.add_node_extras(&mut extras, collider);
+
+        let node_index = Index::new(root.nodes.len() as u32);
+        root.nodes.push(scene::Node {
+            name: Some(
+                options
+                    .engine_hints
+                    .node_name(format!("{}_{}", name, part_index), collider),
+            ),
+            camera: None,
+            children: None,
+            extensions: Default::default(),
+            extras: (extras != json!({}))
+                .then(|| RawValue::from_string(extras.to_string()).unwrap()),
+            matrix: None,
+            mesh: Some(mesh_index),
+            rotation: Some(convert_rotation(part.rotation)),
+            scale: Some(convert_scale(part.scale)),
+            translation: Some(convert_position(part.position)),
+            skin: None,
+            weights: None,
+        });
+        // Parts are only attached to earlier parts, so the nodes can not form
+        // a cycle
+        let parent_node = part
+            .parent
+            .and_then(|parent| part_nodes.get(parent as usize).copied());
+        add_to_parent(root, parent_node, node_index);
+        part_nodes.push(node_index);
+
+        if let Some(animation_path) = part.animation_path.as_ref() {
+            if options.skip_animations {
+                continue;
+            }
+            let animation_path = assets.resolve(animation_path);
+            if let Ok(zmo) = ZMO::from_path(&animation_path) {
+                load_animation(
+                    root,
+                    binary_data,
+                    &zmo,
+                    &format!("{}_{}_anim", name, part_index),
+                    node_index,
+                    options.quantize_animations,
+                );
+            } else {
+                warn(format!(
+                    "Failed to load {}",
+                    animation_path.to_string_lossy()
+                ));
+            }
+        }
+    }
+
+    for (dummy_index, dummy) in model.dummy_points.iter().enumerate() {
+        let dummy_name = format!("{}_dummy_{}", name, dummy_index);
+        let light_nodes = match dummy.attachment.as_ref() {
+            Some(ModelDummyAttachment::Light { name: light_path }) => {
+                Some(load_light_container(root, &dummy_name, light_path, assets))
+            }
+            _ => None,
+        };
+        let node_index = Index::new(root.nodes.len() as u32);
+        root.nodes.push(scene::Node {
+            name: Some(dummy_name),
+            camera: None,
+            children: light_nodes,
+            extensions: Default::default(),
+            extras: dummy.attachment.as_ref().map(|attachment| {
+                RawValue::from_string(dummy_extras(attachment).to_string()).unwrap()
+            }),
+            matrix: None,
+            mesh: None,
+            rotation: Some(convert_rotation(dummy.rotation)),
+            scale: Some(convert_scale(dummy.scale)),
+            translation: Some(convert_position(dummy.position)),
+            skin: None,
+            weights: None,
+        });
+        let parent_node = dummy
+            .parent
+            .and_then(|parent| part_nodes.get(parent as usize).copied());
+        add_to_parent(root, parent_node, node_index);
+    }
+
+    let model_node = Index::new(root.nodes.len() as u32);
+    root.nodes.push(scene::Node {
+        name: Some(name.to_string()),
+        children: Some(children),
+        ..Default::default()
+    });
+    root.scenes[0].nodes.push(model_node);
+    Ok(())
+}
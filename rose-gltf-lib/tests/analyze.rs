@@ -0,0 +1,49 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    analyze_gltf, rose_to_gltf_data, test_assets::write_test_assets, RoseGltfConvOptions,
+};
+
+#[test]
+fn size_breakdown() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("size_breakdown");
+    let assets = write_test_assets(&directory).unwrap();
+    let gltf = rose_to_gltf_data(
+        &[
+            assets.zmd.clone(),
+            assets.skinned_zms.clone(),
+            assets.zmo.clone(),
+        ],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let analysis = analyze_gltf(&gltf);
+
+    // 4 vertices of a position, normal, uv, 4 f32 weights and 4 u16 joints
+    let bytes = &analysis.buffer_view_bytes;
+    assert_eq!(bytes.vertex, 4 * (12 + 12 + 8 + 16 + 8));
+    // 2 triangles of u16 indices
+    assert_eq!(bytes.index, 6 * 2);
+    // 2 keyframe times, translations and rotations
+    assert_eq!(bytes.animation, 2 * 4 + 2 * 12 + 2 * 16);
+    // An inverse bind matrix for each of the 2 bones
+    assert_eq!(bytes.skin, 2 * 64);
+    assert_eq!((bytes.image, bytes.other), (0, 0));
+    assert_eq!(bytes.total(), 224 + 12 + 64 + 128);
+
+    assert_eq!(analysis.nodes, 3);
+    assert_eq!((analysis.meshes, analysis.primitives), (1, 1));
+    assert_eq!((analysis.animations, analysis.skins), (1, 1));
+    assert_eq!(
+        (analysis.materials, analysis.textures, analysis.images),
+        (0, 0, 0)
+    );
+
+    let mesh = &analysis.largest_meshes[0];
+    assert_eq!(mesh.name, "skinned_quad");
+    assert_eq!((mesh.vertices, mesh.triangles), (4, 2));
+    assert_eq!(mesh.bytes, 224 + 12);
+    assert_eq!(mesh.instances, 1);
+    assert_eq!(analysis.instanced_nodes, 0);
+    assert!(analysis.duplicate_images.is_empty());
+}
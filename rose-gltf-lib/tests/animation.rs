@@ -0,0 +1,548 @@
+use std::path::Path;
+
+use rose_file_lib::{
+    files::{
+        zmo::{Channel, ChannelData, ChannelType},
+        ZMO,
+    },
+    io::RoseFile,
+    utils::{Quaternion, Vector3},
+};
+use rose_gltf_lib::{
+    gltf_to_rose, rose_to_gltf_data, test_assets::write_test_assets, AnimationConcat,
+    AnimationLoop, AnimationLoopRule, AnimationOverride, AnimationSplit, GltfData,
+    GltfRoseConvOptions, RoseGltfConvOptions,
+};
+
+fn walk_zmo() -> ZMO {
+    let half_sqrt2 = std::f32::consts::FRAC_1_SQRT_2;
+    ZMO {
+        identifier: "ZMO0002".to_string(),
+        fps: 24,
+        frames: 4,
+        channels: vec![
+            Channel {
+                typ: ChannelType::Position,
+                index: 0,
+                frames: ChannelData::Position(vec![
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 25.0, 5.0),
+                    Vector3::new(0.0, 50.0, 0.0),
+                    Vector3::new(0.0, 75.0, 5.0),
+                ]),
+            },
+            Channel {
+                typ: ChannelType::Rotation,
+                index: 1,
+                frames: ChannelData::Rotation(vec![
+                    Quaternion::IDENTITY,
+                    Quaternion::new(0.0, 0.0, half_sqrt2, half_sqrt2),
+                    Quaternion::new(0.0, 0.0, 1.0, 0.0),
+                    Quaternion::new(0.0, 0.0, half_sqrt2, -half_sqrt2),
+                ]),
+            },
+        ],
+    }
+}
+
+#[test]
+fn frame_times_and_fps_round_trip() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("frame_times_and_fps_round_trip");
+    let assets = write_test_assets(&directory).unwrap();
+    let mut source = walk_zmo();
+    source.write_to_path(&assets.zmo).unwrap();
+
+    let gltf = rose_to_gltf_data(
+        &[assets.zmd.clone(), assets.zmo.clone()],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    // Keyframes are at exact multiples of 1 / fps, and the fps is recorded
+    let animation = gltf.document.animations().next().unwrap();
+    let channel = animation.channels().next().unwrap();
+    let reader = channel.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+    let times: Vec<f32> = reader.read_inputs().unwrap().collect();
+    let expected_times: Vec<f32> = (0..4).map(|frame| frame as f32 / 24.0).collect();
+    assert_eq!(times, expected_times);
+    let extras: serde_json::Value =
+        serde_json::from_str(animation.extras().as_ref().unwrap().get()).unwrap();
+    assert_eq!(extras["rose_zmo"]["fps"], 24);
+
+    // Without an fps in the options the recorded one is used
+    let result = gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap();
+    let zmo = &result.zmo[0].1;
+    assert_eq!(zmo.fps, source.fps);
+    assert_eq!(zmo.frames, source.frames);
+    assert_eq!(zmo.channels.len(), source.channels.len());
+    for (channel, source_channel) in zmo.channels.iter().zip(source.channels.iter()) {
+        assert_eq!(channel.typ, source_channel.typ);
+        assert_eq!(channel.index, source_channel.index);
+        match (&channel.frames, &source_channel.frames) {
+            (ChannelData::Position(positions), ChannelData::Position(source_positions)) => {
+                assert_eq!(positions.len(), source_positions.len());
+                for (position, source_position) in positions.iter().zip(source_positions) {
+                    assert!((position.x - source_position.x).abs() < 1.0e-4);
+                    assert!((position.y - source_position.y).abs() < 1.0e-4);
+                    assert!((position.z - source_position.z).abs() < 1.0e-4);
+                }
+            }
+            (ChannelData::Rotation(rotations), ChannelData::Rotation(source_rotations)) => {
+                assert_eq!(rotations.len(), source_rotations.len());
+                for (rotation, source_rotation) in rotations.iter().zip(source_rotations) {
+                    assert!((rotation.x - source_rotation.x).abs() < 1.0e-4);
+                    assert!((rotation.y - source_rotation.y).abs() < 1.0e-4);
+                    assert!((rotation.z - source_rotation.z).abs() < 1.0e-4);
+                    assert!((rotation.w - source_rotation.w).abs() < 1.0e-4);
+                }
+            }
+            _ => panic!("Unexpected channel data"),
+        }
+    }
+}
+
+#[test]
+fn loop_extras_and_trimmed_duplicate_frame() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("loop_extras");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // The last frame repeats the first so the motion loops in game
+    let mut zmo = walk_zmo();
+    for channel in zmo.channels.iter_mut() {
+        match &mut channel.frames {
+            ChannelData::Position(positions) => positions[3] = positions[0],
+            ChannelData::Rotation(rotations) => rotations[3] = rotations[0],
+            _ => {}
+        }
+    }
+    zmo.write_to_path(&assets.zmo).unwrap();
+    let input_files = [assets.zmd.clone(), assets.zmo.clone()];
+
+    let loop_extras = |gltf: &GltfData| -> serde_json::Value {
+        let animation = gltf.document.animations().next().unwrap();
+        let extras: serde_json::Value =
+            serde_json::from_str(animation.extras().as_ref().unwrap().get()).unwrap();
+        extras["loop"].clone()
+    };
+    let num_keyframes = |gltf: &GltfData| {
+        let animation = gltf.document.animations().next().unwrap();
+        animation.samplers().next().unwrap().input().count()
+    };
+
+    let gltf = rose_to_gltf_data(&input_files, &RoseGltfConvOptions::default()).unwrap();
+    assert_eq!(loop_extras(&gltf), serde_json::Value::Null);
+    assert_eq!(num_keyframes(&gltf), 4);
+
+    let options = RoseGltfConvOptions {
+        animation_loops: vec![
+            AnimationLoopRule {
+                pattern: "^die".to_string(),
+                mode: AnimationLoop::Once,
+            },
+            AnimationLoopRule {
+                pattern: "two_bone".to_string(),
+                mode: AnimationLoop::Loop,
+            },
+        ],
+        trim_last_duplicate_frame: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(&input_files, &options).unwrap();
+    assert_eq!(loop_extras(&gltf), serde_json::Value::Bool(true));
+    assert_eq!(num_keyframes(&gltf), 3);
+
+    // Animations played once keep their last frame
+    let options = RoseGltfConvOptions {
+        animation_loops: vec![AnimationLoopRule {
+            pattern: ".*".to_string(),
+            mode: AnimationLoop::Once,
+        }],
+        trim_last_duplicate_frame: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(&input_files, &options).unwrap();
+    assert_eq!(loop_extras(&gltf), serde_json::Value::Bool(false));
+    assert_eq!(num_keyframes(&gltf), 4);
+}
+
+#[test]
+fn split_animation() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("split_animation");
+    let assets = write_test_assets(&directory).unwrap();
+    walk_zmo().write_to_path(&assets.zmo).unwrap();
+    let input_files = [assets.zmd.clone(), assets.zmo.clone()];
+
+    let options = RoseGltfConvOptions {
+        animation_splits: vec![
+            AnimationSplit {
+                name: "start".to_string(),
+                start_frame: 0,
+                end_frame: 1,
+            },
+            AnimationSplit {
+                name: "end".to_string(),
+                start_frame: 1,
+                end_frame: 3,
+            },
+        ],
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(&input_files, &options).unwrap();
+    let animations: Vec<(String, Vec<[f32; 3]>)> = gltf
+        .document
+        .animations()
+        .map(|animation| {
+            let channel = animation
+                .channels()
+                .find(|channel| {
+                    channel.target().property() == gltf::animation::Property::Translation
+                })
+                .unwrap();
+            let reader = channel.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+            let gltf::animation::util::ReadOutputs::Translations(translations) =
+                reader.read_outputs().unwrap()
+            else {
+                panic!("Expected translations");
+            };
+            (
+                animation.name().unwrap().to_string(),
+                translations.collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        animations,
+        [
+            (
+                "start".to_string(),
+                vec![[0.0, 0.0, 0.0], [0.0, 0.05, -0.25]]
+            ),
+            (
+                "end".to_string(),
+                vec![[0.0, 0.05, -0.25], [0.0, 0.0, -0.5], [0.0, 0.05, -0.75]]
+            ),
+        ]
+    );
+
+    // Ranges past the end of the ZMO are an error
+    let options = RoseGltfConvOptions {
+        animation_splits: vec![AnimationSplit {
+            name: "past_end".to_string(),
+            start_frame: 2,
+            end_frame: 4,
+        }],
+        ..Default::default()
+    };
+    assert!(rose_to_gltf_data(&input_files, &options).is_err());
+}
+
+#[test]
+fn concatenate_animations() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("concatenate_animations");
+    let assets = write_test_assets(&directory).unwrap();
+    walk_zmo().write_to_path(&assets.zmo).unwrap();
+
+    // Split the ZMO so the glTF has two animations to append
+    let options = RoseGltfConvOptions {
+        animation_splits: vec![
+            AnimationSplit {
+                name: "first".to_string(),
+                start_frame: 0,
+                end_frame: 1,
+            },
+            AnimationSplit {
+                name: "second".to_string(),
+                start_frame: 2,
+                end_frame: 3,
+            },
+        ],
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(&[assets.zmd.clone(), assets.zmo.clone()], &options).unwrap();
+
+    let options = GltfRoseConvOptions {
+        animation_concat: Some(AnimationConcat {
+            name: "combined".to_string(),
+            animations: vec!["second".to_string(), "first".to_string()],
+            gap_frames: 1,
+            blend: true,
+        }),
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+    assert_eq!(result.zmo.len(), 1);
+    let (name, zmo) = &result.zmo[0];
+    assert_eq!(name, "combined");
+    assert_eq!(zmo.fps, 24);
+    assert_eq!(zmo.frames, 5);
+
+    let position = zmo
+        .channels
+        .iter()
+        .find(|channel| channel.typ == ChannelType::Position)
+        .unwrap();
+    let ChannelData::Position(positions) = &position.frames else {
+        panic!("Expected positions");
+    };
+    let expected = [
+        Vector3::new(0.0, 50.0, 0.0),
+        Vector3::new(0.0, 75.0, 5.0),
+        // Blended half way from the end of second to the start of first
+        Vector3::new(0.0, 37.5, 2.5),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 25.0, 5.0),
+    ];
+    assert_eq!(positions.len(), expected.len());
+    for (position, expected) in positions.iter().zip(expected.iter()) {
+        assert!((position.x - expected.x).abs() < 1.0e-4);
+        assert!((position.y - expected.y).abs() < 1.0e-4);
+        assert!((position.z - expected.z).abs() < 1.0e-4);
+    }
+
+    let options = GltfRoseConvOptions {
+        animation_concat: Some(AnimationConcat {
+            name: "combined".to_string(),
+            animations: vec!["missing".to_string()],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert!(gltf_to_rose(&gltf, &options).is_err());
+}
+
+#[test]
+fn retime_animations() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("retime_animations");
+    let assets = write_test_assets(&directory).unwrap();
+    walk_zmo().write_to_path(&assets.zmo).unwrap();
+    let input_files = [assets.zmd.clone(), assets.zmo.clone()];
+
+    // Twice as long, the keyframes are 2 / fps apart
+    let options = RoseGltfConvOptions {
+        retime: Some(2.0),
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(&input_files, &options).unwrap();
+    let animation = gltf.document.animations().next().unwrap();
+    let input = animation.samplers().next().unwrap().input();
+    assert_eq!(input.max(), Some(serde_json::json!([6.0f32 / 24.0])));
+    for channel in animation.channels() {
+        let reader = channel.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+        let times: Vec<f32> = reader.read_inputs().unwrap().collect();
+        let expected: Vec<f32> = (0..4).map(|frame| frame as f32 / 24.0 * 2.0).collect();
+        assert_eq!(times, expected);
+    }
+
+    // Converting back at the same speed has a frame for every 1 / fps
+    let result = gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap();
+    assert_eq!(result.zmo[0].1.fps, 24);
+    assert_eq!(result.zmo[0].1.frames, 7);
+
+    // Speeding it back up restores the original frames
+    let options = GltfRoseConvOptions {
+        retime: Some(0.5),
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+    let zmo = &result.zmo[0].1;
+    assert_eq!(zmo.frames, 4);
+    let position = zmo
+        .channels
+        .iter()
+        .find(|channel| channel.typ == ChannelType::Position)
+        .unwrap();
+    let (ChannelData::Position(positions), ChannelData::Position(source_positions)) =
+        (&position.frames, &walk_zmo().channels[0].frames)
+    else {
+        panic!("Expected positions");
+    };
+    for (position, source_position) in positions.iter().zip(source_positions) {
+        assert!((position.y - source_position.y).abs() < 1.0e-3);
+        assert!((position.z - source_position.z).abs() < 1.0e-3);
+    }
+
+    let options = RoseGltfConvOptions {
+        retime: Some(0.0),
+        ..Default::default()
+    };
+    assert!(rose_to_gltf_data(&input_files, &options).is_err());
+}
+
+#[test]
+fn scale_channel_round_trip() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("scale_channel_round_trip");
+    let assets = write_test_assets(&directory).unwrap();
+    let mut source = walk_zmo();
+    let scales = vec![1.0, 1.5, 2.0, 0.5];
+    source.channels.push(Channel {
+        typ: ChannelType::Scale,
+        index: 1,
+        frames: ChannelData::Scale(scales.clone()),
+    });
+    source.write_to_path(&assets.zmo).unwrap();
+
+    let gltf = rose_to_gltf_data(
+        &[assets.zmd.clone(), assets.zmo.clone()],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    // Every frame is sampled exactly at a keyframe
+    let result = gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap();
+    let zmo = &result.zmo[0].1;
+    assert_eq!(zmo.frames, source.frames);
+    let scale = zmo
+        .channels
+        .iter()
+        .find(|channel| channel.typ == ChannelType::Scale)
+        .unwrap();
+    assert_eq!(scale.index, 1);
+    let ChannelData::Scale(frames) = &scale.frames else {
+        panic!("Expected scales");
+    };
+    assert_eq!(frames.len(), scales.len());
+    for (frame, expected) in frames.iter().zip(scales.iter()) {
+        assert!(frame.is_finite());
+        assert!(
+            (frame - expected).abs() < 1.0e-5,
+            "{} != {}",
+            frame,
+            expected
+        );
+    }
+}
+
+#[test]
+fn animation_overrides() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("animation_overrides");
+    let assets = write_test_assets(&directory).unwrap();
+    walk_zmo().write_to_path(&assets.zmo).unwrap();
+
+    let options = RoseGltfConvOptions {
+        animation_splits: vec![
+            AnimationSplit {
+                name: "start".to_string(),
+                start_frame: 0,
+                end_frame: 1,
+            },
+            AnimationSplit {
+                name: "end".to_string(),
+                start_frame: 1,
+                end_frame: 3,
+            },
+        ],
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(&[assets.zmd.clone(), assets.zmo.clone()], &options).unwrap();
+
+    // Only "end" matches, it is clipped to its middle two frames and sampled
+    // at twice the fps
+    let options = GltfRoseConvOptions {
+        animation_overrides: vec![AnimationOverride {
+            pattern: "^en".to_string(),
+            fps: Some(48),
+            start_time: Some(1.0 / 24.0),
+            end_time: Some(2.0 / 24.0),
+        }],
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+    let positions = |zmo: &ZMO| -> Vec<[f32; 3]> {
+        let ChannelData::Position(positions) = &zmo.channels[0].frames else {
+            panic!("Expected positions");
+        };
+        positions
+            .iter()
+            .map(|position| {
+                [position.x, position.y, position.z].map(|x| (x * 100.0).round() / 100.0)
+            })
+            .collect()
+    };
+
+    let (name, start) = &result.zmo[0];
+    assert_eq!(name, "start");
+    assert_eq!((start.fps, start.frames), (24, 2));
+    assert_eq!(positions(start), [[0.0, 0.0, 0.0], [0.0, 25.0, 5.0]]);
+
+    let (name, end) = &result.zmo[1];
+    assert_eq!(name, "end");
+    assert_eq!((end.fps, end.frames), (48, 3));
+    assert_eq!(
+        positions(end),
+        [[0.0, 50.0, 0.0], [0.0, 62.5, 2.5], [0.0, 75.0, 5.0]]
+    );
+}
+
+#[test]
+fn quantized_rotations() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("quantized_rotations");
+    let assets = write_test_assets(&directory).unwrap();
+    let mut source = walk_zmo();
+    source.write_to_path(&assets.zmo).unwrap();
+    let ChannelData::Rotation(source_rotations) = &source.channels[1].frames else {
+        panic!("Expected rotations");
+    };
+
+    let options = RoseGltfConvOptions {
+        quantize_animations: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(&[assets.zmd.clone(), assets.zmo.clone()], &options).unwrap();
+
+    let animation = gltf.document.animations().next().unwrap();
+    assert!(animation
+        .channels()
+        .any(|channel| channel.target().property() == gltf::animation::Property::Rotation));
+    for channel in animation.channels() {
+        let output = channel.sampler().output();
+        if channel.target().property() != gltf::animation::Property::Rotation {
+            // Only rotations may be quantized
+            assert_eq!(output.data_type(), gltf::accessor::DataType::F32);
+            assert!(!output.normalized());
+            continue;
+        }
+
+        assert_eq!(output.data_type(), gltf::accessor::DataType::I16);
+        assert!(output.normalized());
+        assert_eq!(output.dimensions(), gltf::accessor::Dimensions::Vec4);
+
+        let reader = channel.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+        let gltf::animation::util::ReadOutputs::Rotations(rotations) =
+            reader.read_outputs().unwrap()
+        else {
+            panic!("Expected rotations");
+        };
+        let rotations: Vec<[f32; 4]> = rotations.into_f32().collect();
+        assert_eq!(rotations.len(), source_rotations.len());
+        for (rotation, source) in rotations.iter().zip(source_rotations) {
+            let expected = [source.x, source.z, -source.y, source.w];
+            for (value, expected) in rotation.iter().zip(expected) {
+                assert!(
+                    (value - expected).abs() < 1.0e-4,
+                    "{:?} != {:?}",
+                    rotation,
+                    source
+                );
+            }
+        }
+    }
+
+    // Quantized rotations convert back within the same tolerance
+    let result = gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap();
+    let rotation = result.zmo[0]
+        .1
+        .channels
+        .iter()
+        .find(|channel| channel.typ == ChannelType::Rotation)
+        .unwrap();
+    let ChannelData::Rotation(rotations) = &rotation.frames else {
+        panic!("Expected rotations");
+    };
+    assert_eq!(rotations.len(), source_rotations.len());
+    for (rotation, source) in rotations.iter().zip(source_rotations) {
+        assert!((rotation.x - source.x).abs() < 1.0e-4);
+        assert!((rotation.y - source.y).abs() < 1.0e-4);
+        assert!((rotation.z - source.z).abs() < 1.0e-4);
+        assert!((rotation.w - source.w).abs() < 1.0e-4);
+    }
+}
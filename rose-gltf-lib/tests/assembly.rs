@@ -0,0 +1,244 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    assemble_model,
+    rose_file_lib::{
+        files::{
+            chr::CharacterMotionType,
+            stb::DataTableColumn,
+            zsc::{Model, ModelMaterial, ModelPart},
+            ItemStb, MON, STB, ZSC,
+        },
+        io::RoseFile,
+    },
+    test_assets::{write_test_assets, TestAssets},
+    AssemblyPart, ModelAssembly, RoseGltfConvOptions,
+};
+
+/// A cart made of a skinned body and a wheel attached to the second bone
+fn write_cart(assets: &TestAssets) {
+    let material = Some(ModelMaterial {
+        path: "3ddata/test/quad.png".to_string(),
+        ..Default::default()
+    });
+    let mut models = ZSC::new();
+    models.models.push(None);
+    models.models.push(Some(Model {
+        parts: vec![ModelPart {
+            mesh_path: "3ddata/test/skinned_quad.zms".to_string(),
+            material: material.clone(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }));
+    models.models.push(Some(Model {
+        parts: vec![ModelPart {
+            mesh_path: "3ddata/test/quad.zms".to_string(),
+            material,
+            bone_index: Some(1),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }));
+    models
+        .write_to_path(&assets.assets_path.join("3ddata/test/list_pat.zsc"))
+        .unwrap();
+}
+
+fn cart_assembly() -> ModelAssembly {
+    ModelAssembly {
+        name: "cart".to_string(),
+        skeleton: "3ddata/test/two_bone.zmd".to_string(),
+        parts: Vec::new(),
+        motions: vec!["3DDATA\\TEST\\TWO_BONE.ZMO".to_string()],
+        ..Default::default()
+    }
+}
+
+fn check_cart(gltf: &gltf::Gltf) {
+    let document = &gltf.document;
+    assert_eq!(document.skins().count(), 1);
+    assert_eq!(document.animations().count(), 1);
+
+    let body = document
+        .nodes()
+        .find(|node| node.name() == Some("cart_part_0_0"))
+        .unwrap();
+    assert!(body.skin().is_some());
+    assert!(body
+        .mesh()
+        .unwrap()
+        .primitives()
+        .next()
+        .unwrap()
+        .material()
+        .index()
+        .is_some());
+
+    // The wheel is a child of the second joint
+    let joint = document.skins().next().unwrap().joints().nth(1).unwrap();
+    let wheel = joint.children().next().unwrap();
+    assert_eq!(wheel.name(), Some("cart_part_1_0"));
+    assert!(wheel.skin().is_none());
+}
+
+#[test]
+fn assemble_parts() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("assemble_parts");
+    let assets = write_test_assets(&directory).unwrap();
+    write_cart(&assets);
+
+    let mut assembly = cart_assembly();
+    for model_id in [1, 2] {
+        assembly.parts.push(AssemblyPart {
+            models: "3ddata/test/list_pat.zsc".to_string(),
+            model_id,
+        });
+    }
+    let gltf = assemble_model(&directory, &assembly, &RoseGltfConvOptions::default()).unwrap();
+    check_cart(&gltf);
+
+    // Both parts share one texture
+    assert_eq!(gltf.document.images().count(), 1);
+}
+
+#[test]
+fn assemble_items() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("assemble_items");
+    let assets = write_test_assets(&directory).unwrap();
+    write_cart(&assets);
+
+    let mut stb = STB::new();
+    stb.headers = (0..=ItemStb::COLUMN_FIELD_MODEL)
+        .map(|_| DataTableColumn {
+            name: String::new(),
+            width: 100,
+        })
+        .collect();
+    stb.data = vec![vec![String::new(); ItemStb::COLUMN_FIELD_MODEL + 1]; 3];
+    stb.data[1][ItemStb::COLUMN_FIELD_MODEL] = "1".to_string();
+    stb.data[2][ItemStb::COLUMN_FIELD_MODEL] = "2".to_string();
+    let items = ItemStb::from(stb);
+
+    let mut assembly = cart_assembly();
+    assembly
+        .add_items("3ddata/test/list_pat.zsc", &items, &[1, 2])
+        .unwrap();
+    assert!(assembly
+        .clone()
+        .add_items("3ddata/test/list_pat.zsc", &items, &[0])
+        .is_err());
+
+    let gltf = assemble_model(&directory, &assembly, &RoseGltfConvOptions::default()).unwrap();
+    check_cart(&gltf);
+}
+
+#[test]
+fn assemble_texture_variants() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("assemble_texture_variants");
+    let assets = write_test_assets(&directory).unwrap();
+    write_cart(&assets);
+
+    let mut stb = STB::new();
+    stb.headers = (0..2)
+        .map(|_| DataTableColumn {
+            name: String::new(),
+            width: 100,
+        })
+        .collect();
+    stb.data = vec![
+        vec![String::new(), String::new()],
+        vec![String::new(), "3ddata/test/tile.png".to_string()],
+    ];
+
+    let mut assembly = cart_assembly();
+    for model_id in [1, 2] {
+        assembly.parts.push(AssemblyPart {
+            models: "3ddata/test/list_pat.zsc".to_string(),
+            model_id,
+        });
+    }
+    assembly.add_texture_variants("face", 0, &stb, 1);
+    assert_eq!(assembly.texture_variants.len(), 1);
+    assert_eq!(assembly.texture_variants[0].name, "face_1");
+
+    let gltf = assemble_model(&directory, &assembly, &RoseGltfConvOptions::default()).unwrap();
+    check_cart(&gltf);
+    assert_eq!(gltf.document.images().count(), 2);
+
+    let json = gltf.document.as_json();
+    assert!(json
+        .extensions_used
+        .contains(&"KHR_materials_variants".to_string()));
+    let variants = &json
+        .extensions
+        .as_ref()
+        .unwrap()
+        .khr_materials_variants
+        .as_ref()
+        .unwrap()
+        .variants;
+    assert_eq!(variants.len(), 2);
+    assert_eq!(variants[1].name, "face_1");
+
+    // Only the body has a mapping for the variant
+    let primitive_mappings = |name: &str| {
+        let node = json
+            .nodes
+            .iter()
+            .find(|node| node.name.as_deref() == Some(name))
+            .unwrap();
+        json.meshes[node.mesh.unwrap().value()].primitives[0]
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.khr_materials_variants.as_ref())
+            .map(|variants| variants.mappings.len())
+    };
+    assert_eq!(primitive_mappings("cart_part_0_0"), Some(2));
+    assert_eq!(primitive_mappings("cart_part_1_0"), None);
+}
+
+#[test]
+fn assemble_character_motions() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("assemble_character_motions");
+    let assets = write_test_assets(&directory).unwrap();
+    write_cart(&assets);
+    std::fs::copy(&assets.zmo, directory.join("3ddata/test/die.zmo")).unwrap();
+
+    let mut character = MON::default();
+    character.motions.insert(
+        CharacterMotionType::Move,
+        "3ddata/test/two_bone.zmo".to_string(),
+    );
+    character
+        .motions
+        .insert(CharacterMotionType::Die, "3ddata/test/die.zmo".to_string());
+
+    let mut assembly = ModelAssembly {
+        motions: Vec::new(),
+        ..cart_assembly()
+    };
+    assembly.add_character_motions(&character);
+    assert_eq!(assembly.motions.len(), 2);
+
+    let gltf = assemble_model(&directory, &assembly, &RoseGltfConvOptions::default()).unwrap();
+    let loops: Vec<(String, serde_json::Value)> = gltf
+        .document
+        .animations()
+        .map(|animation| {
+            let extras: serde_json::Value =
+                serde_json::from_str(animation.extras().as_ref().unwrap().get()).unwrap();
+            (
+                animation.name().unwrap().to_string(),
+                extras["loop"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        loops,
+        [
+            ("two_bone".to_string(), serde_json::Value::Bool(true)),
+            ("die".to_string(), serde_json::Value::Bool(false)),
+        ]
+    );
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use rose_gltf_lib::{
+    normalize_asset_path, rose_to_gltf, rose_to_gltf_data,
+    test_assets::{gltf_json_snapshot, write_test_assets},
+    AssetLocator, FsAssetLocator, RoseGltfConvOptions,
+};
+
+#[test]
+fn normalize_paths() {
+    assert_eq!(
+        normalize_asset_path("3DDATA\\MAPS\\\\Junon\\./JDT01.ZON"),
+        "3ddata/maps/junon/jdt01.zon"
+    );
+    assert_eq!(normalize_asset_path("/3ddata/stb/"), "3ddata/stb");
+}
+
+#[test]
+fn fs_asset_locator() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("asset_locator");
+    let assets = write_test_assets(&directory.join("client")).unwrap();
+
+    let locator = FsAssetLocator::for_file(&assets.zon, &[directory.join("missing")]);
+    assert_eq!(locator.roots().len(), 2);
+    assert_eq!(
+        locator.locate("3DDATA\\TEST\\Quad.ZMS"),
+        Some(assets.zms.clone())
+    );
+    assert_eq!(locator.locate("3ddata/test/missing.zms"), None);
+    assert_eq!(
+        locator.asset_path(&assets.zon).as_deref(),
+        Some("3ddata/maps/test/test.zon")
+    );
+
+    // Missing files still resolve to a path below the first root
+    assert_eq!(
+        locator.resolve("3ddata\\test\\missing.zms"),
+        directory.join("missing").join("3ddata/test/missing.zms")
+    );
+}
+
+#[test]
+fn zone_with_asset_locator() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zone_asset_locator");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let expected = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions {
+            asset_locator: Some(Arc::new(FsAssetLocator::new(vec![directory.clone()]))),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        gltf_json_snapshot(&gltf).unwrap(),
+        gltf_json_snapshot(&expected).unwrap()
+    );
+}
+
+#[test]
+fn zone_to_gltf_data() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zone_to_gltf_data");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let gltf_data = rose_to_gltf_data(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(gltf_data.buffers.len(), 1);
+    assert_eq!(gltf_data.images.len(), gltf_data.document.images().len());
+
+    // The terrain texture is rasterised at 1024x1024
+    let terrain_image = gltf_data
+        .document
+        .images()
+        .find(|image| image.name() == Some("32_32_tilemap_image"))
+        .unwrap();
+    let terrain_image = &gltf_data.images[terrain_image.index()];
+    assert_eq!((terrain_image.width, terrain_image.height), (1024, 1024));
+}
@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_file_lib::{
+        files::{
+            zsc::{Model, ModelMaterial, ModelPart},
+            ZSC,
+        },
+        io::RoseFile,
+        utils::{Quaternion, Vector3},
+    },
+    rose_to_gltf,
+    test_assets::{quad_zms, write_test_assets, TestAssets},
+    RoseGltfConvOptions,
+};
+use serde_json::Value;
+
+/// Replaces the deco object with a part flagged by an axis rotation and the
+/// cnst object with an upright alpha tested card facing along x.
+fn write_billboards(assets: &TestAssets) {
+    let material = ModelMaterial {
+        path: "3ddata/test/quad.png".to_string(),
+        ..Default::default()
+    };
+
+    let mut deco = ZSC::new();
+    deco.models.push(Some(Model {
+        parts: vec![ModelPart {
+            mesh_path: "3ddata/test/quad.zms".to_string(),
+            material: Some(material.clone()),
+            axis_rotation: Some(Quaternion::IDENTITY),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }));
+    deco.write_to_path(&assets.assets_path.join("3ddata/test/deco.zsc"))
+        .unwrap();
+
+    let mut card = quad_zms();
+    for vertex in card.vertices.iter_mut() {
+        vertex.position = Vector3::new(0.0, vertex.position.x, vertex.position.y);
+        vertex.normal = Vector3::new(1.0, 0.0, 0.0);
+    }
+    card.write_to_path(&assets.assets_path.join("3ddata/test/card.zms"))
+        .unwrap();
+
+    let mut cnst = ZSC::new();
+    cnst.models.push(Some(Model {
+        parts: vec![ModelPart {
+            mesh_path: "3ddata/test/card.zms".to_string(),
+            material: Some(ModelMaterial {
+                alpha_test: Some(128),
+                ..material
+            }),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }));
+    cnst.write_to_path(&assets.assets_path.join("3ddata/test/cnst.zsc"))
+        .unwrap();
+}
+
+fn node_extras(gltf: &gltf::Gltf, name: &str) -> Value {
+    let node = gltf
+        .document
+        .as_json()
+        .nodes
+        .iter()
+        .find(|node| node.name.as_deref() == Some(name))
+        .unwrap();
+    serde_json::from_str(node.extras.as_ref().unwrap().get()).unwrap()
+}
+
+#[test]
+fn billboard_extras() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("billboard_extras");
+    let assets = write_test_assets(&directory).unwrap();
+    write_billboards(&assets);
+
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    let deco = node_extras(&gltf, "32_32_deco_0_0");
+    assert_eq!(deco["rose_billboard"]["source"], "axis_rotation");
+    assert_eq!(deco["rose_billboard"]["pre_rotated"], false);
+    assert!(deco["TLM_ObjectProperties"].is_object());
+
+    let cnst = node_extras(&gltf, "32_32_cnst_0_0");
+    assert_eq!(cnst["rose_billboard"]["source"], "card");
+    let normal = cnst["rose_billboard"]["normal"][0].as_f64().unwrap();
+    assert!((normal.abs() - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn billboard_pre_rotation() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("billboard_pre_rotation");
+    let assets = write_test_assets(&directory).unwrap();
+    write_billboards(&assets);
+
+    let options = RoseGltfConvOptions {
+        billboard_pre_rotation: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+
+    let part = gltf
+        .document
+        .nodes()
+        .find(|node| node.name() == Some("32_32_cnst_0_0"))
+        .unwrap();
+    assert!(part.mesh().is_none());
+    let card = part.children().next().unwrap();
+    assert_eq!(card.name(), Some("32_32_cnst_0_0_billboard"));
+    assert!(card.mesh().is_some());
+
+    // The card's normal along x is turned to face +z
+    let (_, rotation, _) = card.transform().decomposed();
+    let rotation = glam::Quat::from_array(rotation);
+    let normal = rotation * glam::Vec3::X;
+    assert!(normal.z.abs() > 0.999);
+
+    let extras = node_extras(&gltf, "32_32_cnst_0_0");
+    assert_eq!(extras["rose_billboard"]["pre_rotated"], true);
+    assert!(extras.get("TLM_ObjectProperties").is_none());
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use rose_gltf_lib::{
+    rose_file_lib::{
+        files::{ZMD, ZMS},
+        io::RoseFile,
+        utils::Vector3,
+    },
+    rose_to_gltf,
+    test_assets::{gltf_json_snapshot, quad_zms, write_test_assets},
+    AssetCache, AssetCacheStats, RoseGltfConvOptions,
+};
+
+#[test]
+fn asset_cache_lru() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("asset_cache_lru");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let cache = AssetCache::new(1);
+    let zms = cache.get::<ZMS>(&assets.zms).unwrap();
+    assert!(Arc::ptr_eq(&zms, &cache.get::<ZMS>(&assets.zms).unwrap()));
+    assert_eq!(cache.stats(), AssetCacheStats { hits: 1, misses: 1 });
+
+    // Evicts the ZMS
+    cache.get::<ZMD>(&assets.zmd).unwrap();
+    assert_eq!(cache.len(), 1);
+    assert!(!Arc::ptr_eq(&zms, &cache.get::<ZMS>(&assets.zms).unwrap()));
+    assert_eq!(cache.stats(), AssetCacheStats { hits: 1, misses: 3 });
+
+    assert!(cache.get::<ZMS>(&directory.join("missing.zms")).is_err());
+}
+
+#[test]
+fn asset_cache_reloads_changed_files() {
+    let directory =
+        Path::new(env!("CARGO_TARGET_TMPDIR")).join("asset_cache_reloads_changed_files");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let cache = AssetCache::new(16);
+    let zms = cache.get::<ZMS>(&assets.zms).unwrap();
+    assert_eq!(zms.indices.len(), 2);
+
+    // A different size, as the modification time may not have changed
+    let mut changed = quad_zms();
+    changed.indices.push(Vector3::new(0, 1, 3));
+    changed.write_to_path(&assets.zms).unwrap();
+    let zms = cache.get::<ZMS>(&assets.zms).unwrap();
+    assert_eq!(zms.indices.len(), 3);
+    assert_eq!(cache.stats(), AssetCacheStats { hits: 0, misses: 2 });
+    assert!(Arc::ptr_eq(&zms, &cache.get::<ZMS>(&assets.zms).unwrap()));
+}
+
+#[test]
+fn asset_cache_shared_between_conversions() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("asset_cache_zone");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        asset_cache: Some(Arc::new(AssetCache::new(64))),
+        ..Default::default()
+    };
+    let first = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let first_stats = options.asset_cache.as_ref().unwrap().stats();
+    assert!(first_stats.misses > 0);
+
+    let second = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let second_stats = options.asset_cache.as_ref().unwrap().stats();
+    assert_eq!(second_stats.misses, first_stats.misses);
+    assert!(second_stats.hits > first_stats.hits);
+
+    assert_eq!(
+        gltf_json_snapshot(&first).unwrap(),
+        gltf_json_snapshot(&second).unwrap()
+    );
+}
@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_file_lib::{files::IFO, io::RoseFile, utils::Vector3},
+    rose_to_gltf,
+    test_assets::write_test_assets,
+    RoseGltfConvOptions,
+};
+use serde_json::Value;
+
+fn object_extras(gltf: &gltf::Gltf, name: &str) -> Option<Value> {
+    let node = gltf
+        .document
+        .nodes()
+        .find(|node| node.name() == Some(name))?;
+    let extras: Value = serde_json::from_str(node.extras().as_ref().unwrap().get()).unwrap();
+    Some(extras["rose_object"].clone())
+}
+
+#[test]
+fn disabled_objects() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("disabled_objects");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // An object triggered by an event and a copy of it with a zero scale
+    let ifo_path = assets.zon.parent().unwrap().join("32_32.ifo");
+    let mut ifo = IFO::from_path(&ifo_path).unwrap();
+    ifo.objects[0].event_id = 5;
+    let mut hidden = ifo.objects[0].clone();
+    hidden.event_id = 0;
+    hidden.scale = Vector3::new(1.0, 1.0, 0.0);
+    ifo.objects.push(hidden);
+    ifo.write_to_path(&ifo_path).unwrap();
+
+    let convert = |include_disabled_objects| {
+        rose_to_gltf(
+            std::slice::from_ref(&assets.zon),
+            &RoseGltfConvOptions {
+                include_disabled_objects,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    };
+
+    let gltf = convert(false);
+    let event_object = object_extras(&gltf, "32_32_deco_0").unwrap();
+    assert_eq!(event_object["event_id"], 5);
+    assert_eq!(event_object["disabled"], false);
+    assert!(object_extras(&gltf, "32_32_deco_1").is_none());
+
+    let gltf = convert(true);
+    assert_eq!(
+        object_extras(&gltf, "32_32_deco_1").unwrap()["disabled"],
+        true
+    );
+}
@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_file_lib::{
+        files::{
+            zsc::{Model, ModelMaterial, ModelPart},
+            ZSC,
+        },
+        io::RoseFile,
+    },
+    rose_to_gltf_data,
+    test_assets::write_test_assets,
+    EmbeddedImageOptions, ImageEncoding, RoseGltfConvOptions,
+};
+
+#[test]
+fn embedded_image_format_and_size() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("embedded_image_format_and_size");
+    let assets = write_test_assets(&directory).unwrap();
+    let input_files = std::slice::from_ref(&assets.zon);
+
+    let gltf = rose_to_gltf_data(input_files, &RoseGltfConvOptions::default()).unwrap();
+    assert!(gltf.document.images().all(|image| matches!(
+        image.source(),
+        gltf::image::Source::View {
+            mime_type: "image/png",
+            ..
+        }
+    )));
+    assert!(gltf.images.iter().any(|image| image.width == 1024));
+
+    let options = RoseGltfConvOptions {
+        embedded_images: EmbeddedImageOptions {
+            encoding: ImageEncoding::Jpeg { quality: 80 },
+            max_size: Some(256),
+            budget: None,
+        },
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(input_files, &options).unwrap();
+    assert!(gltf.document.images().count() > 1);
+    // The object texture is alpha tested so it stays PNG
+    let mime_type = |name: &str| {
+        gltf.document
+            .images()
+            .find(|image| image.name() == Some(name))
+            .map(|image| match image.source() {
+                gltf::image::Source::View { mime_type, .. } => mime_type,
+                gltf::image::Source::Uri { .. } => "",
+            })
+    };
+    assert_eq!(mime_type("32_32_tilemap_image"), Some("image/jpeg"));
+    assert_eq!(mime_type("deco_material_0_image"), Some("image/png"));
+
+    // The 1024x1024 terrain texture is scaled down, the 4x4 object texture is kept
+    let sizes: Vec<(u32, u32)> = gltf
+        .images
+        .iter()
+        .map(|image| (image.width, image.height))
+        .collect();
+    assert!(sizes.contains(&(256, 256)), "{:?}", sizes);
+    assert!(sizes.contains(&(4, 4)), "{:?}", sizes);
+    assert!(sizes
+        .iter()
+        .all(|&(width, height)| width <= 256 && height <= 256));
+}
+
+#[test]
+fn jpeg_keeps_alpha_textures_as_png() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("jpeg_keeps_alpha_textures_as_png");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // The deco object is alpha tested, the cnst object is opaque
+    let mut cnst = ZSC::new();
+    cnst.models.push(Some(Model {
+        parts: vec![ModelPart {
+            mesh_path: "3ddata/test/quad.zms".to_string(),
+            material: Some(ModelMaterial {
+                path: "3ddata/test/quad.png".to_string(),
+                alpha_test: None,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }));
+    cnst.write_to_path(&assets.assets_path.join("3ddata/test/cnst.zsc"))
+        .unwrap();
+
+    let options = RoseGltfConvOptions {
+        embedded_images: EmbeddedImageOptions {
+            encoding: ImageEncoding::Jpeg { quality: 80 },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let mime_types: Vec<(gltf::material::AlphaMode, &str)> = gltf
+        .document
+        .materials()
+        .filter_map(|material| {
+            let texture = material.pbr_metallic_roughness().base_color_texture()?;
+            match texture.texture().source().source() {
+                gltf::image::Source::View { mime_type, .. } => {
+                    Some((material.alpha_mode(), mime_type))
+                }
+                gltf::image::Source::Uri { .. } => None,
+            }
+        })
+        .collect();
+    assert!(mime_types.contains(&(gltf::material::AlphaMode::Mask, "image/png")));
+    assert!(mime_types.contains(&(gltf::material::AlphaMode::Opaque, "image/jpeg")));
+    assert!(!mime_types.contains(&(gltf::material::AlphaMode::Mask, "image/jpeg")));
+}
+
+#[test]
+fn texture_budget() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("texture_budget");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        embedded_images: EmbeddedImageOptions {
+            budget: Some(1_100_000),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let sizes: Vec<(u32, u32)> = gltf
+        .images
+        .iter()
+        .map(|image| (image.width, image.height))
+        .collect();
+    let total: u32 = sizes.iter().map(|(width, height)| width * height * 4).sum();
+    assert!(total <= 1_100_000, "{:?}", sizes);
+
+    // Only the largest texture is scaled
+    assert!(sizes.contains(&(512, 512)), "{:?}", sizes);
+    assert!(sizes.contains(&(4, 4)), "{:?}", sizes);
+}
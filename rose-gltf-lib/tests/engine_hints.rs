@@ -0,0 +1,110 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_file_lib::{
+        files::{
+            zsc::{Model, ModelCollisionShape, ModelMaterial, ModelPart},
+            ZSC,
+        },
+        io::RoseFile,
+    },
+    rose_to_gltf,
+    test_assets::{write_test_assets, TestAssets},
+    EngineHints, RoseGltfConvOptions,
+};
+use serde_json::Value;
+
+/// Gives the cnst object a mesh collision shape and an alpha tested, two
+/// sided material, the deco object is left without collision.
+fn write_colliding_cnst(assets: &TestAssets) {
+    let mut cnst = ZSC::new();
+    cnst.models.push(Some(Model {
+        parts: vec![ModelPart {
+            mesh_path: "3ddata/test/quad.zms".to_string(),
+            material: Some(ModelMaterial {
+                path: "3ddata/test/quad.png".to_string(),
+                alpha_test: Some(128),
+                two_sided: true,
+                ..Default::default()
+            }),
+            collision_shape: Some(ModelCollisionShape::Mesh),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }));
+    cnst.write_to_path(&assets.assets_path.join("3ddata/test/cnst.zsc"))
+        .unwrap();
+}
+
+fn convert(assets: &TestAssets, engine_hints: EngineHints) -> gltf::Gltf {
+    let options = RoseGltfConvOptions {
+        engine_hints,
+        ..Default::default()
+    };
+    rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap()
+}
+
+fn node_extras(gltf: &gltf::Gltf, name: &str) -> Value {
+    let node = gltf
+        .document
+        .nodes()
+        .find(|node| node.name() == Some(name))
+        .unwrap_or_else(|| panic!("Missing node {}", name));
+    serde_json::from_str(node.extras().as_ref().unwrap().get()).unwrap()
+}
+
+#[test]
+fn godot_collision_names() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("godot_collision_names");
+    let assets = write_test_assets(&directory).unwrap();
+    write_colliding_cnst(&assets);
+
+    let gltf = convert(&assets, EngineHints::Godot);
+    let names: Vec<_> = gltf
+        .document
+        .nodes()
+        .filter_map(|node| node.name())
+        .collect();
+    assert!(names.contains(&"32_32_heightmap-col"));
+    assert!(names.contains(&"32_32_cnst_0_0-col"));
+    assert!(names.contains(&"32_32_deco_0_0"));
+
+    let gltf = convert(&assets, EngineHints::None);
+    assert!(gltf
+        .document
+        .nodes()
+        .filter_map(|node| node.name())
+        .all(|name| !name.ends_with("-col")));
+}
+
+#[test]
+fn unity_extras() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("unity_extras");
+    let assets = write_test_assets(&directory).unwrap();
+    write_colliding_cnst(&assets);
+
+    let gltf = convert(&assets, EngineHints::Unity);
+    assert_eq!(
+        node_extras(&gltf, "32_32_cnst_0_0")["unity"]["collider"],
+        "MeshCollider"
+    );
+    assert_eq!(
+        node_extras(&gltf, "32_32_heightmap")["unity"]["collider"],
+        "MeshCollider"
+    );
+    assert!(node_extras(&gltf, "32_32_deco_0_0").get("unity").is_none());
+
+    let cnst_material = gltf
+        .document
+        .nodes()
+        .find(|node| node.name() == Some("32_32_cnst_0_0"))
+        .and_then(|node| node.mesh())
+        .and_then(|mesh| mesh.primitives().next())
+        .map(|primitive| primitive.material())
+        .unwrap();
+    let extras: Value =
+        serde_json::from_str(cnst_material.extras().as_ref().unwrap().get()).unwrap();
+    assert_eq!(extras["unity"]["RenderType"], "TransparentCutout");
+    assert_eq!(extras["unity"]["_Cull"], 0);
+    assert_eq!(extras["unity"]["_Cutoff"], 0.5);
+}
@@ -0,0 +1,234 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use gltf_json::{animation::Interpolation, validation::Checked::Valid};
+use rose_file_lib::{files::STB, io::RoseFile};
+use rose_gltf_lib::{
+    export_zone_blocks, export_zones, gltf_to_rose, rose_to_gltf, rose_to_gltf_data,
+    test_assets::{skinned_quad_zms, write_test_assets},
+    AssetLocator, CancellationToken, ErrorCategory, FsAssetLocator, GltfData, GltfFormat,
+    GltfRoseConvOptions, RoseGltfConvOptions,
+};
+
+fn category_of(input: &Path) -> ErrorCategory {
+    let error = rose_to_gltf(&[input.to_path_buf()], &RoseGltfConvOptions::default())
+        .expect_err("conversion should fail");
+    ErrorCategory::of(&error)
+}
+
+#[test]
+fn error_categories() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("error_categories");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let unsupported = directory.join("model.obj");
+    std::fs::write(&unsupported, b"").unwrap();
+    assert_eq!(category_of(&unsupported), ErrorCategory::Unsupported);
+
+    assert_eq!(
+        category_of(&directory.join("missing.zms")),
+        ErrorCategory::Io
+    );
+
+    let corrupt = directory.join("corrupt.zms");
+    std::fs::write(&corrupt, b"not a mesh").unwrap();
+    assert_eq!(category_of(&corrupt), ErrorCategory::InputParse);
+
+    std::fs::remove_file(assets.assets_path.join("3ddata/stb/list_zone.stb")).unwrap();
+    assert_eq!(category_of(&assets.zon), ErrorCategory::MissingDependency);
+}
+
+/// Cancels its conversion once `written` exists, i.e. part way through an
+/// export
+struct CancelAfterWrite {
+    assets: FsAssetLocator,
+    written: PathBuf,
+    cancellation: CancellationToken,
+}
+
+impl AssetLocator for CancelAfterWrite {
+    fn locate(&self, path: &str) -> Option<PathBuf> {
+        if self.written.exists() {
+            self.cancellation.cancel();
+        }
+        self.assets.locate(path)
+    }
+
+    fn asset_path(&self, file_path: &Path) -> Option<String> {
+        self.assets.asset_path(file_path)
+    }
+}
+
+fn cancel_after_write(assets_path: &Path, written: PathBuf) -> RoseGltfConvOptions {
+    let cancellation = CancellationToken::new();
+    RoseGltfConvOptions {
+        cancellation: Some(cancellation.clone()),
+        asset_locator: Some(Arc::new(CancelAfterWrite {
+            assets: FsAssetLocator::new(vec![assets_path.to_path_buf()]),
+            written,
+            cancellation,
+        })),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn cancelled_conversion() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cancelled_conversion");
+    let _ = std::fs::remove_dir_all(&directory);
+    let assets = write_test_assets(&directory).unwrap();
+
+    let cancellation = CancellationToken::new();
+    let options = RoseGltfConvOptions {
+        cancellation: Some(cancellation.clone()),
+        ..Default::default()
+    };
+    assert!(rose_to_gltf(std::slice::from_ref(&assets.zon), &options).is_ok());
+
+    cancellation.cancel();
+    let error =
+        rose_to_gltf(std::slice::from_ref(&assets.zon), &options).expect_err("should be cancelled");
+    assert_eq!(ErrorCategory::of(&error), ErrorCategory::Cancelled);
+
+    let output = directory.join("blocks");
+    let error = export_zone_blocks(&assets.zon, &output, &GltfFormat::Binary, &options)
+        .expect_err("should be cancelled");
+    assert_eq!(ErrorCategory::of(&error), ErrorCategory::Cancelled);
+    assert!(!output.join("32_32.glb").exists());
+    assert!(!output.join("blocks.json").exists());
+
+    // A second block, cancelled once the first has been written
+    let map_path = assets.zon.parent().unwrap();
+    for extension in ["him", "til", "ifo"] {
+        std::fs::copy(
+            map_path.join(format!("32_32.{}", extension)),
+            map_path.join(format!("33_32.{}", extension)),
+        )
+        .unwrap();
+    }
+    let output = directory.join("partial_blocks");
+    let options = cancel_after_write(&assets.assets_path, output.join("32_32.gltf"));
+    let error = export_zone_blocks(&assets.zon, &output, &GltfFormat::Text, &options)
+        .expect_err("should be cancelled");
+    assert_eq!(ErrorCategory::of(&error), ErrorCategory::Cancelled);
+    assert!(options
+        .cancellation
+        .as_ref()
+        .is_some_and(|cancellation| cancellation.is_cancelled()));
+    assert!(!output.exists());
+
+    // A second zone, cancelled once the first has been written
+    let list_zone_path = assets.assets_path.join("3ddata/stb/list_zone.stb");
+    let mut list_zone = STB::from_path(&list_zone_path).unwrap();
+    list_zone.data.push(list_zone.data[1].clone());
+    list_zone.write_to_path(&list_zone_path).unwrap();
+    let output = directory.join("zones");
+    std::fs::create_dir_all(&output).unwrap();
+    std::fs::write(output.join("existing.txt"), b"").unwrap();
+    let options = cancel_after_write(&assets.assets_path, output.join("1_test.glb"));
+    let error = export_zones(
+        &assets.assets_path,
+        None,
+        &output,
+        &GltfFormat::Binary,
+        &options,
+    )
+    .expect_err("should be cancelled");
+    assert_eq!(ErrorCategory::of(&error), ErrorCategory::Cancelled);
+    let remaining: Vec<_> = std::fs::read_dir(&output)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(remaining, ["existing.txt"]);
+}
+
+#[test]
+fn bone_palette_outside_of_skeleton() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bone_palette_outside_of_skeleton");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let mut zms = skinned_quad_zms();
+    zms.bones = vec![0, 5];
+    let zms_path = directory.join("3ddata/test/bad_palette.zms");
+    zms.write_to_path(&zms_path).unwrap();
+
+    let error = rose_to_gltf(
+        &[assets.zmd.clone(), zms_path],
+        &RoseGltfConvOptions::default(),
+    )
+    .expect_err("conversion should fail");
+    assert_eq!(ErrorCategory::of(&error), ErrorCategory::Validation);
+    let message = format!("{:#}", error);
+    assert!(message.contains("bad_palette.zms"), "{}", message);
+    assert!(message.contains("two_bone.zmd"), "{}", message);
+    assert!(message.contains("bones 5 "), "{}", message);
+}
+
+#[test]
+fn animation_without_skeleton() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("animation_without_skeleton");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        skip_auto_skeleton: true,
+        ..Default::default()
+    };
+    let error = rose_to_gltf(&[assets.zmo.clone(), assets.skinned_zms.clone()], &options)
+        .expect_err("conversion should fail");
+    assert_eq!(ErrorCategory::of(&error), ErrorCategory::MissingDependency);
+    let message = format!("{:#}", error);
+    assert!(message.contains("two_bone.zmo"), "{}", message);
+
+    // The skeleton is loaded first whichever order the inputs are given in
+    let zmd = assets.zmd.with_extension("ZMD");
+    std::fs::rename(&assets.zmd, &zmd).unwrap();
+    rose_to_gltf(&[assets.zmo.clone(), zmd], &options).unwrap();
+}
+
+#[test]
+fn exit_codes_are_distinct() {
+    let categories = [
+        ErrorCategory::InputParse,
+        ErrorCategory::MissingDependency,
+        ErrorCategory::Unsupported,
+        ErrorCategory::Io,
+        ErrorCategory::Validation,
+        ErrorCategory::Cancelled,
+        ErrorCategory::Other,
+    ];
+    let mut codes = categories.map(ErrorCategory::exit_code).to_vec();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), categories.len());
+    assert!(!codes.contains(&0));
+    assert!(!codes.contains(&2));
+}
+
+#[test]
+fn cubic_spline_animation() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cubic_spline_animation");
+    let assets = write_test_assets(&directory).unwrap();
+    let gltf = rose_to_gltf_data(
+        &[assets.zmd.clone(), assets.zmo.clone()],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    let mut root = gltf.document.into_json();
+    for sampler in root.animations[0].samplers.iter_mut() {
+        sampler.interpolation = Valid(Interpolation::CubicSpline);
+    }
+    let gltf = GltfData {
+        document: gltf::Document::from_json_without_validation(root),
+        buffers: gltf.buffers,
+        images: gltf.images,
+    };
+    let Err(error) = gltf_to_rose(&gltf, &GltfRoseConvOptions::default()) else {
+        panic!("conversion should fail");
+    };
+    assert_eq!(ErrorCategory::of(&error), ErrorCategory::Unsupported);
+    let message = format!("{:#}", error);
+    assert!(message.contains("cubic spline"), "{}", message);
+}
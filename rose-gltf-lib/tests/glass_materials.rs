@@ -0,0 +1,162 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    collect_warnings,
+    rose_file_lib::{
+        files::{
+            zsc::{Model, ModelMaterial, ModelPart},
+            ZSC,
+        },
+        io::RoseFile,
+    },
+    rose_to_gltf,
+    test_assets::{write_test_assets, TestAssets},
+    GlassMaterialRules, RoseGltfConvOptions,
+};
+use serde_json::Value;
+
+/// Gives the cnst object one part per material, in the order of the
+/// `materials` list
+fn write_cnst_materials(assets: &TestAssets, materials: Vec<ModelMaterial>) {
+    let mut cnst = ZSC::new();
+    cnst.models.push(Some(Model {
+        parts: materials
+            .into_iter()
+            .map(|material| ModelPart {
+                mesh_path: "3ddata/test/quad.zms".to_string(),
+                material: Some(material),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }));
+    cnst.write_to_path(&assets.assets_path.join("3ddata/test/cnst.zsc"))
+        .unwrap();
+}
+
+/// The glTF json and the json of the material of each cnst part
+fn convert(
+    assets: &TestAssets,
+    glass_materials: Option<GlassMaterialRules>,
+) -> (Value, Vec<Value>) {
+    let options = RoseGltfConvOptions {
+        glass_materials,
+        ..Default::default()
+    };
+    let (gltf, _) =
+        collect_warnings(|| rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap());
+    let mut materials = Vec::new();
+    for part in 0.. {
+        let Some(node) = gltf
+            .document
+            .nodes()
+            .find(|node| node.name() == Some(&format!("32_32_cnst_0_{}", part)))
+        else {
+            break;
+        };
+        let primitive = node.mesh().unwrap().primitives().next().unwrap();
+        materials.push(primitive.material().index().unwrap());
+    }
+    let root = serde_json::to_value(gltf.document.into_json()).unwrap();
+    let materials = materials
+        .into_iter()
+        .map(|material| root["materials"][material].clone())
+        .collect();
+    (root, materials)
+}
+
+fn is_glass(material: &Value) -> bool {
+    let extensions = &material["extensions"];
+    assert_eq!(
+        extensions.get("KHR_materials_transmission").is_some(),
+        extensions.get("KHR_materials_ior").is_some()
+    );
+    extensions.get("KHR_materials_transmission").is_some()
+}
+
+fn extensions_used(root: &Value) -> Vec<&str> {
+    root["extensionsUsed"]
+        .as_array()
+        .map(|extensions| {
+            extensions
+                .iter()
+                .map(|name| name.as_str().unwrap())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[test]
+fn glass_materials() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("glass_materials");
+    let assets = write_test_assets(&directory).unwrap();
+    let blended = |path: &str| ModelMaterial {
+        path: path.to_string(),
+        alpha_enabled: true,
+        alpha_test: None,
+        ..Default::default()
+    };
+    write_cnst_materials(
+        &assets,
+        vec![
+            // Opaque
+            ModelMaterial {
+                path: "3ddata/test/glass.png".to_string(),
+                alpha_test: None,
+                ..Default::default()
+            },
+            blended("3ddata/test/quad.png"),
+            ModelMaterial {
+                specular_enabled: true,
+                ..blended("3ddata/test/quad.png")
+            },
+            blended("3ddata/test/Window_GLASS.png"),
+            // Alpha tested
+            ModelMaterial {
+                alpha_test: Some(128),
+                ..blended("3ddata/test/glass.png")
+            },
+        ],
+    );
+
+    let (root, materials) = convert(&assets, None);
+    assert_eq!(materials.len(), 5);
+    assert!(materials.iter().all(|material| !is_glass(material)));
+    assert!(!extensions_used(&root).contains(&"KHR_materials_transmission"));
+    assert!(!extensions_used(&root).contains(&"KHR_materials_ior"));
+
+    let (root, materials) = convert(&assets, Some(GlassMaterialRules::default()));
+    let glass: Vec<bool> = materials.iter().map(is_glass).collect();
+    assert_eq!(glass, [false, false, true, true, false]);
+    assert!(extensions_used(&root).contains(&"KHR_materials_transmission"));
+    assert!(extensions_used(&root).contains(&"KHR_materials_ior"));
+    let extensions = &materials[3]["extensions"];
+    assert_eq!(
+        extensions["KHR_materials_transmission"]["transmissionFactor"],
+        1.0
+    );
+    assert_eq!(extensions["KHR_materials_ior"]["ior"], 1.5);
+    assert_eq!(
+        materials[3]["pbrMetallicRoughness"]["roughnessFactor"],
+        serde_json::json!(0.1f32)
+    );
+    assert_eq!(materials[1]["pbrMetallicRoughness"]["roughnessFactor"], 1.0);
+
+    let rules = GlassMaterialRules {
+        match_specular: false,
+        keywords: vec!["window".to_string()],
+        transmission: 0.75,
+        ior: 1.25,
+        roughness: 0.5,
+    };
+    let (_, materials) = convert(&assets, Some(rules));
+    let glass: Vec<bool> = materials.iter().map(is_glass).collect();
+    assert_eq!(glass, [false, false, false, true, false]);
+    let extensions = &materials[3]["extensions"];
+    assert_eq!(
+        extensions["KHR_materials_transmission"]["transmissionFactor"],
+        0.75
+    );
+    assert_eq!(extensions["KHR_materials_ior"]["ior"], 1.25);
+    assert_eq!(materials[3]["pbrMetallicRoughness"]["roughnessFactor"], 0.5);
+}
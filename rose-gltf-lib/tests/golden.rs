@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+
+use pretty_assertions::assert_eq;
+use rose_gltf_lib::{
+    rose_to_gltf,
+    test_assets::{gltf_json_snapshot, write_test_assets, TestAssets},
+    RoseGltfConvOptions,
+};
+
+/// Converts the test assets selected by `inputs` and compares the result with
+/// tests/golden/<name>.json, set UPDATE_GOLDEN=1 to rewrite the snapshot.
+fn check_golden(name: &str, inputs: impl FnOnce(&TestAssets) -> Vec<PathBuf>) {
+    let assets = write_test_assets(&Path::new(env!("CARGO_TARGET_TMPDIR")).join(name)).unwrap();
+    let gltf = rose_to_gltf(&inputs(&assets), &RoseGltfConvOptions::default()).unwrap();
+    let json = gltf_json_snapshot(&gltf).unwrap();
+
+    let golden_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.json", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&golden_path, &json).unwrap();
+        return;
+    }
+
+    let golden = std::fs::read_to_string(&golden_path).unwrap();
+    assert_eq!(golden, json);
+}
+
+#[test]
+fn golden_zms() {
+    check_golden("zms", |assets| vec![assets.zms.clone()]);
+}
+
+#[test]
+fn golden_zmd() {
+    check_golden("zmd", |assets| vec![assets.zmd.clone()]);
+}
+
+#[test]
+fn golden_zmd_zmo() {
+    check_golden("zmd_zmo", |assets| {
+        vec![assets.zmo.clone(), assets.zmd.clone()]
+    });
+}
+
+#[test]
+fn golden_skinned_zms() {
+    check_golden("skinned_zms", |assets| {
+        vec![assets.skinned_zms.clone(), assets.zmd.clone()]
+    });
+}
+
+#[test]
+fn golden_zone() {
+    check_golden("zone", |assets| vec![assets.zon.clone()]);
+}
@@ -0,0 +1,68 @@
+use std::{io::Cursor, path::Path};
+
+use rose_gltf_lib::{
+    rose_to_gltf, save_gltf, test_assets::write_test_assets, GltfData, GltfFormat,
+    RoseGltfConvOptions,
+};
+
+fn assert_same_data(gltf: &GltfData, expected: &GltfData) {
+    assert_eq!(
+        gltf.document.as_json().meshes.len(),
+        expected.document.as_json().meshes.len()
+    );
+    assert_eq!(gltf.buffers.len(), expected.buffers.len());
+    for (buffer, expected) in gltf.buffers.iter().zip(expected.buffers.iter()) {
+        assert!(buffer.0 == expected.0);
+    }
+    assert_eq!(gltf.images.len(), expected.images.len());
+    for (image, expected) in gltf.images.iter().zip(expected.images.iter()) {
+        assert_eq!(
+            (image.width, image.height),
+            (expected.width, expected.height)
+        );
+        assert!(image.pixels == expected.pixels);
+    }
+}
+
+#[test]
+fn read_glb_and_gltf() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("read_glb_and_gltf");
+    let assets = write_test_assets(&directory).unwrap();
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    let glb_path = directory.join("output/zone.glb");
+    save_gltf(&gltf, &glb_path, &GltfFormat::Binary).unwrap();
+    let gltf_path = directory.join("output/zone.gltf");
+    save_gltf(&gltf, &gltf_path, &GltfFormat::Text).unwrap();
+    let expected = GltfData::from_gltf(gltf).unwrap();
+    assert!(!expected.images.is_empty());
+
+    let glb = GltfData::from_slice(&std::fs::read(&glb_path).unwrap()).unwrap();
+    assert_same_data(&glb, &expected);
+
+    // The buffer of the .gltf is in zone.bin next to it
+    let text = std::fs::read(&gltf_path).unwrap();
+    let mut uris = Vec::new();
+    let gltf = GltfData::from_reader_with_resolver(Cursor::new(&text), |uri| {
+        uris.push(uri.to_string());
+        Ok(std::fs::read(gltf_path.with_file_name(uri))?)
+    })
+    .unwrap();
+    assert_eq!(uris, ["zone.bin"]);
+    assert_same_data(&gltf, &expected);
+
+    // Reading from a slice has nothing to resolve external references with
+    let Err(error) = GltfData::from_slice(&text) else {
+        panic!("external buffer should not load");
+    };
+    let message = format!("{:#}", error);
+    assert!(
+        message.contains("External reference zone.bin"),
+        "{}",
+        message
+    );
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    export_zone_incremental,
+    rose_file_lib::{files::IFO, io::RoseFile},
+    test_assets::write_test_assets,
+    GltfData, GltfFormat, IncrementalManifest, RoseGltfConvOptions,
+};
+
+#[test]
+fn incremental_zone_export() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("incremental_zone_export");
+    let assets = write_test_assets(&directory).unwrap();
+    let map_path = assets.zon.parent().unwrap();
+
+    // A second block with the same files
+    for extension in ["him", "til", "ifo"] {
+        std::fs::copy(
+            map_path.join(format!("32_32.{}", extension)),
+            map_path.join(format!("33_32.{}", extension)),
+        )
+        .unwrap();
+    }
+
+    let output = directory.join("out/zone.glb");
+    let cache_dir = directory.join("cache");
+    let export = |options: &RoseGltfConvOptions| {
+        export_zone_incremental(
+            &assets.zon,
+            &output,
+            &cache_dir,
+            &GltfFormat::Binary,
+            options,
+        )
+        .unwrap()
+    };
+    let options = RoseGltfConvOptions::default();
+
+    let first = export(&options);
+    assert_eq!(first.rebuilt, [[32, 32], [33, 32]]);
+    assert!(first.reused.is_empty());
+    let manifest: IncrementalManifest = serde_json::from_slice(
+        &std::fs::read(cache_dir.join(IncrementalManifest::FILE_NAME)).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(manifest.blocks.len(), 2);
+
+    let gltf = GltfData::from_slice(&std::fs::read(&output).unwrap()).unwrap();
+    let json = gltf.document.as_json();
+    for name in [
+        "32_32_deco_0",
+        "33_32_deco_0",
+        "32_32_heightmap",
+        "33_32_heightmap",
+    ] {
+        assert!(
+            json.nodes
+                .iter()
+                .any(|node| node.name.as_deref() == Some(name)),
+            "Missing node {}",
+            name
+        );
+    }
+    // The sun is only added once
+    let lights = &json
+        .extensions
+        .as_ref()
+        .unwrap()
+        .khr_lights_punctual
+        .as_ref()
+        .unwrap()
+        .lights;
+    assert_eq!(lights.len(), 1);
+
+    // Nothing changed
+    let second = export(&options);
+    assert!(second.rebuilt.is_empty());
+    assert_eq!(second.reused, [[32, 32], [33, 32]]);
+
+    // Only the edited block is converted again
+    let ifo_path = map_path.join("33_32.ifo");
+    let mut ifo = IFO::from_path(&ifo_path).unwrap();
+    ifo.objects.clear();
+    ifo.write_to_path(&ifo_path).unwrap();
+    let third = export(&options);
+    assert_eq!(third.rebuilt, [[33, 32]]);
+    assert_eq!(third.reused, [[32, 32]]);
+    let gltf = GltfData::from_slice(&std::fs::read(&output).unwrap()).unwrap();
+    assert!(gltf
+        .document
+        .nodes()
+        .all(|node| node.name() != Some("33_32_deco_0")));
+
+    // Textures used by both blocks and the options are inputs of every block
+    image::RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 255, 255]))
+        .save(directory.join("3ddata/test/quad.png"))
+        .unwrap();
+    assert_eq!(export(&options).rebuilt.len(), 2);
+    let options = RoseGltfConvOptions {
+        flip_v: true,
+        ..Default::default()
+    };
+    assert_eq!(export(&options).rebuilt.len(), 2);
+}
@@ -0,0 +1,170 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_file_lib::{
+        files::{
+            zsc::{ModelDummyAttachment, ModelDummyPoint},
+            ZSC,
+        },
+        io::RoseFile,
+        utils::Vector3,
+    },
+    rose_to_gltf,
+    test_assets::write_test_assets,
+    RoseGltfConvOptions,
+};
+use serde_json::Value;
+
+fn node_extras(gltf: &gltf::Gltf, name: &str) -> Value {
+    let node = gltf
+        .document
+        .as_json()
+        .nodes
+        .iter()
+        .find(|node| node.name.as_deref() == Some(name))
+        .unwrap();
+    serde_json::from_str(node.extras.as_ref().unwrap().get()).unwrap()
+}
+
+#[test]
+fn shadow_and_lightmap_hints() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("shadow_and_lightmap_hints");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // Make the construction an alpha blended part without a lightmap
+    let cnst_path = directory.join("3ddata/test/cnst.zsc");
+    let mut cnst = ZSC::from_path(&cnst_path).unwrap();
+    let part = &mut cnst.models[0].as_mut().unwrap().parts[0];
+    part.use_lightmap = false;
+    let material = part.material.as_mut().unwrap();
+    material.alpha_enabled = true;
+    material.alpha_test = None;
+    cnst.write_to_path(&cnst_path).unwrap();
+
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    let terrain = node_extras(&gltf, "32_32_heightmap");
+    assert_eq!(terrain["castShadows"], true);
+    assert_eq!(terrain["receiveLightmap"], true);
+
+    let deco = node_extras(&gltf, "32_32_deco_0_0");
+    assert_eq!(deco["castShadows"], true);
+    assert_eq!(deco["receiveLightmap"], true);
+    assert_eq!(deco["TLM_ObjectProperties"]["tlm_mesh_lightmap_use"], 1);
+
+    let cnst = node_extras(&gltf, "32_32_cnst_0_0");
+    assert_eq!(cnst["castShadows"], false);
+    assert_eq!(cnst["receiveLightmap"], false);
+    assert_eq!(cnst["TLM_ObjectProperties"]["tlm_mesh_lightmap_use"], 0);
+}
+
+#[test]
+fn light_container_lights() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("light_container_lights");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // Hang a light container a meter above the deco part
+    let deco_path = directory.join("3ddata/test/deco.zsc");
+    let mut deco = ZSC::from_path(&deco_path).unwrap();
+    deco.models[0]
+        .as_mut()
+        .unwrap()
+        .dummy_points
+        .push(ModelDummyPoint {
+            attachment: Some(ModelDummyAttachment::Light {
+                name: "3ddata\\test\\lamp.lct".to_string(),
+            }),
+            position: Vector3::new(0.0, 0.0, 100.0),
+            parent: Some(0),
+            ..Default::default()
+        });
+    deco.write_to_path(&deco_path).unwrap();
+    std::fs::write(
+        directory.join("3ddata/test/lamp.lct"),
+        "numlight 2
+
+light 1
+\ttype point
+\tcolor 1 0.5 0
+\tintensity 2
+\trange 400
+
+light 2
+\ttype spot
+\tpos 0 0 -10
+\tdir 0 0 -1
+\tintensity 8
+\tcone 0 90
+",
+    )
+    .unwrap();
+
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let json = gltf.document.as_json();
+    assert!(json
+        .extensions_used
+        .iter()
+        .any(|extension| extension == "KHR_lights_punctual"));
+    let node = |name: &str| {
+        json.nodes
+            .iter()
+            .find(|node| node.name.as_deref() == Some(name))
+            .unwrap_or_else(|| panic!("Missing node {}", name))
+    };
+    let child_names = |name: &str| {
+        node(name)
+            .children
+            .iter()
+            .flatten()
+            .map(|child| json.nodes[child.value()].name.clone().unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(child_names("32_32_deco_0_0"), ["32_32_deco_0_dummy_0"]);
+    assert_eq!(
+        node("32_32_deco_0_dummy_0").translation,
+        Some([0.0, 1.0, 0.0])
+    );
+    assert_eq!(
+        child_names("32_32_deco_0_dummy_0"),
+        [
+            "32_32_deco_0_dummy_0_light_0",
+            "32_32_deco_0_dummy_0_light_1"
+        ]
+    );
+
+    // The lights follow the sun
+    let lights = &json
+        .extensions
+        .as_ref()
+        .unwrap()
+        .khr_lights_punctual
+        .as_ref()
+        .unwrap()
+        .lights;
+    assert_eq!(lights.len(), 3);
+    assert_eq!(lights[1].color, [1.0, 0.5, 0.0]);
+    assert_eq!(lights[1].intensity, 2.0);
+    assert_eq!(lights[1].range, Some(4.0));
+    let spot = lights[2].spot.as_ref().unwrap();
+    assert_eq!(spot.inner_cone_angle, 0.0);
+    assert_eq!(spot.outer_cone_angle, std::f32::consts::FRAC_PI_2);
+
+    let spot_node = node("32_32_deco_0_dummy_0_light_1");
+    let light = spot_node.extensions.as_ref().unwrap();
+    assert_eq!(light.khr_lights_punctual.as_ref().unwrap().light.value(), 2);
+    assert_eq!(spot_node.translation, Some([0.0, -0.1, 0.0]));
+
+    // The spot light points down
+    let [x, y, z, w] = spot_node.rotation.unwrap().0;
+    let down = glam::Quat::from_xyzw(x, y, z, w) * glam::Vec3::NEG_Z;
+    assert!(down.abs_diff_eq(glam::Vec3::NEG_Y, 1.0e-6));
+}
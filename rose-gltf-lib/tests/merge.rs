@@ -0,0 +1,75 @@
+use std::{collections::HashSet, path::Path};
+
+use rose_gltf_lib::{
+    merge_gltf, rose_to_gltf_data, test_assets::write_test_assets, GltfData, RoseGltfConvOptions,
+};
+
+#[test]
+fn merge_gltf_files() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("merge_gltf_files");
+    let assets = write_test_assets(&directory).unwrap();
+    let convert = |path: &Path| {
+        rose_to_gltf_data(&[path.to_path_buf()], &RoseGltfConvOptions::default()).unwrap()
+    };
+    let scene_nodes = |gltf: &GltfData| gltf.document.scenes().next().unwrap().nodes().len();
+
+    // Merging a zone with itself shares its images, textures and materials,
+    // which are also shared within the zone when they only differ by name
+    let zone = convert(&assets.zon);
+    let single = GltfData::from_gltf(merge_gltf(&[convert(&assets.zon)]).unwrap()).unwrap();
+    assert!(single.document.materials().len() <= zone.document.materials().len());
+    let merged =
+        GltfData::from_gltf(merge_gltf(&[convert(&assets.zon), convert(&assets.zon)]).unwrap())
+            .unwrap();
+    assert_eq!(merged.document.buffers().len(), 1);
+    assert_eq!(
+        merged.document.materials().len(),
+        single.document.materials().len()
+    );
+    assert_eq!(
+        merged.document.textures().len(),
+        single.document.textures().len()
+    );
+    assert_eq!(
+        merged.document.images().len(),
+        single.document.images().len()
+    );
+    assert_eq!(
+        merged.document.meshes().len(),
+        zone.document.meshes().len() * 2
+    );
+    assert_eq!(
+        merged.document.nodes().len(),
+        zone.document.nodes().len() * 2
+    );
+    assert_eq!(scene_nodes(&merged), scene_nodes(&zone) * 2);
+
+    // Nodes of the second copy are renamed
+    let names = merged
+        .document
+        .nodes()
+        .filter_map(|node| node.name())
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        names.len(),
+        merged
+            .document
+            .nodes()
+            .filter(|node| node.name().is_some())
+            .count()
+    );
+
+    // The scene of a merged mesh is added after the zone
+    let mesh = convert(&assets.zms);
+    let merged =
+        GltfData::from_gltf(merge_gltf(&[convert(&assets.zon), convert(&assets.zms)]).unwrap())
+            .unwrap();
+    assert_eq!(
+        scene_nodes(&merged),
+        scene_nodes(&zone) + scene_nodes(&mesh)
+    );
+    assert_eq!(
+        merged.document.accessors().len(),
+        zone.document.accessors().len() + mesh.document.accessors().len()
+    );
+}
@@ -0,0 +1,693 @@
+use std::path::Path;
+
+use glam::Vec2;
+use rose_gltf_lib::{
+    collect_warnings, gltf_to_rose,
+    rose_file_lib::{
+        files::{zms::VertexFormat, ZMS},
+        io::RoseFile,
+        utils::{Vector2, Vector3},
+    },
+    rose_to_gltf_data,
+    test_assets::{quad_zms, write_test_assets},
+    GltfData, GltfRoseConvOptions, RoseGltfConvOptions, UvMode, WindingCorrection,
+};
+
+struct PrimitiveData {
+    positions: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+    bounds: gltf::mesh::Bounds<[f32; 3]>,
+}
+
+fn primitive_data(gltf: &GltfData, position_accessor_name: &str) -> PrimitiveData {
+    let primitive = gltf
+        .document
+        .meshes()
+        .flat_map(|mesh| mesh.primitives())
+        .find(|primitive| {
+            primitive
+                .get(&gltf::Semantic::Positions)
+                .and_then(|accessor| accessor.name().map(|name| name.to_string()))
+                .is_some_and(|name| name == position_accessor_name)
+        })
+        .unwrap();
+    let reader = primitive.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+    PrimitiveData {
+        positions: reader.read_positions().unwrap().collect(),
+        uvs: reader.read_tex_coords(0).unwrap().into_f32().collect(),
+        indices: reader.read_indices().unwrap().into_u32().collect(),
+        bounds: primitive.bounding_box(),
+    }
+}
+
+#[test]
+fn zms_and_zsc_meshes_match() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zms_and_zsc_meshes_match");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let zms_gltf = rose_to_gltf_data(
+        std::slice::from_ref(&assets.zms),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let zms_mesh = primitive_data(&zms_gltf, "quad_position");
+
+    // The same ZMS used by a ZSC part of the zone
+    let zone_gltf = rose_to_gltf_data(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let zsc_mesh = primitive_data(&zone_gltf, "deco_mesh_0_position");
+
+    // ROSE is Z up, glTF is Y up
+    let zms = quad_zms();
+    let expected_positions: Vec<[f32; 3]> = zms
+        .vertices
+        .iter()
+        .map(|vertex| [vertex.position.x, vertex.position.z, -vertex.position.y])
+        .collect();
+    assert_eq!(zms_mesh.positions, expected_positions);
+    assert_eq!(zsc_mesh.positions, expected_positions);
+
+    assert_eq!(zms_mesh.uvs, zsc_mesh.uvs);
+    assert_eq!(zms_mesh.indices, zsc_mesh.indices);
+    assert_eq!(zms_mesh.bounds.min, zsc_mesh.bounds.min);
+    assert_eq!(zms_mesh.bounds.max, zsc_mesh.bounds.max);
+    assert_eq!(zms_mesh.bounds.min, [0.0, 0.0, -1.0]);
+    assert_eq!(zms_mesh.bounds.max, [1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn degenerate_triangles_removed() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("degenerate_triangles_removed");
+    write_test_assets(&directory).unwrap();
+
+    // A vertex no triangle uses, a triangle repeating a vertex and a triangle
+    // with no area, all of which reference the unused vertex
+    let mut zms = quad_zms();
+    let mut unused_vertex = zms.vertices[0].clone();
+    unused_vertex.position = Vector3::new(5.0, 5.0, 5.0);
+    zms.vertices.insert(0, unused_vertex);
+    for triangle in zms.indices.iter_mut() {
+        triangle.x += 1;
+        triangle.y += 1;
+        triangle.z += 1;
+    }
+    zms.indices.push(Vector3::new(0, 1, 1));
+    zms.indices.push(Vector3::new(1, 1, 1));
+    let zms_path = directory.join("degenerate.zms");
+    zms.write_to_path(&zms_path).unwrap();
+
+    let (gltf, warnings) = collect_warnings(|| {
+        rose_to_gltf_data(
+            std::slice::from_ref(&zms_path),
+            &RoseGltfConvOptions::default(),
+        )
+    });
+    let mesh = primitive_data(&gltf.unwrap(), "degenerate_position");
+    assert_eq!(mesh.positions.len(), 4);
+    assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+    assert_eq!(mesh.bounds.max, [1.0, 0.0, 0.0]);
+    assert!(warnings
+        .iter()
+        .any(|warning| warning.contains("removed 2 degenerate triangles and 1 unused vertices")));
+}
+
+#[test]
+fn indices_not_starting_at_first_vertex() {
+    let directory =
+        Path::new(env!("CARGO_TARGET_TMPDIR")).join("indices_not_starting_at_first_vertex");
+    write_test_assets(&directory).unwrap();
+
+    // Every vertex is used but the first triangle does not start at vertex 0
+    let mut zms = quad_zms();
+    zms.indices = vec![Vector3::new(2, 0, 1), Vector3::new(3, 0, 2)];
+    let zms_path = directory.join("rotated.zms");
+    zms.write_to_path(&zms_path).unwrap();
+    let gltf = rose_to_gltf_data(
+        std::slice::from_ref(&zms_path),
+        &RoseGltfConvOptions {
+            winding: WindingCorrection::None,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let triangle_positions = |zms: &ZMS| -> Vec<[[f32; 3]; 3]> {
+        zms.indices
+            .iter()
+            .map(|triangle| {
+                [triangle.x, triangle.y, triangle.z].map(|index| {
+                    let position = &zms.vertices[index as usize].position;
+                    [position.x, position.y, position.z]
+                })
+            })
+            .collect()
+    };
+    for winding in [WindingCorrection::None, WindingCorrection::Flip] {
+        let result = gltf_to_rose(
+            &gltf,
+            &GltfRoseConvOptions {
+                winding,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut expected = triangle_positions(&zms);
+        if winding == WindingCorrection::Flip {
+            for triangle in expected.iter_mut() {
+                triangle.swap(1, 2);
+            }
+        }
+        assert_eq!(result.zms[0].1.vertices.len(), 4);
+        assert_eq!(triangle_positions(&result.zms[0].1), expected);
+    }
+}
+
+/// A tetrahedron with its triangles facing outwards
+fn tetrahedron_zms() -> ZMS {
+    let mut zms = quad_zms();
+    let corners = [
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+    ];
+    for (vertex, corner) in zms.vertices.iter_mut().zip(corners) {
+        vertex.position = corner;
+    }
+    zms.indices = vec![
+        Vector3::new(0, 2, 1),
+        Vector3::new(0, 1, 3),
+        Vector3::new(0, 3, 2),
+        Vector3::new(1, 2, 3),
+    ];
+    zms
+}
+
+#[test]
+fn winding_correction() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("winding_correction");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let inside_out_path = directory.join("inside_out.zms");
+    let mut zms = tetrahedron_zms();
+    for triangle in zms.indices.iter_mut() {
+        std::mem::swap(&mut triangle.y, &mut triangle.z);
+    }
+    zms.write_to_path(&inside_out_path).unwrap();
+
+    let convert = |path: &Path, winding| {
+        let options = RoseGltfConvOptions {
+            winding,
+            ..Default::default()
+        };
+        collect_warnings(|| rose_to_gltf_data(&[path.to_path_buf()], &options).unwrap())
+    };
+    let inward_indices = [0, 1, 2, 0, 3, 1, 0, 2, 3, 1, 3, 2];
+    let outward_indices = [0, 2, 1, 0, 1, 3, 0, 3, 2, 1, 2, 3];
+
+    let (gltf, _) = convert(&inside_out_path, WindingCorrection::None);
+    assert_eq!(
+        primitive_data(&gltf, "inside_out_position").indices,
+        inward_indices
+    );
+    let (gltf, warnings) = convert(&inside_out_path, WindingCorrection::Auto);
+    assert_eq!(
+        primitive_data(&gltf, "inside_out_position").indices,
+        outward_indices
+    );
+    assert!(warnings
+        .iter()
+        .any(|warning| warning.contains("reversed its winding")));
+
+    // The quad is open so auto keeps it, flip reverses it
+    let (gltf, _) = convert(&assets.zms, WindingCorrection::Auto);
+    assert_eq!(
+        primitive_data(&gltf, "quad_position").indices,
+        [0, 1, 2, 0, 2, 3]
+    );
+    let (gltf, _) = convert(&assets.zms, WindingCorrection::Flip);
+    assert_eq!(
+        primitive_data(&gltf, "quad_position").indices,
+        [0, 2, 1, 0, 3, 2]
+    );
+
+    // Flipping in both directions round trips
+    let result = gltf_to_rose(
+        &gltf,
+        &GltfRoseConvOptions {
+            winding: WindingCorrection::Flip,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(result.zms[0].1.indices, quad_zms().indices);
+}
+
+#[test]
+fn hard_edges_split() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("hard_edges_split");
+    let assets = write_test_assets(&directory).unwrap();
+    let tetrahedron_path = directory.join("tetrahedron.zms");
+    tetrahedron_zms().write_to_path(&tetrahedron_path).unwrap();
+
+    let convert = |path: &Path, hard_edge_angle| {
+        let options = RoseGltfConvOptions {
+            hard_edge_angle,
+            ..Default::default()
+        };
+        rose_to_gltf_data(&[path.to_path_buf()], &options).unwrap()
+    };
+    let normals = |gltf: &GltfData, mesh_name: &str| -> Vec<[f32; 3]> {
+        let primitive = gltf
+            .document
+            .meshes()
+            .find(|mesh| mesh.name() == Some(mesh_name))
+            .and_then(|mesh| mesh.primitives().next())
+            .unwrap();
+        let reader = primitive.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+        reader.read_normals().unwrap().collect()
+    };
+
+    // Every face of the tetrahedron meets the others at a hard edge, so each
+    // corner gets its own vertex with the normal of its face
+    let gltf = convert(&tetrahedron_path, Some(30.0));
+    let mesh = primitive_data(&gltf, "tetrahedron_position");
+    assert_eq!(mesh.positions.len(), 12);
+    let normals_of_faces = normals(&gltf, "tetrahedron");
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|corner| {
+            let [x, y, z] = mesh.positions[triangle[corner] as usize];
+            glam::Vec3::new(x, y, z)
+        });
+        let face_normal = (b - a).cross(c - a).normalize();
+        for &index in triangle {
+            let normal = glam::Vec3::from_array(normals_of_faces[index as usize]);
+            assert!(normal.abs_diff_eq(face_normal, 1e-5));
+        }
+    }
+
+    // Above the angle between its faces the tetrahedron is smooth
+    let gltf = convert(&tetrahedron_path, Some(180.0));
+    assert_eq!(
+        primitive_data(&gltf, "tetrahedron_position")
+            .positions
+            .len(),
+        4
+    );
+
+    // The faces of the quad are flat, so they keep sharing vertices
+    let gltf = convert(&assets.zms, Some(0.0));
+    assert_eq!(primitive_data(&gltf, "quad_position").positions.len(), 4);
+    assert!(normals(&gltf, "quad")
+        .iter()
+        .all(|normal| *normal == [0.0, 1.0, 0.0]));
+}
+
+/// Whether two triangles overlap by more than touching along an edge, by
+/// looking for an edge of either which separates them
+fn triangles_overlap(a: [Vec2; 3], b: [Vec2; 3]) -> bool {
+    for triangle in [a, b] {
+        for edge in 0..3 {
+            let direction = triangle[(edge + 1) % 3] - triangle[edge];
+            let axis = Vec2::new(-direction.y, direction.x);
+            let project = |triangle: [Vec2; 3]| {
+                let distances = triangle.map(|point| point.dot(axis));
+                (
+                    distances.into_iter().fold(f32::MAX, f32::min),
+                    distances.into_iter().fold(f32::MIN, f32::max),
+                )
+            };
+            let ((min_a, max_a), (min_b, max_b)) = (project(a), project(b));
+            if max_a <= min_b + 1.0e-6 || max_b <= min_a + 1.0e-6 {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Converts `zms` to glTF and back with lightmap UVs generated
+fn convert_with_lightmap_uvs(name: &str, mut zms: ZMS) -> (ZMS, Vec<String>) {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(format!("{}.zms", name));
+    zms.write_to_path(&path).unwrap();
+    let gltf = rose_to_gltf_data(&[path], &RoseGltfConvOptions::default()).unwrap();
+
+    let options = GltfRoseConvOptions {
+        generate_lightmap_uvs: true,
+        ..Default::default()
+    };
+    let (result, warnings) = collect_warnings(|| gltf_to_rose(&gltf, &options).unwrap());
+    (result.zms.into_iter().next().unwrap().1, warnings)
+}
+
+#[test]
+fn generate_lightmap_uvs() {
+    let (zms, warnings) = convert_with_lightmap_uvs("generate_lightmap_uvs", tetrahedron_zms());
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert!(zms.uv2_enabled());
+
+    // Each face faces a different axis, so is a chart of its own with its
+    // own copy of the corners
+    assert_eq!(zms.indices.len(), 4);
+    assert_eq!(zms.vertices.len(), 12);
+    assert!(zms.vertices.iter().all(|vertex| {
+        (0.0..=1.0).contains(&vertex.uv2.x) && (0.0..=1.0).contains(&vertex.uv2.y)
+    }));
+
+    let triangles: Vec<[Vec2; 3]> = zms
+        .indices
+        .iter()
+        .map(|triangle| {
+            [triangle.x, triangle.y, triangle.z].map(|index| {
+                let uv = &zms.vertices[index as u16 as usize].uv2;
+                Vec2::new(uv.x, uv.y)
+            })
+        })
+        .collect();
+    for (index, a) in triangles.iter().enumerate() {
+        for b in triangles[index + 1..].iter() {
+            assert!(!triangles_overlap(*a, *b), "{:?} overlaps {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn generate_lightmap_uvs_too_many_vertices() {
+    // A strip whose triangles alternately face up and down, so each is a
+    // chart and most vertices are copied into three of them
+    let mut zms = quad_zms();
+    let template = zms.vertices[0].clone();
+    zms.vertices = (0..22002)
+        .map(|index| {
+            let mut vertex = template.clone();
+            vertex.position = Vector3::new((index / 2) as f32, (index % 2) as f32, 0.0);
+            vertex
+        })
+        .collect();
+    zms.indices = (0..22000u16)
+        .map(|index| Vector3::new(index, index + 1, index + 2))
+        .map(|triangle| Vector3::new(triangle.x as i16, triangle.y as i16, triangle.z as i16))
+        .collect();
+
+    let (converted, warnings) =
+        convert_with_lightmap_uvs("generate_lightmap_uvs_too_many_vertices", zms.clone());
+    assert!(!converted.uv2_enabled());
+    assert_eq!(converted.vertices.len(), zms.vertices.len());
+    assert!(
+        warnings
+            .iter()
+            .any(|warning| warning.contains("UV2 not generated")),
+        "{:?}",
+        warnings
+    );
+}
+
+#[test]
+fn keep_attributes() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("keep_attributes");
+    let assets = write_test_assets(&directory).unwrap();
+    let gltf = rose_to_gltf_data(
+        &[assets.zmd.clone(), assets.skinned_zms.clone()],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let format = |keep_attributes| {
+        let options = GltfRoseConvOptions {
+            keep_attributes,
+            ..Default::default()
+        };
+        gltf_to_rose(&gltf, &options).unwrap().zms[0].1.format
+    };
+
+    let position = VertexFormat::Position as i32;
+    let normal = VertexFormat::Normal as i32;
+    let uv1 = VertexFormat::UV1 as i32;
+    let bone_weight = VertexFormat::BoneWeight as i32;
+    let bone_index = VertexFormat::BoneIndex as i32;
+    assert_eq!(
+        format(None),
+        position | normal | uv1 | bone_weight | bone_index
+    );
+
+    // Positions are always kept
+    assert_eq!(format(Some(normal)), position | normal);
+    assert_eq!(
+        format(Some(uv1 | bone_weight | bone_index)),
+        position | uv1 | bone_weight | bone_index
+    );
+
+    // Bone weights are useless without bone indices
+    assert_eq!(format(Some(normal | bone_weight)), position | normal);
+}
+
+#[test]
+fn uv_modes() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("uv_modes");
+    let assets = write_test_assets(&directory).unwrap();
+    let mut zms = quad_zms();
+    for (vertex, (u, v)) in
+        zms.vertices
+            .iter_mut()
+            .zip([(-0.5, 0.25), (1.5, 0.25), (1.5, 2.0), (-0.5, 2.0)])
+    {
+        vertex.uv1 = Vector2::new(u, v);
+    }
+    zms.write_to_path(&assets.zms).unwrap();
+    let gltf = rose_to_gltf_data(
+        std::slice::from_ref(&assets.zms),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    let uvs = |uv_mode| {
+        let options = GltfRoseConvOptions {
+            uv_modes: [uv_mode, UvMode::Keep, UvMode::Keep, UvMode::Keep],
+            ..Default::default()
+        };
+        let (result, warnings) = collect_warnings(|| gltf_to_rose(&gltf, &options).unwrap());
+        let uvs: Vec<[f32; 2]> = result.zms[0]
+            .1
+            .vertices
+            .iter()
+            .map(|vertex| [vertex.uv1.x, vertex.uv1.y])
+            .collect();
+        (uvs, warnings)
+    };
+
+    let (keep, _) = uvs(UvMode::Keep);
+    assert_eq!(keep, [[-0.5, 0.25], [1.5, 0.25], [1.5, 2.0], [-0.5, 2.0]]);
+
+    // UVs in [0, 1] are left as they are
+    let (wrap, warnings) = uvs(UvMode::Wrap);
+    assert_eq!(wrap, [[0.5, 0.25], [0.5, 0.25], [0.5, 0.0], [0.5, 0.0]]);
+    assert!(
+        warnings.iter().any(|warning| warning
+            .ends_with("UV1 extents (-0.5, 0.25) - (1.5, 2) are outside of [0, 1], Wrap applied")),
+        "{:?}",
+        warnings
+    );
+
+    let (clamp, _) = uvs(UvMode::Clamp);
+    assert_eq!(clamp, [[0.0, 0.25], [1.0, 0.25], [1.0, 1.0], [0.0, 1.0]]);
+
+    let (rescale, _) = uvs(UvMode::Rescale);
+    assert_eq!(rescale, [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+}
+
+#[test]
+fn generate_missing_tangents() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("generate_missing_tangents");
+    write_test_assets(&directory).unwrap();
+    let tangents = |mut zms: ZMS, generate_missing_tangents| {
+        let path = directory.join("tangents.zms");
+        zms.write_to_path(&path).unwrap();
+        let options = RoseGltfConvOptions {
+            generate_missing_tangents,
+            ..Default::default()
+        };
+        let gltf = rose_to_gltf_data(&[path], &options).unwrap();
+        let primitive = gltf
+            .document
+            .meshes()
+            .next()
+            .unwrap()
+            .primitives()
+            .next()
+            .unwrap();
+        let reader = primitive.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+        reader
+            .read_tangents()
+            .map(|tangents| tangents.collect::<Vec<[f32; 4]>>())
+    };
+
+    assert_eq!(tangents(quad_zms(), false), None);
+
+    // U increases along X, which stays X in glTF
+    let generated = tangents(quad_zms(), true).unwrap();
+    assert_eq!(generated.len(), 4);
+    for [x, y, z, w] in generated {
+        assert!((glam::Vec3::new(x, y, z).length() - 1.0).abs() < 1.0e-5);
+        assert!((x - 1.0).abs() < 1.0e-5, "{:?}", [x, y, z]);
+        assert_eq!(w.abs(), 1.0);
+    }
+
+    // Tangents of the ZMS are kept
+    let mut zms = quad_zms();
+    zms.format |= VertexFormat::Tangent as i32;
+    for vertex in zms.vertices.iter_mut() {
+        vertex.tangent = Vector3::new(0.0, 1.0, 0.0);
+    }
+    for [x, y, z, _] in tangents(zms, true).unwrap() {
+        assert_eq!([x, y, z], [0.0, 0.0, -1.0]);
+    }
+}
+
+#[test]
+fn texture_transforms() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("texture_transforms");
+    let assets = write_test_assets(&directory).unwrap();
+    let mut zms = quad_zms();
+    zms.format |= VertexFormat::UV2 as i32;
+    for vertex in zms.vertices.iter_mut() {
+        vertex.uv2 = Vector2::new(vertex.uv1.x * 0.5, vertex.uv1.y * 0.5);
+    }
+    zms.write_to_path(&assets.zms).unwrap();
+    let gltf = rose_to_gltf_data(
+        std::slice::from_ref(&assets.zms),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    // Returns UV1 and UV2 after baking the transform of the base color texture
+    let baked_uvs = |texture_info: serde_json::Value| {
+        let mut root = serde_json::to_value(gltf.document.clone().into_json()).unwrap();
+        root["images"] = serde_json::json!([{ "uri": "quad.png" }]);
+        root["textures"] = serde_json::json!([{ "source": 0 }]);
+        root["materials"] = serde_json::json!([{
+            "pbrMetallicRoughness": { "baseColorTexture": texture_info }
+        }]);
+        root["meshes"][0]["primitives"][0]["material"] = serde_json::json!(0);
+        let gltf = GltfData {
+            document: gltf::Document::from_json(serde_json::from_value(root).unwrap()).unwrap(),
+            buffers: gltf.buffers.clone(),
+            images: Vec::new(),
+        };
+        let (result, warnings) =
+            collect_warnings(|| gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap());
+        let uvs = |uv: fn(&rose_file_lib::files::zms::Vertex) -> Vector2<f32>| {
+            result.zms[0]
+                .1
+                .vertices
+                .iter()
+                .map(|vertex| [uv(vertex).x, uv(vertex).y])
+                .collect::<Vec<_>>()
+        };
+        (uvs(|vertex| vertex.uv1), uvs(|vertex| vertex.uv2), warnings)
+    };
+    let assert_uvs = |uvs: Vec<[f32; 2]>, expected: [[f32; 2]; 4]| {
+        for (uv, expected) in uvs.iter().zip(expected) {
+            assert!(
+                (Vec2::from_array(*uv) - Vec2::from_array(expected)).length() < 1.0e-5,
+                "{:?} != {:?}",
+                uvs,
+                expected
+            );
+        }
+    };
+    let uv1 = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+    let uv2 = [[0.0, 0.5], [0.5, 0.5], [0.5, 0.0], [0.0, 0.0]];
+
+    let (baked, _, _) = baked_uvs(serde_json::json!({
+        "index": 0,
+        "extensions": { "KHR_texture_transform": { "offset": [0.5, 0.25] } }
+    }));
+    assert_uvs(baked, [[0.5, 1.25], [1.5, 1.25], [1.5, 0.25], [0.5, 0.25]]);
+
+    let (baked, _, _) = baked_uvs(serde_json::json!({
+        "index": 0,
+        "extensions": { "KHR_texture_transform": { "scale": [2.0, 3.0] } }
+    }));
+    assert_uvs(baked, [[0.0, 3.0], [2.0, 3.0], [2.0, 0.0], [0.0, 0.0]]);
+
+    // A quarter turn maps (u, v) to (v, -u)
+    let (baked, _, _) = baked_uvs(serde_json::json!({
+        "index": 0,
+        "extensions": {
+            "KHR_texture_transform": { "rotation": std::f32::consts::FRAC_PI_2 }
+        }
+    }));
+    assert_uvs(baked, [[1.0, 0.0], [1.0, -1.0], [0.0, -1.0], [0.0, 0.0]]);
+
+    // Scale, then rotate, then offset
+    let (baked, _, _) = baked_uvs(serde_json::json!({
+        "index": 0,
+        "extensions": {
+            "KHR_texture_transform": {
+                "offset": [0.5, 0.25],
+                "rotation": std::f32::consts::FRAC_PI_2,
+                "scale": [2.0, 3.0]
+            }
+        }
+    }));
+    assert_uvs(
+        baked,
+        [[3.5, 0.25], [3.5, -1.75], [0.5, -1.75], [0.5, 0.25]],
+    );
+
+    // The texCoord of the transform overrides the one of the texture
+    let shifted_uv2 = [[0.5, 0.75], [1.0, 0.75], [1.0, 0.25], [0.5, 0.25]];
+    let (baked_uv1, baked_uv2, _) = baked_uvs(serde_json::json!({
+        "index": 0,
+        "texCoord": 0,
+        "extensions": {
+            "KHR_texture_transform": { "offset": [0.5, 0.25], "texCoord": 1 }
+        }
+    }));
+    assert_uvs(baked_uv1, uv1);
+    assert_uvs(baked_uv2, shifted_uv2);
+
+    let (baked_uv1, baked_uv2, _) = baked_uvs(serde_json::json!({
+        "index": 0,
+        "texCoord": 1,
+        "extensions": {
+            "KHR_texture_transform": { "offset": [0.5, 0.25], "texCoord": 0 }
+        }
+    }));
+    assert_uvs(
+        baked_uv1,
+        [[0.5, 1.25], [1.5, 1.25], [1.5, 0.25], [0.5, 0.25]],
+    );
+    assert_uvs(baked_uv2, uv2);
+
+    // Without an override the texCoord of the texture is used
+    let (baked_uv1, baked_uv2, _) = baked_uvs(serde_json::json!({
+        "index": 0,
+        "texCoord": 1,
+        "extensions": { "KHR_texture_transform": { "offset": [0.5, 0.25] } }
+    }));
+    assert_uvs(baked_uv1, uv1);
+    assert_uvs(baked_uv2, shifted_uv2);
+
+    let (baked_uv1, baked_uv2, warnings) = baked_uvs(serde_json::json!({
+        "index": 0,
+        "extensions": {
+            "KHR_texture_transform": { "offset": [0.5, 0.25], "texCoord": 2 }
+        }
+    }));
+    assert_uvs(baked_uv1, uv1);
+    assert_uvs(baked_uv2, uv2);
+    assert!(
+        warnings
+            .iter()
+            .any(|warning| warning
+                .ends_with("KHR_texture_transform references missing UV3, ignored")),
+        "{:?}",
+        warnings
+    );
+}
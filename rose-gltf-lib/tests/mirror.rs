@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use rose_file_lib::{
+    files::{
+        zmd::Bone,
+        zmo::{Channel, ChannelData, ChannelType},
+        ZMD, ZMO,
+    },
+    io::RoseFile,
+    utils::{Quaternion, Vector3},
+};
+use rose_gltf_lib::{
+    mirror_animation, mirrored_bone_name, rose_to_gltf_data, test_assets::write_test_assets,
+    RoseGltfConvOptions,
+};
+
+fn bone(name: &str, parent: i32, x: f32) -> Bone {
+    Bone {
+        parent,
+        name: name.to_string(),
+        position: Vector3::new(x, 0.0, 0.0),
+        rotation: Quaternion::IDENTITY,
+    }
+}
+
+/// A pelvis with an arm on each side
+fn arms_zmd() -> ZMD {
+    ZMD {
+        bones: vec![
+            bone("b1_pelvis", 0, 0.0),
+            bone("b1_l_arm", 0, 50.0),
+            bone("b1_r_arm", 0, -50.0),
+        ],
+        ..Default::default()
+    }
+}
+
+/// The pelvis moves along +X while the left arm turns around the up axis
+fn wave_zmo() -> ZMO {
+    let half_sqrt2 = std::f32::consts::FRAC_1_SQRT_2;
+    ZMO {
+        identifier: "ZMO0002".to_string(),
+        fps: 30,
+        frames: 2,
+        channels: vec![
+            Channel {
+                typ: ChannelType::Position,
+                index: 0,
+                frames: ChannelData::Position(vec![
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(10.0, 0.0, 0.0),
+                ]),
+            },
+            Channel {
+                typ: ChannelType::Rotation,
+                index: 1,
+                frames: ChannelData::Rotation(vec![
+                    Quaternion::IDENTITY,
+                    Quaternion::new(0.0, 0.0, half_sqrt2, half_sqrt2),
+                ]),
+            },
+        ],
+    }
+}
+
+#[test]
+fn mirrored_bone_names() {
+    assert_eq!(mirrored_bone_name("b1_l_arm").as_deref(), Some("b1_r_arm"));
+    assert_eq!(
+        mirrored_bone_name("Bip01 R Hand").as_deref(),
+        Some("Bip01 L Hand")
+    );
+    assert_eq!(
+        mirrored_bone_name("left_foot").as_deref(),
+        Some("right_foot")
+    );
+    assert_eq!(mirrored_bone_name("b1_pelvis"), None);
+    assert_eq!(mirrored_bone_name("b1_lr"), None);
+}
+
+#[test]
+fn mirror_skeletal_animation() {
+    let mirrored = mirror_animation(&wave_zmo(), &arms_zmd());
+    assert_eq!(mirrored.frames, 2);
+
+    let channel = |typ, index| {
+        mirrored
+            .channels
+            .iter()
+            .find(|channel| channel.typ == typ && channel.index == index)
+            .unwrap()
+    };
+
+    // The pelvis moves along -X
+    let ChannelData::Position(positions) = &channel(ChannelType::Position, 0).frames else {
+        panic!("Expected positions");
+    };
+    assert!((positions[1].x + 10.0).abs() < 1.0e-3);
+    assert!(positions[1].y.abs() < 1.0e-3 && positions[1].z.abs() < 1.0e-3);
+
+    // The right arm turns the other way around the up axis
+    let ChannelData::Rotation(rotations) = &channel(ChannelType::Rotation, 2).frames else {
+        panic!("Expected rotations");
+    };
+    let half_sqrt2 = std::f32::consts::FRAC_1_SQRT_2;
+    let expected = Quaternion::new(0.0, 0.0, -half_sqrt2, half_sqrt2);
+    let dot = rotations[1].x * expected.x
+        + rotations[1].y * expected.y
+        + rotations[1].z * expected.z
+        + rotations[1].w * expected.w;
+    assert!((dot.abs() - 1.0).abs() < 1.0e-4);
+}
+
+#[test]
+fn mirror_animations_option() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("mirror_animations_option");
+    let assets = write_test_assets(&directory).unwrap();
+    arms_zmd().write_to_path(&assets.zmd).unwrap();
+    wave_zmo().write_to_path(&assets.zmo).unwrap();
+
+    let options = RoseGltfConvOptions {
+        mirror_animations: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(&[assets.zmd.clone(), assets.zmo.clone()], &options).unwrap();
+    let names: Vec<&str> = gltf
+        .document
+        .animations()
+        .map(|animation| animation.name().unwrap())
+        .collect();
+    assert_eq!(names, ["two_bone", "two_bone_mirrored"]);
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    collect_warnings, find_missing_zone_assets, rose_to_gltf_data, test_assets::write_test_assets,
+    MissingAsset, RoseGltfConvOptions,
+};
+
+#[test]
+fn missing_zone_assets() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("missing_zone_assets");
+    let assets = write_test_assets(&directory).unwrap();
+    std::fs::remove_file(&assets.zms).unwrap();
+    std::fs::remove_file(directory.join("3ddata/test/tile.png")).unwrap();
+
+    let missing_assets =
+        find_missing_zone_assets(&assets.zon, &RoseGltfConvOptions::default()).unwrap();
+    assert_eq!(
+        missing_assets,
+        [
+            MissingAsset {
+                path: "3ddata/test/quad.zms".to_string(),
+                referenced_by: vec![
+                    "cnst object 0 in block 32_32".to_string(),
+                    "deco object 0 in block 32_32".to_string(),
+                ],
+            },
+            MissingAsset {
+                path: "3ddata/test/tile.png".to_string(),
+                referenced_by: vec!["tile texture 0 of 3ddata/maps/test/test.zon".to_string()],
+            },
+        ]
+    );
+
+    // Blocks which are not selected are not scanned
+    let options = RoseGltfConvOptions {
+        filter_block_x: Some(0),
+        ..Default::default()
+    };
+    let missing_assets = find_missing_zone_assets(&assets.zon, &options).unwrap();
+    assert_eq!(missing_assets.len(), 1);
+
+    let input_files = std::slice::from_ref(&assets.zon);
+    let (gltf, warnings) =
+        collect_warnings(|| rose_to_gltf_data(input_files, &RoseGltfConvOptions::default()));
+    assert_eq!(gltf.unwrap().document.meshes().count(), 0);
+    assert!(warnings
+        .iter()
+        .any(|warning| warning.starts_with("Failed to load zone")));
+
+    let options = RoseGltfConvOptions {
+        ignore_missing_assets: true,
+        ..Default::default()
+    };
+    let (gltf, warnings) = collect_warnings(|| rose_to_gltf_data(input_files, &options));
+    let gltf = gltf.unwrap();
+    assert_eq!(
+        warnings,
+        [
+            "Missing mesh 3ddata/test/quad.zms, using a placeholder",
+            "Missing mesh 3ddata/test/quad.zms, using a placeholder",
+            "Missing tile texture 3ddata/test/tile.png, using a placeholder",
+        ]
+    );
+    let placeholder = gltf
+        .document
+        .meshes()
+        .flat_map(|mesh| mesh.primitives())
+        .find(|primitive| {
+            primitive
+                .get(&gltf::Semantic::Positions)
+                .and_then(|accessor| accessor.name().map(|name| name.to_string()))
+                .is_some_and(|name| name.starts_with("deco_mesh_0"))
+        })
+        .unwrap();
+    assert_eq!(
+        placeholder.get(&gltf::Semantic::Positions).unwrap().count(),
+        24
+    );
+    assert_eq!(placeholder.bounding_box().min, [-0.5, -0.5, -0.5]);
+    assert_eq!(placeholder.bounding_box().max, [0.5, 0.5, 0.5]);
+}
+
+#[test]
+fn placeholder_textures() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("placeholder_textures");
+    let assets = write_test_assets(&directory).unwrap();
+    std::fs::remove_file(directory.join("3ddata/test/quad.png")).unwrap();
+    std::fs::write(directory.join("3ddata/test/tile.png"), b"not a png").unwrap();
+
+    let options = RoseGltfConvOptions {
+        placeholder_textures: true,
+        ..Default::default()
+    };
+    let (gltf, warnings) =
+        collect_warnings(|| rose_to_gltf_data(std::slice::from_ref(&assets.zon), &options));
+    let gltf = gltf.unwrap();
+    assert!(!warnings
+        .iter()
+        .any(|warning| warning.starts_with("Failed to load zone")));
+
+    let material = gltf
+        .document
+        .materials()
+        .find(|material| material.name() == Some("deco_material_0"))
+        .unwrap();
+    let extras: serde_json::Value =
+        serde_json::from_str(material.extras().as_ref().unwrap().get()).unwrap();
+    assert_eq!(extras["placeholder_texture"], "3ddata/test/quad.png");
+
+    // A checkerboard of two colors
+    let texture = material
+        .pbr_metallic_roughness()
+        .base_color_texture()
+        .unwrap()
+        .texture();
+    let image = &gltf.images[texture.source().index()];
+    assert_eq!((image.width, image.height), (64, 64));
+    let pixel = |x: usize, y: usize| {
+        let offset = (y * image.width as usize + x) * 4;
+        image.pixels[offset..offset + 4].to_vec()
+    };
+    assert_ne!(pixel(0, 0), pixel(8, 0));
+    assert_eq!(pixel(0, 0), pixel(8, 8));
+    assert_eq!(pixel(8, 0), pixel(0, 8));
+}
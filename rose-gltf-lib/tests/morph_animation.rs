@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use glam::Vec3;
+use rose_gltf_lib::{
+    bake_pose, rose_to_gltf_data,
+    test_assets::{skinned_quad_zms, two_bone_zmd, two_bone_zmo, write_test_assets},
+    RoseGltfConvOptions,
+};
+
+#[test]
+fn morph_target_animation() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("morph_target_animation");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        morph_target_animation: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(
+        &[
+            assets.zmd.clone(),
+            assets.zmo.clone(),
+            assets.skinned_zms.clone(),
+        ],
+        &options,
+    )
+    .unwrap();
+    assert_eq!(gltf.document.skins().count(), 0);
+
+    let node = gltf
+        .document
+        .nodes()
+        .find(|node| node.mesh().is_some())
+        .unwrap();
+    let mesh = node.mesh().unwrap();
+    assert_eq!(mesh.weights(), Some([0.0, 0.0].as_slice()));
+    let primitive = mesh.primitives().next().unwrap();
+    assert!(primitive.get(&gltf::Semantic::Joints(0)).is_none());
+
+    // Each target moves the vertices to where skinning puts them at its frame
+    let reader = primitive.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+    let bind_pose: Vec<Vec3> = reader.read_positions().unwrap().map(Vec3::from).collect();
+    let zms = skinned_quad_zms();
+    for (frame, (positions, normals, _)) in reader.read_morph_targets().enumerate() {
+        let baked = bake_pose(&zms, &two_bone_zmd(), &two_bone_zmo(), frame as u32).unwrap();
+        assert!(normals.is_some());
+        for ((bind, delta), vertex) in bind_pose
+            .iter()
+            .zip(positions.unwrap())
+            .zip(baked.vertices.iter())
+        {
+            let expected = Vec3::new(vertex.position.x, vertex.position.z, -vertex.position.y);
+            assert!((*bind + Vec3::from(delta)).abs_diff_eq(expected, 1.0e-5));
+        }
+    }
+
+    // One frame after another
+    let animation = gltf.document.animations().next().unwrap();
+    assert_eq!(
+        animation.name(),
+        assets.zmo.file_stem().and_then(|name| name.to_str())
+    );
+    let channel = animation.channels().next().unwrap();
+    assert_eq!(channel.target().node().index(), node.index());
+    let reader = channel.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+    let times: Vec<f32> = reader.read_inputs().unwrap().collect();
+    assert_eq!(times, [0.0, 1.0 / 30.0]);
+    match reader.read_outputs().unwrap() {
+        gltf::animation::util::ReadOutputs::MorphTargetWeights(weights) => {
+            let weights: Vec<f32> = weights.into_f32().collect();
+            assert_eq!(weights, [1.0, 0.0, 0.0, 1.0]);
+        }
+        _ => panic!("Unexpected animation channel"),
+    }
+}
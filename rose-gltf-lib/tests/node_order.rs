@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use rose_gltf_lib::{rose_to_gltf, test_assets::write_test_assets, RoseGltfConvOptions};
+
+#[test]
+fn sorted_zone_nodes() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("sorted_zone_nodes");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        sort_nodes: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let scene = gltf.document.scenes().next().unwrap();
+
+    let block = scene
+        .nodes()
+        .find(|node| node.name() == Some("32_32"))
+        .expect("Missing block node");
+    let children: Vec<_> = block.children().filter_map(|node| node.name()).collect();
+    assert_eq!(
+        children[..3],
+        ["32_32_heightmap", "32_32_cnst_0", "32_32_deco_0"]
+    );
+    assert!(scene
+        .nodes()
+        .all(|node| !node.name().is_some_and(|name| name.starts_with("32_32_"))));
+
+    // Nodes are numbered depth first
+    let mut expected_index = 0;
+    let mut stack: Vec<_> = scene.nodes().collect();
+    stack.reverse();
+    while let Some(node) = stack.pop() {
+        assert_eq!(node.index(), expected_index);
+        expected_index += 1;
+        let mut children: Vec<_> = node.children().collect();
+        children.reverse();
+        stack.extend(children);
+    }
+    assert_eq!(expected_index, gltf.document.nodes().len());
+}
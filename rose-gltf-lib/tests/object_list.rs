@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use rose_gltf_lib::{test_assets::write_test_assets, FsAssetLocator, ObjectList, ObjectPartFiles};
+
+#[test]
+fn object_list_queries() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("object_list_queries");
+    let assets = write_test_assets(&directory).unwrap();
+    let locator = FsAssetLocator::new(vec![assets.assets_path.clone()]);
+
+    let deco = ObjectList::from_path(&directory.join("3ddata/test/deco.zsc")).unwrap();
+    assert_eq!(deco.object(0).unwrap().parts.len(), 1);
+    assert!(deco.object(1).is_none());
+    assert!(deco.part(0, 1).is_none());
+    assert_eq!(
+        deco.part_material(0, 0).unwrap().path,
+        "3ddata/test/quad.png"
+    );
+
+    assert_eq!(
+        deco.part_files(0, 0, &locator).unwrap(),
+        ObjectPartFiles {
+            mesh_path: "3ddata/test/quad.zms".to_string(),
+            mesh_file: Some(assets.zms.clone()),
+            texture_path: Some("3ddata/test/quad.png".to_string()),
+            texture_file: Some(directory.join("3ddata/test/quad.png")),
+        }
+    );
+
+    let zms = deco.part_mesh(0, 0, &locator).unwrap();
+    assert_eq!(zms.vertices.len(), 4);
+    assert!(deco.part_mesh(0, 1, &locator).is_err());
+
+    // Files which are missing from the assets are not found
+    std::fs::remove_file(&assets.zms).unwrap();
+    let files = deco.part_files(0, 0, &locator).unwrap();
+    assert_eq!(files.mesh_file, None);
+    assert!(files.texture_file.is_some());
+    assert!(deco.part_mesh(0, 0, &locator).is_err());
+}
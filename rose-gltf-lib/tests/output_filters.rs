@@ -0,0 +1,121 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    gltf_to_rose, rose_to_gltf_data, test_assets::write_test_assets, GltfData, GltfRoseConvOptions,
+    GltfRoseResult, NameFilter, RoseGltfConvOptions,
+};
+
+/// A glTF of the skinned and static quads, the skeleton and its animation as
+/// "two_bone" and "run"
+fn character_gltf(name: &str) -> GltfData {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let assets = write_test_assets(&directory).unwrap();
+    let run_zmo = directory.join("run.zmo");
+    std::fs::copy(&assets.zmo, &run_zmo).unwrap();
+    rose_to_gltf_data(
+        &[
+            assets.zmd.clone(),
+            assets.skinned_zms.clone(),
+            assets.zms.clone(),
+            assets.zmo.clone(),
+            run_zmo,
+        ],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap()
+}
+
+fn names<T>(files: &[(String, T)]) -> Vec<&str> {
+    files.iter().map(|(name, _)| name.as_str()).collect()
+}
+
+fn output_names(result: &GltfRoseResult) -> [Vec<&str>; 3] {
+    [names(&result.zms), names(&result.zmd), names(&result.zmo)]
+}
+
+#[test]
+fn name_filters() {
+    let gltf = character_gltf("name_filters");
+
+    let result = gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap();
+    assert_eq!(
+        output_names(&result),
+        [
+            vec!["skinned_quad", "quad"],
+            vec!["two_bone"],
+            vec!["two_bone", "run"]
+        ]
+    );
+
+    // Excludes apply to the names which are included
+    let options = GltfRoseConvOptions {
+        mesh_filter: NameFilter {
+            include: vec!["quad".to_string()],
+            exclude: vec!["^skinned".to_string()],
+        },
+        skin_filter: NameFilter {
+            include: Vec::new(),
+            exclude: vec!["bone".to_string()],
+        },
+        animation_filter: NameFilter {
+            include: vec!["^run$".to_string()],
+            exclude: Vec::new(),
+        },
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+    assert_eq!(
+        output_names(&result),
+        [vec!["quad"], Vec::new(), vec!["run"]]
+    );
+
+    let options = GltfRoseConvOptions {
+        mesh_filter: NameFilter {
+            include: vec!["^skinned_quad$".to_string()],
+            exclude: Vec::new(),
+        },
+        skin_filter: NameFilter {
+            include: vec!["^two_bone$".to_string()],
+            exclude: Vec::new(),
+        },
+        animation_filter: NameFilter {
+            include: Vec::new(),
+            exclude: vec!["^run$".to_string()],
+        },
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+    assert_eq!(
+        output_names(&result),
+        [vec!["skinned_quad"], vec!["two_bone"], vec!["two_bone"]]
+    );
+}
+
+#[test]
+fn skip_outputs() {
+    let gltf = character_gltf("skip_outputs");
+
+    // Only the skeleton
+    let options = GltfRoseConvOptions {
+        skip_meshes: true,
+        skip_animations: true,
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+    assert_eq!(
+        output_names(&result),
+        [Vec::new(), vec!["two_bone"], Vec::new()]
+    );
+
+    // Only the animations
+    let options = GltfRoseConvOptions {
+        skip_meshes: true,
+        skip_skins: true,
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+    assert_eq!(
+        output_names(&result),
+        [Vec::new(), Vec::new(), vec!["two_bone", "run"]]
+    );
+}
@@ -0,0 +1,158 @@
+use std::path::Path;
+
+use rose_file_lib::{
+    files::{
+        zmo::{Channel, ChannelData, ChannelType},
+        zsc::{Model, ModelPart},
+        ZMO, ZSC,
+    },
+    io::RoseFile,
+    utils::{Quaternion, Vector2},
+};
+use rose_gltf_lib::{
+    gltf_to_rose, rose_to_gltf_data, test_assets::write_test_assets, GltfData, GltfRoseConvOptions,
+    RoseGltfConvOptions,
+};
+
+/// Converts the test zone with `zmo` animating the part of its building
+fn convert_animated_zone(name: &str, mut zmo: ZMO) -> GltfData {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let assets = write_test_assets(&directory).unwrap();
+
+    zmo.write_to_path(&directory.join("3ddata/test/spin.zmo"))
+        .unwrap();
+    let mut cnst = ZSC::new();
+    cnst.models.push(Some(Model {
+        parts: vec![ModelPart {
+            mesh_path: "3ddata/test/quad.zms".to_string(),
+            animation_path: Some("3ddata/test/spin.zmo".to_string()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }));
+    cnst.write_to_path(&directory.join("3ddata/test/cnst.zsc"))
+        .unwrap();
+
+    rose_to_gltf_data(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap()
+}
+
+fn rotation_channel(frames: u32) -> Channel {
+    let half_sqrt2 = std::f32::consts::FRAC_1_SQRT_2;
+    Channel {
+        typ: ChannelType::Rotation,
+        index: 0,
+        frames: ChannelData::Rotation(
+            [
+                Quaternion::IDENTITY,
+                Quaternion::new(0.0, 0.0, half_sqrt2, half_sqrt2),
+                Quaternion::new(0.0, 0.0, 1.0, 0.0),
+            ][..frames as usize]
+                .to_vec(),
+        ),
+    }
+}
+
+#[test]
+fn part_animation_round_trip() {
+    // Spin the building part of the zone around its up axis
+    let gltf = convert_animated_zone(
+        "part_animation_round_trip",
+        ZMO {
+            identifier: "ZMO0002".to_string(),
+            fps: 10,
+            frames: 3,
+            channels: vec![rotation_channel(3)],
+        },
+    );
+    let animation = gltf.document.animations().next().unwrap();
+    let animation_name = animation.name().unwrap().to_string();
+
+    let options = GltfRoseConvOptions {
+        zmo_fps: 10,
+        generate_zsc: true,
+        zsc_mesh_directory: "3ddata/custom".to_string(),
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+
+    // The part is not a joint, so its ZMO has a single channel with index 0
+    assert_eq!(result.zmo.len(), 1);
+    let (zmo_name, zmo) = &result.zmo[0];
+    assert_eq!(zmo_name, &animation_name);
+    assert_eq!(zmo.channels.len(), 1);
+    assert_eq!(zmo.channels[0].index, 0);
+    assert_eq!(zmo.channels[0].typ, ChannelType::Rotation);
+    let ChannelData::Rotation(rotations) = &zmo.channels[0].frames else {
+        panic!("Expected rotation frames");
+    };
+    assert!((rotations[1].z - std::f32::consts::FRAC_1_SQRT_2).abs() < 1.0e-4);
+
+    // The generated ZSC part references the ZMO
+    let animation_path = format!("3ddata\\custom\\{}.zmo", animation_name);
+    assert!(result
+        .zsc
+        .iter()
+        .flat_map(|(_, zsc)| zsc.models.iter().flatten())
+        .flat_map(|model| model.parts.iter())
+        .any(|part| part.animation_path.as_deref() == Some(animation_path.as_str())));
+}
+
+#[test]
+fn passthrough_channels_round_trip() {
+    let alpha = vec![1.0, 0.5, 0.0];
+    let uvs = vec![
+        Vector2::new(0.0, 0.0),
+        Vector2::new(0.25, 0.0),
+        Vector2::new(0.5, 0.0),
+    ];
+    let gltf = convert_animated_zone(
+        "passthrough_channels_round_trip",
+        ZMO {
+            identifier: "ZMO0002".to_string(),
+            fps: 10,
+            frames: 3,
+            channels: vec![
+                rotation_channel(3),
+                Channel {
+                    typ: ChannelType::Alpha,
+                    index: 0,
+                    frames: ChannelData::Alpha(alpha.clone()),
+                },
+                Channel {
+                    typ: ChannelType::UV1,
+                    index: 0,
+                    frames: ChannelData::UV1(uvs.clone()),
+                },
+            ],
+        },
+    );
+
+    let options = GltfRoseConvOptions {
+        zmo_fps: 10,
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+    assert_eq!(result.zmo.len(), 1);
+    let zmo = &result.zmo[0].1;
+    let num_frames = zmo.frames as usize;
+    assert_eq!(zmo.channels.len(), 3);
+
+    let channel = |typ| {
+        zmo.channels
+            .iter()
+            .find(|channel| channel.typ == typ)
+            .unwrap()
+    };
+    assert_eq!(
+        channel(ChannelType::Alpha).frames,
+        ChannelData::Alpha(alpha[..num_frames].to_vec())
+    );
+    assert_eq!(
+        channel(ChannelType::UV1).frames,
+        ChannelData::UV1(uvs[..num_frames].to_vec())
+    );
+}
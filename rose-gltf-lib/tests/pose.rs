@@ -0,0 +1,63 @@
+use rose_file_lib::{
+    files::{zms::VertexFormat, ZMO, ZMS},
+    utils::Vector3,
+};
+use rose_gltf_lib::{
+    bake_pose,
+    test_assets::{skinned_quad_zms, two_bone_zmd, two_bone_zmo},
+};
+
+fn positions(zms: &ZMS) -> Vec<[f32; 3]> {
+    zms.vertices
+        .iter()
+        .map(|vertex| {
+            let Vector3 { x, y, z } = vertex.position;
+            [x, y, z].map(|x| (x * 1000.0).round() / 1000.0)
+        })
+        .collect()
+}
+
+#[test]
+fn rotated_bone() {
+    let zms = skinned_quad_zms();
+    let baked = bake_pose(&zms, &two_bone_zmd(), &two_bone_zmo(), 1).unwrap();
+
+    // The root moves up 0.5, the top edge is rotated 90 degrees around Z
+    // about the child bone 1 unit above the root
+    assert_eq!(
+        positions(&baked),
+        [
+            [0.0, 0.0, 0.5],
+            [1.0, 0.0, 0.5],
+            [0.0, 2.0, 0.5],
+            [0.0, 1.0, 0.5]
+        ]
+    );
+    assert_eq!(
+        baked.format & (VertexFormat::BoneWeight as i32 | VertexFormat::BoneIndex as i32),
+        0
+    );
+    assert!(baked.bones.is_empty());
+    assert_eq!(baked.bounding_box.max.y, 2.0);
+}
+
+#[test]
+fn bind_pose_frame() {
+    let zms = skinned_quad_zms();
+    let identity = ZMO {
+        identifier: "ZMO0002".to_string(),
+        fps: 30,
+        frames: 1,
+        channels: Vec::new(),
+    };
+
+    // Frame 0 of the animation and an animation without channels are the
+    // bind pose
+    for zmo in [two_bone_zmo(), identity] {
+        let baked = bake_pose(&zms, &two_bone_zmd(), &zmo, 0).unwrap();
+        assert_eq!(positions(&baked), positions(&zms));
+        for (vertex, baked_vertex) in zms.vertices.iter().zip(baked.vertices.iter()) {
+            assert_eq!(baked_vertex.normal, vertex.normal);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::{collections::HashSet, path::Path};
+
+use rose_gltf_lib::{
+    rose_to_gltf, test_assets::write_test_assets, PostProcessOptions, RoseGltfConvOptions,
+};
+
+#[test]
+fn post_process_skinned_model() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("post_process_skinned_model");
+    let assets = write_test_assets(&directory).unwrap();
+    let input_files = [
+        assets.zmd.clone(),
+        assets.zmo.clone(),
+        assets.skinned_zms.clone(),
+    ];
+
+    let gltf = rose_to_gltf(&input_files, &RoseGltfConvOptions::default()).unwrap();
+    assert!(gltf.document.animations().len() > 0);
+    assert!(gltf.document.skins().len() > 0);
+
+    let options = RoseGltfConvOptions {
+        post_process: PostProcessOptions {
+            strip_extras: true,
+            strip_lightmapper_extras: false,
+            strip_animations: true,
+            strip_skins: true,
+            node_name_prefix: Some("npc_".to_string()),
+        },
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(&input_files, &options).unwrap();
+    let document = &gltf.document;
+    assert_eq!(document.animations().len(), 0);
+    assert_eq!(document.skins().len(), 0);
+    assert!(document.nodes().all(|node| node.skin().is_none()));
+    assert!(document
+        .nodes()
+        .filter_map(|node| node.name())
+        .all(|name| name.starts_with("npc_")));
+    assert!(document.as_json().scenes[0].extras.is_none());
+
+    // Joints, weights, inverse bind matrices and animation samplers are gone
+    let mut used_accessors = HashSet::new();
+    for primitive in document.meshes().flat_map(|mesh| mesh.primitives()) {
+        assert!(primitive.get(&gltf::Semantic::Joints(0)).is_none());
+        assert!(primitive.get(&gltf::Semantic::Weights(0)).is_none());
+        used_accessors.extend(primitive.attributes().map(|(_, accessor)| accessor.index()));
+        used_accessors.extend(primitive.indices().map(|accessor| accessor.index()));
+    }
+    assert_eq!(used_accessors.len(), document.accessors().len());
+}
+
+#[test]
+fn strip_lightmapper_extras() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("strip_lightmapper_extras");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        post_process: PostProcessOptions {
+            strip_lightmapper_extras: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(&[assets.zon], &options).unwrap();
+    let json = gltf.document.as_json();
+    let extras = json
+        .scenes
+        .iter()
+        .map(|scene| &scene.extras)
+        .chain(json.nodes.iter().map(|node| &node.extras))
+        .flatten()
+        .map(|extras| extras.get())
+        .collect::<Vec<_>>();
+    assert!(extras.iter().all(|extras| !extras.contains("TLM_")));
+    assert!(extras.iter().any(|extras| extras.contains("castShadows")));
+}
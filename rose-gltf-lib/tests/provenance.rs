@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use rose_gltf_lib::{rose_to_gltf, test_assets::write_test_assets, RoseGltfConvOptions};
+use serde_json::Value;
+
+fn extras(extras: &gltf_json::Extras) -> Value {
+    extras
+        .as_ref()
+        .map(|extras| serde_json::from_str(extras.get()).unwrap())
+        .unwrap_or_default()
+}
+
+#[test]
+fn zone_provenance() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zone_provenance");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let json = gltf.document.as_json();
+    let provenance = &extras(&json.asset.extras)["rose_provenance"];
+    assert_eq!(provenance["sources"][0], "3ddata/maps/test/test.zon");
+    assert_eq!(provenance["zone_ids"][0], 1);
+    assert_eq!(provenance["version"], env!("CARGO_PKG_VERSION"));
+
+    // Object nodes keep their rose_object extras and record their block
+    let object = json
+        .nodes
+        .iter()
+        .map(|node| extras(&node.extras))
+        .find(|extras| extras.get("rose_object").is_some())
+        .unwrap();
+    assert_eq!(object["rose_object"]["object_id"], 0);
+    assert_eq!(object["rose_source"]["block"], serde_json::json!([32, 32]));
+    assert_eq!(object["rose_source"]["file"], "3ddata/maps/test/32_32.ifo");
+    assert_eq!(object["rose_source"]["zone_id"], 1);
+
+    let options = RoseGltfConvOptions {
+        skip_provenance: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let json = gltf.document.as_json();
+    assert!(json.asset.extras.is_none());
+    assert!(json
+        .nodes
+        .iter()
+        .all(|node| extras(&node.extras).get("rose_source").is_none()));
+}
+
+#[test]
+fn asset_copyright_and_generator() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("asset_copyright_and_generator");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zms),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let asset = &gltf.document.as_json().asset;
+    assert!(asset.copyright.is_none());
+    assert_eq!(
+        asset.generator.as_deref(),
+        Some(concat!("rose-gltf ", env!("CARGO_PKG_VERSION")))
+    );
+
+    let options = RoseGltfConvOptions {
+        copyright: Some("Original assets (c) Gravity".to_string()),
+        generator: Some("asset-pipeline 2.1".to_string()),
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zms), &options).unwrap();
+    let asset = &gltf.document.as_json().asset;
+    assert_eq!(
+        asset.copyright.as_deref(),
+        Some("Original assets (c) Gravity")
+    );
+    assert_eq!(asset.generator.as_deref(), Some("asset-pipeline 2.1"));
+}
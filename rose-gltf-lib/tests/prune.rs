@@ -0,0 +1,111 @@
+use std::{collections::HashSet, path::Path};
+
+use rose_gltf_lib::{rose_to_gltf, test_assets::write_test_assets, RoseGltfConvOptions};
+
+#[test]
+fn converted_zone_has_no_unused_objects() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("prune_unused");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let document = &gltf.document;
+    let primitives = document
+        .meshes()
+        .flat_map(|mesh| mesh.primitives())
+        .collect::<Vec<_>>();
+
+    let mut materials = HashSet::new();
+    let mut accessors = HashSet::new();
+    for primitive in primitives.iter() {
+        materials.extend(primitive.material().index());
+        accessors.extend(primitive.attributes().map(|(_, accessor)| accessor.index()));
+        accessors.extend(primitive.indices().map(|accessor| accessor.index()));
+    }
+    for primitive in document
+        .as_json()
+        .meshes
+        .iter()
+        .flat_map(|mesh| &mesh.primitives)
+    {
+        let variants = primitive
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.khr_materials_variants.as_ref());
+        for mapping in variants.iter().flat_map(|variants| &variants.mappings) {
+            materials.insert(mapping.material as usize);
+        }
+    }
+    assert_eq!(materials.len(), document.materials().len());
+    assert_eq!(accessors.len(), document.accessors().len());
+
+    let textures = document
+        .materials()
+        .flat_map(|material| {
+            let pbr = material.pbr_metallic_roughness();
+            [
+                pbr.base_color_texture().map(|info| info.texture().index()),
+                material
+                    .occlusion_texture()
+                    .map(|info| info.texture().index()),
+            ]
+        })
+        .flatten()
+        .collect::<HashSet<_>>();
+    assert_eq!(textures.len(), document.textures().len());
+
+    let images = document
+        .textures()
+        .map(|texture| texture.source().index())
+        .collect::<HashSet<_>>();
+    assert_eq!(images.len(), document.images().len());
+
+    // The buffer only contains the buffer views which are left
+    let mut views = HashSet::new();
+    views.extend(
+        document
+            .accessors()
+            .filter_map(|accessor| accessor.view().map(|view| view.index())),
+    );
+    views.extend(document.images().filter_map(|image| match image.source() {
+        gltf::image::Source::View { view, .. } => Some(view.index()),
+        gltf::image::Source::Uri { .. } => None,
+    }));
+    assert_eq!(views.len(), document.views().len());
+    let view_bytes = document
+        .views()
+        .map(|view| view.length().next_multiple_of(4))
+        .sum::<usize>();
+    assert_eq!(gltf.blob.as_ref().unwrap().len(), view_bytes);
+}
+
+#[test]
+fn duplicate_materials_are_shared() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("duplicate_materials_are_shared");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // The deco and cnst objects use the same mesh and material
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let material_of = |name: &str| {
+        gltf.document
+            .nodes()
+            .find(|node| node.name() == Some(name))
+            .and_then(|node| node.mesh())
+            .and_then(|mesh| mesh.primitives().next())
+            .and_then(|primitive| primitive.material().index())
+            .unwrap()
+    };
+    assert_eq!(material_of("32_32_deco_0_0"), material_of("32_32_cnst_0_0"));
+
+    // The terrain, water and shared object material
+    assert_eq!(gltf.document.materials().len(), 3);
+    assert_eq!(gltf.document.images().len(), 2);
+    assert_eq!(gltf.document.samplers().len(), 1);
+}
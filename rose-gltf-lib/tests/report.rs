@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    collect_timings, collect_warnings, rose_to_gltf, test_assets::write_test_assets,
+    RoseGltfConvOptions, TerrainLightmap,
+};
+
+#[test]
+fn collects_conversion_warnings() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("collects_conversion_warnings");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // The test zone has no lightmaps
+    let options = RoseGltfConvOptions {
+        terrain_lightmap: TerrainLightmap::VertexColor,
+        ..Default::default()
+    };
+    let (gltf, warnings) =
+        collect_warnings(|| rose_to_gltf(std::slice::from_ref(&assets.zon), &options));
+    assert!(gltf.is_ok());
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("Failed to load lightmap"));
+
+    // Warnings are only collected inside collect_warnings
+    let (_, warnings) = collect_warnings(|| ());
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn collects_phase_timings() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("collects_phase_timings");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let (gltf, timings) = collect_timings(|| {
+        rose_to_gltf(
+            std::slice::from_ref(&assets.zon),
+            &RoseGltfConvOptions::default(),
+        )
+    });
+    assert!(gltf.is_ok());
+    for phase in ["terrain_mesh", "terrain_materials", "mesh", "finish"] {
+        let timing = timings
+            .get(phase)
+            .unwrap_or_else(|| panic!("Missing phase {}", phase));
+        assert!(timing.count > 0);
+        assert!(timing.seconds >= 0.0);
+    }
+    assert_eq!(timings["finish"].count, 1);
+
+    // Phases are only timed inside collect_timings
+    let (_, timings) = collect_timings(|| ());
+    assert!(timings.is_empty());
+}
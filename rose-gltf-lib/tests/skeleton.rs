@@ -0,0 +1,500 @@
+use std::path::Path;
+
+use rose_file_lib::{
+    files::{
+        chr::{CharacterModel, CharacterMotionType},
+        zmd::Bone,
+        zms::Vertex,
+        CHR, ZMS,
+    },
+    io::RoseFile,
+    utils::{Quaternion, Vector3, Vector4},
+};
+use rose_gltf_lib::{
+    collect_warnings, gltf_to_rose, rose_to_gltf_data,
+    test_assets::{skinned_quad_zms, two_bone_zmd, write_test_assets},
+    BoneNaming, GltfData, GltfRoseConvOptions, RoseGltfConvOptions,
+};
+
+fn joint_names(gltf: &GltfData) -> Vec<String> {
+    gltf.document
+        .skins()
+        .next()
+        .unwrap()
+        .joints()
+        .map(|joint| joint.name().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn indexed_bone_names() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("indexed_bone_names");
+    let assets = write_test_assets(&directory).unwrap();
+    let input_files = [assets.zmd.clone(), assets.skinned_zms.clone()];
+
+    let gltf = rose_to_gltf_data(&input_files, &RoseGltfConvOptions::default()).unwrap();
+    assert_eq!(joint_names(&gltf), ["b1_root", "b1_top"]);
+
+    let options = RoseGltfConvOptions {
+        bone_naming: BoneNaming::Indexed,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(&input_files, &options).unwrap();
+    assert_eq!(joint_names(&gltf), ["two_bone_Bone_0", "two_bone_Bone_1"]);
+}
+
+#[test]
+fn blender_renamed_nodes() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("blender_renamed_nodes");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let mut zmd = two_bone_zmd();
+    zmd.dummy_bones.push(Bone {
+        parent: 1,
+        name: "weapon".to_string(),
+        position: Vector3::new(0.0, 0.0, 50.0),
+        rotation: Quaternion::IDENTITY,
+    });
+    zmd.write_to_path(&assets.zmd).unwrap();
+
+    let gltf = rose_to_gltf_data(
+        &[assets.zmd.clone(), assets.skinned_zms.clone()],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    // Rename nodes the way Blender does when importing next to existing objects
+    let mut json = gltf.document.into_json();
+    for node in json.nodes.iter_mut() {
+        let Some(name) = node.name.as_mut() else {
+            continue;
+        };
+        if name == "dummy_0_weapon" {
+            *name = "Dummy_0_weapon".to_string();
+        }
+        name.push_str(".001");
+    }
+    let gltf = GltfData {
+        document: gltf::Document::from_json(json).unwrap(),
+        buffers: gltf.buffers,
+        images: gltf.images,
+    };
+
+    let result = gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap();
+    let zmd = &result.zmd[0].1;
+    let bone_names: Vec<&str> = zmd.bones.iter().map(|bone| bone.name.as_str()).collect();
+    assert_eq!(bone_names, ["b1_root", "b1_top"]);
+    assert_eq!(zmd.dummy_bones.len(), 1);
+    assert_eq!(zmd.dummy_bones[0].name, "Dummy_0_weapon");
+    assert_eq!(zmd.dummy_bones[0].parent, 1);
+}
+
+#[test]
+fn duplicate_bone_names() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("duplicate_bone_names");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let mut zmd = two_bone_zmd();
+    zmd.bones[1].name = "b1_root".to_string();
+    zmd.bones.push(Bone {
+        parent: 1,
+        name: String::new(),
+        position: Vector3::new(0.0, 100.0, 0.0),
+        rotation: Quaternion::IDENTITY,
+    });
+    zmd.write_to_path(&assets.zmd).unwrap();
+
+    let gltf = rose_to_gltf_data(
+        std::slice::from_ref(&assets.zmd),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(joint_names(&gltf), ["b1_root", "b1_root_1", "bone_2"]);
+}
+
+#[test]
+fn bind_pose_animation() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bind_pose_animation");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        bind_pose_animation: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(std::slice::from_ref(&assets.zmd), &options).unwrap();
+
+    let animation = gltf.document.animations().next().unwrap();
+    assert_eq!(animation.name(), Some("bind_pose"));
+    let joints: Vec<_> = gltf.document.skins().next().unwrap().joints().collect();
+    assert_eq!(animation.channels().count(), joints.len() * 2);
+    for channel in animation.channels() {
+        let reader = channel.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+        let times: Vec<f32> = reader.read_inputs().unwrap().collect();
+        assert_eq!(times, [0.0]);
+
+        // Each channel keeps its joint at the rest transform
+        let (translation, rotation, _) = channel.target().node().transform().decomposed();
+        match reader.read_outputs().unwrap() {
+            gltf::animation::util::ReadOutputs::Translations(mut translations) => {
+                assert_eq!(translations.next(), Some(translation));
+            }
+            gltf::animation::util::ReadOutputs::Rotations(rotations) => {
+                assert_eq!(rotations.into_f32().next(), Some(rotation));
+            }
+            _ => panic!("Unexpected animation channel"),
+        }
+    }
+}
+
+fn skin_name(input_files: &[std::path::PathBuf]) -> Option<String> {
+    let gltf = rose_to_gltf_data(input_files, &RoseGltfConvOptions::default()).unwrap();
+    let skin = gltf.document.skins().next()?;
+    Some(skin.name()?.to_string())
+}
+
+#[test]
+fn auto_skeleton() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("auto_skeleton");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // The only ZMD in the same directory
+    assert_eq!(
+        skin_name(&[assets.zmo.clone(), assets.skinned_zms.clone()]).as_deref(),
+        Some("two_bone")
+    );
+    assert_eq!(skin_name(std::slice::from_ref(&assets.zms)), None);
+
+    // Avatar skeletons chosen by the gender in the file name
+    for subdirectory in ["3ddata/avatar/body", "3ddata/motion/avatar"] {
+        std::fs::create_dir_all(directory.join(subdirectory)).unwrap();
+    }
+    std::fs::copy(&assets.zmd, directory.join("3ddata/avatar/male.zmd")).unwrap();
+    std::fs::copy(&assets.zmd, directory.join("3ddata/avatar/female.zmd")).unwrap();
+    let body = directory.join("3ddata/avatar/body/body2_00100.zms");
+    std::fs::copy(&assets.skinned_zms, &body).unwrap();
+    assert_eq!(skin_name(&[body]).as_deref(), Some("female"));
+    let motion = directory.join("3ddata/motion/avatar/empty_run_m1.zmo");
+    std::fs::copy(&assets.zmo, &motion).unwrap();
+    assert_eq!(skin_name(&[motion]).as_deref(), Some("male"));
+
+    // NPC motions looked up in list_npc.chr
+    for subdirectory in ["3ddata/npc/jelly", "3ddata/motion/npc"] {
+        std::fs::create_dir_all(directory.join(subdirectory)).unwrap();
+    }
+    std::fs::copy(&assets.zmd, directory.join("3ddata/npc/jelly/jelly.zmd")).unwrap();
+    let motion = directory.join("3ddata/motion/npc/jelly_walk.zmo");
+    std::fs::copy(&assets.zmo, &motion).unwrap();
+    let mut character = CharacterModel {
+        name: "jelly".to_string(),
+        skeleton_path: "3DDATA\\NPC\\JELLY\\JELLY.ZMD".to_string(),
+        ..Default::default()
+    };
+    character.motions.insert(
+        CharacterMotionType::Move,
+        "3DDATA\\MOTION\\NPC\\JELLY_WALK.ZMO".to_string(),
+    );
+    CHR {
+        models: vec![None, Some(character)],
+    }
+    .write_to_path(&directory.join("3ddata/npc/list_npc.chr"))
+    .unwrap();
+    assert_eq!(skin_name(&[motion]).as_deref(), Some("jelly"));
+}
+
+#[test]
+fn bone_partitions() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bone_partitions");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // 20 triangles whose vertices are each bound to a different bone of a
+    // 60 bone skeleton
+    let mut zmd = two_bone_zmd();
+    zmd.bones = (0..60)
+        .map(|bone| Bone {
+            parent: 0,
+            name: format!("bone_{}", bone),
+            position: Vector3::new(0.0, 0.0, bone as f32),
+            rotation: Quaternion::IDENTITY,
+        })
+        .collect();
+    zmd.write_to_path(&assets.zmd).unwrap();
+
+    let mut zms = skinned_quad_zms();
+    zms.bones = (0..60).collect();
+    zms.vertices.clear();
+    zms.indices.clear();
+    for triangle in 0..20i16 {
+        for (corner, (x, y)) in [(0.0, 0.0), (0.5, 0.0), (0.0, 1.0)].into_iter().enumerate() {
+            zms.vertices.push(Vertex {
+                position: Vector3::new(triangle as f32 + x, y, 0.0),
+                normal: Vector3::new(0.0, 0.0, 1.0),
+                bone_weights: Vector4::new(1.0, 0.0, 0.0, 0.0),
+                bone_indices: Vector4::new(triangle * 3 + corner as i16, 0, 0, 0),
+                ..Default::default()
+            });
+        }
+        zms.indices.push(Vector3::new(
+            triangle * 3,
+            triangle * 3 + 1,
+            triangle * 3 + 2,
+        ));
+    }
+    zms.write_to_path(&assets.skinned_zms).unwrap();
+
+    let gltf = rose_to_gltf_data(
+        &[assets.zmd.clone(), assets.skinned_zms.clone()],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let result = gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap();
+
+    // 16 triangles fill the first mesh with 48 bones, the rest go in a second
+    assert_eq!(result.zms.len(), 2);
+    for (name, partition) in result.zms.iter() {
+        assert!(partition.bones.len() <= 48, "{}", name);
+        for vertex in partition.vertices.iter() {
+            assert!((vertex.bone_indices.x as usize) < partition.bones.len());
+        }
+    }
+    assert_eq!(result.zms[0].1.indices.len(), 16);
+    assert_eq!(result.zms[1].1.indices.len(), 4);
+
+    // Each triangle keeps its vertex positions and the bones they are bound to
+    let triangles = |zms: &ZMS, bones: &[i16]| -> Vec<Vec<([i32; 3], i16)>> {
+        zms.indices
+            .iter()
+            .map(|triangle| {
+                [triangle.x, triangle.y, triangle.z]
+                    .map(|index| {
+                        let vertex = &zms.vertices[index as usize];
+                        let position = vertex.position;
+                        (
+                            [position.x, position.y, position.z]
+                                .map(|x| (x * 100.0).round() as i32),
+                            bones[vertex.bone_indices.x as usize],
+                        )
+                    })
+                    .to_vec()
+            })
+            .collect()
+    };
+    let converted: Vec<_> = result
+        .zms
+        .iter()
+        .flat_map(|(_, partition)| triangles(partition, &partition.bones))
+        .collect();
+    assert_eq!(converted, triangles(&zms, &zms.bones));
+}
+
+#[test]
+fn bones_from_inverse_bind_matrices() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bones_from_inverse_bind_matrices");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let mut zmd = two_bone_zmd();
+    let half_angle = std::f32::consts::FRAC_PI_6 / 2.0;
+    zmd.bones[0].position = Vector3::new(10.0, -20.0, 30.0);
+    zmd.bones[1].position = Vector3::new(10.0, 100.0, 20.0);
+    zmd.bones[1].rotation = Quaternion::new(half_angle.sin(), 0.0, 0.0, half_angle.cos());
+    zmd.write_to_path(&assets.zmd).unwrap();
+    let gltf = rose_to_gltf_data(
+        &[assets.zmd.clone(), assets.skinned_zms.clone()],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    // Pose the child joint, the bind pose is only left in the inverse bind
+    // matrices
+    let mut root = gltf.document.into_json();
+    let top = root
+        .nodes
+        .iter_mut()
+        .find(|node| node.name.as_deref() == Some("b1_top"))
+        .unwrap();
+    top.translation = Some([0.0, 0.0, 0.0]);
+    top.rotation = None;
+    let gltf = GltfData {
+        document: gltf::Document::from_json(root).unwrap(),
+        buffers: gltf.buffers,
+        images: gltf.images,
+    };
+
+    let (result, warnings) =
+        collect_warnings(|| gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap());
+    assert!(
+        warnings
+            .iter()
+            .any(|warning| warning.contains("1 joint node transforms differ")),
+        "{:?}",
+        warnings
+    );
+    let (_, converted) = &result.zmd[0];
+    for (bone, original) in converted.bones.iter().zip(zmd.bones.iter()) {
+        assert_eq!(bone.parent, original.parent);
+        let position = [bone.position.x, bone.position.y, bone.position.z];
+        let original_position = [
+            original.position.x,
+            original.position.y,
+            original.position.z,
+        ];
+        for (x, original) in position.into_iter().zip(original_position) {
+            assert!((x - original).abs() < 1.0e-3, "{:?}", position);
+        }
+        let rotation = [
+            bone.rotation.x,
+            bone.rotation.y,
+            bone.rotation.z,
+            bone.rotation.w,
+        ];
+        let original_rotation = [
+            original.rotation.x,
+            original.rotation.y,
+            original.rotation.z,
+            original.rotation.w,
+        ];
+        for (x, original) in rotation.into_iter().zip(original_rotation) {
+            assert!((x - original).abs() < 1.0e-4, "{:?}", rotation);
+        }
+    }
+}
+
+#[test]
+fn mismatched_inverse_bind_matrices() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("mismatched_inverse_bind_matrices");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // Bone 2 is a child of bone 1, which is a second root that the inverse
+    // bind matrices are not calculated from
+    let mut zmd = two_bone_zmd();
+    zmd.bones[1].parent = 1;
+    zmd.bones.push(Bone {
+        parent: 1,
+        name: "b1_tip".to_string(),
+        position: Vector3::new(0.0, 100.0, 0.0),
+        rotation: Quaternion::IDENTITY,
+    });
+    zmd.write_to_path(&assets.zmd).unwrap();
+
+    let (_, warnings) = collect_warnings(|| {
+        rose_to_gltf_data(
+            &[assets.zmd.clone(), assets.skinned_zms.clone()],
+            &RoseGltfConvOptions::default(),
+        )
+        .unwrap()
+    });
+    assert!(
+        warnings.iter().any(|warning| warning
+            == "Skeleton two_bone: 1 inverse bind matrices do not match the exported joint hierarchy"),
+        "{:?}",
+        warnings
+    );
+}
+
+#[test]
+fn influence_pruning() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("influence_pruning");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let mut zmd = two_bone_zmd();
+    for bone in 2..6 {
+        zmd.bones.push(Bone {
+            parent: 0,
+            name: format!("b1_extra_{}", bone),
+            position: Vector3::new(0.0, 0.0, 10.0 * bone as f32),
+            rotation: Quaternion::IDENTITY,
+        });
+    }
+    zmd.write_to_path(&assets.zmd).unwrap();
+    let gltf = rose_to_gltf_data(
+        &[assets.zmd.clone(), assets.skinned_zms.clone()],
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+
+    // Every vertex gets 6 influences over JOINTS_0 / JOINTS_1, in a second
+    // buffer
+    let joints: [[u16; 4]; 2] = [[0, 1, 2, 3], [4, 5, 0, 0]];
+    let weights: [[f32; 4]; 2] = [[0.05, 0.3, 0.12, 0.2], [0.25, 0.08, 0.0, 0.0]];
+    let mut data = Vec::new();
+    for set in 0..2 {
+        for _ in 0..4 {
+            data.extend(joints[set].iter().flat_map(|joint| joint.to_le_bytes()));
+        }
+        for _ in 0..4 {
+            data.extend(weights[set].iter().flat_map(|weight| weight.to_le_bytes()));
+        }
+    }
+
+    let mut root = gltf.document.into_json();
+    root.buffers
+        .push(serde_json::from_value(serde_json::json!({ "byteLength": data.len() })).unwrap());
+    let primitive = &mut root.meshes[0].primitives[0];
+    for (set, offset) in [(0, 0), (1, 96)] {
+        for (semantic, component_type, offset, length) in [
+            ("JOINTS", 5123, offset, 32),
+            ("WEIGHTS", 5126, offset + 32, 64),
+        ] {
+            root.buffer_views.push(
+                serde_json::from_value(serde_json::json!({
+                    "buffer": 1,
+                    "byteOffset": offset,
+                    "byteLength": length,
+                }))
+                .unwrap(),
+            );
+            root.accessors.push(
+                serde_json::from_value(serde_json::json!({
+                    "bufferView": root.buffer_views.len() - 1,
+                    "componentType": component_type,
+                    "count": 4,
+                    "type": "VEC4",
+                }))
+                .unwrap(),
+            );
+            let semantic =
+                serde_json::from_value(serde_json::json!(format!("{}_{}", semantic, set))).unwrap();
+            primitive.attributes.insert(
+                semantic,
+                gltf_json::Index::new(root.accessors.len() as u32 - 1),
+            );
+        }
+    }
+    let mut buffers = gltf.buffers;
+    buffers.push(gltf::buffer::Data(data));
+    let gltf = GltfData {
+        document: gltf::Document::from_json(root).unwrap(),
+        buffers,
+        images: gltf.images,
+    };
+
+    let (result, warnings) =
+        collect_warnings(|| gltf_to_rose(&gltf, &GltfRoseConvOptions::default()).unwrap());
+    assert!(
+        warnings.iter().any(|warning| warning.ends_with(
+            "pruned bone influences beyond 4 from 4 vertices (max pruned weight 0.130, average 0.130)"
+        )),
+        "{:?}",
+        warnings
+    );
+
+    // The 4 largest weights are kept, scaled to add up to 1
+    let (_, zms) = &result.zms[0];
+    for vertex in zms.vertices.iter() {
+        let influences: Vec<(i16, f32)> = [
+            (vertex.bone_indices.x, vertex.bone_weights.x),
+            (vertex.bone_indices.y, vertex.bone_weights.y),
+            (vertex.bone_indices.z, vertex.bone_weights.z),
+            (vertex.bone_indices.w, vertex.bone_weights.w),
+        ]
+        .into_iter()
+        .map(|(index, weight)| (zms.bones[index as usize], weight))
+        .collect();
+        let expected = [(1, 0.3), (4, 0.25), (3, 0.2), (2, 0.12)];
+        for ((bone, weight), (expected_bone, expected_weight)) in
+            influences.into_iter().zip(expected)
+        {
+            assert_eq!(bone, expected_bone);
+            assert!((weight - expected_weight / 0.87).abs() < 1.0e-5);
+        }
+    }
+}
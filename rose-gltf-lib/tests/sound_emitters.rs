@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    collect_warnings,
+    rose_file_lib::{
+        files::{
+            ifo::{ObjectData, SoundData},
+            IFO,
+        },
+        io::RoseFile,
+        utils::{Quaternion, Vector3},
+    },
+    rose_to_gltf,
+    test_assets::write_test_assets,
+    RoseGltfConvOptions,
+};
+
+#[test]
+fn sound_emitters() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("sound_emitters");
+    let assets = write_test_assets(&directory).unwrap();
+    std::fs::create_dir_all(assets.assets_path.join("sound/field")).unwrap();
+    std::fs::write(assets.assets_path.join("sound/field/wind.wav"), b"RIFF").unwrap();
+
+    let ifo_path = assets.zon.parent().unwrap().join("32_32.ifo");
+    let mut ifo = IFO::from_path(&ifo_path).unwrap();
+    ifo.sounds.push(SoundData {
+        data: ObjectData {
+            position: Vector3::new(1000.0, -2000.0, 500.0),
+            rotation: Quaternion::IDENTITY,
+            scale: Vector3::new(2.0, 3.0, 4.0),
+            ..Default::default()
+        },
+        file: "sound/field/wind.wav".to_string(),
+        range: 1500,
+        interval: 20,
+    });
+    ifo.sounds.push(SoundData {
+        data: ObjectData {
+            position: Vector3::new(3000.0, -1000.0, 0.0),
+            rotation: Quaternion::IDENTITY,
+            scale: Vector3::ONE,
+            ..Default::default()
+        },
+        file: "sound/field/birds.wav".to_string(),
+        range: 800,
+        interval: 0,
+    });
+    ifo.write_to_path(&ifo_path).unwrap();
+
+    let convert = |resolve_sound_paths| {
+        let options = RoseGltfConvOptions {
+            resolve_sound_paths,
+            ..Default::default()
+        };
+        collect_warnings(|| rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap())
+    };
+    let sound_extras = |gltf: &gltf::Gltf, name| {
+        let node = gltf
+            .document
+            .nodes()
+            .find(|node| node.name() == Some(name))
+            .expect("Missing sound emitter node");
+        assert!(gltf
+            .document
+            .scenes()
+            .next()
+            .unwrap()
+            .nodes()
+            .any(|root| root.index() == node.index()));
+        let extras: serde_json::Value =
+            serde_json::from_str(node.extras().as_ref().unwrap().get()).unwrap();
+        (node.transform().decomposed(), extras["rose_sound"].clone())
+    };
+
+    let (gltf, warnings) = convert(false);
+    assert!(warnings.is_empty(), "{:?}", warnings);
+
+    let ((translation, rotation, scale), wind) = sound_extras(&gltf, "32_32_sound_0");
+    assert_eq!(translation, [10.0, 5.0, 20.0]);
+    assert_eq!(rotation, [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(scale, [2.0, 4.0, 3.0]);
+    assert_eq!(
+        wind,
+        serde_json::json!({
+            "path": "sound/field/wind.wav",
+            "range": 1500,
+            "interval": 20,
+        })
+    );
+
+    let ((translation, _, scale), birds) = sound_extras(&gltf, "32_32_sound_1");
+    assert_eq!(translation, [30.0, 0.0, 10.0]);
+    assert_eq!(scale, [1.0, 1.0, 1.0]);
+    assert_eq!(birds["path"], "sound/field/birds.wav");
+    assert_eq!(birds["range"], 800);
+    assert_eq!(birds["interval"], 0);
+    assert!(birds.get("file").is_none());
+
+    // Sound files are located in the assets when resolving paths
+    let (gltf, warnings) = convert(true);
+    let (_, wind) = sound_extras(&gltf, "32_32_sound_0");
+    let file = wind["file"].as_str().unwrap();
+    assert!(
+        Path::new(file).ends_with("sound/field/wind.wav"),
+        "{}",
+        file
+    );
+    let (_, birds) = sound_extras(&gltf, "32_32_sound_1");
+    assert!(birds.get("file").is_none());
+    assert_eq!(warnings, ["Missing sound file sound/field/birds.wav"]);
+}
@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_file_lib::{
+        files::{
+            ifo::{MonsterSpawn, MonsterSpawnPoint, ObjectData},
+            IFO,
+        },
+        io::RoseFile,
+        utils::{Quaternion, Vector3},
+    },
+    rose_to_gltf,
+    test_assets::write_test_assets,
+    RoseGltfConvOptions, SpawnVolumeShape,
+};
+
+#[test]
+fn spawn_volumes() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("spawn_volumes");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let ifo_path = assets.zon.parent().unwrap().join("32_32.ifo");
+    let mut ifo = IFO::from_path(&ifo_path).unwrap();
+    ifo.monster_spawns.push(MonsterSpawn {
+        data: ObjectData {
+            position: Vector3::new(1000.0, -2000.0, 500.0),
+            rotation: Quaternion::IDENTITY,
+            scale: Vector3::ONE,
+            ..Default::default()
+        },
+        name: "jellies".to_string(),
+        basic_spawns: vec![MonsterSpawnPoint {
+            name: "jelly".to_string(),
+            monster: 101,
+            count: 3,
+        }],
+        interval: 30,
+        limit: 10,
+        range: 20,
+        ..Default::default()
+    });
+    ifo.write_to_path(&ifo_path).unwrap();
+
+    let convert = |spawn_volumes| {
+        let options = RoseGltfConvOptions {
+            spawn_volumes,
+            ..Default::default()
+        };
+        rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap()
+    };
+
+    let gltf = convert(None);
+    assert!(gltf
+        .document
+        .nodes()
+        .all(|node| node.name() != Some("32_32_spawn_0")));
+
+    for (shape, vertex_count) in [
+        (SpawnVolumeShape::Cylinder, 32 * 2 + 33 * 2),
+        (SpawnVolumeShape::Box, 6 * 4),
+    ] {
+        let gltf = convert(Some(shape));
+        let node = gltf
+            .document
+            .nodes()
+            .find(|node| node.name() == Some("32_32_spawn_0"))
+            .expect("Missing spawn volume node");
+        let (translation, _, scale) = node.transform().decomposed();
+        assert_eq!(translation, [10.0, 5.0, 20.0]);
+        assert_eq!(scale, [20.0, 1.0, 20.0]);
+
+        let extras: serde_json::Value =
+            serde_json::from_str(node.extras().as_ref().unwrap().get()).unwrap();
+        let spawn = &extras["rose_spawn"];
+        assert_eq!(spawn["name"], "jellies");
+        assert_eq!(spawn["basic"][0]["monster"], 101);
+        assert_eq!(spawn["basic"][0]["count"], 3);
+        assert_eq!(spawn["tactical"], serde_json::json!([]));
+        assert_eq!(spawn["limit"], 10);
+
+        let primitive = node.mesh().unwrap().primitives().next().unwrap();
+        let positions = primitive.get(&gltf::Semantic::Positions).unwrap();
+        assert_eq!(positions.count(), vertex_count);
+        assert_eq!(
+            primitive.material().alpha_mode(),
+            gltf::material::AlphaMode::Blend
+        );
+    }
+}
@@ -0,0 +1,543 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    gltf_to_rose,
+    rose_file_lib::{
+        files::{HIM, TIL, ZON},
+        io::RoseFile,
+    },
+    rose_to_gltf, rose_to_gltf_data,
+    test_assets::write_test_assets,
+    EmbeddedImageOptions, GltfRoseConvOptions, HeightRange, MaterialVariant, MaterialVariantSource,
+    RadiusFilter, RoseGltfConvOptions, TerrainLightmap, TerrainUvOptions, VertexBufferLayout,
+    ZoneContent, ZoneContentFilter,
+};
+
+fn heightmap_index_count(gltf: &gltf::Gltf) -> Option<usize> {
+    gltf.document
+        .nodes()
+        .find(|node| node.name() == Some("32_32_heightmap"))
+        .map(|node| {
+            let primitive = node.mesh().unwrap().primitives().next().unwrap();
+            primitive.indices().unwrap().count()
+        })
+}
+
+#[test]
+fn terrain_holes() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("terrain_holes");
+    let assets = write_test_assets(&directory).unwrap();
+    let convert = || {
+        rose_to_gltf(
+            std::slice::from_ref(&assets.zon),
+            &RoseGltfConvOptions::default(),
+        )
+        .unwrap()
+    };
+
+    // Each tile is 4x4 quads of 2 triangles
+    let tile_indices = 4 * 4 * 2 * 3;
+    assert_eq!(
+        heightmap_index_count(&convert()),
+        Some(16 * 16 * tile_indices)
+    );
+
+    // Cut a hole of two tiles
+    let til_path = assets.zon.parent().unwrap().join("32_32.til");
+    let mut til = TIL::from_path(&til_path).unwrap();
+    til.tiles[3][5].tile_id = -1;
+    til.tiles[8][2].tile_id = -1;
+    til.write_to_path(&til_path).unwrap();
+    assert_eq!(
+        heightmap_index_count(&convert()),
+        Some((16 * 16 - 2) * tile_indices)
+    );
+
+    // A block which is entirely holes has no terrain
+    for row in til.tiles.iter_mut() {
+        for tile in row.iter_mut() {
+            tile.tile_id = -1;
+        }
+    }
+    til.write_to_path(&til_path).unwrap();
+    assert_eq!(heightmap_index_count(&convert()), None);
+}
+
+#[test]
+fn radius_filter() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("radius_filter");
+    let assets = write_test_assets(&directory).unwrap();
+    let convert = |center, radius| {
+        let options = RoseGltfConvOptions {
+            radius_filter: Some(RadiusFilter { center, radius }),
+            ..Default::default()
+        };
+        rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap()
+    };
+    let has_node = |gltf: &gltf::Gltf, name: &str| {
+        gltf.document.nodes().any(|node| {
+            node.name()
+                .is_some_and(|node_name| node_name.starts_with(name))
+        })
+    };
+
+    // Block 32_32 spans 5120 to 5280 meters, 5200,5200 is a corner of four of
+    // its tiles and 32 tiles are within 25 meters of it
+    let gltf = convert([5200.0, 5200.0], 25.0);
+    assert_eq!(heightmap_index_count(&gltf), Some(32 * 4 * 4 * 2 * 3));
+    assert!(!has_node(&gltf, "32_32_deco_0"));
+    assert!(!has_node(&gltf, "32_32_cnst_0"));
+    assert!(!has_node(&gltf, "32_32_ocean"));
+
+    // The objects of the test zone are placed outside the terrain of their
+    // block, which is kept for them without its terrain
+    let gltf = convert([10360.0, 10440.0], 10.0);
+    assert_eq!(heightmap_index_count(&gltf), None);
+    assert!(has_node(&gltf, "32_32_deco_0"));
+    assert!(!has_node(&gltf, "32_32_cnst_0"));
+
+    // Nothing within the radius
+    let gltf = convert([0.0, 0.0], 10.0);
+    assert_eq!(heightmap_index_count(&gltf), None);
+    assert!(!has_node(&gltf, "32_32"));
+}
+
+#[test]
+fn height_range() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("height_range");
+    let assets = write_test_assets(&directory).unwrap();
+    let object_names = |min, max| {
+        let options = RoseGltfConvOptions {
+            height_range: HeightRange { min, max },
+            ..Default::default()
+        };
+        let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+        assert!(heightmap_index_count(&gltf).is_some());
+        gltf.document
+            .scenes()
+            .flat_map(|scene| scene.nodes())
+            .filter_map(|node| node.name().map(str::to_string))
+            .filter(|name| name.contains("_deco_") || name.contains("_cnst_"))
+            .collect::<Vec<_>>()
+    };
+
+    // The objects of the test zone are at a height of 0, the terrain is kept
+    // whatever the range
+    assert_eq!(object_names(None, None).len(), 2);
+    assert_eq!(object_names(Some(-1.0), Some(0.0)).len(), 2);
+    assert!(object_names(Some(1.0), None).is_empty());
+    assert!(object_names(None, Some(-1.0)).is_empty());
+}
+
+#[test]
+fn zone_content_filter() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zone_content_filter");
+    let assets = write_test_assets(&directory).unwrap();
+    let convert = |include, exclude| {
+        let options = RoseGltfConvOptions {
+            zone_content: ZoneContentFilter { include, exclude },
+            ..Default::default()
+        };
+        rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap()
+    };
+    let has_node = |gltf: &gltf::Gltf, name: &str| {
+        gltf.document.nodes().any(|node| {
+            node.name()
+                .is_some_and(|node_name| node_name.starts_with(name))
+        })
+    };
+    let has_lights = |gltf: &gltf::Gltf| {
+        gltf.document
+            .extensions_used()
+            .any(|extension| extension == "KHR_lights_punctual")
+    };
+
+    // Only the buildings layer
+    let gltf = convert(vec![ZoneContent::Cnst], Vec::new());
+    assert_eq!(heightmap_index_count(&gltf), None);
+    assert!(gltf
+        .document
+        .materials()
+        .all(|material| material.name() != Some("32_32_tilemap_material")));
+    assert!(has_node(&gltf, "32_32_cnst_0"));
+    assert!(!has_node(&gltf, "32_32_deco_0"));
+    assert!(!has_node(&gltf, "32_32_ocean"));
+    assert!(!has_lights(&gltf));
+
+    let gltf = convert(
+        Vec::new(),
+        vec![ZoneContent::Deco, ZoneContent::Ocean, ZoneContent::Lights],
+    );
+    assert!(heightmap_index_count(&gltf).is_some());
+    assert!(has_node(&gltf, "32_32_cnst_0"));
+    assert!(!has_node(&gltf, "32_32_deco_0"));
+    assert!(!has_node(&gltf, "32_32_ocean"));
+    assert!(!has_lights(&gltf));
+
+    // The sun is one of the lights
+    let gltf = convert(vec![ZoneContent::Sun], Vec::new());
+    assert!(heightmap_index_count(&gltf).is_none());
+    assert!(has_lights(&gltf));
+    let gltf = convert(Vec::new(), vec![ZoneContent::Sun]);
+    assert!(has_node(&gltf, "32_32_deco_0"));
+    assert!(!has_lights(&gltf));
+
+    let gltf = convert(Vec::new(), Vec::new());
+    assert!(gltf
+        .document
+        .materials()
+        .any(|material| material.name() == Some("32_32_tilemap_material")));
+    assert!(has_node(&gltf, "32_32_deco_0"));
+    assert!(has_node(&gltf, "32_32_ocean"));
+    assert!(has_lights(&gltf));
+}
+
+/// Writes a 4x4 DXT1 DDS of a single RGB565 color
+fn write_dxt1(path: &Path, color: u16) {
+    // Size, flags (caps, height, width, pixel format, linear size), height,
+    // width, linear size, depth and mipmap count, then 11 reserved
+    let mut header = vec![124, 0x81007, 4, 4, 8, 0, 0];
+    header.extend([0; 11]);
+    // Pixel format of size 32 with a fourcc, and unused bit counts and masks
+    header.extend([32, 0x4, u32::from_le_bytes(*b"DXT1"), 0, 0, 0, 0, 0]);
+    // Caps of a texture
+    header.extend([0x1000, 0, 0, 0, 0]);
+
+    let mut dds = b"DDS ".to_vec();
+    for value in header {
+        dds.extend_from_slice(&value.to_le_bytes());
+    }
+    // Both block colors are the same so every index gives the color
+    dds.extend_from_slice(&color.to_le_bytes());
+    dds.extend_from_slice(&color.to_le_bytes());
+    dds.extend_from_slice(&[0; 4]);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, dds).unwrap();
+}
+
+#[test]
+fn terrain_lightmap() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("terrain_lightmap");
+    let assets = write_test_assets(&directory).unwrap();
+    write_dxt1(
+        &assets
+            .zon
+            .parent()
+            .unwrap()
+            .join("32_32/32_32_planelightingmap.dds"),
+        0xf800,
+    );
+
+    let options = RoseGltfConvOptions {
+        terrain_lightmap: TerrainLightmap::VertexColor,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let heightmap = gltf
+        .document
+        .nodes()
+        .find(|node| node.name() == Some("32_32_heightmap"))
+        .unwrap();
+    let primitive = heightmap.mesh().unwrap().primitives().next().unwrap();
+    let reader = primitive.reader(|_| gltf.blob.as_deref());
+    let colors = reader.read_colors(0).unwrap().into_rgba_f32();
+    assert!(colors
+        .into_iter()
+        .all(|color| color == [1.0, 0.0, 0.0, 1.0]));
+    let color_accessor = primitive.get(&gltf::Semantic::Colors(0)).unwrap();
+    assert_eq!(color_accessor.data_type(), gltf::accessor::DataType::U8);
+    assert!(color_accessor.normalized());
+
+    let options = RoseGltfConvOptions {
+        terrain_lightmap: TerrainLightmap::VertexColor,
+        f32_vertex_colors: true,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let primitive = gltf
+        .document
+        .nodes()
+        .find(|node| node.name() == Some("32_32_heightmap"))
+        .and_then(|node| node.mesh())
+        .and_then(|mesh| mesh.primitives().next())
+        .unwrap();
+    let color_accessor = primitive.get(&gltf::Semantic::Colors(0)).unwrap();
+    assert_eq!(color_accessor.data_type(), gltf::accessor::DataType::F32);
+
+    let options = RoseGltfConvOptions {
+        terrain_lightmap: TerrainLightmap::Texture,
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let material = gltf
+        .document
+        .materials()
+        .find(|material| material.name() == Some("32_32_tilemap_material"))
+        .unwrap();
+    let occlusion = material.occlusion_texture().unwrap();
+    assert_eq!(occlusion.tex_coord(), 0);
+    assert_eq!(occlusion.texture().name(), Some("32_32_lightmap_texture"));
+
+    // With their own texture coordinates
+    let options = RoseGltfConvOptions {
+        terrain_lightmap: TerrainLightmap::Texture,
+        terrain_uvs: TerrainUvOptions {
+            lightmap_uv: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let material = gltf
+        .document
+        .materials()
+        .find(|material| material.name() == Some("32_32_tilemap_material"))
+        .unwrap();
+    assert_eq!(material.occlusion_texture().unwrap().tex_coord(), 2);
+}
+
+#[test]
+fn terrain_uvs() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("terrain_uvs");
+    let assets = write_test_assets(&directory).unwrap();
+    let terrain_uvs = |terrain_uvs: TerrainUvOptions| {
+        let options = RoseGltfConvOptions {
+            terrain_uvs,
+            ..Default::default()
+        };
+        let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+        let primitive = gltf
+            .document
+            .nodes()
+            .find(|node| node.name() == Some("32_32_heightmap"))
+            .and_then(|node| node.mesh())
+            .and_then(|mesh| mesh.primitives().next())
+            .unwrap();
+        let reader = primitive.reader(|_| gltf.blob.as_deref());
+        (0..4)
+            .map(|set| {
+                reader
+                    .read_tex_coords(set)
+                    .map(|uvs| uvs.into_f32().collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // The detail uvs repeat once per tile by default
+    let uvs = terrain_uvs(TerrainUvOptions::default());
+    let block_uvs = uvs[0].as_ref().unwrap();
+    assert!(block_uvs
+        .iter()
+        .all(|uv| uv.iter().all(|x| (0.0..=1.0).contains(x))));
+    let detail_uvs = uvs[1].as_ref().unwrap();
+    for (block_uv, detail_uv) in block_uvs.iter().zip(detail_uvs.iter()) {
+        assert_eq!(detail_uv, &block_uv.map(|x| x * 16.0));
+    }
+    assert!(uvs[2].is_none());
+
+    let uvs = terrain_uvs(TerrainUvOptions {
+        detail_repeat: 64.0,
+        lightmap_uv: true,
+    });
+    for ((block_uv, detail_uv), lightmap_uv) in uvs[0]
+        .as_ref()
+        .unwrap()
+        .iter()
+        .zip(uvs[1].as_ref().unwrap())
+        .zip(uvs[2].as_ref().unwrap())
+    {
+        assert_eq!(detail_uv, &block_uv.map(|x| x * 64.0));
+        assert_eq!(lightmap_uv, block_uv);
+    }
+}
+
+#[test]
+fn heightmap_round_trip() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("heightmap_round_trip");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // A HIM from the game, with heights which do not survive being divided
+    // and multiplied by 100 and data after the heights
+    let him_path = assets.zon.parent().unwrap().join("32_32.him");
+    std::fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../rose-file-lib/tests/data/33_30.him"),
+        &him_path,
+    )
+    .unwrap();
+    let him = HIM::from_path(&him_path).unwrap();
+    assert!(!him.trailing_data.is_empty());
+    let original = std::fs::read(&him_path).unwrap();
+
+    let convert = |heightmap_extras| {
+        let options = RoseGltfConvOptions {
+            heightmap_extras,
+            ..Default::default()
+        };
+        let gltf = rose_to_gltf_data(std::slice::from_ref(&assets.zon), &options).unwrap();
+        let options = GltfRoseConvOptions {
+            generate_him: true,
+            skip_meshes: true,
+            ..Default::default()
+        };
+        let mut result = gltf_to_rose(&gltf, &options).unwrap();
+        assert_eq!(result.him.len(), 1);
+        assert_eq!(result.him[0].0, "32_32");
+        let output = directory.join(format!("output_{}", heightmap_extras));
+        result.save_to_dir(&output).unwrap();
+        output.join("32_32.him")
+    };
+
+    // With the heights kept in the extras the HIM is identical
+    assert_eq!(std::fs::read(convert(true)).unwrap(), original);
+
+    // Without them the heights are only as close as the glTF positions
+    let converted = HIM::from_path(&convert(false)).unwrap();
+    assert_eq!(converted.heights.len(), him.heights.len());
+    for (converted, height) in converted.heights.iter().zip(him.heights.iter()) {
+        assert!((converted - height).abs() < 0.01);
+    }
+}
+
+#[test]
+fn low_memory_matches() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("low_memory_matches");
+    let assets = write_test_assets(&directory).unwrap();
+    write_dxt1(
+        &assets
+            .zon
+            .parent()
+            .unwrap()
+            .join("32_32/32_32_planelightingmap.dds"),
+        0xf800,
+    );
+
+    for terrain_lightmap in [TerrainLightmap::VertexColor, TerrainLightmap::Texture] {
+        let convert = |low_memory| {
+            let options = RoseGltfConvOptions {
+                terrain_lightmap,
+                low_memory,
+                ..Default::default()
+            };
+            let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+            (
+                serde_json::to_string(gltf.document.as_json()).unwrap(),
+                gltf.blob,
+            )
+        };
+        assert!(convert(true) == convert(false));
+    }
+}
+
+#[test]
+fn low_memory_matches_after_post_processing() {
+    let directory =
+        Path::new(env!("CARGO_TARGET_TMPDIR")).join("low_memory_matches_after_post_processing");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // Passes which read back and change data written earlier, which in
+    // `low_memory` mode is already in the temporary file when the zone is
+    // converted after the animation
+    let inputs = [
+        vec![assets.zon.clone()],
+        vec![
+            assets.zmd.clone(),
+            assets.skinned_zms.clone(),
+            assets.zmo.clone(),
+            assets.zon.clone(),
+        ],
+    ];
+    for input_files in inputs {
+        let convert = |low_memory| {
+            let options = RoseGltfConvOptions {
+                vertex_buffer_layout: VertexBufferLayout::Planar,
+                retime: Some(2.0),
+                embedded_images: EmbeddedImageOptions {
+                    budget: Some(1_100_000),
+                    ..Default::default()
+                },
+                low_memory,
+                ..Default::default()
+            };
+            let gltf = rose_to_gltf(&input_files, &options).unwrap();
+            (
+                serde_json::to_string(gltf.document.as_json()).unwrap(),
+                gltf.blob,
+            )
+        };
+        assert!(convert(true) == convert(false));
+    }
+}
+
+#[test]
+fn material_variants() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("material_variants");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // A copy of the zone whose tiles use a blue texture
+    image::RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 255, 255]))
+        .save(assets.assets_path.join("3ddata/test/winter.png"))
+        .unwrap();
+    let mut zon = ZON::from_path(&assets.zon).unwrap();
+    zon.textures = vec!["3ddata/test/winter.png".to_string()];
+    let winter_zon = directory.join("winter.zon");
+    zon.write_to_path(&winter_zon).unwrap();
+
+    let options = RoseGltfConvOptions {
+        material_variants: vec![MaterialVariant {
+            name: "winter".to_string(),
+            source: MaterialVariantSource::ZonPath(winter_zon),
+        }],
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let root = gltf.document.as_json();
+
+    assert!(root
+        .extensions_used
+        .contains(&"KHR_materials_variants".to_string()));
+    let variants: Vec<&str> = root
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.khr_materials_variants.as_ref())
+        .unwrap()
+        .variants
+        .iter()
+        .map(|variant| variant.name.as_str())
+        .collect();
+    assert_eq!(variants, ["default", "winter"]);
+
+    // The default variant maps to the material of the primitive, the winter
+    // variant to a material of the blue texture
+    let primitive = &root
+        .meshes
+        .iter()
+        .find(|mesh| mesh.name.as_deref() == Some("32_32_heightmap_mesh"))
+        .unwrap()
+        .primitives[0];
+    let mappings: Vec<(u32, Vec<u32>)> = primitive
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.khr_materials_variants.as_ref())
+        .unwrap()
+        .mappings
+        .iter()
+        .map(|mapping| (mapping.material, mapping.variants.clone()))
+        .collect();
+    assert_eq!(mappings.len(), 2);
+    assert_eq!(
+        mappings[0],
+        (primitive.material.unwrap().value() as u32, vec![0])
+    );
+    assert_eq!(mappings[1].1, [1]);
+    assert_ne!(mappings[1].0, mappings[0].0);
+    let winter_material = &root.materials[mappings[1].0 as usize];
+    assert!(
+        winter_material
+            .name
+            .as_ref()
+            .unwrap()
+            .starts_with("winter_"),
+        "{:?}",
+        winter_material.name
+    );
+}
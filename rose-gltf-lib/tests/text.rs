@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+
+use pretty_assertions::assert_eq;
+use rose_gltf_lib::{build_rose_file, dump_rose_file, TextFormat};
+
+/// Files which are written back in the same version, the ZMS writer always
+/// writes ZMS0008 so older meshes would not round trip exactly.
+const FILES: [&str; 13] = [
+    "31_30.him",
+    "31_30.ifo",
+    "31_30.til",
+    "eluxsamtower.zmo",
+    "headbad01.zms",
+    "jgt01.zon",
+    "list_faceitem_s.stl",
+    "list_zone.stb",
+    "male.zmd",
+    "objectlightmapdata.lit",
+    "part_npc.zsc",
+    "str_itemtype.stl",
+    "ui.tsi",
+];
+
+fn round_trip(format: TextFormat) {
+    let data_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../rose-file-lib/tests/data");
+    let output_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("text_{:?}", format));
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    for name in FILES {
+        let text = dump_rose_file(&data_path.join(name), format).unwrap();
+
+        let output = output_dir.join(name);
+        build_rose_file(&text, format, &output).unwrap();
+        assert_eq!(text, dump_rose_file(&output, format).unwrap(), "{}", name);
+    }
+}
+
+#[test]
+fn text_round_trip_json() {
+    round_trip(TextFormat::Json);
+}
+
+#[test]
+fn text_round_trip_yaml() {
+    round_trip(TextFormat::Yaml);
+}
+
+#[test]
+fn text_unsupported_extension() {
+    let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join("text.png");
+    assert!(build_rose_file("{}", TextFormat::Json, &output).is_err());
+}
@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    render_thumbnail, rose_to_gltf_data, test_assets::write_test_assets, RoseGltfConvOptions,
+};
+
+#[test]
+fn thumbnail_frames_model() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("thumbnail_frames_model");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let gltf = rose_to_gltf_data(
+        std::slice::from_ref(&assets.zms),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let image = render_thumbnail(&gltf, 64, 48);
+    assert_eq!(image.dimensions(), (64, 48));
+
+    // The quad is in the middle of the image, with transparent corners
+    assert_eq!(image.get_pixel(32, 24)[3], 255);
+    assert_eq!(image.get_pixel(0, 0)[3], 0);
+    assert_eq!(image.get_pixel(63, 47)[3], 0);
+}
+
+#[test]
+fn thumbnail_of_empty_scene() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("thumbnail_of_empty_scene");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let gltf = rose_to_gltf_data(
+        std::slice::from_ref(&assets.zmd),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let image = render_thumbnail(&gltf, 16, 16);
+    assert!(image.pixels().all(|pixel| pixel[3] == 0));
+}
@@ -0,0 +1,101 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_to_gltf, test_assets::write_test_assets, RoseGltfConvOptions, VegetationMode,
+    VegetationOptions, VegetationRule,
+};
+use serde_json::Value;
+
+fn grass(mode: VegetationMode) -> VegetationOptions {
+    VegetationOptions {
+        mode,
+        seed: 1,
+        rules: vec![VegetationRule {
+            terrain_textures: vec!["TILE.PNG".to_string()],
+            texture: "3ddata/test/quad.png".to_string(),
+            density: 0.05,
+            width: 1.0,
+            height: 0.5,
+        }],
+    }
+}
+
+#[test]
+fn vegetation_instanced() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("vegetation_instanced");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        vegetation: Some(grass(VegetationMode::Instanced)),
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let json = gltf.document.as_json();
+    assert!(json
+        .extensions_used
+        .contains(&"EXT_mesh_gpu_instancing".to_string()));
+
+    let node = json
+        .nodes
+        .iter()
+        .find(|node| node.name.as_deref() == Some("32_32_vegetation_0"))
+        .unwrap();
+    assert!(node.mesh.is_some());
+    let instancing = &node.extensions.as_ref().unwrap().others["EXT_mesh_gpu_instancing"];
+    let translation = instancing["attributes"]["TRANSLATION"].as_u64().unwrap() as usize;
+    let rotation = instancing["attributes"]["ROTATION"].as_u64().unwrap() as usize;
+
+    // Every tile uses the matched texture, so each is planted with 5 cards
+    let count = json.accessors[translation].count.0;
+    assert_eq!(count, 16 * 16 * 5);
+    assert_eq!(json.accessors[rotation].count.0, count);
+
+    // The same seed plants the same cards
+    let again = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    assert_eq!(gltf.blob, again.blob);
+}
+
+#[test]
+fn vegetation_density_map() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("vegetation_density_map");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        vegetation: Some(grass(VegetationMode::DensityMap)),
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let json = gltf.document.as_json();
+    assert!(!json
+        .extensions_used
+        .contains(&"EXT_mesh_gpu_instancing".to_string()));
+
+    let node = json
+        .nodes
+        .iter()
+        .find(|node| node.name.as_deref() == Some("32_32_vegetation"))
+        .unwrap();
+    let extras: Value = serde_json::from_str(node.extras.as_ref().unwrap().get()).unwrap();
+    let layer = &extras["rose_vegetation"]["layers"][0];
+    assert_eq!(layer["texture"], "3ddata/test/quad.png");
+    assert_eq!(layer["density"].as_array().unwrap().len(), 16);
+    assert_eq!(layer["density"][3][7].as_f64().unwrap() as f32, 0.05);
+}
+
+#[test]
+fn vegetation_options_from_yaml() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("vegetation_options");
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("vegetation.yaml");
+    std::fs::write(
+        &path,
+        "mode: DensityMap\nrules:\n  - terrain_textures: [grass]\n    texture: 3ddata/grass.dds\n    density: 0.5\n    width: 1.0\n    height: 0.5\n",
+    )
+    .unwrap();
+
+    let options = VegetationOptions::from_path(&path).unwrap();
+    assert_eq!(options.mode, VegetationMode::DensityMap);
+    assert_eq!(options.seed, 0);
+    assert_eq!(options.rules.len(), 1);
+    assert_eq!(options.rules[0].terrain_textures, vec!["grass".to_string()]);
+}
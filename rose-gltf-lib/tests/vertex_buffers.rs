@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_to_gltf_data, test_assets::write_test_assets, GltfData, RoseGltfConvOptions,
+    VertexBufferLayout,
+};
+
+fn attribute_data(gltf: &GltfData) -> Vec<(String, Vec<[f32; 4]>)> {
+    let mut attributes = Vec::new();
+    for primitive in gltf.document.meshes().flat_map(|mesh| mesh.primitives()) {
+        let reader = primitive.reader(|buffer| Some(&gltf.buffers[buffer.index()]));
+        let positions = reader.read_positions().unwrap();
+        attributes.push((
+            "position".to_string(),
+            positions.map(|[x, y, z]| [x, y, z, 0.0]).collect(),
+        ));
+        let normals = reader.read_normals().unwrap();
+        attributes.push((
+            "normal".to_string(),
+            normals.map(|[x, y, z]| [x, y, z, 0.0]).collect(),
+        ));
+        let uvs = reader.read_tex_coords(0).unwrap().into_f32();
+        attributes.push((
+            "uv".to_string(),
+            uvs.map(|[u, v]| [u, v, 0.0, 0.0]).collect(),
+        ));
+        let joints = reader.read_joints(0).unwrap().into_u16();
+        attributes.push((
+            "joints".to_string(),
+            joints.map(|joint| joint.map(f32::from)).collect(),
+        ));
+        let weights = reader.read_weights(0).unwrap().into_f32();
+        attributes.push(("weights".to_string(), weights.collect()));
+    }
+    attributes
+}
+
+#[test]
+fn planar_vertex_buffers() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("planar_vertex_buffers");
+    let assets = write_test_assets(&directory).unwrap();
+    let input_files = [assets.zmd.clone(), assets.skinned_zms.clone()];
+
+    let interleaved = rose_to_gltf_data(&input_files, &RoseGltfConvOptions::default()).unwrap();
+    let options = RoseGltfConvOptions {
+        vertex_buffer_layout: VertexBufferLayout::Planar,
+        ..Default::default()
+    };
+    let planar = rose_to_gltf_data(&input_files, &options).unwrap();
+
+    // Every vertex attribute has its own tightly packed buffer view
+    let primitive = planar
+        .document
+        .meshes()
+        .next()
+        .unwrap()
+        .primitives()
+        .next()
+        .unwrap();
+    let mut views = Vec::new();
+    for (_, accessor) in primitive.attributes() {
+        let view = accessor.view().unwrap();
+        assert_eq!(accessor.offset(), 0);
+        assert_eq!(view.stride(), None);
+        assert_eq!(view.length(), accessor.count() * accessor.size());
+        views.push(view.index());
+    }
+    views.sort();
+    views.dedup();
+    assert_eq!(views.len(), primitive.attributes().count());
+    assert!(planar.document.views().all(|view| view.stride().is_none()));
+
+    assert_eq!(attribute_data(&planar), attribute_data(&interleaved));
+}
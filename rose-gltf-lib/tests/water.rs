@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_file_lib::{
+        files::{ifo::WaterData, IFO},
+        io::RoseFile,
+    },
+    rose_to_gltf,
+    test_assets::write_test_assets,
+    RoseGltfConvOptions, WaterMaterialOptions,
+};
+use serde_json::Value;
+
+#[test]
+fn water_planes() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("water_planes");
+    let assets = write_test_assets(&directory).unwrap();
+
+    // A river along the first row of a 4x4 grid, with one dry cell
+    let ifo_path = assets.zon.parent().unwrap().join("32_32.ifo");
+    let mut ifo = IFO::from_path(&ifo_path).unwrap();
+    ifo.oceans.clear();
+    ifo.waters.push(WaterData {
+        width: 4,
+        height: 4,
+        has_water: (0..16).map(|cell| cell < 4 && cell != 2).collect(),
+        heights: vec![250.0; 16],
+    });
+    ifo.write_to_path(&ifo_path).unwrap();
+
+    let gltf = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    let document = &gltf.document;
+    let water = document
+        .nodes()
+        .find(|node| node.name() == Some("32_32_water_0"))
+        .unwrap();
+    let primitive = water.mesh().unwrap().primitives().next().unwrap();
+    assert_eq!(primitive.material().name(), Some("ocean_material"));
+    assert_eq!(primitive.indices().unwrap().count(), 3 * 6);
+
+    let bounds = primitive.bounding_box();
+    assert_eq!(bounds.min, [0.0, 2.5, 0.0]);
+    assert_eq!(bounds.max, [160.0, 2.5, 40.0]);
+}
+
+fn ocean_material(gltf: &gltf::Gltf) -> gltf::Material<'_> {
+    gltf.document
+        .materials()
+        .find(|material| material.name() == Some("ocean_material"))
+        .unwrap()
+}
+
+#[test]
+fn water_material_texture() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("water_material_texture");
+    let assets = write_test_assets(&directory).unwrap();
+    image::RgbaImage::from_pixel(4, 4, image::Rgba([40, 80, 160, 255]))
+        .save(directory.join("3ddata/test/water_01.png"))
+        .unwrap();
+
+    let options = RoseGltfConvOptions {
+        water_material: WaterMaterialOptions {
+            texture: Some("3ddata/test/water_{}.png".to_string()),
+            frame_count: 2,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let material = ocean_material(&gltf);
+    let pbr = material.pbr_metallic_roughness();
+    assert!(pbr.base_color_texture().is_some());
+    assert_eq!(pbr.base_color_factor(), [1.0, 1.0, 1.0, 0.6]);
+
+    let json = gltf.document.as_json();
+    let extras: Value = serde_json::from_str(
+        json.materials[material.index().unwrap()]
+            .extras
+            .as_ref()
+            .unwrap()
+            .get(),
+    )
+    .unwrap();
+    assert_eq!(
+        extras["rose_water"]["frames"],
+        serde_json::json!(["3ddata/test/water_01.png", "3ddata/test/water_02.png"])
+    );
+}
+
+#[test]
+fn water_material_color() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("water_material_color");
+    let assets = write_test_assets(&directory).unwrap();
+
+    let options = RoseGltfConvOptions {
+        water_material: WaterMaterialOptions {
+            texture: None,
+            color: Some([0.1, 0.2, 0.3]),
+            opacity: 0.8,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf(std::slice::from_ref(&assets.zon), &options).unwrap();
+    let pbr = ocean_material(&gltf).pbr_metallic_roughness();
+    assert!(pbr.base_color_texture().is_none());
+    assert_eq!(pbr.base_color_factor(), [0.1, 0.2, 0.3, 0.8]);
+}
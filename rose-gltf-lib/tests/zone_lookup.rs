@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    rose_file_lib::{files::STB, io::RoseFile},
+    rose_to_gltf, rose_to_gltf_data,
+    test_assets::write_test_assets,
+    zone_stats, ErrorCategory, RoseGltfConvOptions,
+};
+
+fn has_node(gltf: &rose_gltf_lib::GltfData, name: &str) -> bool {
+    gltf.document.nodes().any(|node| node.name() == Some(name))
+}
+
+#[test]
+fn zone_missing_from_list_zone() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zone_missing_from_list_zone");
+    // The ZSCs copied beside the ZON below would be found by a rerun
+    let _ = std::fs::remove_dir_all(&directory);
+    let assets = write_test_assets(&directory).unwrap();
+    let list_zone_path = directory.join("3ddata/stb/list_zone.stb");
+    let mut list_zone = STB::from_path(&list_zone_path).unwrap();
+    list_zone.data[1][2].clear();
+    list_zone.write_to_path(&list_zone_path).unwrap();
+    let map_path = assets.zon.parent().unwrap();
+
+    // Neither in list_zone.stb nor beside the ZON
+    let error = rose_to_gltf(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .expect_err("conversion should fail");
+    assert_eq!(ErrorCategory::of(&error), ErrorCategory::MissingDependency);
+    let message = error.to_string();
+    assert!(message.contains("deco ZSC"), "{}", message);
+    assert!(
+        message.contains("3ddata/maps/test/test.zon is not in list_zone.stb"),
+        "{}",
+        message
+    );
+
+    // Given explicitly
+    let options = RoseGltfConvOptions {
+        deco_zsc: Some("3ddata/test/deco.zsc".to_string()),
+        cnst_zsc: Some(directory.join("3ddata/test/cnst.zsc").display().to_string()),
+        ..Default::default()
+    };
+    let gltf = rose_to_gltf_data(std::slice::from_ref(&assets.zon), &options).unwrap();
+    assert!(has_node(&gltf, "32_32_deco_0"));
+    assert!(has_node(&gltf, "32_32_cnst_0"));
+
+    // Beside the ZON, preferring the ones named after it
+    let copies = [
+        "LIST_DECO_TEST.ZSC",
+        "LIST_CNST_TEST.ZSC",
+        "list_deco_other.zsc",
+    ];
+    for name in copies {
+        std::fs::copy(directory.join("3ddata/test/deco.zsc"), map_path.join(name)).unwrap();
+    }
+    let gltf = rose_to_gltf_data(
+        std::slice::from_ref(&assets.zon),
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    assert!(has_node(&gltf, "32_32_deco_0"));
+    assert!(has_node(&gltf, "32_32_cnst_0"));
+
+    let stats = zone_stats(&assets.zon, &RoseGltfConvOptions::default()).unwrap();
+    assert_eq!(stats.zone_id, None);
+    assert_eq!(stats.blocks.len(), 1);
+
+    for name in copies {
+        std::fs::remove_file(map_path.join(name)).unwrap();
+    }
+}
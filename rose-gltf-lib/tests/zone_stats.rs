@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    test_assets::write_test_assets, zone_stats, BlockStats, ObjectStats, RoseGltfConvOptions,
+    TextureStats, ZoneStatsTable,
+};
+
+#[test]
+fn zone_stats_report() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zone_stats");
+    let assets = write_test_assets(&directory).unwrap();
+    let file_size = |path: &str| std::fs::metadata(directory.join(path)).unwrap().len();
+    let quad_png_bytes = file_size("3ddata/test/quad.png");
+    let tile_png_bytes = file_size("3ddata/test/tile.png");
+
+    // Positions, normals and uvs of the 4 vertices and the 2 triangles
+    let quad_mesh_bytes = 4 * (12 + 12 + 8) + 2 * 3 * 2;
+    // 256 tiles of 25 vertices and 32 triangles
+    let terrain_bytes = 256 * (25 * (12 + 12 + 8 + 8) + 32 * 3 * 2);
+
+    let stats = zone_stats(&assets.zon, &RoseGltfConvOptions::default()).unwrap();
+    assert_eq!(stats.zone_id, Some(1));
+    assert_eq!(stats.terrain_triangles, 256 * 32);
+    assert_eq!(stats.object_triangles, 4);
+    assert_eq!(
+        stats.estimated_bytes,
+        terrain_bytes + quad_mesh_bytes + quad_png_bytes
+    );
+    assert_eq!(
+        stats.blocks,
+        [BlockStats {
+            block_x: 32,
+            block_y: 32,
+            terrain_triangles: 256 * 32,
+            deco_instances: 1,
+            cnst_instances: 1,
+            object_triangles: 4,
+            estimated_bytes: terrain_bytes + quad_mesh_bytes + quad_png_bytes,
+        }]
+    );
+    assert_eq!(
+        stats.objects,
+        ["cnst", "deco"].map(|kind| ObjectStats {
+            kind: kind.to_string(),
+            object_id: 0,
+            instances: 1,
+            triangles: 2,
+            estimated_bytes: quad_mesh_bytes + quad_png_bytes,
+        })
+    );
+    assert_eq!(
+        stats.textures,
+        [
+            TextureStats {
+                path: "3ddata/test/quad.png".to_string(),
+                uses: 2,
+                bytes: quad_png_bytes,
+            },
+            TextureStats {
+                path: "3ddata/test/tile.png".to_string(),
+                uses: 256,
+                bytes: tile_png_bytes,
+            },
+        ]
+    );
+
+    assert_eq!(
+        stats.to_csv(ZoneStatsTable::Objects),
+        format!(
+            "kind,object_id,instances,triangles,estimated_bytes\n\
+             cnst,0,1,2,{0}\n\
+             deco,0,1,2,{0}\n",
+            quad_mesh_bytes + quad_png_bytes
+        )
+    );
+
+    // Blocks which are not selected are not counted
+    let options = RoseGltfConvOptions {
+        filter_block_x: Some(0),
+        ..Default::default()
+    };
+    let stats = zone_stats(&assets.zon, &options).unwrap();
+    assert!(stats.blocks.is_empty());
+    assert_eq!(stats.estimated_bytes, 0);
+}
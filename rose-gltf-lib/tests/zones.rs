@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use rose_gltf_lib::{
+    export_zone_blocks, export_zones, test_assets::write_test_assets, BlockManifest, GltfFormat,
+    RoseGltfConvOptions, ZoneManifest,
+};
+
+#[test]
+fn export_all_zones() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("export_all_zones");
+    let assets = write_test_assets(&directory.join("client")).unwrap();
+    let output = directory.join("out");
+
+    let manifest = export_zones(
+        &assets.assets_path,
+        None,
+        &output,
+        &GltfFormat::Binary,
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(manifest.zones.len(), 1);
+
+    let zone = &manifest.zones[0];
+    assert_eq!(zone.zone_id, 1);
+    assert_eq!(zone.zon, "3ddata/maps/test/test.zon");
+    assert_eq!(zone.output, Some(PathBuf::from("1_test.glb")));
+    assert!(zone.error.is_none());
+    assert!(output.join("1_test.glb").exists());
+
+    let written: ZoneManifest =
+        serde_json::from_str(&std::fs::read_to_string(output.join("zones.json")).unwrap()).unwrap();
+    assert_eq!(written.zones.len(), 1);
+}
+
+#[test]
+fn export_zones_records_errors() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("export_zones_errors");
+    let assets = write_test_assets(&directory.join("client")).unwrap();
+
+    let manifest = export_zones(
+        &assets.assets_path,
+        Some(&[0, 1]),
+        &directory.join("out"),
+        &GltfFormat::Binary,
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(manifest.zones.len(), 2);
+    assert!(manifest.zones[0].output.is_none());
+    assert!(manifest.zones[0].error.is_some());
+    assert!(manifest.zones[1].output.is_some());
+}
+
+#[test]
+fn export_blocks_manifest() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("export_blocks_manifest");
+    let assets = write_test_assets(&directory.join("client")).unwrap();
+    let output = directory.join("out");
+
+    // Add a block next to the test block and one which is not next to any
+    let map_path = assets.zon.parent().unwrap();
+    for block_name in ["33_32", "40_40"] {
+        for extension in ["him", "til", "ifo"] {
+            std::fs::copy(
+                map_path.join(format!("32_32.{}", extension)),
+                map_path.join(format!("{}.{}", block_name, extension)),
+            )
+            .unwrap();
+        }
+    }
+
+    let manifest = export_zone_blocks(
+        &assets.zon,
+        &output,
+        &GltfFormat::Binary,
+        &RoseGltfConvOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(manifest.block_size, 160.0);
+    assert_eq!(manifest.blocks.len(), 3);
+
+    let block = &manifest.blocks[0];
+    assert_eq!((block.block_x, block.block_y), (32, 32));
+    assert_eq!(block.output, PathBuf::from("32_32.glb"));
+    assert_eq!(block.translation, [-80.0, 0.0, -80.0]);
+    assert_eq!(block.min, [-80.0, 0.0, -80.0]);
+    assert_eq!(block.max, [80.0, 6.4, 80.0]);
+    assert_eq!(block.neighbors, vec![[33, 32]]);
+    assert_eq!(manifest.blocks[1].neighbors, vec![[32, 32]]);
+    assert!(manifest.blocks[2].neighbors.is_empty());
+
+    for block in manifest.blocks.iter() {
+        assert!(output.join(&block.output).exists());
+    }
+    assert!(output.join(BlockManifest::FILE_NAME).exists());
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use rose_gltf_lib::{
+    gltf_to_rose,
+    rose_file_lib::{
+        files::ZSCTXT,
+        io::RoseFile,
+        utils::{Vector2, Vector3},
+    },
+    rose_to_gltf, rose_to_gltf_data,
+    test_assets::write_test_assets,
+    GltfRoseConvOptions, RoseGltfConvOptions,
+};
+
+/// A lamp of two parts, the second placed on top of the first, with a fire
+/// effect at its top
+const LAMP: &str = "numobj 2
+cylinder 0 0 100
+
+obj 1
+\tmesh 3ddata\\test\\quad.zms
+\tmat 3ddata\\test\\quad.png
+\t\talpha 0
+\t\ttwoside 1
+
+obj 2
+\tmesh 3ddata\\test\\quad.zms
+\tmat 3ddata\\test\\quad.png
+\tpos 0 0 200
+\tparent 1
+
+numpoint 1
+
+point 1
+\ttype 1
+\teffect 3ddata\\effect\\fire.eft
+\tpos 0 0 50
+\tparent 2
+";
+
+#[test]
+fn zsc_text_model() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zsc_text_model");
+    write_test_assets(&directory).unwrap();
+
+    let model_path = directory.join("3ddata/test/lamp.txt");
+    std::fs::write(&model_path, LAMP).unwrap();
+
+    let gltf = rose_to_gltf(&[model_path], &RoseGltfConvOptions::default()).unwrap();
+    let document = &gltf.document;
+    let node = |name: &str| {
+        document
+            .nodes()
+            .find(|node| node.name() == Some(name))
+            .unwrap_or_else(|| panic!("Missing node {}", name))
+    };
+    let child_names = |name: &str| {
+        node(name)
+            .children()
+            .map(|child| child.name().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let scene_nodes: Vec<_> = document
+        .scenes()
+        .next()
+        .unwrap()
+        .nodes()
+        .map(|node| node.name().unwrap().to_string())
+        .collect();
+    assert_eq!(scene_nodes, ["lamp"]);
+    assert_eq!(child_names("lamp"), ["lamp_0"]);
+    assert_eq!(child_names("lamp_0"), ["lamp_1"]);
+    assert_eq!(child_names("lamp_1"), ["lamp_dummy_0"]);
+
+    // Parts share the mesh of the same ZMS, the first part is two sided
+    assert_eq!(document.accessors().count(), 4);
+    let double_sided = |name: &str| {
+        let mesh = node(name).mesh().unwrap();
+        mesh.primitives().next().unwrap().material().double_sided()
+    };
+    assert!(double_sided("lamp_0"));
+    assert!(!double_sided("lamp_1"));
+    let (translation, _, _) = node("lamp_1").transform().decomposed();
+    assert_eq!(translation, [0.0, 2.0, 0.0]);
+
+    let dummy = node("lamp_dummy_0");
+    let extras: serde_json::Value =
+        serde_json::from_str(dummy.extras().as_ref().unwrap().get()).unwrap();
+    assert_eq!(
+        extras["rose_dummy"],
+        serde_json::json!({
+            "effect": "3ddata\\effect\\fire.eft",
+            "only_visible_at_night": true,
+        })
+    );
+    assert!(dummy.mesh().is_none());
+}
+
+#[test]
+fn zsc_text_generated() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zsc_text_generated");
+    write_test_assets(&directory).unwrap();
+    let model_path = directory.join("3ddata/test/lamp.txt");
+    std::fs::write(&model_path, LAMP).unwrap();
+    let gltf = rose_to_gltf_data(&[model_path], &RoseGltfConvOptions::default()).unwrap();
+
+    let options = GltfRoseConvOptions {
+        generate_zsc: true,
+        zsc_text: true,
+        zsc_mesh_directory: "3ddata\\custom".to_string(),
+        ..Default::default()
+    };
+    let mut result = gltf_to_rose(&gltf, &options).unwrap();
+    assert!(result.zsc.is_empty());
+    assert_eq!(result.zsc_text.len(), 1);
+    assert_eq!(result.zsc_text[0].0, "lamp");
+
+    let output = directory.join("zsc_text_generated");
+    let written = result.save_to_dir(&output).unwrap();
+    let text_path = output.join("lamp.txt");
+    assert!(written.contains(&text_path));
+
+    // Parts are positioned relative to the model node
+    let model = ZSCTXT::from_path(&text_path).unwrap();
+    assert_eq!(model.parts.len(), 2);
+    assert!(model
+        .parts
+        .iter()
+        .all(|part| part.mesh_path.starts_with("3ddata\\custom\\")));
+    assert_eq!(model.parts[0].position, Vector3::new(0.0, 0.0, 0.0));
+    assert_eq!(model.parts[1].position, Vector3::new(0.0, 0.0, 200.0));
+}
+
+#[test]
+fn zsc_generated_bounds() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("zsc_generated_bounds");
+    write_test_assets(&directory).unwrap();
+    let model_path = directory.join("3ddata/test/lamp.txt");
+    std::fs::write(&model_path, LAMP).unwrap();
+    let gltf = rose_to_gltf_data(&[model_path], &RoseGltfConvOptions::default()).unwrap();
+
+    let options = GltfRoseConvOptions {
+        generate_zsc: true,
+        ..Default::default()
+    };
+    let result = gltf_to_rose(&gltf, &options).unwrap();
+    let model = result.zsc[0].1.models[0].as_ref().unwrap();
+
+    // The quads span 1 unit in x and y, the second 200 above the first
+    assert_eq!(model.bounding_box.min, Vector3::new(0.0, 0.0, 0.0));
+    assert_eq!(model.bounding_box.max, Vector3::new(100.0, 100.0, 200.0));
+    assert_eq!(model.bounding_cylinder.center, Vector2::new(50, 50));
+    assert!((model.bounding_cylinder.radius - 50.0 * 2.0f32.sqrt()).abs() < 1.0e-3);
+}
@@ -1,16 +1,67 @@
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+    time::Instant,
+};
 
 use anyhow::Context;
-use clap::Parser;
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use rayon::prelude::*;
+use rose_file_lib::{
+    files::{
+        zmd::SkeletonVersion,
+        zms::{VertexFormat, VertexLayout},
+        ItemStb, CHR, ZMD, ZMO,
+    },
+    io::RoseFile,
+};
 use rose_gltf_lib::{
-    gltf_to_rose, rose_to_gltf, save_gltf, GltfData, GltfFormat, GltfRoseConvOptions,
-    RoseGltfConvOptions,
+    analyze_gltf, assemble_model, build_rose_file, collect_timings, collect_warnings,
+    dump_rose_file, export_zone_blocks, export_zone_incremental, export_zones,
+    find_missing_zone_assets, gltf_to_rose, merge_gltf, mirror_animation, render_thumbnail,
+    rose_to_gltf, rose_to_gltf_data, save_gltf, zone_stats, AnimationConcat, AnimationLoop,
+    AnimationLoopRule, AnimationOverride, AnimationSplit, AssemblyPart, AssetCache, AssetLocator,
+    BlockManifest, BoneNaming, EmbeddedImageOptions, EngineHints, ErrorCategory, FsAssetLocator,
+    GlassMaterialRules, GltfAnalysis, GltfData, GltfFormat, GltfRoseConvOptions, HeightRange,
+    ImageEncoding, MaterialVariant, MaterialVariantSource, ModelAssembly, NameFilter, PhaseTiming,
+    PostProcessOptions, RadiusFilter, RoseGltfConvOptions, SpawnVolumeShape, TerrainLightmap,
+    TerrainUvOptions, TextFormat, TexturePathRules, TextureVariant, UvMode, VegetationMode,
+    VegetationOptions, VertexBufferLayout, WaterMaterialOptions, WindingCorrection, ZoneContent,
+    ZoneContentFilter, ZoneStatsTable,
 };
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "viewer")]
+mod viewer;
+#[cfg(feature = "viewer")]
+mod viewer_scene;
+
+const EXIT_CODES_HELP: &str = "Exit codes:
+  1  Other error
+  2  Invalid command line arguments
+  3  An input file could not be parsed
+  4  A file the input depends on is missing
+  5  The input is not supported
+  6  Reading or writing a file failed
+  7  The input is not valid";
 
 /// Converts ROSE files to a .gltf file
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    after_help = EXIT_CODES_HELP
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// List of input files
     input: Vec<PathBuf>,
 
@@ -26,52 +77,1602 @@ struct Args {
     #[arg(long)]
     filter_block_y: Option<i32>,
 
+    /// When converting a zon, only export what is within --radius of this
+    /// point, as ROSE world coordinates in meters (e.g. 5200,5200)
+    #[arg(long, value_parser = parse_center, requires = "radius")]
+    center: Option<[f32; 2]>,
+
+    /// Radius in meters around --center
+    #[arg(long, requires = "center")]
+    radius: Option<f32>,
+
+    /// When converting a zon, only export objects, effects and sounds at or
+    /// above this world height in meters, e.g. to extract one floor of a dungeon
+    #[arg(long)]
+    min_height: Option<f32>,
+
+    /// When converting a zon, only export objects, effects and sounds at or
+    /// below this world height in meters
+    #[arg(long)]
+    max_height: Option<f32>,
+
+    /// When converting a zon, only export these kinds of content (terrain,
+    /// cnst, deco, ocean, water, effects, sounds, vegetation, lights, sun, spawns)
+    #[arg(long, value_delimiter = ',', value_parser = parse_zone_content)]
+    only: Vec<ZoneContent>,
+
+    /// When converting a zon, do not export these kinds of content, as for --only
+    #[arg(long, value_delimiter = ',', value_parser = parse_zone_content)]
+    skip: Vec<ZoneContent>,
+
     /// Choose better triangulation for heightmaps, though it may not match your ROSE client.
     #[arg(long, default_value_t = true)]
     use_better_heightmap_triangles: bool,
 
+    /// When converting a zon, also export objects which are hidden by default, those with a zero
+    /// scale.
+    #[arg(long)]
+    include_disabled_objects: bool,
+
+    /// When converting a zon, only list the meshes and textures used by the selected blocks which
+    /// are missing, and which objects use them, instead of converting.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// When converting a zon, use placeholder meshes and textures for ones which are missing
+    /// instead of failing.
+    #[arg(long)]
+    ignore_missing: bool,
+
+    /// When converting a zon, the deco ZSC to use instead of the one in list_zone.stb, for zones
+    /// which are not in it. Defaults to a ZSC beside the ZON with deco in its name.
+    #[arg(long)]
+    deco_zsc: Option<String>,
+
+    /// When converting a zon, the cnst ZSC to use instead of the one in list_zone.stb, for zones
+    /// which are not in it. Defaults to a ZSC beside the ZON with cnst in its name.
+    #[arg(long)]
+    cnst_zsc: Option<String>,
+
+    /// Replace textures which are missing or can not be read with a checkerboard colored by the
+    /// texture path, whose path is recorded in the extras of the material.
+    #[arg(long)]
+    placeholder_textures: bool,
+
+    /// When converting a zon, add the full path of sound files to the extras of sound emitter nodes.
+    #[arg(long)]
+    resolve_sound_paths: bool,
+
+    /// When converting a zon, add a quad textured with the effect texture to effect marker nodes.
+    #[arg(long)]
+    effect_billboards: bool,
+
+    /// When converting a zon, add a translucent volume (cylinder, box) for each monster spawn
+    /// area with its monsters in the node extras.
+    #[arg(long, value_parser = parse_spawn_volume_shape)]
+    spawn_volumes: Option<SpawnVolumeShape>,
+
+    /// When converting a zon, rotate billboarded object parts such as tree cards to face +Z
+    /// in a child node, so engines can rebuild the billboarding.
+    #[arg(long)]
+    billboard_pre_rotation: bool,
+
+    /// When converting a zon, plant vegetation using the rules in this JSON or YAML file.
+    #[arg(long)]
+    vegetation: Option<PathBuf>,
+
+    /// Export vegetation as a density map per block in extras instead of instanced cards.
+    #[arg(long, requires = "vegetation")]
+    vegetation_density_map: bool,
+
+    /// When converting a zon, export the terrain textures of another zone as a material
+    /// variant, in the form `name=list_zone_row` or `name=path/to/variant.zon`.
+    #[arg(long = "material-variant", value_parser = parse_material_variant)]
+    material_variants: Vec<MaterialVariant>,
+
+    /// Generate tangents for meshes which have normals and UVs but no tangents.
+    #[arg(long)]
+    generate_missing_tangents: bool,
+
+    /// Mirror the V coordinate of UVs, use the same value in both directions to round trip.
+    #[arg(long)]
+    flip_v: bool,
+
+    /// Write vertex colors as f32 instead of normalized u8.
+    #[arg(long)]
+    f32_vertex_colors: bool,
+
+    /// Reverse the winding of meshes which appear inside-out: none, auto to reverse meshes whose
+    /// triangles face inwards, or flip to reverse every mesh. Use the same value in both
+    /// directions to round trip.
+    #[arg(long, default_value = "none", value_parser = parse_winding)]
+    winding: WindingCorrection,
+
+    /// Split vertices where faces meet at more than this angle in degrees and recompute their
+    /// normals, so hard edges stay hard in viewers. 0 flat shades every face.
+    #[arg(long)]
+    hard_edge_angle: Option<f32>,
+
+    /// Write animation rotations as normalized i16 to reduce the size of animations.
+    #[arg(long)]
+    quantize_animations: bool,
+
+    /// When converting a zon, export glass-like materials (alpha blended with specular, or
+    /// a texture path containing a --glass-keyword) with transmission and IOR.
+    #[arg(long)]
+    glass_materials: bool,
+
+    /// Texture path keyword which marks an alpha blended material as glass, replaces the
+    /// default keywords (glass, crystal).
+    #[arg(long = "glass-keyword", requires = "glass_materials")]
+    glass_keywords: Vec<String>,
+
+    /// Index of refraction used for glass materials.
+    #[arg(long, default_value_t = 1.5, requires = "glass_materials")]
+    glass_ior: f32,
+
+    /// Texture frames used for water, `{}` is replaced by the two digit frame number.
+    #[arg(long, default_value = "3ddata/junon/water/ocean01_{}.dds")]
+    water_texture: String,
+
+    /// Do not texture water, only use the water color.
+    #[arg(long)]
+    no_water_texture: bool,
+
+    /// Base color of water as `r,g,b` in the range 0 to 1.
+    #[arg(long, value_parser = parse_color)]
+    water_color: Option<[f32; 3]>,
+
+    /// Opacity of water in the range 0 to 1.
+    #[arg(long, default_value_t = 0.6)]
+    water_opacity: f32,
+
+    /// When converting a zon, export the planelightingmap of each block as the terrain's
+    /// vertex colors (vertex) or as the occlusion texture of its material (texture).
+    #[arg(long, value_parser = parse_terrain_lightmap)]
+    terrain_lightmap: Option<TerrainLightmap>,
+
+    /// How many times a detail texture using the second texture coordinates of the terrain
+    /// repeats across a block, by default once per tile.
+    #[arg(long, default_value_t = 16.0)]
+    terrain_detail_repeat: f32,
+
+    /// Add third texture coordinates to the terrain covering each block once for lightmaps, the
+    /// terrain lightmap texture uses them.
+    #[arg(long)]
+    terrain_lightmap_uv: bool,
+
+    /// Directory containing 3ddata to search for assets referenced by the input, can be
+    /// given multiple times. The directory above the 3ddata the input is in is searched last.
+    #[arg(long = "asset-root")]
+    asset_roots: Vec<PathBuf>,
+
+    /// Remove the extras of every object from the output.
+    #[arg(long)]
+    strip_extras: bool,
+
+    /// Remove the `TLM_` extras used by The Lightmapper Blender addon from the output.
+    #[arg(long)]
+    strip_lightmapper_extras: bool,
+
+    /// Remove every animation from the output.
+    #[arg(long)]
+    strip_animations: bool,
+
+    /// Remove skins from the output, skinned meshes are left in their bind pose.
+    #[arg(long)]
+    strip_skins: bool,
+
+    /// Prefix added to the name of every node in the output.
+    #[arg(long)]
+    node_prefix: Option<String>,
+
+    /// Do not record the source files, zone, block and converter version in the extras of
+    /// the output.
+    #[arg(long)]
+    no_provenance: bool,
+
+    /// Do not look for the skeleton of character meshes and motions converted without a .ZMD,
+    /// from 3ddata/npc/list_npc.chr, the avatar skeletons or a .ZMD in the same directory.
+    #[arg(long)]
+    no_auto_skeleton: bool,
+
+    /// How mesh vertex attributes are stored, interleaved in one buffer view per mesh or planar
+    /// with one tightly packed buffer view per attribute.
+    #[arg(long, default_value = "interleaved", value_parser = parse_vertex_buffer_layout)]
+    vertex_buffer_layout: VertexBufferLayout,
+
+    /// Mark colliding nodes and material settings of zones in the conventions of a game engine's
+    /// importer: godot names colliding nodes with a -col suffix, unity adds collider and
+    /// Standard shader extras.
+    #[arg(long, default_value = "none", value_parser = parse_engine_hints)]
+    engine_hints: EngineHints,
+
+    /// Group the nodes of each zone block under a parent node, terrain first, then buildings,
+    /// decos and everything else, and number nodes in the order of the hierarchy.
+    #[arg(long)]
+    sort_nodes: bool,
+
+    /// Keep the exact heights of each zone block in the extras of its heightmap node, so
+    /// unedited terrain converts back to an identical HIM with --him.
+    #[arg(long)]
+    heightmap_extras: bool,
+
+    /// Format of every embedded texture: png, or jpeg with an optional quality such as jpeg:85.
+    /// JPEG has no alpha channel, so the textures of alpha tested and blended materials are still
+    /// written as PNG. KTX2 is not supported.
+    #[arg(long, default_value = "png", value_parser = parse_image_encoding)]
+    image_format: ImageEncoding,
+
+    /// Scale down embedded textures whose width or height is larger than this, keeping their
+    /// aspect ratio.
+    #[arg(long)]
+    max_texture_size: Option<u32>,
+
+    /// Total size in MiB of the embedded textures once decoded, the largest textures are halved
+    /// in size until they fit.
+    #[arg(long)]
+    texture_budget: Option<u64>,
+
+    /// How skeleton joints are named, after the ZMD bones (original) or as
+    /// `{skeleton}_Bone_{index}` (indexed).
+    #[arg(long, default_value = "original", value_parser = parse_bone_naming)]
+    bone_names: BoneNaming,
+
+    /// Export a range of frames of each ZMO as a separate animation instead of the whole ZMO, in
+    /// the form `name:start-end` with inclusive frame indices. Can be given multiple times.
+    #[arg(long = "split-animation", value_parser = parse_animation_split)]
+    animation_splits: Vec<AnimationSplit>,
+
+    /// Multiply the duration of animations in either direction of conversion, e.g. 2 plays them
+    /// at half speed and 0.5 at double speed. ZMO frame counts are recomputed for the fps.
+    #[arg(long)]
+    retime: Option<f32>,
+
+    /// Add a mirrored copy of each skeletal animation, named `<name>_mirrored`. Left and right
+    /// bones are paired by name, e.g. `b1_l_hand` and `b1_r_hand`.
+    #[arg(long)]
+    mirror_animations: bool,
+
+    /// Tag animations whose name matches a regex as looped or played once in their extras, in the
+    /// form `pattern=loop` or `pattern=once`.
+    #[arg(long = "animation-loop", value_parser = parse_animation_loop)]
+    animation_loops: Vec<AnimationLoopRule>,
+
+    /// Remove the last frame of looped animations when it repeats the first frame, so they loop
+    /// seamlessly.
+    #[arg(long)]
+    trim_last_duplicate_frame: bool,
+
+    /// Add a single frame `bind_pose` animation to each skeleton which resets it to the rest
+    /// pose of the ZMD.
+    #[arg(long)]
+    bind_pose_animation: bool,
+
+    /// Copyright message written to the asset of the output, e.g. to credit the original game.
+    #[arg(long)]
+    copyright: Option<String>,
+
+    /// Generator written to the asset of the output, defaults to the name and version of
+    /// rose-gltf.
+    #[arg(long)]
+    generator: Option<String>,
+
     /// Ouput GLTF instead of GLB
     #[arg(long)]
     gltf: bool,
 
-    /// When converting from GLTF to ZMO, this is the FPS to use for the generated ZMO.
-    #[arg(short, long, default_value_t = 30)]
-    zmo_fps: u32,
+    /// Set of options tuned for an importer, blender or web, or a profile of --config. Options
+    /// given on the command line take precedence over the profile.
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// JSON or YAML file defining profiles for --profile, by name under `profiles`.
+    #[arg(long, requires = "profile")]
+    config: Option<PathBuf>,
+
+    /// Print a report of the conversion (inputs, outputs, warnings, statistics and timings)
+    /// to stdout as json or yaml, instead of printing warnings as they happen.
+    #[arg(long, value_parser = parse_text_format)]
+    report: Option<TextFormat>,
+
+    /// When converting a zon, write a file per block into the output directory along with a
+    /// blocks.json manifest of their placement, bounds and neighbors.
+    #[arg(long)]
+    split_blocks: bool,
+
+    /// Convert each input file on its own into the output directory, named after the input,
+    /// instead of combining them into a single file.
+    #[arg(long, conflicts_with = "split_blocks")]
+    batch: bool,
+
+    /// When converting a zon, keep the conversion of each block in this directory and only
+    /// convert the blocks whose files changed since the last run, assembling the output from
+    /// the converted blocks.
+    #[arg(long, value_name = "CACHE_DIR", conflicts_with_all = ["split_blocks", "batch"])]
+    incremental: Option<PathBuf>,
+
+    /// Number of input files to convert at once with --batch or when converting from GLTF,
+    /// 0 uses a thread per CPU.
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Reduce the memory used to convert large zones: lightmaps are only loaded while their block
+    /// is converted, decoded textures are not cached, the buffer is written to a temporary file as
+    /// it is built and at most 2 inputs are converted at once. The blocks of a zone are converted
+    /// one at a time as without it.
+    #[arg(long)]
+    low_memory: bool,
+
+    /// When converting from GLTF to ZMO, this is the FPS to use for the generated ZMO. Defaults
+    /// to the FPS of the ZMO the animation was converted from by rose-gltf, or 30.
+    #[arg(short, long)]
+    zmo_fps: Option<u32>,
+
+    /// Override the FPS and time range of animations whose name matches a regex,
+    /// in the form `pattern=fps` or `pattern=fps@start-end` (times in seconds,
+    /// fps or either time may be left empty).
+    #[arg(long = "animation-override", value_parser = parse_animation_override)]
+    animation_overrides: Vec<AnimationOverride>,
+
+    /// When converting from GLTF, append skeletal animations end to end into a single ZMO, in the
+    /// form `name` for all animations or `name=first,second` to choose them and their order.
+    #[arg(long, value_parser = parse_animation_concat)]
+    concat_animations: Option<AnimationConcat>,
+
+    /// Frames between animations appended by --concat-animations.
+    #[arg(long, default_value_t = 0, requires = "concat_animations")]
+    concat_gap_frames: u32,
+
+    /// Blend from one animation to the next during --concat-gap-frames instead of holding the
+    /// last frame.
+    #[arg(long, requires = "concat_animations")]
+    concat_blend: bool,
+
+    /// Only output skeletons, ignoring meshes and animations.
+    #[arg(long, conflicts_with = "animation_only")]
+    skeleton_only: bool,
+
+    /// Only output animations (and the skeleton they need when converting to GLTF), ignoring meshes.
+    #[arg(long)]
+    animation_only: bool,
+
+    /// Bake this frame of the input ZMO into the input skinned ZMS, producing static meshes.
+    #[arg(long)]
+    bake_pose_frame: Option<u32>,
+
+    /// Bake every frame of the input ZMO into the input skinned ZMS as morph targets with an
+    /// animation of their weights, for viewers which do not support skinning.
+    #[arg(long, conflicts_with = "bake_pose_frame")]
+    morph_target_animation: bool,
+
+    /// When converting from GLTF, only keep these vertex attributes in the generated ZMS
+    /// (comma separated list of normal, color, bones, tangent, uv1, uv2, uv3, uv4).
+    #[arg(long, value_delimiter = ',', value_parser = parse_vertex_attribute)]
+    keep_attributes: Option<Vec<i32>>,
+
+    /// When converting from GLTF, how to handle UVs outside of [0, 1]. Either a single mode for
+    /// all channels or a comma separated list per channel (keep, wrap, clamp, rescale).
+    #[arg(long, value_delimiter = ',', value_parser = parse_uv_mode)]
+    uv_mode: Vec<UvMode>,
+
+    /// When converting from GLTF, generate a UV2 channel for lightmapping on meshes without one.
+    #[arg(long)]
+    generate_lightmap_uvs: bool,
+
+    /// When converting from GLTF, store UVs in the generated ZMS as 16 bit floats.
+    #[arg(long)]
+    half_float_uvs: bool,
+
+    /// When converting from GLTF, store vertex colors in the generated ZMS as u8.
+    #[arg(long)]
+    u8_colors: bool,
+
+    /// When converting from GLTF, the version of the generated ZMD (2 or 3). Version 2 is for
+    /// older clients and does not store the rotation of dummy bones.
+    #[arg(long, default_value = "3", value_parser = parse_zmd_version)]
+    zmd_version: SkeletonVersion,
+
+    /// When converting from GLTF, also generate a ZSC with a model per scene root node.
+    #[arg(long)]
+    zsc: bool,
+
+    /// With --zsc, write a text ZSC (.txt) per scene root node instead, which can be edited by
+    /// hand.
+    #[arg(long, requires = "zsc")]
+    zsc_text: bool,
+
+    /// When converting from GLTF, also generate a HIM for each terrain heightmap node.
+    #[arg(long)]
+    him: bool,
+
+    /// Directory used for mesh paths in the generated ZSC, e.g. 3ddata\custom
+    #[arg(long, default_value = "")]
+    zsc_mesh_dir: String,
+
+    /// Rewrite texture paths starting with a prefix in the generated ZSC, in the form `from=to`.
+    #[arg(long, value_parser = parse_prefix_mapping)]
+    texture_prefix_map: Vec<(String, String)>,
+
+    /// Directory for texture paths which do not match any --texture-prefix-map.
+    #[arg(long, default_value = "")]
+    texture_dir: String,
+
+    /// Change texture paths in the generated ZSC to use the .dds extension.
+    #[arg(long)]
+    texture_dds: bool,
+
+    /// Lowercase texture paths in the generated ZSC.
+    #[arg(long)]
+    texture_lowercase: bool,
+
+    /// Write the textures used by the generated ZSC into the output directory.
+    #[arg(long)]
+    copy_textures: bool,
+
+    /// When converting from GLTF, only convert meshes whose name matches one of these regexes.
+    #[arg(long)]
+    include_mesh: Vec<String>,
+
+    /// When converting from GLTF, skip meshes whose name matches one of these regexes.
+    #[arg(long)]
+    exclude_mesh: Vec<String>,
+
+    /// When converting from GLTF, only convert skins whose name matches one of these regexes.
+    #[arg(long)]
+    include_skin: Vec<String>,
+
+    /// When converting from GLTF, skip skins whose name matches one of these regexes.
+    #[arg(long)]
+    exclude_skin: Vec<String>,
+
+    /// When converting from GLTF, only convert animations whose name matches one of these regexes.
+    #[arg(long)]
+    include_animation: Vec<String>,
+
+    /// When converting from GLTF, skip animations whose name matches one of these regexes.
+    #[arg(long)]
+    exclude_animation: Vec<String>,
+}
+
+/// Options set by `--profile`, named after the command line options
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Profile {
+    gltf: Option<bool>,
+    bone_names: Option<String>,
+    image_format: Option<String>,
+    engine_hints: Option<String>,
+    max_texture_size: Option<u32>,
+    texture_budget: Option<u64>,
+    quantize_animations: Option<bool>,
+    generate_missing_tangents: Option<bool>,
+    strip_extras: Option<bool>,
+    strip_lightmapper_extras: Option<bool>,
+    no_provenance: Option<bool>,
+}
+
+impl Profile {
+    /// The profiles which are available without a config file
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            // Blender imports extras as custom properties and names bones
+            // itself, so keep the names of the ZMD and leave out the extras
+            // only The Lightmapper uses
+            "blender" => Some(Profile {
+                gltf: Some(true),
+                bone_names: Some("original".to_string()),
+                image_format: Some("png".to_string()),
+                strip_lightmapper_extras: Some(true),
+                ..Default::default()
+            }),
+            // JPEG only applies to opaque materials, alpha tested and blended
+            // ones keep PNG textures
+            "web" => Some(Profile {
+                gltf: Some(false),
+                image_format: Some("jpeg:85".to_string()),
+                max_texture_size: Some(1024),
+                quantize_animations: Some(true),
+                strip_lightmapper_extras: Some(true),
+                no_provenance: Some(true),
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+
+    /// Sets the options of `args` which were not given on the command line
+    fn apply(&self, args: &mut Args, matches: &ArgMatches) -> anyhow::Result<()> {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let invalid = |error: String| anyhow::anyhow!("Invalid profile: {}", error);
+
+        if let Some(gltf) = self.gltf.filter(|_| !given("gltf")) {
+            args.gltf = gltf;
+        }
+        if let Some(bone_names) = self.bone_names.as_ref().filter(|_| !given("bone_names")) {
+            args.bone_names = parse_bone_naming(bone_names).map_err(invalid)?;
+        }
+        if let Some(image_format) = self
+            .image_format
+            .as_ref()
+            .filter(|_| !given("image_format"))
+        {
+            args.image_format = parse_image_encoding(image_format).map_err(invalid)?;
+        }
+        if let Some(engine_hints) = self
+            .engine_hints
+            .as_ref()
+            .filter(|_| !given("engine_hints"))
+        {
+            args.engine_hints = parse_engine_hints(engine_hints).map_err(invalid)?;
+        }
+        if !given("max_texture_size") && self.max_texture_size.is_some() {
+            args.max_texture_size = self.max_texture_size;
+        }
+        if !given("texture_budget") && self.texture_budget.is_some() {
+            args.texture_budget = self.texture_budget;
+        }
+        for (id, value, arg) in [
+            (
+                "quantize_animations",
+                self.quantize_animations,
+                &mut args.quantize_animations,
+            ),
+            (
+                "generate_missing_tangents",
+                self.generate_missing_tangents,
+                &mut args.generate_missing_tangents,
+            ),
+            ("strip_extras", self.strip_extras, &mut args.strip_extras),
+            (
+                "strip_lightmapper_extras",
+                self.strip_lightmapper_extras,
+                &mut args.strip_lightmapper_extras,
+            ),
+            ("no_provenance", self.no_provenance, &mut args.no_provenance),
+        ] {
+            if let Some(value) = value.filter(|_| !given(id)) {
+                *arg = value;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read from the file given with `--config`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    profiles: HashMap<String, Profile>,
+}
+
+impl Config {
+    fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        match TextFormat::from_path(path) {
+            TextFormat::Json => serde_json::from_str(&text).context("Failed to parse JSON"),
+            TextFormat::Yaml => serde_yaml::from_str(&text).context("Failed to parse YAML"),
+        }
+    }
+}
+
+/// Timings of a conversion in seconds
+#[derive(Default, Serialize)]
+struct ConversionTimings {
+    convert_seconds: f64,
+    write_seconds: f64,
+    total_seconds: f64,
+
+    /// Time spent in each phase of the conversion, such as building meshes
+    /// or decoding images. Phases can run inside other phases.
+    phases: BTreeMap<String, PhaseTiming>,
+}
+
+impl ConversionTimings {
+    /// Adds the phases of a conversion, phases of conversions run at once
+    /// are summed
+    fn add_phases(&mut self, phases: BTreeMap<String, PhaseTiming>) {
+        for (phase, timing) in phases {
+            let total = self.phases.entry(phase).or_default();
+            total.count += timing.count;
+            total.seconds += timing.seconds;
+        }
+    }
+}
+
+/// What was written by converting one or more input files to a single output,
+/// or by converting a glTF to ROSE files
+#[derive(Default)]
+struct FileConversion {
+    outputs: Vec<PathBuf>,
+    statistics: Option<GltfAnalysis>,
+    convert_seconds: f64,
+    write_seconds: f64,
+}
+
+/// Written to stdout by `--report`
+#[derive(Default, Serialize)]
+struct ConversionReport {
+    inputs: Vec<PathBuf>,
+    outputs: Vec<PathBuf>,
+    warnings: Vec<String>,
+
+    /// Analysis of the glTF written, or of each glTF read
+    statistics: Vec<GltfAnalysis>,
+    timings: ConversionTimings,
+
+    /// Most memory the process has used, where the platform reports it
+    peak_memory_bytes: Option<u64>,
+
+    /// Set when the conversion failed, outputs lists what was written before
+    /// the failure
+    error: Option<String>,
+    error_category: Option<ErrorCategory>,
+}
+
+/// A conversion requested of `serve`, one JSON object per line on stdin
+#[derive(Deserialize)]
+struct ServeRequest {
+    inputs: Vec<PathBuf>,
+
+    /// Output file path, the extension is set by `gltf`
+    output: PathBuf,
+
+    /// Ouput GLTF instead of GLB
+    #[serde(default)]
+    gltf: bool,
+
+    /// Add an analysis of the glTF written to the response
+    #[serde(default)]
+    statistics: bool,
+
+    #[serde(default)]
+    options: RoseGltfConvOptions,
+}
+
+/// Written to stdout as a single line by `serve` for each request
+#[derive(Serialize)]
+struct ServeResponse {
+    /// The `id` of the request, so responses can be matched to requests
+    id: serde_json::Value,
+
+    #[serde(flatten)]
+    report: ConversionReport,
+}
+
+impl ConversionReport {
+    /// Adds a conversion, timings of conversions run at once are summed
+    fn add(&mut self, conversion: FileConversion) {
+        self.outputs.extend(conversion.outputs);
+        self.statistics.extend(conversion.statistics);
+        self.timings.convert_seconds += conversion.convert_seconds;
+        self.timings.write_seconds += conversion.write_seconds;
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print a breakdown of what takes up space in a .gltf or .glb file
+    Analyze {
+        /// The .gltf or .glb file to analyze
+        input: PathBuf,
+    },
+
+    /// Print a ROSE file as JSON or YAML
+    Dump {
+        /// The ROSE file to dump, the file type is chosen by its extension
+        input: PathBuf,
+
+        /// Text format to print (json, yaml)
+        #[arg(long, default_value = "json", value_parser = parse_text_format)]
+        format: TextFormat,
+    },
+
+    /// Convert zones of list_zone.stb to a file per zone, and write a zones.json manifest
+    Zones {
+        /// Directory containing 3ddata
+        assets: PathBuf,
+
+        /// Convert every zone in list_zone.stb
+        #[arg(long, required_unless_present = "zone_ids")]
+        all: bool,
+
+        /// Row of list_zone.stb to convert, can be given multiple times
+        #[arg(long = "zone", conflicts_with = "all")]
+        zone_ids: Vec<usize>,
+
+        /// Output directory
+        #[arg(short, long = "out", default_value = ".")]
+        output: PathBuf,
+
+        /// Ouput GLTF instead of GLB
+        #[arg(long)]
+        gltf: bool,
+    },
+
+    /// Print statistics of a zone without converting it: the triangles of each block, the
+    /// instances of each object, the uses of each texture and their estimated export sizes
+    ZoneStats {
+        /// The .zon file of the zone
+        input: PathBuf,
+
+        /// Text format to print the whole report in (json, yaml)
+        #[arg(long, default_value = "json", value_parser = parse_text_format)]
+        format: TextFormat,
+
+        /// Print one table of the report as CSV instead (blocks, objects, textures)
+        #[arg(long, value_parser = parse_zone_stats_table)]
+        csv: Option<ZoneStatsTable>,
+    },
+
+    /// Assemble a model from ZSC parts sharing a skeleton, e.g. a cart or castle gear
+    Assemble {
+        /// Directory containing 3ddata
+        assets: PathBuf,
+
+        /// ZMD which the parts are skinned or attached to, relative to the assets directory
+        #[arg(long)]
+        skeleton: String,
+
+        /// ZSC containing the part models, relative to the assets directory
+        #[arg(long)]
+        models: String,
+
+        /// Model id in --models to add, can be given multiple times
+        #[arg(long = "part")]
+        parts: Vec<usize>,
+
+        /// Item STB whose --item rows choose the models to add, e.g. 3ddata/stb/list_pat.stb
+        #[arg(long, requires = "items")]
+        item_stb: Option<String>,
+
+        /// Row of --item-stb whose model to add, can be given multiple times
+        #[arg(long = "item", requires = "item_stb")]
+        items: Vec<usize>,
+
+        /// ZMO to export as an animation, relative to the assets directory
+        #[arg(long = "motion")]
+        motions: Vec<String>,
+
+        /// CHR whose --character motions to add, motions such as walking are tagged as looped,
+        /// relative to the assets directory
+        #[arg(long, requires = "character")]
+        chr: Option<String>,
+
+        /// Character index in --chr whose motions to add
+        #[arg(long, requires = "chr")]
+        character: Option<usize>,
+
+        /// Export another texture for a part as a material variant, in the form
+        /// `name=part:path/to/texture.dds` where part is the index of the --part or --item.
+        #[arg(long = "texture-variant", value_parser = parse_texture_variant)]
+        texture_variants: Vec<TextureVariant>,
+
+        /// Output file path
+        #[arg(short, long = "out")]
+        output: PathBuf,
+
+        /// Ouput GLTF instead of GLB
+        #[arg(long)]
+        gltf: bool,
+    },
+
+    /// Build a ROSE file from JSON or YAML produced by dump
+    Build {
+        /// The .json, .yaml or .yml file to build from
+        input: PathBuf,
+
+        /// Output ROSE file, the file type is chosen by its extension
+        #[arg(short, long = "out")]
+        output: PathBuf,
+    },
+
+    /// Mirror a ZMO across the X axis of its skeleton, e.g. to create a left handed attack from a
+    /// right handed one
+    Mirror {
+        /// The ZMO to mirror
+        input: PathBuf,
+
+        /// ZMD the ZMO animates, left and right bones are paired by name
+        #[arg(long)]
+        skeleton: PathBuf,
+
+        /// Output ZMO file path
+        #[arg(short, long = "out")]
+        output: PathBuf,
+    },
+
+    /// Convert ROSE files and show them in a window for a quick preview, with an orbit camera
+    /// and playback of their animations
+    View {
+        /// List of input files
+        #[arg(required = true)]
+        input: Vec<PathBuf>,
+
+        /// Program to open a temporary .glb of the conversion with instead of the window
+        #[arg(long)]
+        viewer: Option<String>,
+    },
+
+    /// Convert ROSE files and render a preview of the result to a PNG, from a camera which
+    /// frames the whole model
+    Thumbnail {
+        /// List of input files
+        #[arg(required = true)]
+        input: Vec<PathBuf>,
+
+        /// Output PNG file path
+        #[arg(short, long = "out")]
+        output: PathBuf,
+
+        /// Width and height of the image in pixels
+        #[arg(long, default_value_t = 256)]
+        size: u32,
+    },
+
+    /// Convert ROSE files to glTF for each JSON request read from stdin, writing a JSON report
+    /// per line to stdout. Parsed files are cached between requests until they change on disk.
+    Serve {
+        /// Number of parsed files and textures to keep in the cache
+        #[arg(long, default_value_t = 4096)]
+        cache_size: usize,
+    },
+
+    /// Merge .gltf or .glb files into one, sharing identical images, textures and materials
+    Merge {
+        /// The .gltf or .glb files to merge
+        #[arg(required = true)]
+        input: Vec<PathBuf>,
+
+        /// Output file path
+        #[arg(short, long = "out")]
+        output: PathBuf,
+
+        /// Ouput GLTF instead of GLB
+        #[arg(long)]
+        gltf: bool,
+    },
+}
+
+/// Opens a file with `viewer`, or the program the system associates with it
+fn open_in_viewer(path: &Path, viewer: Option<&str>) -> anyhow::Result<()> {
+    let mut command = match viewer {
+        Some(viewer) => process::Command::new(viewer),
+        None if cfg!(target_os = "windows") => {
+            let mut command = process::Command::new("cmd");
+            command.args(["/C", "start", ""]);
+            command
+        }
+        None if cfg!(target_os = "macos") => process::Command::new("open"),
+        None => process::Command::new("xdg-open"),
+    };
+    let status = command
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to run {:?}", command.get_program()))?;
+    if !status.success() {
+        anyhow::bail!("{:?} exited with {}", command.get_program(), status);
+    }
+    Ok(())
+}
+
+fn parse_color(value: &str) -> Result<[f32; 3], String> {
+    let components = value
+        .split(',')
+        .map(|component| component.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid color {}: {}", value, e))?;
+    components
+        .try_into()
+        .map_err(|_| format!("Expected r,g,b, got {}", value))
+}
+
+fn parse_center(value: &str) -> Result<[f32; 2], String> {
+    let components = value
+        .split(',')
+        .map(|component| component.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid center {}: {}", value, e))?;
+    components
+        .try_into()
+        .map_err(|_| format!("Expected x,y, got {}", value))
+}
+
+fn parse_text_format(value: &str) -> Result<TextFormat, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "json" => TextFormat::Json,
+        "yaml" | "yml" => TextFormat::Yaml,
+        _ => return Err(format!("Unknown text format {}", value)),
+    })
+}
+
+fn parse_zone_stats_table(value: &str) -> Result<ZoneStatsTable, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "blocks" => ZoneStatsTable::Blocks,
+        "objects" => ZoneStatsTable::Objects,
+        "textures" => ZoneStatsTable::Textures,
+        _ => return Err(format!("Unknown zone stats table {}", value)),
+    })
+}
+
+fn parse_vertex_attribute(value: &str) -> Result<i32, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "position" => VertexFormat::Position as i32,
+        "normal" => VertexFormat::Normal as i32,
+        "color" => VertexFormat::Color as i32,
+        "bones" => VertexFormat::BoneWeight as i32 | VertexFormat::BoneIndex as i32,
+        "tangent" => VertexFormat::Tangent as i32,
+        "uv1" => VertexFormat::UV1 as i32,
+        "uv2" => VertexFormat::UV2 as i32,
+        "uv3" => VertexFormat::UV3 as i32,
+        "uv4" => VertexFormat::UV4 as i32,
+        _ => return Err(format!("Unknown vertex attribute {}", value)),
+    })
+}
+
+fn parse_engine_hints(value: &str) -> Result<EngineHints, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "none" => EngineHints::None,
+        "godot" => EngineHints::Godot,
+        "unity" => EngineHints::Unity,
+        _ => return Err(format!("Unknown engine {}", value)),
+    })
+}
+
+fn parse_winding(value: &str) -> Result<WindingCorrection, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "none" => WindingCorrection::None,
+        "auto" => WindingCorrection::Auto,
+        "flip" => WindingCorrection::Flip,
+        _ => return Err(format!("Unknown winding {}", value)),
+    })
+}
+
+fn parse_spawn_volume_shape(value: &str) -> Result<SpawnVolumeShape, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "cylinder" => SpawnVolumeShape::Cylinder,
+        "box" => SpawnVolumeShape::Box,
+        _ => return Err(format!("Unknown spawn volume shape {}", value)),
+    })
+}
+
+fn parse_zone_content(value: &str) -> Result<ZoneContent, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "terrain" => ZoneContent::Terrain,
+        "cnst" => ZoneContent::Cnst,
+        "deco" => ZoneContent::Deco,
+        "ocean" => ZoneContent::Ocean,
+        "water" => ZoneContent::Water,
+        "effects" => ZoneContent::Effects,
+        "sounds" => ZoneContent::Sounds,
+        "vegetation" => ZoneContent::Vegetation,
+        "lights" => ZoneContent::Lights,
+        "sun" => ZoneContent::Sun,
+        "spawns" => ZoneContent::Spawns,
+        _ => return Err(format!("Unknown zone content {}", value)),
+    })
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+fn parse_uv_mode(value: &str) -> Result<UvMode, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "keep" => UvMode::Keep,
+        "wrap" => UvMode::Wrap,
+        "clamp" => UvMode::Clamp,
+        "rescale" => UvMode::Rescale,
+        _ => return Err(format!("Unknown UV mode {}", value)),
+    })
+}
+
+fn parse_vertex_buffer_layout(value: &str) -> Result<VertexBufferLayout, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "interleaved" => VertexBufferLayout::Interleaved,
+        "planar" => VertexBufferLayout::Planar,
+        _ => return Err(format!("Unknown vertex buffer layout {}", value)),
+    })
+}
+
+fn parse_image_encoding(value: &str) -> Result<ImageEncoding, String> {
+    let (format, quality) = match value.split_once(':') {
+        Some((format, quality)) => (format, Some(quality)),
+        None => (value, None),
+    };
+    Ok(match (format.to_ascii_lowercase().as_str(), quality) {
+        ("png", None) => ImageEncoding::Png,
+        ("jpeg" | "jpg", quality) => ImageEncoding::Jpeg {
+            quality: match quality {
+                Some(quality) => quality
+                    .parse()
+                    .ok()
+                    .filter(|quality| (1..=100).contains(quality))
+                    .ok_or_else(|| format!("Invalid JPEG quality {}", quality))?,
+                None => 90,
+            },
+        },
+        ("ktx2", _) => return Err("KTX2 textures are not supported, use png or jpeg".to_string()),
+        _ => return Err(format!("Unknown image format {}", value)),
+    })
+}
+
+fn parse_bone_naming(value: &str) -> Result<BoneNaming, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "original" => BoneNaming::Original,
+        "indexed" => BoneNaming::Indexed,
+        _ => return Err(format!("Unknown bone naming {}", value)),
+    })
+}
+
+fn parse_zmd_version(value: &str) -> Result<SkeletonVersion, String> {
+    Ok(
+        match value.to_ascii_lowercase().trim_start_matches("zmd000") {
+            "2" => SkeletonVersion::Version2,
+            "3" => SkeletonVersion::Version3,
+            _ => return Err(format!("Unknown ZMD version {}", value)),
+        },
+    )
+}
+
+fn parse_terrain_lightmap(value: &str) -> Result<TerrainLightmap, String> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "vertex" => TerrainLightmap::VertexColor,
+        "texture" => TerrainLightmap::Texture,
+        _ => return Err(format!("Unknown terrain lightmap mode {}", value)),
+    })
+}
+
+fn parse_prefix_mapping(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(from, to)| (from.to_string(), to.to_string()))
+        .ok_or_else(|| format!("Expected from=to, got {}", value))
+}
+
+fn parse_material_variant(value: &str) -> Result<MaterialVariant, String> {
+    let (name, source) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Expected name=zone, got {}", value))?;
+    Ok(MaterialVariant {
+        name: name.to_string(),
+        source: match source.parse::<usize>() {
+            Ok(row) => MaterialVariantSource::ListZoneRow(row),
+            Err(_) => MaterialVariantSource::ZonPath(PathBuf::from(source)),
+        },
+    })
+}
+
+fn parse_texture_variant(value: &str) -> Result<TextureVariant, String> {
+    let (name, variant) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Expected name=part:texture, got {}", value))?;
+    let (part, texture) = variant
+        .split_once(':')
+        .ok_or_else(|| format!("Expected name=part:texture, got {}", value))?;
+    Ok(TextureVariant {
+        name: name.to_string(),
+        part: part
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid part {}: {}", part, e))?,
+        texture: texture.to_string(),
+    })
+}
+
+fn parse_animation_concat(value: &str) -> Result<AnimationConcat, String> {
+    let (name, animations) = value.split_once('=').unwrap_or((value, ""));
+    if name.is_empty() {
+        return Err(format!("Expected name[=first,second], got {}", value));
+    }
+    Ok(AnimationConcat {
+        name: name.to_string(),
+        animations: animations
+            .split(',')
+            .filter(|animation| !animation.is_empty())
+            .map(str::to_string)
+            .collect(),
+        ..Default::default()
+    })
+}
+
+fn parse_animation_split(value: &str) -> Result<AnimationSplit, String> {
+    let (name, range) = value
+        .rsplit_once(':')
+        .ok_or_else(|| format!("Expected name:start-end, got {}", value))?;
+    let (start_frame, end_frame) = range
+        .split_once('-')
+        .ok_or_else(|| format!("Expected name:start-end, got {}", value))?;
+    let parse_frame = |frame: &str| -> Result<u32, String> {
+        frame
+            .parse::<u32>()
+            .map_err(|e| format!("Invalid frame {}: {}", frame, e))
+    };
+    Ok(AnimationSplit {
+        name: name.to_string(),
+        start_frame: parse_frame(start_frame)?,
+        end_frame: parse_frame(end_frame)?,
+    })
+}
+
+fn parse_animation_loop(value: &str) -> Result<AnimationLoopRule, String> {
+    let (pattern, mode) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("Expected pattern=loop|once, got {}", value))?;
+    Ok(AnimationLoopRule {
+        pattern: pattern.to_string(),
+        mode: match mode.to_ascii_lowercase().as_str() {
+            "loop" => AnimationLoop::Loop,
+            "once" => AnimationLoop::Once,
+            _ => return Err(format!("Unknown animation loop mode {}", mode)),
+        },
+    })
+}
+
+fn parse_animation_override(value: &str) -> Result<AnimationOverride, String> {
+    let (pattern, settings) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("Expected pattern=fps[@start-end], got {}", value))?;
+    let (fps, range) = settings.split_once('@').unwrap_or((settings, ""));
+    let (start_time, end_time) = range.split_once('-').unwrap_or((range, ""));
+
+    let parse_time = |time: &str| -> Result<Option<f32>, String> {
+        if time.is_empty() {
+            Ok(None)
+        } else {
+            time.parse::<f32>()
+                .map(Some)
+                .map_err(|e| format!("Invalid time {}: {}", time, e))
+        }
+    };
+
+    Ok(AnimationOverride {
+        pattern: pattern.to_string(),
+        fps: if fps.is_empty() {
+            None
+        } else {
+            Some(
+                fps.parse::<u32>()
+                    .map_err(|e| format!("Invalid fps {}: {}", fps, e))?,
+            )
+        },
+        start_time: parse_time(start_time)?,
+        end_time: parse_time(end_time)?,
+    })
+}
+
+fn main() {
+    if let Err(error) = run() {
+        let category = ErrorCategory::of(&error);
+        eprintln!("Error ({}): {:?}", category, error);
+        process::exit(category.exit_code());
+    }
+}
+
+fn run() -> anyhow::Result<()> {
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+    if let Some(name) = args.profile.clone() {
+        let config = match &args.config {
+            Some(path) => Config::from_path(path)
+                .with_context(|| format!("Failed to load config {}", path.display()))?,
+            None => Config::default(),
+        };
+        let profile = config
+            .profiles
+            .get(&name)
+            .cloned()
+            .or_else(|| Profile::builtin(&name))
+            .with_context(|| format!("Unknown profile {}", name))?;
+        profile.apply(&mut args, &matches)?;
+    }
 
+    if let Some(Command::Analyze { input }) = &args.command {
+        let (document, buffers, images) = gltf::import(input)
+            .with_context(|| format!("Failed to read GLTF file {}", input.display()))?;
+        let analysis = analyze_gltf(&GltfData {
+            document,
+            buffers,
+            images,
+        });
+        print!("{}", analysis);
+        return Ok(());
+    }
+
+    if let Some(Command::Dump { input, format }) = &args.command {
+        print!("{}", dump_rose_file(input, *format)?);
+        return Ok(());
+    }
+
+    if let Some(Command::Zones {
+        assets,
+        all,
+        zone_ids,
+        output,
+        gltf,
+    }) = &args.command
+    {
+        let format = if *gltf {
+            GltfFormat::Text
+        } else {
+            GltfFormat::Binary
+        };
+        let manifest = export_zones(
+            assets,
+            (!*all).then_some(zone_ids.as_slice()),
+            output,
+            &format,
+            &RoseGltfConvOptions {
+                use_better_heightmap_triangles: true,
+                ..Default::default()
+            },
+        )?;
+        let failed = manifest
+            .zones
+            .iter()
+            .filter(|zone| zone.error.is_some())
+            .count();
+        println!(
+            "Exported {} of {} zones",
+            manifest.zones.len() - failed,
+            manifest.zones.len()
+        );
+        return Ok(());
+    }
+
+    if let Some(Command::ZoneStats { input, format, csv }) = &args.command {
+        let options = RoseGltfConvOptions {
+            filter_block_x: args.filter_block_x,
+            filter_block_y: args.filter_block_y,
+            include_disabled_objects: args.include_disabled_objects,
+            asset_roots: args.asset_roots.clone(),
+            ..Default::default()
+        };
+        let stats = zone_stats(input, &options)?;
+        match (csv, format) {
+            (Some(table), _) => print!("{}", stats.to_csv(*table)),
+            (None, TextFormat::Yaml) => print!("{}", serde_yaml::to_string(&stats)?),
+            (None, TextFormat::Json) => println!("{}", serde_json::to_string_pretty(&stats)?),
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Assemble {
+        assets,
+        skeleton,
+        models,
+        parts,
+        item_stb,
+        items,
+        motions,
+        chr,
+        character,
+        texture_variants,
+        output,
+        gltf,
+    }) = &args.command
+    {
+        let mut assembly = ModelAssembly {
+            name: output
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            skeleton: skeleton.clone(),
+            parts: parts
+                .iter()
+                .map(|&model_id| AssemblyPart {
+                    models: models.clone(),
+                    model_id,
+                })
+                .collect(),
+            motions: motions.clone(),
+            motion_loops: HashMap::new(),
+            texture_variants: texture_variants.clone(),
+        };
+        if let Some(item_stb) = item_stb {
+            let item_stb_path = FsAssetLocator::new(vec![assets.clone()]).resolve(item_stb);
+            let item_stb = ItemStb::from_path(&item_stb_path)
+                .with_context(|| format!("Failed to read {}", item_stb_path.display()))?;
+            assembly.add_items(models, &item_stb, items)?;
+        }
+        if let (Some(chr), Some(character)) = (chr, character) {
+            let chr_path = FsAssetLocator::new(vec![assets.clone()]).resolve(chr);
+            let chr = CHR::from_path(&chr_path)
+                .with_context(|| format!("Failed to read {}", chr_path.display()))?;
+            let character = chr.get(*character).with_context(|| {
+                format!(
+                    "Character {} not found in {}",
+                    character,
+                    chr_path.display()
+                )
+            })?;
+            assembly.add_character_motions(character);
+        }
+
+        let format = if *gltf {
+            GltfFormat::Text
+        } else {
+            GltfFormat::Binary
+        };
+        let options = RoseGltfConvOptions {
+            animation_loops: args.animation_loops.clone(),
+            trim_last_duplicate_frame: args.trim_last_duplicate_frame,
+            retime: args.retime,
+            placeholder_textures: args.placeholder_textures,
+            embedded_images: EmbeddedImageOptions {
+                encoding: args.image_format,
+                max_size: args.max_texture_size,
+                budget: args.texture_budget.map(|budget| budget * 1024 * 1024),
+            },
+            ..Default::default()
+        };
+        let gltf = assemble_model(assets, &assembly, &options)?;
+        let output = output.with_extension(format.file_extension());
+        save_gltf(&gltf, &output, &format).context("Failed to save gltf")?;
+        return Ok(());
+    }
+
+    if let Some(Command::View { input, viewer }) = &args.command {
+        let options = RoseGltfConvOptions {
+            use_better_heightmap_triangles: true,
+            ..Default::default()
+        };
+        let name = input[0]
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        #[cfg(feature = "viewer")]
+        if viewer.is_none() {
+            return viewer::show(&name, &rose_to_gltf_data(input, &options)?);
+        }
+
+        // Without the window, the system's handler for .glb files is used
+        let gltf = rose_to_gltf(input, &options)?;
+        let output = std::env::temp_dir().join(format!("rose-gltf-view-{}.glb", name));
+        save_gltf(&gltf, &output, &GltfFormat::Binary).context("Failed to save gltf")?;
+        println!("Opening {}", output.display());
+        open_in_viewer(&output, viewer.as_deref())?;
+        return Ok(());
+    }
+
+    if let Some(Command::Thumbnail {
+        input,
+        output,
+        size,
+    }) = &args.command
+    {
+        let gltf = rose_to_gltf_data(
+            input,
+            &RoseGltfConvOptions {
+                use_better_heightmap_triangles: true,
+                ..Default::default()
+            },
+        )?;
+        render_thumbnail(&gltf, *size, *size)
+            .save(output)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+        return Ok(());
+    }
+
+    if let Some(Command::Merge {
+        input,
+        output,
+        gltf,
+    }) = &args.command
+    {
+        let inputs = input
+            .iter()
+            .map(|input| {
+                let (document, buffers, images) = gltf::import(input)
+                    .with_context(|| format!("Failed to read GLTF file {}", input.display()))?;
+                Ok(GltfData {
+                    document,
+                    buffers,
+                    images,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let format = if *gltf {
+            GltfFormat::Text
+        } else {
+            GltfFormat::Binary
+        };
+        let merged = merge_gltf(&inputs)?;
+        save_gltf(
+            &merged,
+            &output.with_extension(format.file_extension()),
+            &format,
+        )
+        .context("Failed to save gltf")?;
+        return Ok(());
+    }
+
+    if let Some(Command::Serve { cache_size }) = &args.command {
+        return serve(*cache_size);
+    }
+
+    if let Some(Command::Build { input, output }) = &args.command {
+        let text = std::fs::read_to_string(input)
+            .with_context(|| format!("Failed to read {}", input.display()))?;
+        build_rose_file(&text, TextFormat::from_path(input), output)?;
+        return Ok(());
+    }
+
+    if let Some(Command::Mirror {
+        input,
+        skeleton,
+        output,
+    }) = &args.command
+    {
+        let zmo =
+            ZMO::from_path(input).with_context(|| format!("Failed to read {}", input.display()))?;
+        let zmd = ZMD::from_path(skeleton)
+            .with_context(|| format!("Failed to read {}", skeleton.display()))?;
+        mirror_animation(&zmo, &zmd)
+            .write_to_path(output)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+        return Ok(());
+    }
+
+    if args.report.is_none() {
+        return convert(&args, &mut ConversionReport::default());
+    }
+
+    let start = Instant::now();
+    let mut report = ConversionReport {
+        inputs: args.input.clone(),
+        ..Default::default()
+    };
+    let ((result, phases), warnings) =
+        collect_warnings(|| collect_timings(|| convert(&args, &mut report)));
+    report.warnings.extend(warnings);
+    report.timings.add_phases(phases);
+    if let Err(error) = result.as_ref() {
+        report.error = Some(format!("{:#}", error));
+        report.error_category = Some(ErrorCategory::of(error));
+    }
+    report.timings.total_seconds = start.elapsed().as_secs_f64();
+    report.peak_memory_bytes = peak_memory_bytes();
+    match args.report {
+        Some(TextFormat::Yaml) => print!("{}", serde_yaml::to_string(&report)?),
+        _ => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    if let Err(error) = result {
+        process::exit(ErrorCategory::of(&error).exit_code());
+    }
+    Ok(())
+}
+
+/// Converts the input files to or from glTF, adding what was written to the
+/// report
+fn convert(args: &Args, report: &mut ConversionReport) -> anyhow::Result<()> {
     if args.input.iter().any(|x| {
         x.extension()
             .is_some_and(|extension| extension == "gltf" || extension == "glb")
     }) {
         // GLTF -> ROSE
-        for input_file in &args.input {
-            let (document, buffers, images) =
-                gltf::import(input_file).expect("Failed to read GLTF file");
-            let mut results = gltf_to_rose(
-                &GltfData {
-                    document,
-                    buffers,
-                    images,
-                },
-                &GltfRoseConvOptions {
-                    zmo_fps: args.zmo_fps,
-                },
-            )?;
-            results.save_to_dir(&args.output)?;
-        }
+        let options = gltf_rose_options(args);
+        convert_each_input(args, report, |input_file| {
+            let start = Instant::now();
+            let (document, buffers, images) = gltf::import(input_file)
+                .with_context(|| format!("Failed to read GLTF file {}", input_file.display()))?;
+            let gltf_data = GltfData {
+                document,
+                buffers,
+                images,
+            };
+            let mut results = gltf_to_rose(&gltf_data, &options)?;
+            let convert_seconds = start.elapsed().as_secs_f64();
+
+            let start = Instant::now();
+            let outputs = results.save_to_dir(&args.output)?;
+            Ok(FileConversion {
+                outputs,
+                statistics: args.report.is_some().then(|| analyze_gltf(&gltf_data)),
+                convert_seconds,
+                write_seconds: start.elapsed().as_secs_f64(),
+            })
+        })?;
     } else {
         // ROSE -> GLTF
-        let gltf = rose_to_gltf(
-            &args.input,
-            &RoseGltfConvOptions {
-                filter_block_x: args.filter_block_x,
-                filter_block_y: args.filter_block_y,
-                use_better_heightmap_triangles: args.use_better_heightmap_triangles,
+        let options = RoseGltfConvOptions {
+            filter_block_x: args.filter_block_x,
+            filter_block_y: args.filter_block_y,
+            radius_filter: args
+                .center
+                .zip(args.radius)
+                .map(|(center, radius)| RadiusFilter { center, radius }),
+            height_range: HeightRange {
+                min: args.min_height,
+                max: args.max_height,
             },
-        )?;
+            zone_content: ZoneContentFilter {
+                include: args.only.clone(),
+                exclude: args.skip.clone(),
+            },
+            use_better_heightmap_triangles: args.use_better_heightmap_triangles,
+            skip_meshes: args.skeleton_only || args.animation_only,
+            skip_animations: args.skeleton_only,
+            skip_auto_skeleton: args.no_auto_skeleton,
+            bake_pose_frame: args.bake_pose_frame,
+            morph_target_animation: args.morph_target_animation,
+            material_variants: args.material_variants.clone(),
+            generate_missing_tangents: args.generate_missing_tangents,
+            flip_v: args.flip_v,
+            f32_vertex_colors: args.f32_vertex_colors,
+            winding: args.winding,
+            hard_edge_angle: args.hard_edge_angle,
+            include_disabled_objects: args.include_disabled_objects,
+            ignore_missing_assets: args.ignore_missing,
+            deco_zsc: args.deco_zsc.clone(),
+            cnst_zsc: args.cnst_zsc.clone(),
+            placeholder_textures: args.placeholder_textures,
+            resolve_sound_paths: args.resolve_sound_paths,
+            effect_billboards: args.effect_billboards,
+            spawn_volumes: args.spawn_volumes,
+            billboard_pre_rotation: args.billboard_pre_rotation,
+            vegetation: args
+                .vegetation
+                .as_ref()
+                .map(|path| {
+                    VegetationOptions::from_path(path).map(|mut vegetation| {
+                        if args.vegetation_density_map {
+                            vegetation.mode = VegetationMode::DensityMap;
+                        }
+                        vegetation
+                    })
+                })
+                .transpose()?,
+            quantize_animations: args.quantize_animations,
+            glass_materials: args.glass_materials.then(|| {
+                let mut rules = GlassMaterialRules {
+                    ior: args.glass_ior,
+                    ..Default::default()
+                };
+                if !args.glass_keywords.is_empty() {
+                    rules.keywords = args.glass_keywords.clone();
+                }
+                rules
+            }),
+            water_material: WaterMaterialOptions {
+                texture: (!args.no_water_texture).then(|| args.water_texture.clone()),
+                color: args.water_color,
+                opacity: args.water_opacity,
+                ..Default::default()
+            },
+            terrain_lightmap: args.terrain_lightmap.unwrap_or_default(),
+            terrain_uvs: TerrainUvOptions {
+                detail_repeat: args.terrain_detail_repeat,
+                lightmap_uv: args.terrain_lightmap_uv,
+            },
+            post_process: PostProcessOptions {
+                strip_extras: args.strip_extras,
+                strip_lightmapper_extras: args.strip_lightmapper_extras,
+                strip_animations: args.strip_animations,
+                strip_skins: args.strip_skins,
+                node_name_prefix: args.node_prefix.clone(),
+            },
+            skip_provenance: args.no_provenance,
+            copyright: args.copyright.clone(),
+            generator: args.generator.clone(),
+            vertex_buffer_layout: args.vertex_buffer_layout,
+            engine_hints: args.engine_hints,
+            sort_nodes: args.sort_nodes,
+            heightmap_extras: args.heightmap_extras,
+            low_memory: args.low_memory,
+            embedded_images: EmbeddedImageOptions {
+                encoding: args.image_format,
+                max_size: args.max_texture_size,
+                budget: args.texture_budget.map(|budget| budget * 1024 * 1024),
+            },
+            bone_naming: args.bone_names,
+            bind_pose_animation: args.bind_pose_animation,
+            animation_loops: args.animation_loops.clone(),
+            trim_last_duplicate_frame: args.trim_last_duplicate_frame,
+            animation_splits: args.animation_splits.clone(),
+            mirror_animations: args.mirror_animations,
+            retime: args.retime,
+            asset_roots: args.asset_roots.clone(),
+            asset_locator: None,
+            asset_cache: None,
+            cancellation: None,
+        };
 
         let format = if args.gltf {
             GltfFormat::Text
@@ -79,9 +1680,343 @@ fn main() -> anyhow::Result<()> {
             GltfFormat::Binary
         };
 
-        let output = &args.output.with_extension(format.file_extension());
-        save_gltf(&gltf, output, &format).context("Failed to save gltf")?;
+        if args.dry_run {
+            return print_missing_zone_assets(&args.input, &options);
+        }
+
+        if args.split_blocks {
+            for input_file in &args.input {
+                let start = Instant::now();
+                let manifest = export_zone_blocks(input_file, &args.output, &format, &options)?;
+                report.timings.convert_seconds += start.elapsed().as_secs_f64();
+                report
+                    .outputs
+                    .push(args.output.join(BlockManifest::FILE_NAME));
+                report.outputs.extend(
+                    manifest
+                        .blocks
+                        .iter()
+                        .map(|block| args.output.join(&block.output)),
+                );
+                if args.report.is_none() {
+                    println!(
+                        "Exported {} blocks of {}",
+                        manifest.blocks.len(),
+                        input_file.display()
+                    );
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(cache_dir) = &args.incremental {
+            let [input_file] = args.input.as_slice() else {
+                anyhow::bail!("--incremental converts a single zon");
+            };
+            let start = Instant::now();
+            let output = args.output.with_extension(format.file_extension());
+            let export =
+                export_zone_incremental(input_file, &output, cache_dir, &format, &options)?;
+            report.timings.convert_seconds += start.elapsed().as_secs_f64();
+            report.outputs.push(output);
+            if args.report.is_none() {
+                println!(
+                    "Converted {} of {} blocks of {}",
+                    export.rebuilt.len(),
+                    export.rebuilt.len() + export.reused.len(),
+                    input_file.display()
+                );
+            }
+            return Ok(());
+        }
+
+        if args.batch {
+            return convert_each_input(args, report, |input_file| {
+                let file_name = input_file.file_stem().with_context(|| {
+                    format!("Input file {} has no file name", input_file.display())
+                })?;
+                convert_rose_to_gltf(
+                    args.report.is_some(),
+                    std::slice::from_ref(&input_file.to_path_buf()),
+                    &args.output.join(file_name),
+                    &options,
+                    &format,
+                )
+            });
+        }
+
+        report.add(convert_rose_to_gltf(
+            args.report.is_some(),
+            &args.input,
+            &args.output,
+            &options,
+            &format,
+        )?);
     }
 
     Ok(())
 }
+
+/// Prints the missing assets of the zones in `input_files`, exiting with the
+/// missing dependency exit code when there are any
+fn print_missing_zone_assets(
+    input_files: &[PathBuf],
+    options: &RoseGltfConvOptions,
+) -> anyhow::Result<()> {
+    let mut num_missing = 0;
+    for input_file in input_files {
+        if !input_file
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("zon"))
+        {
+            continue;
+        }
+
+        let missing_assets = find_missing_zone_assets(input_file, options)?;
+        if missing_assets.is_empty() {
+            println!("{}: no missing assets", input_file.display());
+            continue;
+        }
+
+        println!(
+            "{}: {} missing assets",
+            input_file.display(),
+            missing_assets.len()
+        );
+        for missing_asset in missing_assets.iter() {
+            println!("  {}", missing_asset.path);
+            for referenced_by in missing_asset.referenced_by.iter() {
+                println!("    used by {}", referenced_by);
+            }
+        }
+        num_missing += missing_assets.len();
+    }
+
+    if num_missing > 0 {
+        process::exit(ErrorCategory::MissingDependency.exit_code());
+    }
+    Ok(())
+}
+
+/// Converts `input_files` into a single glTF, saved to `output` with the
+/// extension of `format`
+fn convert_rose_to_gltf(
+    statistics: bool,
+    input_files: &[PathBuf],
+    output: &Path,
+    options: &RoseGltfConvOptions,
+    format: &GltfFormat,
+) -> anyhow::Result<FileConversion> {
+    let start = Instant::now();
+    let gltf = rose_to_gltf(input_files, options)?;
+    let convert_seconds = start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    let output = output.with_extension(format.file_extension());
+    save_gltf(&gltf, &output, format).context("Failed to save gltf")?;
+    let write_seconds = start.elapsed().as_secs_f64();
+
+    let statistics = if statistics {
+        Some(analyze_gltf(&GltfData::from_gltf(gltf)?))
+    } else {
+        None
+    };
+    Ok(FileConversion {
+        outputs: vec![output],
+        statistics,
+        convert_seconds,
+        write_seconds,
+    })
+}
+
+/// Answers conversion requests from stdin until it is closed, keeping parsed
+/// files in a cache shared by every request
+fn serve(cache_size: usize) -> anyhow::Result<()> {
+    let asset_cache = Arc::new(AssetCache::new(cache_size));
+    let mut stdout = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let line = line.context("Failed to read request")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let start = Instant::now();
+        let mut request = serde_json::from_str::<serde_json::Value>(&line);
+        let id = request
+            .as_mut()
+            .ok()
+            .and_then(|request| request.as_object_mut())
+            .and_then(|request| request.remove("id"))
+            .unwrap_or_default();
+
+        let mut report = ConversionReport::default();
+        let ((result, phases), warnings) = collect_warnings(|| {
+            collect_timings(|| {
+                let request: ServeRequest = request
+                    .and_then(serde_json::from_value)
+                    .context("Failed to parse request")?;
+                report.inputs = request.inputs.clone();
+                let options = RoseGltfConvOptions {
+                    asset_cache: Some(asset_cache.clone()),
+                    ..request.options
+                };
+                let format = if request.gltf {
+                    GltfFormat::Text
+                } else {
+                    GltfFormat::Binary
+                };
+                convert_rose_to_gltf(
+                    request.statistics,
+                    &request.inputs,
+                    &request.output,
+                    &options,
+                    &format,
+                )
+            })
+        });
+        report.warnings = warnings;
+        report.timings.add_phases(phases);
+        match result {
+            Ok(conversion) => report.add(conversion),
+            Err(error) => {
+                report.error = Some(format!("{:#}", error));
+                report.error_category = Some(ErrorCategory::of(&error));
+            }
+        }
+        report.timings.total_seconds = start.elapsed().as_secs_f64();
+        report.peak_memory_bytes = peak_memory_bytes();
+
+        let response = ServeResponse { id, report };
+        writeln!(stdout, "{}", serde_json::to_string(&response)?)
+            .and_then(|_| stdout.flush())
+            .context("Failed to write response")?;
+    }
+    Ok(())
+}
+
+/// Most inputs converted at once with `--low-memory`
+const LOW_MEMORY_JOBS: usize = 2;
+
+/// Peak resident memory of the process, from `VmHWM` on Linux
+fn peak_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Converts each input file with `f` on a pool of `--jobs` threads, adding
+/// what was written to the report in the order of the inputs. Every input is
+/// converted even when one fails, and the error of the first to fail is
+/// returned.
+fn convert_each_input(
+    args: &Args,
+    report: &mut ConversionReport,
+    f: impl Fn(&Path) -> anyhow::Result<FileConversion> + Sync,
+) -> anyhow::Result<()> {
+    let jobs = if args.low_memory && (args.jobs == 0 || args.jobs > LOW_MEMORY_JOBS) {
+        LOW_MEMORY_JOBS
+    } else {
+        args.jobs
+    };
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .context("Failed to create thread pool")?;
+
+    // Warnings and timings are collected on the thread which converted the
+    // input, so they must be collected by each conversion rather than only by
+    // main
+    let results = pool.install(|| {
+        args.input
+            .par_iter()
+            .map(|input_file| {
+                if args.report.is_some() {
+                    collect_warnings(|| collect_timings(|| f(input_file)))
+                } else {
+                    ((f(input_file), BTreeMap::new()), Vec::new())
+                }
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut first_error = None;
+    for ((result, phases), warnings) in results {
+        report.warnings.extend(warnings);
+        report.timings.add_phases(phases);
+        match result {
+            Ok(conversion) => report.add(conversion),
+            Err(error) => {
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+fn gltf_rose_options(args: &Args) -> GltfRoseConvOptions {
+    GltfRoseConvOptions {
+        zmo_fps: args.zmo_fps.unwrap_or(0),
+        animation_overrides: args.animation_overrides.clone(),
+        retime: args.retime,
+        animation_concat: args
+            .concat_animations
+            .clone()
+            .map(|animation_concat| AnimationConcat {
+                gap_frames: args.concat_gap_frames,
+                blend: args.concat_blend,
+                ..animation_concat
+            }),
+        mesh_filter: NameFilter {
+            include: args.include_mesh.clone(),
+            exclude: args.exclude_mesh.clone(),
+        },
+        skin_filter: NameFilter {
+            include: args.include_skin.clone(),
+            exclude: args.exclude_skin.clone(),
+        },
+        animation_filter: NameFilter {
+            include: args.include_animation.clone(),
+            exclude: args.exclude_animation.clone(),
+        },
+        skip_meshes: args.skeleton_only || args.animation_only,
+        skip_skins: args.animation_only,
+        skip_animations: args.skeleton_only,
+        keep_attributes: args
+            .keep_attributes
+            .as_ref()
+            .map(|attributes| attributes.iter().fold(0, |mask, flag| mask | flag)),
+        uv_modes: std::array::from_fn(|channel| {
+            args.uv_mode
+                .get(channel)
+                .or(args.uv_mode.last())
+                .copied()
+                .unwrap_or_default()
+        }),
+        flip_v: args.flip_v,
+        winding: args.winding,
+        generate_lightmap_uvs: args.generate_lightmap_uvs,
+        generate_zsc: args.zsc,
+        zsc_text: args.zsc_text,
+        generate_him: args.him,
+        zsc_mesh_directory: args.zsc_mesh_dir.clone(),
+        texture_path_rules: TexturePathRules {
+            prefix_mappings: args.texture_prefix_map.clone(),
+            default_directory: args.texture_dir.clone(),
+            dds_extension: args.texture_dds,
+            lowercase: args.texture_lowercase,
+        },
+        copy_textures: args.copy_textures,
+        vertex_layout: VertexLayout {
+            half_float_uvs: args.half_float_uvs,
+            u8_colors: args.u8_colors,
+        },
+        zmd_version: args.zmd_version,
+    }
+}
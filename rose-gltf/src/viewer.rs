@@ -0,0 +1,845 @@
+//! The window of `view`. The converted glTF is drawn with its base color
+//! textures and a light from the camera, which orbits the model, and its
+//! animations are played by posing the vertices on the CPU every frame.
+use std::{sync::Arc, time::Instant};
+
+use anyhow::Context;
+use glam::{Mat4, Vec3};
+use rose_gltf_lib::GltfData;
+use wgpu::util::DeviceExt;
+use winit::{
+    application::ApplicationHandler,
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    event_loop::{ActiveEventLoop, EventLoop},
+    keyboard::{Key, NamedKey},
+    window::{Window, WindowId},
+};
+
+use crate::viewer_scene::{bounds, AlphaMode, Scene, Vertex};
+
+const FIELD_OF_VIEW: f32 = std::f32::consts::FRAC_PI_4;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.2,
+    g: 0.22,
+    b: 0.25,
+    a: 1.0,
+};
+
+/// Radians the camera turns per pixel the mouse moves
+const ORBIT_SPEED: f32 = 0.01;
+
+/// Factor of the distance each step of the mouse wheel zooms by
+const ZOOM_STEP: f32 = 0.9;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct CameraUniform {
+    view_projection: [[f32; 4]; 4],
+    light_direction: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct MaterialUniform {
+    base_color: [f32; 4],
+    alpha_cutoff: f32,
+    alpha_mode: u32,
+    padding: [u32; 2],
+}
+
+/// A camera looking at `target` from `distance` away
+#[derive(Clone, Copy)]
+struct OrbitCamera {
+    target: Vec3,
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+
+    /// Radius of the bounds of the model, for the depth range
+    radius: f32,
+}
+
+impl OrbitCamera {
+    /// A camera which frames the bounds from the front right, as
+    /// `render_thumbnail` does
+    fn framing(min: Vec3, max: Vec3) -> Self {
+        let radius = ((max - min).length() * 0.5).max(0.01);
+        let direction = Vec3::new(1.0, 0.8, 1.3).normalize();
+        Self {
+            target: (min + max) * 0.5,
+            yaw: direction.x.atan2(direction.z),
+            pitch: direction.y.asin(),
+            distance: radius / (FIELD_OF_VIEW * 0.5).sin() * 1.1,
+            radius,
+        }
+    }
+
+    fn direction(&self) -> Vec3 {
+        Vec3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        )
+    }
+
+    fn eye(&self) -> Vec3 {
+        self.target + self.direction() * self.distance
+    }
+
+    fn orbit(&mut self, dx: f32, dy: f32) {
+        self.yaw -= dx * ORBIT_SPEED;
+        self.pitch = (self.pitch + dy * ORBIT_SPEED).clamp(-1.55, 1.55);
+    }
+
+    /// Moves the target with the mouse, `height` is of the window in pixels
+    fn pan(&mut self, dx: f32, dy: f32, height: f32) {
+        let forward = -self.direction();
+        let right = forward.cross(Vec3::Y).normalize_or(Vec3::X);
+        let up = right.cross(forward);
+        let scale = 2.0 * self.distance * (FIELD_OF_VIEW * 0.5).tan() / height.max(1.0);
+        self.target += (up * dy - right * dx) * scale;
+    }
+
+    fn zoom(&mut self, steps: f32) {
+        self.distance = (self.distance * ZOOM_STEP.powf(steps)).max(self.radius * 0.01);
+    }
+
+    fn uniform(&self, aspect: f32) -> CameraUniform {
+        let near = (self.distance * 0.005).max(self.radius * 0.0005);
+        let far = self.distance + self.radius * 4.0;
+        let view = Mat4::look_at_rh(self.eye(), self.target, Vec3::Y);
+        let projection = Mat4::perspective_rh(FIELD_OF_VIEW, aspect, near, far);
+        CameraUniform {
+            view_projection: (projection * view).to_cols_array_2d(),
+            light_direction: self.direction().extend(0.0).to_array(),
+        }
+    }
+}
+
+/// The animation being played and where it is at
+struct Playback {
+    animation: Option<usize>,
+    time: f32,
+    playing: bool,
+    last_frame: Instant,
+}
+
+struct Mesh {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+    material: Option<usize>,
+    blend: bool,
+}
+
+struct Gpu {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    depth: wgpu::TextureView,
+    opaque_pipeline: wgpu::RenderPipeline,
+    blend_pipeline: wgpu::RenderPipeline,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    materials: Vec<wgpu::BindGroup>,
+    default_material: wgpu::BindGroup,
+
+    /// Meshes of `Scene::primitives`, by index
+    meshes: Vec<Option<Mesh>>,
+}
+
+struct Viewer<'a> {
+    name: &'a str,
+    gltf: &'a GltfData,
+    scene: Scene,
+    home: OrbitCamera,
+    camera: OrbitCamera,
+    playback: Playback,
+    gpu: Option<Gpu>,
+    cursor: Option<PhysicalPosition<f64>>,
+    orbiting: bool,
+    panning: bool,
+    error: Option<anyhow::Error>,
+}
+
+fn create_depth(device: &wgpu::Device, width: u32, height: u32) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("depth"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// RGBA8 pixels of an image, or `None` for formats with more than 8 bits a
+/// channel
+fn rgba8(image: &gltf::image::Data) -> Option<Vec<u8>> {
+    use gltf::image::Format;
+    let pixels = &image.pixels;
+    Some(match image.format {
+        Format::R8G8B8A8 => pixels.clone(),
+        Format::R8G8B8 => pixels
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        Format::R8G8 => pixels
+            .chunks_exact(2)
+            .flat_map(|rg| [rg[0], rg[0], rg[0], rg[1]])
+            .collect(),
+        Format::R8 => pixels.iter().flat_map(|&r| [r, r, r, 255]).collect(),
+        _ => return None,
+    })
+}
+
+fn create_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+) -> wgpu::TextureView {
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        pixels,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(width * 4),
+            rows_per_image: Some(height),
+        },
+        size,
+    );
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    blend: bool,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(if blend { "blend" } else { "opaque" }),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2],
+            }],
+        },
+        // ROSE materials are often two sided, so nothing is culled
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: !blend,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: Default::default(),
+            bias: Default::default(),
+        }),
+        multisample: Default::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: blend.then_some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
+
+impl Gpu {
+    fn new(window: Arc<Window>, gltf: &GltfData, scene: &Scene) -> anyhow::Result<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let surface = instance
+            .create_surface(window.clone())
+            .context("Failed to create a surface for the viewer window")?;
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }))
+        .context("No graphics adapter can draw to the viewer window")?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::empty(),
+                required_limits:
+                    wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+                memory_hints: Default::default(),
+            },
+            None,
+        ))
+        .context("Failed to create a graphics device")?;
+
+        let size = window.inner_size();
+        let (width, height) = (size.width.max(1), size.height.max(1));
+        let mut config = surface
+            .get_default_config(&adapter, width, height)
+            .context("The viewer window cannot be drawn to by the graphics adapter")?;
+        if let Some(format) = surface
+            .get_capabilities(&adapter)
+            .formats
+            .into_iter()
+            .find(|format| format.is_srgb())
+        {
+            config.format = format;
+        }
+        surface.configure(&device, &config);
+
+        let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("camera"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let material_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("material"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&camera_layout, &material_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::include_wgsl!("viewer.wgsl"));
+        let opaque_pipeline = create_pipeline(&device, &layout, &shader, config.format, false);
+        let blend_pipeline = create_pipeline(&device, &layout, &shader, config.format, true);
+
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("camera"),
+            size: std::mem::size_of::<CameraUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("camera"),
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        // Images without 8 bit channels are drawn white
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let white = create_texture(&device, &queue, 1, 1, &[255; 4]);
+        let images: Vec<Option<wgpu::TextureView>> = gltf
+            .images
+            .iter()
+            .map(|image| {
+                rgba8(image).map(|pixels| {
+                    create_texture(&device, &queue, image.width, image.height, &pixels)
+                })
+            })
+            .collect();
+        let create_material = |uniform: MaterialUniform, texture: &wgpu::TextureView| {
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("material"),
+                contents: bytemuck::bytes_of(&uniform),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("material"),
+                layout: &material_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(texture),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+            })
+        };
+        let materials = scene
+            .materials
+            .iter()
+            .map(|material| {
+                let texture = material
+                    .image
+                    .and_then(|image| images[image].as_ref())
+                    .unwrap_or(&white);
+                create_material(
+                    MaterialUniform {
+                        base_color: material.base_color.to_array(),
+                        alpha_cutoff: material.alpha_cutoff,
+                        alpha_mode: material.alpha_mode as u32,
+                        padding: [0; 2],
+                    },
+                    texture,
+                )
+            })
+            .collect();
+        let default_material = create_material(
+            MaterialUniform {
+                base_color: [1.0; 4],
+                alpha_cutoff: 0.5,
+                alpha_mode: AlphaMode::Opaque as u32,
+                padding: [0; 2],
+            },
+            &white,
+        );
+
+        let pose = scene.pose(None, 0.0);
+        let meshes = scene
+            .primitives
+            .iter()
+            .map(|primitive| {
+                if primitive.indices.is_empty() {
+                    return None;
+                }
+                let vertices = scene.vertices(primitive, &pose);
+                Some(Mesh {
+                    vertex_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("vertices"),
+                        contents: bytemuck::cast_slice(&vertices),
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    }),
+                    index_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("indices"),
+                        contents: bytemuck::cast_slice(&primitive.indices),
+                        usage: wgpu::BufferUsages::INDEX,
+                    }),
+                    index_count: primitive.indices.len() as u32,
+                    material: primitive.material,
+                    blend: primitive
+                        .material
+                        .and_then(|material| scene.materials.get(material))
+                        .is_some_and(|material| material.alpha_mode == AlphaMode::Blend),
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            window,
+            depth: create_depth(&device, width, height),
+            surface,
+            device,
+            queue,
+            config,
+            opaque_pipeline,
+            blend_pipeline,
+            camera_buffer,
+            camera_bind_group,
+            materials,
+            default_material,
+            meshes,
+        })
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        self.config.width = size.width;
+        self.config.height = size.height;
+        self.surface.configure(&self.device, &self.config);
+        self.depth = create_depth(&self.device, size.width, size.height);
+    }
+
+    /// Writes the vertices of `scene` at `time` of `animation`
+    fn pose(&self, scene: &Scene, animation: Option<usize>, time: f32) {
+        let pose = scene.pose(animation, time);
+        for (primitive, mesh) in scene.primitives.iter().zip(self.meshes.iter()) {
+            if let Some(mesh) = mesh {
+                let vertices = scene.vertices(primitive, &pose);
+                self.queue
+                    .write_buffer(&mesh.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+            }
+        }
+    }
+
+    fn render(&mut self, camera: &OrbitCamera) -> anyhow::Result<()> {
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(&self.device, &self.config);
+                self.window.request_redraw();
+                return Ok(());
+            }
+            Err(wgpu::SurfaceError::Timeout) => return Ok(()),
+            Err(error) => return Err(error).context("Failed to draw the viewer window"),
+        };
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let aspect = self.config.width as f32 / self.config.height as f32;
+        self.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::bytes_of(&camera.uniform(aspect)),
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(CLEAR_COLOR),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_bind_group(0, &self.camera_bind_group, &[]);
+
+            // Blended meshes after the rest, so what is behind them is drawn
+            for (pipeline, blend) in [(&self.opaque_pipeline, false), (&self.blend_pipeline, true)]
+            {
+                pass.set_pipeline(pipeline);
+                for mesh in self.meshes.iter().flatten() {
+                    if mesh.blend != blend {
+                        continue;
+                    }
+                    let material = mesh
+                        .material
+                        .and_then(|material| self.materials.get(material))
+                        .unwrap_or(&self.default_material);
+                    pass.set_bind_group(1, material, &[]);
+                    pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                    pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+                }
+            }
+        }
+        self.queue.submit([encoder.finish()]);
+        self.window.pre_present_notify();
+        frame.present();
+        Ok(())
+    }
+}
+
+impl Viewer<'_> {
+    fn title(&self) -> String {
+        let animation = match self
+            .playback
+            .animation
+            .and_then(|index| self.scene.animations.get(index).map(|a| (index, a)))
+        {
+            Some((index, animation)) => format!(
+                " - {} ({}/{}){}",
+                animation.name,
+                index + 1,
+                self.scene.animations.len(),
+                if self.playback.playing {
+                    ""
+                } else {
+                    ", paused"
+                }
+            ),
+            None if self.scene.animations.is_empty() => String::new(),
+            None => " - rest pose".to_string(),
+        };
+        format!("{}{} - rose-gltf", self.name, animation)
+    }
+
+    /// Switches to the next or previous animation, with the rest pose
+    /// between the last and the first
+    fn switch_animation(&mut self, step: isize) {
+        let count = self.scene.animations.len() as isize + 1;
+        let current = self
+            .playback
+            .animation
+            .map_or(0, |index| index as isize + 1);
+        let next = (current + step).rem_euclid(count);
+        self.playback.animation = (next > 0).then(|| next as usize - 1);
+        self.playback.time = 0.0;
+        self.playback.last_frame = Instant::now();
+        if let Some(gpu) = &self.gpu {
+            gpu.pose(&self.scene, self.playback.animation, 0.0);
+        }
+        self.update_title();
+    }
+
+    fn request_redraw(&self) {
+        if let Some(gpu) = &self.gpu {
+            gpu.window.request_redraw();
+        }
+    }
+
+    fn update_title(&self) {
+        if let Some(gpu) = &self.gpu {
+            gpu.window.set_title(&self.title());
+        }
+        self.request_redraw();
+    }
+
+    fn is_playing(&self) -> bool {
+        self.playback.playing && self.scene.is_animated(self.playback.animation)
+    }
+
+    fn key_pressed(&mut self, event_loop: &ActiveEventLoop, key: &Key) {
+        match key.as_ref() {
+            Key::Named(NamedKey::Escape) => event_loop.exit(),
+            Key::Named(NamedKey::Space) => {
+                self.playback.playing = !self.playback.playing;
+                self.playback.last_frame = Instant::now();
+                self.update_title();
+            }
+            Key::Named(NamedKey::ArrowRight) => self.switch_animation(1),
+            Key::Named(NamedKey::ArrowLeft) => self.switch_animation(-1),
+            Key::Character("r" | "R") => {
+                self.camera = self.home;
+                self.request_redraw();
+            }
+            _ => {}
+        }
+    }
+
+    fn redraw(&mut self) -> anyhow::Result<()> {
+        let now = Instant::now();
+        if self.is_playing() {
+            let duration = self
+                .playback
+                .animation
+                .map_or(0.0, |index| self.scene.animations[index].duration);
+            let elapsed = (now - self.playback.last_frame).as_secs_f32();
+            self.playback.time = (self.playback.time + elapsed) % duration;
+            if let Some(gpu) = &self.gpu {
+                gpu.pose(&self.scene, self.playback.animation, self.playback.time);
+            }
+        }
+        self.playback.last_frame = now;
+        match &mut self.gpu {
+            Some(gpu) => gpu.render(&self.camera),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ApplicationHandler for Viewer<'_> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.gpu.is_some() {
+            return;
+        }
+        let gpu = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title(self.title())
+                    .with_inner_size(PhysicalSize::new(1280, 720)),
+            )
+            .context("Failed to create the viewer window")
+            .and_then(|window| Gpu::new(Arc::new(window), self.gltf, &self.scene));
+        match gpu {
+            Ok(gpu) => {
+                gpu.pose(&self.scene, self.playback.animation, 0.0);
+                self.playback.last_frame = Instant::now();
+                self.gpu = Some(gpu);
+            }
+            Err(error) => {
+                self.error = Some(error);
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => {
+                if let Some(gpu) = &mut self.gpu {
+                    gpu.resize(size);
+                    gpu.window.request_redraw();
+                }
+            }
+            WindowEvent::RedrawRequested => {
+                if let Err(error) = self.redraw() {
+                    self.error = Some(error);
+                    event_loop.exit();
+                }
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key,
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => self.key_pressed(event_loop, &logical_key),
+            WindowEvent::MouseInput { state, button, .. } => {
+                let pressed = state == ElementState::Pressed;
+                match button {
+                    MouseButton::Left => self.orbiting = pressed,
+                    MouseButton::Right | MouseButton::Middle => self.panning = pressed,
+                    _ => {}
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if let Some(last) = self.cursor.replace(position) {
+                    let (dx, dy) = ((position.x - last.x) as f32, (position.y - last.y) as f32);
+                    if self.orbiting {
+                        self.camera.orbit(dx, dy);
+                    } else if self.panning {
+                        let height = self.gpu.as_ref().map_or(1, |gpu| gpu.config.height);
+                        self.camera.pan(dx, dy, height as f32);
+                    }
+                    if self.orbiting || self.panning {
+                        self.request_redraw();
+                    }
+                }
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.camera.zoom(match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 40.0,
+                });
+                self.request_redraw();
+            }
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, _: &ActiveEventLoop) {
+        // Drawn continuously only while an animation plays
+        if self.is_playing() {
+            self.request_redraw();
+        }
+    }
+}
+
+/// Opens a window showing `gltf` until it is closed. The left mouse button
+/// orbits the camera, the right or middle one pans and the wheel zooms.
+/// Space plays and pauses the animation, the left and right arrows switch
+/// between the animations, R resets the camera and Escape closes the window.
+pub fn show(name: &str, gltf: &GltfData) -> anyhow::Result<()> {
+    let scene = Scene::new(gltf);
+    let rest_pose = scene.pose(None, 0.0);
+    let vertices: Vec<Vertex> = scene
+        .primitives
+        .iter()
+        .flat_map(|primitive| scene.vertices(primitive, &rest_pose))
+        .collect();
+    let (min, max) = bounds(&vertices).unwrap_or((Vec3::splat(-1.0), Vec3::splat(1.0)));
+    let camera = OrbitCamera::framing(min, max);
+
+    let event_loop = EventLoop::new().context("Failed to open the viewer window")?;
+    let mut viewer = Viewer {
+        name,
+        gltf,
+        playback: Playback {
+            animation: (!scene.animations.is_empty()).then_some(0),
+            time: 0.0,
+            playing: true,
+            last_frame: Instant::now(),
+        },
+        scene,
+        home: camera,
+        camera,
+        gpu: None,
+        cursor: None,
+        orbiting: false,
+        panning: false,
+        error: None,
+    };
+    event_loop
+        .run_app(&mut viewer)
+        .context("Failed to run the viewer window")?;
+    match viewer.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
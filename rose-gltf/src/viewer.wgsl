@@ -0,0 +1,51 @@
+struct Camera {
+    view_projection: mat4x4<f32>,
+    light_direction: vec4<f32>,
+};
+
+struct Material {
+    base_color: vec4<f32>,
+    alpha_cutoff: f32,
+    // 0 opaque, 1 mask, 2 blend
+    alpha_mode: u32,
+    _padding: vec2<u32>,
+};
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(1) @binding(0) var<uniform> material: Material;
+@group(1) @binding(1) var base_color_texture: texture_2d<f32>;
+@group(1) @binding(2) var base_color_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = camera.view_projection * vec4<f32>(in.position, 1.0);
+    out.normal = in.normal;
+    out.uv = in.uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = material.base_color * textureSample(base_color_texture, base_color_sampler, in.uv);
+    if material.alpha_mode == 1u && color.a < material.alpha_cutoff {
+        discard;
+    }
+
+    // Lit from the camera, both sides as faces are not culled
+    let light = 0.35 + 0.65 * abs(dot(normalize(in.normal), camera.light_direction.xyz));
+    let alpha = select(1.0, color.a, material.alpha_mode == 2u);
+    return vec4<f32>(color.rgb * light, alpha);
+}
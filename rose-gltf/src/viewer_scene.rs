@@ -0,0 +1,605 @@
+//! The meshes, skins and animations of a glTF flattened for `viewer`, and
+//! posing them at a time of an animation on the CPU.
+use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
+use gltf::animation::{util::ReadOutputs, Interpolation};
+use rose_gltf_lib::GltfData;
+
+/// A vertex in world space, as uploaded to the GPU
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+/// How the alpha of a material is used, matching `alpha_mode` of the shader
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlphaMode {
+    Opaque = 0,
+    Mask = 1,
+    Blend = 2,
+}
+
+pub struct Material {
+    pub base_color: Vec4,
+
+    /// Index of the base color image in `GltfData::images`
+    pub image: Option<usize>,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+}
+
+/// Morph target deltas of a primitive, empty when it does not have the
+/// attribute
+struct MorphTarget {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+}
+
+pub struct Primitive {
+    /// Node the primitive is drawn at
+    node: usize,
+    skin: Option<usize>,
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    joints: Vec<[u16; 4]>,
+    weights: Vec<[f32; 4]>,
+    targets: Vec<MorphTarget>,
+    pub indices: Vec<u32>,
+    pub material: Option<usize>,
+}
+
+struct Skin {
+    joints: Vec<usize>,
+    inverse_bind_matrices: Vec<Mat4>,
+}
+
+#[derive(Clone, Copy)]
+enum Property {
+    Translation,
+    Rotation,
+    Scale,
+    Weights,
+}
+
+struct Channel {
+    node: usize,
+    property: Property,
+    interpolation: Interpolation,
+    times: Vec<f32>,
+
+    /// Values of the keyframes, `components` after another, with the in and
+    /// out tangents around each value for cubic spline interpolation
+    values: Vec<f32>,
+    components: usize,
+}
+
+pub struct Animation {
+    pub name: String,
+    pub duration: f32,
+    channels: Vec<Channel>,
+}
+
+#[derive(Clone, Copy)]
+struct Transform {
+    translation: Vec3,
+    rotation: Quat,
+    scale: Vec3,
+}
+
+impl Transform {
+    fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+/// Transforms and morph target weights of every node at a time of an
+/// animation
+pub struct Pose {
+    globals: Vec<Mat4>,
+    weights: Vec<Vec<f32>>,
+}
+
+pub struct Scene {
+    roots: Vec<usize>,
+    children: Vec<Vec<usize>>,
+    rest: Vec<Transform>,
+    rest_weights: Vec<Vec<f32>>,
+    skins: Vec<Skin>,
+    pub primitives: Vec<Primitive>,
+    pub materials: Vec<Material>,
+    pub animations: Vec<Animation>,
+}
+
+/// Normals of the triangles around each vertex, for meshes without normals
+fn smooth_normals(positions: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| index as usize);
+        let (Some(pa), Some(pb), Some(pc)) = (positions.get(a), positions.get(b), positions.get(c))
+        else {
+            continue;
+        };
+        let normal = (*pb - *pa).cross(*pc - *pa);
+        for index in [a, b, c] {
+            normals[index] += normal;
+        }
+    }
+    normals
+        .into_iter()
+        .map(|normal| normal.normalize_or(Vec3::Y))
+        .collect()
+}
+
+fn load_materials(gltf: &GltfData) -> Vec<Material> {
+    gltf.document
+        .materials()
+        .map(|material| {
+            let pbr = material.pbr_metallic_roughness();
+            Material {
+                base_color: Vec4::from_array(pbr.base_color_factor()),
+                image: pbr
+                    .base_color_texture()
+                    .map(|info| info.texture().source().index())
+                    .filter(|&image| image < gltf.images.len()),
+                alpha_mode: match material.alpha_mode() {
+                    gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+                    gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+                    gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+                },
+                alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+            }
+        })
+        .collect()
+}
+
+fn load_primitives(gltf: &GltfData, node: &gltf::Node, primitives: &mut Vec<Primitive>) {
+    let Some(mesh) = node.mesh() else {
+        return;
+    };
+    for primitive in mesh.primitives() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            continue;
+        }
+        let reader = primitive.reader(|buffer| gltf.buffers.get(buffer.index()).map(|b| &b[..]));
+        let Some(positions) = reader.read_positions() else {
+            continue;
+        };
+        let positions: Vec<Vec3> = positions.map(Vec3::from_array).collect();
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        let indices: Vec<u32> = indices
+            .chunks_exact(3)
+            .filter(|triangle| {
+                triangle
+                    .iter()
+                    .all(|&index| (index as usize) < positions.len())
+            })
+            .flatten()
+            .copied()
+            .collect();
+        let normals = match reader.read_normals() {
+            Some(normals) => normals.map(Vec3::from_array).collect(),
+            None => smooth_normals(&positions, &indices),
+        };
+        let uvs = reader
+            .read_tex_coords(0)
+            .map(|uvs| uvs.into_f32().map(Vec2::from_array).collect())
+            .unwrap_or_else(|| vec![Vec2::ZERO; positions.len()]);
+        let skin = node.skin().map(|skin| skin.index());
+        let (joints, weights) = match (skin, reader.read_joints(0), reader.read_weights(0)) {
+            (Some(_), Some(joints), Some(weights)) => {
+                (joints.into_u16().collect(), weights.into_f32().collect())
+            }
+            _ => (Vec::new(), Vec::new()),
+        };
+        let targets = reader
+            .read_morph_targets()
+            .map(|(positions, normals, _)| MorphTarget {
+                positions: positions
+                    .map(|positions| positions.map(Vec3::from_array).collect())
+                    .unwrap_or_default(),
+                normals: normals
+                    .map(|normals| normals.map(Vec3::from_array).collect())
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        primitives.push(Primitive {
+            node: node.index(),
+            skin: skin.filter(|_| !joints.is_empty()),
+            positions,
+            normals,
+            uvs,
+            joints,
+            weights,
+            targets,
+            indices,
+            material: primitive.material().index(),
+        });
+    }
+}
+
+fn load_animation(gltf: &GltfData, animation: gltf::Animation) -> Animation {
+    let mut channels = Vec::new();
+    for channel in animation.channels() {
+        let reader = channel.reader(|buffer| gltf.buffers.get(buffer.index()).map(|b| &b[..]));
+        let Some(times) = reader.read_inputs() else {
+            continue;
+        };
+        let times: Vec<f32> = times.collect();
+        let (property, values, components): (Property, Vec<f32>, usize) = match reader
+            .read_outputs()
+        {
+            Some(ReadOutputs::Translations(values)) => {
+                (Property::Translation, values.flatten().collect(), 3)
+            }
+            Some(ReadOutputs::Rotations(values)) => {
+                (Property::Rotation, values.into_f32().flatten().collect(), 4)
+            }
+            Some(ReadOutputs::Scales(values)) => (Property::Scale, values.flatten().collect(), 3),
+            Some(ReadOutputs::MorphTargetWeights(values)) => {
+                let values: Vec<f32> = values.into_f32().collect();
+                let keyframes = match channel.sampler().interpolation() {
+                    Interpolation::CubicSpline => times.len() * 3,
+                    _ => times.len(),
+                };
+                let components = values.len() / keyframes.max(1);
+                (Property::Weights, values, components)
+            }
+            None => continue,
+        };
+        if times.is_empty() || components == 0 {
+            continue;
+        }
+        channels.push(Channel {
+            node: channel.target().node().index(),
+            property,
+            interpolation: channel.sampler().interpolation(),
+            times,
+            values,
+            components,
+        });
+    }
+
+    let duration = channels
+        .iter()
+        .filter_map(|channel| channel.times.last().copied())
+        .fold(0.0, f32::max);
+    Animation {
+        name: animation
+            .name()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("animation_{}", animation.index())),
+        duration,
+        channels,
+    }
+}
+
+impl Channel {
+    /// The value of keyframe `index`, skipping the tangents of cubic splines
+    fn keyframe(&self, index: usize) -> &[f32] {
+        let index = match self.interpolation {
+            Interpolation::CubicSpline => index * 3 + 1,
+            _ => index,
+        };
+        self.values
+            .get(index * self.components..(index + 1) * self.components)
+            .unwrap_or(&[])
+    }
+
+    /// The value at `time`, cubic splines are interpolated linearly between
+    /// their keyframes
+    fn sample(&self, time: f32) -> Vec<f32> {
+        let next = self.times.partition_point(|&keyframe| keyframe <= time);
+        if next == 0 {
+            return self.keyframe(0).to_vec();
+        }
+        if next == self.times.len() || self.interpolation == Interpolation::Step {
+            return self.keyframe(next - 1).to_vec();
+        }
+
+        let (start, end) = (self.times[next - 1], self.times[next]);
+        let t = if end > start {
+            (time - start) / (end - start)
+        } else {
+            0.0
+        };
+        let (before, after) = (self.keyframe(next - 1), self.keyframe(next));
+        match self.property {
+            Property::Rotation if before.len() == 4 && after.len() == 4 => Quat::from_slice(before)
+                .slerp(Quat::from_slice(after), t)
+                .to_array()
+                .to_vec(),
+            _ => before
+                .iter()
+                .zip(after)
+                .map(|(before, after)| before + (after - before) * t)
+                .collect(),
+        }
+    }
+}
+
+impl Scene {
+    pub fn new(gltf: &GltfData) -> Self {
+        let nodes: Vec<gltf::Node> = gltf.document.nodes().collect();
+        let rest = nodes
+            .iter()
+            .map(|node| {
+                let (translation, rotation, scale) = node.transform().decomposed();
+                Transform {
+                    translation: Vec3::from_array(translation),
+                    rotation: Quat::from_array(rotation),
+                    scale: Vec3::from_array(scale),
+                }
+            })
+            .collect();
+        let rest_weights = nodes
+            .iter()
+            .map(|node| {
+                node.weights()
+                    .or_else(|| node.mesh().and_then(|mesh| mesh.weights()))
+                    .map(|weights| weights.to_vec())
+                    .unwrap_or_default()
+            })
+            .collect();
+        let children = nodes
+            .iter()
+            .map(|node| node.children().map(|child| child.index()).collect())
+            .collect();
+        let roots: Vec<usize> = gltf
+            .document
+            .default_scene()
+            .or_else(|| gltf.document.scenes().next())
+            .map(|scene| scene.nodes().map(|node| node.index()).collect())
+            .unwrap_or_default();
+
+        // Only the nodes of the scene are drawn
+        let mut primitives = Vec::new();
+        let mut stack: Vec<usize> = roots.clone();
+        while let Some(index) = stack.pop() {
+            load_primitives(gltf, &nodes[index], &mut primitives);
+            stack.extend(nodes[index].children().map(|child| child.index()));
+        }
+
+        let skins = gltf
+            .document
+            .skins()
+            .map(|skin| {
+                let reader = skin.reader(|buffer| gltf.buffers.get(buffer.index()).map(|b| &b[..]));
+                let joints: Vec<usize> = skin.joints().map(|joint| joint.index()).collect();
+                let inverse_bind_matrices = match reader.read_inverse_bind_matrices() {
+                    Some(matrices) => matrices
+                        .map(|matrix| Mat4::from_cols_array_2d(&matrix))
+                        .collect(),
+                    None => vec![Mat4::IDENTITY; joints.len()],
+                };
+                Skin {
+                    joints,
+                    inverse_bind_matrices,
+                }
+            })
+            .collect();
+
+        Self {
+            roots,
+            children,
+            rest,
+            rest_weights,
+            skins,
+            primitives,
+            materials: load_materials(gltf),
+            animations: gltf
+                .document
+                .animations()
+                .map(|animation| load_animation(gltf, animation))
+                .collect(),
+        }
+    }
+
+    /// The pose of the scene at `time` of `animation`, or its rest pose
+    pub fn pose(&self, animation: Option<usize>, time: f32) -> Pose {
+        let mut locals = self.rest.clone();
+        let mut weights = self.rest_weights.clone();
+        if let Some(animation) = animation.and_then(|animation| self.animations.get(animation)) {
+            for channel in animation.channels.iter() {
+                let value = channel.sample(time);
+                let Some(local) = locals.get_mut(channel.node) else {
+                    continue;
+                };
+                match channel.property {
+                    Property::Translation if value.len() == 3 => {
+                        local.translation = Vec3::from_slice(&value)
+                    }
+                    Property::Rotation if value.len() == 4 => {
+                        local.rotation = Quat::from_slice(&value).normalize()
+                    }
+                    Property::Scale if value.len() == 3 => local.scale = Vec3::from_slice(&value),
+                    Property::Weights => weights[channel.node] = value,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut globals = vec![Mat4::IDENTITY; self.rest.len()];
+        let mut stack: Vec<(usize, Mat4)> = self
+            .roots
+            .iter()
+            .map(|&root| (root, Mat4::IDENTITY))
+            .collect();
+        while let Some((index, parent)) = stack.pop() {
+            globals[index] = parent * locals[index].matrix();
+            stack.extend(
+                self.children[index]
+                    .iter()
+                    .map(|&child| (child, globals[index])),
+            );
+        }
+        Pose { globals, weights }
+    }
+
+    /// The vertices of `primitive` in world space at `pose`, morphed and
+    /// skinned
+    pub fn vertices(&self, primitive: &Primitive, pose: &Pose) -> Vec<Vertex> {
+        let weights = pose
+            .weights
+            .get(primitive.node)
+            .map(|weights| weights.as_slice())
+            .unwrap_or(&[]);
+        let joint_matrices: Option<Vec<Mat4>> = primitive
+            .skin
+            .and_then(|skin| self.skins.get(skin))
+            .map(|skin| {
+                skin.joints
+                    .iter()
+                    .zip(skin.inverse_bind_matrices.iter())
+                    .map(|(&joint, inverse_bind)| pose.globals[joint] * *inverse_bind)
+                    .collect()
+            });
+        let node_matrix = pose.globals[primitive.node];
+
+        (0..primitive.positions.len())
+            .map(|index| {
+                let mut position = primitive.positions[index];
+                let mut normal = primitive.normals.get(index).copied().unwrap_or(Vec3::Y);
+                for (target, &weight) in primitive.targets.iter().zip(weights) {
+                    if weight == 0.0 {
+                        continue;
+                    }
+                    if let Some(delta) = target.positions.get(index) {
+                        position += *delta * weight;
+                    }
+                    if let Some(delta) = target.normals.get(index) {
+                        normal += *delta * weight;
+                    }
+                }
+
+                let matrix = match &joint_matrices {
+                    Some(joint_matrices) => {
+                        let joints = primitive.joints[index];
+                        let weights = primitive.weights[index];
+                        let mut matrix = Mat4::ZERO;
+                        for (joint, weight) in joints.iter().zip(weights) {
+                            if let Some(joint_matrix) = joint_matrices.get(*joint as usize) {
+                                matrix += *joint_matrix * weight;
+                            }
+                        }
+                        if matrix == Mat4::ZERO {
+                            node_matrix
+                        } else {
+                            matrix
+                        }
+                    }
+                    None => node_matrix,
+                };
+                Vertex {
+                    position: matrix.transform_point3(position).to_array(),
+                    normal: matrix
+                        .transform_vector3(normal)
+                        .normalize_or(Vec3::Y)
+                        .to_array(),
+                    uv: primitive
+                        .uvs
+                        .get(index)
+                        .copied()
+                        .unwrap_or_default()
+                        .to_array(),
+                }
+            })
+            .collect()
+    }
+
+    /// Whether posing at another time of `animation` can move any vertex
+    pub fn is_animated(&self, animation: Option<usize>) -> bool {
+        animation
+            .and_then(|animation| self.animations.get(animation))
+            .is_some_and(|animation| !animation.channels.is_empty() && animation.duration > 0.0)
+    }
+}
+
+/// Bounds of vertices in world space
+pub fn bounds<'a>(vertices: impl IntoIterator<Item = &'a Vertex>) -> Option<(Vec3, Vec3)> {
+    vertices
+        .into_iter()
+        .map(|vertex| Vec3::from_array(vertex.position))
+        .fold(None, |bounds, position| {
+            Some(match bounds {
+                Some((min, max)) => (position.min(min), position.max(max)),
+                None => (position, position),
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use rose_gltf_lib::{
+        bake_pose, rose_to_gltf_data,
+        test_assets::{skinned_quad_zms, two_bone_zmd, two_bone_zmo, write_test_assets},
+        RoseGltfConvOptions,
+    };
+
+    use super::*;
+
+    /// Checks the vertices posed at each frame of the animation are where
+    /// `bake_pose` puts them
+    fn assert_matches_bake_pose(name: &str, options: RoseGltfConvOptions) {
+        let directory = std::env::temp_dir().join("rose-gltf-tests").join(name);
+        let assets = write_test_assets(&directory).unwrap();
+        let gltf = rose_to_gltf_data(
+            &[
+                assets.zmd.clone(),
+                assets.zmo.clone(),
+                assets.skinned_zms.clone(),
+            ],
+            &options,
+        )
+        .unwrap();
+        let scene = Scene::new(&gltf);
+        assert_eq!(scene.animations.len(), 1);
+        assert!(scene.is_animated(Some(0)));
+        let [primitive] = scene.primitives.as_slice() else {
+            panic!("Expected one primitive");
+        };
+
+        let zmo = two_bone_zmo();
+        for frame in 0..zmo.frames {
+            let time = frame as f32 / zmo.fps as f32;
+            let vertices = scene.vertices(primitive, &scene.pose(Some(0), time));
+            let baked = bake_pose(&skinned_quad_zms(), &two_bone_zmd(), &zmo, frame).unwrap();
+            for (vertex, expected) in vertices.iter().zip(baked.vertices.iter()) {
+                let expected = Vec3::new(
+                    expected.position.x,
+                    expected.position.z,
+                    -expected.position.y,
+                );
+                assert!(
+                    Vec3::from_array(vertex.position).abs_diff_eq(expected, 1.0e-4),
+                    "frame {}: {:?} != {:?}",
+                    frame,
+                    vertex.position,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn skinned_pose() {
+        assert_matches_bake_pose("viewer_skinned_pose", RoseGltfConvOptions::default());
+    }
+
+    #[test]
+    fn morph_target_pose() {
+        assert_matches_bake_pose(
+            "viewer_morph_target_pose",
+            RoseGltfConvOptions {
+                morph_target_animation: true,
+                ..Default::default()
+            },
+        );
+    }
+}
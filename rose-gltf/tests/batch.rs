@@ -0,0 +1,68 @@
+use std::{path::Path, process::Command};
+
+use rose_gltf_lib::{rose_file_lib::io::RoseFile, test_assets::quad_zms, ErrorCategory};
+use serde_json::Value;
+
+#[test]
+fn batch_jobs() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("batch_jobs");
+    let input_directory = directory.join("inputs");
+    let output_directory = directory.join("outputs");
+    let _ = std::fs::remove_dir_all(&output_directory);
+    std::fs::create_dir_all(&input_directory).unwrap();
+    std::fs::create_dir_all(&output_directory).unwrap();
+
+    // A file which fails to parse, followed by one which fails to open
+    let names = ["first", "second", "broken", "third", "missing", "fourth"];
+    for name in names {
+        let path = input_directory.join(format!("{}.zms", name));
+        match name {
+            "broken" => std::fs::write(&path, b"not a zms").unwrap(),
+            "missing" => {}
+            _ => quad_zms().write_to_path(&path).unwrap(),
+        }
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rose-gltf"))
+        .args(["--batch", "--jobs", "2", "--report", "json", "--out"])
+        .arg(&output_directory)
+        .args(
+            names
+                .iter()
+                .map(|name| input_directory.join(format!("{}.zms", name))),
+        )
+        .output()
+        .unwrap();
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    // The good inputs are converted even though one failed, and reported in
+    // the order of the inputs
+    let outputs: Vec<&str> = report["outputs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|output| output.as_str().unwrap())
+        .collect();
+    let expected: Vec<_> = ["first", "second", "third", "fourth"]
+        .iter()
+        .map(|name| output_directory.join(format!("{}.glb", name)))
+        .collect();
+    assert_eq!(
+        outputs,
+        expected
+            .iter()
+            .map(|path| path.to_str().unwrap())
+            .collect::<Vec<_>>()
+    );
+    for path in expected.iter() {
+        assert!(std::fs::metadata(path).unwrap().len() > 0);
+    }
+    assert!(!output_directory.join("broken.glb").exists());
+
+    // The error of the broken input is the one returned
+    let category: ErrorCategory = serde_json::from_value(report["error_category"].clone()).unwrap();
+    assert_eq!(output.status.code(), Some(category.exit_code()));
+    assert_ne!(category, ErrorCategory::Other);
+    let error = report["error"].as_str().unwrap();
+    assert!(error.contains("broken.zms"), "{}", error);
+}
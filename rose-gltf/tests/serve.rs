@@ -0,0 +1,144 @@
+use std::{
+    io::{BufRead, BufReader, Lines, Write},
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use rose_gltf_lib::{
+    rose_file_lib::{io::RoseFile, utils::Vector3},
+    test_assets::{quad_zms, write_test_assets},
+};
+use serde_json::{json, Value};
+
+/// A running `rose-gltf serve`
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl Server {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rose-gltf"))
+            .arg("serve")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+        Self {
+            child,
+            stdin,
+            stdout,
+        }
+    }
+
+    /// Writes `line` to stdin without waiting for a response
+    fn send(&mut self, line: &str) {
+        writeln!(self.stdin, "{}", line).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    /// Writes `line` to stdin and reads the response to it
+    fn request(&mut self, line: &str) -> Value {
+        self.send(line);
+        let response = self.stdout.next().unwrap().unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    /// Closes stdin and checks the server exits once it has answered every
+    /// request
+    fn stop(mut self) {
+        drop(self.stdin);
+        assert!(self.stdout.next().is_none());
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+#[test]
+fn serve_requests() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("serve_requests");
+    let assets = write_test_assets(&directory).unwrap();
+    let output = directory.join("quad.glb");
+    let _ = std::fs::remove_file(&output);
+
+    let mut server = Server::start();
+
+    // Without an id, as the request could not be parsed
+    let response = server.request("not json");
+    assert_eq!(response["id"], Value::Null);
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Failed to parse request"));
+
+    // Blank lines get no response
+    server.send("");
+
+    let response =
+        server.request(&json!({ "id": "missing_output", "inputs": [assets.zms] }).to_string());
+    assert_eq!(response["id"], "missing_output");
+    assert!(response["error"].as_str().unwrap().contains("output"));
+
+    let response = server.request(
+        &json!({ "id": 7, "inputs": [assets.zms], "output": output, "statistics": true })
+            .to_string(),
+    );
+    assert_eq!(response["id"], 7);
+    assert_eq!(response["error"], Value::Null);
+    assert_eq!(response["outputs"], json!([output]));
+    assert_eq!(response["statistics"].as_array().unwrap().len(), 1);
+    assert!(output.exists());
+
+    let response = server.request(
+        &json!({ "id": [1, 2], "inputs": [directory.join("missing.zms")], "output": output })
+            .to_string(),
+    );
+    assert_eq!(response["id"], json!([1, 2]));
+    assert!(response["error"].is_string());
+    assert!(response["error_category"].is_string());
+
+    server.stop();
+}
+
+/// Number of triangles of the meshes of the zone objects in `path`
+fn object_triangles(path: &Path) -> usize {
+    let (document, _, _) = gltf::import(path).unwrap();
+    document
+        .nodes()
+        .filter(|node| node.name().is_some_and(|name| name.contains("_deco_")))
+        .flat_map(|node| node.children().chain(std::iter::once(node)))
+        .filter_map(|node| node.mesh())
+        .flat_map(|mesh| mesh.primitives())
+        .filter_map(|primitive| primitive.indices())
+        .map(|indices| indices.count() / 3)
+        .sum()
+}
+
+#[test]
+fn serve_reloads_changed_files() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("serve_reloads_changed_files");
+    let assets = write_test_assets(&directory).unwrap();
+    let before = directory.join("before.glb");
+    let after = directory.join("after.glb");
+
+    let mut server = Server::start();
+    let response =
+        server.request(&json!({ "id": 1, "inputs": [assets.zon], "output": before }).to_string());
+    assert_eq!(response["error"], Value::Null);
+
+    // The mesh of the zone objects gains a triangle between requests, the
+    // cached mesh must not be used for the second
+    let mut zms = quad_zms();
+    zms.indices.push(Vector3::new(3, 1, 2));
+    zms.write_to_path(&assets.zms).unwrap();
+    let response =
+        server.request(&json!({ "id": 2, "inputs": [assets.zon], "output": after }).to_string());
+    assert_eq!(response["error"], Value::Null);
+    server.stop();
+
+    let triangles_before = object_triangles(&before);
+    assert!(triangles_before > 0);
+    assert_eq!(object_triangles(&after), triangles_before / 2 * 3);
+}